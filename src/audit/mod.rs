@@ -173,9 +173,10 @@ pub struct AuditEvent {
 }
 
 impl AuditEvent {
-    /// Create a new audit event
+    /// Create a new audit event, attributed to the current principal when
+    /// created inside [`scope_principal`]
     pub fn new(event_type: AuditEventType) -> Self {
-        Self {
+        let event = Self {
             event_id: crate::utils::make_short_random_id("evt", 16),
             timestamp: Utc::now(),
             event_type,
@@ -186,6 +187,10 @@ impl AuditEvent {
             error: None,
             duration_ms: None,
             parent_span_id: None,
+        };
+        match current_principal() {
+            Some(principal) => event.with_principal(principal),
+            None => event,
         }
     }
 
@@ -682,6 +687,24 @@ pub fn get_audit_logger() -> Option<Arc<AuditLogger>> {
     AUDIT_LOGGER.get().cloned()
 }
 
+tokio::task_local! {
+    static CURRENT_PRINCIPAL: Option<String>;
+}
+
+/// Run `future` with `principal` recorded on the audit events it creates.
+/// Work moved to other tasks (e.g. `spawn_blocking`) is not covered.
+pub async fn scope_principal<F: Future>(principal: Option<String>, future: F) -> F::Output {
+    CURRENT_PRINCIPAL.scope(principal, future).await
+}
+
+/// Principal of the enclosing [`scope_principal`], if any
+pub fn current_principal() -> Option<String> {
+    CURRENT_PRINCIPAL
+        .try_with(|principal| principal.clone())
+        .ok()
+        .flatten()
+}
+
 /// Log an audit event to the global logger
 pub fn audit_event(event: AuditEvent) {
     if let Some(logger) = get_audit_logger() {
@@ -726,6 +749,18 @@ mod tests {
         assert!(!filter.matches(&event));
     }
 
+    #[tokio::test]
+    async fn test_scope_principal() {
+        let event = scope_principal(Some("ci-bot".to_string()), async {
+            AuditEvent::new(AuditEventType::ToolInvocation)
+        })
+        .await;
+        assert_eq!(event.principal, Some("ci-bot".to_string()));
+
+        let event = AuditEvent::new(AuditEventType::ToolInvocation);
+        assert_eq!(event.principal, None);
+    }
+
     #[test]
     fn test_audit_logger() -> Result<()> {
         let config = AuditConfig {
@@ -0,0 +1,663 @@
+//! Authentication and per-client authorization for the HTTP transport.
+//!
+//! The `http_auth` section of the config file declares identities and the
+//! ways a client can prove one:
+//!
+//! - static bearer tokens,
+//! - HMAC-signed bearer tokens (`v1.<identity>.<expires>.<signature>`) that
+//!   expire and can be issued without touching the server config,
+//! - a client certificate subject forwarded by a TLS-terminating reverse proxy.
+//!
+//! Each identity is limited to a tool set, a workspace subpath and read-only
+//! or read-write access. [`require_auth`] resolves the [`Principal`] for every
+//! request and stores it in the request extensions; rmcp hands the request
+//! parts to the server, which runs the principal's calls against an
+//! [`AppState`](crate::state::AppState) built from [`HttpAuth::scoped_config`].
+//! Workspace, enabled tools, forks and caches are therefore per identity.
+
+use crate::config::ServerConfig;
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use axum::http::{HeaderMap, HeaderName, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use base64::Engine as _;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Component, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const HMAC_TOKEN_VERSION: &str = "v1";
+const MIN_BEARER_TOKEN_LEN: usize = 16;
+const MIN_HMAC_SECRET_LEN: usize = 32;
+const DEFAULT_HMAC_MAX_TTL_SECS: u64 = 86_400;
+const DEFAULT_MTLS_HEADER: &str = "x-client-cert-subject";
+
+/// Tools that only read workbooks or project files. Read-only identities can
+/// call nothing else, so a new tool stays denied to them until it is listed.
+/// Tools that write files, run builds or reach external services (such as
+/// `render_sheet`, `validate_definition_of_done` and `validate_generated_code`,
+/// which can update golden files) are deliberately absent.
+pub const READ_TOOLS: &[&str] = &[
+    // Workbook inspection
+    "list_workbooks",
+    "describe_workbook",
+    "list_sheets",
+    "workbook_summary",
+    "sheet_overview",
+    "sheet_page",
+    "find_value",
+    "read_table",
+    "table_profile",
+    "range_values",
+    "sheet_statistics",
+    "sheet_formula_map",
+    "formula_trace",
+    "named_ranges",
+    "find_formula",
+    "scan_volatiles",
+    "sheet_styles",
+    "workbook_style_summary",
+    "sheet_comments",
+    "data_validations",
+    "find_validation_violations",
+    "list_charts",
+    "chart_data",
+    "list_pivot_tables",
+    "pivot_data",
+    "get_manifest_stub",
+    "diff_workbooks",
+    "vba_project_summary",
+    "vba_module_source",
+    // Ontology generation
+    "render_template",
+    "read_ggen_config",
+    "validate_ggen_config",
+    "read_tera_template",
+    "validate_tera_template",
+    "test_tera_template",
+    "list_template_variables",
+    "verify_receipt",
+];
+
+/// Tools that read or write the ggen project (configs, templates, receipts,
+/// generated code) by paths relative to the server's working directory rather
+/// than the workspace root. Identities confined to a `workspace_subpath`
+/// cannot call them.
+pub const PROJECT_TOOLS: &[&str] = &[
+    "render_template",
+    "write_generated_artifact",
+    "init_ggen_project",
+    "read_ggen_config",
+    "validate_ggen_config",
+    "add_generation_rule",
+    "update_generation_rule",
+    "remove_generation_rule",
+    "sync_ggen",
+    "verify_receipt",
+    "validate_definition_of_done",
+    "read_tera_template",
+    "validate_tera_template",
+    "test_tera_template",
+    "create_tera_template",
+    "list_template_variables",
+    "manage_ggen_resource",
+    "validate_generated_code",
+];
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// A secret read from the config file; never printed.
+#[derive(Clone, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
+
+/// `http_auth` section of the config file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HttpAuthConfig {
+    pub identities: Vec<IdentityConfig>,
+    #[serde(default)]
+    pub bearer_tokens: Vec<BearerTokenConfig>,
+    #[serde(default)]
+    pub hmac: Option<HmacConfig>,
+    #[serde(default)]
+    pub mtls: Option<MtlsConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IdentityConfig {
+    /// Name recorded as the audit principal; letters, digits, `-` and `_`.
+    pub name: String,
+    /// Tools the identity may call, within the server's enabled tools. All
+    /// enabled tools when omitted.
+    #[serde(default)]
+    pub tools: Option<Vec<String>>,
+    /// Directory under the workspace root the identity is confined to.
+    #[serde(default)]
+    pub workspace_subpath: Option<PathBuf>,
+    #[serde(default)]
+    pub access: Access,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Access {
+    /// No forks, and only the [`READ_TOOLS`].
+    #[default]
+    ReadOnly,
+    ReadWrite,
+}
+
+/// A static bearer token. Exactly one of `token` and `token_env` is set.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BearerTokenConfig {
+    pub identity: String,
+    #[serde(default)]
+    pub token: Option<Secret>,
+    /// Environment variable holding the token.
+    #[serde(default)]
+    pub token_env: Option<String>,
+}
+
+/// Key for HMAC-signed tokens. Exactly one of `secret` and `secret_env` is set.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HmacConfig {
+    #[serde(default)]
+    pub secret: Option<Secret>,
+    #[serde(default)]
+    pub secret_env: Option<String>,
+    /// Tokens expiring further in the future than this are rejected.
+    #[serde(default = "default_hmac_max_ttl_secs")]
+    pub max_ttl_secs: u64,
+}
+
+fn default_hmac_max_ttl_secs() -> u64 {
+    DEFAULT_HMAC_MAX_TTL_SECS
+}
+
+/// Client certificate identity forwarded by a reverse proxy. The header is
+/// only trusted from `trusted_proxies`, and the proxy must overwrite any value
+/// the client sent.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MtlsConfig {
+    #[serde(default = "default_mtls_header")]
+    pub header: String,
+    /// Certificate subject (as the proxy formats it) to identity name.
+    pub subjects: HashMap<String, String>,
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+fn default_mtls_header() -> String {
+    DEFAULT_MTLS_HEADER.to_string()
+}
+
+// ============================================================================
+// Principals
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthMethod {
+    BearerToken,
+    HmacToken,
+    ClientCertificate,
+}
+
+/// The authenticated identity behind a request.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub name: String,
+    pub method: AuthMethod,
+    pub access: Access,
+    /// Tools the identity is limited to, lowercased; `None` for no limit.
+    pub tools: Option<HashSet<String>>,
+}
+
+impl Principal {
+    /// Whether the identity's tool list and access level allow `tool`.
+    /// The server's own `enabled_tools` still applies on top.
+    pub fn may_call(&self, tool: &str) -> bool {
+        let tool = tool.to_ascii_lowercase();
+        (self.access == Access::ReadWrite || READ_TOOLS.contains(&tool.as_str()))
+            && self
+                .tools
+                .as_ref()
+                .is_none_or(|tools| tools.contains(&tool))
+    }
+}
+
+/// Returned when a principal calls a tool it is not allowed to use.
+#[derive(Debug, Error)]
+#[error("principal '{principal}' is not allowed to call tool '{tool}'")]
+pub struct AccessDeniedError {
+    pub principal: String,
+    pub tool: String,
+}
+
+/// Why a request was rejected with 401.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AuthError {
+    #[error("missing credentials: send Authorization: Bearer <token>")]
+    MissingCredentials,
+    #[error("invalid bearer token")]
+    InvalidToken,
+    #[error("token expired")]
+    ExpiredToken,
+    #[error("token expiry is further away than the configured maximum")]
+    TokenLifetimeTooLong,
+    #[error("client certificate header from untrusted peer")]
+    UntrustedProxy,
+    #[error("unknown client certificate subject")]
+    UnknownSubject,
+}
+
+// ============================================================================
+// Authenticator
+// ============================================================================
+
+struct HmacKey {
+    secret: Vec<u8>,
+    max_ttl_secs: u64,
+}
+
+struct Mtls {
+    header: HeaderName,
+    subjects: HashMap<String, String>,
+    trusted_proxies: Vec<IpAddr>,
+}
+
+/// Validated `http_auth` configuration, ready to authenticate requests.
+pub struct HttpAuth {
+    identities: HashMap<String, IdentityConfig>,
+    /// SHA-256 of each static token and its identity.
+    bearer_tokens: Vec<([u8; 32], String)>,
+    hmac: Option<HmacKey>,
+    mtls: Option<Mtls>,
+}
+
+impl fmt::Debug for HttpAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpAuth")
+            .field("identities", &self.identities.keys().collect::<Vec<_>>())
+            .field("bearer_tokens", &self.bearer_tokens.len())
+            .field("hmac", &self.hmac.is_some())
+            .field("mtls", &self.mtls.is_some())
+            .finish()
+    }
+}
+
+fn read_secret(value: &Option<Secret>, env: &Option<String>, what: &str) -> Result<String> {
+    let secret = match (value, env) {
+        (Some(Secret(value)), None) => value.clone(),
+        (None, Some(var)) => std::env::var(var)
+            .with_context(|| format!("{what}: environment variable {var} is not set"))?,
+        _ => bail!("{what}: set exactly one of the inline value and the _env variable"),
+    };
+    ensure!(!secret.trim().is_empty(), "{what} is empty");
+    Ok(secret)
+}
+
+fn is_valid_identity_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+impl HttpAuth {
+    pub fn from_config(config: &HttpAuthConfig) -> Result<Self> {
+        ensure!(
+            !config.identities.is_empty(),
+            "http_auth needs at least one identity"
+        );
+        let mut identities = HashMap::new();
+        for identity in &config.identities {
+            ensure!(
+                is_valid_identity_name(&identity.name),
+                "identity name '{}' may only contain letters, digits, '-' and '_'",
+                identity.name
+            );
+            if let Some(subpath) = &identity.workspace_subpath {
+                ensure!(
+                    subpath
+                        .components()
+                        .all(|component| matches!(component, Component::Normal(_))),
+                    "workspace_subpath {:?} of identity '{}' must be a relative path without '..'",
+                    subpath,
+                    identity.name
+                );
+            }
+            let mut identity = identity.clone();
+            if let Some(tools) = &mut identity.tools {
+                for tool in tools.iter_mut() {
+                    *tool = tool.to_ascii_lowercase();
+                }
+            }
+            let name = identity.name.clone();
+            ensure!(
+                identities.insert(name.clone(), identity).is_none(),
+                "identity '{name}' is declared twice"
+            );
+        }
+        let known = |identity: &str, what: &str| {
+            if identities.contains_key(identity) {
+                Ok(())
+            } else {
+                Err(anyhow!("{what} refers to unknown identity '{identity}'"))
+            }
+        };
+
+        let mut bearer_tokens = Vec::new();
+        for entry in &config.bearer_tokens {
+            known(&entry.identity, "bearer token")?;
+            let what = format!("bearer token of identity '{}'", entry.identity);
+            let token = read_secret(&entry.token, &entry.token_env, &what)?;
+            ensure!(
+                token.len() >= MIN_BEARER_TOKEN_LEN,
+                "{what} must be at least {MIN_BEARER_TOKEN_LEN} characters"
+            );
+            ensure!(
+                !token.starts_with(&format!("{HMAC_TOKEN_VERSION}.")),
+                "{what} must not start with '{HMAC_TOKEN_VERSION}.', which marks HMAC tokens"
+            );
+            let digest: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+            ensure!(
+                bearer_tokens
+                    .iter()
+                    .all(|(existing, _)| *existing != digest),
+                "{what} is already used by another entry"
+            );
+            bearer_tokens.push((digest, entry.identity.clone()));
+        }
+
+        let hmac = match &config.hmac {
+            Some(hmac) => {
+                let secret = read_secret(&hmac.secret, &hmac.secret_env, "hmac secret")?;
+                ensure!(
+                    secret.len() >= MIN_HMAC_SECRET_LEN,
+                    "hmac secret must be at least {MIN_HMAC_SECRET_LEN} bytes"
+                );
+                ensure!(hmac.max_ttl_secs > 0, "hmac max_ttl_secs must be positive");
+                Some(HmacKey {
+                    secret: secret.into_bytes(),
+                    max_ttl_secs: hmac.max_ttl_secs,
+                })
+            }
+            None => None,
+        };
+
+        let mtls = match &config.mtls {
+            Some(mtls) => {
+                let header = HeaderName::try_from(mtls.header.as_str())
+                    .with_context(|| format!("invalid mtls header name '{}'", mtls.header))?;
+                ensure!(
+                    !mtls.trusted_proxies.is_empty(),
+                    "mtls.trusted_proxies must list the reverse proxy addresses"
+                );
+                for identity in mtls.subjects.values() {
+                    known(identity, "mtls subject")?;
+                }
+                Some(Mtls {
+                    header,
+                    subjects: mtls.subjects.clone(),
+                    trusted_proxies: mtls.trusted_proxies.clone(),
+                })
+            }
+            None => None,
+        };
+
+        ensure!(
+            !bearer_tokens.is_empty() || hmac.is_some() || mtls.is_some(),
+            "http_auth needs bearer_tokens, hmac or mtls"
+        );
+
+        Ok(Self {
+            identities,
+            bearer_tokens,
+            hmac,
+            mtls,
+        })
+    }
+
+    pub fn identity_names(&self) -> impl Iterator<Item = &str> {
+        self.identities.keys().map(String::as_str)
+    }
+
+    fn principal(&self, identity: &str, method: AuthMethod) -> Result<Arc<Principal>, AuthError> {
+        let identity = self
+            .identities
+            .get(identity)
+            .ok_or(AuthError::InvalidToken)?;
+        Ok(Arc::new(Principal {
+            name: identity.name.clone(),
+            method,
+            access: identity.access,
+            tools: identity
+                .tools
+                .as_ref()
+                .map(|tools| tools.iter().cloned().collect()),
+        }))
+    }
+
+    /// Resolve the principal from the request headers. `peer` is the address
+    /// of the connection, used to decide whether the mTLS header is trusted.
+    pub fn authenticate(
+        &self,
+        headers: &HeaderMap,
+        peer: Option<IpAddr>,
+    ) -> Result<Arc<Principal>, AuthError> {
+        if let Some(mtls) = &self.mtls
+            && let Some(value) = headers.get(&mtls.header)
+        {
+            if !peer.is_some_and(|ip| mtls.trusted_proxies.contains(&ip)) {
+                return Err(AuthError::UntrustedProxy);
+            }
+            let subject = value.to_str().map_err(|_| AuthError::UnknownSubject)?;
+            let identity = mtls
+                .subjects
+                .get(subject.trim())
+                .ok_or(AuthError::UnknownSubject)?;
+            return self.principal(identity, AuthMethod::ClientCertificate);
+        }
+
+        let token = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                value
+                    .strip_prefix("Bearer ")
+                    .or_else(|| value.strip_prefix("bearer "))
+            })
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .ok_or(AuthError::MissingCredentials)?;
+
+        if let Some(hmac) = &self.hmac
+            && token.starts_with(&format!("{HMAC_TOKEN_VERSION}."))
+        {
+            let identity = verify_hmac_token(hmac, token, chrono::Utc::now().timestamp())?;
+            return self.principal(&identity, AuthMethod::HmacToken);
+        }
+
+        let digest = Sha256::digest(token.as_bytes());
+        let mut matched = None;
+        for (expected, identity) in &self.bearer_tokens {
+            if constant_time_eq(expected, &digest) {
+                matched = Some(identity);
+            }
+        }
+        match matched {
+            Some(identity) => self.principal(identity, AuthMethod::BearerToken),
+            None => Err(AuthError::InvalidToken),
+        }
+    }
+
+    /// Server configuration for one identity: its workspace subpath as the
+    /// workspace root, `enabled_tools` narrowed to what it may call among
+    /// `all_tools` (without the [`PROJECT_TOOLS`] when confined to a subpath),
+    /// no recalc for read-only identities and a fork store of its own.
+    pub fn scoped_config(
+        &self,
+        base: &ServerConfig,
+        identity: &str,
+        all_tools: &[String],
+    ) -> Result<ServerConfig> {
+        let principal = self
+            .principal(identity, AuthMethod::BearerToken)
+            .map_err(|_| anyhow!("unknown identity '{identity}'"))?;
+        let identity = &self.identities[identity];
+        let mut config = base.clone();
+        config.http_auth = None;
+        let confined = identity.workspace_subpath.is_some();
+
+        if let Some(subpath) = &identity.workspace_subpath {
+            let base_root = base.workspace_root.canonicalize().with_context(|| {
+                format!("workspace root {:?} is not accessible", base.workspace_root)
+            })?;
+            let root = base_root.join(subpath).canonicalize().with_context(|| {
+                format!(
+                    "workspace_subpath {:?} of identity '{}' does not exist",
+                    subpath, identity.name
+                )
+            })?;
+            ensure!(
+                root.is_dir() && root.starts_with(&base_root),
+                "workspace_subpath {:?} of identity '{}' must be a directory inside the workspace root",
+                subpath,
+                identity.name
+            );
+            if let Some(workbook) = &base.single_workbook {
+                ensure!(
+                    workbook
+                        .canonicalize()
+                        .is_ok_and(|path| path.starts_with(&root)),
+                    "configured workbook {:?} is outside the workspace of identity '{}'",
+                    workbook,
+                    identity.name
+                );
+            }
+            config.workspace_root = root;
+        }
+
+        config.enabled_tools = Some(
+            all_tools
+                .iter()
+                .filter(|tool| base.is_tool_enabled(tool) && principal.may_call(tool))
+                .map(|tool| tool.to_ascii_lowercase())
+                .filter(|tool| !confined || !PROJECT_TOOLS.contains(&tool.as_str()))
+                .collect(),
+        );
+        if identity.access == Access::ReadOnly {
+            config.recalc_enabled = false;
+        }
+        config.fork_store_dir = base
+            .fork_store_dir
+            .as_ref()
+            .map(|dir| dir.join(&identity.name));
+        Ok(config)
+    }
+}
+
+fn hmac_signature(secret: &[u8], payload: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+/// Issue an HMAC token for `identity` that expires at `expires_at` (Unix
+/// seconds).
+pub fn sign_hmac_token(secret: &[u8], identity: &str, expires_at: i64) -> String {
+    let payload = format!("{HMAC_TOKEN_VERSION}.{identity}.{expires_at}");
+    let signature = hmac_signature(secret, &payload).finalize().into_bytes();
+    format!("{payload}.{}", URL_SAFE_NO_PAD.encode(signature))
+}
+
+fn verify_hmac_token(key: &HmacKey, token: &str, now: i64) -> Result<String, AuthError> {
+    let (payload, signature) = token.rsplit_once('.').ok_or(AuthError::InvalidToken)?;
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|_| AuthError::InvalidToken)?;
+    hmac_signature(&key.secret, payload)
+        .verify_slice(&signature)
+        .map_err(|_| AuthError::InvalidToken)?;
+
+    let mut parts = payload.split('.');
+    let (Some(HMAC_TOKEN_VERSION), Some(identity), Some(expires_at), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(AuthError::InvalidToken);
+    };
+    let expires_at: i64 = expires_at.parse().map_err(|_| AuthError::InvalidToken)?;
+    if expires_at <= now {
+        return Err(AuthError::ExpiredToken);
+    }
+    if expires_at - now > key.max_ttl_secs as i64 {
+        return Err(AuthError::TokenLifetimeTooLong);
+    }
+    Ok(identity.to_string())
+}
+
+// ============================================================================
+// Middleware
+// ============================================================================
+
+/// Axum middleware: reject requests without valid credentials with 401 and
+/// attach the `Arc<Principal>` to the others.
+pub async fn require_auth(
+    State(auth): State<Arc<HttpAuth>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    match auth.authenticate(request.headers(), peer) {
+        Ok(principal) => {
+            tracing::debug!(principal = %principal.name, method = ?principal.method, "authenticated request");
+            request.extensions_mut().insert(principal);
+            next.run(request).await
+        }
+        Err(error) => {
+            tracing::warn!(%error, ?peer, path = %request.uri().path(), "rejected HTTP request");
+            crate::audit::integration::audit_error("http_auth", &error.to_string());
+            (
+                StatusCode::UNAUTHORIZED,
+                [(WWW_AUTHENTICATE, "Bearer")],
+                error.to_string(),
+            )
+                .into_response()
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! CSV/TSV files exposed as virtual one-sheet workbooks.
+//!
+//! Encoding, delimiter and header row are sniffed from the file so the read
+//! tools can treat the data like any other sheet. `write_sheet` is the reverse
+//! path used when a fork is exported back to CSV.
+
+use crate::model::{CellValue, DelimitedSourceInfo};
+use crate::workbook::cell_to_value;
+use anyhow::{Context, Result, anyhow};
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use std::fs;
+use std::path::Path;
+use umya_spreadsheet::{Spreadsheet, Worksheet};
+
+const SNIFF_LINES: usize = 50;
+const CANDIDATE_DELIMITERS: &[u8] = b",\t;|";
+const MAX_SHEET_NAME_LEN: usize = 31;
+
+pub(super) fn read(path: &Path) -> Result<(Spreadsheet, DelimitedSourceInfo)> {
+    let bytes = fs::read(path).with_context(|| format!("failed to read {:?}", path))?;
+    let (text, encoding) = decode(&bytes);
+    let delimiter = sniff_delimiter(&text, default_delimiter(path));
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for record in reader.records() {
+        let record = record.with_context(|| format!("failed to parse {:?}", path))?;
+        rows.push(record.iter().map(str::to_string).collect());
+    }
+    let has_header = detect_header(&rows);
+
+    let mut book = umya_spreadsheet::new_file_empty_worksheet();
+    let name = sheet_name_for(path);
+    let sheet = book
+        .new_sheet(name.clone())
+        .map_err(|e| anyhow!("failed to create sheet '{}': {}", name, e))?;
+    populate_sheet(sheet, &rows);
+
+    let info = DelimitedSourceInfo {
+        delimiter: delimiter_label(delimiter).to_string(),
+        encoding: encoding.name().to_string(),
+        header_row: has_header.then_some(1),
+    };
+    Ok((book, info))
+}
+
+/// Write a single sheet as delimited text (UTF-8, `\n` line endings).
+pub fn write_sheet(sheet: &Worksheet, path: &Path, delimiter: u8) -> Result<()> {
+    let (max_col, max_row) = sheet.get_highest_column_and_row();
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_path(path)
+        .with_context(|| format!("failed to create {:?}", path))?;
+    for row in 1..=max_row {
+        let mut record: Vec<String> = (1..=max_col)
+            .map(|col| {
+                sheet
+                    .get_cell((col, row))
+                    .and_then(cell_to_value)
+                    .map(render_value)
+                    .unwrap_or_default()
+            })
+            .collect();
+        while record.last().is_some_and(|v| v.is_empty()) {
+            record.pop();
+        }
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Delimiter implied by a file extension (`.tsv` → tab, otherwise comma).
+pub fn default_delimiter(path: &Path) -> u8 {
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("tsv") | Some("tab") => b'\t',
+        _ => b',',
+    }
+}
+
+fn render_value(value: CellValue) -> String {
+    match value {
+        CellValue::Number(n) => n.to_string(),
+        CellValue::Bool(b) => if b { "TRUE" } else { "FALSE" }.to_string(),
+        CellValue::Text(s) | CellValue::Error(s) | CellValue::Date(s) => s,
+    }
+}
+
+fn decode(bytes: &[u8]) -> (String, &'static Encoding) {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return (text.into_owned(), encoding);
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), UTF_8),
+        // Legacy exports from Excel on Windows are almost always cp1252.
+        Err(_) => {
+            let (text, _) = WINDOWS_1252.decode_without_bom_handling(bytes);
+            (text.into_owned(), WINDOWS_1252)
+        }
+    }
+}
+
+/// Pick the candidate delimiter that splits the sampled lines into the most
+/// consistent number of fields (more than one).
+fn sniff_delimiter(text: &str, fallback: u8) -> u8 {
+    let lines: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(SNIFF_LINES)
+        .collect();
+    if lines.is_empty() {
+        return fallback;
+    }
+
+    let mut best = (fallback, 0usize);
+    for &candidate in CANDIDATE_DELIMITERS {
+        let counts: Vec<usize> = lines
+            .iter()
+            .map(|line| count_fields(line, candidate))
+            .collect();
+        let first = counts[0];
+        if first < 2 {
+            continue;
+        }
+        let consistent = counts.iter().filter(|&&count| count == first).count();
+        // Prefer consistency, then the extension's default on ties.
+        let score = consistent * 2 + usize::from(candidate == fallback);
+        if score > best.1 {
+            best = (candidate, score);
+        }
+    }
+    best.0
+}
+
+fn count_fields(line: &str, delimiter: u8) -> usize {
+    let mut in_quotes = false;
+    let mut fields = 1;
+    for byte in line.bytes() {
+        if byte == b'"' {
+            in_quotes = !in_quotes;
+        } else if byte == delimiter && !in_quotes {
+            fields += 1;
+        }
+    }
+    fields
+}
+
+/// A first row of distinct, non-numeric labels over data containing at least
+/// one numeric column is treated as a header.
+fn detect_header(rows: &[Vec<String>]) -> bool {
+    let Some(first) = rows.first() else {
+        return false;
+    };
+    let labels: Vec<&str> = first
+        .iter()
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .collect();
+    if labels.is_empty() || labels.iter().any(|v| parse_number(v).is_some()) {
+        return false;
+    }
+    let mut unique = labels.clone();
+    unique.sort_unstable();
+    unique.dedup();
+    if unique.len() != labels.len() {
+        return false;
+    }
+    if rows.len() == 1 {
+        return true;
+    }
+    let body = &rows[1..rows.len().min(SNIFF_LINES)];
+    (0..first.len()).any(|col| {
+        body.iter()
+            .filter_map(|row| row.get(col))
+            .any(|v| parse_number(v.trim()).is_some())
+    })
+}
+
+fn parse_number(raw: &str) -> Option<f64> {
+    if raw.is_empty() {
+        return None;
+    }
+    raw.parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+fn populate_sheet(sheet: &mut Worksheet, rows: &[Vec<String>]) {
+    for (row_idx, row) in rows.iter().enumerate() {
+        for (col_idx, raw) in row.iter().enumerate() {
+            if raw.is_empty() {
+                continue;
+            }
+            let cell = sheet.get_cell_mut((col_idx as u32 + 1, row_idx as u32 + 1));
+            // Leading zeros (ids, zip codes) stay text so they survive a round trip.
+            let keeps_zeros = raw.len() > 1 && raw.starts_with('0') && !raw.starts_with("0.");
+            match parse_number(raw.trim()) {
+                Some(n) if !keeps_zeros => {
+                    cell.set_value_number(n);
+                }
+                _ => match raw.trim() {
+                    "TRUE" | "true" => {
+                        cell.set_value_bool(true);
+                    }
+                    "FALSE" | "false" => {
+                        cell.set_value_bool(false);
+                    }
+                    _ => {
+                        cell.set_value_string(raw.clone());
+                    }
+                },
+            }
+        }
+    }
+}
+
+fn sheet_name_for(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let cleaned: String = stem
+        .chars()
+        .map(|c| match c {
+            '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '_',
+            other => other,
+        })
+        .take(MAX_SHEET_NAME_LEN)
+        .collect();
+    let cleaned = cleaned.trim_matches('\'').trim().to_string();
+    if cleaned.is_empty() {
+        "Sheet1".to_string()
+    } else {
+        cleaned
+    }
+}
+
+fn delimiter_label(delimiter: u8) -> &'static str {
+    match delimiter {
+        b',' => ",",
+        b'\t' => "\\t",
+        b';' => ";",
+        b'|' => "|",
+        _ => "?",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn sniffs_semicolon_files_with_header() {
+        let text = "Region;Units;Price\nNorth;10;2,5\nSouth;7;3,1\n";
+        assert_eq!(sniff_delimiter(text, b','), b';');
+        let rows: Vec<Vec<String>> = text
+            .lines()
+            .map(|l| l.split(';').map(str::to_string).collect())
+            .collect();
+        assert!(detect_header(&rows));
+    }
+
+    #[test]
+    fn numeric_first_row_is_not_a_header() {
+        let rows = vec![
+            vec!["1".to_string(), "2".to_string()],
+            vec!["3".to_string(), "4".to_string()],
+        ];
+        assert!(!detect_header(&rows));
+    }
+
+    #[test]
+    fn decodes_cp1252_and_utf16_bom() {
+        let (text, encoding) = decode(b"Caf\xe9,1\n");
+        assert_eq!(text, "Café,1\n");
+        assert_eq!(encoding, WINDOWS_1252);
+
+        let (text, encoding) = decode(b"\xff\xfeA\x00,\x00B\x00");
+        assert_eq!(text, "A,B");
+        assert_eq!(encoding.name(), "UTF-16LE");
+    }
+
+    #[test]
+    fn round_trips_tsv_through_sheet() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("orders.tsv");
+        fs::write(
+            &source,
+            "sku\tqty\tactive\n00123\t4\tTRUE\nA-9\t2.5\tfalse\n",
+        )
+        .unwrap();
+
+        let (book, info) = read(&source).unwrap();
+        assert_eq!(info.delimiter, "\\t");
+        assert_eq!(info.encoding, "UTF-8");
+        assert_eq!(info.header_row, Some(1));
+        let sheet = book.get_sheet_by_name("orders").expect("sheet");
+        assert_eq!(sheet.get_value("A2"), "00123");
+        assert_eq!(sheet.get_value("B3"), "2.5");
+
+        let out = dir.path().join("orders_out.csv");
+        write_sheet(sheet, &out, b',').unwrap();
+        assert_eq!(
+            fs::read_to_string(&out).unwrap(),
+            "sku,qty,active\n00123,4,TRUE\nA-9,2.5,FALSE\n"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! File-format backends that load non-xlsx workbooks into the umya model.
+//!
+//! Every backend produces an in-memory `Spreadsheet` so `WorkbookContext`,
+//! `SheetCacheEntry` and the read tools stay format-agnostic. The advertised
+//! `BackendCaps` tell clients which features survived the conversion.
+
+pub mod delimited;
+pub mod ods;
+mod xls;
+
+use crate::caps::BackendCaps;
+use crate::model::DelimitedSourceInfo;
+use anyhow::{Context, Result};
+use std::path::Path;
+use umya_spreadsheet::Spreadsheet;
+use umya_spreadsheet::reader::xlsx;
+
+fn extension_of(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+}
+
+/// Whether `path` is an xlsx/xlsm package, whose parts the zip-level readers
+/// (comments, charts, pivots, sheet settings) can read directly.
+pub fn is_ooxml(path: &Path) -> bool {
+    matches!(extension_of(path).as_deref(), Some("xlsx") | Some("xlsm"))
+}
+
+/// Capabilities for the backend that will load `path`.
+pub fn caps_for_path(path: &Path) -> BackendCaps {
+    match extension_of(path).as_deref() {
+        Some("xls") | Some("xlsb") => BackendCaps::xls_readonly(),
+        Some("ods") => BackendCaps::degraded_for_ods(),
+        Some("csv") | Some("tsv") => BackendCaps::delimited(),
+        _ => BackendCaps::xlsx(),
+    }
+}
+
+/// A workbook loaded by one of the backends.
+pub struct LoadedSpreadsheet {
+    pub spreadsheet: Spreadsheet,
+    /// Detected layout when the source was CSV/TSV.
+    pub delimited: Option<DelimitedSourceInfo>,
+}
+
+/// Load a workbook from disk using the backend matching its extension.
+pub fn load(path: &Path) -> Result<LoadedSpreadsheet> {
+    let (spreadsheet, delimited) = match extension_of(path).as_deref() {
+        Some("xls") | Some("xlsb") => (xls::read(path)?, None),
+        Some("ods") => (ods::read(path)?, None),
+        Some("csv") | Some("tsv") => {
+            let (spreadsheet, info) = delimited::read(path)?;
+            (spreadsheet, Some(info))
+        }
+        _ => (
+            xlsx::read(path).with_context(|| format!("failed to parse workbook {:?}", path))?,
+            None,
+        ),
+    };
+    Ok(LoadedSpreadsheet {
+        spreadsheet,
+        delimited,
+    })
+}
+
+/// Load just the `Spreadsheet`, e.g. to convert a non-xlsx base for a fork.
+pub fn read_spreadsheet(path: &Path) -> Result<Spreadsheet> {
+    load(path).map(|loaded| loaded.spreadsheet)
+}
@@ -0,0 +1,323 @@
+//! Conversion between OpenFormula (ODF 1.2) and Excel A1 formula syntax.
+//!
+//! OpenFormula wraps references in brackets (`[.A1]`, `[$Sheet2.$B$3:.C4]`),
+//! separates arguments with `;` and array rows with `|`. Everything else the
+//! server cares about (function names, operators, literals) is shared.
+
+use crate::formula::pattern::sheet_name_needs_quoting;
+use anyhow::{Result, anyhow};
+use formualizer_parse::tokenizer::Tokenizer;
+use formualizer_parse::{TokenSubType, TokenType};
+
+const OPENFORMULA_PREFIXES: &[&str] = &["of:", "oooc:", "msoxl:"];
+const MS_FUNCTION_PREFIX: &str = "COM.MICROSOFT.";
+
+/// Convert a `table:formula` attribute (e.g. `of:=SUM([.A1:.A3])`) into Excel
+/// syntax without the leading `=`.
+pub fn to_excel(formula: &str) -> String {
+    let mut body = formula.trim();
+    for prefix in OPENFORMULA_PREFIXES {
+        if let Some(rest) = body.strip_prefix(prefix) {
+            body = rest;
+            break;
+        }
+    }
+    let body = body.strip_prefix('=').unwrap_or(body);
+
+    let chars: Vec<char> = body.chars().collect();
+    let mut out = String::with_capacity(body.len());
+    let mut array_depth = 0usize;
+    let mut i = 0usize;
+    while i < chars.len() {
+        let ch = chars[i];
+        match ch {
+            '"' => {
+                let end = scan_quoted(&chars, i, '"');
+                out.extend(&chars[i..end]);
+                i = end;
+                continue;
+            }
+            '[' => {
+                let end = scan_bracket(&chars, i);
+                let inner: String = chars[i + 1..end.saturating_sub(1).max(i + 1)]
+                    .iter()
+                    .collect();
+                out.push_str(&reference_to_excel(&inner));
+                i = end;
+                continue;
+            }
+            '#' => {
+                // Error literals (#REF!, #N/A, #DIV/0!) must keep their `!`.
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '/') {
+                    i += 1;
+                }
+                if matches!(chars.get(i), Some('!') | Some('?')) {
+                    i += 1;
+                }
+                out.extend(&chars[start..i]);
+                continue;
+            }
+            '{' => {
+                array_depth += 1;
+                out.push(ch);
+            }
+            '}' => {
+                array_depth = array_depth.saturating_sub(1);
+                out.push(ch);
+            }
+            ';' => out.push(','),
+            '|' if array_depth > 0 => out.push(';'),
+            // ODF reference union and intersection operators.
+            '~' => out.push(','),
+            '!' => out.push(' '),
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '.')
+                {
+                    i += 1;
+                }
+                let ident: String = chars[start..i].iter().collect();
+                let is_call = chars.get(i) == Some(&'(');
+                match ident.strip_prefix(MS_FUNCTION_PREFIX) {
+                    Some(name) if is_call => out.push_str(name),
+                    _ => out.push_str(&ident),
+                }
+                continue;
+            }
+            _ => out.push(ch),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Convert an ODF cell range address (`$Sheet1.$A$1:.$B$5`, no brackets) into
+/// an Excel reference (`Sheet1!$A$1:$B$5`).
+pub fn reference_to_excel(reference: &str) -> String {
+    let parts = split_outside_quotes(reference.trim(), ':');
+    let mut out = String::with_capacity(reference.len());
+    for (idx, part) in parts.iter().enumerate() {
+        let (sheet, cell) = split_sheet(part);
+        if idx > 0 {
+            out.push(':');
+        }
+        if idx == 0
+            && let Some(sheet) = sheet
+        {
+            out.push_str(&excel_sheet_prefix(sheet));
+        }
+        out.push_str(cell);
+    }
+    out
+}
+
+/// Convert an Excel formula (with or without leading `=`) into an OpenFormula
+/// `table:formula` attribute value.
+pub fn to_openformula(formula: &str) -> Result<String> {
+    let trimmed = formula.trim();
+    let with_equals = if trimmed.starts_with('=') {
+        trimmed.to_string()
+    } else {
+        format!("={}", trimmed)
+    };
+    let tokenizer = Tokenizer::new(&with_equals)
+        .map_err(|e| anyhow!("failed to tokenize formula: {}", e.message))?;
+
+    let mut out = String::with_capacity(with_equals.len() + 8);
+    let mut cursor = 0usize;
+    for token in &tokenizer.items {
+        if token.start > cursor {
+            out.push_str(&with_equals[cursor..token.start]);
+        }
+        match (&token.token_type, &token.subtype) {
+            (TokenType::Operand, TokenSubType::Range) if is_cell_reference(&token.value) => {
+                out.push_str(&reference_to_ods(&token.value));
+            }
+            (TokenType::Sep, TokenSubType::Arg) => out.push(';'),
+            (TokenType::Sep, TokenSubType::Row) => out.push('|'),
+            (TokenType::Func, TokenSubType::Open) => {
+                out.push_str(token.value.strip_prefix("_xlfn.").unwrap_or(&token.value));
+            }
+            _ => out.push_str(&token.value),
+        }
+        cursor = token.end;
+    }
+    if cursor < with_equals.len() {
+        out.push_str(&with_equals[cursor..]);
+    }
+    Ok(format!("of:{}", out))
+}
+
+/// Convert an Excel reference (`Sheet1!$A$1:$B$5`) into ODF bracket form.
+pub fn reference_to_ods(reference: &str) -> String {
+    format!("[{}]", reference_to_ods_address(reference))
+}
+
+/// ODF cell range address without brackets (`$Sheet1.$A$1:.$B$5`).
+pub fn reference_to_ods_address(reference: &str) -> String {
+    let (sheet, range) = match reference.rsplit_once('!') {
+        Some((sheet, range)) => (Some(sheet), range),
+        None => (None, reference),
+    };
+    range
+        .split(':')
+        .enumerate()
+        .map(|(idx, part)| match (idx, sheet) {
+            (0, Some(sheet)) => format!("${}.{}", sheet, part),
+            _ => format!(".{}", part),
+        })
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// True when an Excel operand token is an A1 reference rather than a name.
+pub fn is_cell_reference(value: &str) -> bool {
+    let range = value.rsplit_once('!').map_or(value, |(_, range)| range);
+    !range.is_empty() && range.split(':').all(is_reference_part)
+}
+
+fn is_reference_part(part: &str) -> bool {
+    let part = part.trim_start_matches('$');
+    let letters = part.chars().take_while(|c| c.is_ascii_alphabetic()).count();
+    let rest = part[letters..].trim_start_matches('$');
+    let digits_ok = rest.chars().all(|c| c.is_ascii_digit());
+    match (letters, rest.is_empty()) {
+        (0, false) => digits_ok,
+        (1..=3, true) => true,
+        (1..=3, false) => digits_ok,
+        _ => false,
+    }
+}
+
+fn excel_sheet_prefix(sheet: &str) -> String {
+    if sheet.starts_with('\'') {
+        return format!("{}!", sheet);
+    }
+    if sheet_name_needs_quoting(sheet) {
+        format!("'{}'!", sheet.replace('\'', "''"))
+    } else {
+        format!("{}!", sheet)
+    }
+}
+
+/// Split an ODF reference part into its (optional) sheet and cell address.
+fn split_sheet(part: &str) -> (Option<&str>, &str) {
+    let part = part.strip_prefix('$').unwrap_or(part);
+    let mut in_quote = false;
+    let mut split_at = None;
+    for (idx, ch) in part.char_indices() {
+        match ch {
+            '\'' => in_quote = !in_quote,
+            '.' if !in_quote => split_at = Some(idx),
+            _ => {}
+        }
+    }
+    match split_at {
+        Some(0) => (None, &part[1..]),
+        Some(idx) => (Some(&part[..idx]), &part[idx + 1..]),
+        None => (None, part),
+    }
+}
+
+fn split_outside_quotes(text: &str, delimiter: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut in_quote = false;
+    let mut start = 0usize;
+    for (idx, ch) in text.char_indices() {
+        if ch == '\'' {
+            in_quote = !in_quote;
+        } else if ch == delimiter && !in_quote {
+            parts.push(&text[start..idx]);
+            start = idx + ch.len_utf8();
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Index one past the closing quote, honouring doubled-quote escapes.
+fn scan_quoted(chars: &[char], start: usize, quote: char) -> usize {
+    let mut i = start + 1;
+    while i < chars.len() {
+        if chars[i] == quote {
+            if chars.get(i + 1) == Some(&quote) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+/// Index one past the `]` closing the bracket opened at `start`.
+fn scan_bracket(chars: &[char], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\'' => {
+                i = scan_quoted(chars, i, '\'');
+                continue;
+            }
+            ']' => return i + 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_openformula_references_and_separators() {
+        assert_eq!(to_excel("of:=SUM([.A1:.A3])"), "SUM(A1:A3)");
+        assert_eq!(
+            to_excel("of:=IF([.B2]>0;[$Data.$C$4];\"a;b\")"),
+            "IF(B2>0,Data!$C$4,\"a;b\")"
+        );
+        assert_eq!(
+            to_excel("of:=VLOOKUP([.A2];['Rate Table'.A1:.B9];2;0)"),
+            "VLOOKUP(A2,'Rate Table'!A1:B9,2,0)"
+        );
+        assert_eq!(to_excel("of:=SUM({1;2|3;4})"), "SUM({1,2;3,4})");
+        assert_eq!(
+            to_excel("of:=COM.MICROSOFT.IFS([.A1]>1;1;TRUE();0)"),
+            "IFS(A1>1,1,TRUE(),0)"
+        );
+    }
+
+    #[test]
+    fn converts_named_range_addresses() {
+        assert_eq!(reference_to_excel("$Sheet1.$A$1:.$B$5"), "Sheet1!$A$1:$B$5");
+        assert_eq!(reference_to_excel("$'Q1 Plan'.$C$3"), "'Q1 Plan'!$C$3");
+        assert_eq!(reference_to_excel("$My-Sheet.A1"), "'My-Sheet'!A1");
+    }
+
+    #[test]
+    fn converts_excel_back_to_openformula() {
+        assert_eq!(
+            to_openformula("=SUM(A1:A3)+Data!$C$4").unwrap(),
+            "of:=SUM([.A1:.A3])+[$Data.$C$4]"
+        );
+        assert_eq!(
+            to_openformula("IF(Total>0,'Rate Table'!B2,\"x,y\")").unwrap(),
+            "of:=IF(Total>0;[$'Rate Table'.B2];\"x,y\")"
+        );
+    }
+
+    #[test]
+    fn distinguishes_references_from_names() {
+        assert!(is_cell_reference("A1"));
+        assert!(is_cell_reference("Sheet1!$A$1:$B$2"));
+        assert!(is_cell_reference("A:C"));
+        assert!(!is_cell_reference("Revenue"));
+        assert!(!is_cell_reference("Tax_2024"));
+    }
+}
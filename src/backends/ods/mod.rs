@@ -0,0 +1,59 @@
+//! OpenDocument spreadsheet (`.ods`) support.
+//!
+//! `content.xml` is mapped onto an umya `Spreadsheet`: sheets, cell values,
+//! formulas (OpenFormula converted to Excel syntax so `FormulaAtlas` and the
+//! formula tools see the same text as for xlsx) and named ranges. The writer
+//! performs the reverse mapping so forks can be saved back to `.ods`.
+
+pub mod formula;
+mod reader;
+mod writer;
+
+pub(super) use reader::read;
+pub use writer::write;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn round_trips_values_formulas_and_names() {
+        let mut book = umya_spreadsheet::new_file();
+        {
+            let sheet = book.get_sheet_mut(&0).unwrap();
+            sheet.get_cell_mut("A1").set_value("Item");
+            sheet.get_cell_mut("B1").set_value("Amount");
+            sheet.get_cell_mut("A2").set_value("Rent");
+            sheet.get_cell_mut("B2").set_value_number(1200);
+            sheet.get_cell_mut("A4").set_value("Food; misc");
+            sheet.get_cell_mut("B4").set_value_number(310.5);
+            sheet.get_cell_mut("D2").set_value_bool(true);
+            let total = sheet.get_cell_mut("B5");
+            total.set_formula("SUM(B2:B4)");
+            total.set_formula_result_default("1510.5");
+        }
+        book.add_defined_name("Amounts", "Sheet1!$B$2:$B$4")
+            .unwrap();
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("budget.ods");
+        write(&book, &path).unwrap();
+        let loaded = read(&path).unwrap();
+
+        let sheet = loaded.get_sheet_by_name("Sheet1").expect("sheet");
+        assert_eq!(sheet.get_value("A1"), "Item");
+        assert_eq!(sheet.get_value("B2"), "1200");
+        assert_eq!(sheet.get_value("A4"), "Food; misc");
+        assert_eq!(sheet.get_value("D2"), "TRUE");
+        assert!(sheet.get_cell("A3").is_none());
+        let total = sheet.get_cell("B5").expect("formula cell");
+        assert_eq!(total.get_formula(), "SUM(B2:B4)");
+        assert_eq!(total.get_value(), "1510.5");
+
+        let names = loaded.get_defined_names();
+        assert_eq!(names.len(), 1);
+        assert_eq!(names[0].get_name(), "Amounts");
+        assert_eq!(names[0].get_address(), "Sheet1!$B$2:$B$4");
+    }
+}
@@ -0,0 +1,406 @@
+use super::formula;
+use anyhow::{Context, Result, anyhow};
+use chrono::{NaiveDate, NaiveDateTime};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use umya_spreadsheet::Spreadsheet;
+use zip::ZipArchive;
+
+const MAX_ROWS: u32 = 1_048_576;
+const MAX_COLS: u32 = 16_384;
+const DATE_FORMAT: &str = "yyyy-mm-dd";
+const DATE_TIME_FORMAT: &str = "yyyy-mm-dd hh:mm:ss";
+const TIME_FORMAT: &str = "hh:mm:ss";
+const PERCENT_FORMAT: &str = "0.00%";
+
+#[derive(Debug, Clone, PartialEq)]
+enum OdsValue {
+    Number(f64),
+    Percent(f64),
+    Date(f64, &'static str),
+    Bool(bool),
+    Text(String),
+    Error(String),
+}
+
+#[derive(Debug, Default, Clone)]
+struct PendingCell {
+    value_type: Option<String>,
+    value: Option<String>,
+    date_value: Option<String>,
+    time_value: Option<String>,
+    bool_value: Option<String>,
+    string_value: Option<String>,
+    formula: Option<String>,
+    text: String,
+    paragraphs: usize,
+    repeat: u32,
+}
+
+impl PendingCell {
+    fn from_start<R>(reader: &Reader<R>, e: &BytesStart) -> Result<Self> {
+        let mut cell = PendingCell {
+            repeat: 1,
+            ..Default::default()
+        };
+        for attr in e.attributes() {
+            let attr = attr?;
+            let value = attr.decode_and_unescape_value(reader)?.to_string();
+            match attr.key.as_ref() {
+                b"table:number-columns-repeated" => cell.repeat = value.parse().unwrap_or(1),
+                b"office:value-type" if cell.value_type.is_none() => cell.value_type = Some(value),
+                // LibreOffice marks error results with its own extension attribute.
+                b"calcext:value-type" if value == "error" => cell.value_type = Some(value),
+                b"office:value" => cell.value = Some(value),
+                b"office:date-value" => cell.date_value = Some(value),
+                b"office:time-value" => cell.time_value = Some(value),
+                b"office:boolean-value" => cell.bool_value = Some(value),
+                b"office:string-value" => cell.string_value = Some(value),
+                b"table:formula" => cell.formula = Some(value),
+                _ => {}
+            }
+        }
+        Ok(cell)
+    }
+
+    fn resolve(&self) -> Option<OdsValue> {
+        let number = || self.value.as_deref().and_then(|v| v.parse::<f64>().ok());
+        match self.value_type.as_deref() {
+            Some("float") | Some("currency") => number().map(OdsValue::Number),
+            Some("percentage") => number().map(OdsValue::Percent),
+            Some("date") => self.date_value.as_deref().and_then(parse_date_value),
+            Some("time") => self
+                .time_value
+                .as_deref()
+                .and_then(parse_duration)
+                .map(|serial| OdsValue::Date(serial, TIME_FORMAT)),
+            Some("boolean") => self
+                .bool_value
+                .as_deref()
+                .map(|v| OdsValue::Bool(v.eq_ignore_ascii_case("true"))),
+            Some("error") => Some(OdsValue::Error(self.text.clone())),
+            Some(_) => {
+                let text = self
+                    .string_value
+                    .clone()
+                    .unwrap_or_else(|| self.text.clone());
+                (!text.is_empty()).then_some(OdsValue::Text(text))
+            }
+            None if !self.text.is_empty() => Some(OdsValue::Text(self.text.clone())),
+            None => None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.formula.is_none() && self.resolve().is_none()
+    }
+}
+
+struct NamedItem {
+    name: String,
+    address: String,
+    scope: Option<String>,
+}
+
+pub(super) fn read(path: &Path) -> Result<Spreadsheet> {
+    let file = File::open(path).with_context(|| format!("failed to open {:?}", path))?;
+    let mut archive =
+        ZipArchive::new(file).with_context(|| format!("{:?} is not an ODF package", path))?;
+    let content = archive
+        .by_name("content.xml")
+        .with_context(|| format!("{:?} has no content.xml", path))?;
+    let mut reader = Reader::from_reader(BufReader::new(content));
+    reader.trim_text(false);
+
+    let mut book = umya_spreadsheet::new_file_empty_worksheet();
+    let mut names: Vec<NamedItem> = Vec::new();
+    let mut sheet_index: Option<usize> = None;
+    let mut sheet_name = String::new();
+    let mut row = 1u32;
+    let mut row_repeat = 1u32;
+    let mut col = 1u32;
+    let mut row_cells: Vec<(u32, PendingCell)> = Vec::new();
+    let mut cell: Option<PendingCell> = None;
+    let mut in_paragraph = false;
+    let mut annotation_depth = 0usize;
+    let mut buf = Vec::new();
+
+    loop {
+        let event = reader.read_event_into(&mut buf)?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let is_empty = matches!(event, Event::Empty(_));
+                match e.name().as_ref() {
+                    b"table:table" => {
+                        sheet_name = attribute(&reader, e, b"table:name")?.unwrap_or_else(|| {
+                            format!("Sheet{}", book.get_sheet_collection().len() + 1)
+                        });
+                        book.new_sheet(sheet_name.clone()).map_err(|err| {
+                            anyhow!("failed to create sheet '{}': {}", sheet_name, err)
+                        })?;
+                        sheet_index = Some(book.get_sheet_collection().len() - 1);
+                        row = 1;
+                    }
+                    b"table:table-row" => {
+                        row_repeat = attribute(&reader, e, b"table:number-rows-repeated")?
+                            .and_then(|v| v.parse().ok())
+                            .unwrap_or(1);
+                        col = 1;
+                        row_cells.clear();
+                        if is_empty {
+                            row = row.saturating_add(row_repeat);
+                        }
+                    }
+                    b"table:table-cell" | b"table:covered-table-cell" => {
+                        let pending = PendingCell::from_start(&reader, e)?;
+                        if is_empty {
+                            col = push_cell(&mut row_cells, col, pending);
+                        } else {
+                            cell = Some(pending);
+                        }
+                    }
+                    b"office:annotation" if !is_empty => annotation_depth += 1,
+                    b"text:p" if annotation_depth == 0 => {
+                        if let Some(cell) = cell.as_mut() {
+                            if cell.paragraphs > 0 {
+                                cell.text.push('\n');
+                            }
+                            cell.paragraphs += 1;
+                            in_paragraph = !is_empty;
+                        }
+                    }
+                    b"text:s" if in_paragraph && annotation_depth == 0 => {
+                        let count = attribute(&reader, e, b"text:c")?
+                            .and_then(|v| v.parse::<usize>().ok())
+                            .unwrap_or(1);
+                        if let Some(cell) = cell.as_mut() {
+                            cell.text.push_str(&" ".repeat(count));
+                        }
+                    }
+                    b"text:tab" if in_paragraph && annotation_depth == 0 => {
+                        if let Some(cell) = cell.as_mut() {
+                            cell.text.push('\t');
+                        }
+                    }
+                    b"text:line-break" if in_paragraph && annotation_depth == 0 => {
+                        if let Some(cell) = cell.as_mut() {
+                            cell.text.push('\n');
+                        }
+                    }
+                    b"table:named-range" | b"table:named-expression" => {
+                        let name = attribute(&reader, e, b"table:name")?.unwrap_or_default();
+                        let address = match attribute(&reader, e, b"table:cell-range-address")? {
+                            Some(range) => formula::reference_to_excel(&range),
+                            None => attribute(&reader, e, b"table:expression")?
+                                .map(|expr| formula::to_excel(&expr))
+                                .unwrap_or_default(),
+                        };
+                        let scope = sheet_index.map(|_| sheet_name.clone());
+                        if !name.is_empty() && !address.is_empty() {
+                            names.push(NamedItem {
+                                name,
+                                address,
+                                scope,
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(ref t) if in_paragraph && annotation_depth == 0 => {
+                if let Some(cell) = cell.as_mut() {
+                    cell.text.push_str(&t.unescape()?);
+                }
+            }
+            Event::End(ref e) => match e.name().as_ref() {
+                b"table:table" => sheet_index = None,
+                b"text:p" => in_paragraph = false,
+                b"office:annotation" => annotation_depth = annotation_depth.saturating_sub(1),
+                b"table:table-cell" | b"table:covered-table-cell" => {
+                    if let Some(pending) = cell.take() {
+                        col = push_cell(&mut row_cells, col, pending);
+                    }
+                }
+                b"table:table-row" => {
+                    if let Some(index) = sheet_index
+                        && !row_cells.is_empty()
+                        && let Some(sheet) = book.get_sheet_mut(&index)
+                    {
+                        let last = row.saturating_add(row_repeat).min(MAX_ROWS + 1);
+                        for target_row in row..last {
+                            for (target_col, pending) in &row_cells {
+                                write_cell(sheet, *target_col, target_row, pending);
+                            }
+                        }
+                    }
+                    row = row.saturating_add(row_repeat);
+                    row_cells.clear();
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    if book.get_sheet_collection().is_empty() {
+        return Err(anyhow!("workbook {:?} contains no sheets", path));
+    }
+
+    for item in names {
+        let result = match item.scope.as_deref() {
+            Some(scope) => match book.get_sheet_by_name_mut(scope) {
+                Some(sheet) => sheet.add_defined_name(item.name.clone(), item.address),
+                None => continue,
+            },
+            None => book.add_defined_name(item.name.clone(), item.address),
+        };
+        if let Err(e) = result {
+            tracing::debug!(name = %item.name, error = %e, "skipping named range");
+        }
+    }
+
+    Ok(book)
+}
+
+fn attribute<R>(reader: &Reader<R>, e: &BytesStart, key: &[u8]) -> Result<Option<String>> {
+    for attr in e.attributes() {
+        let attr = attr?;
+        if attr.key.as_ref() == key {
+            return Ok(Some(attr.decode_and_unescape_value(reader)?.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// Queue a parsed cell for the current row; returns the next column index.
+fn push_cell(row_cells: &mut Vec<(u32, PendingCell)>, col: u32, pending: PendingCell) -> u32 {
+    let repeat = pending.repeat.max(1);
+    if !pending.is_empty() {
+        let last = col.saturating_add(repeat).min(MAX_COLS + 1);
+        for target_col in col..last {
+            row_cells.push((target_col, pending.clone()));
+        }
+    }
+    col.saturating_add(repeat)
+}
+
+fn write_cell(sheet: &mut umya_spreadsheet::Worksheet, col: u32, row: u32, pending: &PendingCell) {
+    let value = pending.resolve();
+    let cell = sheet.get_cell_mut((col, row));
+    let mut number_format = None;
+    match &value {
+        Some(OdsValue::Number(n)) => {
+            cell.set_value_number(*n);
+        }
+        Some(OdsValue::Percent(n)) => {
+            cell.set_value_number(*n);
+            number_format = Some(PERCENT_FORMAT);
+        }
+        Some(OdsValue::Date(serial, format)) => {
+            cell.set_value_number(*serial);
+            number_format = Some(*format);
+        }
+        Some(OdsValue::Bool(b)) => {
+            cell.set_value_bool(*b);
+        }
+        Some(OdsValue::Text(s)) => {
+            cell.set_value_string(s.clone());
+        }
+        Some(OdsValue::Error(s)) => {
+            cell.set_value(s.clone());
+        }
+        None => {}
+    }
+    if let Some(raw) = pending.formula.as_deref() {
+        cell.set_formula(formula::to_excel(raw));
+    }
+    if let Some(format) = number_format {
+        sheet
+            .get_style_mut((col, row))
+            .get_number_format_mut()
+            .set_format_code(format);
+    }
+}
+
+fn ods_epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(1899, 12, 30)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .expect("valid ODF epoch")
+}
+
+/// `office:date-value` (`2024-01-15` or `2024-01-15T10:30:00`) as a serial.
+fn parse_date_value(raw: &str) -> Option<OdsValue> {
+    let (datetime, has_time) = match NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S%.f") {
+        Ok(dt) => (dt, true),
+        Err(_) => (
+            NaiveDate::parse_from_str(raw.get(..10)?, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)?,
+            false,
+        ),
+    };
+    let delta = datetime - ods_epoch();
+    let serial = delta.num_milliseconds() as f64 / 86_400_000.0;
+    let format = if has_time && serial.fract() != 0.0 {
+        DATE_TIME_FORMAT
+    } else {
+        DATE_FORMAT
+    };
+    Some(OdsValue::Date(serial, format))
+}
+
+/// ISO 8601 duration (`PT10H30M00S`, `P1DT2H`) as a fraction of a day.
+fn parse_duration(raw: &str) -> Option<f64> {
+    let (negative, body) = match raw.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, raw),
+    };
+    let body = body.strip_prefix('P')?;
+    let mut seconds = 0.0f64;
+    let mut number = String::new();
+    let mut in_time = false;
+    for ch in body.chars() {
+        match ch {
+            'T' => in_time = true,
+            '0'..='9' | '.' => number.push(ch),
+            unit => {
+                let value: f64 = number.parse().ok()?;
+                number.clear();
+                seconds += value
+                    * match (unit, in_time) {
+                        ('D', false) => 86_400.0,
+                        ('H', true) => 3_600.0,
+                        ('M', true) => 60.0,
+                        ('S', true) => 1.0,
+                        _ => return None,
+                    };
+            }
+        }
+    }
+    let days = seconds / 86_400.0;
+    Some(if negative { -days } else { days })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_odf_dates_and_durations() {
+        assert_eq!(
+            parse_date_value("2024-02-29"),
+            Some(OdsValue::Date(45351.0, DATE_FORMAT))
+        );
+        assert_eq!(
+            parse_date_value("2024-02-29T12:00:00"),
+            Some(OdsValue::Date(45351.5, DATE_TIME_FORMAT))
+        );
+        assert_eq!(parse_duration("PT12H00M00S"), Some(0.5));
+        assert_eq!(parse_duration("P1DT6H"), Some(1.25));
+    }
+}
@@ -0,0 +1,233 @@
+use super::formula;
+use crate::model::CellValue;
+use crate::workbook::cell_to_value;
+use anyhow::{Context, Result};
+use chrono::{Duration, NaiveDate};
+use quick_xml::escape::escape;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use umya_spreadsheet::{Cell, DefinedName, Spreadsheet, Worksheet};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const MIMETYPE: &str = "application/vnd.oasis.opendocument.spreadsheet";
+
+const MANIFEST_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.2">
+ <manifest:file-entry manifest:full-path="/" manifest:version="1.2" manifest:media-type="application/vnd.oasis.opendocument.spreadsheet"/>
+ <manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/>
+ <manifest:file-entry manifest:full-path="styles.xml" manifest:media-type="text/xml"/>
+</manifest:manifest>
+"#;
+
+const STYLES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-styles xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" office:version="1.2">
+ <office:styles/>
+</office:document-styles>
+"#;
+
+const CONTENT_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:number="urn:oasis:names:tc:opendocument:xmlns:datastyle:1.0" xmlns:of="urn:oasis:names:tc:opendocument:xmlns:of:1.2" office:version="1.2">
+<office:body>
+<office:spreadsheet>
+"#;
+
+const CONTENT_FOOTER: &str = "</office:spreadsheet>\n</office:body>\n</office:document-content>\n";
+
+/// Write `book` as an OpenDocument spreadsheet. Values, formulas, sheets and
+/// defined names are kept; styling is not.
+pub fn write(book: &Spreadsheet, path: &Path) -> Result<()> {
+    let content = render_content(book)?;
+
+    let file = File::create(path).with_context(|| format!("failed to create {:?}", path))?;
+    let mut zip = ZipWriter::new(file);
+    // The mimetype entry must come first and be stored uncompressed.
+    let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(MIMETYPE.as_bytes())?;
+    zip.start_file("META-INF/manifest.xml", deflated)?;
+    zip.write_all(MANIFEST_XML.as_bytes())?;
+    zip.start_file("styles.xml", deflated)?;
+    zip.write_all(STYLES_XML.as_bytes())?;
+    zip.start_file("content.xml", deflated)?;
+    zip.write_all(content.as_bytes())?;
+    zip.finish()?;
+    Ok(())
+}
+
+fn render_content(book: &Spreadsheet) -> Result<String> {
+    let mut out = String::from(CONTENT_HEADER);
+    for sheet in book.get_sheet_collection() {
+        render_sheet(&mut out, sheet)?;
+    }
+
+    let names: Vec<&DefinedName> = book.get_defined_names().iter().collect();
+    if !names.is_empty() {
+        out.push_str("<table:named-expressions>\n");
+        for name in names {
+            render_defined_name(&mut out, name)?;
+        }
+        out.push_str("</table:named-expressions>\n");
+    }
+    out.push_str(CONTENT_FOOTER);
+    Ok(out)
+}
+
+fn render_sheet(out: &mut String, sheet: &Worksheet) -> Result<()> {
+    let mut rows: BTreeMap<u32, BTreeMap<u32, &Cell>> = BTreeMap::new();
+    let mut max_col = 1u32;
+    for cell in sheet.get_cell_collection() {
+        let coordinate = cell.get_coordinate();
+        let (col, row) = (*coordinate.get_col_num(), *coordinate.get_row_num());
+        if cell.get_value().is_empty() && !cell.is_formula() {
+            continue;
+        }
+        max_col = max_col.max(col);
+        rows.entry(row).or_default().insert(col, cell);
+    }
+
+    writeln!(
+        out,
+        "<table:table table:name=\"{}\">",
+        escape(sheet.get_name())
+    )?;
+    writeln!(
+        out,
+        "<table:table-column table:number-columns-repeated=\"{}\"/>",
+        max_col
+    )?;
+
+    let mut next_row = 1u32;
+    for (row, cells) in &rows {
+        if *row > next_row {
+            writeln!(
+                out,
+                "<table:table-row table:number-rows-repeated=\"{}\"><table:table-cell/></table:table-row>",
+                row - next_row
+            )?;
+        }
+        out.push_str("<table:table-row>");
+        let mut next_col = 1u32;
+        for (col, cell) in cells {
+            if *col > next_col {
+                write!(
+                    out,
+                    "<table:table-cell table:number-columns-repeated=\"{}\"/>",
+                    col - next_col
+                )?;
+            }
+            render_cell(out, cell)?;
+            next_col = col + 1;
+        }
+        out.push_str("</table:table-row>\n");
+        next_row = row + 1;
+    }
+
+    let local_names = sheet.get_defined_names();
+    if !local_names.is_empty() {
+        out.push_str("<table:named-expressions>\n");
+        for name in local_names {
+            render_defined_name(out, name)?;
+        }
+        out.push_str("</table:named-expressions>\n");
+    }
+    out.push_str("</table:table>\n");
+    Ok(())
+}
+
+fn render_cell(out: &mut String, cell: &Cell) -> Result<()> {
+    out.push_str("<table:table-cell");
+    if cell.is_formula() {
+        let excel = cell.get_formula();
+        // Keep the formula even if it cannot be tokenized; LibreOffice will flag it.
+        let of = formula::to_openformula(excel).unwrap_or_else(|_| format!("of:={}", excel));
+        write!(out, " table:formula=\"{}\"", escape(&of))?;
+    }
+
+    let text = match cell_to_value(cell) {
+        Some(CellValue::Number(n)) => {
+            write!(out, " office:value-type=\"float\" office:value=\"{}\"", n)?;
+            n.to_string()
+        }
+        Some(CellValue::Bool(b)) => {
+            write!(
+                out,
+                " office:value-type=\"boolean\" office:boolean-value=\"{}\"",
+                b
+            )?;
+            if b { "TRUE" } else { "FALSE" }.to_string()
+        }
+        Some(CellValue::Date(iso)) => {
+            let date_value = cell
+                .get_value()
+                .parse::<f64>()
+                .ok()
+                .and_then(serial_to_date_value)
+                .unwrap_or_else(|| iso.clone());
+            write!(
+                out,
+                " office:value-type=\"date\" office:date-value=\"{}\"",
+                date_value
+            )?;
+            iso
+        }
+        Some(CellValue::Text(s)) | Some(CellValue::Error(s)) => {
+            out.push_str(" office:value-type=\"string\"");
+            s
+        }
+        None => String::new(),
+    };
+
+    if text.is_empty() {
+        out.push_str("/>");
+        return Ok(());
+    }
+    out.push('>');
+    for line in text.split('\n') {
+        write!(out, "<text:p>{}</text:p>", escape(line))?;
+    }
+    out.push_str("</table:table-cell>");
+    Ok(())
+}
+
+fn render_defined_name(out: &mut String, name: &DefinedName) -> Result<()> {
+    let address = name.get_address();
+    let address = address.trim_start_matches('=');
+    if formula::is_cell_reference(address) && address.contains('!') {
+        let ods_address = formula::reference_to_ods_address(address);
+        let base = ods_address.split(':').next().unwrap_or(&ods_address);
+        writeln!(
+            out,
+            "<table:named-range table:name=\"{}\" table:base-cell-address=\"{}\" table:cell-range-address=\"{}\"/>",
+            escape(name.get_name()),
+            escape(base),
+            escape(&ods_address)
+        )?;
+    } else {
+        let expression =
+            formula::to_openformula(address).unwrap_or_else(|_| format!("of:={}", address));
+        writeln!(
+            out,
+            "<table:named-expression table:name=\"{}\" table:expression=\"{}\"/>",
+            escape(name.get_name()),
+            escape(&expression)
+        )?;
+    }
+    Ok(())
+}
+
+/// Serial (1899-12-30 epoch, shared by ODF and post-1900 Excel) to `office:date-value`.
+fn serial_to_date_value(serial: f64) -> Option<String> {
+    let epoch = NaiveDate::from_ymd_opt(1899, 12, 30)?.and_hms_opt(0, 0, 0)?;
+    let millis = (serial * 86_400_000.0).round() as i64;
+    let datetime = epoch.checked_add_signed(Duration::milliseconds(millis))?;
+    if serial.fract() == 0.0 {
+        Some(datetime.format("%Y-%m-%d").to_string())
+    } else {
+        Some(datetime.format("%Y-%m-%dT%H:%M:%S").to_string())
+    }
+}
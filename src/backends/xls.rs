@@ -0,0 +1,193 @@
+//! Read-only loader for legacy BIFF8 `.xls` and binary `.xlsb` workbooks.
+//!
+//! calamine decodes values, formulas and defined names; they are copied into a
+//! fresh umya `Spreadsheet`. Styles, tables, comments and conditional formats
+//! are not carried over (see `BackendCaps::xls_readonly`).
+
+use anyhow::{Context, Result, anyhow};
+use calamine::{
+    Data, ExcelDateTime, ExcelDateTimeType, Range, Reader, SheetType, SheetVisible,
+    open_workbook_auto,
+};
+use std::path::Path;
+use umya_spreadsheet::{Spreadsheet, Worksheet};
+
+const DATE_FORMAT: &str = "yyyy-mm-dd";
+const DATE_TIME_FORMAT: &str = "yyyy-mm-dd hh:mm:ss";
+const DURATION_FORMAT: &str = "[h]:mm:ss";
+/// Serial of 1904-01-01 in the 1900 date system.
+const DAYS_1900_TO_1904: f64 = 1462.0;
+
+pub(super) fn read(path: &Path) -> Result<Spreadsheet> {
+    let mut workbook =
+        open_workbook_auto(path).with_context(|| format!("failed to open workbook {:?}", path))?;
+    let sheets: Vec<_> = workbook
+        .sheets_metadata()
+        .iter()
+        .filter(|sheet| sheet.typ == SheetType::WorkSheet)
+        .cloned()
+        .collect();
+
+    let mut book = umya_spreadsheet::new_file_empty_worksheet();
+    for meta in &sheets {
+        let values = workbook
+            .worksheet_range(&meta.name)
+            .with_context(|| format!("failed to read sheet '{}' in {:?}", meta.name, path))?;
+        // Not every format exposes formulas; fall back to values only.
+        let formulas = workbook.worksheet_formula(&meta.name).unwrap_or_default();
+
+        let sheet = book
+            .new_sheet(meta.name.clone())
+            .map_err(|e| anyhow!("failed to create sheet '{}': {}", meta.name, e))?;
+        match meta.visible {
+            SheetVisible::Visible => {}
+            SheetVisible::Hidden => sheet.set_sheet_state("hidden".to_string()),
+            SheetVisible::VeryHidden => sheet.set_sheet_state("veryHidden".to_string()),
+        }
+        populate_sheet(sheet, &values, &formulas);
+    }
+
+    if book.get_sheet_collection().is_empty() {
+        return Err(anyhow!("workbook {:?} contains no worksheets", path));
+    }
+
+    for (name, formula) in workbook.defined_names() {
+        let address = formula.trim_start_matches('=');
+        if address.is_empty() || name.starts_with("_xlnm.") {
+            continue;
+        }
+        if let Err(e) = book.add_defined_name(name.clone(), address.to_string()) {
+            tracing::debug!(name = %name, error = %e, "skipping defined name");
+        }
+    }
+
+    Ok(book)
+}
+
+fn populate_sheet(sheet: &mut Worksheet, values: &Range<Data>, formulas: &Range<String>) {
+    if let Some((start_row, start_col)) = values.start() {
+        for (row, col, value) in values.used_cells() {
+            let coordinate = (start_col + col as u32 + 1, start_row + row as u32 + 1);
+            let cell = sheet.get_cell_mut(coordinate);
+            match value {
+                Data::Int(n) => {
+                    cell.set_value_number(*n as f64);
+                }
+                Data::Float(n) => {
+                    cell.set_value_number(*n);
+                }
+                Data::String(s) => {
+                    cell.set_value_string(s.clone());
+                }
+                Data::Bool(b) => {
+                    cell.set_value_bool(*b);
+                }
+                Data::DateTime(dt) => {
+                    let serial = serial_1900(dt);
+                    cell.set_value_number(serial);
+                    let format = if dt.is_duration() {
+                        DURATION_FORMAT
+                    } else if serial.fract() == 0.0 {
+                        DATE_FORMAT
+                    } else {
+                        DATE_TIME_FORMAT
+                    };
+                    sheet
+                        .get_style_mut(coordinate)
+                        .get_number_format_mut()
+                        .set_format_code(format);
+                }
+                Data::DateTimeIso(s) | Data::DurationIso(s) => {
+                    cell.set_value_string(s.clone());
+                }
+                Data::Error(err) => {
+                    cell.set_value(err.to_string());
+                }
+                Data::Empty => {}
+            }
+        }
+    }
+
+    if let Some((start_row, start_col)) = formulas.start() {
+        for (row, col, formula) in formulas.used_cells() {
+            let formula = formula.trim_start_matches('=');
+            if formula.is_empty() {
+                continue;
+            }
+            let coordinate = (start_col + col as u32 + 1, start_row + row as u32 + 1);
+            sheet
+                .get_cell_mut(coordinate)
+                .set_formula(formula.to_string());
+        }
+    }
+}
+
+/// Serial for `dt` in the 1900 date system, which is the only one umya and
+/// the read tools assume. calamine keeps the stored serial of 1904-system
+/// workbooks and records the system in a private flag, so it is recovered by
+/// comparing against the same serial tagged as 1900. Durations have no epoch.
+fn serial_1900(dt: &ExcelDateTime) -> f64 {
+    if dt.is_duration() {
+        return dt.as_f64();
+    }
+    let as_1900 = ExcelDateTime::new(dt.as_f64(), ExcelDateTimeType::DateTime, false);
+    if *dt == as_1900 {
+        dt.as_f64()
+    } else {
+        dt.as_f64() + DAYS_1900_TO_1904
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn populate_sheet_offsets_ranges_and_keeps_cached_values() {
+        let mut values = Range::new((2, 1), (3, 2));
+        values.set_value((2, 1), Data::String("Revenue".to_string()));
+        values.set_value((2, 2), Data::Float(1250.5));
+        values.set_value((3, 1), Data::Bool(true));
+        values.set_value((3, 2), Data::Int(2501));
+        let mut formulas = Range::new((3, 2), (3, 2));
+        formulas.set_value((3, 2), "C3*2".to_string());
+
+        let mut book = umya_spreadsheet::new_file_empty_worksheet();
+        let sheet = book.new_sheet("Data").unwrap();
+        populate_sheet(sheet, &values, &formulas);
+
+        assert_eq!(sheet.get_value("B3"), "Revenue");
+        assert_eq!(sheet.get_value("C3"), "1250.5");
+        assert_eq!(sheet.get_value("B4"), "TRUE");
+        let formula_cell = sheet.get_cell("C4").expect("formula cell");
+        assert!(formula_cell.is_formula());
+        assert_eq!(formula_cell.get_formula(), "C3*2");
+        assert_eq!(formula_cell.get_value(), "2501");
+    }
+
+    #[test]
+    fn populate_sheet_moves_1904_dates_onto_the_1900_epoch() {
+        let mut values = Range::new((0, 0), (0, 2));
+        let dt = |serial, kind, is_1904| Data::DateTime(ExcelDateTime::new(serial, kind, is_1904));
+        values.set_value((0, 0), dt(43904.0, ExcelDateTimeType::DateTime, true));
+        values.set_value((0, 1), dt(45366.5, ExcelDateTimeType::DateTime, false));
+        values.set_value((0, 2), dt(1.25, ExcelDateTimeType::TimeDelta, true));
+
+        let mut book = umya_spreadsheet::new_file_empty_worksheet();
+        let sheet = book.new_sheet("Data").unwrap();
+        populate_sheet(sheet, &values, &Range::empty());
+
+        assert_eq!(sheet.get_value("A1"), "45366");
+        assert_eq!(sheet.get_value("B1"), "45366.5");
+        assert_eq!(sheet.get_value("C1"), "1.25");
+        let format = |addr: &str| {
+            sheet
+                .get_style(addr)
+                .get_number_format()
+                .map(|f| f.get_format_code().to_string())
+        };
+        assert_eq!(format("A1").as_deref(), Some(DATE_FORMAT));
+        assert_eq!(format("B1").as_deref(), Some(DATE_TIME_FORMAT));
+        assert_eq!(format("C1").as_deref(), Some(DURATION_FORMAT));
+    }
+}
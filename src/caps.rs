@@ -25,9 +25,35 @@ impl BackendCaps {
         }
     }
 
+    /// Read-only legacy `.xls`/`.xlsb`: values, formulas and defined names only.
+    pub fn xls_readonly() -> Self {
+        Self {
+            backend: BackendKind::XlsCalamine,
+            supports_styles: false,
+            supports_tables: false,
+            supports_comments: false,
+            supports_defined_names: true,
+            supports_conditional_formatting: false,
+            supports_formula_graph: true,
+        }
+    }
+
+    /// CSV/TSV: a single sheet of plain values.
+    pub fn delimited() -> Self {
+        Self {
+            backend: BackendKind::Delimited,
+            supports_styles: false,
+            supports_tables: false,
+            supports_comments: false,
+            supports_defined_names: false,
+            supports_conditional_formatting: false,
+            supports_formula_graph: false,
+        }
+    }
+
     pub fn degraded_for_ods() -> Self {
         Self {
-            backend: BackendKind::OdsFuture,
+            backend: BackendKind::Ods,
             supports_styles: false,
             supports_tables: false,
             supports_comments: false,
@@ -42,5 +68,7 @@ impl BackendCaps {
 #[serde(rename_all = "snake_case")]
 pub enum BackendKind {
     XlsxUmya,
-    OdsFuture,
+    XlsCalamine,
+    Ods,
+    Delimited,
 }
@@ -0,0 +1,676 @@
+//! Charts: reading chart definitions out of an xlsx package.
+//!
+//! Each worksheet points at a drawing part whose anchors hold graphic frames;
+//! a frame that carries a chart points at an `xl/charts/chartN.xml` part.
+//! Chart parts are small, so they are parsed into an [`XmlNode`] tree and
+//! read from there. Series references are kept as written; resolving them
+//! against cells is left to the caller, which holds the workbook.
+
+use crate::backends::is_ooxml;
+use crate::comments::{read_string, rels_path_for, resolve_target, sheet_parts};
+use crate::data_validation::{Area, resolve_reference};
+use crate::utils::column_number_to_name;
+use anyhow::{Result, anyhow};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::Path;
+use umya_spreadsheet::{Spreadsheet, Worksheet};
+use zip::ZipArchive;
+
+pub(crate) const DRAWING_REL: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/drawing";
+pub(crate) const CHART_REL: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/chart";
+
+/// A parsed XML element. Lookups go by local name; the qualified name and
+/// attribute keys are kept so an edited tree can be written back.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct XmlNode {
+    /// Local name, e.g. `barChart` for `<c:barChart>`.
+    pub name: String,
+    pub qname: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<XmlNode>,
+    pub text: String,
+}
+
+impl XmlNode {
+    /// Parse a document into a nameless root holding its top-level element.
+    pub fn parse(xml: &str) -> Result<XmlNode> {
+        let mut reader = Reader::from_str(xml);
+        let mut stack = vec![XmlNode::default()];
+        loop {
+            match reader.read_event()? {
+                Event::Start(ref e) => stack.push(Self::from_start(e)?),
+                Event::Empty(ref e) => {
+                    let node = Self::from_start(e)?;
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(node);
+                    }
+                }
+                Event::End(_) => {
+                    if stack.len() > 1
+                        && let Some(node) = stack.pop()
+                        && let Some(parent) = stack.last_mut()
+                    {
+                        parent.children.push(node);
+                    }
+                }
+                Event::Text(t) => {
+                    if let Some(node) = stack.last_mut() {
+                        node.text.push_str(&t.unescape()?);
+                    }
+                }
+                Event::CData(t) => {
+                    if let Some(node) = stack.last_mut() {
+                        node.text.push_str(&String::from_utf8_lossy(&t));
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        stack.truncate(1);
+        Ok(stack.pop().unwrap_or_default())
+    }
+
+    /// Parse a fragment holding a single element.
+    pub fn parse_element(xml: &str) -> Result<XmlNode> {
+        Self::parse(xml)?
+            .children
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("empty XML fragment"))
+    }
+
+    fn from_start(e: &BytesStart<'_>) -> Result<XmlNode> {
+        let mut attrs = Vec::new();
+        for attr in e.attributes() {
+            let attr = attr?;
+            attrs.push((
+                String::from_utf8_lossy(attr.key.as_ref()).to_string(),
+                attr.unescape_value()?.to_string(),
+            ));
+        }
+        Ok(XmlNode {
+            name: String::from_utf8_lossy(e.local_name().as_ref()).to_string(),
+            qname: String::from_utf8_lossy(e.name().as_ref()).to_string(),
+            attrs,
+            children: Vec::new(),
+            text: String::new(),
+        })
+    }
+
+    /// Attribute by qualified key, or by local name when unambiguous enough
+    /// for these parts (`r:id` answers to `id`).
+    pub fn attr(&self, key: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == key)
+            .or_else(|| {
+                self.attrs.iter().find(|(k, _)| {
+                    !k.starts_with("xmlns") && k.rsplit_once(':').is_some_and(|(_, l)| l == key)
+                })
+            })
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn set_attr(&mut self, key: &str, value: &str) {
+        match self.attrs.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.attrs.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn child(&self, name: &str) -> Option<&XmlNode> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    pub fn child_mut(&mut self, name: &str) -> Option<&mut XmlNode> {
+        self.children.iter_mut().find(|c| c.name == name)
+    }
+
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a XmlNode> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// The `val` attribute of the named child, as in `<c:barDir val="col"/>`.
+    pub fn val(&self, name: &str) -> Option<&str> {
+        self.child(name).and_then(|c| c.attr("val"))
+    }
+
+    /// Depth-first search for the first element with this name.
+    pub fn find(&self, name: &str) -> Option<&XmlNode> {
+        self.children.iter().find_map(|c| {
+            if c.name == name {
+                Some(c)
+            } else {
+                c.find(name)
+            }
+        })
+    }
+
+    pub fn find_all<'a>(&'a self, name: &str, out: &mut Vec<&'a XmlNode>) {
+        for child in &self.children {
+            if child.name == name {
+                out.push(child);
+            } else {
+                child.find_all(name, out);
+            }
+        }
+    }
+
+    /// Serialize a root from [`XmlNode::parse`] as a standalone part.
+    pub fn to_document(&self) -> String {
+        let mut out =
+            String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n");
+        for child in &self.children {
+            child.write_to(&mut out);
+        }
+        out
+    }
+
+    fn write_to(&self, out: &mut String) {
+        out.push('<');
+        out.push_str(&self.qname);
+        for (key, value) in &self.attrs {
+            out.push_str(&format!(" {}=\"{}\"", key, escape(value)));
+        }
+        // Whitespace between child elements is indentation, not content.
+        let text = if self.children.is_empty() || !self.text.trim().is_empty() {
+            self.text.as_str()
+        } else {
+            ""
+        };
+        if self.children.is_empty() && text.is_empty() {
+            out.push_str("/>");
+            return;
+        }
+        out.push('>');
+        out.push_str(&escape(text));
+        for child in &self.children {
+            child.write_to(out);
+        }
+        out.push_str("</");
+        out.push_str(&self.qname);
+        out.push('>');
+    }
+}
+
+fn escape(value: &str) -> String {
+    quick_xml::escape::escape(value).to_string()
+}
+
+/// Where a chart sits on its sheet, as cell addresses.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChartPlacement {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// A reference as written in the chart plus the values Excel cached for it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CachedRef {
+    pub formula: Option<String>,
+    pub cache: Vec<Option<String>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SeriesDef {
+    pub index: u32,
+    pub chart_type: String,
+    /// Literal name, when the series has no name reference.
+    pub name_literal: Option<String>,
+    pub name: CachedRef,
+    /// `cat`, or `xVal` for scatter and bubble charts.
+    pub categories: CachedRef,
+    /// `val`, or `yVal` for scatter and bubble charts.
+    pub values: CachedRef,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AxisDef {
+    /// category | value | date | series
+    pub kind: String,
+    pub position: Option<String>,
+    pub title: Option<String>,
+    pub deleted: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChartDef {
+    /// Package path of the chart part, e.g. `xl/charts/chart1.xml`.
+    pub part: String,
+    /// Drawing object name, e.g. `Chart 1`.
+    pub name: Option<String>,
+    pub placement: ChartPlacement,
+    /// Type of the first plot; combo charts list each series' own type.
+    pub chart_type: String,
+    pub title: Option<String>,
+    pub axes: Vec<AxisDef>,
+    pub series: Vec<SeriesDef>,
+}
+
+/// Charts of every sheet, in workbook and drawing order. Packages other
+/// than xlsx/xlsm have none.
+pub fn read_charts(path: &Path) -> Result<Vec<(String, Vec<ChartDef>)>> {
+    if !is_ooxml(path) {
+        return Ok(Vec::new());
+    }
+    charts_from_zip(&mut ZipArchive::new(File::open(path)?)?)
+}
+
+pub fn charts_from_zip(zip: &mut ZipArchive<File>) -> Result<Vec<(String, Vec<ChartDef>)>> {
+    let mut out = Vec::new();
+    for (sheet_name, sheet_path) in sheet_parts(zip)? {
+        let mut charts = Vec::new();
+        for (rel_type, drawing) in relationships(zip, &sheet_path)?.into_values() {
+            if rel_type != DRAWING_REL {
+                continue;
+            }
+            charts.extend(read_drawing_charts(zip, &drawing)?);
+        }
+        out.push((sheet_name, charts));
+    }
+    Ok(out)
+}
+
+fn read_drawing_charts(zip: &mut ZipArchive<File>, drawing: &str) -> Result<Vec<ChartDef>> {
+    let Some(xml) = read_string(zip, drawing)? else {
+        return Ok(Vec::new());
+    };
+    let rels = relationships(zip, drawing)?;
+    let mut charts = Vec::new();
+    for (placement, name, rid) in drawing_frames(&XmlNode::parse(&xml)?) {
+        let Some((_, part)) = rels.get(&rid).filter(|(t, _)| t == CHART_REL) else {
+            continue;
+        };
+        let Some(chart_xml) = read_string(zip, part)? else {
+            continue;
+        };
+        let mut chart = parse_chart_space(&XmlNode::parse(&chart_xml)?);
+        chart.part = part.clone();
+        chart.name = name;
+        chart.placement = placement;
+        charts.push(chart);
+    }
+    Ok(charts)
+}
+
+pub(crate) fn relationships(
+    zip: &mut ZipArchive<File>,
+    owner: &str,
+) -> Result<BTreeMap<String, (String, String)>> {
+    match read_string(zip, &rels_path_for(owner))? {
+        Some(rels) => parse_relationships(owner, &rels),
+        None => Ok(BTreeMap::new()),
+    }
+}
+
+/// Relationship id -> (type, resolved target) for the part owning `rels`.
+/// External targets are skipped.
+pub(crate) fn parse_relationships(
+    owner: &str,
+    rels: &str,
+) -> Result<BTreeMap<String, (String, String)>> {
+    let mut out = BTreeMap::new();
+    let root = XmlNode::parse(rels)?;
+    if let Some(list) = root.child("Relationships") {
+        for rel in list.children_named("Relationship") {
+            if rel.attr("TargetMode") == Some("External") {
+                continue;
+            }
+            if let (Some(id), Some(kind), Some(target)) =
+                (rel.attr("Id"), rel.attr("Type"), rel.attr("Target"))
+            {
+                out.insert(
+                    id.to_string(),
+                    (kind.to_string(), resolve_target(owner, target)),
+                );
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Chart frames of a drawing as (placement, object name, relationship id).
+pub(crate) fn drawing_frames(root: &XmlNode) -> Vec<(ChartPlacement, Option<String>, String)> {
+    let Some(ws_dr) = root.child("wsDr") else {
+        return Vec::new();
+    };
+    let mut frames = Vec::new();
+    for anchor in &ws_dr.children {
+        if !matches!(
+            anchor.name.as_str(),
+            "twoCellAnchor" | "oneCellAnchor" | "absoluteAnchor"
+        ) {
+            continue;
+        }
+        let placement = ChartPlacement {
+            from: anchor.child("from").and_then(marker_address),
+            to: anchor.child("to").and_then(marker_address),
+        };
+        let mut graphic_frames = Vec::new();
+        anchor.find_all("graphicFrame", &mut graphic_frames);
+        for frame in graphic_frames {
+            let Some(rid) = frame.find("chart").and_then(|c| c.attr("id")) else {
+                continue;
+            };
+            let name = frame
+                .find("cNvPr")
+                .and_then(|c| c.attr("name"))
+                .map(str::to_string);
+            frames.push((placement.clone(), name, rid.to_string()));
+        }
+    }
+    frames
+}
+
+/// `<xdr:from><xdr:col>2</xdr:col><xdr:row>4</xdr:row>...` as `C5`.
+fn marker_address(marker: &XmlNode) -> Option<String> {
+    let col: u32 = marker.child("col")?.text.trim().parse().ok()?;
+    let row: u32 = marker.child("row")?.text.trim().parse().ok()?;
+    Some(format!("{}{}", column_number_to_name(col + 1), row + 1))
+}
+
+pub(crate) fn parse_chart_space(root: &XmlNode) -> ChartDef {
+    let mut chart = ChartDef::default();
+    let Some(body) = root.child("chartSpace").and_then(|cs| cs.child("chart")) else {
+        return chart;
+    };
+    chart.title = body.child("title").and_then(title_text);
+    let Some(plot_area) = body.child("plotArea") else {
+        return chart;
+    };
+    for node in &plot_area.children {
+        if let Some(plot_type) = plot_type_name(node) {
+            if chart.chart_type.is_empty() {
+                chart.chart_type = plot_type.clone();
+            }
+            for ser in node.children_named("ser") {
+                chart.series.push(parse_series(ser, &plot_type));
+            }
+            continue;
+        }
+        let kind = match node.name.as_str() {
+            "catAx" => "category",
+            "valAx" => "value",
+            "dateAx" => "date",
+            "serAx" => "series",
+            _ => continue,
+        };
+        chart.axes.push(AxisDef {
+            kind: kind.to_string(),
+            position: node.val("axPos").map(|p| {
+                match p {
+                    "b" => "bottom",
+                    "t" => "top",
+                    "l" => "left",
+                    "r" => "right",
+                    other => other,
+                }
+                .to_string()
+            }),
+            title: node.child("title").and_then(title_text),
+            deleted: matches!(node.val("delete"), Some("1") | Some("true")),
+        });
+    }
+    chart
+}
+
+/// `column`, `bar`, `line`, `pie`, ... with a `_3d` suffix for 3-D plots;
+/// `None` for children of the plot area that are not plots.
+pub(crate) fn plot_type_name(node: &XmlNode) -> Option<String> {
+    let base = match node.name.as_str() {
+        "barChart" | "bar3DChart" => match node.val("barDir") {
+            Some("bar") => "bar",
+            _ => "column",
+        },
+        "lineChart" | "line3DChart" => "line",
+        "pieChart" | "pie3DChart" | "ofPieChart" => "pie",
+        "doughnutChart" => "doughnut",
+        "areaChart" | "area3DChart" => "area",
+        "scatterChart" => "scatter",
+        "radarChart" => "radar",
+        "bubbleChart" => "bubble",
+        "stockChart" => "stock",
+        "surfaceChart" | "surface3DChart" => "surface",
+        _ => return None,
+    };
+    Some(if node.name.contains("3D") {
+        format!("{base}_3d")
+    } else {
+        base.to_string()
+    })
+}
+
+fn parse_series(ser: &XmlNode, chart_type: &str) -> SeriesDef {
+    let tx = ser.child("tx");
+    SeriesDef {
+        index: ser
+            .val("idx")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default(),
+        chart_type: chart_type.to_string(),
+        name_literal: tx.and_then(|tx| tx.child("v")).map(|v| v.text.clone()),
+        name: tx.map(data_ref).unwrap_or_default(),
+        categories: ser
+            .child("cat")
+            .or_else(|| ser.child("xVal"))
+            .map(data_ref)
+            .unwrap_or_default(),
+        values: ser
+            .child("val")
+            .or_else(|| ser.child("yVal"))
+            .map(data_ref)
+            .unwrap_or_default(),
+    }
+}
+
+/// The formula and cached points under `c:tx`, `c:cat`, `c:val` and friends.
+fn data_ref(holder: &XmlNode) -> CachedRef {
+    let Some(reference) = holder
+        .children
+        .iter()
+        .find(|c| matches!(c.name.as_str(), "strRef" | "numRef" | "multiLvlStrRef"))
+    else {
+        // Literal data: `c:strLit` / `c:numLit`.
+        return CachedRef {
+            formula: None,
+            cache: holder
+                .children
+                .iter()
+                .find(|c| matches!(c.name.as_str(), "strLit" | "numLit"))
+                .map(cache_points)
+                .unwrap_or_default(),
+        };
+    };
+    let cache = reference
+        .children
+        .iter()
+        .find(|c| c.name.ends_with("Cache"))
+        .map(|cache| match cache.child("lvl") {
+            // Multi-level categories: the innermost level is listed first.
+            Some(level) => cache_points_sized(level, cache.val("ptCount")),
+            None => cache_points(cache),
+        })
+        .unwrap_or_default();
+    CachedRef {
+        formula: reference.child("f").map(|f| f.text.trim().to_string()),
+        cache,
+    }
+}
+
+fn cache_points(cache: &XmlNode) -> Vec<Option<String>> {
+    cache_points_sized(cache, cache.val("ptCount"))
+}
+
+fn cache_points_sized(holder: &XmlNode, count: Option<&str>) -> Vec<Option<String>> {
+    let points: Vec<(usize, String)> = holder
+        .children_named("pt")
+        .filter_map(|pt| {
+            let idx = pt.attr("idx")?.parse().ok()?;
+            Some((
+                idx,
+                pt.child("v").map(|v| v.text.clone()).unwrap_or_default(),
+            ))
+        })
+        .collect();
+    let count = count
+        .and_then(|c| c.parse::<usize>().ok())
+        .unwrap_or_else(|| points.iter().map(|(i, _)| i + 1).max().unwrap_or(0));
+    let mut out = vec![None; count];
+    for (idx, value) in points {
+        if let Some(slot) = out.get_mut(idx) {
+            *slot = Some(value);
+        }
+    }
+    out
+}
+
+/// Rich title text (paragraphs joined by newlines), or the cached text of a
+/// title that points at a cell.
+pub(crate) fn title_text(title: &XmlNode) -> Option<String> {
+    let tx = title.child("tx")?;
+    if let Some(rich) = tx.child("rich") {
+        let text = rich
+            .children_named("p")
+            .map(|p| {
+                let mut runs = Vec::new();
+                p.find_all("t", &mut runs);
+                runs.iter().map(|t| t.text.as_str()).collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        return (!text.is_empty()).then_some(text);
+    }
+    data_ref(tx).cache.into_iter().flatten().next()
+}
+
+/// Resolve a series formula such as `Sheet1!$B$2:$B$9` or the union
+/// `(Sheet1!$B$2,Sheet1!$B$5)` to sheet areas. `None` when any piece
+/// points outside the workbook or at a computed name.
+pub fn resolve_series_ref<'a>(
+    book: &'a Spreadsheet,
+    host: &'a Worksheet,
+    formula: &str,
+) -> Option<Vec<(&'a Worksheet, Area)>> {
+    let formula = formula.trim().trim_start_matches('=');
+    let formula = formula
+        .strip_prefix('(')
+        .and_then(|f| f.strip_suffix(')'))
+        .unwrap_or(formula);
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, c) in formula.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            ',' if !quoted => {
+                pieces.push(&formula[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    pieces.push(&formula[start..]);
+    pieces
+        .into_iter()
+        .map(|piece| resolve_reference(book, host, piece))
+        .collect()
+}
+
+/// `Sheet!A1:B2` for a resolved area, quoting sheet names as Excel does.
+pub fn qualified_range(sheet: &Worksheet, area: &Area) -> String {
+    let name = sheet.get_name();
+    let plain = name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !name.starts_with(|c: char| c.is_ascii_digit());
+    if plain {
+        format!("{}!{}", name, area.to_a1())
+    } else {
+        format!("'{}'!{}", name.replace('\'', "''"), area.to_a1())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHART: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<c:chartSpace xmlns:c="http://schemas.openxmlformats.org/drawingml/2006/chart" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main">
+  <c:chart>
+    <c:title><c:tx><c:rich><a:bodyPr/><a:p><a:r><a:t>Revenue &amp; Cost</a:t></a:r></a:p></c:rich></c:tx></c:title>
+    <c:plotArea>
+      <c:barChart>
+        <c:barDir val="col"/>
+        <c:ser>
+          <c:idx val="0"/><c:order val="0"/>
+          <c:tx><c:strRef><c:f>Data!$B$1</c:f><c:strCache><c:ptCount val="1"/><c:pt idx="0"><c:v>Revenue</c:v></c:pt></c:strCache></c:strRef></c:tx>
+          <c:cat><c:strRef><c:f>Data!$A$2:$A$4</c:f><c:strCache><c:ptCount val="3"/><c:pt idx="0"><c:v>Q1</c:v></c:pt><c:pt idx="2"><c:v>Q3</c:v></c:pt></c:strCache></c:strRef></c:cat>
+          <c:val><c:numRef><c:f>Data!$B$2:$B$4</c:f><c:numCache><c:ptCount val="3"/><c:pt idx="0"><c:v>10</c:v></c:pt></c:numCache></c:numRef></c:val>
+        </c:ser>
+      </c:barChart>
+      <c:lineChart>
+        <c:ser><c:idx val="1"/><c:tx><c:v>Cost</c:v></c:tx><c:val><c:numRef><c:f>'My Data'!$C$2:$C$4</c:f></c:numRef></c:val></c:ser>
+      </c:lineChart>
+      <c:catAx><c:axId val="1"/><c:delete val="0"/><c:axPos val="b"/></c:catAx>
+      <c:valAx><c:axId val="2"/><c:axPos val="l"/><c:title><c:tx><c:rich><a:p><a:r><a:t>USD</a:t></a:r></a:p></c:rich></c:tx></c:title></c:valAx>
+    </c:plotArea>
+  </c:chart>
+</c:chartSpace>"#;
+
+    #[test]
+    fn parses_titles_series_and_axes() {
+        let chart = parse_chart_space(&XmlNode::parse(CHART).unwrap());
+        assert_eq!(chart.chart_type, "column");
+        assert_eq!(chart.title.as_deref(), Some("Revenue & Cost"));
+        assert_eq!(chart.series.len(), 2);
+
+        let revenue = &chart.series[0];
+        assert_eq!(revenue.name.formula.as_deref(), Some("Data!$B$1"));
+        assert_eq!(revenue.name.cache, vec![Some("Revenue".to_string())]);
+        assert_eq!(
+            revenue.categories.formula.as_deref(),
+            Some("Data!$A$2:$A$4")
+        );
+        assert_eq!(
+            revenue.categories.cache,
+            vec![Some("Q1".to_string()), None, Some("Q3".to_string())]
+        );
+        assert_eq!(revenue.values.cache.len(), 3);
+
+        let cost = &chart.series[1];
+        assert_eq!(cost.chart_type, "line");
+        assert_eq!(cost.index, 1);
+        assert_eq!(cost.name_literal.as_deref(), Some("Cost"));
+        assert_eq!(cost.values.formula.as_deref(), Some("'My Data'!$C$2:$C$4"));
+
+        assert_eq!(chart.axes.len(), 2);
+        assert_eq!(chart.axes[0].kind, "category");
+        assert_eq!(chart.axes[0].position.as_deref(), Some("bottom"));
+        assert_eq!(chart.axes[1].title.as_deref(), Some("USD"));
+    }
+
+    #[test]
+    fn reads_chart_frames_from_drawing_anchors() {
+        let drawing = r#"<xdr:wsDr xmlns:xdr="http://schemas.openxmlformats.org/drawingml/2006/spreadsheetDrawing" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:c="http://schemas.openxmlformats.org/drawingml/2006/chart">
+  <xdr:twoCellAnchor>
+    <xdr:from><xdr:col>3</xdr:col><xdr:colOff>0</xdr:colOff><xdr:row>1</xdr:row><xdr:rowOff>0</xdr:rowOff></xdr:from>
+    <xdr:to><xdr:col>10</xdr:col><xdr:colOff>0</xdr:colOff><xdr:row>15</xdr:row><xdr:rowOff>0</xdr:rowOff></xdr:to>
+    <xdr:graphicFrame><xdr:nvGraphicFramePr><xdr:cNvPr id="2" name="Chart 1"/></xdr:nvGraphicFramePr>
+      <a:graphic><a:graphicData><c:chart r:id="rId3"/></a:graphicData></a:graphic></xdr:graphicFrame>
+    <xdr:clientData/>
+  </xdr:twoCellAnchor>
+  <xdr:oneCellAnchor><xdr:from><xdr:col>0</xdr:col><xdr:row>20</xdr:row></xdr:from><xdr:pic/></xdr:oneCellAnchor>
+</xdr:wsDr>"#;
+        let frames = drawing_frames(&XmlNode::parse(drawing).unwrap());
+        assert_eq!(frames.len(), 1);
+        let (placement, name, rid) = &frames[0];
+        assert_eq!(placement.from.as_deref(), Some("D2"));
+        assert_eq!(placement.to.as_deref(), Some("K16"));
+        assert_eq!(name.as_deref(), Some("Chart 1"));
+        assert_eq!(rid, "rId3");
+    }
+}
@@ -0,0 +1,771 @@
+//! Threaded comments (Excel 365 comments, as opposed to legacy notes).
+//!
+//! umya-spreadsheet only models legacy notes, so threaded comments are read
+//! from and written to the package directly: one `xl/threadedComments/*.xml`
+//! part per sheet plus the workbook's `xl/persons/person.xml`. Excel keeps a
+//! legacy note at every threaded anchor as a fallback for older readers;
+//! [`placeholder_text`] renders that note.
+
+use crate::backends::is_ooxml;
+use anyhow::{Result, anyhow};
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+use zip::write::FileOptions;
+
+const THREADED_COMMENT_REL: &str =
+    "http://schemas.microsoft.com/office/2017/10/relationships/threadedComment";
+const PERSON_REL: &str = "http://schemas.microsoft.com/office/2017/10/relationships/person";
+const THREADED_COMMENT_CONTENT_TYPE: &str = "application/vnd.ms-excel.threadedcomments+xml";
+const PERSON_CONTENT_TYPE: &str = "application/vnd.ms-excel.person+xml";
+const THREADED_NS: &str = "http://schemas.microsoft.com/office/spreadsheetml/2018/threadedcomments";
+const MAIN_NS: &str = "http://schemas.openxmlformats.org/spreadsheetml/2006/main";
+const PERSONS_PART: &str = "xl/persons/person.xml";
+
+/// Author prefix Excel uses for the legacy note backing a thread.
+pub const PLACEHOLDER_AUTHOR_PREFIX: &str = "tc=";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Person {
+    pub id: String,
+    pub display_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreadedComment {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub address: String,
+    pub person_id: String,
+    pub created_at: Option<String>,
+    /// Only meaningful on the first comment of a thread.
+    pub done: bool,
+    pub text: String,
+}
+
+/// One thread: the opening comment followed by its replies in order.
+#[derive(Debug, Clone, Copy)]
+pub struct Thread<'a> {
+    pub root: &'a ThreadedComment,
+    pub replies: &'a [ThreadedComment],
+}
+
+/// All threaded comments of a workbook, keyed by sheet name.
+#[derive(Debug, Clone, Default)]
+pub struct ThreadedComments {
+    pub persons: Vec<Person>,
+    sheets: BTreeMap<String, Vec<ThreadedComment>>,
+}
+
+impl ThreadedComments {
+    /// Read every threaded comment part of an xlsx/xlsm package. Other
+    /// formats have none.
+    pub fn read(path: &Path) -> Result<Self> {
+        if !is_ooxml(path) {
+            return Ok(Self::default());
+        }
+
+        Self::from_zip(&mut ZipArchive::new(File::open(path)?)?)
+    }
+
+    /// Read every threaded comment part of an already opened package.
+    pub fn from_zip(zip: &mut ZipArchive<File>) -> Result<Self> {
+        let mut out = Self::default();
+        if let Some(part) = relationship_targets(zip, "xl/workbook.xml", PERSON_REL)?
+            .into_iter()
+            .next()
+            && let Ok(f) = zip.by_name(&part)
+        {
+            out.persons = parse_persons(&mut Reader::from_reader(BufReader::new(f)))?;
+        }
+        for (sheet_name, sheet_path) in sheet_parts(zip)? {
+            let mut comments = Vec::new();
+            for part in relationship_targets(zip, &sheet_path, THREADED_COMMENT_REL)? {
+                if let Ok(f) = zip.by_name(&part) {
+                    comments.extend(parse_threaded_comments(&mut Reader::from_reader(
+                        BufReader::new(f),
+                    ))?);
+                }
+            }
+            if !comments.is_empty() {
+                out.sheets.insert(sheet_name, group_threads(comments));
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sheets.values().all(Vec::is_empty)
+    }
+
+    pub fn sheet_names(&self) -> impl Iterator<Item = &str> {
+        self.sheets.keys().map(String::as_str)
+    }
+
+    pub fn author(&self, person_id: &str) -> Option<&str> {
+        self.persons
+            .iter()
+            .find(|p| p.id == person_id)
+            .map(|p| p.display_name.as_str())
+    }
+
+    /// The id of the person with this display name, registering them if new.
+    pub fn person_id(&mut self, display_name: &str) -> String {
+        if let Some(person) = self.persons.iter().find(|p| p.display_name == display_name) {
+            return person.id.clone();
+        }
+        let id = new_guid();
+        self.persons.push(Person {
+            id: id.clone(),
+            display_name: display_name.to_string(),
+        });
+        id
+    }
+
+    /// Threads of a sheet, in the order they appear in the part.
+    pub fn threads(&self, sheet_name: &str) -> Vec<Thread<'_>> {
+        let Some(comments) = self.sheets.get(sheet_name) else {
+            return Vec::new();
+        };
+        let mut threads = Vec::new();
+        let mut start = 0;
+        while start < comments.len() {
+            let root = &comments[start];
+            let mut end = start + 1;
+            while end < comments.len() && comments[end].parent_id.as_ref() == Some(&root.id) {
+                end += 1;
+            }
+            threads.push(Thread {
+                root,
+                replies: &comments[start + 1..end],
+            });
+            start = end;
+        }
+        threads
+    }
+
+    pub fn thread(&self, sheet_name: &str, address: &str) -> Option<Thread<'_>> {
+        self.threads(sheet_name)
+            .into_iter()
+            .find(|t| t.root.address == address)
+    }
+
+    /// Start a thread at `address`; the caller makes sure none exists yet.
+    pub fn add_thread(&mut self, sheet_name: &str, address: &str, author: &str, text: &str) {
+        let comment = self.new_comment(address, author, text, None);
+        self.sheets
+            .entry(sheet_name.to_string())
+            .or_default()
+            .push(comment);
+    }
+
+    /// Append a reply to the thread at `address`. Returns false if there is none.
+    pub fn add_reply(&mut self, sheet_name: &str, address: &str, author: &str, text: &str) -> bool {
+        let Some(root_id) = self.thread(sheet_name, address).map(|t| t.root.id.clone()) else {
+            return false;
+        };
+        let reply = self.new_comment(address, author, text, Some(root_id.clone()));
+        let comments = self.sheets.entry(sheet_name.to_string()).or_default();
+        let root = comments.iter().position(|c| c.id == root_id).unwrap_or(0);
+        let end = comments[root + 1..]
+            .iter()
+            .position(|c| c.parent_id.as_ref() != Some(&root_id))
+            .map_or(comments.len(), |offset| root + 1 + offset);
+        comments.insert(end, reply);
+        true
+    }
+
+    /// The opening comment of the thread at `address`, for editing.
+    pub fn root_mut(&mut self, sheet_name: &str, address: &str) -> Option<&mut ThreadedComment> {
+        self.sheets
+            .get_mut(sheet_name)?
+            .iter_mut()
+            .find(|c| c.parent_id.is_none() && c.address == address)
+    }
+
+    /// Remove the whole thread at `address`. Returns false if there is none.
+    pub fn remove_thread(&mut self, sheet_name: &str, address: &str) -> bool {
+        let Some(comments) = self.sheets.get_mut(sheet_name) else {
+            return false;
+        };
+        let before = comments.len();
+        comments.retain(|c| c.address != address);
+        before != comments.len()
+    }
+
+    fn new_comment(
+        &mut self,
+        address: &str,
+        author: &str,
+        text: &str,
+        parent_id: Option<String>,
+    ) -> ThreadedComment {
+        ThreadedComment {
+            id: new_guid(),
+            parent_id,
+            address: address.to_string(),
+            person_id: self.person_id(author),
+            created_at: Some(
+                chrono::Utc::now()
+                    .format("%Y-%m-%dT%H:%M:%S%.2f")
+                    .to_string(),
+            ),
+            done: false,
+            text: text.to_string(),
+        }
+    }
+
+    /// Write the threaded comment parts into a package that umya has just
+    /// written (and which therefore carries none). Sheets without a part
+    /// are left alone; persons are written once for the whole workbook.
+    pub fn write_into(&self, path: &Path) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let mut zip = ZipArchive::new(File::open(path)?)?;
+        let sheets = sheet_parts(&mut zip)?;
+        let mut added: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+        let mut patched: HashMap<String, String> = HashMap::new();
+
+        let mut index = 0;
+        for (sheet_name, sheet_path) in &sheets {
+            let comments = match self.sheets.get(sheet_name) {
+                Some(comments) if !comments.is_empty() => comments,
+                _ => continue,
+            };
+            index += 1;
+            let part = format!("xl/threadedComments/threadedComment{index}.xml");
+            added.insert(part.clone(), threaded_comments_xml(comments).into_bytes());
+            let rels_path = rels_path_for(sheet_path);
+            let rels = read_string(&mut zip, &rels_path)?;
+            patched.insert(
+                rels_path,
+                add_relationship(
+                    rels.as_deref(),
+                    THREADED_COMMENT_REL,
+                    &relative_target(sheet_path, &part),
+                ),
+            );
+        }
+        if index == 0 {
+            return Ok(());
+        }
+
+        added.insert(
+            PERSONS_PART.to_string(),
+            persons_xml(&self.persons).into_bytes(),
+        );
+        let workbook_rels = "xl/_rels/workbook.xml.rels".to_string();
+        let rels = read_string(&mut zip, &workbook_rels)?;
+        patched.insert(
+            workbook_rels,
+            add_relationship(rels.as_deref(), PERSON_REL, "persons/person.xml"),
+        );
+
+        let content_types = read_string(&mut zip, "[Content_Types].xml")?
+            .ok_or_else(|| anyhow!("package has no [Content_Types].xml"))?;
+        let mut overrides = vec![(format!("/{PERSONS_PART}"), PERSON_CONTENT_TYPE)];
+        overrides.extend(
+            added
+                .keys()
+                .filter(|p| p.starts_with("xl/threadedComments/"))
+                .map(|p| (format!("/{p}"), THREADED_COMMENT_CONTENT_TYPE)),
+        );
+        patched.insert(
+            "[Content_Types].xml".to_string(),
+            add_overrides(&content_types, &overrides),
+        );
+
+        let tmp = tempfile::NamedTempFile::new_in(path.parent().unwrap_or(Path::new(".")))?;
+        {
+            let mut writer = zip::ZipWriter::new(tmp.reopen()?);
+            let options =
+                FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+            for i in 0..zip.len() {
+                let mut entry = zip.by_index(i)?;
+                let name = entry.name().to_string();
+                if added.contains_key(&name) || is_threaded_part(&name) {
+                    continue;
+                }
+                writer.start_file(name.as_str(), options)?;
+                match patched.remove(&name) {
+                    Some(content) => writer.write_all(content.as_bytes())?,
+                    None => {
+                        std::io::copy(&mut entry, &mut writer)?;
+                    }
+                }
+            }
+            // Relationship parts that did not exist yet.
+            for (name, content) in patched {
+                writer.start_file(name.as_str(), options)?;
+                writer.write_all(content.as_bytes())?;
+            }
+            for (name, content) in &added {
+                writer.start_file(name.as_str(), options)?;
+                writer.write_all(content)?;
+            }
+            writer.finish()?;
+        }
+        drop(zip);
+        tmp.persist(path)?;
+        Ok(())
+    }
+}
+
+/// Text of the legacy note Excel keeps at a thread's anchor.
+pub fn placeholder_text(threads: &ThreadedComments, thread: Thread<'_>) -> String {
+    let mut text = String::from(
+        "[Threaded comment]\n\nYour version of Excel allows you to read this threaded comment; \
+however, any edits to it will get removed if the file is opened in a newer version of Excel. \
+Learn more: https://go.microsoft.com/fwlink/?linkid=870924\n\nComment:\n    ",
+    );
+    text.push_str(&thread.root.text);
+    for reply in thread.replies {
+        text.push_str("\nReply:\n    ");
+        if let Some(author) = threads.author(&reply.person_id) {
+            text.push_str(author);
+            text.push_str(": ");
+        }
+        text.push_str(&reply.text);
+    }
+    text
+}
+
+fn new_guid() -> String {
+    format!("{{{}}}", uuid::Uuid::new_v4().to_string().to_uppercase())
+}
+
+fn is_threaded_part(name: &str) -> bool {
+    name.starts_with("xl/threadedComments/") || name.starts_with("xl/persons/")
+}
+
+/// Keep each thread's replies directly after its opening comment.
+fn group_threads(comments: Vec<ThreadedComment>) -> Vec<ThreadedComment> {
+    let (roots, replies): (Vec<_>, Vec<_>) =
+        comments.into_iter().partition(|c| c.parent_id.is_none());
+    let mut grouped = Vec::with_capacity(roots.len() + replies.len());
+    for root in roots {
+        let id = root.id.clone();
+        grouped.push(root);
+        grouped.extend(
+            replies
+                .iter()
+                .filter(|r| r.parent_id.as_deref() == Some(id.as_str()))
+                .cloned(),
+        );
+    }
+    grouped
+}
+
+fn parse_threaded_comments<R: BufRead>(reader: &mut Reader<R>) -> Result<Vec<ThreadedComment>> {
+    let mut comments = Vec::new();
+    let mut buf = Vec::new();
+    let mut current: Option<ThreadedComment> = None;
+    let mut in_text = false;
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(ref e) => match e.local_name().as_ref() {
+                b"threadedComment" => {
+                    let mut comment = ThreadedComment {
+                        id: String::new(),
+                        parent_id: None,
+                        address: String::new(),
+                        person_id: String::new(),
+                        created_at: None,
+                        done: false,
+                        text: String::new(),
+                    };
+                    for attr in e.attributes() {
+                        let attr = attr?;
+                        let value = attr.unescape_value()?.to_string();
+                        match attr.key.local_name().as_ref() {
+                            b"id" => comment.id = value,
+                            b"parentId" => comment.parent_id = Some(value),
+                            b"ref" => comment.address = value,
+                            b"personId" => comment.person_id = value,
+                            b"dT" => comment.created_at = Some(value),
+                            b"done" => comment.done = value == "1" || value == "true",
+                            _ => {}
+                        }
+                    }
+                    current = Some(comment);
+                }
+                b"text" if current.is_some() => in_text = true,
+                _ => {}
+            },
+            Event::Text(ref t) if in_text => {
+                if let Some(comment) = current.as_mut() {
+                    comment.text.push_str(&t.unescape()?);
+                }
+            }
+            Event::End(ref e) => match e.local_name().as_ref() {
+                b"text" => in_text = false,
+                b"threadedComment" => comments.extend(current.take()),
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(comments)
+}
+
+fn parse_persons<R: BufRead>(reader: &mut Reader<R>) -> Result<Vec<Person>> {
+    let mut persons = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(ref e) | Event::Empty(ref e) if e.local_name().as_ref() == b"person" => {
+                let mut person = Person {
+                    id: String::new(),
+                    display_name: String::new(),
+                };
+                for attr in e.attributes() {
+                    let attr = attr?;
+                    match attr.key.local_name().as_ref() {
+                        b"id" => person.id = attr.unescape_value()?.to_string(),
+                        b"displayName" => person.display_name = attr.unescape_value()?.to_string(),
+                        _ => {}
+                    }
+                }
+                persons.push(person);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(persons)
+}
+
+fn threaded_comments_xml(comments: &[ThreadedComment]) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<ThreadedComments xmlns=\"{THREADED_NS}\" xmlns:x=\"{MAIN_NS}\">"
+    );
+    for comment in comments {
+        xml.push_str(&format!(
+            "<threadedComment ref=\"{}\"",
+            escape(&comment.address)
+        ));
+        if let Some(created) = &comment.created_at {
+            xml.push_str(&format!(" dT=\"{}\"", escape(created)));
+        }
+        xml.push_str(&format!(
+            " personId=\"{}\" id=\"{}\"",
+            escape(&comment.person_id),
+            escape(&comment.id)
+        ));
+        if let Some(parent) = &comment.parent_id {
+            xml.push_str(&format!(" parentId=\"{}\"", escape(parent)));
+        } else if comment.done {
+            xml.push_str(" done=\"1\"");
+        }
+        xml.push_str(&format!(
+            "><text>{}</text></threadedComment>",
+            escape(&comment.text)
+        ));
+    }
+    xml.push_str("</ThreadedComments>");
+    xml
+}
+
+fn persons_xml(persons: &[Person]) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<personList xmlns=\"{THREADED_NS}\" xmlns:x=\"{MAIN_NS}\">"
+    );
+    for person in persons {
+        xml.push_str(&format!(
+            "<person displayName=\"{name}\" id=\"{id}\" userId=\"{name}\" providerId=\"None\"/>",
+            name = escape(&person.display_name),
+            id = escape(&person.id)
+        ));
+    }
+    xml.push_str("</personList>");
+    xml
+}
+
+fn escape(value: &str) -> String {
+    quick_xml::escape::escape(value).to_string()
+}
+
+pub(crate) fn read_string(zip: &mut ZipArchive<File>, name: &str) -> Result<Option<String>> {
+    match zip.by_name(name) {
+        Ok(mut f) => {
+            let mut out = String::new();
+            f.read_to_string(&mut out)?;
+            Ok(Some(out))
+        }
+        Err(zip::result::ZipError::FileNotFound) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub(crate) fn rels_path_for(part: &str) -> String {
+    let path = Path::new(part);
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    let filename = path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .unwrap_or_default();
+    parent
+        .join("_rels")
+        .join(format!("{filename}.rels"))
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Resolve a relationship target relative to the part that owns it.
+pub(crate) fn resolve_target(owner: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut path: PathBuf = Path::new(owner)
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .to_path_buf();
+    for segment in target.split('/') {
+        match segment {
+            ".." => {
+                path.pop();
+            }
+            "." | "" => {}
+            other => path.push(other),
+        }
+    }
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// `target` as seen from `owner`'s directory (both are zip paths under `xl/`).
+pub(crate) fn relative_target(owner: &str, target: &str) -> String {
+    let owner_dir = Path::new(owner).parent().unwrap_or_else(|| Path::new(""));
+    match Path::new(target).strip_prefix(owner_dir) {
+        Ok(rest) => rest.to_string_lossy().replace('\\', "/"),
+        Err(_) => {
+            let depth = owner_dir.components().count().saturating_sub(1);
+            let rest = target.strip_prefix("xl/").unwrap_or(target);
+            format!("{}{}", "../".repeat(depth), rest)
+        }
+    }
+}
+
+fn relationship_targets(
+    zip: &mut ZipArchive<File>,
+    owner: &str,
+    rel_type: &str,
+) -> Result<Vec<String>> {
+    let Some(rels) = read_string(zip, &rels_path_for(owner))? else {
+        return Ok(Vec::new());
+    };
+    let mut reader = Reader::from_str(&rels);
+    let mut targets = Vec::new();
+    loop {
+        match reader.read_event()? {
+            Event::Start(ref e) | Event::Empty(ref e)
+                if e.local_name().as_ref() == b"Relationship" =>
+            {
+                let mut target = String::new();
+                let mut type_attr = String::new();
+                for attr in e.attributes() {
+                    let attr = attr?;
+                    match attr.key.as_ref() {
+                        b"Target" => target = attr.unescape_value()?.to_string(),
+                        b"Type" => type_attr = attr.unescape_value()?.to_string(),
+                        _ => {}
+                    }
+                }
+                if type_attr == rel_type {
+                    targets.push(resolve_target(owner, &target));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(targets)
+}
+
+/// Sheet name -> worksheet part path, in workbook order.
+pub(crate) fn sheet_parts(zip: &mut ZipArchive<File>) -> Result<Vec<(String, String)>> {
+    let Some(rels) = read_string(zip, "xl/_rels/workbook.xml.rels")? else {
+        return Ok(Vec::new());
+    };
+    let mut targets = HashMap::new();
+    let mut reader = Reader::from_str(&rels);
+    loop {
+        match reader.read_event()? {
+            Event::Start(ref e) | Event::Empty(ref e)
+                if e.local_name().as_ref() == b"Relationship" =>
+            {
+                let mut id = String::new();
+                let mut target = String::new();
+                for attr in e.attributes() {
+                    let attr = attr?;
+                    match attr.key.as_ref() {
+                        b"Id" => id = attr.unescape_value()?.to_string(),
+                        b"Target" => target = attr.unescape_value()?.to_string(),
+                        _ => {}
+                    }
+                }
+                targets.insert(id, resolve_target("xl/workbook.xml", &target));
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let Some(workbook) = read_string(zip, "xl/workbook.xml")? else {
+        return Ok(Vec::new());
+    };
+    let mut sheets = Vec::new();
+    let mut reader = Reader::from_str(&workbook);
+    loop {
+        match reader.read_event()? {
+            Event::Start(ref e) | Event::Empty(ref e) if e.local_name().as_ref() == b"sheet" => {
+                let mut name = String::new();
+                let mut rid = String::new();
+                for attr in e.attributes() {
+                    let attr = attr?;
+                    match attr.key.local_name().as_ref() {
+                        b"name" => name = attr.unescape_value()?.to_string(),
+                        b"id" => rid = attr.unescape_value()?.to_string(),
+                        _ => {}
+                    }
+                }
+                if let Some(path) = targets.get(&rid) {
+                    sheets.push((name, path.clone()));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(sheets)
+}
+
+fn add_relationship(rels: Option<&str>, rel_type: &str, target: &str) -> String {
+    let rels = rels.map(str::to_string).unwrap_or_else(|| {
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\"></Relationships>"
+            .to_string()
+    });
+    let existing = Regex::new(&format!(
+        r#"<Relationship\b[^>]*Type="{}"[^>]*/>"#,
+        regex::escape(rel_type)
+    ))
+    .expect("valid relationship regex");
+    let rels = existing.replace_all(&rels, "").to_string();
+
+    let ids = Regex::new(r#"Id="rId(\d+)""#).expect("valid id regex");
+    let next = ids
+        .captures_iter(&rels)
+        .filter_map(|c| c[1].parse::<u32>().ok())
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let relationship =
+        format!("<Relationship Id=\"rId{next}\" Type=\"{rel_type}\" Target=\"{target}\"/>");
+    match rels.rfind("</Relationships>") {
+        Some(pos) => format!("{}{}{}", &rels[..pos], relationship, &rels[pos..]),
+        // A self-closing `<Relationships/>` with nothing in it yet.
+        None => {
+            rels.trim_end().trim_end_matches("/>").to_string()
+                + ">"
+                + &relationship
+                + "</Relationships>"
+        }
+    }
+}
+
+fn add_overrides(content_types: &str, overrides: &[(String, &str)]) -> String {
+    let existing =
+        Regex::new(r#"<Override\b[^>]*PartName="/xl/(threadedComments|persons)/[^"]*"[^>]*/>"#)
+            .expect("valid override regex");
+    let mut out = existing.replace_all(content_types, "").to_string();
+    let additions: String = overrides
+        .iter()
+        .map(|(part, content_type)| {
+            format!("<Override PartName=\"{part}\" ContentType=\"{content_type}\"/>")
+        })
+        .collect();
+    if let Some(pos) = out.rfind("</Types>") {
+        out.insert_str(pos, &additions);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_threads_and_keeps_replies_with_their_root() {
+        let xml = r#"<ThreadedComments xmlns="http://schemas.microsoft.com/office/spreadsheetml/2018/threadedcomments">
+<threadedComment ref="B2" dT="2024-01-01T10:00:00.00" personId="{P1}" id="{A}" done="1"><text>Check total</text></threadedComment>
+<threadedComment ref="C3" personId="{P2}" id="{B}"><text>Source?</text></threadedComment>
+<threadedComment ref="B2" personId="{P2}" id="{C}" parentId="{A}"><text>Fixed &amp; done</text></threadedComment>
+</ThreadedComments>"#;
+        let comments = parse_threaded_comments(&mut Reader::from_reader(xml.as_bytes())).unwrap();
+        let mut threads = ThreadedComments {
+            persons: vec![Person {
+                id: "{P2}".to_string(),
+                display_name: "Bo".to_string(),
+            }],
+            sheets: BTreeMap::new(),
+        };
+        threads
+            .sheets
+            .insert("Sheet1".to_string(), group_threads(comments));
+
+        let thread = threads.thread("Sheet1", "B2").unwrap();
+        assert!(thread.root.done);
+        assert_eq!(thread.replies.len(), 1);
+        assert_eq!(thread.replies[0].text, "Fixed & done");
+        assert!(placeholder_text(&threads, thread).ends_with("Reply:\n    Bo: Fixed & done"));
+
+        assert!(threads.add_reply("Sheet1", "C3", "Ana", "From the ledger"));
+        assert_eq!(threads.thread("Sheet1", "C3").unwrap().replies.len(), 1);
+        assert_eq!(threads.persons.len(), 2);
+
+        let reparsed = parse_threaded_comments(&mut Reader::from_reader(
+            threaded_comments_xml(&threads.sheets["Sheet1"]).as_bytes(),
+        ))
+        .unwrap();
+        assert_eq!(reparsed, threads.sheets["Sheet1"]);
+    }
+
+    #[test]
+    fn relationship_targets_resolve_relative_to_owner() {
+        assert_eq!(
+            resolve_target(
+                "xl/worksheets/sheet1.xml",
+                "../threadedComments/threadedComment1.xml"
+            ),
+            "xl/threadedComments/threadedComment1.xml"
+        );
+        assert_eq!(
+            relative_target(
+                "xl/worksheets/sheet1.xml",
+                "xl/threadedComments/threadedComment1.xml"
+            ),
+            "../threadedComments/threadedComment1.xml"
+        );
+        let rels = add_relationship(
+            Some(r#"<Relationships><Relationship Id="rId2" Type="x" Target="y"/></Relationships>"#),
+            PERSON_REL,
+            "persons/person.xml",
+        );
+        assert!(rels.contains(
+            r#"Id="rId3" Type="http://schemas.microsoft.com/office/2017/10/relationships/person""#
+        ));
+    }
+}
@@ -0,0 +1,274 @@
+//! Argument completion for `completion/complete`.
+//!
+//! Prompt arguments and resource template variables name what they hold
+//! (`workbook_id`, `sheet_name`, `fork_id`, ...). Each name maps to a
+//! [`CompletionSource`] that lists the current candidates from the workspace,
+//! and [`fuzzy_filter`] ranks them against what the client has typed so far.
+//! Arguments the client already filled in narrow the candidates: sheet names
+//! come from the chosen workbook, checkpoints from the chosen fork.
+
+use crate::model::WorkbookId;
+use crate::resources::{self, ArtifactKind};
+use crate::state::AppState;
+use crate::tools::ggen_config::{ReadGgenConfigParams, read_ggen_config};
+use anyhow::{Result, bail};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Where the candidates for an argument come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionSource {
+    /// Workbook ids from `list_workbooks`, followed by fork ids.
+    WorkbookId,
+    /// Sheet names of the workbook in the `workbook_id` argument.
+    SheetName,
+    ForkId,
+    /// Checkpoints of the fork in the `fork_id` argument.
+    CheckpointId,
+    /// Detected region ids of the sheet in the `sheet_name` argument.
+    RegionId,
+    /// Generation rule names in `ggen.toml`.
+    RuleName,
+}
+
+impl CompletionSource {
+    /// Source for an argument or completion hint name, if it has one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "workbook_id" | "workbook_or_fork_id" => Self::WorkbookId,
+            "sheet_name" | "sheet" => Self::SheetName,
+            "fork_id" => Self::ForkId,
+            "checkpoint_id" => Self::CheckpointId,
+            "region_id" => Self::RegionId,
+            "rule_name" | "rule" => Self::RuleName,
+            _ => return None,
+        })
+    }
+}
+
+const WORKBOOK_ARGS: &[&str] = &["workbook_or_fork_id", "workbook_id", "fork_id"];
+
+fn context_value<'a>(context: &'a HashMap<String, String>, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .filter_map(|key| context.get(*key))
+        .map(|value| value.trim())
+        .find(|value| !value.is_empty())
+}
+
+/// Candidates from `source` that match `input`, best matches first.
+pub async fn complete(
+    state: &Arc<AppState>,
+    source: CompletionSource,
+    input: &str,
+    context: &HashMap<String, String>,
+) -> Result<Vec<String>> {
+    let candidates = match source {
+        CompletionSource::WorkbookId => {
+            let listed = state.list_workbooks(Default::default())?;
+            let mut ids: Vec<String> = listed
+                .workbooks
+                .into_iter()
+                .map(|workbook| workbook.workbook_id.0)
+                .collect();
+            ids.extend(fork_ids(state));
+            ids
+        }
+        CompletionSource::SheetName => match context_value(context, WORKBOOK_ARGS) {
+            Some(workbook_id) => state
+                .open_workbook(&WorkbookId(workbook_id.to_string()))
+                .await
+                .map(|workbook| workbook.sheet_names())
+                .unwrap_or_default(),
+            None => Vec::new(),
+        },
+        CompletionSource::ForkId => fork_ids(state),
+        CompletionSource::CheckpointId => {
+            match context_value(context, &["fork_id", "workbook_or_fork_id"]) {
+                Some(fork_id) => checkpoint_ids(state, fork_id),
+                None => Vec::new(),
+            }
+        }
+        CompletionSource::RegionId => region_ids(state, context).await,
+        CompletionSource::RuleName => {
+            let params = ReadGgenConfigParams {
+                config_path: context_value(context, &["config_path"]).map(str::to_string),
+            };
+            read_ggen_config(state.clone(), params)
+                .await
+                .map(|config| config.rule_names)
+                .unwrap_or_default()
+        }
+    };
+    Ok(fuzzy_filter(input, candidates))
+}
+
+/// Complete a variable of one of the templates in
+/// [`resources::resource_templates`].
+pub async fn complete_resource(
+    state: &Arc<AppState>,
+    uri_template: &str,
+    argument: &str,
+    input: &str,
+    context: &HashMap<String, String>,
+) -> Result<Vec<String>> {
+    let known = resources::resource_templates()
+        .iter()
+        .any(|template| template.raw.uri_template == uri_template);
+    if !known {
+        bail!("unknown resource template '{uri_template}'");
+    }
+    if !uri_template.contains(&format!("{{{argument}}}")) {
+        bail!("resource template '{uri_template}' has no variable '{argument}'");
+    }
+
+    let artifact_kind = if uri_template.starts_with("ggen://receipts/") {
+        Some(ArtifactKind::Receipt)
+    } else if uri_template.starts_with("ggen://reports/") {
+        Some(ArtifactKind::Report)
+    } else {
+        None
+    };
+    match (argument, artifact_kind) {
+        ("file", Some(kind)) => {
+            let files = resources::artifact_files(state, kind)
+                .into_iter()
+                .map(|(_, file_name)| file_name)
+                .collect();
+            Ok(fuzzy_filter(input, files))
+        }
+        _ => match CompletionSource::from_name(argument) {
+            Some(source) => complete(state, source, input, context).await,
+            None => Ok(Vec::new()),
+        },
+    }
+}
+
+/// Keep the candidates that match `input` and order them by how well they
+/// match, then by their original order. Matching ignores case and, in
+/// decreasing order of preference, accepts:
+///
+/// 1. a prefix (`sum` → `Summary`),
+/// 2. a prefix once spaces, `_`, `-` and `.` are dropped (`q1s` → `Q1 Summary`),
+/// 3. a prefix of any later word (`sum` → `Q1 Summary`, `rev` → `NetRevenue`),
+/// 4. the typed characters in order, starting with the first
+///    (`qsmry` → `Q1 Summary`).
+///
+/// Duplicates are dropped; empty input keeps every candidate.
+pub fn fuzzy_filter(input: &str, candidates: Vec<String>) -> Vec<String> {
+    let input = input.trim().to_lowercase();
+    let compact_input = compact(&input);
+    let mut seen = HashSet::new();
+    let mut ranked: Vec<(u8, String)> = candidates
+        .into_iter()
+        .filter(|candidate| seen.insert(candidate.clone()))
+        .filter_map(|candidate| {
+            match_rank(&input, &compact_input, &candidate).map(|rank| (rank, candidate))
+        })
+        .collect();
+    ranked.sort_by_key(|(rank, _)| *rank);
+    ranked.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+fn compact(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '_' | '-' | '.'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn match_rank(input: &str, compact_input: &str, candidate: &str) -> Option<u8> {
+    let lower = candidate.to_lowercase();
+    if lower.starts_with(input) {
+        return Some(0);
+    }
+    if compact_input.is_empty() {
+        return None;
+    }
+    let compact_candidate = compact(candidate);
+    if compact_candidate.starts_with(compact_input) {
+        return Some(1);
+    }
+    if word_starts(candidate).any(|start| compact(&candidate[start..]).starts_with(compact_input)) {
+        return Some(2);
+    }
+    let mut remaining = compact_candidate.chars();
+    let is_subsequence = compact_input
+        .chars()
+        .all(|wanted| remaining.any(|c| c == wanted));
+    let same_start = compact_candidate.chars().next() == compact_input.chars().next();
+    (same_start && is_subsequence).then_some(3)
+}
+
+/// Byte offsets where a word other than the first starts: after a separator,
+/// at a lowercase-to-uppercase step or at a letter-digit boundary.
+fn word_starts(candidate: &str) -> impl Iterator<Item = usize> + '_ {
+    let chars: Vec<(usize, char)> = candidate.char_indices().collect();
+    (1..chars.len()).filter_map(move |i| {
+        let (offset, c) = chars[i];
+        let prev = chars[i - 1].1;
+        let starts = c.is_alphanumeric()
+            && (!prev.is_alphanumeric()
+                || (prev.is_lowercase() && c.is_uppercase())
+                || (prev.is_alphabetic() != c.is_alphabetic()));
+        starts.then_some(offset)
+    })
+}
+
+pub(crate) fn fork_ids(state: &AppState) -> Vec<String> {
+    #[cfg(feature = "recalc")]
+    if let Some(registry) = state.fork_registry() {
+        return registry
+            .list_forks()
+            .into_iter()
+            .map(|fork| fork.fork_id)
+            .collect();
+    }
+    let _ = state;
+    Vec::new()
+}
+
+fn checkpoint_ids(state: &AppState, fork_id: &str) -> Vec<String> {
+    #[cfg(feature = "recalc")]
+    if let Some(registry) = state.fork_registry() {
+        return registry
+            .list_checkpoints(fork_id)
+            .map(|checkpoints| {
+                checkpoints
+                    .into_iter()
+                    .map(|checkpoint| checkpoint.checkpoint_id)
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+    let _ = (state, fork_id);
+    Vec::new()
+}
+
+async fn region_ids(state: &AppState, context: &HashMap<String, String>) -> Vec<String> {
+    let (Some(workbook_id), Some(sheet_name)) = (
+        context_value(context, WORKBOOK_ARGS),
+        context_value(context, &["sheet_name", "sheet"]),
+    ) else {
+        return Vec::new();
+    };
+    let Ok(workbook) = state
+        .open_workbook(&WorkbookId(workbook_id.to_string()))
+        .await
+    else {
+        return Vec::new();
+    };
+    let sheet_name = sheet_name.to_string();
+    tokio::task::spawn_blocking(move || workbook.get_sheet_metrics(&sheet_name))
+        .await
+        .ok()
+        .and_then(Result::ok)
+        .map(|entry| {
+            entry
+                .detected_regions()
+                .iter()
+                .map(|region| region.id.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
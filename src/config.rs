@@ -9,7 +9,7 @@ use std::time::Duration;
 
 const DEFAULT_CACHE_CAPACITY: usize = 5;
 const DEFAULT_MAX_RECALCS: usize = 2;
-const DEFAULT_EXTENSIONS: &[&str] = &["xlsx", "xlsm", "xls", "xlsb"];
+const DEFAULT_EXTENSIONS: &[&str] = &["xlsx", "xlsm", "xls", "xlsb", "ods", "csv", "tsv"];
 const DEFAULT_HTTP_BIND: &str = "127.0.0.1:8079";
 const DEFAULT_TOOL_TIMEOUT_MS: u64 = 30_000;
 const DEFAULT_MAX_RESPONSE_BYTES: u64 = 1_000_000;
@@ -47,6 +47,29 @@ impl std::fmt::Display for TransportKind {
     }
 }
 
+/// Which engine `recalculate` uses for forks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecalcBackendKind {
+    /// LibreOffice when soffice is installed, otherwise the native evaluator.
+    #[default]
+    Auto,
+    #[value(alias = "soffice")]
+    #[serde(alias = "soffice")]
+    Libreoffice,
+    Native,
+}
+
+impl std::fmt::Display for RecalcBackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecalcBackendKind::Auto => write!(f, "auto"),
+            RecalcBackendKind::Libreoffice => write!(f, "libreoffice"),
+            RecalcBackendKind::Native => write!(f, "native"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
     pub workspace_root: PathBuf,
@@ -57,6 +80,10 @@ pub struct ServerConfig {
     pub transport: TransportKind,
     pub http_bind_address: SocketAddr,
     pub recalc_enabled: bool,
+    pub recalc_backend: RecalcBackendKind,
+    pub recalc_pooled: bool,
+    /// Durable fork store directory; forks survive restarts when set.
+    pub fork_store_dir: Option<PathBuf>,
     pub vba_enabled: bool,
     pub max_concurrent_recalcs: usize,
     pub tool_timeout_ms: Option<u64>,
@@ -69,6 +96,8 @@ pub struct ServerConfig {
     pub query_cache_ttl_secs: u64,
     pub entitlement_enabled: bool,
     pub entitlement_config: crate::entitlement::EntitlementConfig,
+    /// Authentication for the HTTP transport; config file only.
+    pub http_auth: Option<crate::auth::HttpAuthConfig>,
 }
 
 impl ServerConfig {
@@ -83,6 +112,9 @@ impl ServerConfig {
             transport: cli_transport,
             http_bind: cli_http_bind,
             recalc_enabled: cli_recalc_enabled,
+            recalc_backend: cli_recalc_backend,
+            recalc_pooled: cli_recalc_pooled,
+            fork_store_dir: cli_fork_store_dir,
             vba_enabled: cli_vba_enabled,
             max_concurrent_recalcs: cli_max_concurrent_recalcs,
             tool_timeout_ms: cli_tool_timeout_ms,
@@ -113,6 +145,9 @@ impl ServerConfig {
             transport: file_transport,
             http_bind: file_http_bind,
             recalc_enabled: file_recalc_enabled,
+            recalc_backend: file_recalc_backend,
+            recalc_pooled: file_recalc_pooled,
+            fork_store_dir: file_fork_store_dir,
             vba_enabled: file_vba_enabled,
             max_concurrent_recalcs: file_max_concurrent_recalcs,
             tool_timeout_ms: file_tool_timeout_ms,
@@ -126,6 +161,7 @@ impl ServerConfig {
             entitlement_enabled: file_entitlement_enabled,
             entitlement_provider: file_entitlement_provider,
             entitlement_license_path: file_entitlement_license_path,
+            http_auth,
         } = file_config;
 
         let single_workbook = cli_single_workbook.or(file_single_workbook);
@@ -224,6 +260,11 @@ impl ServerConfig {
         });
 
         let recalc_enabled = cli_recalc_enabled || file_recalc_enabled.unwrap_or(false);
+        let recalc_backend = cli_recalc_backend
+            .or(file_recalc_backend)
+            .unwrap_or_default();
+        let recalc_pooled = cli_recalc_pooled || file_recalc_pooled.unwrap_or(false);
+        let fork_store_dir = cli_fork_store_dir.or(file_fork_store_dir);
         let vba_enabled = cli_vba_enabled || file_vba_enabled.unwrap_or(false);
 
         let max_concurrent_recalcs = cli_max_concurrent_recalcs
@@ -297,6 +338,9 @@ impl ServerConfig {
             transport,
             http_bind_address,
             recalc_enabled,
+            recalc_backend,
+            recalc_pooled,
+            fork_store_dir,
             vba_enabled,
             max_concurrent_recalcs,
             tool_timeout_ms,
@@ -309,6 +353,7 @@ impl ServerConfig {
             query_cache_ttl_secs,
             entitlement_enabled,
             entitlement_config,
+            http_auth,
         })
     }
 
@@ -458,6 +503,20 @@ impl ServerConfig {
             );
         }
 
+        // 10. Validate HTTP auth, and warn when HTTP is reachable without it
+        if let Some(http_auth) = &self.http_auth {
+            crate::auth::HttpAuth::from_config(http_auth)
+                .context("invalid http_auth configuration")?;
+        } else if self.transport == TransportKind::Http
+            && !self.http_bind_address.ip().is_loopback()
+        {
+            tracing::warn!(
+                bind = %self.http_bind_address,
+                "HTTP transport is bound to a non-loopback address without http_auth; \
+                 any client that can reach it has full access"
+            );
+        }
+
         Ok(())
     }
 
@@ -606,6 +665,30 @@ pub struct CliArgs {
     )]
     pub recalc_enabled: bool,
 
+    #[arg(
+        long,
+        env = "SPREADSHEET_MCP_RECALC_BACKEND",
+        value_enum,
+        value_name = "BACKEND",
+        help = "Recalc engine: auto, libreoffice or native (default: auto)"
+    )]
+    pub recalc_backend: Option<RecalcBackendKind>,
+
+    #[arg(
+        long,
+        env = "SPREADSHEET_MCP_RECALC_POOLED",
+        help = "Keep one warm LibreOffice process per concurrent recalc instead of spawning per job"
+    )]
+    pub recalc_pooled: bool,
+
+    #[arg(
+        long,
+        env = "SPREADSHEET_MCP_FORK_STORE_DIR",
+        value_name = "DIR",
+        help = "Keep forks in this directory with on-disk manifests so they survive restarts"
+    )]
+    pub fork_store_dir: Option<PathBuf>,
+
     #[arg(
         long,
         env = "SPREADSHEET_MCP_VBA_ENABLED",
@@ -724,6 +807,9 @@ struct PartialConfig {
     transport: Option<TransportKind>,
     http_bind: Option<SocketAddr>,
     recalc_enabled: Option<bool>,
+    recalc_backend: Option<RecalcBackendKind>,
+    recalc_pooled: Option<bool>,
+    fork_store_dir: Option<PathBuf>,
     vba_enabled: Option<bool>,
     max_concurrent_recalcs: Option<usize>,
     tool_timeout_ms: Option<u64>,
@@ -737,6 +823,7 @@ struct PartialConfig {
     entitlement_enabled: Option<bool>,
     entitlement_provider: Option<String>,
     entitlement_license_path: Option<String>,
+    http_auth: Option<crate::auth::HttpAuthConfig>,
 }
 
 fn load_config_file(path: &Path) -> Result<PartialConfig> {
@@ -0,0 +1,709 @@
+//! Data-validation rules: reading them off a worksheet and checking existing
+//! cell values against them.
+//!
+//! Checks cover what can be decided without a formula engine: literal bounds,
+//! bounds held in a single cell, `DATE()`/`TIME()` literals, and list sources
+//! that are inline or point at a range or defined name. Custom formulas and
+//! computed sources (OFFSET, INDIRECT, ...) are reported as unchecked instead.
+
+use crate::model::{DataValidationEntry, ListSource, ValidationViolation};
+use crate::utils::column_number_to_name;
+use anyhow::{Result, bail};
+use chrono::NaiveDate;
+use umya_spreadsheet::helper::coordinate::index_from_coordinate;
+use umya_spreadsheet::structs::{
+    DataValidation, DataValidationErrorStyleValues, DataValidationOperatorValues,
+    DataValidationValues,
+};
+use umya_spreadsheet::{Spreadsheet, Worksheet};
+
+/// Entries of a range-backed list reported by the read tool.
+pub const MAX_LIST_PREVIEW: usize = 50;
+/// Excel caps an inline list at 255 characters.
+pub const MAX_INLINE_LIST_LEN: usize = 255;
+
+const MAX_ROW: u32 = 1_048_576;
+const MAX_COL: u32 = 16_384;
+
+/// One rectangle of an `sqref`, 1-based and inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Area {
+    pub min_col: u32,
+    pub min_row: u32,
+    pub max_col: u32,
+    pub max_row: u32,
+}
+
+impl Area {
+    /// Parse `A1`, `A1:B9`, `A:C` or `2:5`; `$` markers are ignored.
+    pub fn parse(text: &str) -> Option<Area> {
+        let text = text.trim().replace('$', "").to_ascii_uppercase();
+        let (start, end) = text.split_once(':').unwrap_or((&text, &text));
+        let (c1, r1, _, _) = index_from_coordinate(start);
+        let (c2, r2, _, _) = index_from_coordinate(end);
+        let (min_col, max_col) = match (c1, c2) {
+            (Some(a), Some(b)) => (a.min(b), a.max(b)),
+            (None, None) => (1, MAX_COL),
+            _ => return None,
+        };
+        let (min_row, max_row) = match (r1, r2) {
+            (Some(a), Some(b)) => (a.min(b), a.max(b)),
+            (None, None) => (1, MAX_ROW),
+            _ => return None,
+        };
+        if min_col == 1 && max_col == MAX_COL && min_row == 1 && max_row == MAX_ROW {
+            return None;
+        }
+        Some(Area {
+            min_col,
+            min_row,
+            max_col,
+            max_row,
+        })
+    }
+
+    pub fn contains(&self, col: u32, row: u32) -> bool {
+        (self.min_col..=self.max_col).contains(&col) && (self.min_row..=self.max_row).contains(&row)
+    }
+
+    pub fn intersects(&self, other: &Area) -> bool {
+        self.min_col <= other.max_col
+            && other.min_col <= self.max_col
+            && self.min_row <= other.max_row
+            && other.min_row <= self.max_row
+    }
+
+    pub fn is_single_cell(&self) -> bool {
+        self.min_col == self.max_col && self.min_row == self.max_row
+    }
+
+    /// Every row of its columns, as `A:C` parses.
+    pub fn is_whole_columns(&self) -> bool {
+        self.min_row == 1 && self.max_row == MAX_ROW
+    }
+
+    /// Every column of its rows, as `2:5` parses.
+    pub fn is_whole_rows(&self) -> bool {
+        self.min_col == 1 && self.max_col == MAX_COL
+    }
+
+    /// The parts of `self` outside `cut`: bands above and below, then the
+    /// pieces left and right of it.
+    pub fn subtract(&self, cut: &Area) -> Vec<Area> {
+        if !self.intersects(cut) {
+            return vec![*self];
+        }
+        let mut parts = Vec::new();
+        if self.min_row < cut.min_row {
+            parts.push(Area {
+                max_row: cut.min_row - 1,
+                ..*self
+            });
+        }
+        if self.max_row > cut.max_row {
+            parts.push(Area {
+                min_row: cut.max_row + 1,
+                ..*self
+            });
+        }
+        let band = Area {
+            min_row: self.min_row.max(cut.min_row),
+            max_row: self.max_row.min(cut.max_row),
+            ..*self
+        };
+        if band.min_col < cut.min_col {
+            parts.push(Area {
+                max_col: cut.min_col - 1,
+                ..band
+            });
+        }
+        if band.max_col > cut.max_col {
+            parts.push(Area {
+                min_col: cut.max_col + 1,
+                ..band
+            });
+        }
+        parts
+    }
+
+    pub fn to_a1(&self) -> String {
+        let full_cols = self.is_whole_columns();
+        let full_rows = self.is_whole_rows();
+        if full_cols {
+            return format!(
+                "{}:{}",
+                column_number_to_name(self.min_col),
+                column_number_to_name(self.max_col)
+            );
+        }
+        if full_rows {
+            return format!("{}:{}", self.min_row, self.max_row);
+        }
+        let start = format!("{}{}", column_number_to_name(self.min_col), self.min_row);
+        if self.is_single_cell() {
+            return start;
+        }
+        format!(
+            "{}:{}{}",
+            start,
+            column_number_to_name(self.max_col),
+            self.max_row
+        )
+    }
+}
+
+/// Areas of a space-separated `sqref`; unparseable parts are skipped.
+pub fn parse_sqref(sqref: &str) -> Vec<Area> {
+    sqref.split_whitespace().filter_map(Area::parse).collect()
+}
+
+pub fn format_sqref(areas: &[Area]) -> String {
+    areas.iter().map(Area::to_a1).collect::<Vec<_>>().join(" ")
+}
+
+/// Remove `cut` from every area of `sqref`; `None` when nothing is left.
+pub fn subtract_sqref(sqref: &str, cut: &[Area]) -> Option<String> {
+    let mut areas = parse_sqref(sqref);
+    for hole in cut {
+        areas = areas.iter().flat_map(|area| area.subtract(hole)).collect();
+    }
+    (!areas.is_empty()).then(|| format_sqref(&areas))
+}
+
+pub fn type_name(value: &DataValidationValues) -> &'static str {
+    match value {
+        DataValidationValues::Custom => "custom",
+        DataValidationValues::Date => "date",
+        DataValidationValues::Decimal => "decimal",
+        DataValidationValues::List => "list",
+        DataValidationValues::None => "any",
+        DataValidationValues::TextLength => "text_length",
+        DataValidationValues::Time => "time",
+        DataValidationValues::Whole => "whole",
+    }
+}
+
+pub fn parse_type(name: &str) -> Result<DataValidationValues> {
+    Ok(match name.to_ascii_lowercase().replace('_', "").as_str() {
+        "custom" => DataValidationValues::Custom,
+        "date" => DataValidationValues::Date,
+        "decimal" => DataValidationValues::Decimal,
+        "list" => DataValidationValues::List,
+        "any" | "none" => DataValidationValues::None,
+        "textlength" => DataValidationValues::TextLength,
+        "time" => DataValidationValues::Time,
+        "whole" => DataValidationValues::Whole,
+        _ => bail!(
+            "unknown validation_type '{}' (expected list, whole, decimal, date, time, text_length, custom or any)",
+            name
+        ),
+    })
+}
+
+pub fn operator_name(value: &DataValidationOperatorValues) -> &'static str {
+    match value {
+        DataValidationOperatorValues::Between => "between",
+        DataValidationOperatorValues::Equal => "equal",
+        DataValidationOperatorValues::GreaterThan => "greater_than",
+        DataValidationOperatorValues::GreaterThanOrEqual => "greater_than_or_equal",
+        DataValidationOperatorValues::LessThan => "less_than",
+        DataValidationOperatorValues::LessThanOrEqual => "less_than_or_equal",
+        DataValidationOperatorValues::NotBetween => "not_between",
+        DataValidationOperatorValues::NotEqual => "not_equal",
+    }
+}
+
+pub fn parse_operator(name: &str) -> Result<DataValidationOperatorValues> {
+    Ok(match name.to_ascii_lowercase().replace('_', "").as_str() {
+        "between" => DataValidationOperatorValues::Between,
+        "equal" => DataValidationOperatorValues::Equal,
+        "greaterthan" => DataValidationOperatorValues::GreaterThan,
+        "greaterthanorequal" => DataValidationOperatorValues::GreaterThanOrEqual,
+        "lessthan" => DataValidationOperatorValues::LessThan,
+        "lessthanorequal" => DataValidationOperatorValues::LessThanOrEqual,
+        "notbetween" => DataValidationOperatorValues::NotBetween,
+        "notequal" => DataValidationOperatorValues::NotEqual,
+        _ => bail!("unknown validation operator '{}'", name),
+    })
+}
+
+pub fn error_style_name(value: &DataValidationErrorStyleValues) -> &'static str {
+    match value {
+        DataValidationErrorStyleValues::Information => "information",
+        DataValidationErrorStyleValues::Stop => "stop",
+        DataValidationErrorStyleValues::Warning => "warning",
+    }
+}
+
+pub fn parse_error_style(name: &str) -> Result<DataValidationErrorStyleValues> {
+    Ok(match name.to_ascii_lowercase().as_str() {
+        "information" | "info" => DataValidationErrorStyleValues::Information,
+        "stop" => DataValidationErrorStyleValues::Stop,
+        "warning" => DataValidationErrorStyleValues::Warning,
+        _ => bail!(
+            "unknown error_style '{}' (expected stop, warning or information)",
+            name
+        ),
+    })
+}
+
+/// Whether the rule's operator and second formula mean anything.
+pub fn uses_operator(value: &DataValidationValues) -> bool {
+    matches!(
+        value,
+        DataValidationValues::Whole
+            | DataValidationValues::Decimal
+            | DataValidationValues::Date
+            | DataValidationValues::Time
+            | DataValidationValues::TextLength
+    )
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Every rule on `sheet`, in file order.
+pub fn sheet_rules(book: &Spreadsheet, sheet: &Worksheet) -> Vec<DataValidationEntry> {
+    let Some(validations) = sheet.get_data_validations() else {
+        return Vec::new();
+    };
+    validations
+        .get_data_validation_list()
+        .iter()
+        .map(|dv| {
+            let kind = dv.get_type();
+            let formula1 = non_empty(dv.get_formula1());
+            let list_source = match (kind, &formula1) {
+                (DataValidationValues::List, Some(formula)) => {
+                    Some(match list_entries(book, sheet, formula) {
+                        ListEntries::Inline(values) => ListSource::Inline { values },
+                        ListEntries::Cells(values) => ListSource::Range {
+                            reference: formula.clone(),
+                            resolved: true,
+                            values_truncated: values.len() > MAX_LIST_PREVIEW,
+                            values: values.into_iter().take(MAX_LIST_PREVIEW).collect(),
+                        },
+                        ListEntries::Unresolved => ListSource::Range {
+                            reference: formula.clone(),
+                            resolved: false,
+                            values: Vec::new(),
+                            values_truncated: false,
+                        },
+                    })
+                }
+                _ => None,
+            };
+            let with_operator = uses_operator(kind);
+            DataValidationEntry {
+                sheet_name: sheet.get_name().to_string(),
+                range: dv.get_sequence_of_references().get_sqref(),
+                validation_type: type_name(kind).to_string(),
+                operator: with_operator.then(|| operator_name(dv.get_operator()).to_string()),
+                formula1,
+                formula2: if with_operator {
+                    non_empty(dv.get_formula2())
+                } else {
+                    None
+                },
+                list_source,
+                allow_blank: *dv.get_allow_blank(),
+                show_input_message: *dv.get_show_input_message(),
+                prompt_title: non_empty(dv.get_prompt_title()),
+                prompt: non_empty(dv.get_prompt()),
+                show_error_message: *dv.get_show_error_message(),
+                error_style: error_style_name(dv.get_error_style()).to_string(),
+                error_title: non_empty(dv.get_error_title()),
+                error_message: non_empty(dv.get_error_message()),
+            }
+        })
+        .collect()
+}
+
+enum ListEntries {
+    Inline(Vec<String>),
+    Cells(Vec<String>),
+    Unresolved,
+}
+
+fn list_entries(book: &Spreadsheet, sheet: &Worksheet, formula: &str) -> ListEntries {
+    let formula = formula.trim().trim_start_matches('=');
+    if let Some(inner) = formula
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        return ListEntries::Inline(inner.split(',').map(|v| v.trim().to_string()).collect());
+    }
+    match resolve_reference(book, sheet, formula) {
+        Some((source, area)) => ListEntries::Cells(
+            cells_in(source, &[area])
+                .into_iter()
+                .map(|(_, _, value)| value)
+                .collect(),
+        ),
+        None => ListEntries::Unresolved,
+    }
+}
+
+/// Resolve `A1:A9`, `Sheet!$A$1:$A$9`, `'My Sheet'!A1` or a workbook-level
+/// defined name to a sheet and area.
+pub(crate) fn resolve_reference<'a>(
+    book: &'a Spreadsheet,
+    host: &'a Worksheet,
+    reference: &str,
+) -> Option<(&'a Worksheet, Area)> {
+    resolve_reference_inner(book, host, reference, true)
+}
+
+fn resolve_reference_inner<'a>(
+    book: &'a Spreadsheet,
+    host: &'a Worksheet,
+    reference: &str,
+    follow_names: bool,
+) -> Option<(&'a Worksheet, Area)> {
+    let reference = reference.trim().trim_start_matches('=');
+    if let Some((sheet_part, area_part)) = reference.rsplit_once('!') {
+        let sheet_name = sheet_part
+            .strip_prefix('\'')
+            .and_then(|s| s.strip_suffix('\''))
+            .map(|s| s.replace("''", "'"))
+            .unwrap_or_else(|| sheet_part.to_string());
+        let sheet = book.get_sheet_by_name(&sheet_name)?;
+        return Area::parse(area_part).map(|area| (sheet, area));
+    }
+    if let Some(area) = Area::parse(reference) {
+        return Some((host, area));
+    }
+    if !follow_names {
+        return None;
+    }
+    let defined = book
+        .get_defined_names()
+        .iter()
+        .find(|d| d.get_name().eq_ignore_ascii_case(reference))?;
+    resolve_reference_inner(book, host, &defined.get_address(), false)
+}
+
+/// Non-empty cells inside `areas`, row-major, as `(row, col, value)`.
+fn cells_in(sheet: &Worksheet, areas: &[Area]) -> Vec<(u32, u32, String)> {
+    let mut cells: Vec<(u32, u32, String)> = sheet
+        .get_cell_collection()
+        .into_iter()
+        .filter_map(|cell| {
+            let coord = cell.get_coordinate();
+            let (col, row) = (*coord.get_col_num(), *coord.get_row_num());
+            let value = cell.get_value();
+            (!value.is_empty() && areas.iter().any(|a| a.contains(col, row)))
+                .then(|| (row, col, value.to_string()))
+        })
+        .collect();
+    cells.sort_by_key(|(row, col, _)| (*row, *col));
+    cells
+}
+
+/// A rule reduced to something a cell value can be tested against.
+enum Check {
+    List(Vec<String>),
+    Number {
+        kind: DataValidationValues,
+        operator: DataValidationOperatorValues,
+        low: f64,
+        high: f64,
+    },
+    TextLength {
+        operator: DataValidationOperatorValues,
+        low: f64,
+        high: f64,
+    },
+}
+
+impl Check {
+    /// Why `value` is rejected, or `None` when it passes.
+    fn reject(&self, value: &str) -> Option<String> {
+        match self {
+            Check::List(entries) => {
+                let accepted = entries.iter().any(|entry| {
+                    entry.eq_ignore_ascii_case(value)
+                        || matches!(
+                            (entry.parse::<f64>(), value.parse::<f64>()),
+                            (Ok(a), Ok(b)) if a == b
+                        )
+                });
+                (!accepted).then(|| "value is not in the list".to_string())
+            }
+            Check::Number {
+                kind,
+                operator,
+                low,
+                high,
+            } => {
+                let Ok(number) = value.trim().parse::<f64>() else {
+                    return Some(format!("value is not a {}", number_label(kind)));
+                };
+                if matches!(kind, DataValidationValues::Whole) && number.fract() != 0.0 {
+                    return Some("value is not a whole number".to_string());
+                }
+                (!compare(operator, number, *low, *high)).then(|| {
+                    format!(
+                        "value must be {}",
+                        describe(
+                            operator,
+                            &bound_label(kind, *low),
+                            &bound_label(kind, *high)
+                        )
+                    )
+                })
+            }
+            Check::TextLength {
+                operator,
+                low,
+                high,
+            } => {
+                let length = value.chars().count() as f64;
+                (!compare(operator, length, *low, *high)).then(|| {
+                    format!(
+                        "length {} must be {}",
+                        format_number(length),
+                        describe(operator, &format_number(*low), &format_number(*high))
+                    )
+                })
+            }
+        }
+    }
+}
+
+fn number_label(kind: &DataValidationValues) -> &'static str {
+    match kind {
+        DataValidationValues::Whole => "whole number",
+        DataValidationValues::Date => "date",
+        DataValidationValues::Time => "time",
+        _ => "number",
+    }
+}
+
+fn bound_label(kind: &DataValidationValues, value: f64) -> String {
+    match kind {
+        DataValidationValues::Date => crate::workbook::excel_serial_to_iso(value, false),
+        _ => format_number(value),
+    }
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        value.to_string()
+    }
+}
+
+fn compare(operator: &DataValidationOperatorValues, value: f64, low: f64, high: f64) -> bool {
+    match operator {
+        DataValidationOperatorValues::Between => value >= low && value <= high,
+        DataValidationOperatorValues::NotBetween => value < low || value > high,
+        DataValidationOperatorValues::Equal => value == low,
+        DataValidationOperatorValues::NotEqual => value != low,
+        DataValidationOperatorValues::GreaterThan => value > low,
+        DataValidationOperatorValues::GreaterThanOrEqual => value >= low,
+        DataValidationOperatorValues::LessThan => value < low,
+        DataValidationOperatorValues::LessThanOrEqual => value <= low,
+    }
+}
+
+fn describe(operator: &DataValidationOperatorValues, low: &str, high: &str) -> String {
+    match operator {
+        DataValidationOperatorValues::Between => format!("between {} and {}", low, high),
+        DataValidationOperatorValues::NotBetween => format!("outside {} to {}", low, high),
+        DataValidationOperatorValues::Equal => format!("equal to {}", low),
+        DataValidationOperatorValues::NotEqual => format!("not equal to {}", low),
+        DataValidationOperatorValues::GreaterThan => format!("greater than {}", low),
+        DataValidationOperatorValues::GreaterThanOrEqual => format!("at least {}", low),
+        DataValidationOperatorValues::LessThan => format!("less than {}", low),
+        DataValidationOperatorValues::LessThanOrEqual => format!("at most {}", low),
+    }
+}
+
+/// Compile `dv` for checking. `Ok(None)` means the rule accepts anything;
+/// `Err` carries the reason it cannot be evaluated here.
+fn compile(
+    book: &Spreadsheet,
+    sheet: &Worksheet,
+    dv: &DataValidation,
+) -> std::result::Result<Option<Check>, String> {
+    let kind = dv.get_type();
+    match kind {
+        DataValidationValues::None => Ok(None),
+        DataValidationValues::Custom => Err("custom formula".to_string()),
+        DataValidationValues::List => match list_entries(book, sheet, dv.get_formula1()) {
+            ListEntries::Inline(values) | ListEntries::Cells(values) => {
+                Ok(Some(Check::List(values)))
+            }
+            ListEntries::Unresolved => {
+                Err(format!("list source `{}` is computed", dv.get_formula1()))
+            }
+        },
+        _ => {
+            let operator = dv.get_operator().clone();
+            let low = bound(book, sheet, dv.get_formula1())?;
+            let high = if matches!(
+                operator,
+                DataValidationOperatorValues::Between | DataValidationOperatorValues::NotBetween
+            ) {
+                bound(book, sheet, dv.get_formula2())?
+            } else {
+                low
+            };
+            Ok(Some(match kind {
+                DataValidationValues::TextLength => Check::TextLength {
+                    operator,
+                    low,
+                    high,
+                },
+                _ => Check::Number {
+                    kind: kind.clone(),
+                    operator,
+                    low,
+                    high,
+                },
+            }))
+        }
+    }
+}
+
+fn bound(book: &Spreadsheet, sheet: &Worksheet, formula: &str) -> std::result::Result<f64, String> {
+    let formula = formula.trim().trim_start_matches('=');
+    if formula.is_empty() {
+        return Err("missing bound".to_string());
+    }
+    if let Ok(number) = formula.parse::<f64>() {
+        return Ok(number);
+    }
+    if let Some(serial) = date_time_literal(formula) {
+        return Ok(serial);
+    }
+    if let Some((source, area)) = resolve_reference(book, sheet, formula)
+        && area.is_single_cell()
+        && let Some(cell) = source.get_cell((area.min_col, area.min_row))
+        && let Ok(number) = cell.get_value().trim().parse::<f64>()
+    {
+        return Ok(number);
+    }
+    Err(format!("bound `{}` is computed", formula))
+}
+
+/// Serial value of a `DATE(y,m,d)` or `TIME(h,m,s)` literal.
+fn date_time_literal(formula: &str) -> Option<f64> {
+    let upper = formula.to_ascii_uppercase();
+    let (func, args) = upper.split_once('(')?;
+    let args: Vec<i64> = args
+        .strip_suffix(')')?
+        .split(',')
+        .map(|a| a.trim().parse::<i64>())
+        .collect::<std::result::Result<_, _>>()
+        .ok()?;
+    match (func.trim(), args.as_slice()) {
+        ("DATE", [year, month, day]) => {
+            let date = NaiveDate::from_ymd_opt(*year as i32, *month as u32, *day as u32)?;
+            let epoch = NaiveDate::from_ymd_opt(1899, 12, 30)?;
+            Some((date - epoch).num_days() as f64)
+        }
+        ("TIME", [hour, minute, second]) => {
+            Some((hour * 3600 + minute * 60 + second) as f64 / 86_400.0)
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ViolationScan {
+    pub violations: Vec<ValidationViolation>,
+    pub total: u32,
+    pub rules_checked: u32,
+    pub unchecked_rules: Vec<String>,
+}
+
+/// Check the non-empty cells covered by each rule `select` accepts, keeping
+/// at most `limit` violations but counting all of them.
+pub fn scan_violations(
+    book: &Spreadsheet,
+    sheet: &Worksheet,
+    limit: usize,
+    select: impl Fn(&DataValidation) -> bool,
+) -> ViolationScan {
+    let mut scan = ViolationScan::default();
+    let Some(validations) = sheet.get_data_validations() else {
+        return scan;
+    };
+    for dv in validations.get_data_validation_list() {
+        if !select(dv) {
+            continue;
+        }
+        let sqref = dv.get_sequence_of_references().get_sqref();
+        let check = match compile(book, sheet, dv) {
+            Ok(Some(check)) => check,
+            Ok(None) => continue,
+            Err(why) => {
+                scan.unchecked_rules
+                    .push(format!("{}!{}: {}", sheet.get_name(), sqref, why));
+                continue;
+            }
+        };
+        scan.rules_checked += 1;
+        for (row, col, value) in cells_in(sheet, &parse_sqref(&sqref)) {
+            let Some(reason) = check.reject(&value) else {
+                continue;
+            };
+            scan.total += 1;
+            if scan.violations.len() < limit {
+                scan.violations.push(ValidationViolation {
+                    sheet_name: sheet.get_name().to_string(),
+                    address: format!("{}{}", column_number_to_name(col), row),
+                    value,
+                    rule_range: sqref.clone(),
+                    validation_type: type_name(dv.get_type()).to_string(),
+                    reason,
+                });
+            }
+        }
+    }
+    scan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subtracting_a_hole_keeps_the_frame() {
+        let remaining = subtract_sqref("A1:C3", &[Area::parse("B2").unwrap()]).unwrap();
+        assert_eq!(remaining, "A1:C1 A3:C3 A2 C2");
+        assert_eq!(
+            subtract_sqref("B2:B4", &[Area::parse("A1:C9").unwrap()]),
+            None
+        );
+        assert_eq!(
+            subtract_sqref("A:A", &[Area::parse("A1").unwrap()]).as_deref(),
+            Some("A2:A1048576")
+        );
+    }
+
+    #[test]
+    fn numeric_checks_follow_the_operator() {
+        let check = Check::Number {
+            kind: DataValidationValues::Whole,
+            operator: DataValidationOperatorValues::Between,
+            low: 1.0,
+            high: 10.0,
+        };
+        assert!(check.reject("5").is_none());
+        assert_eq!(
+            check.reject("11").as_deref(),
+            Some("value must be between 1 and 10")
+        );
+        assert_eq!(
+            check.reject("2.5").as_deref(),
+            Some("value is not a whole number")
+        );
+        assert_eq!(date_time_literal("DATE(2024,1,1)"), Some(45292.0));
+        assert_eq!(date_time_literal("time(12, 0, 0)"), Some(0.5));
+    }
+}
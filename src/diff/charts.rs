@@ -0,0 +1,129 @@
+use crate::charts::{ChartDef, SeriesDef};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// What a changeset reports about one chart: enough to tell what moved,
+/// what it plots and what it is called, without the chart's styling.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct ChartInfo {
+    pub chart_type: String,
+    pub title: Option<String>,
+    /// Anchor cells, e.g. `D2:K16`.
+    pub anchor: Option<String>,
+    pub axis_titles: Vec<String>,
+    pub series: Vec<ChartSeriesRef>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct ChartSeriesRef {
+    /// Name formula, or the literal name.
+    pub name: Option<String>,
+    pub categories: Option<String>,
+    pub values: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChartDiff {
+    ChartAdded {
+        sheet: String,
+        chart: String,
+        info: ChartInfo,
+    },
+    ChartDeleted {
+        sheet: String,
+        chart: String,
+        info: ChartInfo,
+    },
+    ChartModified {
+        sheet: String,
+        chart: String,
+        old_info: ChartInfo,
+        new_info: ChartInfo,
+    },
+}
+
+impl ChartDiff {
+    pub fn sheet(&self) -> &str {
+        match self {
+            ChartDiff::ChartAdded { sheet, .. }
+            | ChartDiff::ChartDeleted { sheet, .. }
+            | ChartDiff::ChartModified { sheet, .. } => sheet,
+        }
+    }
+}
+
+impl From<&ChartDef> for ChartInfo {
+    fn from(chart: &ChartDef) -> Self {
+        let anchor = match (&chart.placement.from, &chart.placement.to) {
+            (Some(from), Some(to)) => Some(format!("{from}:{to}")),
+            (Some(from), None) => Some(from.clone()),
+            _ => None,
+        };
+        ChartInfo {
+            chart_type: chart.chart_type.clone(),
+            title: chart.title.clone(),
+            anchor,
+            axis_titles: chart.axes.iter().filter_map(|a| a.title.clone()).collect(),
+            series: chart.series.iter().map(series_ref).collect(),
+        }
+    }
+}
+
+fn series_ref(series: &SeriesDef) -> ChartSeriesRef {
+    ChartSeriesRef {
+        name: series
+            .name
+            .formula
+            .clone()
+            .or_else(|| series.name_literal.clone()),
+        categories: series.categories.formula.clone(),
+        values: series.values.formula.clone(),
+    }
+}
+
+/// Charts keyed by drawing object name, falling back to `#<index>` for
+/// frames without one.
+fn keyed(charts: &[ChartDef]) -> BTreeMap<String, ChartInfo> {
+    charts
+        .iter()
+        .enumerate()
+        .map(|(idx, chart)| {
+            let key = chart.name.clone().unwrap_or_else(|| format!("#{idx}"));
+            (key, ChartInfo::from(chart))
+        })
+        .collect()
+}
+
+pub fn diff_charts(sheet: &str, base: &[ChartDef], fork: &[ChartDef]) -> Vec<ChartDiff> {
+    let base = keyed(base);
+    let fork = keyed(fork);
+    let mut diffs = Vec::new();
+    for (chart, new_info) in &fork {
+        match base.get(chart) {
+            None => diffs.push(ChartDiff::ChartAdded {
+                sheet: sheet.to_string(),
+                chart: chart.clone(),
+                info: new_info.clone(),
+            }),
+            Some(old_info) if old_info != new_info => diffs.push(ChartDiff::ChartModified {
+                sheet: sheet.to_string(),
+                chart: chart.clone(),
+                old_info: old_info.clone(),
+                new_info: new_info.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (chart, info) in &base {
+        if !fork.contains_key(chart) {
+            diffs.push(ChartDiff::ChartDeleted {
+                sheet: sheet.to_string(),
+                chart: chart.clone(),
+                info: info.clone(),
+            });
+        }
+    }
+    diffs
+}
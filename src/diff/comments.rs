@@ -0,0 +1,230 @@
+use crate::comments::ThreadedComments;
+use anyhow::Result;
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::BufRead;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommentInfo {
+    pub author: Option<String>,
+    pub text: String,
+    /// Threaded comments only; legacy notes cannot be resolved.
+    pub resolved: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CommentDiff {
+    CommentAdded {
+        sheet: String,
+        address: String,
+        author: Option<String>,
+        text: String,
+    },
+    CommentDeleted {
+        sheet: String,
+        address: String,
+        author: Option<String>,
+        old_text: String,
+    },
+    CommentModified {
+        sheet: String,
+        address: String,
+        old_author: Option<String>,
+        new_author: Option<String>,
+        old_text: String,
+        new_text: String,
+    },
+    CommentResolved {
+        sheet: String,
+        address: String,
+        resolved: bool,
+    },
+}
+
+impl CommentDiff {
+    pub fn sheet(&self) -> &str {
+        match self {
+            CommentDiff::CommentAdded { sheet, .. }
+            | CommentDiff::CommentDeleted { sheet, .. }
+            | CommentDiff::CommentModified { sheet, .. }
+            | CommentDiff::CommentResolved { sheet, .. } => sheet,
+        }
+    }
+}
+
+/// Parse a legacy `xl/commentsN.xml` part into address -> comment.
+pub fn parse_comments_xml<R: BufRead>(
+    reader: &mut Reader<R>,
+) -> Result<BTreeMap<String, CommentInfo>> {
+    let mut authors: Vec<String> = Vec::new();
+    let mut comments = BTreeMap::new();
+    let mut buf = Vec::new();
+
+    let mut in_author = false;
+    let mut current: Option<(String, Option<usize>, String)> = None;
+    let mut in_text_run = false;
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(ref e) => match e.local_name().as_ref() {
+                b"author" => {
+                    in_author = true;
+                    authors.push(String::new());
+                }
+                b"comment" => {
+                    let mut address = String::new();
+                    let mut author_id = None;
+                    for attr in e.attributes() {
+                        let attr = attr?;
+                        match attr.key.local_name().as_ref() {
+                            b"ref" => address = String::from_utf8_lossy(&attr.value).to_string(),
+                            b"authorId" => {
+                                author_id = String::from_utf8_lossy(&attr.value).parse().ok()
+                            }
+                            _ => {}
+                        }
+                    }
+                    current = Some((address, author_id, String::new()));
+                }
+                b"t" if current.is_some() => in_text_run = true,
+                _ => {}
+            },
+            Event::Text(ref t) => {
+                if in_author {
+                    if let Some(author) = authors.last_mut() {
+                        author.push_str(&t.unescape()?);
+                    }
+                } else if in_text_run && let Some((_, _, text)) = current.as_mut() {
+                    text.push_str(&t.unescape()?);
+                }
+            }
+            Event::End(ref e) => match e.local_name().as_ref() {
+                b"author" => in_author = false,
+                b"t" => in_text_run = false,
+                b"comment" => {
+                    if let Some((address, author_id, text)) = current.take() {
+                        let author = author_id.and_then(|id| authors.get(id).cloned());
+                        comments.insert(
+                            address,
+                            CommentInfo {
+                                author,
+                                text,
+                                resolved: false,
+                            },
+                        );
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(comments)
+}
+
+/// Replace the legacy placeholder notes of threaded comments with the threads
+/// themselves: the opening text followed by one `author: reply` line per reply.
+pub fn overlay_threads(
+    comments: &mut BTreeMap<String, CommentInfo>,
+    threads: &ThreadedComments,
+    sheet: &str,
+) {
+    for thread in threads.threads(sheet) {
+        let mut text = thread.root.text.clone();
+        for reply in thread.replies {
+            text.push('\n');
+            text.push_str(threads.author(&reply.person_id).unwrap_or("?"));
+            text.push_str(": ");
+            text.push_str(&reply.text);
+        }
+        comments.insert(
+            thread.root.address.clone(),
+            CommentInfo {
+                author: threads.author(&thread.root.person_id).map(str::to_string),
+                text,
+                resolved: thread.root.done,
+            },
+        );
+    }
+}
+
+pub fn diff_comments(
+    sheet: &str,
+    base: &BTreeMap<String, CommentInfo>,
+    fork: &BTreeMap<String, CommentInfo>,
+) -> Vec<CommentDiff> {
+    let mut diffs = Vec::new();
+    for (address, new) in fork {
+        match base.get(address) {
+            None => diffs.push(CommentDiff::CommentAdded {
+                sheet: sheet.to_string(),
+                address: address.clone(),
+                author: new.author.clone(),
+                text: new.text.clone(),
+            }),
+            Some(old) if old.author == new.author && old.text == new.text => {
+                if old.resolved != new.resolved {
+                    diffs.push(CommentDiff::CommentResolved {
+                        sheet: sheet.to_string(),
+                        address: address.clone(),
+                        resolved: new.resolved,
+                    });
+                }
+            }
+            Some(old) => diffs.push(CommentDiff::CommentModified {
+                sheet: sheet.to_string(),
+                address: address.clone(),
+                old_author: old.author.clone(),
+                new_author: new.author.clone(),
+                old_text: old.text.clone(),
+                new_text: new.text.clone(),
+            }),
+        }
+    }
+    for (address, old) in base {
+        if !fork.contains_key(address) {
+            diffs.push(CommentDiff::CommentDeleted {
+                sheet: sheet.to_string(),
+                address: address.clone(),
+                author: old.author.clone(),
+                old_text: old.text.clone(),
+            });
+        }
+    }
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rich_text_comments() {
+        let xml = r#"<comments xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<authors><author>Ana</author><author>Bo</author></authors>
+<commentList>
+<comment ref="B2" authorId="1"><text><r><rPr><b/></rPr><t>Bo:</t></r><r><t xml:space="preserve"> check &amp; fix</t></r></text></comment>
+<comment ref="A1" authorId="0"><text><t>Plain</t></text></comment>
+</commentList></comments>"#;
+        let mut reader = Reader::from_reader(xml.as_bytes());
+        let comments = parse_comments_xml(&mut reader).unwrap();
+        assert_eq!(comments["B2"].author.as_deref(), Some("Bo"));
+        assert_eq!(comments["B2"].text, "Bo: check & fix");
+        assert_eq!(comments["A1"].text, "Plain");
+
+        let mut fork = comments.clone();
+        fork.remove("A1");
+        let diffs = diff_comments("Sheet1", &comments, &fork);
+        assert!(matches!(
+            diffs.as_slice(),
+            [CommentDiff::CommentDeleted { address, .. }] if address == "A1"
+        ));
+    }
+}
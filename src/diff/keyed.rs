@@ -0,0 +1,396 @@
+//! Row-aligned sheet diff.
+//!
+//! The address diff in `merge` reports every shifted cell once rows are
+//! inserted, deleted or re-sorted. Here rows are matched by the value in a key
+//! column (an id, a SKU, ...) and columns by their header label, so a sorted
+//! or re-ordered sheet only reports the rows whose content actually changed.
+//! Styles are not compared in this mode.
+
+use super::merge::{ModificationType, values_equal};
+use crate::utils::{cell_address, column_number_to_name};
+use anyhow::{Result, anyhow};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use umya_spreadsheet::Worksheet;
+use umya_spreadsheet::helper::coordinate::column_index_from_string;
+
+#[derive(Debug, Serialize, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RowDiff {
+    RowAdded {
+        key: String,
+        row: u32,
+        values: BTreeMap<String, String>,
+    },
+    RowDeleted {
+        key: String,
+        old_row: u32,
+        values: BTreeMap<String, String>,
+    },
+    RowModified {
+        key: String,
+        old_row: u32,
+        new_row: u32,
+        /// Most significant change among `cells` (formula > value > recalc).
+        subtype: ModificationType,
+        cells: Vec<KeyedCellChange>,
+    },
+    ColumnAdded {
+        header: String,
+        column: String,
+    },
+    ColumnDeleted {
+        header: String,
+        old_column: String,
+    },
+}
+
+#[derive(Debug, Serialize, Clone, JsonSchema)]
+pub struct KeyedCellChange {
+    pub header: String,
+    pub old_address: String,
+    pub new_address: String,
+    pub subtype: ModificationType,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub old_formula: Option<String>,
+    pub new_formula: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct KeyedDiffOptions {
+    /// Header label (case-insensitive) or column letter of the key column.
+    pub key_column: String,
+    /// 1-based row holding the headers; data starts on the row below.
+    pub header_row: u32,
+}
+
+struct KeyedCell {
+    col: u32,
+    value: Option<String>,
+    formula: Option<String>,
+}
+
+struct KeyedRow {
+    key: String,
+    row: u32,
+    cells: HashMap<String, KeyedCell>,
+}
+
+/// Diff two versions of a sheet with rows aligned by `options.key_column`.
+pub fn diff_sheet_by_key(
+    base: &Worksheet,
+    fork: &Worksheet,
+    options: &KeyedDiffOptions,
+) -> Result<Vec<RowDiff>> {
+    let base_headers = header_labels(base, options.header_row);
+    let fork_headers = header_labels(fork, options.header_row);
+    let base_key = resolve_key_column(&base_headers, &options.key_column).ok_or_else(|| {
+        anyhow!(
+            "key column '{}' not found in base sheet '{}'",
+            options.key_column,
+            base.get_name()
+        )
+    })?;
+    let fork_key = resolve_key_column(&fork_headers, &options.key_column).ok_or_else(|| {
+        anyhow!(
+            "key column '{}' not found in sheet '{}'",
+            options.key_column,
+            fork.get_name()
+        )
+    })?;
+
+    let base_labels: HashSet<&str> = base_headers.iter().map(|(_, l)| l.as_str()).collect();
+    let fork_labels: HashSet<&str> = fork_headers.iter().map(|(_, l)| l.as_str()).collect();
+    let common: Vec<String> = fork_headers
+        .iter()
+        .filter(|(_, label)| base_labels.contains(label.as_str()))
+        .map(|(_, label)| label.clone())
+        .collect();
+
+    let mut diffs = Vec::new();
+    for (col, label) in &fork_headers {
+        if !base_labels.contains(label.as_str()) {
+            diffs.push(RowDiff::ColumnAdded {
+                header: label.clone(),
+                column: column_number_to_name(*col),
+            });
+        }
+    }
+    for (col, label) in &base_headers {
+        if !fork_labels.contains(label.as_str()) {
+            diffs.push(RowDiff::ColumnDeleted {
+                header: label.clone(),
+                old_column: column_number_to_name(*col),
+            });
+        }
+    }
+
+    let base_rows = keyed_rows(base, &base_headers, base_key, options.header_row);
+    let fork_rows = keyed_rows(fork, &fork_headers, fork_key, options.header_row);
+    let base_index: HashMap<&str, &KeyedRow> =
+        base_rows.iter().map(|r| (r.key.as_str(), r)).collect();
+    let fork_keys: HashSet<&str> = fork_rows.iter().map(|r| r.key.as_str()).collect();
+
+    for fork_row in &fork_rows {
+        match base_index.get(fork_row.key.as_str()) {
+            None => diffs.push(RowDiff::RowAdded {
+                key: fork_row.key.clone(),
+                row: fork_row.row,
+                values: row_values(fork_row),
+            }),
+            Some(base_row) => {
+                let cells = compare_rows(base_row, fork_row, &common);
+                if let Some(subtype) = strongest_subtype(&cells) {
+                    diffs.push(RowDiff::RowModified {
+                        key: fork_row.key.clone(),
+                        old_row: base_row.row,
+                        new_row: fork_row.row,
+                        subtype,
+                        cells,
+                    });
+                }
+            }
+        }
+    }
+    for base_row in &base_rows {
+        if !fork_keys.contains(base_row.key.as_str()) {
+            diffs.push(RowDiff::RowDeleted {
+                key: base_row.key.clone(),
+                old_row: base_row.row,
+                values: row_values(base_row),
+            });
+        }
+    }
+
+    Ok(diffs)
+}
+
+/// Header label per column. Blank headers fall back to the column letter and
+/// repeated labels get a ` (n)` suffix so every column has a unique identity.
+fn header_labels(sheet: &Worksheet, header_row: u32) -> Vec<(u32, String)> {
+    let (max_col, _) = sheet.get_highest_column_and_row();
+    let mut seen: HashMap<String, u32> = HashMap::new();
+    let mut labels = Vec::new();
+    for col in 1..=max_col {
+        let text = sheet
+            .get_cell((col, header_row))
+            .map(|cell| cell.get_value().trim().to_string())
+            .unwrap_or_default();
+        let base = if text.is_empty() {
+            column_number_to_name(col)
+        } else {
+            text
+        };
+        let count = seen.entry(base.clone()).or_insert(0);
+        *count += 1;
+        let label = if *count == 1 {
+            base
+        } else {
+            format!("{} ({})", base, count)
+        };
+        labels.push((col, label));
+    }
+    labels
+}
+
+fn resolve_key_column(headers: &[(u32, String)], key: &str) -> Option<u32> {
+    let key = key.trim();
+    if let Some((col, _)) = headers
+        .iter()
+        .find(|(_, label)| label.eq_ignore_ascii_case(key))
+    {
+        return Some(*col);
+    }
+    let is_letters =
+        !key.is_empty() && key.len() <= 3 && key.chars().all(|c| c.is_ascii_alphabetic());
+    if is_letters {
+        let col = column_index_from_string(&key.to_ascii_uppercase());
+        return headers.iter().any(|(c, _)| *c == col).then_some(col);
+    }
+    None
+}
+
+/// Data rows below the header with a non-empty key. Repeated keys are
+/// disambiguated as `key#2`, `key#3`, ... in sheet order.
+fn keyed_rows(
+    sheet: &Worksheet,
+    headers: &[(u32, String)],
+    key_col: u32,
+    header_row: u32,
+) -> Vec<KeyedRow> {
+    let (_, max_row) = sheet.get_highest_column_and_row();
+    let mut seen: HashMap<String, u32> = HashMap::new();
+    let mut rows = Vec::new();
+    for row in (header_row + 1)..=max_row {
+        let key = sheet
+            .get_cell((key_col, row))
+            .map(|cell| cell.get_value().trim().to_string())
+            .unwrap_or_default();
+        if key.is_empty() {
+            continue;
+        }
+        let count = seen.entry(key.clone()).or_insert(0);
+        *count += 1;
+        let key = if *count == 1 {
+            key
+        } else {
+            format!("{}#{}", key, count)
+        };
+
+        let cells = headers
+            .iter()
+            .map(|(col, label)| {
+                let cell = sheet.get_cell((*col, row));
+                let value = cell
+                    .map(|c| c.get_value().to_string())
+                    .filter(|v| !v.is_empty());
+                let formula = cell
+                    .filter(|c| c.is_formula())
+                    .map(|c| c.get_formula().to_string());
+                (
+                    label.clone(),
+                    KeyedCell {
+                        col: *col,
+                        value,
+                        formula,
+                    },
+                )
+            })
+            .collect();
+        rows.push(KeyedRow { key, row, cells });
+    }
+    rows
+}
+
+fn row_values(row: &KeyedRow) -> BTreeMap<String, String> {
+    row.cells
+        .iter()
+        .filter_map(|(label, cell)| cell.value.clone().map(|v| (label.clone(), v)))
+        .collect()
+}
+
+fn compare_rows(base: &KeyedRow, fork: &KeyedRow, common: &[String]) -> Vec<KeyedCellChange> {
+    let mut changes = Vec::new();
+    for label in common {
+        let (Some(b), Some(f)) = (base.cells.get(label), fork.cells.get(label)) else {
+            continue;
+        };
+        let formula_changed = b.formula != f.formula;
+        let value_changed = !values_equal(&b.value, &f.value);
+        let subtype = match (formula_changed, value_changed, f.formula.is_some()) {
+            (true, _, _) => ModificationType::FormulaEdit,
+            (false, true, true) => ModificationType::RecalcResult,
+            (false, true, false) => ModificationType::ValueEdit,
+            (false, false, _) => continue,
+        };
+        changes.push(KeyedCellChange {
+            header: label.clone(),
+            old_address: cell_address(b.col, base.row),
+            new_address: cell_address(f.col, fork.row),
+            subtype,
+            old_value: b.value.clone(),
+            new_value: f.value.clone(),
+            old_formula: b.formula.clone(),
+            new_formula: f.formula.clone(),
+        });
+    }
+    changes
+}
+
+fn strongest_subtype(cells: &[KeyedCellChange]) -> Option<ModificationType> {
+    let rank = |subtype: &ModificationType| match subtype {
+        ModificationType::FormulaEdit => 3,
+        ModificationType::ValueEdit => 2,
+        ModificationType::RecalcResult => 1,
+        ModificationType::StyleEdit => 0,
+    };
+    cells
+        .iter()
+        .map(|c| &c.subtype)
+        .max_by_key(|subtype| rank(subtype))
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sheet_with(rows: &[&[&str]]) -> umya_spreadsheet::Spreadsheet {
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        for (r, row) in rows.iter().enumerate() {
+            for (c, value) in row.iter().enumerate() {
+                if !value.is_empty() {
+                    sheet
+                        .get_cell_mut((c as u32 + 1, r as u32 + 1))
+                        .set_value(*value);
+                }
+            }
+        }
+        book
+    }
+
+    #[test]
+    fn aligns_resorted_rows_and_reordered_columns() {
+        let base = sheet_with(&[
+            &["Id", "Name", "Qty"],
+            &["A1", "Bolt", "10"],
+            &["B2", "Nut", "5"],
+            &["C3", "Washer", "7"],
+        ]);
+        let fork = sheet_with(&[
+            &["Qty", "Id", "Name", "Bin"],
+            &["5", "B2", "Nut", "x"],
+            &["12", "A1", "Bolt", ""],
+            &["1", "D4", "Pin", ""],
+        ]);
+        let options = KeyedDiffOptions {
+            key_column: "id".to_string(),
+            header_row: 1,
+        };
+        let diffs = diff_sheet_by_key(
+            base.get_sheet(&0).unwrap(),
+            fork.get_sheet(&0).unwrap(),
+            &options,
+        )
+        .unwrap();
+
+        assert!(
+            matches!(&diffs[0], RowDiff::ColumnAdded { header, column } if header == "Bin" && column == "D")
+        );
+        let modified: Vec<_> = diffs
+            .iter()
+            .filter_map(|d| match d {
+                RowDiff::RowModified { key, cells, .. } => Some((key.as_str(), cells)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(modified.len(), 1);
+        assert_eq!(modified[0].0, "A1");
+        assert_eq!(modified[0].1[0].header, "Qty");
+        assert_eq!(modified[0].1[0].old_address, "C2");
+        assert_eq!(modified[0].1[0].new_address, "A3");
+        assert!(
+            diffs
+                .iter()
+                .any(|d| matches!(d, RowDiff::RowAdded { key, row: 4, .. } if key == "D4"))
+        );
+        assert!(
+            diffs
+                .iter()
+                .any(|d| matches!(d, RowDiff::RowDeleted { key, old_row: 4, .. } if key == "C3"))
+        );
+    }
+
+    #[test]
+    fn key_column_accepts_letters_and_reports_missing() {
+        let book = sheet_with(&[&["", "Name"], &["1", "a"]]);
+        let sheet = book.get_sheet(&0).unwrap();
+        let headers = header_labels(sheet, 1);
+        assert_eq!(resolve_key_column(&headers, "a"), Some(1));
+        assert_eq!(resolve_key_column(&headers, "NAME"), Some(2));
+        assert_eq!(resolve_key_column(&headers, "Missing"), None);
+    }
+}
@@ -142,7 +142,7 @@ fn compare_cells(base: &RawCell, fork: &RawCell) -> Option<CellDiff> {
     })
 }
 
-fn values_equal(a: &Option<String>, b: &Option<String>) -> bool {
+pub(crate) fn values_equal(a: &Option<String>, b: &Option<String>) -> bool {
     match (a, b) {
         (None, None) => true,
         (Some(a), Some(b)) => {
@@ -1,24 +1,42 @@
 pub mod address;
 pub mod cells;
+pub mod charts;
+pub mod comments;
 pub mod hash;
+pub mod keyed;
 pub mod merge;
 pub mod names;
+pub mod sheet_parts;
+pub mod sheets;
 pub mod sst;
+pub mod styles;
 pub mod tables;
 
-use anyhow::Result;
+use crate::comments::ThreadedComments;
+use anyhow::{Result, anyhow};
 use cells::CellIterator;
+use charts::{ChartDiff, diff_charts};
+use comments::{CommentDiff, CommentInfo, diff_comments, overlay_threads, parse_comments_xml};
+use keyed::RowDiff;
 use merge::{CellDiff, diff_streams};
 use names::{DefinedName, NameDiff, NameKey, diff_names, parse_defined_names};
 use quick_xml::events::Event;
 use quick_xml::reader::Reader;
 use schemars::JsonSchema;
 use serde::Serialize;
+use sheet_parts::{
+    ConditionalFormatDiff, MergeDiff, SheetParts, ValidationDiff, diff_conditional_formats,
+    diff_merges, diff_validations, parse_dxf_signatures, parse_sheet_parts,
+};
+use sheets::{SheetDiff, SheetEntry, diff_sheet_list};
 use sst::Sst;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::BufReader;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use styles::{
+    StyleDiff, cell_styles_may_differ, diff_sheet_styles, parse_cell_xf_ids, parse_xf_signatures,
+};
 use tables::{TableDiff, TableInfo, diff_tables, parse_table_xml};
 use zip::ZipArchive;
 
@@ -28,6 +46,14 @@ pub enum Change {
     Cell(CellChange),
     Table(TableDiff),
     Name(NameDiff),
+    Row(RowChange),
+    Style(StyleDiff),
+    Comment(CommentDiff),
+    Chart(ChartDiff),
+    DataValidation(ValidationDiff),
+    ConditionalFormat(ConditionalFormatDiff),
+    Merge(MergeDiff),
+    Sheet(SheetDiff),
 }
 
 #[derive(Debug, Serialize, JsonSchema)]
@@ -37,6 +63,14 @@ pub struct CellChange {
     pub diff: CellDiff,
 }
 
+/// Row-level change from a key-aligned diff (see `keyed`).
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RowChange {
+    pub sheet: String,
+    #[serde(flatten)]
+    pub diff: RowDiff,
+}
+
 // Legacy alias for tests until updated
 pub type DiffResult = CellChange;
 
@@ -65,6 +99,12 @@ pub fn calculate_changeset(
     // Load Workbook Meta (Sheets + Names)
     let base_meta = load_workbook_meta(&mut base_zip)?;
     let fork_meta = load_workbook_meta(&mut fork_zip)?;
+    let base_threads = ThreadedComments::from_zip(&mut base_zip)?;
+    let fork_threads = ThreadedComments::from_zip(&mut fork_zip)?;
+    let mut base_charts: HashMap<String, _> =
+        crate::charts::charts_from_zip(&mut base_zip)?.into_iter().collect();
+    let mut fork_charts: HashMap<String, _> =
+        crate::charts::charts_from_zip(&mut fork_zip)?.into_iter().collect();
 
     let mut all_changes = Vec::new();
 
@@ -110,8 +150,25 @@ pub fn calculate_changeset(
         all_changes.push(Change::Table(d));
     }
 
-    // 3. Diff Cells (per sheet)
-    // We iterate the UNION of sheets
+    // 3. Sheet list (added/deleted/moved/visibility)
+    for d in diff_sheet_list(&base_meta.sheets, &fork_meta.sheets) {
+        if let Some(filter) = sheet_filter
+            && d.sheet() != filter
+        {
+            continue;
+        }
+        all_changes.push(Change::Sheet(d));
+    }
+
+    let base_styles_hash = part_hash(&mut base_zip, "xl/styles.xml");
+    let fork_styles_hash = part_hash(&mut fork_zip, "xl/styles.xml");
+    let styles_changed = base_styles_hash != fork_styles_hash;
+    let base_dxfs = load_dxf_signatures(&mut base_zip)?;
+    let fork_dxfs = load_dxf_signatures(&mut fork_zip)?;
+    let base_xfs = load_xf_signatures(&mut base_zip)?;
+    let fork_xfs = load_xf_signatures(&mut fork_zip)?;
+
+    // 4. Per-sheet diffs. We iterate the UNION of sheets
     let mut all_sheets: Vec<_> = base_meta
         .sheet_map
         .keys()
@@ -120,6 +177,10 @@ pub fn calculate_changeset(
     all_sheets.sort();
     all_sheets.dedup();
 
+    // Sheets (present on both sides) where some cell's xf signature differs, so
+    // styles need resolving through umya
+    let mut style_sheets: Vec<String> = Vec::new();
+
     for name in all_sheets {
         if let Some(filter) = sheet_filter
             && name != filter
@@ -130,28 +191,63 @@ pub fn calculate_changeset(
         let base_path_str = base_meta.sheet_map.get(name);
         let fork_path_str = fork_meta.sheet_map.get(name);
 
+        // Comments live in their own part, so check them even for unchanged sheets
+        let mut base_comments = match base_path_str {
+            Some(p) => load_comments(&mut base_zip, p)?,
+            None => BTreeMap::new(),
+        };
+        let mut fork_comments = match fork_path_str {
+            Some(p) => load_comments(&mut fork_zip, p)?,
+            None => BTreeMap::new(),
+        };
+        overlay_threads(&mut base_comments, &base_threads, name);
+        overlay_threads(&mut fork_comments, &fork_threads, name);
+        for d in diff_comments(name, &base_comments, &fork_comments) {
+            all_changes.push(Change::Comment(d));
+        }
+
+        // So do charts, behind the sheet's drawing
+        let sheet_base_charts = base_charts.remove(name).unwrap_or_default();
+        let sheet_fork_charts = fork_charts.remove(name).unwrap_or_default();
+        for d in diff_charts(name, &sheet_base_charts, &sheet_fork_charts) {
+            all_changes.push(Change::Chart(d));
+        }
+
         // Hash Check (optimization)
-        let base_hash = if let Some(p) = base_path_str {
-            if let Ok(f) = base_zip.by_name(p) {
-                hash::compute_hash(f)?
-            } else {
-                0
+        let base_hash = base_path_str.map_or(0, |p| part_hash(&mut base_zip, p));
+        let fork_hash = fork_path_str.map_or(0, |p| part_hash(&mut fork_zip, p));
+        let sheet_unchanged = base_hash != 0 && base_hash == fork_hash;
+
+        // Merges/CF/DV/panes live in the sheet part; CF formats also depend on styles.xml
+        let parts_may_differ = !sheet_unchanged || styles_changed;
+        if parts_may_differ && let (Some(b), Some(f)) = (base_path_str, fork_path_str) {
+            let base_cells = load_cell_xf_ids(&mut base_zip, b)?;
+            let fork_cells = load_cell_xf_ids(&mut fork_zip, f)?;
+            if cell_styles_may_differ(&base_cells, &base_xfs, &fork_cells, &fork_xfs) {
+                style_sheets.push(name.clone());
             }
-        } else {
-            0
-        };
 
-        let fork_hash = if let Some(p) = fork_path_str {
-            if let Ok(f) = fork_zip.by_name(p) {
-                hash::compute_hash(f)?
-            } else {
-                0
+            let base_parts = load_sheet_parts(&mut base_zip, b, &base_dxfs)?;
+            let fork_parts = load_sheet_parts(&mut fork_zip, f, &fork_dxfs)?;
+            for d in diff_merges(name, &base_parts, &fork_parts) {
+                all_changes.push(Change::Merge(d));
             }
-        } else {
-            0
-        };
+            for d in diff_conditional_formats(name, &base_parts, &fork_parts) {
+                all_changes.push(Change::ConditionalFormat(d));
+            }
+            for d in diff_validations(name, &base_parts, &fork_parts) {
+                all_changes.push(Change::DataValidation(d));
+            }
+            if base_parts.freeze_panes != fork_parts.freeze_panes {
+                all_changes.push(Change::Sheet(SheetDiff::FreezePanesChanged {
+                    sheet: name.clone(),
+                    old_panes: base_parts.freeze_panes,
+                    new_panes: fork_parts.freeze_panes,
+                }));
+            }
+        }
 
-        if base_hash != 0 && base_hash == fork_hash && base_sst_hash == fork_sst_hash {
+        if sheet_unchanged && base_sst_hash == fork_sst_hash {
             continue;
         }
 
@@ -191,6 +287,25 @@ pub fn calculate_changeset(
         }
     }
 
+    // 5. Resolved cell styles (needs the full umya model, so only for sheets
+    // where the xf comparison above found candidate cells)
+    if !style_sheets.is_empty() {
+        let base_book = umya_spreadsheet::reader::xlsx::read(base_path)
+            .map_err(|e| anyhow!("failed to read {:?} for style diff: {}", base_path, e))?;
+        let fork_book = umya_spreadsheet::reader::xlsx::read(fork_path)
+            .map_err(|e| anyhow!("failed to read {:?} for style diff: {}", fork_path, e))?;
+        for name in &style_sheets {
+            if let (Some(b), Some(f)) = (
+                base_book.get_sheet_by_name(name),
+                fork_book.get_sheet_by_name(name),
+            ) {
+                for d in diff_sheet_styles(name, b, f) {
+                    all_changes.push(Change::Style(d));
+                }
+            }
+        }
+    }
+
     Ok(all_changes)
 }
 
@@ -204,6 +319,7 @@ struct WorkbookMeta {
     sheet_id_map: HashMap<u32, String>, // index (0-based from sheetId or array?) -> name
     // Spec says localSheetId is 0-based index of sheet in workbook
     names: HashMap<NameKey, DefinedName>,
+    sheets: Vec<SheetEntry>, // tab order with visibility state
 }
 
 fn load_workbook_meta(zip: &mut ZipArchive<File>) -> Result<WorkbookMeta> {
@@ -215,6 +331,7 @@ fn load_workbook_meta(zip: &mut ZipArchive<File>) -> Result<WorkbookMeta> {
     // We need to know the *order* of sheets for localSheetId (0, 1, 2...)
     // Iterate sheets in order of appearance?
     let mut sheet_order = Vec::new();
+    let mut sheets = Vec::new();
 
     {
         let workbook_xml = zip.by_name("xl/workbook.xml")?;
@@ -227,16 +344,23 @@ fn load_workbook_meta(zip: &mut ZipArchive<File>) -> Result<WorkbookMeta> {
                     if e.name().as_ref() == b"sheet" {
                         let mut name = String::new();
                         let mut rid = String::new();
+                        let mut state = String::from("visible");
                         for attr in e.attributes() {
                             let attr = attr?;
                             if attr.key.as_ref() == b"name" {
-                                name = String::from_utf8_lossy(&attr.value).to_string();
+                                name = attr.unescape_value()?.to_string();
                             } else if attr.key.as_ref() == b"r:id" {
                                 rid = String::from_utf8_lossy(&attr.value).to_string();
+                            } else if attr.key.as_ref() == b"state" {
+                                state = String::from_utf8_lossy(&attr.value).to_string();
                             }
                         }
                         if !name.is_empty() && !rid.is_empty() {
                             name_to_rid.insert(rid, name.clone());
+                            sheets.push(SheetEntry {
+                                name: name.clone(),
+                                state,
+                            });
                             sheet_order.push(name);
                         }
                     } else if e.name().as_ref() == b"definedNames" {
@@ -308,64 +432,120 @@ fn load_workbook_meta(zip: &mut ZipArchive<File>) -> Result<WorkbookMeta> {
         sheet_map,
         sheet_id_map,
         names: defined_names,
+        sheets,
     })
 }
 
-fn load_tables(
+fn part_hash(zip: &mut ZipArchive<File>, path: &str) -> u64 {
+    zip.by_name(path)
+        .ok()
+        .and_then(|f| hash::compute_hash(f).ok())
+        .unwrap_or(0)
+}
+
+fn load_dxf_signatures(zip: &mut ZipArchive<File>) -> Result<Vec<String>> {
+    match zip.by_name("xl/styles.xml") {
+        Ok(f) => parse_dxf_signatures(&mut Reader::from_reader(BufReader::new(f))),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+fn load_xf_signatures(zip: &mut ZipArchive<File>) -> Result<Vec<String>> {
+    match zip.by_name("xl/styles.xml") {
+        Ok(f) => parse_xf_signatures(&mut Reader::from_reader(BufReader::new(f))),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+fn load_cell_xf_ids(
     zip: &mut ZipArchive<File>,
-    sheet_map: &HashMap<String, String>,
-) -> Result<HashMap<String, TableInfo>> {
-    let mut tables = HashMap::new();
+    sheet_path: &str,
+) -> Result<HashMap<(u32, u32), u32>> {
+    match zip.by_name(sheet_path) {
+        Ok(f) => parse_cell_xf_ids(&mut Reader::from_reader(BufReader::new(f))),
+        Err(_) => Ok(HashMap::new()),
+    }
+}
 
-    for (sheet_name, sheet_path) in sheet_map {
-        let path = Path::new(sheet_path);
-        let parent = path.parent().unwrap();
-        let filename = path.file_name().unwrap().to_str().unwrap();
-        let rels_path = parent.join("_rels").join(format!("{}.rels", filename));
-        let rels_path_str = rels_path.to_str().unwrap();
-
-        // 1. Find table files in rels
-        let mut table_files = Vec::new();
-        if let Ok(f) = zip.by_name(rels_path_str) {
-            let mut reader = Reader::from_reader(BufReader::new(f));
-            let mut buf = Vec::new();
-            loop {
-                match reader.read_event_into(&mut buf) {
-                    Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
-                        if e.name().as_ref() == b"Relationship" {
-                            let mut target = String::new();
-                            let mut type_attr = String::new();
-                            for attr in e.attributes() {
-                                let attr = attr?;
-                                if attr.key.as_ref() == b"Target" {
-                                    target = String::from_utf8_lossy(&attr.value).to_string();
-                                } else if attr.key.as_ref() == b"Type" {
-                                    type_attr = String::from_utf8_lossy(&attr.value).to_string();
-                                }
-                            }
-                            if type_attr.ends_with("/table") {
-                                table_files.push(target);
+fn load_sheet_parts(
+    zip: &mut ZipArchive<File>,
+    sheet_path: &str,
+    dxf_signatures: &[String],
+) -> Result<SheetParts> {
+    match zip.by_name(sheet_path) {
+        Ok(f) => parse_sheet_parts(&mut Reader::from_reader(BufReader::new(f)), dxf_signatures),
+        Err(_) => Ok(SheetParts::default()),
+    }
+}
+
+fn load_comments(
+    zip: &mut ZipArchive<File>,
+    sheet_path: &str,
+) -> Result<BTreeMap<String, CommentInfo>> {
+    let mut comments = BTreeMap::new();
+    for part in sheet_relationship_targets(zip, sheet_path, "/comments")? {
+        if let Ok(f) = zip.by_name(&part) {
+            comments.extend(parse_comments_xml(&mut Reader::from_reader(
+                BufReader::new(f),
+            ))?);
+        }
+    }
+    Ok(comments)
+}
+
+/// Resolved zip paths of the sheet's relationships whose `Type` ends with `type_suffix`.
+fn sheet_relationship_targets(
+    zip: &mut ZipArchive<File>,
+    sheet_path: &str,
+    type_suffix: &str,
+) -> Result<Vec<String>> {
+    let path = Path::new(sheet_path);
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    let filename = path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .unwrap_or_default();
+    let rels_path = parent.join("_rels").join(format!("{}.rels", filename));
+    let rels_path_str = rels_path.to_string_lossy().replace('\\', "/");
+
+    let mut targets = Vec::new();
+    if let Ok(f) = zip.by_name(&rels_path_str) {
+        let mut reader = Reader::from_reader(BufReader::new(f));
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                    if e.name().as_ref() == b"Relationship" {
+                        let mut target = String::new();
+                        let mut type_attr = String::new();
+                        for attr in e.attributes() {
+                            let attr = attr?;
+                            if attr.key.as_ref() == b"Target" {
+                                target = String::from_utf8_lossy(&attr.value).to_string();
+                            } else if attr.key.as_ref() == b"Type" {
+                                type_attr = String::from_utf8_lossy(&attr.value).to_string();
                             }
                         }
+                        if type_attr.ends_with(type_suffix) {
+                            targets.push(target);
+                        }
                     }
-                    Ok(Event::Eof) => break,
-                    _ => {}
                 }
-                buf.clear();
+                Ok(Event::Eof) => break,
+                _ => {}
             }
+            buf.clear();
         }
+    }
 
-        // 2. Parse table files
-        for target in table_files {
-            // Target is relative to sheet path parent (e.g. "../tables/table1.xml")
-            // parent is "xl/worksheets"
-            // We need to resolve this path.
-            // Simple normalization: if starts with "../", strip last component of parent.
-            // parent: "xl/worksheets" -> parent of that is "xl"
-            // target: "tables/table1.xml" -> "xl/tables/table1.xml"
-
-            // Or use PathBuf logic
-            let mut full_path = parent.to_path_buf();
+    // Targets are relative to the sheet's folder (e.g. "../tables/table1.xml")
+    Ok(targets
+        .into_iter()
+        .map(|target| {
+            if let Some(absolute) = target.strip_prefix('/') {
+                return absolute.to_string();
+            }
+            let mut full_path: PathBuf = parent.to_path_buf();
             for component in Path::new(&target).components() {
                 match component {
                     std::path::Component::ParentDir => {
@@ -377,10 +557,20 @@ fn load_tables(
                     _ => {}
                 }
             }
+            full_path.to_string_lossy().replace('\\', "/")
+        })
+        .collect())
+}
 
-            let full_path_str = full_path.to_str().unwrap();
+fn load_tables(
+    zip: &mut ZipArchive<File>,
+    sheet_map: &HashMap<String, String>,
+) -> Result<HashMap<String, TableInfo>> {
+    let mut tables = HashMap::new();
 
-            if let Ok(f) = zip.by_name(full_path_str) {
+    for (sheet_name, sheet_path) in sheet_map {
+        for full_path in sheet_relationship_targets(zip, sheet_path, "/table")? {
+            if let Ok(f) = zip.by_name(&full_path) {
                 let mut reader = Reader::from_reader(BufReader::new(f));
                 if let Ok(info) = parse_table_xml(&mut reader, sheet_name.clone()) {
                     tables.insert(info.display_name.clone(), info);
@@ -0,0 +1,552 @@
+//! Worksheet-level parts outside `<sheetData>`: merged cells, conditional
+//! formats, data validations and the frozen pane.
+//!
+//! The sheet XML is streamed once with `<sheetData>` skipped, so the cost is
+//! proportional to the (small) metadata rather than the number of cells.
+
+use anyhow::Result;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::QName;
+use quick_xml::reader::Reader;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::BufRead;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SheetParts {
+    pub merges: BTreeSet<String>,
+    /// Rules keyed by `sqref`; blocks sharing a range are concatenated.
+    pub conditional_formats: BTreeMap<String, Vec<ConditionalFormatRule>>,
+    pub data_validations: BTreeMap<String, ValidationRule>,
+    pub freeze_panes: Option<FreezePanes>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct FreezePanes {
+    pub top_left_cell: Option<String>,
+    pub frozen_rows: u32,
+    pub frozen_cols: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, JsonSchema)]
+pub struct ConditionalFormatRule {
+    pub rule_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub formulas: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dxf_id: Option<u32>,
+    /// Remaining rule attributes and color-scale/data-bar/icon-set settings.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<String>,
+    /// Canonical form of the referenced differential format; compared instead
+    /// of `dxf_id` because ids are renumbered when styles.xml is rewritten.
+    #[serde(skip)]
+    pub format_signature: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, JsonSchema)]
+pub struct ValidationRule {
+    pub validation_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formula1: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formula2: Option<String>,
+    pub allow_blank: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_style: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MergeDiff {
+    MergeAdded { sheet: String, range: String },
+    MergeRemoved { sheet: String, range: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConditionalFormatDiff {
+    ConditionalFormatAdded {
+        sheet: String,
+        range: String,
+        rules: Vec<ConditionalFormatRule>,
+    },
+    ConditionalFormatDeleted {
+        sheet: String,
+        range: String,
+        rules: Vec<ConditionalFormatRule>,
+    },
+    ConditionalFormatModified {
+        sheet: String,
+        range: String,
+        old_rules: Vec<ConditionalFormatRule>,
+        new_rules: Vec<ConditionalFormatRule>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ValidationDiff {
+    ValidationAdded {
+        sheet: String,
+        range: String,
+        rule: ValidationRule,
+    },
+    ValidationDeleted {
+        sheet: String,
+        range: String,
+        rule: ValidationRule,
+    },
+    ValidationModified {
+        sheet: String,
+        range: String,
+        old_rule: ValidationRule,
+        new_rule: ValidationRule,
+    },
+}
+
+impl MergeDiff {
+    pub fn sheet(&self) -> &str {
+        match self {
+            MergeDiff::MergeAdded { sheet, .. } | MergeDiff::MergeRemoved { sheet, .. } => sheet,
+        }
+    }
+}
+
+impl ConditionalFormatDiff {
+    pub fn sheet(&self) -> &str {
+        match self {
+            ConditionalFormatDiff::ConditionalFormatAdded { sheet, .. }
+            | ConditionalFormatDiff::ConditionalFormatDeleted { sheet, .. }
+            | ConditionalFormatDiff::ConditionalFormatModified { sheet, .. } => sheet,
+        }
+    }
+}
+
+impl ValidationDiff {
+    pub fn sheet(&self) -> &str {
+        match self {
+            ValidationDiff::ValidationAdded { sheet, .. }
+            | ValidationDiff::ValidationDeleted { sheet, .. }
+            | ValidationDiff::ValidationModified { sheet, .. } => sheet,
+        }
+    }
+}
+
+/// Element whose text content is being collected.
+#[derive(Clone, Copy)]
+enum TextTarget {
+    CfFormula,
+    DvFormula1,
+    DvFormula2,
+}
+
+fn attr_string(e: &BytesStart, key: &[u8]) -> Result<Option<String>> {
+    for attr in e.attributes() {
+        let attr = attr?;
+        if attr.key.local_name().as_ref() == key {
+            return Ok(Some(attr.unescape_value()?.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+fn attr_flag(e: &BytesStart, key: &[u8]) -> Result<bool> {
+    Ok(matches!(
+        attr_string(e, key)?.as_deref(),
+        Some("1") | Some("true")
+    ))
+}
+
+/// Parse the parts of a worksheet XML stream. `dxf_signatures` is indexed by
+/// `dxfId` (see [`parse_dxf_signatures`]).
+pub fn parse_sheet_parts<R: BufRead>(
+    reader: &mut Reader<R>,
+    dxf_signatures: &[String],
+) -> Result<SheetParts> {
+    let mut parts = SheetParts::default();
+    let mut buf = Vec::new();
+    let mut skip_buf = Vec::new();
+
+    let mut cf_range: Option<String> = None;
+    let mut cf_rule: Option<ConditionalFormatRule> = None;
+    let mut dv: Option<(String, ValidationRule)> = None;
+    let mut text_target: Option<TextTarget> = None;
+
+    loop {
+        let event = reader.read_event_into(&mut buf)?;
+        match event {
+            Event::Start(ref e) if e.local_name().as_ref() == b"sheetData" => {
+                let name = e.name().as_ref().to_vec();
+                reader.read_to_end_into(QName(&name), &mut skip_buf)?;
+                skip_buf.clear();
+            }
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let is_empty = matches!(event, Event::Empty(_));
+                match e.local_name().as_ref() {
+                    b"pane" => {
+                        let state = attr_string(e, b"state")?;
+                        if matches!(state.as_deref(), Some("frozen") | Some("frozenSplit")) {
+                            let split = |key: &[u8]| -> Result<u32> {
+                                Ok(attr_string(e, key)?
+                                    .and_then(|v| v.parse::<f64>().ok())
+                                    .map(|v| v as u32)
+                                    .unwrap_or(0))
+                            };
+                            parts.freeze_panes = Some(FreezePanes {
+                                top_left_cell: attr_string(e, b"topLeftCell")?,
+                                frozen_rows: split(b"ySplit")?,
+                                frozen_cols: split(b"xSplit")?,
+                            });
+                        }
+                    }
+                    b"mergeCell" => {
+                        if let Some(range) = attr_string(e, b"ref")? {
+                            parts.merges.insert(range);
+                        }
+                    }
+                    b"conditionalFormatting" if !is_empty => {
+                        cf_range = attr_string(e, b"sqref")?;
+                    }
+                    b"cfRule" if cf_range.is_some() => {
+                        let mut rule = ConditionalFormatRule::default();
+                        for attr in e.attributes() {
+                            let attr = attr?;
+                            let value = attr.unescape_value()?.to_string();
+                            match attr.key.local_name().as_ref() {
+                                b"type" => rule.rule_type = value,
+                                b"operator" => rule.operator = Some(value),
+                                b"priority" => rule.priority = value.parse().ok(),
+                                b"dxfId" => rule.dxf_id = value.parse().ok(),
+                                key => rule.details.push(format!(
+                                    "{}={}",
+                                    String::from_utf8_lossy(key),
+                                    value
+                                )),
+                            }
+                        }
+                        rule.format_signature = rule
+                            .dxf_id
+                            .and_then(|id| dxf_signatures.get(id as usize).cloned());
+                        if is_empty {
+                            push_cf_rule(&mut parts, &cf_range, rule);
+                        } else {
+                            cf_rule = Some(rule);
+                        }
+                    }
+                    b"formula" if cf_rule.is_some() => text_target = Some(TextTarget::CfFormula),
+                    b"cfvo" | b"color" | b"colorScale" | b"dataBar" | b"iconSet" | b"cfIcon"
+                        if cf_rule.is_some() =>
+                    {
+                        let mut detail =
+                            String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                        for attr in e.attributes() {
+                            let attr = attr?;
+                            detail.push_str(&format!(
+                                " {}={}",
+                                String::from_utf8_lossy(attr.key.local_name().as_ref()),
+                                attr.unescape_value()?
+                            ));
+                        }
+                        if let Some(rule) = cf_rule.as_mut() {
+                            rule.details.push(detail);
+                        }
+                    }
+                    b"dataValidation" => {
+                        let range = attr_string(e, b"sqref")?.unwrap_or_default();
+                        let rule = ValidationRule {
+                            validation_type: attr_string(e, b"type")?
+                                .unwrap_or_else(|| "none".to_string()),
+                            operator: attr_string(e, b"operator")?,
+                            formula1: None,
+                            formula2: None,
+                            allow_blank: attr_flag(e, b"allowBlank")?,
+                            error_style: attr_string(e, b"errorStyle")?,
+                            error_title: attr_string(e, b"errorTitle")?,
+                            error: attr_string(e, b"error")?,
+                            prompt_title: attr_string(e, b"promptTitle")?,
+                            prompt: attr_string(e, b"prompt")?,
+                        };
+                        if is_empty {
+                            parts.data_validations.insert(range, rule);
+                        } else {
+                            dv = Some((range, rule));
+                        }
+                    }
+                    b"formula1" if dv.is_some() => text_target = Some(TextTarget::DvFormula1),
+                    b"formula2" if dv.is_some() => text_target = Some(TextTarget::DvFormula2),
+                    _ => {}
+                }
+            }
+            Event::Text(ref t) => {
+                if let Some(target) = text_target {
+                    let text = t.unescape()?.to_string();
+                    match target {
+                        TextTarget::CfFormula => {
+                            if let Some(rule) = cf_rule.as_mut() {
+                                rule.formulas.push(text);
+                            }
+                        }
+                        TextTarget::DvFormula1 => {
+                            if let Some((_, rule)) = dv.as_mut() {
+                                rule.formula1 = Some(text);
+                            }
+                        }
+                        TextTarget::DvFormula2 => {
+                            if let Some((_, rule)) = dv.as_mut() {
+                                rule.formula2 = Some(text);
+                            }
+                        }
+                    }
+                }
+            }
+            Event::End(ref e) => match e.local_name().as_ref() {
+                b"formula" | b"formula1" | b"formula2" => text_target = None,
+                b"cfRule" => {
+                    if let Some(rule) = cf_rule.take() {
+                        push_cf_rule(&mut parts, &cf_range, rule);
+                    }
+                }
+                b"conditionalFormatting" => cf_range = None,
+                b"dataValidation" => {
+                    if let Some((range, rule)) = dv.take() {
+                        parts.data_validations.insert(range, rule);
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(parts)
+}
+
+fn push_cf_rule(parts: &mut SheetParts, range: &Option<String>, rule: ConditionalFormatRule) {
+    if let Some(range) = range {
+        parts
+            .conditional_formats
+            .entry(range.clone())
+            .or_default()
+            .push(rule);
+    }
+}
+
+/// Canonical text of each `<dxf>` in styles.xml, in `dxfId` order.
+pub fn parse_dxf_signatures<R: BufRead>(reader: &mut Reader<R>) -> Result<Vec<String>> {
+    let mut signatures = Vec::new();
+    let mut current: Option<String> = None;
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                if e.local_name().as_ref() == b"dxf" {
+                    current = Some(String::new());
+                } else if let Some(sig) = current.as_mut() {
+                    sig.push('<');
+                    sig.push_str(&String::from_utf8_lossy(e.local_name().as_ref()));
+                    for attr in e.attributes() {
+                        let attr = attr?;
+                        sig.push_str(&format!(
+                            " {}={}",
+                            String::from_utf8_lossy(attr.key.local_name().as_ref()),
+                            String::from_utf8_lossy(&attr.value)
+                        ));
+                    }
+                    sig.push('>');
+                }
+            }
+            Event::End(ref e) if e.local_name().as_ref() == b"dxf" => {
+                if let Some(sig) = current.take() {
+                    signatures.push(sig);
+                }
+            }
+            Event::End(ref e) if e.local_name().as_ref() == b"dxfs" => break,
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(signatures)
+}
+
+pub fn diff_merges(sheet: &str, base: &SheetParts, fork: &SheetParts) -> Vec<MergeDiff> {
+    let mut diffs = Vec::new();
+    for range in fork.merges.difference(&base.merges) {
+        diffs.push(MergeDiff::MergeAdded {
+            sheet: sheet.to_string(),
+            range: range.clone(),
+        });
+    }
+    for range in base.merges.difference(&fork.merges) {
+        diffs.push(MergeDiff::MergeRemoved {
+            sheet: sheet.to_string(),
+            range: range.clone(),
+        });
+    }
+    diffs
+}
+
+fn same_cf_rules(a: &[ConditionalFormatRule], b: &[ConditionalFormatRule]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).all(|(x, y)| {
+            x.rule_type == y.rule_type
+                && x.operator == y.operator
+                && x.priority == y.priority
+                && x.formulas == y.formulas
+                && x.details == y.details
+                && x.format_signature == y.format_signature
+        })
+}
+
+pub fn diff_conditional_formats(
+    sheet: &str,
+    base: &SheetParts,
+    fork: &SheetParts,
+) -> Vec<ConditionalFormatDiff> {
+    let mut diffs = Vec::new();
+    for (range, new_rules) in &fork.conditional_formats {
+        match base.conditional_formats.get(range) {
+            None => diffs.push(ConditionalFormatDiff::ConditionalFormatAdded {
+                sheet: sheet.to_string(),
+                range: range.clone(),
+                rules: new_rules.clone(),
+            }),
+            Some(old_rules) if !same_cf_rules(old_rules, new_rules) => {
+                diffs.push(ConditionalFormatDiff::ConditionalFormatModified {
+                    sheet: sheet.to_string(),
+                    range: range.clone(),
+                    old_rules: old_rules.clone(),
+                    new_rules: new_rules.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    for (range, rules) in &base.conditional_formats {
+        if !fork.conditional_formats.contains_key(range) {
+            diffs.push(ConditionalFormatDiff::ConditionalFormatDeleted {
+                sheet: sheet.to_string(),
+                range: range.clone(),
+                rules: rules.clone(),
+            });
+        }
+    }
+    diffs
+}
+
+pub fn diff_validations(sheet: &str, base: &SheetParts, fork: &SheetParts) -> Vec<ValidationDiff> {
+    let mut diffs = Vec::new();
+    for (range, new_rule) in &fork.data_validations {
+        match base.data_validations.get(range) {
+            None => diffs.push(ValidationDiff::ValidationAdded {
+                sheet: sheet.to_string(),
+                range: range.clone(),
+                rule: new_rule.clone(),
+            }),
+            Some(old_rule) if old_rule != new_rule => {
+                diffs.push(ValidationDiff::ValidationModified {
+                    sheet: sheet.to_string(),
+                    range: range.clone(),
+                    old_rule: old_rule.clone(),
+                    new_rule: new_rule.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    for (range, rule) in &base.data_validations {
+        if !fork.data_validations.contains_key(range) {
+            diffs.push(ValidationDiff::ValidationDeleted {
+                sheet: sheet.to_string(),
+                range: range.clone(),
+                rule: rule.clone(),
+            });
+        }
+    }
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHEET: &str = r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<sheetViews><sheetView workbookViewId="0"><pane xSplit="1" ySplit="2" topLeftCell="B3" activePane="bottomRight" state="frozen"/></sheetView></sheetViews>
+<sheetData><row r="1"><c r="A1"><v>1</v></c></row></sheetData>
+<mergeCells count="1"><mergeCell ref="A1:B1"/></mergeCells>
+<conditionalFormatting sqref="C2:C9"><cfRule type="cellIs" dxfId="0" priority="1" operator="greaterThan"><formula>10</formula></cfRule></conditionalFormatting>
+<conditionalFormatting sqref="D2:D9"><cfRule type="colorScale" priority="2"><colorScale><cfvo type="min"/><cfvo type="max"/><color rgb="FFF8696B"/><color rgb="FF63BE7B"/></colorScale></cfRule></conditionalFormatting>
+<dataValidations count="1"><dataValidation type="list" allowBlank="1" showErrorMessage="1" error="Pick one" sqref="E2:E9"><formula1>"Open,Closed"</formula1></dataValidation></dataValidations>
+</worksheet>"#;
+
+    #[test]
+    fn parses_parts_outside_sheet_data() {
+        let mut reader = Reader::from_reader(SHEET.as_bytes());
+        let parts = parse_sheet_parts(&mut reader, &["<font><b>".to_string()]).unwrap();
+
+        assert_eq!(
+            parts.freeze_panes,
+            Some(FreezePanes {
+                top_left_cell: Some("B3".to_string()),
+                frozen_rows: 2,
+                frozen_cols: 1,
+            })
+        );
+        assert!(parts.merges.contains("A1:B1"));
+        let cell_is = &parts.conditional_formats["C2:C9"][0];
+        assert_eq!(cell_is.rule_type, "cellIs");
+        assert_eq!(cell_is.formulas, vec!["10".to_string()]);
+        assert_eq!(cell_is.format_signature.as_deref(), Some("<font><b>"));
+        assert_eq!(parts.conditional_formats["D2:D9"][0].details.len(), 5);
+        let dv = &parts.data_validations["E2:E9"];
+        assert_eq!(dv.validation_type, "list");
+        assert_eq!(dv.formula1.as_deref(), Some("\"Open,Closed\""));
+        assert_eq!(dv.error.as_deref(), Some("Pick one"));
+        assert!(dv.allow_blank);
+    }
+
+    #[test]
+    fn reports_added_and_modified_parts() {
+        let mut reader = Reader::from_reader(SHEET.as_bytes());
+        let base = parse_sheet_parts(&mut reader, &[]).unwrap();
+        let mut fork = base.clone();
+        fork.merges.insert("F1:G1".to_string());
+        fork.data_validations.get_mut("E2:E9").unwrap().formula1 =
+            Some("\"Open,Closed,Parked\"".to_string());
+        fork.conditional_formats.remove("D2:D9");
+
+        assert_eq!(
+            diff_merges("S", &base, &fork),
+            vec![MergeDiff::MergeAdded {
+                sheet: "S".to_string(),
+                range: "F1:G1".to_string()
+            }]
+        );
+        assert!(matches!(
+            diff_validations("S", &base, &fork).as_slice(),
+            [ValidationDiff::ValidationModified { range, .. }] if range == "E2:E9"
+        ));
+        assert!(matches!(
+            diff_conditional_formats("S", &base, &fork).as_slice(),
+            [ConditionalFormatDiff::ConditionalFormatDeleted { range, .. }] if range == "D2:D9"
+        ));
+    }
+}
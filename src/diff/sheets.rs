@@ -0,0 +1,203 @@
+use super::sheet_parts::FreezePanes;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Sheet list entry from workbook.xml, in tab order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetEntry {
+    pub name: String,
+    /// `visible`, `hidden` or `veryHidden`.
+    pub state: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SheetDiff {
+    SheetAdded {
+        sheet: String,
+        index: u32,
+    },
+    SheetDeleted {
+        sheet: String,
+        old_index: u32,
+    },
+    SheetMoved {
+        sheet: String,
+        old_index: u32,
+        new_index: u32,
+    },
+    SheetVisibilityChanged {
+        sheet: String,
+        old_state: String,
+        new_state: String,
+    },
+    FreezePanesChanged {
+        sheet: String,
+        old_panes: Option<FreezePanes>,
+        new_panes: Option<FreezePanes>,
+    },
+}
+
+impl SheetDiff {
+    pub fn sheet(&self) -> &str {
+        match self {
+            SheetDiff::SheetAdded { sheet, .. }
+            | SheetDiff::SheetDeleted { sheet, .. }
+            | SheetDiff::SheetMoved { sheet, .. }
+            | SheetDiff::SheetVisibilityChanged { sheet, .. }
+            | SheetDiff::FreezePanesChanged { sheet, .. } => sheet,
+        }
+    }
+}
+
+/// Added/deleted sheets, visibility changes and tab moves.
+///
+/// Only sheets that actually moved are reported: the longest run of sheets
+/// keeping their relative order is treated as stationary, so inserting one
+/// sheet at the front does not report every other sheet as moved.
+pub fn diff_sheet_list(base: &[SheetEntry], fork: &[SheetEntry]) -> Vec<SheetDiff> {
+    let mut diffs = Vec::new();
+    let base_index: HashMap<&str, usize> = base
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.name.as_str(), i))
+        .collect();
+    let fork_index: HashMap<&str, usize> = fork
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.name.as_str(), i))
+        .collect();
+
+    for (idx, entry) in fork.iter().enumerate() {
+        match base_index.get(entry.name.as_str()) {
+            None => diffs.push(SheetDiff::SheetAdded {
+                sheet: entry.name.clone(),
+                index: idx as u32,
+            }),
+            Some(&old_idx) if base[old_idx].state != entry.state => {
+                diffs.push(SheetDiff::SheetVisibilityChanged {
+                    sheet: entry.name.clone(),
+                    old_state: base[old_idx].state.clone(),
+                    new_state: entry.state.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    for (idx, entry) in base.iter().enumerate() {
+        if !fork_index.contains_key(entry.name.as_str()) {
+            diffs.push(SheetDiff::SheetDeleted {
+                sheet: entry.name.clone(),
+                old_index: idx as u32,
+            });
+        }
+    }
+
+    let common_base: Vec<&str> = base
+        .iter()
+        .map(|s| s.name.as_str())
+        .filter(|name| fork_index.contains_key(name))
+        .collect();
+    let common_fork: Vec<&str> = fork
+        .iter()
+        .map(|s| s.name.as_str())
+        .filter(|name| base_index.contains_key(name))
+        .collect();
+    let stationary = longest_common_subsequence(&common_base, &common_fork);
+    for name in common_fork {
+        if !stationary.contains(name) {
+            diffs.push(SheetDiff::SheetMoved {
+                sheet: name.to_string(),
+                old_index: base_index[name] as u32,
+                new_index: fork_index[name] as u32,
+            });
+        }
+    }
+
+    diffs
+}
+
+fn longest_common_subsequence<'a>(a: &[&'a str], b: &[&'a str]) -> HashSet<&'a str> {
+    let mut table = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            table[i][j] = if a[i] == b[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+    let mut out = HashSet::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            out.insert(a[i]);
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(names: &[(&str, &str)]) -> Vec<SheetEntry> {
+        names
+            .iter()
+            .map(|(name, state)| SheetEntry {
+                name: name.to_string(),
+                state: state.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reports_only_the_sheet_that_moved() {
+        let base = entries(&[
+            ("Inputs", "visible"),
+            ("Calc", "visible"),
+            ("Report", "visible"),
+            ("Old", "visible"),
+        ]);
+        let fork = entries(&[
+            ("New", "visible"),
+            ("Report", "visible"),
+            ("Inputs", "visible"),
+            ("Calc", "hidden"),
+        ]);
+        let diffs = diff_sheet_list(&base, &fork);
+        assert!(diffs.contains(&SheetDiff::SheetAdded {
+            sheet: "New".to_string(),
+            index: 0
+        }));
+        assert!(diffs.contains(&SheetDiff::SheetDeleted {
+            sheet: "Old".to_string(),
+            old_index: 3
+        }));
+        assert!(diffs.contains(&SheetDiff::SheetVisibilityChanged {
+            sheet: "Calc".to_string(),
+            old_state: "visible".to_string(),
+            new_state: "hidden".to_string(),
+        }));
+        let moved: Vec<_> = diffs
+            .iter()
+            .filter(|d| matches!(d, SheetDiff::SheetMoved { .. }))
+            .collect();
+        assert_eq!(
+            moved,
+            vec![&SheetDiff::SheetMoved {
+                sheet: "Report".to_string(),
+                old_index: 2,
+                new_index: 1
+            }]
+        );
+    }
+}
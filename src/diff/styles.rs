@@ -0,0 +1,489 @@
+//! Cell style changes compared by resolved formatting.
+//!
+//! The raw `s` attribute compared in `merge` is an index into `cellXfs`, which
+//! is renumbered whenever styles.xml is rewritten. Here each cell's style is
+//! resolved to a `StyleDescriptor` and compared by `stable_style_id`; cells
+//! sharing the same before/after pair are grouped into ranges. A cell that
+//! exists on one side only is compared against that sheet's inferred default
+//! (most common) style, so adding a plain value is not a style change.
+//!
+//! Resolving through umya means parsing both workbooks, so the zip pass first
+//! compares each cell's `cellXfs` entry by signature (`parse_xf_signatures`)
+//! and only sheets where some cell may resolve differently are parsed.
+
+use super::address::CellAddress;
+use crate::model::StyleDescriptor;
+use crate::styles::{compress_positions_to_ranges, descriptor_from_style, stable_style_id};
+use anyhow::Result;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::BufRead;
+use umya_spreadsheet::{Style, Worksheet};
+
+const MAX_RANGES_PER_CHANGE: usize = 50;
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StyleDiff {
+    StyleChanged {
+        sheet: String,
+        ranges: Vec<String>,
+        ranges_truncated: bool,
+        cell_count: u32,
+        old_style_id: String,
+        new_style_id: String,
+        old_style: StyleDescriptor,
+        new_style: StyleDescriptor,
+    },
+}
+
+impl StyleDiff {
+    pub fn sheet(&self) -> &str {
+        match self {
+            StyleDiff::StyleChanged { sheet, .. } => sheet,
+        }
+    }
+}
+
+struct StyleGroup {
+    old_style: StyleDescriptor,
+    new_style: StyleDescriptor,
+    positions: Vec<(u32, u32)>,
+}
+
+fn resolved_styles(sheet: &Worksheet) -> HashMap<(u32, u32), ResolvedStyle> {
+    sheet
+        .get_cell_collection()
+        .into_iter()
+        .map(|cell| {
+            let coordinate = cell.get_coordinate();
+            let descriptor = descriptor_from_style(cell.get_style());
+            (
+                (*coordinate.get_row_num(), *coordinate.get_col_num()),
+                (stable_style_id(&descriptor), descriptor),
+            )
+        })
+        .collect()
+}
+
+type ResolvedStyle = (String, StyleDescriptor);
+
+fn inferred_default(styles: &HashMap<(u32, u32), ResolvedStyle>) -> ResolvedStyle {
+    let mut counts: HashMap<&str, (u32, &StyleDescriptor)> = HashMap::new();
+    for (id, descriptor) in styles.values() {
+        counts.entry(id.as_str()).or_insert((0, descriptor)).0 += 1;
+    }
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.0.cmp(&b.1.0).then_with(|| b.0.cmp(a.0)))
+        .map(|(id, (_, descriptor))| (id.to_string(), descriptor.clone()))
+        .unwrap_or_else(|| {
+            let descriptor = descriptor_from_style(&Style::default());
+            (stable_style_id(&descriptor), descriptor)
+        })
+}
+
+pub fn diff_sheet_styles(sheet_name: &str, base: &Worksheet, fork: &Worksheet) -> Vec<StyleDiff> {
+    let base_styles = resolved_styles(base);
+    let fork_styles = resolved_styles(fork);
+    let base_default = inferred_default(&base_styles);
+    let fork_default = inferred_default(&fork_styles);
+
+    let mut groups: BTreeMap<(String, String), StyleGroup> = BTreeMap::new();
+    let mut record = |pos: (u32, u32), old: &ResolvedStyle, new: &ResolvedStyle| {
+        let ((old_id, old_style), (new_id, new_style)) = (old.clone(), new.clone());
+        if old_id == new_id {
+            return;
+        }
+        groups
+            .entry((old_id, new_id))
+            .or_insert_with(|| StyleGroup {
+                old_style,
+                new_style,
+                positions: Vec::new(),
+            })
+            .positions
+            .push(pos);
+    };
+
+    for (pos, new) in &fork_styles {
+        record(*pos, base_styles.get(pos).unwrap_or(&base_default), new);
+    }
+    for (pos, old) in &base_styles {
+        if !fork_styles.contains_key(pos) {
+            record(*pos, old, &fork_default);
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|((old_style_id, new_style_id), group)| {
+            let (ranges, ranges_truncated) =
+                compress_positions_to_ranges(&group.positions, MAX_RANGES_PER_CHANGE);
+            StyleDiff::StyleChanged {
+                sheet: sheet_name.to_string(),
+                ranges,
+                ranges_truncated,
+                cell_count: group.positions.len() as u32,
+                old_style_id,
+                new_style_id,
+                old_style: group.old_style,
+                new_style: group.new_style,
+            }
+        })
+        .collect()
+}
+
+/// Cell position (row, col) → `cellXfs` index for every `<c>` in a sheet part,
+/// including empty styled cells. Cells without `s` use xf 0.
+pub fn parse_cell_xf_ids<R: BufRead>(reader: &mut Reader<R>) -> Result<HashMap<(u32, u32), u32>> {
+    let mut cells = HashMap::new();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(ref e) | Event::Empty(ref e) if e.local_name().as_ref() == b"c" => {
+                let mut address = None;
+                let mut xf = 0;
+                for attr in e.attributes() {
+                    let attr = attr?;
+                    match attr.key.local_name().as_ref() {
+                        b"r" => address = CellAddress::parse(&String::from_utf8_lossy(&attr.value)),
+                        b"s" => xf = String::from_utf8_lossy(&attr.value).parse().unwrap_or(0),
+                        _ => {}
+                    }
+                }
+                if let Some(address) = address {
+                    cells.insert((address.row, address.col), xf);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(cells)
+}
+
+/// An item of one of styles.xml's top-level collections being read.
+struct StyleItem {
+    section: &'static str,
+    depth: usize,
+    attrs: Vec<(String, String)>,
+    children: String,
+}
+
+/// One signature per `cellXfs` entry in styles.xml: the xf's attributes and
+/// children, with the number format code and the font, fill, border and cell
+/// style xf it points at inlined. Equal signatures resolve to equal styles
+/// whatever the entries' indices.
+pub fn parse_xf_signatures<R: BufRead>(reader: &mut Reader<R>) -> Result<Vec<String>> {
+    let mut num_fmts: HashMap<String, String> = HashMap::new();
+    let mut parts: HashMap<&'static str, Vec<String>> = HashMap::new();
+    let mut xfs: HashMap<&'static str, Vec<StyleItem>> = HashMap::new();
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    let mut item: Option<StyleItem> = None;
+    let mut store = |item: StyleItem| match item.section {
+        "cellXfs" | "cellStyleXfs" => xfs.entry(item.section).or_default().push(item),
+        section => {
+            let sig = render_attrs(&item.attrs) + &item.children;
+            parts.entry(section).or_default().push(sig);
+        }
+    };
+
+    let mut buf = Vec::new();
+    loop {
+        let (e, empty) = match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => (e.into_owned(), false),
+            Event::Empty(e) => (e.into_owned(), true),
+            Event::End(_) => {
+                path.pop();
+                match item.take() {
+                    Some(done) if done.depth == path.len() => store(done),
+                    Some(mut open) => {
+                        open.children.push_str("</>");
+                        item = Some(open);
+                    }
+                    None => {}
+                }
+                buf.clear();
+                continue;
+            }
+            Event::Eof => break,
+            _ => {
+                buf.clear();
+                continue;
+            }
+        };
+        buf.clear();
+
+        let name = e.local_name().as_ref().to_vec();
+        let parent = path.last().map(Vec::as_slice);
+        if let Some(open) = item.as_mut() {
+            open.children.push('<');
+            open.children.push_str(&String::from_utf8_lossy(&name));
+            open.children.push_str(&render_attrs(&attributes(&e)?));
+            open.children.push_str(if empty { "/>" } else { ">" });
+        } else if name == b"numFmt" && parent == Some(b"numFmts".as_slice()) {
+            let attrs = attributes(&e)?;
+            let value = |key: &str| attrs.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+            if let (Some(id), Some(code)) = (value("numFmtId"), value("formatCode")) {
+                num_fmts.insert(id, code);
+            }
+        } else if let Some(section) = parent.and_then(|parent| item_section(&name, parent)) {
+            let opened = StyleItem {
+                section,
+                depth: path.len(),
+                attrs: attributes(&e)?,
+                children: String::new(),
+            };
+            if empty {
+                store(opened);
+            } else {
+                item = Some(opened);
+            }
+        }
+        if !empty {
+            path.push(name);
+        }
+    }
+
+    // Cell style xfs are resolved first so cell xfs can inline them by xfId.
+    for section in ["cellStyleXfs", "cellXfs"] {
+        let sigs = xfs
+            .remove(section)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|xf| {
+                let attrs: Vec<(String, String)> = xf
+                    .attrs
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let part = |section: &str| {
+                            value
+                                .parse::<usize>()
+                                .ok()
+                                .and_then(|idx| parts.get(section)?.get(idx).cloned())
+                                .unwrap_or_default()
+                        };
+                        let value = match key.as_str() {
+                            "numFmtId" => match num_fmts.get(&value) {
+                                Some(code) => format!("code:{code}"),
+                                None => format!("builtin:{value}"),
+                            },
+                            "fontId" => part("fonts"),
+                            "fillId" => part("fills"),
+                            "borderId" => part("borders"),
+                            "xfId" => part("cellStyleXfs"),
+                            _ => value.clone(),
+                        };
+                        (key, value)
+                    })
+                    .collect();
+                render_attrs(&attrs) + &xf.children
+            })
+            .collect();
+        parts.insert(section, sigs);
+    }
+    Ok(parts.remove("cellXfs").unwrap_or_default())
+}
+
+/// Top-level style collection an element is an item of, if any.
+fn item_section(name: &[u8], parent: &[u8]) -> Option<&'static str> {
+    match (name, parent) {
+        (b"font", b"fonts") => Some("fonts"),
+        (b"fill", b"fills") => Some("fills"),
+        (b"border", b"borders") => Some("borders"),
+        (b"xf", b"cellXfs") => Some("cellXfs"),
+        (b"xf", b"cellStyleXfs") => Some("cellStyleXfs"),
+        _ => None,
+    }
+}
+
+fn attributes(e: &BytesStart) -> Result<Vec<(String, String)>> {
+    let mut attrs = Vec::new();
+    for attr in e.attributes() {
+        let attr = attr?;
+        attrs.push((
+            String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string(),
+            attr.unescape_value()?.to_string(),
+        ));
+    }
+    attrs.sort();
+    Ok(attrs)
+}
+
+fn render_attrs(attrs: &[(String, String)]) -> String {
+    attrs
+        .iter()
+        .map(|(key, value)| format!(" {key}={value:?}"))
+        .collect()
+}
+
+/// Whether some cell of a sheet present in both workbooks may resolve to a
+/// different style. Never misses a change `diff_sheet_styles` would report,
+/// but may flag a sheet that reports none.
+pub fn cell_styles_may_differ(
+    base: &HashMap<(u32, u32), u32>,
+    base_xfs: &[String],
+    fork: &HashMap<(u32, u32), u32>,
+    fork_xfs: &[String],
+) -> bool {
+    let base_default = majority_signature(base, base_xfs);
+    let fork_default = majority_signature(fork, fork_xfs);
+
+    let changed = |new: Option<&str>, old: Option<&str>| new.is_none() || new != old;
+    fork.iter().any(|(pos, xf)| {
+        let new = xf_signature(fork_xfs, *xf);
+        match base.get(pos) {
+            Some(old) => changed(new, xf_signature(base_xfs, *old)),
+            None => changed(new, base_default),
+        }
+    }) || base.iter().any(|(pos, xf)| {
+        !fork.contains_key(pos) && changed(xf_signature(base_xfs, *xf), fork_default)
+    })
+}
+
+fn xf_signature(xfs: &[String], xf: u32) -> Option<&str> {
+    xfs.get(xf as usize).map(String::as_str)
+}
+
+/// The signature held by more than half of the cells. Cells on one side only
+/// are compared against the other side's most common style, and only a strict
+/// majority is certain to be that style.
+fn majority_signature<'a>(cells: &HashMap<(u32, u32), u32>, xfs: &'a [String]) -> Option<&'a str> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for xf in cells.values() {
+        if let Some(sig) = xf_signature(xfs, *xf) {
+            *counts.entry(sig).or_default() += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .find(|(_, count)| count * 2 > cells.len())
+        .map(|(sig, _)| sig)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_cells_by_style_transition() {
+        let base = umya_spreadsheet::new_file();
+        let mut fork = umya_spreadsheet::new_file();
+        {
+            let sheet = fork.get_sheet_mut(&0).unwrap();
+            for addr in ["A1", "B1", "C1", "A2", "B2", "C2"] {
+                sheet.get_style_mut(addr).get_font_mut().set_bold(true);
+            }
+            sheet
+                .get_style_mut("E5")
+                .get_number_format_mut()
+                .set_format_code("0.00%");
+        }
+
+        let diffs = diff_sheet_styles(
+            "Sheet1",
+            base.get_sheet(&0).unwrap(),
+            fork.get_sheet(&0).unwrap(),
+        );
+        assert_eq!(diffs.len(), 2);
+        let bold = diffs
+            .iter()
+            .find_map(|d| match d {
+                StyleDiff::StyleChanged {
+                    ranges,
+                    cell_count,
+                    new_style,
+                    ..
+                } if new_style.font.is_some() => Some((ranges, *cell_count)),
+                _ => None,
+            })
+            .expect("bold change");
+        assert_eq!(bold.0, &vec!["A1:C2".to_string()]);
+        assert_eq!(bold.1, 6);
+    }
+
+    fn xf_signatures(styles_xml: &str) -> Vec<String> {
+        parse_xf_signatures(&mut Reader::from_str(styles_xml)).unwrap()
+    }
+
+    const STYLES: &str = r#"<styleSheet>
+        <numFmts count="1"><numFmt numFmtId="164" formatCode="0.0%"/></numFmts>
+        <fonts count="2"><font><sz val="11"/></font><font><b/><sz val="11"/></font></fonts>
+        <fills count="1"><fill><patternFill patternType="none"/></fill></fills>
+        <borders count="1"><border><left/><right/></border></borders>
+        <cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs>
+        <cellXfs count="3">
+            <xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/>
+            <xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/>
+            <xf numFmtId="164" fontId="0" fillId="0" borderId="0" xfId="0"><alignment horizontal="center"/></xf>
+        </cellXfs>
+        <dxfs count="1"><dxf><font><i/></font></dxf></dxfs>
+    </styleSheet>"#;
+
+    // Same styles, with fonts, number formats and xfs renumbered.
+    const RENUMBERED: &str = r#"<styleSheet>
+        <numFmts count="1"><numFmt numFmtId="170" formatCode="0.0%"/></numFmts>
+        <fonts count="2"><font><b/><sz val="11"/></font><font><sz val="11"/></font></fonts>
+        <fills count="1"><fill><patternFill patternType="none"/></fill></fills>
+        <borders count="1"><border><left/><right/></border></borders>
+        <cellStyleXfs count="1"><xf numFmtId="0" fontId="1" fillId="0" borderId="0"/></cellStyleXfs>
+        <cellXfs count="3">
+            <xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0"/>
+            <xf numFmtId="170" fontId="1" fillId="0" borderId="0" xfId="0"><alignment horizontal="center"/></xf>
+            <xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0" applyFont="1"/>
+        </cellXfs>
+    </styleSheet>"#;
+
+    #[test]
+    fn xf_signatures_resolve_through_renumbering() {
+        let base = xf_signatures(STYLES);
+        let fork = xf_signatures(RENUMBERED);
+        assert_eq!(base.len(), 3);
+        assert_eq!(fork.len(), 3);
+        assert_eq!(base[1], fork[2]);
+        assert_eq!(base[2], fork[1]);
+        assert_ne!(base[0], base[1]);
+        assert_ne!(base[0], base[2]);
+        assert!(base[2].contains("0.0%"), "{}", base[2]);
+    }
+
+    #[test]
+    fn only_resolved_style_changes_need_umya() {
+        let base_xfs = xf_signatures(STYLES);
+        let fork_xfs = xf_signatures(RENUMBERED);
+        let base: HashMap<(u32, u32), u32> = [((1, 1), 0), ((1, 2), 0), ((2, 1), 1)].into();
+
+        // Renumbered xfs, same resolved styles, plus a new plain cell.
+        let mut fork: HashMap<(u32, u32), u32> = [((1, 1), 0), ((1, 2), 0), ((2, 1), 2)].into();
+        fork.insert((3, 1), 0);
+        assert!(!cell_styles_may_differ(&base, &base_xfs, &fork, &fork_xfs));
+
+        // A1 turns bold.
+        fork.insert((1, 1), 2);
+        assert!(cell_styles_may_differ(&base, &base_xfs, &fork, &fork_xfs));
+
+        // A new cell that differs from the base's most common style.
+        fork.insert((1, 1), 0);
+        fork.insert((3, 1), 1);
+        assert!(cell_styles_may_differ(&base, &base_xfs, &fork, &fork_xfs));
+
+        // Dropping a bold cell from the fork compares it against the plain default.
+        fork.remove(&(3, 1));
+        fork.remove(&(2, 1));
+        assert!(cell_styles_may_differ(&base, &base_xfs, &fork, &fork_xfs));
+    }
+
+    #[test]
+    fn cell_xf_ids_include_empty_styled_cells() {
+        let sheet = r#"<worksheet><sheetData><row r="1">
+            <c r="A1" s="2"><v>1</v></c><c r="B1" s="1"/><c r="C1"><v>3</v></c>
+        </row></sheetData></worksheet>"#;
+        let cells = parse_cell_xf_ids(&mut Reader::from_str(sheet)).unwrap();
+        assert_eq!(cells, [((1, 1), 2), ((1, 2), 1), ((1, 3), 0)].into());
+    }
+}
@@ -6,6 +6,7 @@
 use crate::dod::check::*;
 use crate::dod::profile::*;
 use crate::dod::types::*;
+use crate::progress::{CancelledError, ProgressReporter};
 use anyhow::{Context, Result};
 use futures::future;
 use std::collections::{HashMap, HashSet, VecDeque};
@@ -29,6 +30,17 @@ impl CheckExecutor {
 
     /// Execute all enabled checks in dependency order
     pub async fn execute_all(&self, context: &CheckContext) -> Result<Vec<DodCheckResult>> {
+        self.execute_all_with_progress(context, &ProgressReporter::none())
+            .await
+    }
+
+    /// Execute all enabled checks, reporting progress after each check and
+    /// stopping between batches (or abandoning running checks) on cancellation
+    pub async fn execute_all_with_progress(
+        &self,
+        context: &CheckContext,
+        progress: &ProgressReporter,
+    ) -> Result<Vec<DodCheckResult>> {
         let start = Instant::now();
 
         // Get enabled checks based on profile
@@ -50,7 +62,7 @@ impl CheckExecutor {
 
         // Execute in topological order with parallelism
         let results = self
-            .execute_with_dependencies(context, &enabled_checks, &dep_graph)
+            .execute_with_dependencies(context, &enabled_checks, &dep_graph, progress)
             .await?;
 
         let duration_ms = start.elapsed().as_millis();
@@ -127,6 +139,7 @@ impl CheckExecutor {
         context: &CheckContext,
         checks: &[&Box<dyn DodCheck>],
         dep_graph: &HashMap<String, Vec<String>>,
+        progress: &ProgressReporter,
     ) -> Result<Vec<DodCheckResult>> {
         let mut results = HashMap::new();
         let mut completed: HashSet<String> = HashSet::new();
@@ -144,6 +157,7 @@ impl CheckExecutor {
 
         // Execute in waves based on dependencies
         while !ready_queue.is_empty() {
+            progress.check_cancelled()?;
             let current_batch: Vec<&str> = ready_queue.drain(..).collect();
 
             tracing::debug!(
@@ -155,11 +169,11 @@ impl CheckExecutor {
             // Execute batch based on parallelism config
             let batch_results = match self.profile.parallelism {
                 ParallelismConfig::Serial => {
-                    self.execute_batch_serial(context, &current_batch, &check_map)
+                    self.execute_batch_serial(context, &current_batch, &check_map, progress)
                         .await?
                 }
                 ParallelismConfig::Auto | ParallelismConfig::Parallel(_) => {
-                    self.execute_batch_parallel(context, &current_batch, &check_map)
+                    self.execute_batch_parallel(context, &current_batch, &check_map, progress)
                         .await?
                 }
             };
@@ -167,6 +181,11 @@ impl CheckExecutor {
             // Collect results and update completed set
             for (check_id, result) in batch_results {
                 completed.insert(check_id.clone());
+                progress.report(
+                    completed.len(),
+                    checks.len(),
+                    format!("{}: {:?}", check_id, result.status),
+                );
                 results.insert(check_id, result);
             }
 
@@ -201,12 +220,16 @@ impl CheckExecutor {
         context: &CheckContext,
         batch: &[&str],
         check_map: &HashMap<&str, &Box<dyn DodCheck>>,
+        progress: &ProgressReporter,
     ) -> Result<Vec<(String, DodCheckResult)>> {
         let mut batch_results = vec![];
 
         for check_id in batch {
+            progress.check_cancelled()?;
             if let Some(check) = check_map.get(check_id) {
-                let result = self.execute_check_with_timeout(context, check).await?;
+                let result = self
+                    .execute_check_with_timeout(context, check, progress)
+                    .await?;
                 batch_results.push((check_id.to_string(), result));
             }
         }
@@ -220,6 +243,7 @@ impl CheckExecutor {
         context: &CheckContext,
         batch: &[&str],
         check_map: &HashMap<&str, &Box<dyn DodCheck>>,
+        progress: &ProgressReporter,
     ) -> Result<Vec<(String, DodCheckResult)>> {
         // Use futures::future::join_all instead of spawning tasks
         // to avoid lifetime issues with trait objects
@@ -230,7 +254,9 @@ impl CheckExecutor {
                     let check_id = check_id.to_string();
                     let context = context.clone();
                     async move {
-                        let result = self.execute_check_with_timeout(&context, check).await;
+                        let result = self
+                            .execute_check_with_timeout(&context, check, progress)
+                            .await;
                         (check_id, result)
                     }
                 })
@@ -251,11 +277,19 @@ impl CheckExecutor {
         &self,
         context: &CheckContext,
         check: &Box<dyn DodCheck>,
+        progress: &ProgressReporter,
     ) -> Result<DodCheckResult> {
         let timeout_ms = self.profile.get_timeout(check.category());
         let timeout = std::time::Duration::from_millis(timeout_ms);
 
-        match tokio::time::timeout(timeout, check.execute(context)).await {
+        // Checks only inspect the workspace, so a cancelled check is dropped
+        // without anything to undo.
+        let outcome = tokio::select! {
+            outcome = tokio::time::timeout(timeout, check.execute(context)) => outcome,
+            _ = progress.cancelled() => return Err(CancelledError.into()),
+        };
+
+        match outcome {
             Ok(result) => result,
             Err(_) => {
                 // Timeout occurred
@@ -76,6 +76,8 @@ pub enum ErrorCode {
     FormulaParseError = -32019,
     /// Entitlement required for capability
     EntitlementRequired = -32020,
+    /// Request cancelled by the client
+    RequestCancelled = -32021,
 }
 
 impl ErrorCode {
@@ -115,6 +117,7 @@ impl ErrorCode {
                 "validation_error"
             }
             ErrorCode::EntitlementRequired => "entitlement_error",
+            ErrorCode::RequestCancelled => "cancelled",
             ErrorCode::ResourceExhausted | ErrorCode::ResponseTooLarge => "resource_limit",
             ErrorCode::VbaError | ErrorCode::SparqlError | ErrorCode::TemplateError => {
                 "subsystem_error"
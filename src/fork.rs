@@ -12,11 +12,13 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
 use tracing::{debug, warn};
 
+mod store;
+
 const FORK_DIR: &str = "/tmp/mcp-forks";
 const CHECKPOINT_DIR: &str = "/tmp/mcp-checkpoints";
-#[allow(dead_code)]
 const STAGED_SNAPSHOT_DIR: &str = "/tmp/mcp-staged";
 const DEFAULT_TTL_SECS: u64 = 0;
 const DEFAULT_MAX_FORKS: usize = 10;
@@ -25,6 +27,9 @@ const MAX_FILE_SIZE: u64 = 100 * 1024 * 1024; // 100MB
 const DEFAULT_MAX_CHECKPOINTS_PER_FORK: usize = 10;
 const DEFAULT_MAX_STAGED_CHANGES_PER_FORK: usize = 20;
 const DEFAULT_MAX_CHECKPOINT_TOTAL_BYTES: u64 = 500 * 1024 * 1024;
+const CHANGE_CHANNEL_CAPACITY: usize = 256;
+/// How often a durable store rewrites a manifest just to record a read.
+const ACCESS_PERSIST_INTERVAL: Duration = Duration::from_secs(60);
 
 /// RAII guard for temporary files - ensures cleanup on drop
 #[derive(Debug)]
@@ -94,6 +99,7 @@ impl<'a> Drop for ForkCreationGuard<'a> {
             warn!(fork_id = %self.fork_id, "rolling back failed fork creation");
             // Remove from registry if present
             let _ = self.registry.forks.write().remove(&self.fork_id);
+            self.registry.forget(&self.fork_id);
             // Clean up work file
             let _ = fs::remove_file(&self.work_path);
         }
@@ -152,7 +158,7 @@ pub struct ChangeSummary {
     pub warnings: Vec<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StagedChange {
     pub change_id: String,
     pub created_at: DateTime<Utc>,
@@ -162,7 +168,7 @@ pub struct StagedChange {
     pub fork_path_snapshot: Option<PathBuf>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Checkpoint {
     pub checkpoint_id: String,
     pub created_at: DateTime<Utc>,
@@ -178,9 +184,14 @@ pub struct ForkContext {
     pub work_path: PathBuf,
     pub created_at: Instant,
     pub last_accessed: Mutex<Instant>,
+    /// Last access time written to the durable store's manifest, if known.
+    access_persisted: Mutex<Option<Instant>>,
     pub edits: Vec<EditOp>,
     pub staged_changes: Vec<StagedChange>,
     pub checkpoints: Vec<Checkpoint>,
+    /// xlsx conversion of a non-xlsx base (e.g. `.ods`), used as the diff baseline.
+    pub converted_base: Option<PathBuf>,
+    checkpoint_root: PathBuf,
     base_hash: String,
     base_modified: std::time::SystemTime,
     /// Version counter for optimistic locking - incremented on each modification
@@ -188,7 +199,12 @@ pub struct ForkContext {
 }
 
 impl ForkContext {
-    fn new(fork_id: String, base_path: PathBuf, work_path: PathBuf) -> Result<Self> {
+    fn new(
+        fork_id: String,
+        base_path: PathBuf,
+        work_path: PathBuf,
+        checkpoint_root: PathBuf,
+    ) -> Result<Self> {
         let metadata = fs::metadata(&base_path)?;
         let base_modified = metadata.modified()?;
         let base_hash = hash_file(&base_path)?;
@@ -199,9 +215,12 @@ impl ForkContext {
             work_path,
             created_at: Instant::now(),
             last_accessed: Mutex::new(Instant::now()),
+            access_persisted: Mutex::new(Some(Instant::now())),
             edits: Vec::new(),
             staged_changes: Vec::new(),
             checkpoints: Vec::new(),
+            converted_base: None,
+            checkpoint_root,
             base_hash,
             base_modified,
             version: AtomicU64::new(0),
@@ -219,6 +238,20 @@ impl ForkContext {
         *self.last_accessed.lock() = Instant::now();
     }
 
+    /// Claim the next write of the access time to the store: true at most
+    /// once per `ACCESS_PERSIST_INTERVAL` of recorded access.
+    fn claim_access_persist(&self) -> bool {
+        let last_accessed = *self.last_accessed.lock();
+        let mut persisted = self.access_persisted.lock();
+        if persisted
+            .is_some_and(|at| last_accessed.saturating_duration_since(at) < ACCESS_PERSIST_INTERVAL)
+        {
+            return false;
+        }
+        *persisted = Some(last_accessed);
+        true
+    }
+
     /// Get current version for optimistic locking
     pub fn version(&self) -> u64 {
         self.version.load(Ordering::SeqCst)
@@ -258,22 +291,74 @@ impl ForkContext {
         Ok(())
     }
 
+    /// SHA-256 of the base file taken when the fork was created.
+    pub fn base_hash(&self) -> &str {
+        &self.base_hash
+    }
+
+    /// Workbook the fork's changes are measured against: the base itself, or
+    /// its xlsx conversion when the base is in another format.
+    pub fn diff_base_path(&self) -> &Path {
+        self.converted_base.as_deref().unwrap_or(&self.base_path)
+    }
+
     fn checkpoint_dir(&self) -> PathBuf {
-        PathBuf::from(CHECKPOINT_DIR).join(&self.fork_id)
+        self.checkpoint_root.join(&self.fork_id)
     }
 
     fn cleanup_files(&self) {
         let _ = fs::remove_file(&self.work_path);
+        if let Some(converted) = self.converted_base.as_ref() {
+            let _ = fs::remove_file(converted);
+        }
         for staged in &self.staged_changes {
             remove_staged_snapshot(staged);
         }
         let checkpoint_dir = self.checkpoint_dir();
-        if checkpoint_dir.starts_with(CHECKPOINT_DIR) {
+        if checkpoint_dir.starts_with(&self.checkpoint_root) {
             let _ = fs::remove_dir_all(&checkpoint_dir);
         }
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+enum SaveFormat {
+    Xlsx,
+    Ods,
+    Delimited(u8),
+}
+
+fn write_fork_output(
+    work_path: &Path,
+    target_path: &Path,
+    format: SaveFormat,
+    sheet_name: Option<&str>,
+) -> Result<()> {
+    let read_work = || {
+        umya_spreadsheet::reader::xlsx::read(work_path)
+            .map_err(|e| anyhow!("failed to read fork work file: {}", e))
+    };
+    match format {
+        SaveFormat::Xlsx => {
+            fs::copy(work_path, target_path)?;
+            Ok(())
+        }
+        SaveFormat::Ods => crate::backends::ods::write(&read_work()?, target_path),
+        SaveFormat::Delimited(delimiter) => {
+            let book = read_work()?;
+            let sheet = match sheet_name {
+                Some(name) => book
+                    .get_sheet_by_name(name)
+                    .ok_or_else(|| anyhow!("sheet '{}' not found in fork", name))?,
+                None => book
+                    .get_sheet(&0)
+                    .ok_or_else(|| anyhow!("fork has no sheets"))?,
+            };
+            crate::backends::delimited::write_sheet(sheet, target_path, delimiter)
+        }
+    }
+}
+
 fn hash_file(path: &Path) -> Result<String> {
     let contents = fs::read(path)?;
     let mut hasher = Sha256::new();
@@ -286,6 +371,11 @@ pub struct ForkConfig {
     pub ttl: Duration,
     pub max_forks: usize,
     pub fork_dir: PathBuf,
+    /// Treat `fork_dir` as a durable store: keep a manifest per fork next to
+    /// its work file, put checkpoints under `fork_dir/checkpoints` and staged
+    /// snapshots under `fork_dir/staged`, and rebuild the registry from the
+    /// manifests at startup.
+    pub durable: bool,
 }
 
 impl Default for ForkConfig {
@@ -294,6 +384,7 @@ impl Default for ForkConfig {
             ttl: Duration::from_secs(DEFAULT_TTL_SECS),
             max_forks: DEFAULT_MAX_FORKS,
             fork_dir: PathBuf::from(FORK_DIR),
+            durable: false,
         }
     }
 }
@@ -304,18 +395,104 @@ pub struct ForkRegistry {
     forks: RwLock<HashMap<String, ForkContext>>,
     /// Per-fork locks for recalc operations to prevent concurrent recalc on same fork
     recalc_locks: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+    /// Ids of forks whose version was bumped or that were discarded.
+    changes: broadcast::Sender<String>,
     config: ForkConfig,
 }
 
 impl ForkRegistry {
     pub fn new(config: ForkConfig) -> Result<Self> {
         fs::create_dir_all(&config.fork_dir)?;
-        fs::create_dir_all(CHECKPOINT_DIR)?;
-        Ok(Self {
+        let registry = Self {
             forks: RwLock::new(HashMap::new()),
             recalc_locks: Mutex::new(HashMap::new()),
+            changes: broadcast::channel(CHANGE_CHANNEL_CAPACITY).0,
             config,
-        })
+        };
+        fs::create_dir_all(registry.checkpoint_root())?;
+        if registry.config.durable {
+            registry.restore_from_store()?;
+        }
+        Ok(registry)
+    }
+
+    fn checkpoint_root(&self) -> PathBuf {
+        if self.config.durable {
+            self.config.fork_dir.join("checkpoints")
+        } else {
+            PathBuf::from(CHECKPOINT_DIR)
+        }
+    }
+
+    fn staged_root(&self) -> PathBuf {
+        if self.config.durable {
+            self.config.fork_dir.join("staged")
+        } else {
+            PathBuf::from(STAGED_SNAPSHOT_DIR)
+        }
+    }
+
+    /// Path for a staged change's snapshot of a fork, with its directory created.
+    pub fn staged_snapshot_path(&self, fork_id: &str, change_id: &str) -> Result<PathBuf> {
+        let dir = self.staged_root();
+        fs::create_dir_all(&dir)?;
+        Ok(dir.join(format!("{fork_id}_{change_id}.xlsx")))
+    }
+
+    /// Rebuild forks from their manifests. Forks whose work file is gone are
+    /// forgotten; forks idle past the TTL are cleaned up as if evicted.
+    fn restore_from_store(&self) -> Result<()> {
+        let mut forks = self.forks.write();
+        for manifest in store::load_manifests(&self.config.fork_dir)? {
+            let fork_id = manifest.fork_id().to_string();
+            if !manifest.work_path().exists() {
+                warn!(fork_id = %fork_id, "fork work file missing, dropping manifest");
+                store::remove_manifest(&self.config.fork_dir, &fork_id);
+                continue;
+            }
+            let expired = !self.config.ttl.is_zero() && manifest.idle_for() > self.config.ttl;
+            let ctx = manifest.into_context(self.checkpoint_root());
+            if expired {
+                ctx.cleanup_files();
+                store::remove_manifest(&self.config.fork_dir, &fork_id);
+                debug!(fork_id = %fork_id, "evicted expired fork from store");
+                continue;
+            }
+            debug!(fork_id = %fork_id, version = ctx.version(), "restored fork from store");
+            forks.insert(fork_id, ctx);
+        }
+        drop(forks);
+        self.update_fork_metrics();
+        Ok(())
+    }
+
+    /// Write the fork's manifest when the store is durable. Failures are
+    /// logged rather than returned: the in-memory change already happened.
+    fn persist(&self, ctx: &ForkContext) {
+        if !self.config.durable {
+            return;
+        }
+        let accessed = *ctx.last_accessed.lock();
+        *ctx.access_persisted.lock() = Some(accessed);
+        if let Err(e) = store::write_manifest(&self.config.fork_dir, ctx) {
+            warn!(fork_id = %ctx.fork_id, error = %e, "failed to persist fork manifest");
+        }
+    }
+
+    /// Record a read of the fork. Durable stores also write the access time
+    /// to the manifest (throttled), so the TTL applied at startup counts reads
+    /// as well as edits.
+    fn touch(&self, ctx: &ForkContext) {
+        ctx.touch();
+        if self.config.durable && ctx.claim_access_persist() {
+            self.persist(ctx);
+        }
+    }
+
+    fn forget(&self, fork_id: &str) {
+        if self.config.durable {
+            store::remove_manifest(&self.config.fork_dir, fork_id);
+        }
     }
 
     pub fn start_cleanup_task(self: Arc<Self>) {
@@ -371,12 +548,16 @@ impl ForkRegistry {
             .and_then(|e| e.to_str())
             .map(|e| e.to_ascii_lowercase());
 
-        if ext.as_deref() != Some("xlsx") {
-            return Err(anyhow!(
-                "only .xlsx files supported for fork/recalc (got {:?})",
-                ext
-            ));
-        }
+        let needs_conversion = match ext.as_deref() {
+            Some("xlsx") => false,
+            Some("ods") | Some("csv") | Some("tsv") => true,
+            _ => {
+                return Err(anyhow!(
+                    "only .xlsx, .ods, .csv and .tsv files supported for fork/recalc (got {:?})",
+                    ext
+                ));
+            }
+        };
 
         if !base_path.starts_with(workspace_root) {
             return Err(anyhow!("base path must be within workspace root"));
@@ -416,11 +597,29 @@ impl ForkRegistry {
         // Create RAII guard for rollback on error
         let guard = ForkCreationGuard::new(fork_id.clone(), work_path.clone(), self);
 
-        // Copy file
-        fs::copy(base_path, &work_path)?;
+        // Copy file (non-xlsx bases are converted so edits and recalc run on xlsx)
+        let converted_base = if needs_conversion {
+            let book = crate::backends::read_spreadsheet(base_path)?;
+            let converted = self.config.fork_dir.join(format!("{}.base.xlsx", fork_id));
+            umya_spreadsheet::writer::xlsx::write(&book, &converted)
+                .map_err(|e| anyhow!("failed to convert base workbook: {}", e))?;
+            let converted_guard = TempFileGuard::new(converted);
+            fs::copy(converted_guard.path(), &work_path)?;
+            Some(converted_guard)
+        } else {
+            fs::copy(base_path, &work_path)?;
+            None
+        };
 
         // Create context
-        let context = ForkContext::new(fork_id.clone(), base_path.to_path_buf(), work_path)?;
+        let mut context = ForkContext::new(
+            fork_id.clone(),
+            base_path.to_path_buf(),
+            work_path,
+            self.checkpoint_root(),
+        )?;
+        context.converted_base = converted_base.map(TempFileGuard::disarm);
+        self.persist(&context);
 
         // Insert with write lock
         self.forks.write().insert(fork_id.clone(), context);
@@ -442,14 +641,14 @@ impl ForkRegistry {
         let ctx = forks
             .get(fork_id)
             .ok_or_else(|| anyhow!("fork not found: {}", fork_id))?;
-        ctx.touch();
+        self.touch(ctx);
         Ok(Arc::new(ctx.clone()))
     }
 
     pub fn get_fork_path(&self, fork_id: &str) -> Option<PathBuf> {
         let forks = self.forks.read();
         if let Some(ctx) = forks.get(fork_id) {
-            ctx.touch();
+            self.touch(ctx);
             return Some(ctx.work_path.clone());
         }
         None
@@ -468,6 +667,8 @@ impl ForkRegistry {
         ctx.touch();
         let result = f(ctx)?;
         ctx.increment_version();
+        self.persist(ctx);
+        self.publish_change(fork_id);
         Ok(result)
     }
 
@@ -489,13 +690,33 @@ impl ForkRegistry {
         ctx.touch();
         let result = f(ctx)?;
         ctx.increment_version();
+        self.persist(ctx);
+        self.publish_change(fork_id);
         Ok(result)
     }
 
+    /// Bump the version of a fork whose work file was edited in place, so
+    /// change subscribers hear about it.
+    pub fn mark_modified(&self, fork_id: &str) -> Result<u64> {
+        self.with_fork_mut(fork_id, |ctx| Ok(ctx.version() + 1))
+    }
+
+    /// Receive the id of every fork that changes version or is discarded.
+    pub fn subscribe_changes(&self) -> broadcast::Receiver<String> {
+        self.changes.subscribe()
+    }
+
+    fn publish_change(&self, fork_id: &str) {
+        // No receivers is the common case and not an error.
+        let _ = self.changes.send(fork_id.to_string());
+    }
+
     pub fn discard_fork(&self, fork_id: &str) -> Result<()> {
         let mut forks = self.forks.write();
         if let Some(ctx) = forks.remove(fork_id) {
             ctx.cleanup_files();
+            self.forget(fork_id);
+            self.publish_change(fork_id);
             debug!(fork_id = %fork_id, "discarded fork");
         }
         // Clean up recalc lock
@@ -512,6 +733,20 @@ impl ForkRegistry {
         target_path: &Path,
         workspace_root: &Path,
         drop_fork: bool,
+    ) -> Result<()> {
+        self.save_fork_as(fork_id, target_path, workspace_root, drop_fork, None)
+    }
+
+    /// Save a fork, choosing the output format from the target extension.
+    /// `.csv`/`.tsv` targets export a single sheet (`sheet_name`, default first);
+    /// other targets reject `sheet_name`.
+    pub fn save_fork_as(
+        &self,
+        fork_id: &str,
+        target_path: &Path,
+        workspace_root: &Path,
+        drop_fork: bool,
+        sheet_name: Option<&str>,
     ) -> Result<()> {
         if !target_path.starts_with(workspace_root) {
             return Err(anyhow!("target path must be within workspace root"));
@@ -522,13 +757,24 @@ impl ForkRegistry {
             .and_then(|e| e.to_str())
             .map(|e| e.to_ascii_lowercase());
 
-        if ext.as_deref() != Some("xlsx") {
-            return Err(anyhow!("target must be .xlsx"));
+        let format = match ext.as_deref() {
+            Some("xlsx") => SaveFormat::Xlsx,
+            Some("ods") => SaveFormat::Ods,
+            Some("csv") | Some("tsv") => {
+                SaveFormat::Delimited(crate::backends::delimited::default_delimiter(target_path))
+            }
+            _ => return Err(anyhow!("target must be .xlsx, .ods, .csv or .tsv")),
+        };
+        if sheet_name.is_some() && !matches!(format, SaveFormat::Delimited(_)) {
+            return Err(anyhow!(
+                "sheet_name only applies to .csv and .tsv targets; .xlsx and .ods save every sheet"
+            ));
         }
 
         // Create backup of target if it exists (for rollback on error)
         let backup_guard = if target_path.exists() {
-            let backup = target_path.with_extension("backup.xlsx");
+            let backup =
+                target_path.with_extension(format!("backup.{}", ext.as_deref().unwrap_or("xlsx")));
             if let Ok(_) = fs::copy(target_path, &backup) {
                 Some(TempFileGuard::new(backup))
             } else {
@@ -555,7 +801,7 @@ impl ForkRegistry {
         }
 
         // Attempt to save
-        let save_result = fs::copy(&ctx.work_path, target_path);
+        let save_result = write_fork_output(&ctx.work_path, target_path, format, sheet_name);
 
         if let Err(e) = save_result {
             // Rollback: restore backup if it exists
@@ -574,6 +820,7 @@ impl ForkRegistry {
         if drop_fork {
             if let Some(ctx) = forks.remove(fork_id) {
                 ctx.cleanup_files();
+                self.forget(fork_id);
                 debug!(fork_id = %fork_id, "saved and discarded fork");
             }
             // Clean up recalc lock
@@ -599,7 +846,7 @@ impl ForkRegistry {
         forks
             .values()
             .map(|ctx| {
-                ctx.touch(); // Update last_accessed
+                self.touch(ctx);
                 ForkInfo {
                     fork_id: ctx.fork_id.clone(),
                     base_path: ctx.base_path.display().to_string(),
@@ -623,7 +870,7 @@ impl ForkRegistry {
         };
 
         let checkpoint_id = make_short_random_id("cp", 12);
-        let dir = PathBuf::from(CHECKPOINT_DIR).join(fork_id);
+        let dir = self.checkpoint_root().join(fork_id);
         fs::create_dir_all(&dir)?;
         let snapshot_path = dir.join(format!("{}.xlsx", checkpoint_id));
 
@@ -823,6 +1070,7 @@ impl ForkRegistry {
         for id in expired {
             if let Some(ctx) = forks.remove(&id) {
                 ctx.cleanup_files();
+                self.forget(&id);
                 debug!(fork_id = %id, "evicted expired fork");
             }
             // Clean up recalc lock
@@ -888,9 +1136,12 @@ impl Clone for ForkContext {
             work_path: self.work_path.clone(),
             created_at: self.created_at,
             last_accessed: Mutex::new(*self.last_accessed.lock()),
+            access_persisted: Mutex::new(*self.access_persisted.lock()),
             edits: self.edits.clone(),
             staged_changes: self.staged_changes.clone(),
             checkpoints: self.checkpoints.clone(),
+            converted_base: self.converted_base.clone(),
+            checkpoint_root: self.checkpoint_root.clone(),
             base_hash: self.base_hash.clone(),
             base_modified: self.base_modified,
             version: AtomicU64::new(self.version.load(Ordering::SeqCst)),
@@ -898,10 +1149,15 @@ impl Clone for ForkContext {
     }
 }
 
-impl Drop for ForkContext {
+impl Drop for ForkRegistry {
     fn drop(&mut self) {
-        debug!(fork_id = %self.fork_id, "fork context dropped, cleaning up files");
-        self.cleanup_files();
+        if self.config.durable {
+            return;
+        }
+        for ctx in self.forks.get_mut().values() {
+            debug!(fork_id = %ctx.fork_id, "registry dropped, cleaning up fork files");
+            ctx.cleanup_files();
+        }
     }
 }
 
@@ -0,0 +1,179 @@
+//! On-disk manifests for the durable fork store.
+//!
+//! Each fork gets `<fork_dir>/<fork_id>.json` describing everything the
+//! registry keeps in memory. Instants do not survive a restart, so creation
+//! and last-access times are stored as wall-clock timestamps and converted
+//! back when the registry is rebuilt.
+
+use super::{Checkpoint, EditOp, ForkContext, StagedChange};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicU64;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::warn;
+
+const MANIFEST_FORMAT: u32 = 1;
+const MANIFEST_EXT: &str = "json";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct ForkManifest {
+    format: u32,
+    fork_id: String,
+    base_path: PathBuf,
+    base_hash: String,
+    base_modified: SystemTime,
+    work_path: PathBuf,
+    converted_base: Option<PathBuf>,
+    version: u64,
+    created_at: DateTime<Utc>,
+    last_accessed: DateTime<Utc>,
+    edits: Vec<EditOp>,
+    checkpoints: Vec<Checkpoint>,
+    staged_changes: Vec<StagedChange>,
+}
+
+impl ForkManifest {
+    pub(super) fn from_context(ctx: &ForkContext) -> Self {
+        Self {
+            format: MANIFEST_FORMAT,
+            fork_id: ctx.fork_id.clone(),
+            base_path: ctx.base_path.clone(),
+            base_hash: ctx.base_hash.clone(),
+            base_modified: ctx.base_modified,
+            work_path: ctx.work_path.clone(),
+            converted_base: ctx.converted_base.clone(),
+            version: ctx.version(),
+            created_at: wall_clock(ctx.created_at),
+            last_accessed: wall_clock(*ctx.last_accessed.lock()),
+            edits: ctx.edits.clone(),
+            checkpoints: ctx.checkpoints.clone(),
+            staged_changes: ctx.staged_changes.clone(),
+        }
+    }
+
+    pub(super) fn fork_id(&self) -> &str {
+        &self.fork_id
+    }
+
+    pub(super) fn work_path(&self) -> &Path {
+        &self.work_path
+    }
+
+    /// Time since the last persisted access, measured on the wall clock.
+    pub(super) fn idle_for(&self) -> Duration {
+        (Utc::now() - self.last_accessed)
+            .to_std()
+            .unwrap_or_default()
+    }
+
+    /// Rebuild the in-memory context. Checkpoints whose snapshot is gone are
+    /// dropped, and staged changes lose a snapshot path that no longer exists.
+    pub(super) fn into_context(self, checkpoint_root: PathBuf) -> ForkContext {
+        let checkpoints = self
+            .checkpoints
+            .into_iter()
+            .filter(|cp| {
+                let exists = cp.snapshot_path.exists();
+                if !exists {
+                    warn!(fork_id = %self.fork_id, checkpoint_id = %cp.checkpoint_id, "dropping checkpoint with missing snapshot");
+                }
+                exists
+            })
+            .collect();
+        let staged_changes = self
+            .staged_changes
+            .into_iter()
+            .map(|mut staged| {
+                if staged
+                    .fork_path_snapshot
+                    .as_ref()
+                    .is_some_and(|p| !p.exists())
+                {
+                    staged.fork_path_snapshot = None;
+                }
+                staged
+            })
+            .collect();
+
+        ForkContext {
+            fork_id: self.fork_id,
+            base_path: self.base_path,
+            work_path: self.work_path,
+            created_at: instant_from(self.created_at),
+            last_accessed: Mutex::new(instant_from(self.last_accessed)),
+            // Restored instants are approximate; record the next read.
+            access_persisted: Mutex::new(None),
+            edits: self.edits,
+            staged_changes,
+            checkpoints,
+            converted_base: self.converted_base,
+            checkpoint_root,
+            base_hash: self.base_hash,
+            base_modified: self.base_modified,
+            version: AtomicU64::new(self.version),
+        }
+    }
+}
+
+fn wall_clock(instant: Instant) -> DateTime<Utc> {
+    chrono::Duration::from_std(instant.elapsed())
+        .map(|elapsed| Utc::now() - elapsed)
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn instant_from(timestamp: DateTime<Utc>) -> Instant {
+    let elapsed = (Utc::now() - timestamp).to_std().unwrap_or_default();
+    Instant::now()
+        .checked_sub(elapsed)
+        .unwrap_or_else(Instant::now)
+}
+
+pub(super) fn manifest_path(dir: &Path, fork_id: &str) -> PathBuf {
+    dir.join(format!("{fork_id}.{MANIFEST_EXT}"))
+}
+
+/// Write the manifest via a temp file and rename so a crash never leaves a
+/// half-written manifest behind.
+pub(super) fn write_manifest(dir: &Path, ctx: &ForkContext) -> Result<()> {
+    let path = manifest_path(dir, &ctx.fork_id);
+    let tmp = path.with_extension(format!("{MANIFEST_EXT}.tmp"));
+    let json = serde_json::to_vec_pretty(&ForkManifest::from_context(ctx))?;
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+pub(super) fn remove_manifest(dir: &Path, fork_id: &str) {
+    let _ = fs::remove_file(manifest_path(dir, fork_id));
+}
+
+fn read_manifest(path: &Path) -> Result<ForkManifest> {
+    let manifest: ForkManifest = serde_json::from_slice(&fs::read(path)?)?;
+    if manifest.format != MANIFEST_FORMAT {
+        return Err(anyhow!(
+            "unsupported fork manifest format {}",
+            manifest.format
+        ));
+    }
+    Ok(manifest)
+}
+
+/// Every readable manifest in the store; unreadable ones are logged and skipped.
+pub(super) fn load_manifests(dir: &Path) -> Result<Vec<ForkManifest>> {
+    let mut manifests = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(MANIFEST_EXT) {
+            continue;
+        }
+        match read_manifest(&path) {
+            Ok(manifest) => manifests.push(manifest),
+            Err(e) => warn!(path = ?path, error = %e, "skipping unreadable fork manifest"),
+        }
+    }
+    Ok(manifests)
+}
@@ -302,7 +302,7 @@ fn format_sheet_prefix(sheet: &Option<String>) -> String {
 
 /// Check if a sheet name needs quoting based on Excel rules
 /// Returns true if the name contains special characters or starts with a digit
-fn sheet_name_needs_quoting(name: &str) -> bool {
+pub(crate) fn sheet_name_needs_quoting(name: &str) -> bool {
     if name.is_empty() {
         return false;
     }
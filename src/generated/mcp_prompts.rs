@@ -0,0 +1,420 @@
+// =============================================================================
+// AUTO-GENERATED FILE - DO NOT EDIT MANUALLY
+// Generated by ggen from RDF ontology
+// Regenerate with: ggen sync
+// =============================================================================
+//! MCP Prompt Catalog
+//! Generated from ggen-mcp.ttl - DO NOT EDIT MANUALLY
+//!
+//! Each prompt is a parameterized workflow defined in the ontology with its
+//! arguments and ordered steps. `crate::prompts` lists the prompts whose
+//! tools are enabled and renders the steps with the client's arguments.
+
+/// An argument the client fills in when requesting a prompt.
+#[derive(Debug, Clone, Copy)]
+pub struct PromptArgumentDef {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub required: bool,
+    /// Where completions come from: a `crate::completion::CompletionSource`
+    /// name such as `workbook_id`, `sheet_name`, `fork_id` or `rule_name`.
+    pub completion: Option<&'static str>,
+    /// Allowed values; empty when any value is accepted.
+    pub choices: &'static [&'static str],
+    pub default: Option<&'static str>,
+}
+
+/// One step of a prompt's workflow.
+#[derive(Debug, Clone, Copy)]
+pub struct PromptStepDef {
+    /// Instruction text; `{argument}` placeholders are filled in.
+    pub text: &'static str,
+    /// Tool the step calls; the prompt is hidden when it is disabled.
+    pub tool: Option<&'static str>,
+    /// Include the step only when this argument is given.
+    pub when: Option<&'static str>,
+    /// Include the step only when this argument is omitted.
+    pub unless: Option<&'static str>,
+    /// The agent must stop for user approval at this step.
+    pub manual_gate: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PromptDef {
+    pub name: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    /// Cargo feature (and matching server switch) the prompt needs.
+    pub requires_feature: Option<&'static str>,
+    pub arguments: &'static [PromptArgumentDef],
+    pub steps: &'static [PromptStepDef],
+}
+
+pub const PROMPTS: &[PromptDef] = &[
+    PromptDef {
+        name: "add_entity_and_regenerate",
+        title: "Add an entity and regenerate code",
+        description: "Add an entity to the ontology, validate it, preview the regenerated code and apply it after approval",
+        requires_feature: None,
+        arguments: &[
+            PromptArgumentDef {
+                name: "entity_name",
+                description: "PascalCase name of the new entity",
+                required: true,
+                completion: None,
+                choices: &[],
+                default: None,
+            },
+            PromptArgumentDef {
+                name: "entity_type",
+                description: "DDD kind of the entity",
+                required: false,
+                completion: None,
+                choices: &[
+                    "Entity",
+                    "ValueObject",
+                    "AggregateRoot",
+                    "Event",
+                    "Command",
+                    "Query",
+                ],
+                default: Some("Entity"),
+            },
+            PromptArgumentDef {
+                name: "properties",
+                description: "Properties as comma-separated name:Type pairs",
+                required: false,
+                completion: None,
+                choices: &[],
+                default: None,
+            },
+            PromptArgumentDef {
+                name: "ontology_path",
+                description: "Turtle file to add the entity to",
+                required: false,
+                completion: None,
+                choices: &[],
+                default: Some("ontology/mcp-domain.ttl"),
+            },
+        ],
+        steps: &[
+            PromptStepDef {
+                text: "Call `manage_ggen_resource` with operation {type: read_config} to see the ontology sources and generation rules.",
+                tool: Some("manage_ggen_resource"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Call `manage_ggen_resource` with operation {type: query_entities, path: `{ontology_path}`} and make sure `{entity_name}` does not exist yet.",
+                tool: Some("manage_ggen_resource"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Call `manage_ggen_resource` with operation {type: add_entity, path: `{ontology_path}`, entity_name: `{entity_name}`, entity_type: `{entity_type}`} and one property per pair in: {properties}.",
+                tool: Some("manage_ggen_resource"),
+                when: Some("properties"),
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Ask the user which properties `{entity_name}` needs, then call `manage_ggen_resource` with operation {type: add_entity, path: `{ontology_path}`, entity_name: `{entity_name}`, entity_type: `{entity_type}`}.",
+                tool: Some("manage_ggen_resource"),
+                when: None,
+                unless: Some("properties"),
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Call `manage_ggen_resource` with operation {type: validate_ontology, path: `{ontology_path}`, shacl_validation: true} and fix any violations.",
+                tool: Some("manage_ggen_resource"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Call `sync_ggen` with mode=preview and review the report and diff for the files the new entity touches.",
+                tool: Some("sync_ggen"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Show the preview to the user and wait for approval before writing files.",
+                tool: None,
+                when: None,
+                unless: None,
+                manual_gate: true,
+            },
+            PromptStepDef {
+                text: "Call `sync_ggen` with mode=apply, then `verify_receipt` on the receipt it reports.",
+                tool: Some("verify_receipt"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Call `validate_definition_of_done` with profile=standard to confirm the regenerated code builds and its tests pass.",
+                tool: Some("validate_definition_of_done"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+        ],
+    },
+    PromptDef {
+        name: "profile_workbook",
+        title: "Profile a workbook",
+        description: "Orient in a workbook: sheets, regions, tables, formulas and data quality, then summarize what it computes",
+        requires_feature: None,
+        arguments: &[
+            PromptArgumentDef {
+                name: "workbook_id",
+                description: "Workbook or fork to profile",
+                required: true,
+                completion: Some("workbook_id"),
+                choices: &[],
+                default: None,
+            },
+            PromptArgumentDef {
+                name: "sheet_name",
+                description: "Limit the deep dive to one sheet",
+                required: false,
+                completion: Some("sheet_name"),
+                choices: &[],
+                default: None,
+            },
+        ],
+        steps: &[
+            PromptStepDef {
+                text: "Call `describe_workbook` with workbook_or_fork_id=`{workbook_id}` for size, sheet count and protection.",
+                tool: Some("describe_workbook"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Call `workbook_summary` for the regions and entry points of each sheet.",
+                tool: Some("workbook_summary"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Call `sheet_overview` on each sheet to detect its regions (ids, bounds, kind, confidence).",
+                tool: Some("sheet_overview"),
+                when: None,
+                unless: Some("sheet_name"),
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Call `sheet_overview` on sheet `{sheet_name}` to detect its regions (ids, bounds, kind, confidence).",
+                tool: Some("sheet_overview"),
+                when: Some("sheet_name"),
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "For each table-like region, call `table_profile` with its region_id; use `read_table` with a small limit only where a column needs a closer look.",
+                tool: Some("table_profile"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Call `sheet_formula_map` (limit=10, sort_by=complexity) and `scan_volatiles` to find the key calculations and volatile formulas; `formula_trace` the main outputs.",
+                tool: Some("sheet_formula_map"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Check `named_ranges`, and `data_validations`, `list_charts` or `list_pivot_tables` where the overview mentions them.",
+                tool: Some("named_ranges"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Summarize the purpose of each sheet, its inputs, outputs and key formulas, and any data quality issues found. Keep it under a page.",
+                tool: None,
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+        ],
+    },
+    PromptDef {
+        name: "run_definition_of_done",
+        title: "Run definition of done",
+        description: "Run the definition-of-done checks, fix what fails and re-run until the verdict is ready",
+        requires_feature: None,
+        arguments: &[PromptArgumentDef {
+            name: "profile",
+            description: "Check profile to run",
+            required: false,
+            completion: None,
+            choices: &["minimal", "standard", "comprehensive"],
+            default: Some("comprehensive"),
+        }],
+        steps: &[
+            PromptStepDef {
+                text: "Call `validate_definition_of_done` with profile=`{profile}` and include_remediation=true.",
+                tool: Some("validate_definition_of_done"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Group the failing checks by category and read each one's evidence and remediation.",
+                tool: None,
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Fix the failures, smallest blast radius first. Do not weaken or skip a check to make it pass.",
+                tool: None,
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Call `validate_definition_of_done` with profile=`{profile}` again until it passes; if code was regenerated, `verify_receipt` the latest receipt.",
+                tool: Some("validate_definition_of_done"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Report the verdict, what changed and anything still failing.",
+                tool: None,
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+        ],
+    },
+    PromptDef {
+        name: "safe_fork_edit",
+        title: "Safely edit a fork and review the changeset",
+        description: "Make a change in a fork with a checkpoint and previews, recalculate, review the changeset and save only after approval",
+        requires_feature: Some("recalc"),
+        arguments: &[
+            PromptArgumentDef {
+                name: "workbook_id",
+                description: "Workbook to fork and edit",
+                required: true,
+                completion: Some("workbook_id"),
+                choices: &[],
+                default: None,
+            },
+            PromptArgumentDef {
+                name: "change",
+                description: "The edit to make, in plain words",
+                required: true,
+                completion: None,
+                choices: &[],
+                default: None,
+            },
+            PromptArgumentDef {
+                name: "sheet_name",
+                description: "Sheet the change applies to",
+                required: false,
+                completion: Some("sheet_name"),
+                choices: &[],
+                default: None,
+            },
+        ],
+        steps: &[
+            PromptStepDef {
+                text: "Call `create_fork` with workbook_or_fork_id=`{workbook_id}` and use the returned fork_id from here on.",
+                tool: Some("create_fork"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Call `checkpoint_fork` so the edit can be rolled back with `restore_checkpoint`.",
+                tool: Some("checkpoint_fork"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Call `sheet_overview` on `{sheet_name}` in the fork to find the cells and regions involved.",
+                tool: Some("sheet_overview"),
+                when: Some("sheet_name"),
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Use `workbook_summary` and `sheet_overview` on the fork to find the cells and regions involved.",
+                tool: Some("workbook_summary"),
+                when: None,
+                unless: Some("sheet_name"),
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Make this change: {change}. Use the narrowest batch tool (transform_batch, style_batch, structure_batch, ...) with mode=`preview` where it is supported, and edit_batch only for individual cells.",
+                tool: None,
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Review `list_staged_changes`; `apply_staged_change` the ones that match the intent and `discard_staged_change` the rest.",
+                tool: Some("list_staged_changes"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Call `recalculate` on the fork so formula results reflect the change.",
+                tool: Some("recalculate"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Call `get_changeset` with summary_only=true, then page through the changes (exclude_subtypes=['recalc_result'] if noisy) and confirm every one was intended.",
+                tool: Some("get_changeset"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Call `render_sheet` on the edited range for a visual check.",
+                tool: Some("render_sheet"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+            PromptStepDef {
+                text: "Present the changeset summary to the user and wait for approval. Do not save without it.",
+                tool: None,
+                when: None,
+                unless: None,
+                manual_gate: true,
+            },
+            PromptStepDef {
+                text: "On approval call `save_fork` with a new target_path; otherwise `restore_checkpoint` and revise, or `discard_fork`.",
+                tool: Some("save_fork"),
+                when: None,
+                unless: None,
+                manual_gate: false,
+            },
+        ],
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_count() {
+        assert_eq!(PROMPTS.len(), 4, "Expected 4 prompts from ontology");
+    }
+}
@@ -159,6 +159,7 @@ impl From<ReadTableParams> for crate::tools::ReadTableParams {
             sample_mode: None,
             limit: p.limit,
             offset: p.offset,
+            value_mode: None,
         }
     }
 }
@@ -74,6 +74,7 @@ pub mod queries;
 pub mod repositories;
 
 // MCP Tool Generation (from ontology)
+pub mod mcp_prompts;
 pub mod mcp_tool_params;
 pub mod mcp_tools;
 
@@ -1,9 +1,14 @@
 pub mod analysis;
 pub mod audit;
+pub mod auth;
+pub mod backends;
 pub mod caps;
+pub mod charts;
 pub mod codegen;
+pub mod comments;
+pub mod completion;
 pub mod config;
-#[cfg(feature = "recalc")]
+pub mod data_validation;
 pub mod diff;
 pub mod dod;
 pub mod domain;
@@ -18,11 +23,18 @@ pub mod health;
 pub mod logging;
 pub mod metrics;
 pub mod model;
+pub mod number_format;
 pub mod ontology;
+pub mod pivots;
+pub mod progress;
+pub mod prompts;
 #[cfg(feature = "recalc")]
 pub mod recalc;
 pub mod recovery;
+pub mod render;
+pub mod resources;
 pub mod server;
+pub mod sheet_settings;
 pub mod shutdown;
 pub mod sparql;
 pub mod state;
@@ -33,7 +45,7 @@ pub mod utils;
 pub mod validation;
 pub mod workbook;
 
-pub use config::{CliArgs, ServerConfig, TransportKind};
+pub use config::{CliArgs, RecalcBackendKind, ServerConfig, TransportKind};
 pub use error::{ERROR_METRICS, ErrorCode, ErrorMetrics, McpError, to_mcp_error, to_rmcp_error};
 pub use logging::{LoggingConfig, init_logging, shutdown_telemetry};
 pub use server::SpreadsheetServer;
@@ -43,15 +55,18 @@ use anyhow::Result;
 use audit::{AuditConfig, init_audit_logger};
 use axum::Router;
 use model::WorkbookListResponse;
+use progress::{CancelledError, ProgressReporter};
 use rmcp::transport::streamable_http_server::{
     StreamableHttpService, session::local::LocalSessionManager,
 };
-use state::AppState;
-use std::{future::IntoFuture, sync::Arc};
+use state::{AppState, CacheWarmingConfig, CacheWarmingResult};
+use std::{collections::HashMap, future::IntoFuture, net::SocketAddr, sync::Arc};
 use tokio::{
     net::TcpListener,
+    task::JoinHandle,
     time::{Duration, timeout},
 };
+use tokio_util::sync::CancellationToken;
 use tools::filters::WorkbookFilter;
 
 const HTTP_SERVICE_PATH: &str = "/mcp";
@@ -103,8 +118,12 @@ pub async fn run_server(config: ServerConfig) -> Result<()> {
 
     match config.transport {
         TransportKind::Stdio => {
+            let shutdown = CancellationToken::new();
+            spawn_cache_warming(state.clone(), shutdown.clone());
             let server = SpreadsheetServer::from_state(state);
-            server.run_stdio().await
+            let result = server.run_stdio().await;
+            shutdown.cancel();
+            result
         }
         TransportKind::Http => run_stream_http_transport(config, state).await,
     }
@@ -126,6 +145,9 @@ async fn run_stream_http_transport(config: Arc<ServerConfig>, state: Arc<AppStat
         ShutdownConfig::default().with_total_timeout(config.graceful_shutdown_timeout_secs);
     let coordinator = Arc::new(ShutdownCoordinator::new(shutdown_config));
 
+    // Warm the cache in the background; a shutdown signal stops it
+    spawn_cache_warming(state.clone(), coordinator.token());
+
     // Setup composite shutdown handler
     let mut composite_handler = CompositeShutdownHandler::new();
     composite_handler.add_handler(Box::new(AppStateShutdownHandler::new(state.clone())));
@@ -137,12 +159,42 @@ async fn run_stream_http_transport(config: Arc<ServerConfig>, state: Arc<AppStat
         composite_handler.add_handler(Box::new(LibreOfficeShutdownHandler::new(backend.clone())));
     }
 
+    // With http_auth, every identity gets its own state scoped to its
+    // workspace subpath, tools and access level; recalc processes and locks
+    // are shared with the server's state
+    let auth = match &config.http_auth {
+        Some(auth_config) => Some(Arc::new(auth::HttpAuth::from_config(auth_config)?)),
+        None => None,
+    };
+    let principal_states = match &auth {
+        Some(auth) => {
+            let tool_names = SpreadsheetServer::from_state(state.clone()).tool_names();
+            let mut states = HashMap::new();
+            for identity in auth.identity_names() {
+                let scoped = auth.scoped_config(&config, identity, &tool_names)?;
+                let scoped_state = Arc::new(state.scoped(Arc::new(scoped)));
+                composite_handler
+                    .add_handler(Box::new(AppStateShutdownHandler::new(scoped_state.clone())));
+                states.insert(identity.to_string(), scoped_state);
+            }
+            tracing::info!(identities = states.len(), "HTTP authentication enabled");
+            Some(Arc::new(states))
+        }
+        None => None,
+    };
+
     let composite_handler = Arc::new(composite_handler);
 
     let bind_addr = config.http_bind_address;
     let service_state = state.clone();
     let service = StreamableHttpService::new(
-        move || Ok(SpreadsheetServer::from_state(service_state.clone())),
+        move || {
+            let server = SpreadsheetServer::from_state(service_state.clone());
+            Ok(match &principal_states {
+                Some(states) => server.with_principal_states(states.clone()),
+                None => server,
+            })
+        },
         LocalSessionManager::default().into(),
         Default::default(),
     );
@@ -150,15 +202,26 @@ async fn run_stream_http_transport(config: Arc<ServerConfig>, state: Arc<AppStat
     // Create health checker
     let health_checker = Arc::new(health::HealthChecker::new(config.clone(), state.clone()));
 
-    let router = Router::new()
+    // Liveness and readiness stay open for probes; everything else requires
+    // credentials when http_auth is configured
+    let mut protected = Router::new()
         .nest_service(HTTP_SERVICE_PATH, service)
-        .route("/health", axum::routing::get(health::liveness_handler))
-        .route("/ready", axum::routing::get(health::readiness_handler))
         .route(
             "/health/components",
             axum::routing::get(health::components_handler),
         )
-        .route("/metrics", axum::routing::get(metrics_handler))
+        .route("/metrics", axum::routing::get(metrics_handler));
+    if let Some(auth) = auth {
+        protected = protected.layer(axum::middleware::from_fn_with_state(
+            auth,
+            auth::require_auth,
+        ));
+    }
+
+    let router = Router::new()
+        .route("/health", axum::routing::get(health::liveness_handler))
+        .route("/ready", axum::routing::get(health::readiness_handler))
+        .merge(protected)
         .with_state(health_checker);
     let listener = TcpListener::bind(bind_addr).await?;
     let actual_addr = listener.local_addr()?;
@@ -168,11 +231,15 @@ async fn run_stream_http_transport(config: Arc<ServerConfig>, state: Arc<AppStat
     let shutdown_coordinator = coordinator.clone();
 
     // Spawn server with graceful shutdown
-    let server_future = axum::serve(listener, router)
-        .with_graceful_shutdown(async move {
-            shutdown_coordinator.wait_for_signal().await;
-        })
-        .into_future();
+    let server_future = axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        shutdown_coordinator.wait_for_signal().await;
+        shutdown_coordinator.token().cancel();
+    })
+    .into_future();
 
     tokio::pin!(server_future);
 
@@ -188,6 +255,44 @@ async fn run_stream_http_transport(config: Arc<ServerConfig>, state: Arc<AppStat
     server_result.map_err(anyhow::Error::from)
 }
 
+/// Pre-loads recently modified workbooks (up to the cache capacity) on a
+/// background task. Warming stops between workbooks once `shutdown` is
+/// cancelled; workbooks already loaded stay cached.
+pub fn spawn_cache_warming(
+    state: Arc<AppState>,
+    shutdown: CancellationToken,
+) -> JoinHandle<Result<CacheWarmingResult>> {
+    let defaults = CacheWarmingConfig::default();
+    let warming = CacheWarmingConfig {
+        max_workbooks: defaults
+            .max_workbooks
+            .min(state.config().cache_capacity.max(1)),
+        ..defaults
+    };
+    let (progress, mut updates) = ProgressReporter::channel(shutdown);
+    tokio::spawn(async move {
+        while let Some(update) = updates.recv().await {
+            tracing::debug!(
+                progress = update.progress,
+                total = ?update.total,
+                message = %update.message,
+                "cache warming progress"
+            );
+        }
+    });
+    tokio::spawn(async move {
+        let result = state.warm_cache_with_progress(warming, &progress).await;
+        match &result {
+            Ok(_) => {}
+            Err(error) if error.is::<CancelledError>() => {
+                tracing::info!("cache warming stopped by shutdown");
+            }
+            Err(error) => tracing::warn!(?error, "cache warming failed"),
+        }
+        result
+    })
+}
+
 pub fn startup_scan(state: &Arc<AppState>) -> Result<WorkbookListResponse> {
     state.list_workbooks(WorkbookFilter::default())
 }
@@ -447,6 +447,8 @@ pub fn classify_error(error: &anyhow::Error) -> &'static str {
 
     if error_str.contains("not found") {
         "not_found"
+    } else if error_str.contains("cancelled") {
+        "cancelled"
     } else if error_str.contains("timeout") || error_str.contains("timed out") {
         "timeout"
     } else if error_str.contains("permission") || error_str.contains("denied") {
@@ -1,4 +1,5 @@
 use crate::caps::BackendCaps;
+use crate::sheet_settings::{SheetSettings, WorkbookProtectionInfo};
 use chrono::{DateTime, Utc};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -50,6 +51,20 @@ pub struct WorkbookDescription {
     pub macros_present: bool,
     pub last_modified: Option<String>,
     pub caps: BackendCaps,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delimited: Option<DelimitedSourceInfo>,
+    /// Workbook structure/window protection, when set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protection: Option<WorkbookProtectionInfo>,
+}
+
+/// Layout detected for a CSV/TSV workbook.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DelimitedSourceInfo {
+    pub delimiter: String,
+    pub encoding: String,
+    /// 1-based header row, if the first row looks like column labels.
+    pub header_row: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -139,6 +154,9 @@ pub struct SheetOverviewResponse {
     pub formula_ratio: f32,
     pub notable_features: Vec<String>,
     pub notes: Vec<String>,
+    /// Visibility, panes, view, outline groups and protection of the sheet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sheet_settings: Option<SheetSettings>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -213,6 +231,9 @@ pub struct CellSnapshot {
     pub number_format: Option<String>,
     pub style_tags: Vec<String>,
     pub notes: Vec<String>,
+    /// Display text under the cell's number format, with `value_mode: both`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -235,16 +256,34 @@ pub enum SheetPageFormat {
     ValuesOnly,
 }
 
+/// Which values read tools return: the stored value, the text Excel shows
+/// under the cell's number format (as `Text`), or the stored value plus a
+/// parallel `formatted` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueMode {
+    #[default]
+    Raw,
+    Formatted,
+    Both,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SheetPageCompact {
     pub headers: Vec<String>,
     pub header_row: Vec<Option<CellValue>>,
     pub rows: Vec<Vec<Option<CellValue>>>,
+    /// Display text mirroring `rows`, with `value_mode: both`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted_rows: Option<Vec<Vec<Option<String>>>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SheetPageValues {
     pub rows: Vec<Vec<Option<CellValue>>>,
+    /// Display text mirroring `rows`, with `value_mode: both`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted_rows: Option<Vec<Vec<Option<String>>>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -431,6 +470,114 @@ pub struct FindFormulaResponse {
     pub next_offset: Option<u32>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CommentKind {
+    /// Legacy cell note.
+    Note,
+    /// Threaded comment with replies.
+    Thread,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CommentReply {
+    pub author: Option<String>,
+    pub text: String,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SheetComment {
+    pub sheet_name: String,
+    pub address: String,
+    pub kind: CommentKind,
+    pub author: Option<String>,
+    pub text: String,
+    /// Threads only; notes cannot be resolved.
+    pub resolved: Option<bool>,
+    pub created_at: Option<String>,
+    pub replies: Vec<CommentReply>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SheetCommentsResponse {
+    pub workbook_id: WorkbookId,
+    pub workbook_short_id: String,
+    pub comments: Vec<SheetComment>,
+    pub total: u32,
+    pub truncated: bool,
+    pub next_offset: Option<u32>,
+}
+
+/// Where a dropdown list takes its entries from.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ListSource {
+    /// Entries typed into the rule, e.g. `"Open,Closed"`.
+    Inline { values: Vec<String> },
+    /// Entries read from a range or defined name. `values` is empty when the
+    /// reference could not be resolved (e.g. OFFSET or INDIRECT).
+    Range {
+        reference: String,
+        resolved: bool,
+        values: Vec<String>,
+        values_truncated: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DataValidationEntry {
+    pub sheet_name: String,
+    /// Space-separated ranges the rule covers (the `sqref`).
+    pub range: String,
+    /// list | whole | decimal | date | time | text_length | custom | any
+    pub validation_type: String,
+    pub operator: Option<String>,
+    pub formula1: Option<String>,
+    pub formula2: Option<String>,
+    pub list_source: Option<ListSource>,
+    pub allow_blank: bool,
+    pub show_input_message: bool,
+    pub prompt_title: Option<String>,
+    pub prompt: Option<String>,
+    pub show_error_message: bool,
+    /// stop | warning | information
+    pub error_style: String,
+    pub error_title: Option<String>,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DataValidationsResponse {
+    pub workbook_id: WorkbookId,
+    pub workbook_short_id: String,
+    pub rules: Vec<DataValidationEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ValidationViolation {
+    pub sheet_name: String,
+    pub address: String,
+    pub value: String,
+    /// Range of the rule that rejects the value.
+    pub rule_range: String,
+    pub validation_type: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ValidationViolationsResponse {
+    pub workbook_id: WorkbookId,
+    pub workbook_short_id: String,
+    pub violations: Vec<ValidationViolation>,
+    pub total: u32,
+    pub truncated: bool,
+    pub rules_checked: u32,
+    /// Rules that could not be evaluated (custom formulas, computed list
+    /// sources or bounds), as `Sheet!range: why`.
+    pub unchecked_rules: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct VolatileScanEntry {
     pub address: String,
@@ -773,6 +920,9 @@ pub struct FindValueResponse {
 
 pub type TableRow = BTreeMap<String, Option<CellValue>>;
 
+/// Display text for a [`TableRow`], keyed by the same headers.
+pub type FormattedTableRow = BTreeMap<String, Option<String>>;
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ReadTableResponse {
     pub workbook_id: WorkbookId,
@@ -783,6 +933,9 @@ pub struct ReadTableResponse {
     pub rows: Vec<TableRow>,
     pub total_rows: u32,
     pub has_more: bool,
+    /// Display text mirroring `rows`, with `value_mode: both`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted_rows: Option<Vec<FormattedTableRow>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -822,6 +975,9 @@ pub struct RangeValuesResponse {
 pub struct RangeValuesEntry {
     pub range: String,
     pub rows: Vec<Vec<Option<CellValue>>>,
+    /// Display text mirroring `rows`, with `value_mode: both`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted_rows: Option<Vec<Vec<Option<String>>>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -875,7 +1031,7 @@ pub struct VbaModuleSourceResponse {
 }
 
 /// Edit operation for fork-based editing
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EditOp {
     pub timestamp: DateTime<Utc>,
     pub sheet: String,
@@ -0,0 +1,1233 @@
+//! Excel number formats: turning a cell value and its format code into the
+//! text Excel shows.
+//!
+//! Covers `General`, digit placeholders (`0`, `#`, `?`) with thousands
+//! separators and scaling, percentages, scientific notation, fractions, up to
+//! four `;` sections with `[Red]`-style colors and `[>=100]` conditions, text
+//! sections, dates, times and elapsed `[h]:mm:ss`. Fill repeats (`*x`) are
+//! dropped and locale tags only contribute their currency symbol.
+
+use chrono::{Datelike, Duration, NaiveDate};
+use umya_spreadsheet::Cell;
+
+/// Display text plus the color a `[Red]`-style section asks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormattedValue {
+    pub text: String,
+    /// CSS hex color, e.g. `#FF0000`.
+    pub color: Option<String>,
+}
+
+impl FormattedValue {
+    fn plain(text: String) -> Self {
+        Self { text, color: None }
+    }
+}
+
+/// The text Excel shows for `cell`, or `None` when it has no value.
+pub fn cell_display_text(cell: &Cell) -> Option<String> {
+    cell_display(cell).map(|formatted| formatted.text)
+}
+
+/// Like [`cell_display_text`], keeping the section color.
+pub fn cell_display(cell: &Cell) -> Option<FormattedValue> {
+    let raw = cell.get_value();
+    if raw.is_empty() {
+        return None;
+    }
+    let code = cell_format_code(cell);
+    let formatted = match cell.get_data_type() {
+        "b" => FormattedValue::plain(
+            if raw.eq_ignore_ascii_case("true") || raw == "1" {
+                "TRUE"
+            } else {
+                "FALSE"
+            }
+            .to_string(),
+        ),
+        "e" => FormattedValue::plain(raw.to_string()),
+        "s" | "str" | "inlineStr" => format_text(&raw, &code),
+        _ => match raw.trim().parse::<f64>() {
+            Ok(number) => format_number(number, &code, false),
+            Err(_) => format_text(&raw, &code),
+        },
+    };
+    Some(formatted)
+}
+
+/// The cell's format code, resolving built-in ids that carry no code.
+pub fn cell_format_code(cell: &Cell) -> String {
+    let Some(format) = cell.get_style().get_number_format() else {
+        return "General".to_string();
+    };
+    let id = *format.get_number_format_id();
+    // 14 is the locale short date; umya reports the ECMA default for it.
+    if id == 14 {
+        return "m/d/yyyy".to_string();
+    }
+    let code = format.get_format_code();
+    if code.is_empty() {
+        builtin_format_code(id).unwrap_or("General").to_string()
+    } else {
+        code.to_string()
+    }
+}
+
+/// Format codes for the built-in ids Excel writes without a `numFmt` entry.
+pub fn builtin_format_code(id: u32) -> Option<&'static str> {
+    Some(match id {
+        0 => "General",
+        1 => "0",
+        2 => "0.00",
+        3 => "#,##0",
+        4 => "#,##0.00",
+        9 => "0%",
+        10 => "0.00%",
+        11 => "0.00E+00",
+        12 => "# ?/?",
+        13 => "# ??/??",
+        14 => "m/d/yyyy",
+        15 => "d-mmm-yy",
+        16 => "d-mmm",
+        17 => "mmm-yy",
+        18 => "h:mm AM/PM",
+        19 => "h:mm:ss AM/PM",
+        20 => "h:mm",
+        21 => "h:mm:ss",
+        22 => "m/d/yyyy h:mm",
+        37 => "#,##0 ;(#,##0)",
+        38 => "#,##0 ;[Red](#,##0)",
+        39 => "#,##0.00;(#,##0.00)",
+        40 => "#,##0.00;[Red](#,##0.00)",
+        45 => "mm:ss",
+        46 => "[h]:mm:ss",
+        47 => "mmss.0",
+        48 => "##0.0E+0",
+        49 => "@",
+        _ => return None,
+    })
+}
+
+/// Format a number. `date_1904` selects the Mac epoch for date sections.
+pub fn format_number(value: f64, code: &str, date_1904: bool) -> FormattedValue {
+    if !value.is_finite() {
+        return FormattedValue::plain("#NUM!".to_string());
+    }
+    let sections = parse_sections(code);
+    let Some((section, signed)) = pick_section(&sections, value) else {
+        return FormattedValue::plain("#".repeat(11));
+    };
+    let color = section.color.clone();
+    let text = match section.kind() {
+        Kind::General => {
+            let general = format_general(value.abs());
+            let sign = if signed && value < 0.0 { "-" } else { "" };
+            let mut out = String::new();
+            for token in &section.tokens {
+                match token {
+                    Token::General => {
+                        out.push_str(sign);
+                        out.push_str(&general);
+                    }
+                    other => push_literal(&mut out, other),
+                }
+            }
+            out
+        }
+        Kind::Date => format_date(&section.tokens, value, date_1904),
+        Kind::Number => format_digits(&section.tokens, value, signed),
+        Kind::Text => {
+            // `@` alone shows the number as General; other sections without
+            // placeholders show only their literals.
+            if section.tokens.iter().any(|t| matches!(t, Token::TextAt)) {
+                let mut out = String::new();
+                for token in &section.tokens {
+                    match token {
+                        Token::TextAt => out.push_str(&format_general(value)),
+                        other => push_literal(&mut out, other),
+                    }
+                }
+                out
+            } else {
+                let mut out = String::new();
+                if signed && value < 0.0 && sections.len() == 1 {
+                    out.push('-');
+                }
+                for token in &section.tokens {
+                    push_literal(&mut out, token);
+                }
+                out
+            }
+        }
+    };
+    FormattedValue { text, color }
+}
+
+/// Format text through the code's text section (the fourth, or a lone `@`).
+pub fn format_text(text: &str, code: &str) -> FormattedValue {
+    let sections = parse_sections(code);
+    let section = match sections.len() {
+        4.. => &sections[3],
+        1 if sections[0]
+            .tokens
+            .iter()
+            .any(|t| matches!(t, Token::TextAt)) =>
+        {
+            &sections[0]
+        }
+        _ => return FormattedValue::plain(text.to_string()),
+    };
+    let mut out = String::new();
+    for token in &section.tokens {
+        match token {
+            Token::TextAt => out.push_str(text),
+            other => push_literal(&mut out, other),
+        }
+    }
+    FormattedValue {
+        text: out,
+        color: section.color.clone(),
+    }
+}
+
+/// Excel's General format: up to eleven characters, switching to scientific
+/// notation for very large or very small magnitudes.
+pub fn format_general(value: f64) -> String {
+    let magnitude = value.abs();
+    let sign = if value < 0.0 { "-" } else { "" };
+    if magnitude == 0.0 {
+        return "0".to_string();
+    }
+    if !(1e-9..1e11).contains(&magnitude) {
+        let (mantissa, exponent) = scientific(magnitude, 5);
+        let mantissa = trim_fraction(&mantissa);
+        let exp_sign = if exponent < 0 { '-' } else { '+' };
+        return format!("{sign}{mantissa}E{exp_sign}{:02}", exponent.abs());
+    }
+    let int_digits = if magnitude >= 1.0 {
+        magnitude.log10().floor() as i32 + 1
+    } else {
+        1
+    };
+    let places = (10 - int_digits).max(0) as usize;
+    let (int_part, frac_part) = round_digits(magnitude, places);
+    let frac_part = frac_part.trim_end_matches('0');
+    if frac_part.is_empty() {
+        format!("{sign}{int_part}")
+    } else {
+        format!("{sign}{int_part}.{frac_part}")
+    }
+}
+
+fn trim_fraction(text: &str) -> String {
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// `magnitude` as a mantissa with `places` decimals and its exponent.
+fn scientific(magnitude: f64, places: usize) -> (String, i32) {
+    let mut exponent = magnitude.log10().floor() as i32;
+    loop {
+        let mantissa = magnitude / 10f64.powi(exponent);
+        let (int_part, frac_part) = round_digits(mantissa, places);
+        if int_part.len() > 1 {
+            exponent += 1;
+            continue;
+        }
+        if int_part == "0" && mantissa > 0.0 && exponent > -400 {
+            exponent -= 1;
+            continue;
+        }
+        let text = if places == 0 {
+            int_part
+        } else {
+            format!("{int_part}.{frac_part}")
+        };
+        return (text, exponent);
+    }
+}
+
+/// Round a non-negative `value` half away from zero to `places` decimals,
+/// starting from its 15 significant digits the way Excel does, so 1.005
+/// rounds to 1.01. Returns the integer and fraction digit strings.
+fn round_digits(value: f64, places: usize) -> (String, String) {
+    if value == 0.0 {
+        return ("0".to_string(), "0".repeat(places));
+    }
+    let sci = format!("{value:.14e}");
+    let (mantissa, exponent) = sci.split_once('e').unwrap_or((&sci, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let mut digits: Vec<u8> = mantissa
+        .bytes()
+        .filter(u8::is_ascii_digit)
+        .map(|b| b - b'0')
+        .collect();
+    let mut point = exponent + 1;
+    let keep = point + places as i32;
+    if keep < 0 {
+        return ("0".to_string(), "0".repeat(places));
+    }
+    let keep = keep as usize;
+    if keep < digits.len() {
+        let round_up = digits[keep] >= 5;
+        digits.truncate(keep);
+        if round_up {
+            let mut i = keep;
+            loop {
+                if i == 0 {
+                    digits.insert(0, 1);
+                    point += 1;
+                    break;
+                }
+                i -= 1;
+                if digits[i] == 9 {
+                    digits[i] = 0;
+                } else {
+                    digits[i] += 1;
+                    break;
+                }
+            }
+        }
+    }
+    let total = (point + places as i32).max(0) as usize;
+    digits.resize(digits.len().max(total), 0);
+
+    let (int_digits, frac_digits) = if point <= 0 {
+        let mut frac = vec![0u8; (-point) as usize];
+        frac.extend_from_slice(&digits);
+        frac.resize(places, 0);
+        (vec![0u8], frac)
+    } else {
+        let point = point as usize;
+        (
+            digits[..point].to_vec(),
+            digits[point..point + places].to_vec(),
+        )
+    };
+    let to_text = |d: &[u8]| d.iter().map(|d| char::from(b'0' + d)).collect::<String>();
+    let int_text = to_text(&int_digits);
+    let int_text = int_text.trim_start_matches('0');
+    let int_text = if int_text.is_empty() { "0" } else { int_text };
+    (int_text.to_string(), to_text(&frac_digits))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Literal(String),
+    General,
+    /// `0`, `#` or `?`.
+    Digit(char),
+    /// A literal digit, meaningful as a fixed fraction denominator.
+    FixedDigit(char),
+    Point,
+    Comma,
+    Percent,
+    Slash,
+    /// `E+` (true) or `E-` (false).
+    Exponent(bool),
+    TextAt,
+    /// Lower-cased date/time letter and its repeat count.
+    DatePart(char, usize),
+    /// `[h]`, `[m]` or `[s]` with its width.
+    Elapsed(char, usize),
+    /// `AM/PM` (true) or `A/P` (false), with the case of the first letter.
+    AmPm(bool, bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    General,
+    Number,
+    Date,
+    Text,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Section {
+    tokens: Vec<Token>,
+    color: Option<String>,
+    condition: Option<(Comparison, f64)>,
+}
+
+impl Section {
+    fn kind(&self) -> Kind {
+        if self.tokens.iter().any(|t| {
+            matches!(
+                t,
+                Token::DatePart(..) | Token::Elapsed(..) | Token::AmPm(..)
+            )
+        }) {
+            Kind::Date
+        } else if self.tokens.iter().any(|t| matches!(t, Token::General)) {
+            Kind::General
+        } else if self.tokens.iter().any(|t| matches!(t, Token::Digit(_))) {
+            Kind::Number
+        } else {
+            Kind::Text
+        }
+    }
+
+    fn matches(&self, value: f64) -> bool {
+        match self.condition {
+            None => true,
+            Some((cmp, bound)) => match cmp {
+                Comparison::Lt => value < bound,
+                Comparison::Le => value <= bound,
+                Comparison::Gt => value > bound,
+                Comparison::Ge => value >= bound,
+                Comparison::Eq => value == bound,
+                Comparison::Ne => value != bound,
+            },
+        }
+    }
+}
+
+/// The section for `value` and whether it should print a minus sign itself.
+fn pick_section(sections: &[Section], value: f64) -> Option<(&Section, bool)> {
+    if sections.iter().any(|s| s.condition.is_some()) {
+        let numeric = &sections[..sections.len().min(3)];
+        if let Some(section) = numeric
+            .iter()
+            .find(|s| s.condition.is_some() && s.matches(value))
+        {
+            return Some((section, true));
+        }
+        return numeric
+            .iter()
+            .find(|s| s.condition.is_none())
+            .map(|s| (s, true));
+    }
+    let numeric = match sections.len() {
+        4.. => 3,
+        n => n,
+    };
+    Some(match numeric {
+        0 => return None,
+        1 => (&sections[0], true),
+        2 if value < 0.0 => (&sections[1], false),
+        2 => (&sections[0], false),
+        _ if value < 0.0 => (&sections[1], false),
+        _ if value == 0.0 => (&sections[2], false),
+        _ => (&sections[0], false),
+    })
+}
+
+fn parse_sections(code: &str) -> Vec<Section> {
+    let code = if code.trim().is_empty() {
+        "General"
+    } else {
+        code
+    };
+    split_sections(code)
+        .into_iter()
+        .map(parse_section)
+        .collect()
+}
+
+fn split_sections(code: &str) -> Vec<&str> {
+    let mut sections = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    let mut in_brackets = false;
+    let mut chars = code.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' if !in_brackets => in_quotes = !in_quotes,
+            '\\' | '_' | '*' if !in_quotes && !in_brackets => {
+                chars.next();
+            }
+            '[' if !in_quotes => in_brackets = true,
+            ']' if !in_quotes => in_brackets = false,
+            ';' if !in_quotes && !in_brackets => {
+                sections.push(&code[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    sections.push(&code[start..]);
+    sections.truncate(4);
+    sections
+}
+
+fn parse_section(text: &str) -> Section {
+    let mut section = Section::default();
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '"' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&c| c == '"')
+                    .map(|p| i + 1 + p)
+                    .unwrap_or(chars.len());
+                section
+                    .tokens
+                    .push(Token::Literal(chars[i + 1..end].iter().collect()));
+                i = end + 1;
+            }
+            '\\' => {
+                if let Some(&next) = chars.get(i + 1) {
+                    section.tokens.push(Token::Literal(next.to_string()));
+                }
+                i += 2;
+            }
+            '_' => {
+                section.tokens.push(Token::Literal(" ".to_string()));
+                i += 2;
+            }
+            '*' => i += 2,
+            '[' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&c| c == ']')
+                    .map(|p| i + 1 + p)
+                    .unwrap_or(chars.len());
+                let inner: String = chars[i + 1..end].iter().collect();
+                parse_bracket(&inner, &mut section);
+                i = end + 1;
+            }
+            '0' | '#' | '?' => {
+                section.tokens.push(Token::Digit(c));
+                i += 1;
+            }
+            '1'..='9' => {
+                section.tokens.push(Token::FixedDigit(c));
+                i += 1;
+            }
+            '.' => {
+                section.tokens.push(Token::Point);
+                i += 1;
+            }
+            ',' => {
+                section.tokens.push(Token::Comma);
+                i += 1;
+            }
+            '%' => {
+                section.tokens.push(Token::Percent);
+                i += 1;
+            }
+            '/' => {
+                section.tokens.push(Token::Slash);
+                i += 1;
+            }
+            '@' => {
+                section.tokens.push(Token::TextAt);
+                i += 1;
+            }
+            'E' | 'e' if matches!(chars.get(i + 1), Some('+') | Some('-')) => {
+                section.tokens.push(Token::Exponent(chars[i + 1] == '+'));
+                i += 2;
+            }
+            _ if starts_with_ignore_case(&chars[i..], "general") => {
+                section.tokens.push(Token::General);
+                i += "general".len();
+            }
+            _ if starts_with_ignore_case(&chars[i..], "am/pm") => {
+                section.tokens.push(Token::AmPm(true, c.is_uppercase()));
+                i += "am/pm".len();
+            }
+            _ if starts_with_ignore_case(&chars[i..], "a/p") => {
+                section.tokens.push(Token::AmPm(false, c.is_uppercase()));
+                i += "a/p".len();
+            }
+            _ if matches!(c.to_ascii_lowercase(), 'y' | 'm' | 'd' | 'h' | 's') => {
+                let letter = c.to_ascii_lowercase();
+                let count = chars[i..]
+                    .iter()
+                    .take_while(|ch| ch.to_ascii_lowercase() == letter)
+                    .count();
+                section.tokens.push(Token::DatePart(letter, count));
+                i += count;
+            }
+            _ => {
+                section.tokens.push(Token::Literal(c.to_string()));
+                i += 1;
+            }
+        }
+    }
+    section
+}
+
+fn starts_with_ignore_case(chars: &[char], word: &str) -> bool {
+    chars.len() >= word.len()
+        && chars
+            .iter()
+            .zip(word.chars())
+            .all(|(a, b)| a.to_ascii_lowercase() == b)
+}
+
+fn parse_bracket(inner: &str, section: &mut Section) {
+    let lower = inner.to_ascii_lowercase();
+    if let Some(color) = named_color(&lower) {
+        section.color = Some(color.to_string());
+        return;
+    }
+    if let Some(index) = lower.strip_prefix("color")
+        && let Ok(index) = index.trim().parse::<usize>()
+    {
+        section.color = PALETTE.get(index.wrapping_sub(1)).map(|c| c.to_string());
+        return;
+    }
+    if let Some(condition) = parse_condition(inner) {
+        section.condition = Some(condition);
+        return;
+    }
+    if let Some(currency) = inner.strip_prefix('$') {
+        let symbol = currency.split('-').next().unwrap_or_default();
+        if !symbol.is_empty() {
+            section.tokens.push(Token::Literal(symbol.to_string()));
+        }
+        return;
+    }
+    let mut letters = lower.chars();
+    if let Some(first) = letters.next()
+        && matches!(first, 'h' | 'm' | 's')
+        && letters.all(|c| c == first)
+    {
+        section.tokens.push(Token::Elapsed(first, lower.len()));
+    }
+    // Anything else is a locale or calendar tag with nothing to display.
+}
+
+fn parse_condition(inner: &str) -> Option<(Comparison, f64)> {
+    let (cmp, rest) = [
+        ("<=", Comparison::Le),
+        (">=", Comparison::Ge),
+        ("<>", Comparison::Ne),
+        ("<", Comparison::Lt),
+        (">", Comparison::Gt),
+        ("=", Comparison::Eq),
+    ]
+    .into_iter()
+    .find_map(|(prefix, cmp)| inner.strip_prefix(prefix).map(|rest| (cmp, rest)))?;
+    rest.trim().parse::<f64>().ok().map(|bound| (cmp, bound))
+}
+
+const PALETTE: [&str; 8] = [
+    "#000000", "#FFFFFF", "#FF0000", "#00FF00", "#0000FF", "#FFFF00", "#FF00FF", "#00FFFF",
+];
+
+fn named_color(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "black" => "#000000",
+        "white" => "#FFFFFF",
+        "red" => "#FF0000",
+        "green" => "#00FF00",
+        "blue" => "#0000FF",
+        "yellow" => "#FFFF00",
+        "magenta" => "#FF00FF",
+        "cyan" => "#00FFFF",
+        _ => return None,
+    })
+}
+
+fn push_literal(out: &mut String, token: &Token) {
+    match token {
+        Token::Literal(text) => out.push_str(text),
+        Token::FixedDigit(c) => out.push(*c),
+        Token::Point => out.push('.'),
+        Token::Comma => out.push(','),
+        Token::Percent => out.push('%'),
+        Token::Slash => out.push('/'),
+        _ => {}
+    }
+}
+
+fn format_digits(tokens: &[Token], value: f64, signed: bool) -> String {
+    let percent = tokens.iter().filter(|t| **t == Token::Percent).count() as i32;
+    let mut magnitude = value.abs() * 100f64.powi(percent);
+
+    // Commas right after the last digit placeholder scale by a thousand each;
+    // commas between placeholders turn on grouping.
+    let mut grouping = false;
+    let mut comma_literals = vec![false; tokens.len()];
+    for (i, token) in tokens.iter().enumerate() {
+        if *token != Token::Comma {
+            continue;
+        }
+        let before = tokens[..i]
+            .iter()
+            .rev()
+            .find(|t| **t != Token::Comma)
+            .is_some_and(|t| matches!(t, Token::Digit(_)));
+        let after = tokens[i + 1..]
+            .iter()
+            .find(|t| **t != Token::Comma)
+            .is_some_and(|t| matches!(t, Token::Digit(_)));
+        if before && after {
+            grouping = true;
+        } else if before {
+            magnitude /= 1000.0;
+        } else {
+            comma_literals[i] = true;
+        }
+    }
+
+    let body = if tokens.contains(&Token::Slash) {
+        format_fraction(tokens, magnitude, &comma_literals)
+    } else {
+        format_decimal(tokens, magnitude, grouping, &comma_literals)
+    };
+    let negative = signed && value < 0.0 && body.chars().any(|c| c.is_ascii_digit() && c != '0');
+    if negative { format!("-{body}") } else { body }
+}
+
+fn format_decimal(
+    tokens: &[Token],
+    magnitude: f64,
+    grouping: bool,
+    comma_literals: &[bool],
+) -> String {
+    let exponent_at = tokens.iter().position(|t| matches!(t, Token::Exponent(_)));
+    let mantissa_end = exponent_at.unwrap_or(tokens.len());
+    let point_at = tokens[..mantissa_end]
+        .iter()
+        .position(|t| *t == Token::Point);
+    let int_end = point_at.unwrap_or(mantissa_end);
+    let int_places = tokens[..int_end]
+        .iter()
+        .filter(|t| matches!(t, Token::Digit(_)))
+        .count();
+    let frac_places = point_at
+        .map(|p| {
+            tokens[p + 1..mantissa_end]
+                .iter()
+                .filter(|t| matches!(t, Token::Digit(_)))
+                .count()
+        })
+        .unwrap_or(0);
+
+    let (int_digits, frac_digits, exponent) = match exponent_at {
+        Some(_) => {
+            let width = int_places.max(1) as i32;
+            let engineering = width > 1
+                && tokens[..int_end]
+                    .iter()
+                    .find(|t| matches!(t, Token::Digit(_)))
+                    == Some(&Token::Digit('#'));
+            let mut exponent = if magnitude > 0.0 {
+                magnitude.log10().floor() as i32
+            } else {
+                0
+            };
+            exponent = if engineering {
+                exponent.div_euclid(width) * width
+            } else {
+                exponent - (width - 1)
+            };
+            let (mut int_digits, mut frac_digits) =
+                round_digits(magnitude / 10f64.powi(exponent), frac_places);
+            if magnitude > 0.0 && int_digits.len() > width as usize {
+                exponent += if engineering { width } else { 1 };
+                (int_digits, frac_digits) =
+                    round_digits(magnitude / 10f64.powi(exponent), frac_places);
+            }
+            (int_digits, frac_digits, Some(exponent))
+        }
+        None => {
+            let (int_digits, frac_digits) = round_digits(magnitude, frac_places);
+            (int_digits, frac_digits, None)
+        }
+    };
+
+    let mut out = render_integer(
+        &tokens[..int_end],
+        &int_digits,
+        grouping,
+        &comma_literals[..int_end],
+    );
+    if let Some(point) = point_at {
+        let frac: Vec<char> = frac_digits.chars().collect();
+        let mut next = 0;
+        for (offset, token) in tokens[point..mantissa_end].iter().enumerate() {
+            match token {
+                Token::Point => out.push('.'),
+                Token::Digit(placeholder) => {
+                    let rest_zero = frac[next..].iter().all(|&d| d == '0');
+                    match placeholder {
+                        '0' => out.push(frac[next]),
+                        _ if !rest_zero => out.push(frac[next]),
+                        '?' => out.push(' '),
+                        _ => {}
+                    }
+                    next += 1;
+                }
+                Token::Comma if !comma_literals[point + offset] => {}
+                other => push_literal(&mut out, other),
+            }
+        }
+    }
+    if let (Some(at), Some(exponent)) = (exponent_at, exponent) {
+        let Token::Exponent(plus) = tokens[at] else {
+            unreachable!()
+        };
+        out.push('E');
+        if exponent < 0 {
+            out.push('-');
+        } else if plus {
+            out.push('+');
+        }
+        let width = tokens[at + 1..]
+            .iter()
+            .take_while(|t| matches!(t, Token::Digit(_)))
+            .filter(|t| **t == Token::Digit('0'))
+            .count();
+        out.push_str(&format!("{:0width$}", exponent.abs()));
+        let skip = tokens[at + 1..]
+            .iter()
+            .take_while(|t| matches!(t, Token::Digit(_)))
+            .count();
+        for token in &tokens[at + 1 + skip..] {
+            push_literal(&mut out, token);
+        }
+    }
+    out
+}
+
+/// Lay integer digits into placeholders right to left; the leftmost
+/// placeholder takes any digits left over.
+fn render_integer(
+    tokens: &[Token],
+    digits: &str,
+    grouping: bool,
+    comma_literals: &[bool],
+) -> String {
+    let digits: Vec<char> = if digits == "0" {
+        Vec::new()
+    } else {
+        digits.chars().collect()
+    };
+    let first_placeholder = tokens.iter().position(|t| matches!(t, Token::Digit(_)));
+    let mut pieces: Vec<String> = Vec::new();
+    let mut remaining = digits.len();
+    let mut emitted = 0usize;
+    let push_digit = |pieces: &mut Vec<String>, digit: char, emitted: &mut usize| {
+        if grouping && *emitted > 0 && emitted.is_multiple_of(3) {
+            pieces.push(",".to_string());
+        }
+        pieces.push(digit.to_string());
+        *emitted += 1;
+    };
+    for (i, token) in tokens.iter().enumerate().rev() {
+        match token {
+            Token::Digit(placeholder) => {
+                if Some(i) == first_placeholder && remaining > 0 {
+                    while remaining > 0 {
+                        remaining -= 1;
+                        push_digit(&mut pieces, digits[remaining], &mut emitted);
+                    }
+                } else if remaining > 0 {
+                    remaining -= 1;
+                    push_digit(&mut pieces, digits[remaining], &mut emitted);
+                } else {
+                    match placeholder {
+                        '0' => push_digit(&mut pieces, '0', &mut emitted),
+                        '?' => pieces.push(" ".to_string()),
+                        _ => {}
+                    }
+                }
+            }
+            Token::Comma if !comma_literals[i] => {}
+            Token::Percent => pieces.push("%".to_string()),
+            other => {
+                let mut literal = String::new();
+                push_literal(&mut literal, other);
+                pieces.push(literal);
+            }
+        }
+    }
+    pieces.reverse();
+    pieces.concat()
+}
+
+fn format_fraction(tokens: &[Token], magnitude: f64, comma_literals: &[bool]) -> String {
+    let slash = tokens
+        .iter()
+        .position(|t| *t == Token::Slash)
+        .unwrap_or(tokens.len());
+    // The numerator is the placeholder run right before the slash; any
+    // placeholders further left hold the whole part.
+    let mut numerator_start = slash;
+    while numerator_start > 0 && matches!(tokens[numerator_start - 1], Token::Digit(_)) {
+        numerator_start -= 1;
+    }
+    let int_end = tokens[..numerator_start]
+        .iter()
+        .rposition(|t| matches!(t, Token::Digit(_)))
+        .map(|p| p + 1);
+    let denominator_len = tokens[slash + 1..]
+        .iter()
+        .take_while(|t| matches!(t, Token::Digit(_) | Token::FixedDigit(_)))
+        .count();
+    let denominator_tokens = &tokens[slash + 1..slash + 1 + denominator_len];
+    let fixed: String = denominator_tokens
+        .iter()
+        .filter_map(|t| match t {
+            Token::FixedDigit(c) => Some(*c),
+            Token::Digit('0') if !denominator_tokens.is_empty() => Some('0'),
+            _ => None,
+        })
+        .collect();
+    let fixed_denominator = denominator_tokens
+        .iter()
+        .any(|t| matches!(t, Token::FixedDigit(_)))
+        .then(|| fixed.parse::<u64>().ok())
+        .flatten();
+
+    let (mut whole, fraction) = match int_end {
+        Some(_) => (magnitude.trunc() as u64, magnitude.fract()),
+        None => (0, magnitude),
+    };
+    let (mut numerator, denominator) = match fixed_denominator {
+        Some(denominator) => (
+            (fraction * denominator as f64).round() as u64,
+            denominator.max(1),
+        ),
+        None => {
+            let max = 10u64.pow(denominator_len.clamp(1, 6) as u32) - 1;
+            best_fraction(fraction, max)
+        }
+    };
+    if int_end.is_some() && numerator >= denominator {
+        whole += numerator / denominator;
+        numerator %= denominator;
+    }
+
+    let show_fraction = numerator != 0 || int_end.is_none();
+    let mut out = String::new();
+    if let Some(int_end) = int_end {
+        let digits = whole.to_string();
+        let int_tokens = &tokens[..int_end];
+        let rendered = render_integer(int_tokens, &digits, false, &comma_literals[..int_end]);
+        if rendered.trim().is_empty() && !show_fraction {
+            out.push('0');
+        } else {
+            out.push_str(&rendered);
+        }
+        if show_fraction {
+            for token in &tokens[int_end..numerator_start] {
+                push_literal(&mut out, token);
+            }
+        }
+    } else {
+        for token in &tokens[..numerator_start] {
+            push_literal(&mut out, token);
+        }
+    }
+    if show_fraction {
+        let numerator_width = slash - numerator_start;
+        out.push_str(&format!("{numerator:>numerator_width$}"));
+        out.push('/');
+        let denominator_width = denominator_len;
+        out.push_str(&format!("{denominator:<denominator_width$}"));
+    }
+    for token in &tokens[slash + 1 + denominator_len..] {
+        push_literal(&mut out, token);
+    }
+    out
+}
+
+/// The closest `n/d` to `value` with `d <= max_denominator`.
+fn best_fraction(value: f64, max_denominator: u64) -> (u64, u64) {
+    let mut best = (value.round() as u64, 1u64);
+    let mut best_error = (value - best.0 as f64).abs();
+    for denominator in 2..=max_denominator {
+        let numerator = (value * denominator as f64).round();
+        let error = (value - numerator / denominator as f64).abs();
+        if error < best_error - 1e-12 {
+            best = (numerator as u64, denominator);
+            best_error = error;
+        }
+    }
+    best
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// Calendar parts for an Excel serial day, including the fictional
+/// 1900-02-29 and 1900-01-00.
+fn calendar(days: i64, date_1904: bool) -> (i32, u32, u32, usize) {
+    let weekday = |date: NaiveDate| date.weekday().num_days_from_sunday() as usize;
+    if date_1904 {
+        let date = NaiveDate::from_ymd_opt(1904, 1, 1).expect("valid epoch") + Duration::days(days);
+        return (date.year(), date.month(), date.day(), weekday(date));
+    }
+    match days {
+        0 => (1900, 1, 0, 6),
+        60 => (1900, 2, 29, 3),
+        1..=59 => {
+            let date =
+                NaiveDate::from_ymd_opt(1899, 12, 31).expect("valid epoch") + Duration::days(days);
+            (date.year(), date.month(), date.day(), weekday(date))
+        }
+        _ => {
+            let date =
+                NaiveDate::from_ymd_opt(1899, 12, 30).expect("valid epoch") + Duration::days(days);
+            (date.year(), date.month(), date.day(), weekday(date))
+        }
+    }
+}
+
+fn format_date(tokens: &[Token], value: f64, date_1904: bool) -> String {
+    if !(0.0..=2_958_465.999_999).contains(&value) {
+        return "#".repeat(11);
+    }
+    // Sub-second digits follow a seconds field as `.0`, `.00` or `.000`.
+    let mut subsecond_places = 0usize;
+    for (i, token) in tokens.iter().enumerate() {
+        if *token == Token::Point {
+            let run = tokens[i + 1..]
+                .iter()
+                .take_while(|t| **t == Token::Digit('0'))
+                .count();
+            subsecond_places = subsecond_places.max(run.min(3));
+        }
+    }
+    let scale = 10i64.pow(subsecond_places as u32);
+    let ticks = (value * 86_400.0 * scale as f64).round() as i64;
+    let day_ticks = 86_400 * scale;
+    let days = ticks / day_ticks;
+    let time_ticks = ticks % day_ticks;
+    let seconds_of_day = time_ticks / scale;
+    let subsecond = time_ticks % scale;
+    let (hour, minute, second) = (
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60,
+    );
+    let total_seconds = ticks / scale;
+    let (year, month, day, weekday) = calendar(days, date_1904);
+    let twelve_hour = tokens.iter().any(|t| matches!(t, Token::AmPm(..)));
+
+    // `m` means minutes right after an hour or right before a second.
+    let date_positions: Vec<usize> = tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| matches!(t, Token::DatePart(..) | Token::Elapsed(..)))
+        .map(|(i, _)| i)
+        .collect();
+    let is_minute = |index: usize| -> bool {
+        let slot = date_positions.iter().position(|&p| p == index);
+        let Some(slot) = slot else { return false };
+        let previous = slot
+            .checked_sub(1)
+            .and_then(|s| date_positions.get(s))
+            .map(|&p| &tokens[p]);
+        let next = date_positions.get(slot + 1).map(|&p| &tokens[p]);
+        matches!(
+            previous,
+            Some(Token::DatePart('h', _)) | Some(Token::Elapsed('h', _))
+        ) || matches!(
+            next,
+            Some(Token::DatePart('s', _)) | Some(Token::Elapsed('s', _))
+        )
+    };
+
+    let mut out = String::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::DatePart('y', count) => {
+                if *count <= 2 {
+                    out.push_str(&format!("{:02}", year % 100));
+                } else {
+                    out.push_str(&format!("{year:04}"));
+                }
+            }
+            Token::DatePart('m', count) if is_minute(i) => {
+                out.push_str(&pad(minute, *count));
+            }
+            Token::DatePart('m', count) => {
+                let name = MONTHS[(month - 1) as usize];
+                match count {
+                    1 => out.push_str(&month.to_string()),
+                    2 => out.push_str(&format!("{month:02}")),
+                    3 => out.push_str(&name[..3]),
+                    5 => out.push_str(&name[..1]),
+                    _ => out.push_str(name),
+                }
+            }
+            Token::DatePart('d', count) => {
+                let name = WEEKDAYS[weekday];
+                match count {
+                    1 => out.push_str(&day.to_string()),
+                    2 => out.push_str(&format!("{day:02}")),
+                    3 => out.push_str(&name[..3]),
+                    _ => out.push_str(name),
+                }
+            }
+            Token::DatePart('h', count) => {
+                let hour = if twelve_hour {
+                    match hour % 12 {
+                        0 => 12,
+                        h => h,
+                    }
+                } else {
+                    hour
+                };
+                out.push_str(&pad(hour, *count));
+            }
+            Token::DatePart('s', count) => out.push_str(&pad(second, *count)),
+            Token::DatePart(..) => {}
+            Token::Elapsed(unit, width) => {
+                let amount = match unit {
+                    'h' => total_seconds / 3600,
+                    'm' => total_seconds / 60,
+                    _ => total_seconds,
+                };
+                out.push_str(&pad(amount, *width));
+            }
+            Token::AmPm(full, upper) => {
+                let text = match (hour < 12, full) {
+                    (true, true) => "AM",
+                    (false, true) => "PM",
+                    (true, false) => "A",
+                    (false, false) => "P",
+                };
+                if *upper {
+                    out.push_str(text);
+                } else {
+                    out.push_str(&text.to_ascii_lowercase());
+                }
+            }
+            Token::Point if subsecond_places > 0 => {
+                let run = tokens[i + 1..]
+                    .iter()
+                    .take_while(|t| **t == Token::Digit('0'))
+                    .count();
+                if run > 0 {
+                    let digits = format!("{subsecond:0subsecond_places$}");
+                    out.push('.');
+                    out.push_str(&digits[..run.min(subsecond_places)]);
+                    i += run;
+                } else {
+                    out.push('.');
+                }
+            }
+            Token::Digit(c) => out.push(*c),
+            other => push_literal(&mut out, other),
+        }
+        i += 1;
+    }
+    out
+}
+
+fn pad(value: i64, width: usize) -> String {
+    format!("{value:0width$}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(value: f64, code: &str) -> String {
+        format_number(value, code, false).text
+    }
+
+    #[test]
+    fn formats_numbers_currency_and_percentages() {
+        assert_eq!(fmt(1234.5, "General"), "1234.5");
+        assert_eq!(fmt(1.0 / 3.0, "General"), "0.333333333");
+        assert_eq!(fmt(123456789012.0, "General"), "1.23457E+11");
+        assert_eq!(fmt(1234567.891, "#,##0.00"), "1,234,567.89");
+        assert_eq!(fmt(1.005, "0.00"), "1.01");
+        assert_eq!(fmt(0.0725, "0.00%"), "7.25%");
+        assert_eq!(fmt(-1234.5, "$#,##0.00"), "-$1,234.50");
+        assert_eq!(fmt(1234.5, "[$€-407] #,##0.00"), "€ 1,234.50");
+        assert_eq!(fmt(12_345_678.0, "#,##0.0,,\"M\""), "12.3M");
+        assert_eq!(fmt(0.5, "#.##"), ".5");
+        assert_eq!(fmt(7.0, "000"), "007");
+        assert_eq!(fmt(12345.0, "0.00E+00"), "1.23E+04");
+        assert_eq!(fmt(12345.0, "##0.0E+0"), "12.3E+3");
+        assert_eq!(fmt(5551234567.0, "(000) 000-0000"), "(555) 123-4567");
+    }
+
+    #[test]
+    fn picks_sections_colors_and_conditions() {
+        let code = "#,##0.00;[Red](#,##0.00);\"zero\";\"note: \"@";
+        assert_eq!(fmt(-42.0, code), "(42.00)");
+        assert_eq!(
+            format_number(-42.0, code, false).color.as_deref(),
+            Some("#FF0000")
+        );
+        assert_eq!(fmt(0.0, code), "zero");
+        assert_eq!(format_text("hi", code).text, "note: hi");
+        assert_eq!(fmt(-3.0, "0;-0;;@"), "-3");
+        assert_eq!(fmt(0.0, "0;-0;;@"), "");
+
+        let conditional = "[Blue][>=1000]#,##0;[Red][<0]-0;0";
+        assert_eq!(fmt(2500.0, conditional), "2,500");
+        assert_eq!(
+            format_number(2500.0, conditional, false).color.as_deref(),
+            Some("#0000FF")
+        );
+        assert_eq!(fmt(-5.0, conditional), "--5");
+        assert_eq!(fmt(12.0, conditional), "12");
+        assert_eq!(fmt(3.0, "@"), "3");
+        assert_eq!(format_text("abc", "0.00").text, "abc");
+    }
+
+    #[test]
+    fn formats_fractions() {
+        assert_eq!(fmt(1.25, "# ?/?"), "1 1/4");
+        assert_eq!(fmt(0.3333, "# ??/??"), "  1/3 ");
+        assert_eq!(fmt(2.0, "# ?/?"), "2");
+        assert_eq!(fmt(0.625, "?/8"), "5/8");
+        assert_eq!(fmt(1.5, "?/?"), "3/2");
+    }
+
+    #[test]
+    fn formats_dates_times_and_elapsed() {
+        assert_eq!(fmt(45305.0, "yyyy-mm-dd"), "2024-01-14");
+        assert_eq!(fmt(45305.0, "m/d/yyyy"), "1/14/2024");
+        assert_eq!(fmt(45305.0, "dddd, mmmm d"), "Sunday, January 14");
+        assert_eq!(fmt(45305.0, "d-mmm-yy"), "14-Jan-24");
+        assert_eq!(fmt(60.0, "yyyy-mm-dd"), "1900-02-29");
+        assert_eq!(fmt(0.0, "yyyy-mm-dd"), "1900-01-00");
+        assert_eq!(fmt(0.75, "h:mm AM/PM"), "6:00 PM");
+        assert_eq!(fmt(0.5 + 30.5 / 86_400.0, "hh:mm:ss.0"), "12:00:30.5");
+        assert_eq!(fmt(1.5 + 90.0 / 86_400.0, "[h]:mm:ss"), "36:01:30");
+        assert_eq!(fmt(0.0625, "[mm]:ss"), "90:00");
+        assert_eq!(format_number(0.0, "yyyy-mm-dd", true).text, "1904-01-01");
+        assert_eq!(fmt(-1.0, "yyyy-mm-dd"), "###########");
+    }
+}
@@ -0,0 +1,784 @@
+//! Pivot tables: reading pivot definitions and their caches out of an xlsx
+//! package.
+//!
+//! A worksheet relates to its `xl/pivotTables/pivotTableN.xml` parts, each of
+//! which relates to the pivot cache it was built from. The cache definition
+//! names the source and lists the fields with their shared items; the cache
+//! records part holds a snapshot of the source rows as of the last refresh.
+//! Aggregating those records reproduces the pivot's figures without a
+//! recalculation, which is what [`aggregate`] does.
+
+use crate::backends::is_ooxml;
+use crate::charts::{XmlNode, relationships};
+use crate::comments::{read_string, sheet_parts};
+use anyhow::{Result, anyhow};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::path::Path;
+use zip::ZipArchive;
+
+pub(crate) const PIVOT_TABLE_REL: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/pivotTable";
+pub(crate) const PIVOT_CACHE_REL: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/pivotCacheDefinition";
+pub(crate) const PIVOT_RECORDS_REL: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/pivotCacheRecords";
+
+/// Field index used in row/column field lists for the "Values" pseudo-field.
+const VALUES_FIELD: i64 = -2;
+
+/// One value in a pivot cache, shared item or record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CacheValue {
+    Missing,
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    /// ISO 8601 date-time as stored in the cache.
+    Date(String),
+    Error(String),
+}
+
+impl CacheValue {
+    fn from_element(name: &str, value: Option<&str>) -> Option<CacheValue> {
+        let value = value.unwrap_or_default();
+        Some(match name {
+            "m" => CacheValue::Missing,
+            "n" => CacheValue::Number(value.parse().ok()?),
+            "s" => CacheValue::Text(value.to_string()),
+            "b" => CacheValue::Bool(value == "1" || value.eq_ignore_ascii_case("true")),
+            "d" => CacheValue::Date(value.to_string()),
+            "e" => CacheValue::Error(value.to_string()),
+            _ => return None,
+        })
+    }
+
+    /// The value as a row or column label, the way Excel captions it.
+    pub fn label(&self) -> String {
+        match self {
+            CacheValue::Missing => "(blank)".to_string(),
+            CacheValue::Number(n) => {
+                if n.fract() == 0.0 && n.abs() < 1e15 {
+                    format!("{}", *n as i64)
+                } else {
+                    n.to_string()
+                }
+            }
+            CacheValue::Text(s) | CacheValue::Date(s) | CacheValue::Error(s) => s.clone(),
+            CacheValue::Bool(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+        }
+    }
+
+    pub fn number(&self) -> Option<f64> {
+        match self {
+            CacheValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CacheField {
+    pub name: String,
+    pub shared_items: Vec<CacheValue>,
+    /// Formula of a calculated field; such fields have no record values.
+    pub formula: Option<String>,
+    /// The field groups another field (dates by month, numbers by range).
+    pub grouped: bool,
+}
+
+/// Where a pivot cache reads its rows from.
+#[derive(Debug, Clone, Default)]
+pub struct PivotSource {
+    /// worksheet | external | consolidation | scenario
+    pub kind: String,
+    pub sheet: Option<String>,
+    pub range: Option<String>,
+    /// Table or defined name, when the source is named instead of a range.
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PivotCacheDef {
+    pub part: String,
+    pub source: PivotSource,
+    pub fields: Vec<CacheField>,
+    pub record_count: Option<u64>,
+    pub refreshed_by: Option<String>,
+    pub records_part: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PivotFieldDef {
+    /// axisRow | axisCol | axisPage | axisValues
+    pub axis: Option<String>,
+    /// Shared item indexes in display order.
+    pub items: Vec<u32>,
+    /// Shared item indexes hidden by the field's item filter.
+    pub hidden: Vec<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PageFieldDef {
+    pub field: usize,
+    /// Position in the pivot field's item list of the selected item; `None`
+    /// means "(All)".
+    pub item: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DataFieldDef {
+    pub name: String,
+    pub field: usize,
+    /// sum | count | average | max | min | product | countNums | stdDev |
+    /// stdDevp | var | varp
+    pub subtotal: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct FilterDef {
+    pub field: usize,
+    /// e.g. captionEqual, valueGreaterThan, count
+    pub kind: String,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PivotTableDef {
+    pub part: String,
+    pub name: String,
+    /// Top-left-anchored output range, e.g. `A3:D12`.
+    pub location: String,
+    pub fields: Vec<PivotFieldDef>,
+    pub row_fields: Vec<usize>,
+    pub col_fields: Vec<usize>,
+    pub page_fields: Vec<PageFieldDef>,
+    pub data_fields: Vec<DataFieldDef>,
+    pub filters: Vec<FilterDef>,
+    pub cache: PivotCacheDef,
+}
+
+impl PivotTableDef {
+    pub fn field_name(&self, field: usize) -> String {
+        self.cache
+            .fields
+            .get(field)
+            .map(|f| f.name.clone())
+            .unwrap_or_else(|| format!("field {field}"))
+    }
+
+    /// Label of a shared item of `field`.
+    pub fn item_label(&self, field: usize, item: u32) -> String {
+        self.cache
+            .fields
+            .get(field)
+            .and_then(|f| f.shared_items.get(item as usize))
+            .map(CacheValue::label)
+            .unwrap_or_else(|| format!("item {item}"))
+    }
+
+    /// A value field's caption; Excel writes one, but fall back to its
+    /// default form (`Sum of Units`) when it did not.
+    pub fn data_caption(&self, data: &DataFieldDef) -> String {
+        if !data.name.is_empty() {
+            return data.name.clone();
+        }
+        let verb = match data.subtotal.as_str() {
+            "count" | "countNums" => "Count",
+            "average" => "Average",
+            "max" => "Max",
+            "min" => "Min",
+            "product" => "Product",
+            "stdDev" => "StdDev",
+            "stdDevp" => "StdDevp",
+            "var" => "Var",
+            "varp" => "Varp",
+            _ => "Sum",
+        };
+        format!("{} of {}", verb, self.field_name(data.field))
+    }
+
+    /// The shared item a page field is filtered to, if any.
+    pub fn page_selection(&self, page: &PageFieldDef) -> Option<u32> {
+        let item = page.item?;
+        self.fields.get(page.field)?.items.get(item).copied()
+    }
+}
+
+/// Pivot tables of every sheet, in workbook order. Packages other than
+/// xlsx/xlsm have none.
+pub fn read_pivot_tables(path: &Path) -> Result<Vec<(String, Vec<PivotTableDef>)>> {
+    if !is_ooxml(path) {
+        return Ok(Vec::new());
+    }
+    let mut zip = ZipArchive::new(File::open(path)?)?;
+    let mut caches: HashMap<String, PivotCacheDef> = HashMap::new();
+    let mut out = Vec::new();
+    for (sheet_name, sheet_path) in sheet_parts(&mut zip)? {
+        let mut tables = Vec::new();
+        for (rel_type, part) in relationships(&mut zip, &sheet_path)?.into_values() {
+            if rel_type != PIVOT_TABLE_REL {
+                continue;
+            }
+            let Some(xml) = read_string(&mut zip, &part)? else {
+                continue;
+            };
+            let mut table = parse_pivot_table(&XmlNode::parse(&xml)?)?;
+            table.part = part.clone();
+            let cache_part = relationships(&mut zip, &part)?
+                .into_values()
+                .find(|(t, _)| t == PIVOT_CACHE_REL)
+                .map(|(_, target)| target);
+            if let Some(cache_part) = cache_part {
+                if !caches.contains_key(&cache_part) {
+                    let cache = read_cache_definition(&mut zip, &cache_part)?;
+                    caches.insert(cache_part.clone(), cache);
+                }
+                table.cache = caches[&cache_part].clone();
+            }
+            tables.push(table);
+        }
+        // Relationship ids carry no meaning; list by output position.
+        tables.sort_by_key(|t| top_left(&t.location));
+        out.push((sheet_name, tables));
+    }
+    Ok(out)
+}
+
+/// (row, column) of a location's first cell, for ordering.
+fn top_left(location: &str) -> (u32, u32) {
+    let first = location.split(':').next().unwrap_or_default();
+    let letters = first.trim_start_matches('$');
+    let split = letters
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(letters.len());
+    let col = letters[..split].bytes().fold(0u32, |acc, b| {
+        acc * 26 + u32::from(b.to_ascii_uppercase() - b'A' + 1)
+    });
+    let row = letters[split..]
+        .trim_start_matches('$')
+        .parse()
+        .unwrap_or(0);
+    (row, col)
+}
+
+fn read_cache_definition(zip: &mut ZipArchive<File>, part: &str) -> Result<PivotCacheDef> {
+    let xml =
+        read_string(zip, part)?.ok_or_else(|| anyhow!("pivot cache part '{}' is missing", part))?;
+    let mut cache = parse_cache_definition(&XmlNode::parse(&xml)?)?;
+    cache.part = part.to_string();
+    cache.records_part = relationships(zip, part)?
+        .into_values()
+        .find(|(t, _)| t == PIVOT_RECORDS_REL)
+        .map(|(_, target)| target);
+    Ok(cache)
+}
+
+fn parse_index(node: &XmlNode, key: &str) -> Option<i64> {
+    node.attr(key).and_then(|v| v.parse().ok())
+}
+
+pub(crate) fn parse_cache_definition(root: &XmlNode) -> Result<PivotCacheDef> {
+    let def = root
+        .child("pivotCacheDefinition")
+        .ok_or_else(|| anyhow!("not a pivot cache definition"))?;
+    let mut source = PivotSource::default();
+    if let Some(node) = def.child("cacheSource") {
+        source.kind = node.attr("type").unwrap_or("worksheet").to_string();
+        if let Some(ws) = node.child("worksheetSource") {
+            source.sheet = ws.attr("sheet").map(str::to_string);
+            source.range = ws.attr("ref").map(str::to_string);
+            source.name = ws.attr("name").map(str::to_string);
+        }
+    }
+    let fields = def
+        .child("cacheFields")
+        .map(|list| {
+            list.children_named("cacheField")
+                .map(|field| CacheField {
+                    name: field.attr("name").unwrap_or_default().to_string(),
+                    shared_items: field
+                        .child("sharedItems")
+                        .map(|items| {
+                            items
+                                .children
+                                .iter()
+                                .filter_map(|item| {
+                                    CacheValue::from_element(&item.name, item.attr("v"))
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    formula: field.attr("formula").map(str::to_string),
+                    grouped: field.child("fieldGroup").is_some(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(PivotCacheDef {
+        part: String::new(),
+        source,
+        fields,
+        record_count: def.attr("recordCount").and_then(|v| v.parse().ok()),
+        refreshed_by: def.attr("refreshedBy").map(str::to_string),
+        records_part: None,
+    })
+}
+
+pub(crate) fn parse_pivot_table(root: &XmlNode) -> Result<PivotTableDef> {
+    let def = root
+        .child("pivotTableDefinition")
+        .ok_or_else(|| anyhow!("not a pivot table definition"))?;
+    let field_list = |name: &str| -> Vec<usize> {
+        def.child(name)
+            .map(|list| {
+                list.children_named("field")
+                    .filter_map(|f| parse_index(f, "x"))
+                    .filter(|x| *x != VALUES_FIELD && *x >= 0)
+                    .map(|x| x as usize)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let fields = def
+        .child("pivotFields")
+        .map(|list| {
+            list.children_named("pivotField")
+                .map(|field| {
+                    let mut parsed = PivotFieldDef {
+                        axis: field.attr("axis").map(str::to_string),
+                        ..Default::default()
+                    };
+                    let items = field.child("items").into_iter().flat_map(|i| &i.children);
+                    for item in items.filter(|i| i.attr("t").is_none()) {
+                        let Some(x) = parse_index(item, "x") else {
+                            continue;
+                        };
+                        parsed.items.push(x as u32);
+                        if matches!(item.attr("h"), Some("1" | "true")) {
+                            parsed.hidden.push(x as u32);
+                        }
+                    }
+                    parsed
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let page_fields = def
+        .child("pageFields")
+        .map(|list| {
+            list.children_named("pageField")
+                .filter_map(|p| {
+                    Some(PageFieldDef {
+                        field: usize::try_from(parse_index(p, "fld")?).ok()?,
+                        item: parse_index(p, "item").and_then(|i| usize::try_from(i).ok()),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let data_fields = def
+        .child("dataFields")
+        .map(|list| {
+            list.children_named("dataField")
+                .filter_map(|d| {
+                    Some(DataFieldDef {
+                        name: d.attr("name").unwrap_or_default().to_string(),
+                        field: usize::try_from(parse_index(d, "fld")?).ok()?,
+                        subtotal: d.attr("subtotal").unwrap_or("sum").to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let filters = def
+        .child("filters")
+        .map(|list| {
+            list.children_named("filter")
+                .filter_map(|f| {
+                    Some(FilterDef {
+                        field: usize::try_from(parse_index(f, "fld")?).ok()?,
+                        kind: f.attr("type").unwrap_or_default().to_string(),
+                        value: f.attr("stringValue1").map(str::to_string),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(PivotTableDef {
+        part: String::new(),
+        name: def.attr("name").unwrap_or_default().to_string(),
+        location: def
+            .child("location")
+            .and_then(|l| l.attr("ref"))
+            .unwrap_or_default()
+            .to_string(),
+        fields,
+        row_fields: field_list("rowFields"),
+        col_fields: field_list("colFields"),
+        page_fields,
+        data_fields,
+        filters,
+        cache: PivotCacheDef::default(),
+    })
+}
+
+/// A cache record value: an index into the field's shared items, or inline.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordValue {
+    Shared(u32),
+    Inline(CacheValue),
+}
+
+/// The cache records behind `table`, as of the pivot's last refresh.
+pub fn read_cache_records(path: &Path, table: &PivotTableDef) -> Result<Vec<Vec<RecordValue>>> {
+    let part = table.cache.records_part.as_deref().ok_or_else(|| {
+        anyhow!(
+            "pivot table '{}' was saved without cache records; refresh it in Excel with \
+             'save source data with file' enabled",
+            table.name
+        )
+    })?;
+    let mut zip = ZipArchive::new(File::open(path)?)?;
+    let xml = read_string(&mut zip, part)?
+        .ok_or_else(|| anyhow!("pivot cache records part '{}' is missing", part))?;
+    parse_cache_records(&xml)
+}
+
+/// Records are the bulk of a cache, so they are streamed rather than built
+/// into a tree.
+pub(crate) fn parse_cache_records(xml: &str) -> Result<Vec<Vec<RecordValue>>> {
+    fn value(e: &BytesStart) -> Result<Option<RecordValue>> {
+        let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+        let mut v = None;
+        for attr in e.attributes() {
+            let attr = attr?;
+            if attr.key.local_name().as_ref() == b"v" {
+                v = Some(attr.unescape_value()?.to_string());
+            }
+        }
+        if name == "x" {
+            return Ok(v.and_then(|v| v.parse().ok()).map(RecordValue::Shared));
+        }
+        Ok(CacheValue::from_element(&name, v.as_deref()).map(RecordValue::Inline))
+    }
+
+    let mut reader = Reader::from_str(xml);
+    let mut records = Vec::new();
+    let mut current: Option<Vec<RecordValue>> = None;
+    loop {
+        match reader.read_event()? {
+            Event::Start(ref e) if e.local_name().as_ref() == b"r" => current = Some(Vec::new()),
+            Event::Empty(ref e) if e.local_name().as_ref() == b"r" => records.push(Vec::new()),
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                if let Some(record) = current.as_mut()
+                    && let Some(v) = value(e)?
+                {
+                    record.push(v);
+                }
+            }
+            Event::End(ref e) if e.local_name().as_ref() == b"r" => {
+                if let Some(record) = current.take() {
+                    records.push(record);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(records)
+}
+
+/// One output line of a pivot: the row and column items it sits under and
+/// one figure per data field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PivotLine {
+    pub row_items: Vec<String>,
+    pub column_items: Vec<String>,
+    pub values: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PivotResult {
+    pub lines: Vec<PivotLine>,
+    pub grand_totals: Vec<Option<f64>>,
+    /// Records left after page fields and hidden items were applied.
+    pub records_used: usize,
+    pub warnings: Vec<String>,
+}
+
+/// Grouping key for one field of a record: display position, then label.
+type ItemKey = (usize, String);
+
+/// Recompute the pivot's figures from its cache records, honouring page
+/// field selections and hidden items. Label and value filters are reported
+/// but not applied.
+pub fn aggregate(table: &PivotTableDef, records: &[Vec<RecordValue>]) -> PivotResult {
+    let mut warnings = Vec::new();
+    for filter in &table.filters {
+        warnings.push(format!(
+            "{} filter on '{}' is not applied to the cached figures",
+            filter.kind,
+            table.field_name(filter.field)
+        ));
+    }
+    for field in table.row_fields.iter().chain(&table.col_fields) {
+        if table.cache.fields.get(*field).is_some_and(|f| f.grouped) {
+            warnings.push(format!(
+                "field '{}' is grouped; items are listed by their cached group labels",
+                table.field_name(*field)
+            ));
+        }
+    }
+    for data in &table.data_fields {
+        if table
+            .cache
+            .fields
+            .get(data.field)
+            .is_some_and(|f| f.formula.is_some())
+        {
+            warnings.push(format!(
+                "'{}' uses a calculated field, which has no cached records",
+                table.data_caption(data)
+            ));
+        }
+    }
+
+    let value_of = |record: &[RecordValue], field: usize| -> Option<(Option<u32>, CacheValue)> {
+        match record.get(field)? {
+            RecordValue::Shared(idx) => {
+                let item = table
+                    .cache
+                    .fields
+                    .get(field)?
+                    .shared_items
+                    .get(*idx as usize)?;
+                Some((Some(*idx), item.clone()))
+            }
+            RecordValue::Inline(v) => Some((None, v.clone())),
+        }
+    };
+    let key_of = |record: &[RecordValue], field: usize| -> ItemKey {
+        match value_of(record, field) {
+            Some((Some(idx), value)) => {
+                let position = table
+                    .fields
+                    .get(field)
+                    .and_then(|f| f.items.iter().position(|i| *i == idx))
+                    .unwrap_or(idx as usize);
+                (position, value.label())
+            }
+            Some((None, value)) => (usize::MAX, value.label()),
+            None => (usize::MAX, String::new()),
+        }
+    };
+    let selections: Vec<(usize, u32)> = table
+        .page_fields
+        .iter()
+        .filter_map(|page| Some((page.field, table.page_selection(page)?)))
+        .collect();
+    let visible = |record: &[RecordValue]| -> bool {
+        let shared = |field: usize| match record.get(field) {
+            Some(RecordValue::Shared(idx)) => Some(*idx),
+            _ => None,
+        };
+        selections
+            .iter()
+            .all(|(field, item)| shared(*field) == Some(*item))
+            && table.fields.iter().enumerate().all(|(field, def)| {
+                def.hidden.is_empty() || shared(field).is_none_or(|i| !def.hidden.contains(&i))
+            })
+    };
+
+    let mut groups: BTreeMap<(Vec<ItemKey>, Vec<ItemKey>), Vec<Accumulator>> = BTreeMap::new();
+    let mut totals: Vec<Accumulator> = vec![Accumulator::default(); table.data_fields.len()];
+    let mut records_used = 0;
+    for record in records.iter().filter(|r| visible(r)) {
+        records_used += 1;
+        let key = (
+            table
+                .row_fields
+                .iter()
+                .map(|f| key_of(record, *f))
+                .collect(),
+            table
+                .col_fields
+                .iter()
+                .map(|f| key_of(record, *f))
+                .collect(),
+        );
+        let accs = groups
+            .entry(key)
+            .or_insert_with(|| vec![Accumulator::default(); table.data_fields.len()]);
+        for (idx, data) in table.data_fields.iter().enumerate() {
+            let value = value_of(record, data.field).map(|(_, v)| v);
+            accs[idx].add(value.as_ref());
+            totals[idx].add(value.as_ref());
+        }
+    }
+
+    let finish = |accs: &[Accumulator]| -> Vec<Option<f64>> {
+        accs.iter()
+            .zip(&table.data_fields)
+            .map(|(acc, data)| acc.finish(&data.subtotal))
+            .collect()
+    };
+    let lines = groups
+        .into_iter()
+        .map(|((rows, cols), accs)| PivotLine {
+            row_items: rows.into_iter().map(|(_, label)| label).collect(),
+            column_items: cols.into_iter().map(|(_, label)| label).collect(),
+            values: finish(&accs),
+        })
+        .collect();
+    PivotResult {
+        lines,
+        grand_totals: finish(&totals),
+        records_used,
+        warnings,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Accumulator {
+    /// Non-empty values of any type, for `count`.
+    count: usize,
+    numbers: Vec<f64>,
+}
+
+impl Accumulator {
+    fn add(&mut self, value: Option<&CacheValue>) {
+        match value {
+            None | Some(CacheValue::Missing) => {}
+            Some(v) => {
+                self.count += 1;
+                if let Some(n) = v.number() {
+                    self.numbers.push(n);
+                }
+            }
+        }
+    }
+
+    fn finish(&self, subtotal: &str) -> Option<f64> {
+        let n = self.numbers.len() as f64;
+        let sum: f64 = self.numbers.iter().sum();
+        let variance = |sample: bool| {
+            let denom = if sample { n - 1.0 } else { n };
+            if denom <= 0.0 {
+                return None;
+            }
+            let mean = sum / n;
+            Some(self.numbers.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / denom)
+        };
+        match subtotal {
+            "count" => Some(self.count as f64),
+            "countNums" => Some(n),
+            "average" => (n > 0.0).then(|| sum / n),
+            "max" => self.numbers.iter().copied().reduce(f64::max),
+            "min" => self.numbers.iter().copied().reduce(f64::min),
+            "product" => (n > 0.0).then(|| self.numbers.iter().product()),
+            "stdDev" => variance(true).map(f64::sqrt),
+            "stdDevp" => variance(false).map(f64::sqrt),
+            "var" => variance(true),
+            "varp" => variance(false),
+            _ => Some(sum),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CACHE: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<pivotCacheDefinition xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" r:id="rId1" refreshedBy="ana" recordCount="5">
+  <cacheSource type="worksheet"><worksheetSource ref="A1:C6" sheet="Data"/></cacheSource>
+  <cacheFields count="3">
+    <cacheField name="Region" numFmtId="0"><sharedItems count="2"><s v="North"/><s v="South"/></sharedItems></cacheField>
+    <cacheField name="Quarter" numFmtId="0"><sharedItems count="2"><s v="Q1"/><s v="Q2"/></sharedItems></cacheField>
+    <cacheField name="Units" numFmtId="0"><sharedItems containsNumber="1" minValue="1" maxValue="9"/></cacheField>
+  </cacheFields>
+</pivotCacheDefinition>"#;
+
+    const RECORDS: &str = r#"<pivotCacheRecords xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" count="5">
+  <r><x v="0"/><x v="0"/><n v="4"/></r>
+  <r><x v="0"/><x v="1"/><n v="6"/></r>
+  <r><x v="1"/><x v="0"/><n v="1"/></r>
+  <r><x v="1"/><x v="1"/><n v="9"/></r>
+  <r><x v="0"/><x v="0"/><m/></r>
+</pivotCacheRecords>"#;
+
+    fn table(extra_fields: &str, page_fields: &str) -> PivotTableDef {
+        let xml = format!(
+            r#"<pivotTableDefinition xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" name="SalesPivot" cacheId="1" dataCaption="Values">
+  <location ref="A3:B6" firstHeaderRow="1" firstDataRow="1" firstDataCol="1"/>
+  <pivotFields count="3">
+    <pivotField axis="axisRow" showAll="0"><items count="3"><item x="1"/><item x="0"/><item t="default"/></items></pivotField>
+    {extra_fields}
+    <pivotField dataField="1" showAll="0"/>
+  </pivotFields>
+  <rowFields count="1"><field x="0"/></rowFields>
+  {page_fields}
+  <dataFields count="2"><dataField name="Sum of Units" fld="2" baseField="0" baseItem="0"/><dataField name="Count of Units" fld="2" subtotal="count"/></dataFields>
+</pivotTableDefinition>"#
+        );
+        let mut table = parse_pivot_table(&XmlNode::parse(&xml).unwrap()).unwrap();
+        table.cache = parse_cache_definition(&XmlNode::parse(CACHE).unwrap()).unwrap();
+        table
+    }
+
+    #[test]
+    fn aggregates_records_in_item_order() {
+        let table = table(r#"<pivotField showAll="0"/>"#, "");
+        assert_eq!(table.cache.source.sheet.as_deref(), Some("Data"));
+        assert_eq!(table.cache.source.range.as_deref(), Some("A1:C6"));
+        assert_eq!(table.cache.record_count, Some(5));
+        assert_eq!(table.location, "A3:B6");
+        assert_eq!(table.row_fields, vec![0]);
+        assert_eq!(table.data_fields[0].subtotal, "sum");
+
+        let records = parse_cache_records(RECORDS).unwrap();
+        assert_eq!(records.len(), 5);
+        let result = aggregate(&table, &records);
+        // Items are listed South first, as the pivot field orders them.
+        assert_eq!(
+            result.lines,
+            vec![
+                PivotLine {
+                    row_items: vec!["South".into()],
+                    column_items: vec![],
+                    values: vec![Some(10.0), Some(2.0)],
+                },
+                PivotLine {
+                    row_items: vec!["North".into()],
+                    column_items: vec![],
+                    values: vec![Some(10.0), Some(2.0)],
+                },
+            ]
+        );
+        assert_eq!(result.grand_totals, vec![Some(20.0), Some(4.0)]);
+    }
+
+    #[test]
+    fn page_fields_and_hidden_items_narrow_the_records() {
+        let page = table(
+            r#"<pivotField axis="axisPage" showAll="0"><items count="3"><item x="0"/><item x="1"/><item t="default"/></items></pivotField>"#,
+            r#"<pageFields count="1"><pageField fld="1" item="1" hier="-1"/></pageFields>"#,
+        );
+        let records = parse_cache_records(RECORDS).unwrap();
+        let result = aggregate(&page, &records);
+        assert_eq!(result.records_used, 2);
+        assert_eq!(result.grand_totals, vec![Some(15.0), Some(2.0)]);
+
+        let hidden = table(
+            r#"<pivotField axis="axisCol" showAll="0"><items count="3"><item x="0" h="1"/><item x="1"/><item t="default"/></items></pivotField>"#,
+            "",
+        );
+        let result = aggregate(&hidden, &records);
+        assert_eq!(result.records_used, 2);
+        assert_eq!(result.lines[0].row_items, vec!["South".to_string()]);
+        assert_eq!(result.lines[0].values, vec![Some(9.0), Some(1.0)]);
+    }
+}
@@ -0,0 +1,128 @@
+//! Progress notifications and cooperative cancellation for long-running tools.
+//!
+//! A [`ProgressReporter`] is built from the request context. When the client
+//! sent a progress token, [`ProgressReporter::report`] forwards
+//! `notifications/progress` in order through a background task. Its
+//! cancellation token is the request's, which rmcp cancels when the client
+//! sends `notifications/cancelled`.
+//!
+//! Long operations call [`ProgressReporter::check_cancelled`] between stages,
+//! checks or chunks and undo their partial work when it fails, so a cancelled
+//! tool leaves files and forks as they were. [`ProgressReporter::none`]
+//! reports nothing and is never cancelled, for callers outside a request.
+
+use anyhow::Result;
+use rmcp::RoleServer;
+use rmcp::model::ProgressNotificationParam;
+use rmcp::service::RequestContext;
+use std::fmt;
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+/// Returned by a tool that stopped because the client cancelled the request.
+#[derive(Debug, Error)]
+#[error("operation cancelled by client")]
+pub struct CancelledError;
+
+/// One progress step: `progress` of `total` units done.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressUpdate {
+    pub progress: usize,
+    pub total: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Clone, Default)]
+pub struct ProgressReporter {
+    updates: Option<mpsc::UnboundedSender<ProgressUpdate>>,
+    cancel: CancellationToken,
+}
+
+impl fmt::Debug for ProgressReporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressReporter")
+            .field("reporting", &self.updates.is_some())
+            .field("cancelled", &self.cancel.is_cancelled())
+            .finish()
+    }
+}
+
+impl ProgressReporter {
+    /// Reporter that sends nothing and is never cancelled.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Reporter for an MCP request: progress goes to the client when it asked
+    /// for it, and cancellation follows the request.
+    pub fn from_context(context: &RequestContext<RoleServer>) -> Self {
+        let updates = context.meta.get_progress_token().map(|token| {
+            let (sender, mut receiver) = mpsc::unbounded_channel::<ProgressUpdate>();
+            let peer = context.peer.clone();
+            tokio::spawn(async move {
+                while let Some(update) = receiver.recv().await {
+                    let param = ProgressNotificationParam {
+                        progress_token: token.clone(),
+                        progress: update.progress as f64,
+                        total: update.total.map(|total| total as f64),
+                        message: Some(update.message),
+                    };
+                    if let Err(error) = peer.notify_progress(param).await {
+                        debug!(?error, "failed to send progress notification");
+                        break;
+                    }
+                }
+            });
+            sender
+        });
+        Self {
+            updates,
+            cancel: context.ct.clone(),
+        }
+    }
+
+    /// Reporter whose updates are delivered on the returned channel and which
+    /// is cancelled with `cancel`, such as a [`ShutdownCoordinator`] token.
+    ///
+    /// [`ShutdownCoordinator`]: crate::shutdown::ShutdownCoordinator
+    pub fn channel(cancel: CancellationToken) -> (Self, mpsc::UnboundedReceiver<ProgressUpdate>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (
+            Self {
+                updates: Some(sender),
+                cancel,
+            },
+            receiver,
+        )
+    }
+
+    pub fn report(&self, progress: usize, total: usize, message: impl Into<String>) {
+        if let Some(updates) = &self.updates {
+            let _ = updates.send(ProgressUpdate {
+                progress,
+                total: Some(total),
+                message: message.into(),
+            });
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// `Err(CancelledError)` once the request has been cancelled.
+    pub fn check_cancelled(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(CancelledError.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Resolves when the request is cancelled.
+    pub async fn cancelled(&self) {
+        self.cancel.cancelled().await
+    }
+}
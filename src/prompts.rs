@@ -0,0 +1,249 @@
+//! MCP prompts: the workflows in the ontology's prompt catalog
+//! (`crate::generated::mcp_prompts`), filtered by server configuration and
+//! rendered with the client's arguments.
+//!
+//! A prompt is offered only when its required feature is on and every tool its
+//! steps call is enabled. Arguments that name a workbook, fork or sheet are
+//! checked against the workspace before rendering, so a typo surfaces as an
+//! error instead of a workflow that fails halfway.
+
+use crate::completion::{self, CompletionSource};
+use crate::config::ServerConfig;
+use crate::generated::mcp_prompts::{PROMPTS, PromptArgumentDef, PromptDef};
+use crate::model::WorkbookId;
+use crate::state::AppState;
+use anyhow::{Result, anyhow, bail};
+use rmcp::model::{Prompt, PromptArgument};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+fn feature_enabled(config: &ServerConfig, feature: &str) -> bool {
+    match feature {
+        "recalc" => cfg!(feature = "recalc") && config.recalc_enabled,
+        "vba" => config.vba_enabled,
+        _ => false,
+    }
+}
+
+fn is_available(def: &PromptDef, config: &ServerConfig) -> bool {
+    def.requires_feature
+        .is_none_or(|feature| feature_enabled(config, feature))
+        && def
+            .steps
+            .iter()
+            .filter_map(|step| step.tool)
+            .all(|tool| config.is_tool_enabled(tool))
+}
+
+/// Prompts offered under this configuration, in catalog order.
+pub fn available_prompts(config: &ServerConfig) -> Vec<&'static PromptDef> {
+    PROMPTS
+        .iter()
+        .filter(|def| is_available(def, config))
+        .collect()
+}
+
+fn find_prompt(config: &ServerConfig, name: &str) -> Result<&'static PromptDef> {
+    let def = PROMPTS
+        .iter()
+        .find(|def| def.name == name)
+        .ok_or_else(|| anyhow!("unknown prompt '{name}'"))?;
+    if !is_available(def, config) {
+        bail!("prompt '{name}' is not available: a feature or tool it needs is disabled");
+    }
+    Ok(def)
+}
+
+pub fn list_prompts(config: &ServerConfig) -> Vec<Prompt> {
+    available_prompts(config)
+        .into_iter()
+        .map(|def| {
+            let arguments = def
+                .arguments
+                .iter()
+                .map(|arg| PromptArgument {
+                    name: arg.name.to_string(),
+                    title: None,
+                    description: Some(argument_description(arg)),
+                    required: Some(arg.required),
+                })
+                .collect();
+            let mut prompt = Prompt::new(def.name, Some(def.description), Some(arguments));
+            prompt.title = Some(def.title.to_string());
+            prompt
+        })
+        .collect()
+}
+
+fn argument_description(arg: &PromptArgumentDef) -> String {
+    let mut description = arg.description.to_string();
+    if !arg.choices.is_empty() {
+        let _ = write!(description, " (one of: {})", arg.choices.join(", "));
+    }
+    if let Some(default) = arg.default {
+        let _ = write!(description, " [default: {default}]");
+    }
+    description
+}
+
+/// A prompt rendered into the instructions sent to the client.
+#[derive(Debug, Clone)]
+pub struct RenderedPrompt {
+    pub description: String,
+    pub text: String,
+}
+
+/// Validate `arguments` against the prompt and render its steps.
+pub async fn render_prompt(
+    state: &AppState,
+    name: &str,
+    arguments: &HashMap<String, String>,
+) -> Result<RenderedPrompt> {
+    let config = state.config();
+    let def = find_prompt(&config, name)?;
+
+    if let Some(unknown) = arguments
+        .keys()
+        .find(|key| !def.arguments.iter().any(|arg| arg.name == key.as_str()))
+    {
+        bail!("prompt '{name}' has no argument '{unknown}'");
+    }
+
+    let mut values: HashMap<&str, String> = HashMap::new();
+    for arg in def.arguments {
+        let given = arguments
+            .get(arg.name)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty());
+        let value = match (given, arg.default) {
+            (Some(value), _) => value.to_string(),
+            (None, Some(default)) => default.to_string(),
+            (None, None) if arg.required => {
+                bail!("prompt '{name}' requires argument '{}'", arg.name)
+            }
+            (None, None) => continue,
+        };
+        if !arg.choices.is_empty() && !arg.choices.contains(&value.as_str()) {
+            bail!(
+                "argument '{}' must be one of: {} (got '{value}')",
+                arg.name,
+                arg.choices.join(", ")
+            );
+        }
+        values.insert(arg.name, value);
+    }
+
+    let context = check_references(state, def, &values).await?;
+
+    let mut text = format!("# {}\n\n{}.\n", def.title, def.description);
+    for line in context {
+        let _ = write!(text, "\n{line}");
+    }
+    text.push_str("\n\nSteps:\n");
+    let steps = def.steps.iter().filter(|step| {
+        step.when.is_none_or(|arg| values.contains_key(arg))
+            && step.unless.is_none_or(|arg| !values.contains_key(arg))
+    });
+    for (index, step) in steps.enumerate() {
+        let mut line = step.text.to_string();
+        for (arg, value) in &values {
+            line = line.replace(&format!("{{{arg}}}"), value);
+        }
+        let gate = if step.manual_gate { "STOP: " } else { "" };
+        let _ = writeln!(text, "{}. {gate}{line}", index + 1);
+    }
+
+    Ok(RenderedPrompt {
+        description: def.description.to_string(),
+        text,
+    })
+}
+
+/// Check that workbook, fork and sheet arguments exist and describe them for
+/// the rendered prompt.
+async fn check_references(
+    state: &AppState,
+    def: &PromptDef,
+    values: &HashMap<&str, String>,
+) -> Result<Vec<String>> {
+    let mut context = Vec::new();
+    let workbook = def
+        .arguments
+        .iter()
+        .find(|arg| arg.completion == Some("workbook_id"))
+        .and_then(|arg| values.get(arg.name));
+    let sheet_names = match workbook {
+        Some(workbook_id) => {
+            let workbook = state
+                .open_workbook(&WorkbookId(workbook_id.clone()))
+                .await
+                .map_err(|error| anyhow!("workbook '{workbook_id}' not found: {error}"))?;
+            let names = workbook.sheet_names();
+            context.push(format!(
+                "Workbook `{workbook_id}` has sheets: {}.",
+                names.join(", ")
+            ));
+            Some(names)
+        }
+        None => None,
+    };
+
+    for arg in def.arguments {
+        let Some(value) = values.get(arg.name) else {
+            continue;
+        };
+        match arg.completion {
+            Some("sheet_name") => {
+                if let Some(names) = &sheet_names
+                    && !names.contains(value)
+                {
+                    bail!("sheet '{value}' not found; available: {}", names.join(", "));
+                }
+            }
+            Some("fork_id") => {
+                if !completion::fork_ids(state).contains(value) {
+                    bail!("fork '{value}' not found");
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(context)
+}
+
+/// Values for a prompt argument that match `input`, best first. `context`
+/// holds arguments the client already filled in, so sheet names can be
+/// completed for the chosen workbook.
+pub async fn complete_argument(
+    state: &Arc<AppState>,
+    prompt: &str,
+    argument: &str,
+    input: &str,
+    context: &HashMap<String, String>,
+) -> Result<Vec<String>> {
+    let config = state.config();
+    let def = find_prompt(&config, prompt)?;
+    let arg = def
+        .arguments
+        .iter()
+        .find(|arg| arg.name == argument)
+        .ok_or_else(|| anyhow!("prompt '{prompt}' has no argument '{argument}'"))?;
+
+    if !arg.choices.is_empty() {
+        let choices = arg
+            .choices
+            .iter()
+            .map(|choice| choice.to_string())
+            .collect();
+        return Ok(completion::fuzzy_filter(input, choices));
+    }
+    match arg
+        .completion
+        .and_then(CompletionSource::from_name)
+        .or_else(|| CompletionSource::from_name(arg.name))
+    {
+        Some(source) => completion::complete(state, source, input, context).await,
+        None => Ok(Vec::new()),
+    }
+}
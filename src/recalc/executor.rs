@@ -1,5 +1,7 @@
 use anyhow::Result;
 use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Serialize;
 use std::path::Path;
 
 #[async_trait]
@@ -13,4 +15,18 @@ pub struct RecalcResult {
     pub duration_ms: u64,
     pub was_warm: bool,
     pub executor_type: &'static str,
+    /// Formula cells the backend could not evaluate (always empty for LibreOffice).
+    pub unsupported: Vec<UnsupportedFormula>,
+}
+
+/// A formula cell left with its previous cached value because it could not be evaluated.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct UnsupportedFormula {
+    pub sheet: String,
+    pub cell: String,
+    pub formula: String,
+    /// Function names (or constructs) the evaluator does not implement.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub functions: Vec<String>,
+    pub reason: String,
 }
@@ -80,6 +80,7 @@ impl RecalcExecutor for FireAndForgetExecutor {
             duration_ms: duration.as_millis() as u64,
             was_warm: false,
             executor_type: "fire_and_forget",
+            unsupported: Vec::new(),
         })
     }
 
@@ -5,6 +5,8 @@ mod executor;
 #[cfg(feature = "recalc")]
 mod fire_and_forget;
 #[cfg(feature = "recalc")]
+mod native;
+#[cfg(feature = "recalc")]
 mod pooled;
 #[cfg(feature = "recalc")]
 mod screenshot;
@@ -12,10 +14,14 @@ mod screenshot;
 #[cfg(feature = "recalc")]
 pub use backend::{LibreOfficeBackend, RecalcBackend};
 #[cfg(feature = "recalc")]
-pub use executor::{RecalcExecutor, RecalcResult};
+pub use executor::{RecalcExecutor, RecalcResult, UnsupportedFormula};
 #[cfg(feature = "recalc")]
 pub use fire_and_forget::FireAndForgetExecutor;
 #[cfg(feature = "recalc")]
+pub use native::NativeBackend;
+#[cfg(feature = "recalc")]
+pub use pooled::PooledExecutor;
+#[cfg(feature = "recalc")]
 pub use screenshot::{ScreenshotExecutor, ScreenshotResult};
 
 use std::path::PathBuf;
@@ -53,10 +59,12 @@ impl Default for GlobalScreenshotLock {
     }
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum ExecutorStrategy {
+    /// Spawn a fresh soffice process per recalc.
     #[default]
     FireAndForget,
+    /// Reuse long-lived soffice processes (one per concurrent recalc).
     Pooled,
 }
 
@@ -65,6 +73,14 @@ pub struct RecalcConfig {
     pub soffice_path: Option<PathBuf>,
     pub timeout_ms: Option<u64>,
     pub strategy: ExecutorStrategy,
+    /// Number of soffice processes kept warm by the pooled strategy.
+    pub pool_size: usize,
+    /// Directory for pooled slot profiles; defaults to a per-process directory
+    /// under the system temp dir.
+    pub pool_dir: Option<PathBuf>,
+    /// Python with the `uno` module, used by the pooled strategy's workers;
+    /// defaults to the interpreter bundled next to soffice, else `python3`.
+    pub python_path: Option<PathBuf>,
 }
 
 impl Default for RecalcConfig {
@@ -73,6 +89,9 @@ impl Default for RecalcConfig {
             soffice_path: None,
             timeout_ms: Some(30_000),
             strategy: ExecutorStrategy::FireAndForget,
+            pool_size: 2,
+            pool_dir: None,
+            python_path: None,
         }
     }
 }
@@ -81,9 +100,6 @@ impl Default for RecalcConfig {
 pub fn create_executor(config: &RecalcConfig) -> Arc<dyn RecalcExecutor> {
     match config.strategy {
         ExecutorStrategy::FireAndForget => Arc::new(FireAndForgetExecutor::new(config)),
-        ExecutorStrategy::Pooled => {
-            tracing::warn!("Pooled executor not yet implemented, falling back to fire-and-forget");
-            Arc::new(FireAndForgetExecutor::new(config))
-        }
+        ExecutorStrategy::Pooled => Arc::new(PooledExecutor::new(config)),
     }
 }
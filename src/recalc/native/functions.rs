@@ -0,0 +1,1496 @@
+//! Built-in worksheet functions for the native evaluator.
+//!
+//! Each builtin receives the unevaluated argument nodes so that short-circuiting functions
+//! (IF, IFERROR, AND/OR) and reference-aware aggregates (SUM ignores text in ranges but not in
+//! literals) can follow Excel's rules. Returning `None` from [`call`] marks the function as
+//! unsupported for the calling cell.
+
+use super::Evaluator;
+use super::value::{
+    Array, CellError, Value, compare_values, date_to_serial, parse_number_text, serial_to_date,
+};
+use chrono::{Datelike, Duration, NaiveDate, Timelike};
+use formualizer_parse::{ASTNode, ASTNodeType};
+use std::cmp::Ordering;
+
+type Builtin = fn(&mut Evaluator<'_>, &[ASTNode]) -> Result<Value, CellError>;
+
+pub(super) fn call(ev: &mut Evaluator<'_>, name: &str, args: &[ASTNode]) -> Option<Value> {
+    let builtin = lookup(name)?;
+    Some(builtin(ev, args).unwrap_or_else(Value::Error))
+}
+
+fn lookup(name: &str) -> Option<Builtin> {
+    let f: Builtin = match name {
+        // Math and aggregates
+        "SUM" => sum,
+        "PRODUCT" => product,
+        "AVERAGE" => average,
+        "MIN" => min,
+        "MAX" => max,
+        "MEDIAN" => median,
+        "COUNT" => count,
+        "COUNTA" => counta,
+        "COUNTBLANK" => countblank,
+        "SUMPRODUCT" => sumproduct,
+        "SUMIF" => sumif,
+        "SUMIFS" => sumifs,
+        "COUNTIF" => countif,
+        "COUNTIFS" => countifs,
+        "AVERAGEIF" => averageif,
+        "AVERAGEIFS" => averageifs,
+        "ABS" => abs,
+        "INT" => int,
+        "MOD" => modulo,
+        "POWER" => power,
+        "SQRT" => sqrt,
+        "SIGN" => sign,
+        "EXP" => exp,
+        "LN" => ln,
+        "LOG" => log,
+        "LOG10" => log10,
+        "PI" => pi,
+        "ROUND" => round,
+        "ROUNDUP" => roundup,
+        "ROUNDDOWN" => rounddown,
+        "CEILING" => ceiling,
+        "FLOOR" => floor,
+        // Logical
+        "IF" => if_fn,
+        "IFS" => ifs,
+        "IFERROR" => iferror,
+        "IFNA" => ifna,
+        "AND" => and,
+        "OR" => or,
+        "XOR" => xor,
+        "NOT" => not,
+        "TRUE" => true_fn,
+        "FALSE" => false_fn,
+        // Information
+        "ISBLANK" => isblank,
+        "ISNUMBER" => isnumber,
+        "ISTEXT" => istext,
+        "ISLOGICAL" => islogical,
+        "ISERROR" => iserror,
+        "ISERR" => iserr,
+        "ISNA" => isna,
+        "NA" => na,
+        // Lookup
+        "VLOOKUP" => vlookup,
+        "HLOOKUP" => hlookup,
+        "INDEX" => index,
+        "MATCH" => match_fn,
+        "CHOOSE" => choose,
+        // Text
+        "LEN" => len,
+        "LEFT" => left,
+        "RIGHT" => right,
+        "MID" => mid,
+        "UPPER" => upper,
+        "LOWER" => lower,
+        "PROPER" => proper,
+        "TRIM" => trim,
+        "CONCATENATE" => concatenate,
+        "CONCAT" => concat,
+        "TEXTJOIN" => textjoin,
+        "SUBSTITUTE" => substitute,
+        "REPLACE" => replace,
+        "FIND" => find,
+        "SEARCH" => search,
+        "REPT" => rept,
+        "EXACT" => exact,
+        "VALUE" => value,
+        // Date and time
+        "DATE" => date,
+        "TIME" => time,
+        "YEAR" => year,
+        "MONTH" => month,
+        "DAY" => day,
+        "HOUR" => hour,
+        "MINUTE" => minute,
+        "SECOND" => second,
+        "TODAY" => today,
+        "NOW" => now,
+        "EDATE" => edate,
+        "EOMONTH" => eomonth,
+        "WEEKDAY" => weekday,
+        "DAYS" => days,
+        "DATEVALUE" => datevalue,
+        _ => return None,
+    };
+    Some(f)
+}
+
+// Argument helpers
+
+fn arity(args: &[ASTNode], min: usize, max: usize) -> Result<(), CellError> {
+    if args.len() < min || args.len() > max {
+        return Err(CellError::Value);
+    }
+    Ok(())
+}
+
+fn is_reference(node: &ASTNode) -> bool {
+    matches!(node.node_type, ASTNodeType::Reference { .. })
+}
+
+fn scalar(ev: &mut Evaluator<'_>, node: &ASTNode) -> Result<Value, CellError> {
+    let value = ev.eval(node).scalar();
+    match value {
+        Value::Error(err) => Err(err),
+        other => Ok(other),
+    }
+}
+
+fn number(ev: &mut Evaluator<'_>, node: &ASTNode) -> Result<f64, CellError> {
+    scalar(ev, node)?.to_number()
+}
+
+fn text(ev: &mut Evaluator<'_>, node: &ASTNode) -> Result<String, CellError> {
+    scalar(ev, node)?.to_text()
+}
+
+fn boolean(ev: &mut Evaluator<'_>, node: &ASTNode) -> Result<bool, CellError> {
+    scalar(ev, node)?.to_bool()
+}
+
+fn opt_number(
+    ev: &mut Evaluator<'_>,
+    args: &[ASTNode],
+    idx: usize,
+    default: f64,
+) -> Result<f64, CellError> {
+    match args.get(idx) {
+        Some(node) => number(ev, node),
+        None => Ok(default),
+    }
+}
+
+fn array(ev: &mut Evaluator<'_>, node: &ASTNode) -> Result<Array, CellError> {
+    match ev.eval(node) {
+        Value::Array(array) => Ok(array),
+        Value::Error(err) => Err(err),
+        other => Ok(Array::new(1, 1, vec![other])),
+    }
+}
+
+/// Collect numbers the way SUM/AVERAGE/MIN/MAX do: literal arguments are coerced, while
+/// text and booleans inside references are ignored.
+fn collect_numbers(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Vec<f64>, CellError> {
+    let mut out = Vec::new();
+    for node in args {
+        let value = ev.eval(node);
+        match value {
+            Value::Array(array) => {
+                for item in array.values {
+                    match item {
+                        Value::Number(n) => out.push(n),
+                        Value::Error(err) => return Err(err),
+                        _ => {}
+                    }
+                }
+            }
+            Value::Error(err) => return Err(err),
+            Value::Number(n) => out.push(n),
+            Value::Empty => {}
+            Value::Text(_) | Value::Bool(_) if is_reference(node) => {}
+            other => out.push(other.to_number()?),
+        }
+    }
+    Ok(out)
+}
+
+fn flatten_args(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Vec<Value> {
+    args.iter()
+        .flat_map(|node| ev.eval(node).flatten())
+        .collect()
+}
+
+fn num_result(n: f64) -> Result<Value, CellError> {
+    if n.is_finite() {
+        Ok(Value::Number(n))
+    } else {
+        Err(CellError::Num)
+    }
+}
+
+// Criteria (SUMIF / COUNTIFS / ...)
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CriteriaOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+struct Criterion {
+    op: CriteriaOp,
+    target: Value,
+    pattern: Option<regex::Regex>,
+}
+
+impl Criterion {
+    fn parse(value: &Value) -> Result<Self, CellError> {
+        let raw = match value {
+            Value::Error(err) => return Err(*err),
+            Value::Text(s) => s.clone(),
+            Value::Empty => {
+                return Ok(Self {
+                    op: CriteriaOp::Eq,
+                    target: Value::Number(0.0),
+                    pattern: None,
+                });
+            }
+            other => {
+                return Ok(Self {
+                    op: CriteriaOp::Eq,
+                    target: other.clone(),
+                    pattern: None,
+                });
+            }
+        };
+
+        let (op, rest) = [
+            (">=", CriteriaOp::Ge),
+            ("<=", CriteriaOp::Le),
+            ("<>", CriteriaOp::Ne),
+            (">", CriteriaOp::Gt),
+            ("<", CriteriaOp::Lt),
+            ("=", CriteriaOp::Eq),
+        ]
+        .iter()
+        .find_map(|(prefix, op)| raw.strip_prefix(prefix).map(|rest| (*op, rest)))
+        .unwrap_or((CriteriaOp::Eq, raw.as_str()));
+
+        let target = if rest.is_empty() {
+            Value::Empty
+        } else if let Some(n) = parse_number_text(rest) {
+            Value::Number(n)
+        } else if rest.eq_ignore_ascii_case("TRUE") {
+            Value::Bool(true)
+        } else if rest.eq_ignore_ascii_case("FALSE") {
+            Value::Bool(false)
+        } else {
+            Value::Text(rest.to_string())
+        };
+
+        let pattern = match (&target, op) {
+            (Value::Text(s), CriteriaOp::Eq | CriteriaOp::Ne) => Some(wildcard_regex(s)),
+            _ => None,
+        };
+
+        Ok(Self {
+            op,
+            target,
+            pattern,
+        })
+    }
+
+    fn matches(&self, cell: &Value) -> bool {
+        let equal = match (&self.target, cell) {
+            (Value::Empty, Value::Empty) => true,
+            (Value::Empty, Value::Text(s)) => s.is_empty(),
+            (Value::Empty, _) => false,
+            (Value::Text(_), Value::Text(s)) => {
+                self.pattern.as_ref().is_some_and(|re| re.is_match(s))
+            }
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::Number(a), Value::Text(s)) => parse_number_text(s) == Some(*a),
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            _ => false,
+        };
+
+        match self.op {
+            CriteriaOp::Eq => equal,
+            CriteriaOp::Ne => !equal,
+            _ => {
+                let comparable = matches!(
+                    (&self.target, cell),
+                    (Value::Number(_), Value::Number(_))
+                        | (Value::Text(_), Value::Text(_))
+                        | (Value::Bool(_), Value::Bool(_))
+                );
+                if !comparable {
+                    return false;
+                }
+                let ordering = compare_values(cell, &self.target);
+                match self.op {
+                    CriteriaOp::Lt => ordering == Ordering::Less,
+                    CriteriaOp::Le => ordering != Ordering::Greater,
+                    CriteriaOp::Gt => ordering == Ordering::Greater,
+                    _ => ordering != Ordering::Less,
+                }
+            }
+        }
+    }
+}
+
+/// Translate Excel wildcards (`*`, `?`, `~` escape) into an anchored, case-insensitive regex.
+fn wildcard_regex(pattern: &str) -> regex::Regex {
+    let mut out = String::from("(?is)^");
+    let mut chars = pattern.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '~' => {
+                if let Some(next) = chars.next() {
+                    out.push_str(&regex::escape(&next.to_string()));
+                }
+            }
+            '*' => out.push_str(".*"),
+            '?' => out.push('.'),
+            other => out.push_str(&regex::escape(&other.to_string())),
+        }
+    }
+    out.push('$');
+    regex::Regex::new(&out).unwrap_or_else(|_| regex::Regex::new("^$").expect("static regex"))
+}
+
+/// Evaluate (range, criteria) pairs into a per-cell mask; all ranges must share a shape.
+fn criteria_mask(
+    ev: &mut Evaluator<'_>,
+    pairs: &[ASTNode],
+) -> Result<(usize, usize, Vec<bool>), CellError> {
+    if pairs.is_empty() || pairs.len() % 2 != 0 {
+        return Err(CellError::Value);
+    }
+    let mut shape: Option<(usize, usize)> = None;
+    let mut mask: Vec<bool> = Vec::new();
+    for pair in pairs.chunks(2) {
+        let range = array(ev, &pair[0])?;
+        let criterion = Criterion::parse(&ev.eval(&pair[1]).scalar())?;
+        match shape {
+            None => {
+                shape = Some((range.rows, range.cols));
+                mask = vec![true; range.values.len()];
+            }
+            Some((rows, cols)) if rows != range.rows || cols != range.cols => {
+                return Err(CellError::Value);
+            }
+            _ => {}
+        }
+        for (slot, cell) in mask.iter_mut().zip(range.values.iter()) {
+            *slot = *slot && criterion.matches(cell);
+        }
+    }
+    let (rows, cols) = shape.unwrap_or((0, 0));
+    Ok((rows, cols, mask))
+}
+
+fn masked_numbers(values: &Array, mask: &[bool]) -> Result<Vec<f64>, CellError> {
+    let mut out = Vec::new();
+    for (value, keep) in values.values.iter().zip(mask.iter()) {
+        if !keep {
+            continue;
+        }
+        match value {
+            Value::Number(n) => out.push(*n),
+            Value::Error(err) => return Err(*err),
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+// Math
+
+fn sum(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    num_result(collect_numbers(ev, args)?.iter().sum())
+}
+
+fn product(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    let numbers = collect_numbers(ev, args)?;
+    if numbers.is_empty() {
+        return Ok(Value::Number(0.0));
+    }
+    num_result(numbers.iter().product())
+}
+
+fn average(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    let numbers = collect_numbers(ev, args)?;
+    if numbers.is_empty() {
+        return Err(CellError::Div0);
+    }
+    num_result(numbers.iter().sum::<f64>() / numbers.len() as f64)
+}
+
+fn min(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    let numbers = collect_numbers(ev, args)?;
+    Ok(Value::Number(
+        numbers.into_iter().reduce(f64::min).unwrap_or(0.0),
+    ))
+}
+
+fn max(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    let numbers = collect_numbers(ev, args)?;
+    Ok(Value::Number(
+        numbers.into_iter().reduce(f64::max).unwrap_or(0.0),
+    ))
+}
+
+fn median(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    let mut numbers = collect_numbers(ev, args)?;
+    if numbers.is_empty() {
+        return Err(CellError::Num);
+    }
+    numbers.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    let mid = numbers.len() / 2;
+    if numbers.len() % 2 == 0 {
+        Ok(Value::Number((numbers[mid - 1] + numbers[mid]) / 2.0))
+    } else {
+        Ok(Value::Number(numbers[mid]))
+    }
+}
+
+fn count(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    let mut total = 0usize;
+    for node in args {
+        match ev.eval(node) {
+            Value::Array(array) => {
+                total += array
+                    .values
+                    .iter()
+                    .filter(|v| matches!(v, Value::Number(_)))
+                    .count();
+            }
+            Value::Number(_) => total += 1,
+            Value::Bool(_) if !is_reference(node) => total += 1,
+            Value::Text(s) if !is_reference(node) && parse_number_text(&s).is_some() => total += 1,
+            _ => {}
+        }
+    }
+    Ok(Value::Number(total as f64))
+}
+
+fn counta(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    let total = flatten_args(ev, args)
+        .iter()
+        .filter(|v| !matches!(v, Value::Empty))
+        .count();
+    Ok(Value::Number(total as f64))
+}
+
+fn countblank(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 1)?;
+    let total = ev
+        .eval(&args[0])
+        .flatten()
+        .iter()
+        .filter(|v| match v {
+            Value::Empty => true,
+            Value::Text(s) => s.is_empty(),
+            _ => false,
+        })
+        .count();
+    Ok(Value::Number(total as f64))
+}
+
+fn sumproduct(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 255)?;
+    let arrays = args
+        .iter()
+        .map(|node| array(ev, node))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (rows, cols) = (arrays[0].rows, arrays[0].cols);
+    if arrays.iter().any(|a| a.rows != rows || a.cols != cols) {
+        return Err(CellError::Value);
+    }
+    let mut total = 0.0;
+    for idx in 0..rows * cols {
+        let mut term = 1.0;
+        for array in &arrays {
+            term *= match &array.values[idx] {
+                Value::Number(n) => *n,
+                Value::Error(err) => return Err(*err),
+                _ => 0.0,
+            };
+        }
+        total += term;
+    }
+    num_result(total)
+}
+
+fn sumif(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 3)?;
+    let (_, _, mask) = criteria_mask(ev, &args[..2])?;
+    let values = match args.get(2) {
+        Some(node) => array(ev, node)?,
+        None => array(ev, &args[0])?,
+    };
+    num_result(masked_numbers(&values, &mask)?.iter().sum())
+}
+
+fn sumifs(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 3, 255)?;
+    let values = array(ev, &args[0])?;
+    let (rows, cols, mask) = criteria_mask(ev, &args[1..])?;
+    if rows != values.rows || cols != values.cols {
+        return Err(CellError::Value);
+    }
+    num_result(masked_numbers(&values, &mask)?.iter().sum())
+}
+
+fn countif(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 2)?;
+    let (_, _, mask) = criteria_mask(ev, args)?;
+    Ok(Value::Number(mask.iter().filter(|m| **m).count() as f64))
+}
+
+fn countifs(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    let (_, _, mask) = criteria_mask(ev, args)?;
+    Ok(Value::Number(mask.iter().filter(|m| **m).count() as f64))
+}
+
+fn averageif(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 3)?;
+    let (_, _, mask) = criteria_mask(ev, &args[..2])?;
+    let values = match args.get(2) {
+        Some(node) => array(ev, node)?,
+        None => array(ev, &args[0])?,
+    };
+    let numbers = masked_numbers(&values, &mask)?;
+    if numbers.is_empty() {
+        return Err(CellError::Div0);
+    }
+    num_result(numbers.iter().sum::<f64>() / numbers.len() as f64)
+}
+
+fn averageifs(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 3, 255)?;
+    let values = array(ev, &args[0])?;
+    let (rows, cols, mask) = criteria_mask(ev, &args[1..])?;
+    if rows != values.rows || cols != values.cols {
+        return Err(CellError::Value);
+    }
+    let numbers = masked_numbers(&values, &mask)?;
+    if numbers.is_empty() {
+        return Err(CellError::Div0);
+    }
+    num_result(numbers.iter().sum::<f64>() / numbers.len() as f64)
+}
+
+fn unary_math(
+    ev: &mut Evaluator<'_>,
+    args: &[ASTNode],
+    f: impl Fn(f64) -> Result<f64, CellError>,
+) -> Result<Value, CellError> {
+    arity(args, 1, 1)?;
+    num_result(f(number(ev, &args[0])?)?)
+}
+
+fn abs(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    unary_math(ev, args, |n| Ok(n.abs()))
+}
+
+fn int(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    unary_math(ev, args, |n| Ok(n.floor()))
+}
+
+fn sqrt(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    unary_math(ev, args, |n| {
+        if n < 0.0 {
+            Err(CellError::Num)
+        } else {
+            Ok(n.sqrt())
+        }
+    })
+}
+
+fn sign(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    unary_math(ev, args, |n| {
+        Ok(if n > 0.0 {
+            1.0
+        } else if n < 0.0 {
+            -1.0
+        } else {
+            0.0
+        })
+    })
+}
+
+fn exp(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    unary_math(ev, args, |n| Ok(n.exp()))
+}
+
+fn ln(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    unary_math(ev, args, |n| {
+        if n <= 0.0 {
+            Err(CellError::Num)
+        } else {
+            Ok(n.ln())
+        }
+    })
+}
+
+fn log10(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    unary_math(ev, args, |n| {
+        if n <= 0.0 {
+            Err(CellError::Num)
+        } else {
+            Ok(n.log10())
+        }
+    })
+}
+
+fn log(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 2)?;
+    let n = number(ev, &args[0])?;
+    let base = opt_number(ev, args, 1, 10.0)?;
+    if n <= 0.0 || base <= 0.0 {
+        return Err(CellError::Num);
+    }
+    if base == 1.0 {
+        return Err(CellError::Div0);
+    }
+    num_result(n.log(base))
+}
+
+fn pi(_ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 0, 0)?;
+    Ok(Value::Number(std::f64::consts::PI))
+}
+
+fn modulo(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 2)?;
+    let n = number(ev, &args[0])?;
+    let d = number(ev, &args[1])?;
+    if d == 0.0 {
+        return Err(CellError::Div0);
+    }
+    num_result(n - d * (n / d).floor())
+}
+
+fn power(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 2)?;
+    let base = number(ev, &args[0])?;
+    let exponent = number(ev, &args[1])?;
+    if base == 0.0 && exponent < 0.0 {
+        return Err(CellError::Div0);
+    }
+    num_result(base.powf(exponent))
+}
+
+/// Scale by 10^digits, snapping away binary noise so 2.675 rounds like Excel does.
+fn scaled(n: f64, digits: f64) -> (f64, f64) {
+    let factor = 10f64.powi(digits.trunc() as i32);
+    let value: f64 = format!("{:.12e}", n * factor).parse().unwrap_or(n * factor);
+    (value, factor)
+}
+
+fn round(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 2)?;
+    let (value, factor) = scaled(number(ev, &args[0])?, number(ev, &args[1])?);
+    num_result(value.round() / factor)
+}
+
+fn roundup(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 2)?;
+    let (value, factor) = scaled(number(ev, &args[0])?, number(ev, &args[1])?);
+    num_result(value.abs().ceil().copysign(value) / factor)
+}
+
+fn rounddown(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 2)?;
+    let (value, factor) = scaled(number(ev, &args[0])?, number(ev, &args[1])?);
+    num_result(value.trunc() / factor)
+}
+
+fn ceiling(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 2)?;
+    let n = number(ev, &args[0])?;
+    let significance = opt_number(ev, args, 1, 1.0)?;
+    if significance == 0.0 {
+        return Ok(Value::Number(0.0));
+    }
+    if n > 0.0 && significance < 0.0 {
+        return Err(CellError::Num);
+    }
+    num_result((n / significance).ceil() * significance)
+}
+
+fn floor(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 2)?;
+    let n = number(ev, &args[0])?;
+    let significance = opt_number(ev, args, 1, 1.0)?;
+    if significance == 0.0 {
+        return Err(CellError::Div0);
+    }
+    if n > 0.0 && significance < 0.0 {
+        return Err(CellError::Num);
+    }
+    num_result((n / significance).floor() * significance)
+}
+
+// Logical and information
+
+fn if_fn(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 3)?;
+    if boolean(ev, &args[0])? {
+        Ok(args.get(1).map(|n| ev.eval(n)).unwrap_or(Value::Bool(true)))
+    } else {
+        Ok(args
+            .get(2)
+            .map(|n| ev.eval(n))
+            .unwrap_or(Value::Bool(false)))
+    }
+}
+
+fn ifs(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    if args.is_empty() || args.len() % 2 != 0 {
+        return Err(CellError::Value);
+    }
+    for pair in args.chunks(2) {
+        if boolean(ev, &pair[0])? {
+            return Ok(ev.eval(&pair[1]));
+        }
+    }
+    Err(CellError::Na)
+}
+
+fn iferror(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 2)?;
+    let value = ev.eval(&args[0]);
+    if value.clone().scalar().is_error() {
+        Ok(ev.eval(&args[1]))
+    } else {
+        Ok(value)
+    }
+}
+
+fn ifna(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 2)?;
+    let value = ev.eval(&args[0]);
+    if value.clone().scalar().as_error() == Some(CellError::Na) {
+        Ok(ev.eval(&args[1]))
+    } else {
+        Ok(value)
+    }
+}
+
+fn logical_values(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Vec<bool>, CellError> {
+    let mut out = Vec::new();
+    for node in args {
+        match ev.eval(node) {
+            Value::Array(array) => {
+                for item in array.values {
+                    match item {
+                        Value::Bool(b) => out.push(b),
+                        Value::Number(n) => out.push(n != 0.0),
+                        Value::Error(err) => return Err(err),
+                        _ => {}
+                    }
+                }
+            }
+            Value::Empty => {}
+            Value::Text(_) if is_reference(node) => {}
+            other => out.push(other.to_bool()?),
+        }
+    }
+    if out.is_empty() {
+        return Err(CellError::Value);
+    }
+    Ok(out)
+}
+
+fn and(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    Ok(Value::Bool(logical_values(ev, args)?.iter().all(|b| *b)))
+}
+
+fn or(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    Ok(Value::Bool(logical_values(ev, args)?.iter().any(|b| *b)))
+}
+
+fn xor(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    let trues = logical_values(ev, args)?.iter().filter(|b| **b).count();
+    Ok(Value::Bool(trues % 2 == 1))
+}
+
+fn not(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 1)?;
+    Ok(Value::Bool(!boolean(ev, &args[0])?))
+}
+
+fn true_fn(_ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 0, 0)?;
+    Ok(Value::Bool(true))
+}
+
+fn false_fn(_ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 0, 0)?;
+    Ok(Value::Bool(false))
+}
+
+fn is_check(
+    ev: &mut Evaluator<'_>,
+    args: &[ASTNode],
+    f: impl Fn(&Value) -> bool,
+) -> Result<Value, CellError> {
+    arity(args, 1, 1)?;
+    let value = ev.eval(&args[0]).scalar();
+    Ok(Value::Bool(f(&value)))
+}
+
+fn isblank(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    is_check(ev, args, |v| matches!(v, Value::Empty))
+}
+
+fn isnumber(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    is_check(ev, args, |v| matches!(v, Value::Number(_)))
+}
+
+fn istext(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    is_check(ev, args, |v| matches!(v, Value::Text(_)))
+}
+
+fn islogical(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    is_check(ev, args, |v| matches!(v, Value::Bool(_)))
+}
+
+fn iserror(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    is_check(ev, args, |v| v.is_error())
+}
+
+fn iserr(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    is_check(ev, args, |v| {
+        v.is_error() && v.as_error() != Some(CellError::Na)
+    })
+}
+
+fn isna(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    is_check(ev, args, |v| v.as_error() == Some(CellError::Na))
+}
+
+fn na(_ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 0, 0)?;
+    Err(CellError::Na)
+}
+
+// Lookup
+
+fn lookup_equal(needle: &Value, candidate: &Value) -> bool {
+    match (needle, candidate) {
+        (Value::Text(pattern), Value::Text(text)) => {
+            if pattern.contains(['*', '?', '~']) {
+                wildcard_regex(pattern).is_match(text)
+            } else {
+                pattern.to_lowercase() == text.to_lowercase()
+            }
+        }
+        (Value::Number(a), Value::Number(b)) => a == b,
+        (Value::Bool(a), Value::Bool(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn same_kind(a: &Value, b: &Value) -> bool {
+    matches!(
+        (a, b),
+        (Value::Number(_), Value::Number(_))
+            | (Value::Text(_), Value::Text(_))
+            | (Value::Bool(_), Value::Bool(_))
+    )
+}
+
+/// Shared MATCH semantics: 0 = exact, 1 = largest <= needle, -1 = smallest >= needle.
+fn find_position(needle: &Value, haystack: &[Value], match_type: i32) -> Option<usize> {
+    match match_type {
+        0 => haystack.iter().position(|c| lookup_equal(needle, c)),
+        1 => {
+            let mut found = None;
+            for (idx, candidate) in haystack.iter().enumerate() {
+                if !same_kind(needle, candidate) {
+                    continue;
+                }
+                if compare_values(candidate, needle) == Ordering::Greater {
+                    break;
+                }
+                found = Some(idx);
+            }
+            found
+        }
+        _ => {
+            let mut found = None;
+            for (idx, candidate) in haystack.iter().enumerate() {
+                if !same_kind(needle, candidate) {
+                    continue;
+                }
+                if compare_values(candidate, needle) == Ordering::Less {
+                    break;
+                }
+                found = Some(idx);
+            }
+            found
+        }
+    }
+}
+
+fn lookup_needle(ev: &mut Evaluator<'_>, node: &ASTNode) -> Result<Value, CellError> {
+    match scalar(ev, node)? {
+        Value::Empty => Ok(Value::Number(0.0)),
+        other => Ok(other),
+    }
+}
+
+fn vlookup(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 3, 4)?;
+    let needle = lookup_needle(ev, &args[0])?;
+    let table = array(ev, &args[1])?;
+    let col = number(ev, &args[2])?.trunc() as i64;
+    let approximate = match args.get(3) {
+        Some(node) => boolean(ev, node)?,
+        None => true,
+    };
+    if col < 1 {
+        return Err(CellError::Value);
+    }
+    if col as usize > table.cols {
+        return Err(CellError::Ref);
+    }
+    let keys = table.column(0);
+    let row =
+        find_position(&needle, &keys, if approximate { 1 } else { 0 }).ok_or(CellError::Na)?;
+    Ok(table
+        .get(row, col as usize - 1)
+        .cloned()
+        .unwrap_or(Value::Empty))
+}
+
+fn hlookup(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 3, 4)?;
+    let needle = lookup_needle(ev, &args[0])?;
+    let table = array(ev, &args[1])?;
+    let row = number(ev, &args[2])?.trunc() as i64;
+    let approximate = match args.get(3) {
+        Some(node) => boolean(ev, node)?,
+        None => true,
+    };
+    if row < 1 {
+        return Err(CellError::Value);
+    }
+    if row as usize > table.rows {
+        return Err(CellError::Ref);
+    }
+    let keys = table.row(0);
+    let col =
+        find_position(&needle, &keys, if approximate { 1 } else { 0 }).ok_or(CellError::Na)?;
+    Ok(table
+        .get(row as usize - 1, col)
+        .cloned()
+        .unwrap_or(Value::Empty))
+}
+
+fn match_fn(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 3)?;
+    let needle = lookup_needle(ev, &args[0])?;
+    let haystack = array(ev, &args[1])?;
+    if haystack.rows > 1 && haystack.cols > 1 {
+        return Err(CellError::Na);
+    }
+    let match_type = opt_number(ev, args, 2, 1.0)?;
+    let match_type = if match_type > 0.0 {
+        1
+    } else if match_type < 0.0 {
+        -1
+    } else {
+        0
+    };
+    let position = find_position(&needle, &haystack.values, match_type).ok_or(CellError::Na)?;
+    Ok(Value::Number((position + 1) as f64))
+}
+
+fn index(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 3)?;
+    let table = array(ev, &args[0])?;
+    let first = number(ev, &args[1])?.trunc();
+    let second = match args.get(2) {
+        Some(node) => Some(number(ev, node)?.trunc()),
+        None => None,
+    };
+    if first < 0.0 || second.is_some_and(|c| c < 0.0) {
+        return Err(CellError::Value);
+    }
+
+    // A single index into a one-row range walks its columns.
+    let (row, col) = match second {
+        Some(col) => (first as usize, col as usize),
+        None if table.rows == 1 => (1, first as usize),
+        None if table.cols == 1 => (first as usize, 1),
+        None => (first as usize, 0),
+    };
+
+    if row > table.rows || col > table.cols {
+        return Err(CellError::Ref);
+    }
+    match (row, col) {
+        (0, 0) => Ok(Value::Array(table)),
+        (0, c) => {
+            let values = table.column(c - 1);
+            Ok(Value::Array(Array::new(values.len(), 1, values)))
+        }
+        (r, 0) => {
+            let values = table.row(r - 1);
+            Ok(Value::Array(Array::new(1, values.len(), values)))
+        }
+        (r, c) => Ok(table.get(r - 1, c - 1).cloned().unwrap_or(Value::Empty)),
+    }
+}
+
+fn choose(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 255)?;
+    let idx = number(ev, &args[0])?.trunc() as i64;
+    if idx < 1 || idx as usize >= args.len() {
+        return Err(CellError::Value);
+    }
+    Ok(ev.eval(&args[idx as usize]))
+}
+
+// Text
+
+fn len(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 1)?;
+    Ok(Value::Number(text(ev, &args[0])?.chars().count() as f64))
+}
+
+fn char_count(ev: &mut Evaluator<'_>, args: &[ASTNode], idx: usize) -> Result<usize, CellError> {
+    let n = opt_number(ev, args, idx, 1.0)?;
+    if n < 0.0 {
+        return Err(CellError::Value);
+    }
+    Ok(n.trunc() as usize)
+}
+
+fn left(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 2)?;
+    let s = text(ev, &args[0])?;
+    let n = char_count(ev, args, 1)?;
+    Ok(Value::Text(s.chars().take(n).collect()))
+}
+
+fn right(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 2)?;
+    let s = text(ev, &args[0])?;
+    let n = char_count(ev, args, 1)?;
+    let total = s.chars().count();
+    Ok(Value::Text(
+        s.chars().skip(total.saturating_sub(n)).collect(),
+    ))
+}
+
+fn mid(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 3, 3)?;
+    let s = text(ev, &args[0])?;
+    let start = number(ev, &args[1])?.trunc();
+    let n = number(ev, &args[2])?.trunc();
+    if start < 1.0 || n < 0.0 {
+        return Err(CellError::Value);
+    }
+    Ok(Value::Text(
+        s.chars()
+            .skip(start as usize - 1)
+            .take(n as usize)
+            .collect(),
+    ))
+}
+
+fn upper(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 1)?;
+    Ok(Value::Text(text(ev, &args[0])?.to_uppercase()))
+}
+
+fn lower(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 1)?;
+    Ok(Value::Text(text(ev, &args[0])?.to_lowercase()))
+}
+
+fn proper(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 1)?;
+    let s = text(ev, &args[0])?;
+    let mut out = String::with_capacity(s.len());
+    let mut start_of_word = true;
+    for ch in s.chars() {
+        if ch.is_alphabetic() {
+            if start_of_word {
+                out.extend(ch.to_uppercase());
+            } else {
+                out.extend(ch.to_lowercase());
+            }
+            start_of_word = false;
+        } else {
+            out.push(ch);
+            start_of_word = true;
+        }
+    }
+    Ok(Value::Text(out))
+}
+
+fn trim(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 1)?;
+    let s = text(ev, &args[0])?;
+    Ok(Value::Text(
+        s.split(' ')
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "),
+    ))
+}
+
+fn concatenate(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    let mut out = String::new();
+    for node in args {
+        out.push_str(&text(ev, node)?);
+    }
+    Ok(Value::Text(out))
+}
+
+fn concat(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    let mut out = String::new();
+    for value in flatten_args(ev, args) {
+        out.push_str(&value.to_text()?);
+    }
+    Ok(Value::Text(out))
+}
+
+fn textjoin(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 3, 255)?;
+    let delimiter = text(ev, &args[0])?;
+    let ignore_empty = boolean(ev, &args[1])?;
+    let mut parts = Vec::new();
+    for value in flatten_args(ev, &args[2..]) {
+        let part = value.to_text()?;
+        if ignore_empty && part.is_empty() {
+            continue;
+        }
+        parts.push(part);
+    }
+    Ok(Value::Text(parts.join(&delimiter)))
+}
+
+fn substitute(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 3, 4)?;
+    let s = text(ev, &args[0])?;
+    let old = text(ev, &args[1])?;
+    let new = text(ev, &args[2])?;
+    if old.is_empty() {
+        return Ok(Value::Text(s));
+    }
+    let Some(node) = args.get(3) else {
+        return Ok(Value::Text(s.replace(&old, &new)));
+    };
+    let instance = number(ev, node)?.trunc();
+    if instance < 1.0 {
+        return Err(CellError::Value);
+    }
+    match s.match_indices(&old).nth(instance as usize - 1) {
+        Some((pos, _)) => {
+            let mut out = String::with_capacity(s.len());
+            out.push_str(&s[..pos]);
+            out.push_str(&new);
+            out.push_str(&s[pos + old.len()..]);
+            Ok(Value::Text(out))
+        }
+        None => Ok(Value::Text(s)),
+    }
+}
+
+fn replace(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 4, 4)?;
+    let s: Vec<char> = text(ev, &args[0])?.chars().collect();
+    let start = number(ev, &args[1])?.trunc();
+    let n = number(ev, &args[2])?.trunc();
+    let new = text(ev, &args[3])?;
+    if start < 1.0 || n < 0.0 {
+        return Err(CellError::Value);
+    }
+    let start = (start as usize - 1).min(s.len());
+    let end = (start + n as usize).min(s.len());
+    let mut out: String = s[..start].iter().collect();
+    out.push_str(&new);
+    out.extend(&s[end..]);
+    Ok(Value::Text(out))
+}
+
+fn find_impl(
+    ev: &mut Evaluator<'_>,
+    args: &[ASTNode],
+    case_insensitive: bool,
+) -> Result<Value, CellError> {
+    arity(args, 2, 3)?;
+    let mut needle = text(ev, &args[0])?;
+    let mut haystack = text(ev, &args[1])?;
+    let start = opt_number(ev, args, 2, 1.0)?.trunc();
+    if case_insensitive {
+        needle = needle.to_lowercase();
+        haystack = haystack.to_lowercase();
+    }
+    let chars: Vec<char> = haystack.chars().collect();
+    if start < 1.0 || start as usize > chars.len() + 1 {
+        return Err(CellError::Value);
+    }
+    let offset: usize = chars[..start as usize - 1]
+        .iter()
+        .map(|c| c.len_utf8())
+        .sum();
+    let found = haystack[offset..].find(&needle).ok_or(CellError::Value)?;
+    let position = haystack[..offset + found].chars().count() + 1;
+    Ok(Value::Number(position as f64))
+}
+
+fn find(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    find_impl(ev, args, false)
+}
+
+fn search(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    find_impl(ev, args, true)
+}
+
+fn rept(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 2)?;
+    let s = text(ev, &args[0])?;
+    let n = number(ev, &args[1])?.trunc();
+    if n < 0.0 || s.len() as f64 * n > 32_767.0 {
+        return Err(CellError::Value);
+    }
+    Ok(Value::Text(s.repeat(n as usize)))
+}
+
+fn exact(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 2)?;
+    Ok(Value::Bool(text(ev, &args[0])? == text(ev, &args[1])?))
+}
+
+fn value(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 1)?;
+    match scalar(ev, &args[0])? {
+        Value::Number(n) => Ok(Value::Number(n)),
+        Value::Empty => Ok(Value::Number(0.0)),
+        Value::Text(s) => parse_number_text(&s)
+            .or_else(|| parse_date_text(&s).map(date_to_serial))
+            .map(Value::Number)
+            .ok_or(CellError::Value),
+        _ => Err(CellError::Value),
+    }
+}
+
+// Date and time
+
+fn date_arg(ev: &mut Evaluator<'_>, node: &ASTNode) -> Result<NaiveDate, CellError> {
+    let serial = match scalar(ev, node)? {
+        Value::Text(s) => parse_date_text(&s)
+            .map(date_to_serial)
+            .ok_or(CellError::Value)?,
+        other => other.to_number()?,
+    };
+    serial_to_date(serial).ok_or(CellError::Num)
+}
+
+fn parse_date_text(raw: &str) -> Option<NaiveDate> {
+    let trimmed = raw.trim();
+    ["%Y-%m-%d", "%m/%d/%Y", "%Y/%m/%d", "%d-%b-%Y", "%B %d, %Y"]
+        .iter()
+        .find_map(|fmt| NaiveDate::parse_from_str(trimmed, fmt).ok())
+}
+
+fn build_date(year: i64, month: i64, day: i64) -> Option<NaiveDate> {
+    let year = if (0..1900).contains(&year) {
+        year + 1900
+    } else {
+        year
+    };
+    let months = year * 12 + (month - 1);
+    let first = NaiveDate::from_ymd_opt(
+        months.div_euclid(12) as i32,
+        (months.rem_euclid(12) + 1) as u32,
+        1,
+    )?;
+    first.checked_add_signed(Duration::days(day - 1))
+}
+
+fn add_months(date: NaiveDate, months: i64) -> Option<NaiveDate> {
+    let total = date.year() as i64 * 12 + date.month0() as i64 + months;
+    let year = total.div_euclid(12) as i32;
+    let month = total.rem_euclid(12) as u32 + 1;
+    let last = last_day_of_month(year, month)?;
+    NaiveDate::from_ymd_opt(year, month, date.day().min(last.day()))
+}
+
+fn last_day_of_month(year: i32, month: u32) -> Option<NaiveDate> {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)?.pred_opt()
+}
+
+fn date(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 3, 3)?;
+    let year = number(ev, &args[0])?.trunc() as i64;
+    let month = number(ev, &args[1])?.trunc() as i64;
+    let day = number(ev, &args[2])?.trunc() as i64;
+    if !(0..=9999).contains(&year) {
+        return Err(CellError::Num);
+    }
+    let date = build_date(year, month, day).ok_or(CellError::Num)?;
+    Ok(Value::Number(date_to_serial(date)))
+}
+
+fn time(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 3, 3)?;
+    let h = number(ev, &args[0])?.trunc();
+    let m = number(ev, &args[1])?.trunc();
+    let s = number(ev, &args[2])?.trunc();
+    let seconds = h * 3600.0 + m * 60.0 + s;
+    if seconds < 0.0 {
+        return Err(CellError::Num);
+    }
+    Ok(Value::Number((seconds / 86_400.0).fract()))
+}
+
+fn date_part(
+    ev: &mut Evaluator<'_>,
+    args: &[ASTNode],
+    f: impl Fn(NaiveDate) -> u32,
+) -> Result<Value, CellError> {
+    arity(args, 1, 1)?;
+    Ok(Value::Number(f(date_arg(ev, &args[0])?) as f64))
+}
+
+fn year(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    date_part(ev, args, |d| d.year() as u32)
+}
+
+fn month(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    date_part(ev, args, |d| d.month())
+}
+
+fn day(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    date_part(ev, args, |d| d.day())
+}
+
+fn time_part(
+    ev: &mut Evaluator<'_>,
+    args: &[ASTNode],
+    f: impl Fn(u32) -> u32,
+) -> Result<Value, CellError> {
+    arity(args, 1, 1)?;
+    let serial = number(ev, &args[0])?;
+    if serial < 0.0 {
+        return Err(CellError::Num);
+    }
+    let seconds = (serial.fract() * 86_400.0).round() as u32 % 86_400;
+    Ok(Value::Number(f(seconds) as f64))
+}
+
+fn hour(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    time_part(ev, args, |s| s / 3600)
+}
+
+fn minute(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    time_part(ev, args, |s| (s % 3600) / 60)
+}
+
+fn second(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    time_part(ev, args, |s| s % 60)
+}
+
+fn today(_ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 0, 0)?;
+    Ok(Value::Number(date_to_serial(
+        chrono::Local::now().date_naive(),
+    )))
+}
+
+fn now(_ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 0, 0)?;
+    let now = chrono::Local::now().naive_local();
+    let fraction = now.time().num_seconds_from_midnight() as f64 / 86_400.0;
+    Ok(Value::Number(date_to_serial(now.date()) + fraction))
+}
+
+fn edate(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 2)?;
+    let start = date_arg(ev, &args[0])?;
+    let months = number(ev, &args[1])?.trunc() as i64;
+    let shifted = add_months(start, months).ok_or(CellError::Num)?;
+    Ok(Value::Number(date_to_serial(shifted)))
+}
+
+fn eomonth(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 2)?;
+    let start = date_arg(ev, &args[0])?;
+    let months = number(ev, &args[1])?.trunc() as i64;
+    let shifted = add_months(start.with_day(1).ok_or(CellError::Num)?, months)
+        .and_then(|d| last_day_of_month(d.year(), d.month()))
+        .ok_or(CellError::Num)?;
+    Ok(Value::Number(date_to_serial(shifted)))
+}
+
+fn weekday(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 2)?;
+    let d = date_arg(ev, &args[0])?;
+    let kind = opt_number(ev, args, 1, 1.0)? as i64;
+    let from_sunday = d.weekday().num_days_from_sunday() as f64;
+    let from_monday = d.weekday().num_days_from_monday() as f64;
+    match kind {
+        1 | 17 => Ok(Value::Number(from_sunday + 1.0)),
+        2 | 11 => Ok(Value::Number(from_monday + 1.0)),
+        3 => Ok(Value::Number(from_monday)),
+        _ => Err(CellError::Num),
+    }
+}
+
+fn days(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 2, 2)?;
+    let end = date_arg(ev, &args[0])?;
+    let start = date_arg(ev, &args[1])?;
+    Ok(Value::Number((end - start).num_days() as f64))
+}
+
+fn datevalue(ev: &mut Evaluator<'_>, args: &[ASTNode]) -> Result<Value, CellError> {
+    arity(args, 1, 1)?;
+    let raw = text(ev, &args[0])?;
+    parse_date_text(&raw)
+        .map(|d| Value::Number(date_to_serial(d)))
+        .ok_or(CellError::Value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn criteria_parse_operators_and_wildcards() {
+        let gt = Criterion::parse(&Value::Text(">=10".into())).unwrap();
+        assert!(gt.matches(&Value::Number(10.0)));
+        assert!(!gt.matches(&Value::Number(9.0)));
+        assert!(!gt.matches(&Value::Text("abc".into())));
+
+        let wild = Criterion::parse(&Value::Text("ap*".into())).unwrap();
+        assert!(wild.matches(&Value::Text("Apple".into())));
+        assert!(!wild.matches(&Value::Text("grape".into())));
+
+        let not_blank = Criterion::parse(&Value::Text("<>".into())).unwrap();
+        assert!(not_blank.matches(&Value::Number(1.0)));
+        assert!(!not_blank.matches(&Value::Empty));
+    }
+
+    #[test]
+    fn approximate_match_picks_largest_not_greater() {
+        let haystack = vec![Value::Number(1.0), Value::Number(5.0), Value::Number(10.0)];
+        assert_eq!(find_position(&Value::Number(7.0), &haystack, 1), Some(1));
+        assert_eq!(find_position(&Value::Number(0.5), &haystack, 1), None);
+        assert_eq!(find_position(&Value::Number(10.0), &haystack, 0), Some(2));
+    }
+
+    #[test]
+    fn date_construction_rolls_over_months() {
+        let d = build_date(2024, 13, 1).unwrap();
+        assert_eq!(d, NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+        let d = build_date(2024, 3, 0).unwrap();
+        assert_eq!(d, NaiveDate::from_ymd_opt(2024, 2, 29).unwrap());
+        let eom = add_months(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(), 1).unwrap();
+        assert_eq!(eom, NaiveDate::from_ymd_opt(2024, 2, 29).unwrap());
+    }
+}
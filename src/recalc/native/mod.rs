@@ -0,0 +1,775 @@
+//! In-process formula evaluation.
+//!
+//! `NativeBackend` recalculates a fork without LibreOffice: every formula is parsed with
+//! formualizer, ordered topologically by the cells and ranges it references (looked up in a
+//! per-column index of formula cells), evaluated against an in-memory snapshot of the workbook
+//! and written back as cached values. Cells that use
+//! functions outside the supported library keep their previous cached value and are reported.
+
+mod functions;
+mod value;
+
+pub use value::{CellError, Value};
+
+use super::backend::RecalcBackend;
+use super::executor::{RecalcResult, UnsupportedFormula};
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use formualizer_parse::parser::{CollectPolicy, ReferenceType};
+use formualizer_parse::{ASTNode, ASTNodeType, LiteralValue};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::path::Path;
+use std::time::Instant;
+use umya_spreadsheet::Spreadsheet;
+use value::{Array, compare_values};
+
+/// Ranges larger than this evaluate to #NUM! instead of being materialised.
+const MAX_RANGE_CELLS: usize = 2_000_000;
+
+pub struct NativeBackend;
+
+impl NativeBackend {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for NativeBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl RecalcBackend for NativeBackend {
+    async fn recalculate(&self, fork_work_path: &Path) -> Result<RecalcResult> {
+        let start = Instant::now();
+        let path = fork_work_path.to_path_buf();
+
+        let unsupported = tokio::task::spawn_blocking(move || recalculate_file(&path))
+            .await
+            .map_err(|e| anyhow!("native recalc task failed: {}", e))??;
+
+        let duration = start.elapsed();
+        crate::metrics::METRICS.record_recalc_duration(duration);
+
+        Ok(RecalcResult {
+            duration_ms: duration.as_millis() as u64,
+            was_warm: true,
+            executor_type: "native",
+            unsupported,
+        })
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "native"
+    }
+}
+
+/// Recalculate every formula in the workbook at `path` and save it in place.
+pub fn recalculate_file(path: &Path) -> Result<Vec<UnsupportedFormula>> {
+    let mut book = umya_spreadsheet::reader::xlsx::read(path)
+        .with_context(|| format!("failed to read workbook {}", path.display()))?;
+    let outcome = recalculate_book(&mut book);
+    umya_spreadsheet::writer::xlsx::write(&book, path)
+        .with_context(|| format!("failed to write workbook {}", path.display()))?;
+    Ok(outcome)
+}
+
+/// Recalculate an already-loaded workbook, updating cached formula results.
+pub fn recalculate_book(book: &mut Spreadsheet) -> Vec<UnsupportedFormula> {
+    let mut model = WorkbookModel::from_book(book);
+    let mut unsupported = Vec::new();
+
+    let order = model.evaluation_order();
+    for &idx in &order.ordered {
+        let (sheet, row, col) = model.formulas[idx].key;
+        let result = {
+            let mut evaluator = Evaluator::new(&model, sheet);
+            let value = match &model.formulas[idx].ast {
+                Ok(ast) => evaluator.eval(ast).scalar(),
+                Err(_) => Value::Error(CellError::Name),
+            };
+            (value, evaluator.unsupported)
+        };
+        let (value, missing) = result;
+        let formula = &model.formulas[idx];
+
+        if formula.ast.is_err() || !missing.is_empty() {
+            let reason = if let Err(message) = &formula.ast {
+                message.clone()
+            } else {
+                "unsupported function".to_string()
+            };
+            unsupported.push(UnsupportedFormula {
+                sheet: model.sheet_names[sheet].clone(),
+                cell: cell_address(row, col),
+                formula: formula.text.clone(),
+                functions: missing.into_iter().collect(),
+                reason,
+            });
+            // Leave the previous cached value in place for downstream cells.
+            continue;
+        }
+
+        let value = match value {
+            Value::Empty => Value::Number(0.0),
+            other => other,
+        };
+        model.values.insert((sheet, row, col), value.clone());
+        model.formulas[idx].result = Some(value);
+    }
+
+    for &idx in &order.cyclic {
+        let formula = &model.formulas[idx];
+        let (sheet, row, col) = formula.key;
+        unsupported.push(UnsupportedFormula {
+            sheet: model.sheet_names[sheet].clone(),
+            cell: cell_address(row, col),
+            formula: formula.text.clone(),
+            functions: Vec::new(),
+            reason: "circular reference".to_string(),
+        });
+    }
+
+    let sheets = book.get_sheet_collection_mut();
+    for formula in &model.formulas {
+        let Some(value) = &formula.result else {
+            continue;
+        };
+        let (sheet, row, col) = formula.key;
+        let Some(worksheet) = sheets.get_mut(sheet) else {
+            continue;
+        };
+        // Numbers and booleans keep their type so the file stores `t="n"`/`t="b"`.
+        let cell = worksheet.get_cell_mut((col, row));
+        match value {
+            Value::Number(n) => {
+                cell.set_value_number(*n);
+            }
+            Value::Bool(b) => {
+                cell.set_value_bool(*b);
+            }
+            other => {
+                cell.set_formula_result_default(other.to_cached_text());
+            }
+        }
+        // Keep the formula whichever setter stored the result.
+        if !cell.is_formula() {
+            cell.set_formula(formula.text.clone());
+        }
+    }
+
+    unsupported
+}
+
+type CellKey = (usize, u32, u32);
+
+struct FormulaCell {
+    key: CellKey,
+    text: String,
+    ast: std::result::Result<ASTNode, String>,
+    result: Option<Value>,
+}
+
+#[derive(Clone)]
+struct Bounds {
+    sheet: usize,
+    min_row: u32,
+    min_col: u32,
+    max_row: u32,
+    max_col: u32,
+}
+
+struct EvaluationOrder {
+    ordered: Vec<usize>,
+    cyclic: Vec<usize>,
+}
+
+/// Formula cells by sheet and column, sorted by row, so a range reference
+/// finds the formulas inside it without scanning every formula.
+struct FormulaIndex {
+    cells: HashMap<CellKey, usize>,
+    columns: Vec<BTreeMap<u32, Vec<(u32, usize)>>>,
+}
+
+impl FormulaIndex {
+    fn new(formulas: &[FormulaCell], sheet_count: usize) -> Self {
+        let mut cells = HashMap::with_capacity(formulas.len());
+        let mut columns: Vec<BTreeMap<u32, Vec<(u32, usize)>>> = vec![BTreeMap::new(); sheet_count];
+        for (idx, formula) in formulas.iter().enumerate() {
+            let (sheet, row, col) = formula.key;
+            cells.insert(formula.key, idx);
+            columns[sheet].entry(col).or_default().push((row, idx));
+        }
+        for rows in columns.iter_mut().flat_map(|sheet| sheet.values_mut()) {
+            rows.sort_unstable();
+        }
+        Self { cells, columns }
+    }
+
+    /// Add the formulas inside `bounds` to `found`.
+    fn collect_within(&self, bounds: &Bounds, found: &mut BTreeSet<usize>) {
+        let area = (bounds.max_row - bounds.min_row + 1) as u64
+            * (bounds.max_col - bounds.min_col + 1) as u64;
+        if area <= 64 {
+            for row in bounds.min_row..=bounds.max_row {
+                for col in bounds.min_col..=bounds.max_col {
+                    if let Some(&idx) = self.cells.get(&(bounds.sheet, row, col)) {
+                        found.insert(idx);
+                    }
+                }
+            }
+            return;
+        }
+        for rows in self.columns[bounds.sheet]
+            .range(bounds.min_col..=bounds.max_col)
+            .map(|(_, rows)| rows)
+        {
+            let start = rows.partition_point(|(row, _)| *row < bounds.min_row);
+            found.extend(
+                rows[start..]
+                    .iter()
+                    .take_while(|(row, _)| *row <= bounds.max_row)
+                    .map(|(_, idx)| *idx),
+            );
+        }
+    }
+}
+
+/// In-memory snapshot of every cell value and formula in a workbook.
+struct WorkbookModel {
+    sheet_names: Vec<String>,
+    sheet_lookup: HashMap<String, usize>,
+    /// (max_row, max_col) of populated cells per sheet.
+    extents: Vec<(u32, u32)>,
+    values: HashMap<CellKey, Value>,
+    formulas: Vec<FormulaCell>,
+    names: HashMap<String, String>,
+}
+
+impl WorkbookModel {
+    fn from_book(book: &Spreadsheet) -> Self {
+        let mut sheet_names = Vec::new();
+        let mut sheet_lookup = HashMap::new();
+        let mut extents = Vec::new();
+        let mut values = HashMap::new();
+        let mut formulas = Vec::new();
+
+        for (sheet_idx, sheet) in book.get_sheet_collection().iter().enumerate() {
+            sheet_names.push(sheet.get_name().to_string());
+            sheet_lookup.insert(sheet.get_name().to_ascii_uppercase(), sheet_idx);
+            let mut max_row = 0u32;
+            let mut max_col = 0u32;
+
+            for cell in sheet.get_cell_collection() {
+                let coord = cell.get_coordinate();
+                let row = *coord.get_row_num();
+                let col = *coord.get_col_num();
+                let raw = cell.get_value();
+                let key = (sheet_idx, row, col);
+
+                if cell.is_formula() && !cell.get_formula().is_empty() {
+                    let text = cell.get_formula().to_string();
+                    let with_prefix = if text.starts_with('=') {
+                        text.clone()
+                    } else {
+                        format!("={}", text)
+                    };
+                    let ast = formualizer_parse::parse(&with_prefix)
+                        .map_err(|e| format!("failed to parse formula: {}", e.message));
+                    formulas.push(FormulaCell {
+                        key,
+                        text,
+                        ast,
+                        result: None,
+                    });
+                } else if raw.is_empty() {
+                    continue;
+                }
+
+                max_row = max_row.max(row);
+                max_col = max_col.max(col);
+                values.insert(key, Value::from_cell(&raw, cell.get_data_type()));
+            }
+            extents.push((max_row, max_col));
+        }
+
+        let mut names = HashMap::new();
+        for defined in book.get_defined_names() {
+            names.insert(
+                defined.get_name().to_ascii_uppercase(),
+                defined.get_address().to_string(),
+            );
+        }
+
+        Self {
+            sheet_names,
+            sheet_lookup,
+            extents,
+            values,
+            formulas,
+            names,
+        }
+    }
+
+    fn sheet_index(&self, sheet: Option<&str>, current: usize) -> Option<usize> {
+        match sheet {
+            None => Some(current),
+            Some(name) => self
+                .sheet_lookup
+                .get(&name.trim_matches('\'').to_ascii_uppercase())
+                .copied(),
+        }
+    }
+
+    fn resolve_name(&self, name: &str) -> Option<ReferenceType> {
+        let address = self.names.get(&name.to_ascii_uppercase())?;
+        let ast =
+            formualizer_parse::parse(&format!("={}", address.trim_start_matches('='))).ok()?;
+        match ast.node_type {
+            ASTNodeType::Reference { reference, .. } => Some(reference),
+            _ => None,
+        }
+    }
+
+    /// Resolve a reference into concrete sheet bounds, clamping open ranges to the used area.
+    fn bounds(&self, reference: &ReferenceType, current: usize) -> Option<Bounds> {
+        match reference {
+            ReferenceType::Cell { sheet, row, col } => {
+                let sheet = self.sheet_index(sheet.as_deref(), current)?;
+                Some(Bounds {
+                    sheet,
+                    min_row: *row,
+                    min_col: *col,
+                    max_row: *row,
+                    max_col: *col,
+                })
+            }
+            ReferenceType::Range {
+                sheet,
+                start_row,
+                start_col,
+                end_row,
+                end_col,
+            } => {
+                let sheet = self.sheet_index(sheet.as_deref(), current)?;
+                let (used_rows, used_cols) = self.extents[sheet];
+                let min_row = start_row.unwrap_or(1);
+                let min_col = start_col.unwrap_or(1);
+                let max_row = end_row.unwrap_or(used_rows.max(min_row));
+                let max_col = end_col.unwrap_or(used_cols.max(min_col));
+                Some(Bounds {
+                    sheet,
+                    min_row: min_row.min(max_row),
+                    min_col: min_col.min(max_col),
+                    max_row: max_row.max(min_row),
+                    max_col: max_col.max(min_col),
+                })
+            }
+            ReferenceType::NamedRange(name) => {
+                let resolved = self.resolve_name(name)?;
+                if matches!(resolved, ReferenceType::NamedRange(_)) {
+                    return None;
+                }
+                self.bounds(&resolved, current)
+            }
+            ReferenceType::Table(_) => None,
+        }
+    }
+
+    /// Topologically order formula cells using the references each formula collects.
+    fn evaluation_order(&self) -> EvaluationOrder {
+        let policy = CollectPolicy {
+            expand_small_ranges: false,
+            range_expansion_limit: 0,
+            include_names: true,
+        };
+
+        let index = FormulaIndex::new(&self.formulas, self.sheet_names.len());
+
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); self.formulas.len()];
+        let mut in_degree = vec![0usize; self.formulas.len()];
+        let mut self_cyclic = vec![false; self.formulas.len()];
+
+        for (idx, formula) in self.formulas.iter().enumerate() {
+            let Ok(ast) = &formula.ast else {
+                continue;
+            };
+            let mut precedents = BTreeSet::new();
+            for reference in ast.collect_references(&policy) {
+                if let Some(bounds) = self.bounds(&reference, formula.key.0) {
+                    index.collect_within(&bounds, &mut precedents);
+                }
+            }
+            if precedents.remove(&idx) {
+                self_cyclic[idx] = true;
+            }
+            for dep in precedents {
+                dependents[dep].push(idx);
+                in_degree[idx] += 1;
+            }
+        }
+
+        let mut queue: VecDeque<usize> = (0..self.formulas.len())
+            .filter(|idx| in_degree[*idx] == 0)
+            .collect();
+        let mut ordered = Vec::with_capacity(self.formulas.len());
+        while let Some(idx) = queue.pop_front() {
+            if self_cyclic[idx] {
+                continue;
+            }
+            ordered.push(idx);
+            for &next in &dependents[idx] {
+                in_degree[next] -= 1;
+                if in_degree[next] == 0 {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let cyclic = (0..self.formulas.len())
+            .filter(|idx| in_degree[*idx] > 0 || self_cyclic[*idx])
+            .collect();
+
+        EvaluationOrder { ordered, cyclic }
+    }
+}
+
+fn cell_address(row: u32, col: u32) -> String {
+    format!("{}{}", crate::utils::column_number_to_name(col), row)
+}
+
+/// Evaluates a single formula against the workbook snapshot.
+struct Evaluator<'a> {
+    model: &'a WorkbookModel,
+    sheet: usize,
+    unsupported: BTreeSet<String>,
+}
+
+impl<'a> Evaluator<'a> {
+    fn new(model: &'a WorkbookModel, sheet: usize) -> Self {
+        Self {
+            model,
+            sheet,
+            unsupported: BTreeSet::new(),
+        }
+    }
+
+    fn eval(&mut self, node: &ASTNode) -> Value {
+        match &node.node_type {
+            ASTNodeType::Literal(literal) => literal_value(literal),
+            ASTNodeType::Reference { reference, .. } => self.eval_reference(reference),
+            ASTNodeType::UnaryOp { op, expr } => {
+                let operand = self.eval(expr);
+                map_unary(operand, |v| {
+                    let n = match v.to_number() {
+                        Ok(n) => n,
+                        Err(err) => return Value::Error(err),
+                    };
+                    match op.as_str() {
+                        "-" => Value::Number(-n),
+                        "%" => Value::Number(n / 100.0),
+                        _ => Value::Number(n),
+                    }
+                })
+            }
+            ASTNodeType::BinaryOp { op, left, right } => {
+                if op == ":" || op == "," || op == " " {
+                    self.unsupported.insert(format!("operator '{}'", op));
+                    return Value::Error(CellError::Ref);
+                }
+                let left = self.eval(left);
+                let right = self.eval(right);
+                binary_op(op, left, right)
+            }
+            ASTNodeType::Function { name, args } => {
+                let upper = name.to_ascii_uppercase();
+                let upper = upper
+                    .trim_start_matches("_XLFN.")
+                    .trim_start_matches("_XLWS.")
+                    .to_string();
+                match functions::call(self, &upper, args) {
+                    Some(value) => value,
+                    None => {
+                        self.unsupported.insert(upper);
+                        Value::Error(CellError::Name)
+                    }
+                }
+            }
+            ASTNodeType::Array(rows) => {
+                let row_count = rows.len();
+                let col_count = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+                let mut values = Vec::with_capacity(row_count * col_count);
+                for row in rows {
+                    for col in 0..col_count {
+                        values.push(
+                            row.get(col)
+                                .map(|n| self.eval(n).scalar())
+                                .unwrap_or(Value::Error(CellError::Na)),
+                        );
+                    }
+                }
+                Value::Array(Array::new(row_count, col_count, values))
+            }
+        }
+    }
+
+    fn eval_reference(&mut self, reference: &ReferenceType) -> Value {
+        if let ReferenceType::Table(_) = reference {
+            self.unsupported.insert("structured reference".to_string());
+            return Value::Error(CellError::Ref);
+        }
+        let Some(bounds) = self.model.bounds(reference, self.sheet) else {
+            if let ReferenceType::NamedRange(_) = reference {
+                return Value::Error(CellError::Name);
+            }
+            return Value::Error(CellError::Ref);
+        };
+
+        if bounds.min_row == bounds.max_row && bounds.min_col == bounds.max_col {
+            return self.cell_value(bounds.sheet, bounds.min_row, bounds.min_col);
+        }
+
+        let rows = (bounds.max_row - bounds.min_row + 1) as usize;
+        let cols = (bounds.max_col - bounds.min_col + 1) as usize;
+        if rows.saturating_mul(cols) > MAX_RANGE_CELLS {
+            return Value::Error(CellError::Num);
+        }
+        let mut values = Vec::with_capacity(rows * cols);
+        for row in bounds.min_row..=bounds.max_row {
+            for col in bounds.min_col..=bounds.max_col {
+                values.push(self.cell_value(bounds.sheet, row, col));
+            }
+        }
+        Value::Array(Array::new(rows, cols, values))
+    }
+
+    fn cell_value(&self, sheet: usize, row: u32, col: u32) -> Value {
+        self.model
+            .values
+            .get(&(sheet, row, col))
+            .cloned()
+            .unwrap_or(Value::Empty)
+    }
+}
+
+fn literal_value(literal: &LiteralValue) -> Value {
+    match literal {
+        LiteralValue::Number(n) => Value::Number(*n),
+        LiteralValue::Int(i) => Value::Number(*i as f64),
+        LiteralValue::Text(s) => Value::Text(s.clone()),
+        LiteralValue::Boolean(b) => Value::Bool(*b),
+        other => {
+            let rendered = other.to_string();
+            CellError::parse(&rendered)
+                .map(Value::Error)
+                .unwrap_or_else(|| Value::from_cell_text(&rendered))
+        }
+    }
+}
+
+fn map_unary(value: Value, f: impl Fn(&Value) -> Value) -> Value {
+    match value {
+        Value::Array(array) => Value::Array(Array::new(
+            array.rows,
+            array.cols,
+            array.values.iter().map(f).collect(),
+        )),
+        other => f(&other),
+    }
+}
+
+/// Apply a binary operator, broadcasting over arrays element-wise.
+fn binary_op(op: &str, left: Value, right: Value) -> Value {
+    match (left, right) {
+        (Value::Array(a), Value::Array(b)) => {
+            let rows = if a.rows == 1 { b.rows } else { a.rows };
+            let cols = if a.cols == 1 { b.cols } else { a.cols };
+            let mut values = Vec::with_capacity(rows * cols);
+            for r in 0..rows {
+                for c in 0..cols {
+                    let lv = a.get(
+                        if a.rows == 1 { 0 } else { r },
+                        if a.cols == 1 { 0 } else { c },
+                    );
+                    let rv = b.get(
+                        if b.rows == 1 { 0 } else { r },
+                        if b.cols == 1 { 0 } else { c },
+                    );
+                    values.push(match (lv, rv) {
+                        (Some(l), Some(r)) => scalar_op(op, l, r),
+                        _ => Value::Error(CellError::Na),
+                    });
+                }
+            }
+            Value::Array(Array::new(rows, cols, values))
+        }
+        (Value::Array(a), right) => Value::Array(Array::new(
+            a.rows,
+            a.cols,
+            a.values.iter().map(|l| scalar_op(op, l, &right)).collect(),
+        )),
+        (left, Value::Array(b)) => Value::Array(Array::new(
+            b.rows,
+            b.cols,
+            b.values.iter().map(|r| scalar_op(op, &left, r)).collect(),
+        )),
+        (left, right) => scalar_op(op, &left, &right),
+    }
+}
+
+fn scalar_op(op: &str, left: &Value, right: &Value) -> Value {
+    if let Some(err) = left.as_error() {
+        return Value::Error(err);
+    }
+    if let Some(err) = right.as_error() {
+        return Value::Error(err);
+    }
+
+    match op {
+        "&" => match (left.to_text(), right.to_text()) {
+            (Ok(l), Ok(r)) => Value::Text(l + &r),
+            (Err(e), _) | (_, Err(e)) => Value::Error(e),
+        },
+        "=" | "<>" | "<" | ">" | "<=" | ">=" => {
+            let ordering = compare_values(left, right);
+            use std::cmp::Ordering::*;
+            Value::Bool(match op {
+                "=" => ordering == Equal,
+                "<>" => ordering != Equal,
+                "<" => ordering == Less,
+                ">" => ordering == Greater,
+                "<=" => ordering != Greater,
+                _ => ordering != Less,
+            })
+        }
+        _ => {
+            let (l, r) = match (left.to_number(), right.to_number()) {
+                (Ok(l), Ok(r)) => (l, r),
+                (Err(e), _) | (_, Err(e)) => return Value::Error(e),
+            };
+            let result = match op {
+                "+" => l + r,
+                "-" => l - r,
+                "*" => l * r,
+                "/" => {
+                    if r == 0.0 {
+                        return Value::Error(CellError::Div0);
+                    }
+                    l / r
+                }
+                "^" => l.powf(r),
+                _ => return Value::Error(CellError::Value),
+            };
+            if result.is_finite() {
+                Value::Number(result)
+            } else {
+                Value::Error(CellError::Num)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book_with(cells: &[(&str, &str, bool)]) -> Spreadsheet {
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        for (addr, value, is_formula) in cells {
+            let cell = sheet.get_cell_mut(*addr);
+            if *is_formula {
+                cell.set_formula(value.to_string());
+            } else {
+                cell.set_value(value.to_string());
+            }
+        }
+        book
+    }
+
+    fn cached(book: &Spreadsheet, addr: &str) -> String {
+        book.get_sheet(&0)
+            .unwrap()
+            .get_cell(addr)
+            .map(|c| c.get_value().to_string())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn evaluates_chain_in_dependency_order() {
+        let mut book = book_with(&[
+            ("A1", "2", false),
+            ("A2", "3", false),
+            ("B1", "B2*10", true),
+            ("B2", "SUM(A1:A2)", true),
+        ]);
+        let unsupported = recalculate_book(&mut book);
+        assert!(unsupported.is_empty());
+        assert_eq!(cached(&book, "B2"), "5");
+        assert_eq!(cached(&book, "B1"), "50");
+    }
+
+    #[test]
+    fn orders_formulas_inside_whole_column_ranges() {
+        let mut cells: Vec<(String, String, bool)> = (1..=100)
+            .map(|row| (format!("A{row}"), format!("B{row}*2"), true))
+            .collect();
+        cells.extend((1..=100).map(|row| (format!("B{row}"), row.to_string(), false)));
+        cells.insert(0, ("C1".to_string(), "SUM(A:A)".to_string(), true));
+        let cells: Vec<(&str, &str, bool)> = cells
+            .iter()
+            .map(|(addr, value, is_formula)| (addr.as_str(), value.as_str(), *is_formula))
+            .collect();
+        let mut book = book_with(&cells);
+        let unsupported = recalculate_book(&mut book);
+        assert!(unsupported.is_empty());
+        assert_eq!(cached(&book, "A100"), "200");
+        assert_eq!(cached(&book, "C1"), "10100");
+    }
+
+    #[test]
+    fn reports_unsupported_functions_per_cell() {
+        let mut book = book_with(&[("A1", "1", false), ("B1", "WEBSERVICE(A1)", true)]);
+        let unsupported = recalculate_book(&mut book);
+        assert_eq!(unsupported.len(), 1);
+        assert_eq!(unsupported[0].cell, "B1");
+        assert_eq!(unsupported[0].functions, vec!["WEBSERVICE".to_string()]);
+    }
+
+    #[test]
+    fn detects_circular_references() {
+        let mut book = book_with(&[("A1", "B1+1", true), ("B1", "A1+1", true)]);
+        let unsupported = recalculate_book(&mut book);
+        assert_eq!(unsupported.len(), 2);
+        assert!(unsupported.iter().all(|u| u.reason == "circular reference"));
+    }
+
+    #[test]
+    fn lookup_and_conditional_aggregates() {
+        let mut book = book_with(&[
+            ("A1", "apple", false),
+            ("A2", "pear", false),
+            ("A3", "apple", false),
+            ("B1", "1", false),
+            ("B2", "2", false),
+            ("B3", "4", false),
+            ("C1", "VLOOKUP(\"pear\",A1:B3,2,FALSE)", true),
+            ("C2", "SUMIFS(B1:B3,A1:A3,\"apple\")", true),
+            ("C3", "INDEX(B1:B3,MATCH(\"apple\",A1:A3,0))", true),
+            ("C4", "IF(C2>4,\"big\",\"small\")", true),
+            ("C5", "1/0", true),
+        ]);
+        recalculate_book(&mut book);
+        assert_eq!(cached(&book, "C1"), "2");
+        assert_eq!(cached(&book, "C2"), "5");
+        assert_eq!(cached(&book, "C3"), "1");
+        assert_eq!(cached(&book, "C4"), "big");
+        assert_eq!(cached(&book, "C5"), "#DIV/0!");
+    }
+}
@@ -0,0 +1,363 @@
+use chrono::{Duration, NaiveDate};
+
+/// Excel error literals produced by the native evaluator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellError {
+    Div0,
+    Na,
+    Name,
+    Null,
+    Num,
+    Ref,
+    Value,
+}
+
+impl CellError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CellError::Div0 => "#DIV/0!",
+            CellError::Na => "#N/A",
+            CellError::Name => "#NAME?",
+            CellError::Null => "#NULL!",
+            CellError::Num => "#NUM!",
+            CellError::Ref => "#REF!",
+            CellError::Value => "#VALUE!",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_uppercase().as_str() {
+            "#DIV/0!" => Some(CellError::Div0),
+            "#N/A" => Some(CellError::Na),
+            "#NAME?" => Some(CellError::Name),
+            "#NULL!" => Some(CellError::Null),
+            "#NUM!" => Some(CellError::Num),
+            "#REF!" => Some(CellError::Ref),
+            "#VALUE!" => Some(CellError::Value),
+            _ => None,
+        }
+    }
+}
+
+/// A rectangular block of values (range references and array results).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Array {
+    pub rows: usize,
+    pub cols: usize,
+    pub values: Vec<Value>,
+}
+
+impl Array {
+    pub fn new(rows: usize, cols: usize, values: Vec<Value>) -> Self {
+        debug_assert_eq!(rows * cols, values.len());
+        Self { rows, cols, values }
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&Value> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        self.values.get(row * self.cols + col)
+    }
+
+    pub fn row(&self, row: usize) -> Vec<Value> {
+        (0..self.cols)
+            .filter_map(|col| self.get(row, col).cloned())
+            .collect()
+    }
+
+    pub fn column(&self, col: usize) -> Vec<Value> {
+        (0..self.rows)
+            .filter_map(|row| self.get(row, col).cloned())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Empty,
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Error(CellError),
+    Array(Array),
+}
+
+impl Value {
+    /// Interpret a stored cell by its data type, so text such as `00123` or
+    /// `1e3` stays text and only untyped or numeric cells are read as numbers.
+    pub fn from_cell(raw: &str, data_type: &str) -> Self {
+        if raw.is_empty() {
+            return Value::Empty;
+        }
+        match data_type {
+            "b" => Value::Bool(raw.eq_ignore_ascii_case("true") || raw == "1"),
+            "s" | "inlineStr" => Value::Text(raw.to_string()),
+            "e" | "str" => CellError::parse(raw)
+                .map(Value::Error)
+                .unwrap_or_else(|| Value::Text(raw.to_string())),
+            _ => Value::from_cell_text(raw),
+        }
+    }
+
+    /// Interpret a cached cell string the same way the read tools do.
+    pub fn from_cell_text(raw: &str) -> Self {
+        if raw.is_empty() {
+            return Value::Empty;
+        }
+        if let Ok(number) = raw.parse::<f64>() {
+            return Value::Number(number);
+        }
+        match raw.to_ascii_uppercase().as_str() {
+            "TRUE" => Value::Bool(true),
+            "FALSE" => Value::Bool(false),
+            _ => CellError::parse(raw)
+                .map(Value::Error)
+                .unwrap_or_else(|| Value::Text(raw.to_string())),
+        }
+    }
+
+    /// Collapse an array to its top-left element (implicit intersection lite).
+    pub fn scalar(self) -> Value {
+        match self {
+            Value::Array(array) => array.values.into_iter().next().unwrap_or(Value::Empty),
+            other => other,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self, Value::Error(_))
+    }
+
+    pub fn as_error(&self) -> Option<CellError> {
+        match self {
+            Value::Error(err) => Some(*err),
+            _ => None,
+        }
+    }
+
+    pub fn to_number(&self) -> Result<f64, CellError> {
+        match self {
+            Value::Empty => Ok(0.0),
+            Value::Number(n) => Ok(*n),
+            Value::Bool(b) => Ok(if *b { 1.0 } else { 0.0 }),
+            Value::Text(s) => parse_number_text(s).ok_or(CellError::Value),
+            Value::Error(err) => Err(*err),
+            Value::Array(array) => array.values.first().map_or(Ok(0.0), |v| v.to_number()),
+        }
+    }
+
+    pub fn to_text(&self) -> Result<String, CellError> {
+        match self {
+            Value::Empty => Ok(String::new()),
+            Value::Number(n) => Ok(format_number(*n)),
+            Value::Bool(b) => Ok(if *b { "TRUE" } else { "FALSE" }.to_string()),
+            Value::Text(s) => Ok(s.clone()),
+            Value::Error(err) => Err(*err),
+            Value::Array(array) => array
+                .values
+                .first()
+                .map_or(Ok(String::new()), |v| v.to_text()),
+        }
+    }
+
+    pub fn to_bool(&self) -> Result<bool, CellError> {
+        match self {
+            Value::Empty => Ok(false),
+            Value::Number(n) => Ok(*n != 0.0),
+            Value::Bool(b) => Ok(*b),
+            Value::Text(s) => match s.trim().to_ascii_uppercase().as_str() {
+                "TRUE" => Ok(true),
+                "FALSE" => Ok(false),
+                _ => Err(CellError::Value),
+            },
+            Value::Error(err) => Err(*err),
+            Value::Array(array) => array.values.first().map_or(Ok(false), |v| v.to_bool()),
+        }
+    }
+
+    /// Text written into the cell's cached value after evaluation.
+    pub fn to_cached_text(&self) -> String {
+        match self {
+            Value::Empty => "0".to_string(),
+            Value::Number(n) => n.to_string(),
+            Value::Bool(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+            Value::Text(s) => s.clone(),
+            Value::Error(err) => err.as_str().to_string(),
+            Value::Array(array) => array
+                .values
+                .first()
+                .map(|v| v.to_cached_text())
+                .unwrap_or_else(|| "0".to_string()),
+        }
+    }
+
+    /// Flatten into scalar values (row-major for arrays).
+    pub fn flatten(&self) -> Vec<Value> {
+        match self {
+            Value::Array(array) => array.values.clone(),
+            other => vec![other.clone()],
+        }
+    }
+}
+
+pub fn parse_number_text(raw: &str) -> Option<f64> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    if let Some(pct) = trimmed.strip_suffix('%') {
+        return pct
+            .trim()
+            .replace(',', "")
+            .parse::<f64>()
+            .ok()
+            .map(|n| n / 100.0);
+    }
+    let cleaned = trimmed.trim_start_matches('$').replace(',', "");
+    cleaned.parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+/// Render a number the way Excel's General format does (15 significant digits).
+pub fn format_number(n: f64) -> String {
+    if n == 0.0 {
+        return "0".to_string();
+    }
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        return format!("{:.0}", n);
+    }
+    let rounded: f64 = format!("{:.14e}", n).parse().unwrap_or(n);
+    let text = rounded.to_string();
+    if text.contains('e') {
+        return format!("{:E}", rounded);
+    }
+    text
+}
+
+/// Compare two scalars using Excel ordering: numbers < text < booleans.
+pub fn compare_values(left: &Value, right: &Value) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Number(_) | Value::Empty => 0,
+            Value::Text(_) => 1,
+            Value::Bool(_) => 2,
+            Value::Error(_) | Value::Array(_) => 3,
+        }
+    }
+
+    match (left, right) {
+        (Value::Empty, Value::Empty) => Ordering::Equal,
+        (Value::Empty, Value::Text(s)) => {
+            if s.is_empty() {
+                Ordering::Equal
+            } else {
+                Ordering::Less
+            }
+        }
+        (Value::Text(s), Value::Empty) => {
+            if s.is_empty() {
+                Ordering::Equal
+            } else {
+                Ordering::Greater
+            }
+        }
+        (Value::Empty, Value::Bool(b)) => false.cmp(b),
+        (Value::Bool(b), Value::Empty) => b.cmp(&false),
+        (Value::Number(a), Value::Empty) => a.partial_cmp(&0.0).unwrap_or(Ordering::Equal),
+        (Value::Empty, Value::Number(b)) => 0.0_f64.partial_cmp(b).unwrap_or(Ordering::Equal),
+        (Value::Number(a), Value::Number(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+        (Value::Text(a), Value::Text(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        _ => rank(left).cmp(&rank(right)),
+    }
+}
+
+const EXCEL_LEAP_BUG_SERIAL: i64 = 60;
+
+fn epoch() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1899, 12, 30).expect("valid excel epoch")
+}
+
+/// Convert a 1900-system serial into a calendar date, honouring the 1900 leap-year bug.
+pub fn serial_to_date(serial: f64) -> Option<NaiveDate> {
+    let days = serial.floor() as i64;
+    if days < 0 {
+        return None;
+    }
+    if days < EXCEL_LEAP_BUG_SERIAL {
+        return epoch().checked_add_signed(Duration::days(days + 1));
+    }
+    if days == EXCEL_LEAP_BUG_SERIAL {
+        // Excel's phantom 1900-02-29; clamp to the 28th.
+        return NaiveDate::from_ymd_opt(1900, 2, 28);
+    }
+    epoch().checked_add_signed(Duration::days(days))
+}
+
+pub fn date_to_serial(date: NaiveDate) -> f64 {
+    let days = (date - epoch()).num_days();
+    if days <= EXCEL_LEAP_BUG_SERIAL {
+        (days - 1) as f64
+    } else {
+        days as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serial_round_trip_respects_leap_bug() {
+        let jan1 = NaiveDate::from_ymd_opt(1900, 1, 1).unwrap();
+        assert_eq!(date_to_serial(jan1), 1.0);
+        let mar1 = NaiveDate::from_ymd_opt(1900, 3, 1).unwrap();
+        assert_eq!(date_to_serial(mar1), 61.0);
+        let d = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        assert_eq!(serial_to_date(date_to_serial(d)), Some(d));
+        assert_eq!(date_to_serial(d), 45351.0);
+    }
+
+    #[test]
+    fn general_format_trims_float_noise() {
+        assert_eq!(format_number(0.1 + 0.2), "0.3");
+        assert_eq!(format_number(42.0), "42");
+        assert_eq!(format_number(-1.5), "-1.5");
+    }
+
+    #[test]
+    fn cached_text_round_trips_through_from_cell_text() {
+        for value in [
+            Value::Number(3.25),
+            Value::Bool(true),
+            Value::Text("abc".into()),
+            Value::Error(CellError::Div0),
+        ] {
+            assert_eq!(Value::from_cell_text(&value.to_cached_text()), value);
+        }
+    }
+
+    #[test]
+    fn cell_data_type_keeps_numeric_looking_text() {
+        assert_eq!(Value::from_cell("00123", "s"), Value::Text("00123".into()));
+        assert_eq!(Value::from_cell("1e3", "str"), Value::Text("1e3".into()));
+        assert_eq!(Value::from_cell("1e3", "n"), Value::Number(1000.0));
+        assert_eq!(Value::from_cell("1", "b"), Value::Bool(true));
+        assert_eq!(Value::from_cell("#N/A", "e"), Value::Error(CellError::Na));
+    }
+
+    #[test]
+    fn compare_orders_types_like_excel() {
+        use std::cmp::Ordering;
+        assert_eq!(
+            compare_values(&Value::Number(100.0), &Value::Text("a".into())),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_values(&Value::Text("ABC".into()), &Value::Text("abc".into())),
+            Ordering::Equal
+        );
+    }
+}
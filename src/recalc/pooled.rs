@@ -1,28 +1,745 @@
+use super::RecalcConfig;
 use super::executor::{RecalcExecutor, RecalcResult};
-use anyhow::Result;
+use crate::recovery::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakerState};
+use anyhow::{Context, Result, anyhow, bail};
 use async_trait::async_trait;
-use std::path::Path;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::{Mutex, Notify, OnceCell, OwnedMutexGuard, OwnedSemaphorePermit, Semaphore};
+use tokio::time;
+use tracing::{debug, info, warn};
 
-/// Pooled executor for V2 implementation
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+/// How long a new slot may take until its worker is connected to soffice.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+/// How long an idle slot may take to answer a health check ping.
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+const WORKER_SCRIPT: &str = include_str!("uno_worker.py");
+const WORKER_SCRIPT_NAME: &str = "uno_worker.py";
+
+/// Numbers the pools of this process, so pool dirs and pipes never collide.
+static NEXT_POOL: AtomicUsize = AtomicUsize::new(0);
+
+/// Pool of long-lived headless soffice processes.
 ///
-/// This executor will use UNO socket connections for improved performance.
-/// Currently marked as future work - V2 feature.
-#[allow(dead_code)]
+/// Each slot runs one soffice instance with its own user profile, listening on a private UNO
+/// pipe (`--accept=pipe,...;urp;`), plus a Python worker (`uno_worker.py`) connected to that
+/// pipe. A job is one JSON line to the worker, which loads the workbook in the running
+/// instance, calls `calculateAll()`, stores it and answers with the outcome, so no process is
+/// started per job and completion is the worker's reply. The periodic health check pings idle
+/// slots through their worker; crashed or hung slots are killed and restarted, either by the
+/// health check or by the next job that needs the slot.
+///
+/// The pool is sized to `max_concurrent_recalcs`, so every holder of a `GlobalRecalcLock`
+/// permit is guaranteed an idle slot; the internal semaphore only matters for callers that
+/// bypass the global lock.
 pub struct PooledExecutor {
-    socket_path: std::path::PathBuf,
+    launcher: Arc<Launcher>,
+    timeout: Duration,
+    slots: Vec<Arc<Mutex<Slot>>>,
+    available: Arc<Semaphore>,
+    /// Signalled whenever a job or the health check releases a slot.
+    released: Arc<Notify>,
+    circuit_breaker: CircuitBreaker,
+    /// Whether `pool_dir` was created for this process and is removed on drop.
+    owns_pool_dir: bool,
+}
+
+/// Everything needed to start a slot.
+struct Launcher {
+    soffice_path: PathBuf,
+    python_path: PathBuf,
+    pool_dir: PathBuf,
+    script_written: OnceCell<()>,
+}
+
+struct Slot {
+    id: usize,
+    profile_dir: PathBuf,
+    /// Pipe name, unique per process and pool so no two slots share an instance.
+    pipe_name: String,
+    soffice: Option<Child>,
+    worker: Option<Worker>,
+    jobs_completed: u64,
+}
+
+struct Worker {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkerReply {
+    #[serde(default)]
+    ready: Option<bool>,
+    #[serde(default)]
+    ok: Option<bool>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+impl Worker {
+    async fn send(&mut self, message: &serde_json::Value) -> Result<()> {
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+        self.stdin
+            .write_all(line.as_bytes())
+            .await
+            .context("failed to send to pooled recalc worker")?;
+        self.stdin.flush().await?;
+        Ok(())
+    }
+
+    async fn read_reply(&mut self) -> Result<WorkerReply> {
+        let line = self
+            .stdout
+            .next_line()
+            .await
+            .context("failed to read from pooled recalc worker")?
+            .ok_or_else(|| anyhow!("pooled recalc worker exited"))?;
+        serde_json::from_str(&line)
+            .with_context(|| format!("unexpected output from pooled recalc worker: {line}"))
+    }
+}
+
+fn is_running(child: &mut Child, slot: usize, what: &str) -> bool {
+    match child.try_wait() {
+        Ok(None) => true,
+        Ok(Some(status)) => {
+            warn!(slot, %status, "pooled {} exited", what);
+            false
+        }
+        Err(e) => {
+            warn!(slot, error = %e, "failed to poll pooled {}", what);
+            false
+        }
+    }
+}
+
+impl Slot {
+    fn new(id: usize, pool_dir: &Path, pool_tag: &str) -> Self {
+        Self {
+            id,
+            profile_dir: pool_dir.join(format!("slot-{id}")),
+            pipe_name: format!("mcp_pool_{pool_tag}_{id}"),
+            soffice: None,
+            worker: None,
+            jobs_completed: 0,
+        }
+    }
+
+    fn profile_url(&self) -> String {
+        format!("file://{}", self.profile_dir.display())
+    }
+
+    /// True if both the slot's soffice and its worker are running.
+    fn is_alive(&mut self) -> bool {
+        let id = self.id;
+        if !self
+            .soffice
+            .as_mut()
+            .is_some_and(|child| is_running(child, id, "soffice"))
+        {
+            self.soffice = None;
+        }
+        if !self
+            .worker
+            .as_mut()
+            .is_some_and(|worker| is_running(&mut worker.child, id, "recalc worker"))
+        {
+            self.worker = None;
+        }
+        self.soffice.is_some() && self.worker.is_some()
+    }
+
+    /// True if the slot is running and its soffice answers a call over the UNO bridge
+    /// within `timeout`. A hung instance keeps its process alive but never answers.
+    async fn ping(&mut self, timeout: Duration) -> bool {
+        if !self.is_alive() {
+            return false;
+        }
+        let Some(worker) = self.worker.as_mut() else {
+            return false;
+        };
+        let answer = async {
+            worker.send(&serde_json::json!({ "ping": true })).await?;
+            worker.read_reply().await
+        };
+        match time::timeout(timeout, answer).await {
+            Ok(Ok(reply)) if reply.ok == Some(true) => true,
+            Ok(Ok(reply)) => {
+                warn!(slot = self.id, error = ?reply.error, "pooled soffice failed its ping");
+                false
+            }
+            Ok(Err(e)) => {
+                warn!(slot = self.id, error = %e, "pooled soffice ping failed");
+                false
+            }
+            Err(_) => {
+                warn!(
+                    slot = self.id,
+                    "pooled soffice did not answer its ping; restarting"
+                );
+                false
+            }
+        }
+    }
+
+    async fn kill(&mut self) {
+        if let Some(mut worker) = self.worker.take() {
+            let _ = worker.child.kill().await;
+        }
+        if let Some(mut child) = self.soffice.take() {
+            let _ = child.kill().await;
+        }
+    }
+
+    async fn start(&mut self, launcher: &Launcher) -> Result<()> {
+        self.kill().await;
+        tokio::fs::create_dir_all(&self.profile_dir)
+            .await
+            .with_context(|| format!("failed to create {}", self.profile_dir.display()))?;
+
+        let soffice = Command::new(&launcher.soffice_path)
+            .args([
+                "--headless",
+                "--invisible",
+                "--norestore",
+                "--nodefault",
+                "--nofirststartwizard",
+                "--nolockcheck",
+                &format!("-env:UserInstallation={}", self.profile_url()),
+                &format!("--accept=pipe,name={};urp;", self.pipe_name),
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow!("failed to spawn pooled soffice: {}", e))?;
+        self.soffice = Some(soffice);
+
+        let mut child = Command::new(&launcher.python_path)
+            .arg(launcher.pool_dir.join(WORKER_SCRIPT_NAME))
+            .arg(&self.pipe_name)
+            .arg(STARTUP_TIMEOUT.as_secs().to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                anyhow!(
+                    "failed to spawn pooled recalc worker with {}: {}",
+                    launcher.python_path.display(),
+                    e
+                )
+            })?;
+        let stdin = child.stdin.take().context("worker stdin not captured")?;
+        let stdout = child.stdout.take().context("worker stdout not captured")?;
+        let mut worker = Worker {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout).lines(),
+        };
+
+        let ready = time::timeout(STARTUP_TIMEOUT, worker.read_reply())
+            .await
+            .map_err(|_| anyhow!("pooled soffice slot {} did not start in time", self.id))??;
+        if ready.ready != Some(true) {
+            bail!(
+                "pooled recalc worker for slot {} could not connect to soffice: {}",
+                self.id,
+                ready.error.unwrap_or_default()
+            );
+        }
+        self.worker = Some(worker);
+        info!(slot = self.id, "pooled soffice started");
+        Ok(())
+    }
+}
+
+impl Launcher {
+    /// Write this build's worker script into the pool directory before the first slot starts.
+    async fn ensure_worker_script(&self) -> Result<()> {
+        self.script_written
+            .get_or_try_init(|| async {
+                tokio::fs::create_dir_all(&self.pool_dir)
+                    .await
+                    .with_context(|| format!("failed to create {}", self.pool_dir.display()))?;
+                let path = self.pool_dir.join(WORKER_SCRIPT_NAME);
+                tokio::fs::write(&path, WORKER_SCRIPT)
+                    .await
+                    .with_context(|| format!("failed to write {}", path.display()))
+            })
+            .await
+            .map(|_| ())
+    }
+
+    async fn start_slot(&self, slot: &mut Slot) -> Result<()> {
+        self.ensure_worker_script().await?;
+        slot.start(self).await
+    }
+}
+
+/// Python that can `import uno`: LibreOffice's bundled interpreter when soffice ships one
+/// (`program/python`), otherwise the system `python3` (Debian's `python3-uno`).
+fn default_python_path(soffice_path: &Path) -> PathBuf {
+    soffice_path
+        .canonicalize()
+        .ok()
+        .and_then(|soffice| soffice.parent().map(|dir| dir.join("python")))
+        .filter(|python| python.is_file())
+        .unwrap_or_else(|| PathBuf::from("python3"))
+}
+
+impl PooledExecutor {
+    pub fn new(config: &RecalcConfig) -> Self {
+        let pool_size = config.pool_size.max(1);
+        let soffice_path = config
+            .soffice_path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("/usr/bin/soffice"));
+        let python_path = config
+            .python_path
+            .clone()
+            .unwrap_or_else(|| default_python_path(&soffice_path));
+        let pool_tag = format!(
+            "{}_{}",
+            std::process::id(),
+            NEXT_POOL.fetch_add(1, Ordering::Relaxed)
+        );
+        let (pool_dir, owns_pool_dir) = match &config.pool_dir {
+            Some(dir) => (dir.clone(), false),
+            None => (
+                std::env::temp_dir().join(format!("mcp-soffice-pool-{pool_tag}")),
+                true,
+            ),
+        };
+        let executor = Self {
+            timeout: Duration::from_millis(config.timeout_ms.unwrap_or(30_000)),
+            slots: (0..pool_size)
+                .map(|id| Arc::new(Mutex::new(Slot::new(id, &pool_dir, &pool_tag))))
+                .collect(),
+            launcher: Arc::new(Launcher {
+                soffice_path,
+                python_path,
+                pool_dir,
+                script_written: OnceCell::new(),
+            }),
+            available: Arc::new(Semaphore::new(pool_size)),
+            released: Arc::new(Notify::new()),
+            circuit_breaker: CircuitBreaker::new("pooled_recalc", CircuitBreakerConfig::recalc()),
+            owns_pool_dir,
+        };
+        executor.start_health_checks();
+        executor
+    }
+
+    pub fn pool_size(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Directory holding the slot profiles and the worker script.
+    pub fn pool_dir(&self) -> &Path {
+        &self.launcher.pool_dir
+    }
+
+    pub fn circuit_state(&self) -> CircuitBreakerState {
+        self.circuit_breaker.state()
+    }
+
+    /// Periodically ping idle slots and restart those that died or hang. Also pre-warms every
+    /// slot on the first tick. Skipped when constructed outside a tokio runtime.
+    fn start_health_checks(&self) {
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            debug!("no tokio runtime; pooled soffice health checks disabled");
+            return;
+        };
+        if !self.launcher.soffice_path.exists() {
+            return;
+        }
+
+        let slots: Vec<std::sync::Weak<Mutex<Slot>>> =
+            self.slots.iter().map(Arc::downgrade).collect();
+        let launcher = Arc::downgrade(&self.launcher);
+        let released = self.released.clone();
+        handle.spawn(async move {
+            let mut interval = time::interval(HEALTH_CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                // Executor dropped; stop checking.
+                let Some(launcher) = launcher.upgrade() else {
+                    break;
+                };
+                for weak in &slots {
+                    let Some(slot) = weak.upgrade() else {
+                        continue;
+                    };
+                    // Busy slots are in use and therefore checked by their job.
+                    let Ok(mut slot) = slot.try_lock() else {
+                        continue;
+                    };
+                    if !slot.ping(PING_TIMEOUT).await
+                        && let Err(e) = launcher.start_slot(&mut slot).await
+                    {
+                        warn!(slot = slot.id, error = %e, "pooled soffice restart failed");
+                    }
+                    drop(slot);
+                    released.notify_waiters();
+                }
+            }
+        });
+    }
+
+    async fn checkout(&self) -> Result<Lease> {
+        let permit = self
+            .available
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| anyhow!("recalc pool closed: {}", e))?;
+        loop {
+            // Register before scanning so a release during the scan is not missed.
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+            for slot in &self.slots {
+                if let Ok(guard) = slot.clone().try_lock_owned() {
+                    return Ok(Lease {
+                        slot: Some(guard),
+                        _permit: permit,
+                        released: self.released.clone(),
+                    });
+                }
+            }
+            // A permit guarantees a free slot unless the health check holds one, possibly
+            // through a restart; wait for it to be released.
+            released.await;
+        }
+    }
+
+    /// Run one job on `slot`. Returns whether the slot's instance was already running, i.e.
+    /// the job paid no LibreOffice startup.
+    async fn run_job(&self, slot: &mut Slot, workbook_path: &Path) -> Result<bool> {
+        let was_warm = slot.is_alive();
+        if !was_warm {
+            self.launcher.start_slot(slot).await?;
+        }
+
+        let abs_path = tokio::fs::canonicalize(workbook_path)
+            .await
+            .map_err(|e| anyhow!("failed to canonicalize path: {}", e))?;
+        let path = abs_path
+            .to_str()
+            .ok_or_else(|| anyhow!("workbook path is not valid UTF-8"))?;
+        let worker = slot
+            .worker
+            .as_mut()
+            .ok_or_else(|| anyhow!("pooled soffice slot {} has no worker", slot.id))?;
+        worker.send(&serde_json::json!({ "path": path })).await?;
+
+        let reply = worker.read_reply().await?;
+        match reply.ok {
+            Some(true) => {}
+            _ => bail!(
+                "pooled soffice failed to recalculate {}: {}",
+                abs_path.display(),
+                reply
+                    .error
+                    .unwrap_or_else(|| "no error reported".to_string())
+            ),
+        }
+
+        slot.jobs_completed += 1;
+        Ok(was_warm)
+    }
+}
+
+/// A checked-out slot. Dropping it releases the slot and wakes waiting checkouts.
+struct Lease {
+    slot: Option<OwnedMutexGuard<Slot>>,
+    _permit: OwnedSemaphorePermit,
+    released: Arc<Notify>,
+}
+
+impl std::ops::Deref for Lease {
+    type Target = Slot;
+
+    fn deref(&self) -> &Slot {
+        self.slot
+            .as_ref()
+            .expect("lease holds its slot until dropped")
+    }
+}
+
+impl std::ops::DerefMut for Lease {
+    fn deref_mut(&mut self) -> &mut Slot {
+        self.slot
+            .as_mut()
+            .expect("lease holds its slot until dropped")
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        // Unlock before notifying, so woken checkouts find the slot free.
+        self.slot.take();
+        self.released.notify_waiters();
+    }
+}
+
+impl Drop for PooledExecutor {
+    fn drop(&mut self) {
+        if self.owns_pool_dir {
+            let _ = std::fs::remove_dir_all(&self.launcher.pool_dir);
+        }
+    }
 }
 
 #[async_trait]
 impl RecalcExecutor for PooledExecutor {
-    async fn recalculate(&self, _workbook_path: &Path) -> Result<RecalcResult> {
-        // V2: Pooled executor with UNO socket not yet implemented
-        // This is a planned feature for improved performance
-        todo!("V2: Pooled executor with UNO socket not yet implemented")
+    async fn recalculate(&self, workbook_path: &Path) -> Result<RecalcResult> {
+        let start = Instant::now();
+        let mut slot = self.checkout().await?;
+        let slot_id = slot.id;
+        let slot_ref: &mut Slot = &mut slot;
+
+        let result = self
+            .circuit_breaker
+            .execute_async(|| async move {
+                match time::timeout(self.timeout, self.run_job(slot_ref, workbook_path)).await {
+                    Ok(result) => result,
+                    Err(_) => Err(anyhow!("soffice timed out after {:?}", self.timeout)),
+                }
+            })
+            .await;
+
+        let was_warm = match result {
+            Ok(was_warm) => was_warm,
+            Err(e) => {
+                // The instance may be wedged mid-document, and a timed-out worker may still
+                // answer the abandoned job; restart the slot lazily on next use.
+                warn!(slot = slot_id, error = %e, "pooled recalc failed; recycling slot");
+                slot.kill().await;
+                return Err(e);
+            }
+        };
+
+        let duration = start.elapsed();
+        crate::metrics::METRICS.record_recalc_duration(duration);
+        debug!(
+            slot = slot_id,
+            was_warm,
+            jobs_completed = slot.jobs_completed,
+            ?duration,
+            "pooled recalc complete"
+        );
+
+        Ok(RecalcResult {
+            duration_ms: duration.as_millis() as u64,
+            was_warm,
+            executor_type: "pooled",
+            unsupported: Vec::new(),
+        })
     }
 
     fn is_available(&self) -> bool {
-        // V2: Pooled executor availability check
-        // This will check if UNO socket is available
-        todo!("V2: Pooled executor availability check")
+        self.launcher.soffice_path.exists()
+            && self.circuit_breaker.state() != CircuitBreakerState::Open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(pool_size: usize) -> RecalcConfig {
+        RecalcConfig {
+            soffice_path: Some(PathBuf::from("/nonexistent/soffice")),
+            timeout_ms: Some(1_000),
+            pool_size,
+            ..RecalcConfig::default()
+        }
+    }
+
+    #[test]
+    fn pool_size_is_at_least_one() {
+        assert_eq!(PooledExecutor::new(&config(0)).pool_size(), 1);
+        assert_eq!(PooledExecutor::new(&config(3)).pool_size(), 3);
+    }
+
+    #[test]
+    fn unavailable_without_soffice() {
+        assert!(!PooledExecutor::new(&config(1)).is_available());
+    }
+
+    #[test]
+    fn pool_dir_is_per_executor_unless_configured() {
+        let executor = PooledExecutor::new(&config(1));
+        let other = PooledExecutor::new(&config(1));
+        let prefix = format!("mcp-soffice-pool-{}_", std::process::id());
+        for pool in [&executor, &other] {
+            let name = pool.pool_dir().file_name().unwrap().to_string_lossy();
+            assert!(name.starts_with(&prefix), "{name}");
+        }
+        assert_ne!(executor.pool_dir(), other.pool_dir());
+        let pipe = |pool: &PooledExecutor| pool.slots[0].try_lock().unwrap().pipe_name.clone();
+        assert_ne!(pipe(&executor), pipe(&other));
+
+        let dir = tempfile::tempdir().unwrap();
+        let executor = PooledExecutor::new(&RecalcConfig {
+            pool_dir: Some(dir.path().to_path_buf()),
+            ..config(1)
+        });
+        assert_eq!(executor.pool_dir(), dir.path());
+        drop(executor);
+        assert!(dir.path().exists());
+    }
+
+    #[tokio::test]
+    async fn failed_spawn_is_reported_and_counted_by_breaker() {
+        let executor = PooledExecutor::new(&config(1));
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        for _ in 0..3 {
+            assert!(executor.recalculate(tmp.path()).await.is_err());
+        }
+        assert_eq!(executor.circuit_state(), CircuitBreakerState::Open);
+        let err = executor.recalculate(tmp.path()).await.unwrap_err();
+        assert!(err.to_string().contains("circuit breaker"));
+    }
+
+    #[tokio::test]
+    async fn checkout_waits_for_a_held_slot_to_be_released() {
+        let executor = PooledExecutor::new(&config(1));
+        let held = executor.slots[0].clone().lock_owned().await;
+
+        let checkout = executor.checkout();
+        tokio::pin!(checkout);
+        assert!(
+            time::timeout(Duration::from_millis(50), checkout.as_mut())
+                .await
+                .is_err()
+        );
+
+        // What the health check does once it is done with a slot.
+        drop(held);
+        executor.released.notify_waiters();
+        let lease = time::timeout(Duration::from_secs(1), checkout)
+            .await
+            .expect("checkout woken by the release")
+            .unwrap();
+        assert_eq!(lease.id, 0);
+    }
+
+    /// Stand-ins for soffice and the Python worker that speak the worker protocol.
+    #[cfg(unix)]
+    fn fake_launcher(dir: &Path) -> RecalcConfig {
+        use std::os::unix::fs::PermissionsExt;
+
+        let write_script = |name: &str, body: &str| {
+            let path = dir.join(name);
+            std::fs::write(&path, body).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+        let soffice = write_script("soffice", "#!/bin/sh\nexec sleep 60\n");
+        let python = write_script(
+            "python",
+            "#!/bin/sh\n\
+             echo '{\"ready\":true}'\n\
+             while read -r line; do\n\
+               case \"$line\" in\n\
+                 *broken*) echo '{\"ok\":false,\"error\":\"could not open\"}' ;;\n\
+                 *) echo '{\"ok\":true}' ;;\n\
+               esac\n\
+             done\n",
+        );
+        RecalcConfig {
+            soffice_path: Some(soffice),
+            python_path: Some(python),
+            pool_dir: Some(dir.join("pool")),
+            timeout_ms: Some(5_000),
+            pool_size: 1,
+            ..RecalcConfig::default()
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn jobs_reuse_the_running_slot_and_report_worker_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        // Built outside a runtime, so no health check pre-warms the slot.
+        let executor = PooledExecutor::new(&fake_launcher(dir.path()));
+        let workbook = dir.path().join("model.xlsx");
+        std::fs::write(&workbook, b"").unwrap();
+        let broken = dir.path().join("broken.xlsx");
+        std::fs::write(&broken, b"").unwrap();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let first = executor.recalculate(&workbook).await.unwrap();
+            let second = executor.recalculate(&workbook).await.unwrap();
+            assert_eq!(first.executor_type, "pooled");
+            assert!(!first.was_warm);
+            assert!(second.was_warm);
+            assert!(dir.path().join("pool").join(WORKER_SCRIPT_NAME).exists());
+
+            let err = executor.recalculate(&broken).await.unwrap_err();
+            assert!(err.to_string().contains("could not open"), "{err}");
+
+            // The failed slot was recycled; the next job starts it cold again.
+            let third = executor.recalculate(&workbook).await.unwrap();
+            assert!(!third.was_warm);
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ping_detects_a_hung_instance() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = fake_launcher(dir.path());
+        let hung = dir.path().join("hung-python");
+        // Connects, then stops answering, like a worker stuck on a wedged soffice.
+        std::fs::write(&hung, "#!/bin/sh\necho '{\"ready\":true}'\nexec sleep 60\n").unwrap();
+        std::fs::set_permissions(&hung, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        // Built outside a runtime, so no health check touches the slots.
+        let healthy = PooledExecutor::new(&config);
+        let stuck = PooledExecutor::new(&RecalcConfig {
+            python_path: Some(hung),
+            pool_dir: Some(dir.path().join("stuck-pool")),
+            ..config.clone()
+        });
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mut slot = healthy.slots[0].lock().await;
+            assert!(!slot.ping(Duration::from_secs(1)).await, "not started yet");
+            healthy.launcher.start_slot(&mut slot).await.unwrap();
+            assert!(slot.ping(Duration::from_secs(1)).await);
+            slot.kill().await;
+
+            let mut slot = stuck.slots[0].lock().await;
+            stuck.launcher.start_slot(&mut slot).await.unwrap();
+            assert!(slot.is_alive());
+            assert!(!slot.ping(Duration::from_millis(200)).await);
+            slot.kill().await;
+        });
     }
 }
@@ -0,0 +1,97 @@
+"""Recalc worker for the pooled LibreOffice executor.
+
+Connects to one running soffice over the UNO pipe named on the command line
+and serves recalc jobs read from stdin, one JSON object per line:
+
+    {"path": "/abs/path/to/workbook.xlsx"}
+
+Each job loads the workbook hidden, runs calculateAll(), stores it in its own
+format and closes it, then answers with one JSON line: {"ok": true} or
+{"ok": false, "error": "..."}. A {"ping": true} line makes one call over the
+bridge and answers the same way, so a hung soffice shows up as a missing
+reply. Before the first job the worker writes
+{"ready": true} once the bridge is connected, or {"ready": false, "error": ...}
+if soffice did not accept the connection in time.
+"""
+
+import json
+import sys
+import time
+
+import uno
+from com.sun.star.beans import PropertyValue
+
+
+def property_value(name, value):
+    prop = PropertyValue()
+    prop.Name = name
+    prop.Value = value
+    return prop
+
+
+def connect(pipe_name, timeout_secs):
+    local = uno.getComponentContext()
+    resolver = local.ServiceManager.createInstanceWithContext(
+        "com.sun.star.bridge.UnoUrlResolver", local
+    )
+    url = "uno:pipe,name=%s;urp;StarOffice.ComponentContext" % pipe_name
+    deadline = time.monotonic() + timeout_secs
+    while True:
+        try:
+            context = resolver.resolve(url)
+            break
+        except Exception:
+            if time.monotonic() >= deadline:
+                raise
+            time.sleep(0.1)
+    return context.ServiceManager.createInstanceWithContext(
+        "com.sun.star.frame.Desktop", context
+    )
+
+
+def recalculate(desktop, path):
+    url = uno.systemPathToFileUrl(path)
+    doc = desktop.loadComponentFromURL(
+        url, "_blank", 0, (property_value("Hidden", True),)
+    )
+    if doc is None:
+        raise RuntimeError("LibreOffice could not open %s" % path)
+    try:
+        doc.calculateAll()
+        doc.store()
+    finally:
+        doc.close(True)
+
+
+def reply(message):
+    sys.stdout.write(json.dumps(message) + "\n")
+    sys.stdout.flush()
+
+
+def main():
+    pipe_name = sys.argv[1]
+    timeout_secs = float(sys.argv[2])
+    try:
+        desktop = connect(pipe_name, timeout_secs)
+    except Exception as error:
+        reply({"ready": False, "error": "%s: %s" % (type(error).__name__, error)})
+        return 1
+    reply({"ready": True})
+
+    for line in sys.stdin:
+        if not line.strip():
+            continue
+        try:
+            job = json.loads(line)
+            if job.get("ping"):
+                desktop.getFrames().getCount()
+            else:
+                recalculate(desktop, job["path"])
+            reply({"ok": True})
+        except Exception as error:
+            reply({"ok": False, "error": "%s: %s" % (type(error).__name__, error)})
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -0,0 +1,752 @@
+//! Conditional-format results for rendering.
+//!
+//! Rules are evaluated against cached cell values, highest priority first.
+//! Comparisons and expressions are handled when they are built from literals,
+//! cell references, `AND`/`OR`/`NOT` and comparison operators; anything that
+//! needs a formula engine is skipped with a warning.
+
+use super::{DataBarFill, css_color, fill_color};
+use crate::data_validation::{Area, parse_sqref};
+use crate::styles::descriptor_from_style;
+use std::collections::{HashMap, HashSet};
+use umya_spreadsheet::Worksheet;
+use umya_spreadsheet::helper::coordinate::index_from_coordinate;
+use umya_spreadsheet::structs::{
+    Color, ConditionalFormatValueObject, ConditionalFormatValueObjectValues,
+    ConditionalFormatValues, ConditionalFormattingOperatorValues, ConditionalFormattingRule,
+};
+
+/// What the matching rules change about one cell.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct CellEffect {
+    pub fill: Option<String>,
+    pub font_color: Option<String>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub data_bar: Option<DataBarFill>,
+    pub icon: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Blank,
+}
+
+impl Value {
+    fn of(sheet: &Worksheet, col: u32, row: u32) -> Value {
+        let Some(cell) = sheet.get_cell((col, row)) else {
+            return Value::Blank;
+        };
+        let raw = cell.get_value();
+        if raw.is_empty() {
+            return Value::Blank;
+        }
+        match cell.get_data_type() {
+            "b" => Value::Bool(raw.eq_ignore_ascii_case("true") || raw == "1"),
+            "s" | "str" | "inlineStr" | "e" => Value::Text(raw.to_string()),
+            _ => raw
+                .parse::<f64>()
+                .map(Value::Number)
+                .unwrap_or_else(|_| Value::Text(raw.to_string())),
+        }
+    }
+
+    fn number(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn truthy(&self) -> bool {
+        match self {
+            Value::Bool(b) => *b,
+            Value::Number(n) => *n != 0.0,
+            _ => false,
+        }
+    }
+
+    /// Excel ordering across types: numbers < text < booleans.
+    fn compare(&self, other: &Value) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        let rank = |v: &Value| match v {
+            Value::Number(_) | Value::Blank => 0,
+            Value::Text(_) => 1,
+            Value::Bool(_) => 2,
+        };
+        match (self, other) {
+            (Value::Text(a), Value::Text(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (Value::Blank, Value::Text(b)) => "".cmp(b.as_str()),
+            (Value::Text(a), Value::Blank) => a.as_str().cmp(""),
+            (a, b) if rank(a) == 0 && rank(b) == 0 => {
+                let (x, y) = (a.number().unwrap_or(0.0), b.number().unwrap_or(0.0));
+                x.partial_cmp(&y).unwrap_or(Ordering::Equal)
+            }
+            (a, b) => rank(a).cmp(&rank(b)),
+        }
+    }
+}
+
+/// Evaluate the sheet's rules for every cell of `range`.
+pub(super) fn evaluate(
+    sheet: &Worksheet,
+    range: &Area,
+    warnings: &mut Vec<String>,
+) -> HashMap<(u32, u32), CellEffect> {
+    let mut rules: Vec<(&ConditionalFormattingRule, Vec<Area>)> = Vec::new();
+    for format in sheet.get_conditional_formatting_collection() {
+        let areas = parse_sqref(&format.get_sequence_of_references().get_sqref());
+        if !areas.iter().any(|a| a.intersects(range)) {
+            continue;
+        }
+        for rule in format.get_conditional_collection() {
+            rules.push((rule, areas.clone()));
+        }
+    }
+    rules.sort_by_key(|(rule, _)| *rule.get_priority());
+
+    let mut effects: HashMap<(u32, u32), CellEffect> = HashMap::new();
+    let mut stopped: HashSet<(u32, u32)> = HashSet::new();
+    let mut skipped: Vec<&'static str> = Vec::new();
+    for (rule, areas) in rules {
+        let mut evaluator = RuleEvaluator::new(sheet, rule, &areas);
+        for area in &areas {
+            for row in area.min_row.max(range.min_row)..=area.max_row.min(range.max_row) {
+                for col in area.min_col.max(range.min_col)..=area.max_col.min(range.max_col) {
+                    if stopped.contains(&(col, row)) {
+                        continue;
+                    }
+                    let outcome = match evaluator.apply(col, row) {
+                        Ok(outcome) => outcome,
+                        Err(kind) => {
+                            if !skipped.contains(&kind) {
+                                skipped.push(kind);
+                            }
+                            continue;
+                        }
+                    };
+                    let Some(outcome) = outcome else {
+                        continue;
+                    };
+                    merge_effect(effects.entry((col, row)).or_default(), outcome);
+                    if *rule.get_stop_if_true() {
+                        stopped.insert((col, row));
+                    }
+                }
+            }
+        }
+    }
+    for kind in skipped {
+        warnings.push(format!(
+            "conditional format rule '{kind}' could not be evaluated and was not drawn"
+        ));
+    }
+    effects
+}
+
+/// Earlier (higher-priority) rules win each property they set.
+fn merge_effect(into: &mut CellEffect, from: CellEffect) {
+    into.fill = into.fill.take().or(from.fill);
+    into.font_color = into.font_color.take().or(from.font_color);
+    into.bold = into.bold.or(from.bold);
+    into.italic = into.italic.or(from.italic);
+    into.data_bar = into.data_bar.take().or(from.data_bar);
+    into.icon = into.icon.take().or(from.icon);
+}
+
+struct RuleEvaluator<'a> {
+    sheet: &'a Worksheet,
+    rule: &'a ConditionalFormattingRule,
+    anchor: (u32, u32),
+    /// Numbers in the rule's areas, sorted, for scales, bars and top-N.
+    numbers: Option<Vec<f64>>,
+    /// Lower-cased text of every value, for duplicate/unique rules.
+    counts: Option<HashMap<String, usize>>,
+    areas: &'a [Area],
+}
+
+impl<'a> RuleEvaluator<'a> {
+    fn new(sheet: &'a Worksheet, rule: &'a ConditionalFormattingRule, areas: &'a [Area]) -> Self {
+        let anchor = areas
+            .first()
+            .map(|a| (a.min_col, a.min_row))
+            .unwrap_or((1, 1));
+        Self {
+            sheet,
+            rule,
+            anchor,
+            numbers: None,
+            counts: None,
+            areas,
+        }
+    }
+
+    /// Existing cells inside the rule's areas.
+    fn area_values(&self) -> Vec<Value> {
+        self.sheet
+            .get_cell_collection()
+            .into_iter()
+            .filter(|cell| {
+                let coordinate = cell.get_coordinate();
+                let (col, row) = (*coordinate.get_col_num(), *coordinate.get_row_num());
+                self.areas.iter().any(|a| a.contains(col, row))
+            })
+            .map(|cell| {
+                let coordinate = cell.get_coordinate();
+                Value::of(
+                    self.sheet,
+                    *coordinate.get_col_num(),
+                    *coordinate.get_row_num(),
+                )
+            })
+            .collect()
+    }
+
+    fn numbers(&mut self) -> &[f64] {
+        if self.numbers.is_none() {
+            let mut numbers: Vec<f64> = self
+                .area_values()
+                .iter()
+                .filter_map(Value::number)
+                .collect();
+            numbers.sort_by(|a, b| a.total_cmp(b));
+            self.numbers = Some(numbers);
+        }
+        self.numbers.as_deref().unwrap_or_default()
+    }
+
+    fn counts(&mut self) -> &HashMap<String, usize> {
+        if self.counts.is_none() {
+            let mut counts = HashMap::new();
+            for value in self.area_values() {
+                if let Some(key) = value_key(&value) {
+                    *counts.entry(key).or_insert(0) += 1;
+                }
+            }
+            self.counts = Some(counts);
+        }
+        self.counts.get_or_insert_with(HashMap::new)
+    }
+
+    /// `Ok(None)` when the rule does not match; `Err` names a rule type that
+    /// cannot be evaluated here.
+    fn apply(&mut self, col: u32, row: u32) -> Result<Option<CellEffect>, &'static str> {
+        let value = Value::of(self.sheet, col, row);
+        let matched = match self.rule.get_type() {
+            ConditionalFormatValues::CellIs => self.cell_is(&value, col, row)?,
+            ConditionalFormatValues::Expression => {
+                let text = self
+                    .rule
+                    .get_formula()
+                    .map(|f| f.get_string_value().to_string())
+                    .unwrap_or_default();
+                let offset = (
+                    col as i64 - self.anchor.0 as i64,
+                    row as i64 - self.anchor.1 as i64,
+                );
+                Expr::new(self.sheet, offset)
+                    .eval(&text)
+                    .ok_or("expression")?
+                    .truthy()
+            }
+            ConditionalFormatValues::ContainsBlanks => value == Value::Blank,
+            ConditionalFormatValues::NotContainsBlanks => value != Value::Blank,
+            ConditionalFormatValues::Top10 => {
+                let Some(number) = value.number() else {
+                    return Ok(None);
+                };
+                self.in_top(number)
+            }
+            ConditionalFormatValues::DuplicateValues | ConditionalFormatValues::UniqueValues => {
+                let unique = matches!(self.rule.get_type(), ConditionalFormatValues::UniqueValues);
+                let Some(key) = value_key(&value) else {
+                    return Ok(None);
+                };
+                let count = self.counts().get(&key).copied().unwrap_or(0);
+                if unique { count == 1 } else { count > 1 }
+            }
+            ConditionalFormatValues::ColorScale => {
+                let Some(number) = value.number() else {
+                    return Ok(None);
+                };
+                return Ok(self.color_scale(number).map(|fill| CellEffect {
+                    fill: Some(fill),
+                    ..Default::default()
+                }));
+            }
+            ConditionalFormatValues::DataBar => {
+                let Some(number) = value.number() else {
+                    return Ok(None);
+                };
+                return Ok(self.data_bar(number).map(|bar| CellEffect {
+                    data_bar: Some(bar),
+                    ..Default::default()
+                }));
+            }
+            ConditionalFormatValues::IconSet => {
+                let Some(number) = value.number() else {
+                    return Ok(None);
+                };
+                return Ok(self.icon(number).map(|icon| CellEffect {
+                    icon: Some(icon),
+                    ..Default::default()
+                }));
+            }
+            ConditionalFormatValues::AboveAverage => return Err("above_average"),
+            ConditionalFormatValues::ContainsText
+            | ConditionalFormatValues::NotContainsText
+            | ConditionalFormatValues::BeginsWith
+            | ConditionalFormatValues::EndsWith => return Err("text"),
+            ConditionalFormatValues::ContainsErrors
+            | ConditionalFormatValues::NotContainsErrors => return Err("errors"),
+            _ => return Err("time_period"),
+        };
+        Ok(matched.then(|| self.style_effect()))
+    }
+
+    fn cell_is(&self, value: &Value, col: u32, row: u32) -> Result<bool, &'static str> {
+        use ConditionalFormattingOperatorValues as Op;
+        use std::cmp::Ordering;
+        let text = self
+            .rule
+            .get_formula()
+            .map(|f| f.get_string_value().to_string())
+            .unwrap_or_default();
+        let offset = (
+            col as i64 - self.anchor.0 as i64,
+            row as i64 - self.anchor.1 as i64,
+        );
+        let operand = Expr::new(self.sheet, offset).eval(&text).ok_or("cell_is")?;
+        let order = value.compare(&operand);
+        Ok(match self.rule.get_operator() {
+            Op::Equal => order == Ordering::Equal,
+            Op::NotEqual => order != Ordering::Equal,
+            Op::GreaterThan => order == Ordering::Greater,
+            Op::GreaterThanOrEqual => order != Ordering::Less,
+            Op::LessThan => order == Ordering::Less,
+            Op::LessThanOrEqual => order != Ordering::Greater,
+            // umya keeps one formula per rule, so the second bound is gone.
+            _ => return Err("cell_is between"),
+        })
+    }
+
+    fn in_top(&mut self, number: f64) -> bool {
+        let rank = *self.rule.get_rank() as usize;
+        let percent = *self.rule.get_percent();
+        let bottom = *self.rule.get_bottom();
+        let numbers = self.numbers();
+        if numbers.is_empty() {
+            return false;
+        }
+        let take = if percent {
+            ((numbers.len() * rank) / 100).max(1)
+        } else {
+            rank.max(1)
+        }
+        .min(numbers.len());
+        if bottom {
+            number <= numbers[take - 1]
+        } else {
+            number >= numbers[numbers.len() - take]
+        }
+    }
+
+    fn threshold(&mut self, cfvo: &ConditionalFormatValueObject) -> Option<f64> {
+        let numbers = self.numbers();
+        let (min, max) = (*numbers.first()?, *numbers.last()?);
+        let val = cfvo.get_val().trim().parse::<f64>().ok();
+        Some(match cfvo.get_type() {
+            ConditionalFormatValueObjectValues::Min => min,
+            ConditionalFormatValueObjectValues::Max => max,
+            ConditionalFormatValueObjectValues::Number => val?,
+            ConditionalFormatValueObjectValues::Percent => min + (max - min) * val? / 100.0,
+            ConditionalFormatValueObjectValues::Percentile => percentile(numbers, val? / 100.0),
+            ConditionalFormatValueObjectValues::Formula => val?,
+        })
+    }
+
+    fn color_scale(&mut self, number: f64) -> Option<String> {
+        let rule = self.rule;
+        let scale = rule.get_color_scale()?;
+        let mut stops = Vec::new();
+        for (cfvo, color) in scale
+            .get_cfvo_collection()
+            .iter()
+            .zip(scale.get_color_collection())
+        {
+            stops.push((self.threshold(cfvo)?, rgb(color)?));
+        }
+        let (first, last) = (stops.first()?, stops.last()?);
+        if number <= first.0 {
+            return Some(hex(first.1));
+        }
+        if number >= last.0 {
+            return Some(hex(last.1));
+        }
+        stops.windows(2).find_map(|pair| {
+            let ((lo, from), (hi, to)) = (pair[0], pair[1]);
+            (number >= lo && number <= hi).then(|| {
+                let t = if hi > lo {
+                    (number - lo) / (hi - lo)
+                } else {
+                    0.0
+                };
+                hex(mix(from, to, t))
+            })
+        })
+    }
+
+    fn data_bar(&mut self, number: f64) -> Option<DataBarFill> {
+        let rule = self.rule;
+        let bar = rule.get_data_bar()?;
+        let points = bar.get_cfvo_collection();
+        let lo = self.threshold(points.first()?)?;
+        let hi = self.threshold(points.get(1)?)?;
+        let color = bar.get_color_collection().first().and_then(rgb)?;
+        let t = if hi > lo {
+            ((number - lo) / (hi - lo)).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        // Excel keeps a stub of 10% for the lowest value.
+        Some(DataBarFill {
+            fraction: 0.1 + 0.9 * t,
+            color: hex(color),
+        })
+    }
+
+    fn icon(&mut self, number: f64) -> Option<String> {
+        let rule = self.rule;
+        let icons = rule.get_icon_set()?;
+        let points = icons.get_cfvo_collection();
+        if points.len() < 2 {
+            return None;
+        }
+        let mut level = 0;
+        for (i, cfvo) in points.iter().enumerate().skip(1) {
+            if number >= self.threshold(cfvo)? {
+                level = i;
+            }
+        }
+        // Red through amber to green, like the default traffic lights.
+        let palette = [(0xF8, 0x69, 0x6B), (0xFF, 0xC0, 0x00), (0x63, 0xBE, 0x7B)];
+        let t = level as f64 / (points.len() - 1) as f64;
+        let color = if t <= 0.5 {
+            mix(palette[0], palette[1], t * 2.0)
+        } else {
+            mix(palette[1], palette[2], (t - 0.5) * 2.0)
+        };
+        Some(hex(color))
+    }
+
+    fn style_effect(&self) -> CellEffect {
+        let Some(style) = self.rule.get_style() else {
+            return CellEffect::default();
+        };
+        let descriptor = descriptor_from_style(style);
+        let font = descriptor.font.as_ref();
+        CellEffect {
+            fill: descriptor.fill.as_ref().and_then(|fill| {
+                // Differential fills keep a solid color in bgColor.
+                fill_color(fill).or_else(|| match fill {
+                    crate::model::FillDescriptor::Pattern(p) => {
+                        p.background_color.as_deref().and_then(css_color)
+                    }
+                    _ => None,
+                })
+            }),
+            font_color: font.and_then(|f| f.color.as_deref()).and_then(css_color),
+            bold: font.and_then(|f| f.bold),
+            italic: font.and_then(|f| f.italic),
+            data_bar: None,
+            icon: None,
+        }
+    }
+}
+
+fn value_key(value: &Value) -> Option<String> {
+    match value {
+        Value::Blank => None,
+        Value::Number(n) => Some(n.to_string()),
+        Value::Text(t) => Some(t.to_lowercase()),
+        Value::Bool(b) => Some(b.to_string()),
+    }
+}
+
+/// PERCENTILE.INC over sorted `numbers`.
+fn percentile(numbers: &[f64], p: f64) -> f64 {
+    let rank = p.clamp(0.0, 1.0) * (numbers.len() - 1) as f64;
+    let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+    numbers[lo] + (numbers[hi] - numbers[lo]) * (rank - lo as f64)
+}
+
+fn rgb(color: &Color) -> Option<(u8, u8, u8)> {
+    let css = css_color(color.get_argb())?;
+    let channel = |i: usize| u8::from_str_radix(&css[i..i + 2], 16).ok();
+    Some((channel(1)?, channel(3)?, channel(5)?))
+}
+
+fn mix(from: (u8, u8, u8), to: (u8, u8, u8), t: f64) -> (u8, u8, u8) {
+    let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    (lerp(from.0, to.0), lerp(from.1, to.1), lerp(from.2, to.2))
+}
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{r:02X}{g:02X}{b:02X}")
+}
+
+/// A tiny evaluator for rule formulas: literals, cell references shifted by
+/// the cell's offset from the rule anchor, comparisons, `&`, `AND`, `OR` and
+/// `NOT`.
+struct Expr<'a> {
+    sheet: &'a Worksheet,
+    offset: (i64, i64),
+}
+
+impl<'a> Expr<'a> {
+    fn new(sheet: &'a Worksheet, offset: (i64, i64)) -> Self {
+        Self { sheet, offset }
+    }
+
+    fn eval(&self, text: &str) -> Option<Value> {
+        let text = text.trim();
+        let text = text.strip_prefix('=').unwrap_or(text).trim();
+        if text.is_empty() {
+            return None;
+        }
+        for op in ["<>", ">=", "<=", "=", ">", "<"] {
+            if let Some((left, right)) = split_top_level(text, op) {
+                let order = self.eval(left)?.compare(&self.eval(right)?);
+                use std::cmp::Ordering::*;
+                return Some(Value::Bool(match op {
+                    "<>" => order != Equal,
+                    ">=" => order != Less,
+                    "<=" => order != Greater,
+                    "=" => order == Equal,
+                    ">" => order == Greater,
+                    _ => order == Less,
+                }));
+            }
+        }
+        if let Some((left, right)) = split_top_level(text, "&") {
+            return Some(Value::Text(format!(
+                "{}{}",
+                display(&self.eval(left)?),
+                display(&self.eval(right)?)
+            )));
+        }
+        if let Some(inner) = text.strip_prefix('(').and_then(|t| t.strip_suffix(')'))
+            && balanced(inner)
+        {
+            return self.eval(inner);
+        }
+        if let Some(open) = text.find('(')
+            && text.ends_with(')')
+        {
+            let name = text[..open].trim().to_ascii_uppercase();
+            let args: Vec<Value> = split_args(&text[open + 1..text.len() - 1])
+                .into_iter()
+                .map(|arg| self.eval(arg))
+                .collect::<Option<_>>()?;
+            return match name.as_str() {
+                "AND" => Some(Value::Bool(args.iter().all(Value::truthy))),
+                "OR" => Some(Value::Bool(args.iter().any(Value::truthy))),
+                "NOT" if args.len() == 1 => Some(Value::Bool(!args[0].truthy())),
+                "ISBLANK" if args.len() == 1 => Some(Value::Bool(args[0] == Value::Blank)),
+                _ => None,
+            };
+        }
+        if let Some(inner) = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+            return Some(Value::Text(inner.replace("\"\"", "\"")));
+        }
+        match text.to_ascii_uppercase().as_str() {
+            "TRUE" => return Some(Value::Bool(true)),
+            "FALSE" => return Some(Value::Bool(false)),
+            _ => {}
+        }
+        if let Ok(number) = text.parse::<f64>() {
+            return Some(Value::Number(number));
+        }
+        if let Some(rest) = text.strip_prefix('-') {
+            return self.eval(rest)?.number().map(|n| Value::Number(-n));
+        }
+        self.reference(text)
+    }
+
+    fn reference(&self, text: &str) -> Option<Value> {
+        if text.contains(['!', ':']) {
+            return None;
+        }
+        let (col, row, col_fixed, row_fixed) = index_from_coordinate(text.to_ascii_uppercase());
+        let (col, row) = (col?, row?);
+        let shift = |index: u32, fixed: Option<bool>, by: i64| {
+            if fixed.unwrap_or(false) {
+                Some(index)
+            } else {
+                u32::try_from(index as i64 + by).ok().filter(|i| *i > 0)
+            }
+        };
+        let col = shift(col, col_fixed, self.offset.0)?;
+        let row = shift(row, row_fixed, self.offset.1)?;
+        Some(Value::of(self.sheet, col, row))
+    }
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::Number(n) => n.to_string(),
+        Value::Text(t) => t.clone(),
+        Value::Bool(b) => b.to_string().to_ascii_uppercase(),
+        Value::Blank => String::new(),
+    }
+}
+
+/// Split at the first `op` outside quotes and parentheses.
+fn split_top_level<'t>(text: &'t str, op: &str) -> Option<(&'t str, &'t str)> {
+    let mut depth = 0i32;
+    let mut quoted = false;
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => quoted = !quoted,
+            b'(' if !quoted => depth += 1,
+            b')' if !quoted => depth -= 1,
+            _ if !quoted && depth == 0 && text.is_char_boundary(i) && text[i..].starts_with(op) => {
+                // `>=` must not be read as `>` followed by `=`.
+                let before = i.checked_sub(1).map(|j| bytes[j]);
+                let after = bytes.get(i + op.len()).copied();
+                let glued = op.len() == 1
+                    && (matches!(before, Some(b'<' | b'>')) || matches!(after, Some(b'=' | b'>')));
+                if i > 0 && !glued {
+                    return Some((&text[..i], &text[i + op.len()..]));
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+fn split_args(text: &str) -> Vec<&str> {
+    let mut args = Vec::new();
+    let (mut depth, mut quoted, mut start) = (0i32, false, 0);
+    for (i, ch) in text.char_indices() {
+        match ch {
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth -= 1,
+            ',' if !quoted && depth == 0 => {
+                args.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    args.push(&text[start..]);
+    args
+}
+
+fn balanced(text: &str) -> bool {
+    let mut depth = 0i32;
+    let mut quoted = false;
+    for ch in text.chars() {
+        match ch {
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => {
+                depth -= 1;
+                if depth < 0 {
+                    return false;
+                }
+            }
+            _ => {}
+        }
+    }
+    depth == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use umya_spreadsheet::new_file;
+    use umya_spreadsheet::structs::{ColorScale, ConditionalFormatting, Formula, Style};
+
+    fn cfvo(kind: ConditionalFormatValueObjectValues) -> ConditionalFormatValueObject {
+        let mut cfvo = ConditionalFormatValueObject::default();
+        cfvo.set_type(kind);
+        cfvo
+    }
+
+    fn color(argb: &str) -> Color {
+        let mut color = Color::default();
+        color.set_argb(argb);
+        color
+    }
+
+    #[test]
+    fn color_scale_interpolates_between_points() {
+        let mut book = new_file();
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        for (row, value) in [(1, 0.0), (2, 50.0), (3, 100.0)] {
+            sheet.get_cell_mut((1, row)).set_value_number(value);
+        }
+        let mut scale = ColorScale::default();
+        scale.add_cfvo_collection(cfvo(ConditionalFormatValueObjectValues::Min));
+        scale.add_cfvo_collection(cfvo(ConditionalFormatValueObjectValues::Max));
+        scale.add_color_collection(color("FFFFFFFF"));
+        scale.add_color_collection(color("FF000000"));
+        let mut rule = ConditionalFormattingRule::default();
+        rule.set_type(ConditionalFormatValues::ColorScale);
+        rule.set_color_scale(scale);
+        rule.set_priority(1);
+        let mut format = ConditionalFormatting::default();
+        format.get_sequence_of_references_mut().set_sqref("A1:A3");
+        format.add_conditional_collection(rule);
+        sheet.set_conditional_formatting_collection(vec![format]);
+
+        let mut warnings = Vec::new();
+        let effects = evaluate(sheet, &Area::parse("A1:A3").unwrap(), &mut warnings);
+        assert!(warnings.is_empty());
+        assert_eq!(effects[&(1, 1)].fill.as_deref(), Some("#FFFFFF"));
+        assert_eq!(effects[&(1, 2)].fill.as_deref(), Some("#808080"));
+        assert_eq!(effects[&(1, 3)].fill.as_deref(), Some("#000000"));
+    }
+
+    #[test]
+    fn expressions_shift_relative_references() {
+        let mut book = new_file();
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        for (row, actual, target) in [(2, 5.0, 3.0), (3, 1.0, 3.0)] {
+            sheet.get_cell_mut((1, row)).set_value_number(actual);
+            sheet.get_cell_mut((2, row)).set_value_number(target);
+        }
+        sheet.get_cell_mut("C1").set_value("Open");
+
+        let mut style = Style::default();
+        style.set_background_color("FFFFC7CE");
+        let mut rule = ConditionalFormattingRule::default();
+        rule.set_type(ConditionalFormatValues::Expression);
+        let mut formula = Formula::default();
+        formula.set_string_value("AND($A2>$B2,$C$1=\"open\")");
+        rule.set_formula(formula);
+        rule.set_style(style);
+        rule.set_priority(1);
+        let mut format = ConditionalFormatting::default();
+        format.get_sequence_of_references_mut().set_sqref("A2:B3");
+        format.add_conditional_collection(rule);
+        sheet.set_conditional_formatting_collection(vec![format]);
+
+        let mut warnings = Vec::new();
+        let effects = evaluate(sheet, &Area::parse("A1:C3").unwrap(), &mut warnings);
+        assert!(warnings.is_empty());
+        assert_eq!(effects[&(1, 2)].fill.as_deref(), Some("#FFC7CE"));
+        assert_eq!(effects[&(2, 2)].fill.as_deref(), Some("#FFC7CE"));
+        assert!(!effects.contains_key(&(1, 3)));
+    }
+}
@@ -0,0 +1,175 @@
+//! HTML output: a fixed-layout `<table>` with inline styles, so the markup
+//! stands alone without a stylesheet.
+
+use super::{
+    BorderLine, CELL_PADDING_PX, CellBox, GRIDLINE_COLOR, HAlign, HEADER_FILL, HEADER_LINE,
+    HEADER_TEXT, SheetLayout, VAlign, escape_xml, svg::FONT_FALLBACKS,
+};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+pub fn render_html(layout: &SheetLayout) -> String {
+    let gridline = if layout.options.gridlines {
+        format!("1px solid {GRIDLINE_COLOR}")
+    } else {
+        "none".to_string()
+    };
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<table data-sheet="{}" data-range="{}" style="border-collapse:collapse;table-layout:fixed;width:{}px;font-family:Calibri, {FONT_FALLBACKS};font-size:14.7px">"#,
+        escape_xml(&layout.sheet_name),
+        escape_xml(&layout.range),
+        layout.width_px()
+    );
+
+    out.push_str("<colgroup>");
+    if layout.options.headers {
+        let _ = write!(out, r#"<col style="width:{}px">"#, layout.header_width);
+    }
+    for col in &layout.columns {
+        let _ = write!(out, r#"<col style="width:{}px">"#, col.size);
+    }
+    out.push_str("</colgroup>\n");
+
+    let header_cell = format!(
+        "background:{HEADER_FILL};color:{HEADER_TEXT};border:1px solid {HEADER_LINE};font-size:12px;font-weight:normal;text-align:center;padding:0;overflow:hidden"
+    );
+    if layout.options.headers {
+        let _ = write!(
+            out,
+            r#"<tr style="height:{}px"><th style="{header_cell}"></th>"#,
+            layout.header_height
+        );
+        for col in &layout.columns {
+            let _ = write!(
+                out,
+                r#"<th style="{header_cell}">{}</th>"#,
+                layout.column_label(col)
+            );
+        }
+        out.push_str("</tr>\n");
+    }
+
+    let by_position: HashMap<(u32, u32), &CellBox> =
+        layout.cells.iter().map(|c| ((c.col, c.row), c)).collect();
+    for row in &layout.rows {
+        let hidden = if row.size == 0.0 { ";display:none" } else { "" };
+        let _ = write!(out, r#"<tr style="height:{}px{hidden}">"#, row.size);
+        if layout.options.headers {
+            let _ = write!(out, r#"<th style="{header_cell}">{}</th>"#, row.index);
+        }
+        for col in &layout.columns {
+            match by_position.get(&(col.index, row.index)) {
+                Some(cell) => cell_html(&mut out, cell, &gridline),
+                None if layout
+                    .merges
+                    .iter()
+                    .any(|m| m.contains(col.index, row.index)) => {}
+                None => {
+                    let _ = write!(out, r#"<td style="border:{gridline};padding:0"></td>"#);
+                }
+            }
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+    out
+}
+
+fn cell_html(out: &mut String, cell: &CellBox, gridline: &str) {
+    let mut style = format!("padding:0 {CELL_PADDING_PX}px;overflow:hidden;");
+    let sides = ["top", "right", "bottom", "left"];
+    for (side, border) in sides.iter().zip(&cell.borders) {
+        let value = border.as_ref().map(css_border);
+        let _ = write!(
+            style,
+            "border-{side}:{};",
+            value.as_deref().unwrap_or(gridline)
+        );
+    }
+    match (&cell.data_bar, &cell.fill) {
+        (Some(bar), fill) => {
+            let percent = bar.fraction * 100.0;
+            let _ = write!(
+                style,
+                "background:linear-gradient(to right,{c} {percent:.1}%,{f} {percent:.1}%);",
+                c = bar.color,
+                f = fill.as_deref().unwrap_or("transparent")
+            );
+        }
+        (None, Some(fill)) => {
+            let _ = write!(style, "background:{fill};");
+        }
+        (None, None) => {}
+    }
+    let align = match cell.halign {
+        HAlign::Left => "left",
+        HAlign::Center => "center",
+        HAlign::Right => "right",
+    };
+    let valign = match cell.valign {
+        VAlign::Top => "top",
+        VAlign::Center => "middle",
+        VAlign::Bottom => "bottom",
+    };
+    let font = &cell.font;
+    let _ = write!(
+        style,
+        "text-align:{align};vertical-align:{valign};color:{};font-family:'{}', {FONT_FALLBACKS};font-size:{:.1}px;",
+        font.color,
+        escape_xml(&font.family.replace('\'', "")),
+        font.size_px()
+    );
+    if font.bold {
+        style.push_str("font-weight:bold;");
+    }
+    if font.italic {
+        style.push_str("font-style:italic;");
+    }
+    match (font.underline, font.strikethrough) {
+        (true, true) => style.push_str("text-decoration:underline line-through;"),
+        (true, false) => style.push_str("text-decoration:underline;"),
+        (false, true) => style.push_str("text-decoration:line-through;"),
+        (false, false) => {}
+    }
+    style.push_str(if cell.wrap {
+        "white-space:pre-wrap;word-wrap:break-word"
+    } else {
+        "white-space:pre"
+    });
+
+    out.push_str("<td");
+    if cell.col_span > 1 {
+        let _ = write!(out, r#" colspan="{}""#, cell.col_span);
+    }
+    if cell.row_span > 1 {
+        let _ = write!(out, r#" rowspan="{}""#, cell.row_span);
+    }
+    let _ = write!(out, r#" style="{style}">"#);
+    if let Some(color) = &cell.icon {
+        let _ = write!(out, r#"<span style="color:{color}">&#9679;</span> "#);
+    }
+    let text = escape_xml(&cell.text);
+    if cell.text_width > cell.width {
+        // Spill into the empty cells to the right, as Excel does.
+        let _ = write!(
+            out,
+            r#"<div style="width:{}px;overflow:hidden;position:relative">{text}</div>"#,
+            cell.text_width - 2.0 * CELL_PADDING_PX
+        );
+    } else {
+        out.push_str(&text);
+    }
+    out.push_str("</td>");
+}
+
+fn css_border(line: &BorderLine) -> String {
+    let kind = match (line.double, line.dash) {
+        (true, _) => "double",
+        (false, Some("1 2")) | (false, Some("1 1")) => "dotted",
+        (false, Some(_)) => "dashed",
+        (false, None) => "solid",
+    };
+    format!("{}px {kind} {}", line.width, line.color)
+}
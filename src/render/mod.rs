@@ -0,0 +1,752 @@
+//! Native sheet rendering.
+//!
+//! A range is laid out straight from umya data — column widths, row heights,
+//! merged cells, cell styles and conditional-format results — into a
+//! [`SheetLayout`] of pixel boxes, which the HTML and SVG writers draw. PNG
+//! output rasterizes the SVG with resvg, so nothing here needs LibreOffice.
+
+mod conditional;
+mod html;
+mod png;
+mod svg;
+
+pub use html::render_html;
+pub use png::render_png;
+pub use svg::render_svg;
+
+use crate::data_validation::Area;
+use crate::model::{BorderSideDescriptor, FillDescriptor, StyleDescriptor};
+use crate::styles::descriptor_from_style;
+use crate::utils::column_number_to_name;
+use conditional::CellEffect;
+use std::collections::{HashMap, HashSet};
+use umya_spreadsheet::{Cell, Worksheet};
+
+/// Excel's default column: 8.43 characters of Calibri 11, padding included.
+const DEFAULT_COLUMN_PX: f64 = 64.0;
+const DEFAULT_ROW_PT: f64 = 15.0;
+const HEADER_ROW_PX: f64 = 20.0;
+const MIN_HEADER_COLUMN_PX: f64 = 40.0;
+const CELL_PADDING_PX: f64 = 3.0;
+
+const DEFAULT_FONT_NAME: &str = "Calibri";
+const DEFAULT_FONT_PT: f64 = 11.0;
+const DEFAULT_TEXT_COLOR: &str = "#000000";
+pub(crate) const GRIDLINE_COLOR: &str = "#D9D9D9";
+pub(crate) const HEADER_FILL: &str = "#F3F3F3";
+pub(crate) const HEADER_LINE: &str = "#BFBFBF";
+pub(crate) const HEADER_TEXT: &str = "#444444";
+
+#[derive(Debug, Clone, Copy)]
+pub struct RenderOptions {
+    /// Draw the column-letter and row-number headers.
+    pub headers: bool,
+    pub gridlines: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            headers: true,
+            gridlines: true,
+        }
+    }
+}
+
+/// One column or row of the rendered range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Track {
+    pub index: u32,
+    /// Pixel offset from the top-left corner of the image, headers included.
+    pub offset: f64,
+    /// Zero for hidden columns and rows.
+    pub size: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HAlign {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VAlign {
+    Top,
+    Center,
+    Bottom,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStyle {
+    pub family: String,
+    pub size_pt: f64,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    /// CSS `#RRGGBB`.
+    pub color: String,
+}
+
+impl TextStyle {
+    pub fn size_px(&self) -> f64 {
+        self.size_pt * 96.0 / 72.0
+    }
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            family: DEFAULT_FONT_NAME.to_string(),
+            size_pt: DEFAULT_FONT_PT,
+            bold: false,
+            italic: false,
+            underline: false,
+            strikethrough: false,
+            color: DEFAULT_TEXT_COLOR.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BorderLine {
+    pub width: f64,
+    pub color: String,
+    /// SVG `stroke-dasharray` / CSS style hint for dashed and dotted lines.
+    pub dash: Option<&'static str>,
+    pub double: bool,
+}
+
+/// Edges in top, right, bottom, left order.
+pub type Borders = [Option<BorderLine>; 4];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataBarFill {
+    /// Share of the cell width covered by the bar, 0..=1.
+    pub fraction: f64,
+    pub color: String,
+}
+
+/// A drawn cell: a single cell, or the anchor of a merge spanning several.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellBox {
+    pub col: u32,
+    pub row: u32,
+    /// Columns and rows covered, clipped to the rendered range.
+    pub col_span: u32,
+    pub row_span: u32,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub text: String,
+    /// `text` broken to fit the cell when wrapping is on; otherwise one line.
+    pub lines: Vec<String>,
+    pub halign: HAlign,
+    pub valign: VAlign,
+    pub wrap: bool,
+    /// Width available to the text: wider than the box when left-aligned
+    /// text spills into empty neighbours.
+    pub text_width: f64,
+    pub font: TextStyle,
+    pub fill: Option<String>,
+    pub borders: Borders,
+    pub data_bar: Option<DataBarFill>,
+    /// Icon-set marker color.
+    pub icon: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SheetLayout {
+    pub sheet_name: String,
+    pub range: String,
+    pub options: RenderOptions,
+    pub columns: Vec<Track>,
+    pub rows: Vec<Track>,
+    /// Width of the row-number gutter, zero without headers.
+    pub header_width: f64,
+    /// Height of the column-letter band, zero without headers.
+    pub header_height: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Merged areas (clipped to the range) where gridlines are not drawn.
+    pub merges: Vec<Area>,
+    pub cells: Vec<CellBox>,
+    pub warnings: Vec<String>,
+}
+
+impl SheetLayout {
+    pub fn width_px(&self) -> u32 {
+        self.width.ceil() as u32
+    }
+
+    pub fn height_px(&self) -> u32 {
+        self.height.ceil() as u32
+    }
+
+    pub fn column_label(&self, track: &Track) -> String {
+        column_number_to_name(track.index)
+    }
+
+    fn covered_by_merge(&self, col: u32, row: u32) -> bool {
+        self.merges.iter().any(|m| m.contains(col, row))
+    }
+
+    /// Whether the gridline between column `col` and the next one is drawn
+    /// at `row`.
+    pub fn vertical_gridline_at(&self, col: u32, row: u32) -> bool {
+        !self
+            .merges
+            .iter()
+            .any(|m| m.contains(col, row) && m.contains(col + 1, row))
+    }
+
+    /// Whether the gridline between row `row` and the next one is drawn at
+    /// `col`.
+    pub fn horizontal_gridline_at(&self, col: u32, row: u32) -> bool {
+        !self
+            .merges
+            .iter()
+            .any(|m| m.contains(col, row) && m.contains(col, row + 1))
+    }
+}
+
+/// Lay out `area` of `sheet`. The caller checks the range size limits.
+pub fn layout_sheet(
+    sheet: &Worksheet,
+    area: Area,
+    range: &str,
+    options: RenderOptions,
+) -> SheetLayout {
+    let mut warnings = Vec::new();
+    let header_height = if options.headers { HEADER_ROW_PX } else { 0.0 };
+    let header_width = if options.headers {
+        let digits = area.max_row.to_string().len() as f64;
+        MIN_HEADER_COLUMN_PX.max(digits * 7.0 + 12.0)
+    } else {
+        0.0
+    };
+
+    let default_row_pt = Some(*sheet.get_sheet_format_properties().get_default_row_height())
+        .filter(|h| *h > 0.0)
+        .unwrap_or(DEFAULT_ROW_PT);
+
+    let mut columns = Vec::new();
+    let mut x = header_width;
+    for col in area.min_col..=area.max_col {
+        let size = column_px(sheet, col);
+        columns.push(Track {
+            index: col,
+            offset: x,
+            size,
+        });
+        x += size;
+    }
+    let mut rows = Vec::new();
+    let mut y = header_height;
+    for row in area.min_row..=area.max_row {
+        let size = row_px(sheet, row, default_row_pt);
+        rows.push(Track {
+            index: row,
+            offset: y,
+            size,
+        });
+        y += size;
+    }
+
+    let mut merges = Vec::new();
+    let mut merge_anchors: HashMap<(u32, u32), Area> = HashMap::new();
+    for merge in sheet.get_merge_cells() {
+        let Some(full) = Area::parse(&merge.get_range()) else {
+            continue;
+        };
+        if !full.intersects(&area) {
+            continue;
+        }
+        let clipped = Area {
+            min_col: full.min_col.max(area.min_col),
+            min_row: full.min_row.max(area.min_row),
+            max_col: full.max_col.min(area.max_col),
+            max_row: full.max_row.min(area.max_row),
+        };
+        if clipped.min_col != full.min_col || clipped.min_row != full.min_row {
+            warnings.push(format!(
+                "merged area {} starts outside the range; its content is drawn from {}",
+                full.to_a1(),
+                crate::utils::cell_address(full.min_col, full.min_row)
+            ));
+        }
+        merge_anchors.insert((clipped.min_col, clipped.min_row), full);
+        merges.push(clipped);
+    }
+
+    if !sheet.get_image_collection().is_empty() || !sheet.get_chart_collection().is_empty() {
+        warnings.push("charts and images are not drawn".to_string());
+    }
+    let effects = conditional::evaluate(sheet, &area, &mut warnings);
+
+    let mut layout = SheetLayout {
+        sheet_name: sheet.get_name().to_string(),
+        range: range.to_string(),
+        options,
+        columns,
+        rows,
+        header_width,
+        header_height,
+        width: x,
+        height: y,
+        merges,
+        cells: Vec::new(),
+        warnings,
+    };
+
+    let mut cells = Vec::new();
+    for (ri, row_track) in layout.rows.iter().enumerate() {
+        for (ci, col_track) in layout.columns.iter().enumerate() {
+            let (col, row) = (col_track.index, row_track.index);
+            let merge = merge_anchors.get(&(col, row)).copied();
+            if merge.is_none() && layout.covered_by_merge(col, row) {
+                continue;
+            }
+            let (source_col, source_row) =
+                merge.map(|m| (m.min_col, m.min_row)).unwrap_or((col, row));
+            let (col_span, row_span) = merge
+                .map(|m| {
+                    (
+                        m.max_col.min(area.max_col) - col + 1,
+                        m.max_row.min(area.max_row) - row + 1,
+                    )
+                })
+                .unwrap_or((1, 1));
+            let width: f64 = layout.columns[ci..ci + col_span as usize]
+                .iter()
+                .map(|t| t.size)
+                .sum();
+            let height: f64 = layout.rows[ri..ri + row_span as usize]
+                .iter()
+                .map(|t| t.size)
+                .sum();
+
+            let cell = sheet.get_cell((source_col, source_row));
+            let effect = effects.get(&(source_col, source_row));
+            if cell.is_none() && effect.is_none() {
+                continue;
+            }
+            let style = cell
+                .map(|c| descriptor_from_style(c.get_style()))
+                .unwrap_or_default();
+            let mut cell_box = build_box(cell, &style, effect);
+            cell_box.col = col;
+            cell_box.row = row;
+            cell_box.col_span = col_span;
+            cell_box.row_span = row_span;
+            cell_box.x = col_track.offset;
+            cell_box.y = row_track.offset;
+            cell_box.width = width;
+            cell_box.height = height;
+            cell_box.text_width = width;
+
+            if let Some(m) = merge {
+                // A merge is bordered by its edge cells.
+                let edge = |c: u32, r: u32| {
+                    sheet
+                        .get_cell((c, r))
+                        .map(|cell| borders_from(&descriptor_from_style(cell.get_style())))
+                        .unwrap_or_default()
+                };
+                let right = edge(m.max_col, m.min_row);
+                let bottom = edge(m.min_col, m.max_row);
+                cell_box.borders[1] = right[1].clone();
+                cell_box.borders[2] = bottom[2].clone();
+            }
+            cells.push(cell_box);
+        }
+    }
+
+    fit_text(&mut cells, &layout);
+    layout.cells = cells;
+    layout
+}
+
+fn column_px(sheet: &Worksheet, col: u32) -> f64 {
+    match sheet.get_column_dimension_by_number(&col) {
+        Some(column) if *column.get_hidden() => 0.0,
+        Some(column) if *column.get_width() > 0.0 => (*column.get_width() * 7.0).round(),
+        _ => DEFAULT_COLUMN_PX,
+    }
+}
+
+fn row_px(sheet: &Worksheet, row: u32, default_pt: f64) -> f64 {
+    let pt = match sheet.get_row_dimension(&row) {
+        Some(dimension) if *dimension.get_hidden() => return 0.0,
+        Some(dimension) if *dimension.get_height() > 0.0 => *dimension.get_height(),
+        _ => default_pt,
+    };
+    (pt * 96.0 / 72.0).round()
+}
+
+fn build_box(cell: Option<&Cell>, style: &StyleDescriptor, effect: Option<&CellEffect>) -> CellBox {
+    let display = cell.and_then(crate::number_format::cell_display);
+    let section_color = display.as_ref().and_then(|d| d.color.clone());
+    let text = display.map(|d| d.text).unwrap_or_default();
+    let data_type = cell
+        .map(|c| c.get_data_type().to_string())
+        .unwrap_or_default();
+    let is_number = cell.and_then(|c| c.get_value_number()).is_some() && data_type != "s";
+
+    let alignment = style.alignment.as_ref();
+    let halign = match alignment.and_then(|a| a.horizontal.as_deref()) {
+        Some("center") | Some("centerContinuous") => HAlign::Center,
+        Some("right") => HAlign::Right,
+        Some("left") | Some("fill") | Some("justify") | Some("distributed") => HAlign::Left,
+        _ if is_number => HAlign::Right,
+        _ if data_type == "b" || data_type == "e" => HAlign::Center,
+        _ => HAlign::Left,
+    };
+    let valign = match alignment.and_then(|a| a.vertical.as_deref()) {
+        Some("top") => VAlign::Top,
+        Some("center") | Some("justify") | Some("distributed") => VAlign::Center,
+        _ => VAlign::Bottom,
+    };
+    let wrap = alignment.and_then(|a| a.wrap_text).unwrap_or(false);
+
+    let mut font = TextStyle::default();
+    if let Some(f) = &style.font {
+        if let Some(name) = &f.name {
+            font.family = name.clone();
+        }
+        if let Some(size) = f.size {
+            font.size_pt = size;
+        }
+        font.bold = f.bold.unwrap_or(false);
+        font.italic = f.italic.unwrap_or(false);
+        font.underline = f.underline.is_some();
+        font.strikethrough = f.strikethrough.unwrap_or(false);
+        if let Some(color) = f.color.as_deref().and_then(css_color) {
+            font.color = color;
+        }
+    }
+    if let Some(color) = section_color {
+        font.color = color;
+    }
+    let mut fill = style.fill.as_ref().and_then(fill_color);
+    let mut data_bar = None;
+    let mut icon = None;
+    if let Some(effect) = effect {
+        if effect.fill.is_some() {
+            fill = effect.fill.clone();
+        }
+        if let Some(color) = &effect.font_color {
+            font.color = color.clone();
+        }
+        if let Some(bold) = effect.bold {
+            font.bold = bold;
+        }
+        if let Some(italic) = effect.italic {
+            font.italic = italic;
+        }
+        data_bar = effect.data_bar.clone();
+        icon = effect.icon.clone();
+    }
+
+    CellBox {
+        col: 0,
+        row: 0,
+        col_span: 1,
+        row_span: 1,
+        x: 0.0,
+        y: 0.0,
+        width: 0.0,
+        height: 0.0,
+        lines: vec![text.clone()],
+        text,
+        halign,
+        valign,
+        wrap,
+        text_width: 0.0,
+        font,
+        fill,
+        borders: borders_from(style),
+        data_bar,
+        icon,
+    }
+}
+
+/// Let left-aligned text run into empty cells to its right, show `###` for
+/// numbers that do not fit, and break wrapped text into lines.
+fn fit_text(cells: &mut [CellBox], layout: &SheetLayout) {
+    let occupied: HashSet<(u32, u32)> = cells
+        .iter()
+        .filter(|c| !c.text.is_empty())
+        .map(|c| (c.col, c.row))
+        .collect();
+    let right_edge = |col: u32| {
+        layout
+            .columns
+            .iter()
+            .find(|t| t.index == col)
+            .map(|t| t.offset + t.size)
+    };
+
+    for cell in cells.iter_mut() {
+        if cell.text.is_empty() {
+            continue;
+        }
+        let available = cell.width - 2.0 * CELL_PADDING_PX;
+        if cell.wrap {
+            cell.lines = wrap_lines(&cell.text, available, &cell.font);
+            continue;
+        }
+        let needed = text_width(&cell.text, &cell.font);
+        if needed <= available {
+            continue;
+        }
+        let numeric = cell.halign == HAlign::Right
+            && cell.text.chars().any(|ch| ch.is_ascii_digit())
+            && cell.text.chars().all(|ch| !ch.is_alphabetic() || ch == 'E');
+        if numeric && cell.col_span == 1 {
+            let hashes = (available / char_width(&cell.font)).floor().max(1.0) as usize;
+            cell.text = "#".repeat(hashes);
+            cell.lines = vec![cell.text.clone()];
+            continue;
+        }
+        if cell.halign != HAlign::Left || cell.row_span != 1 {
+            continue;
+        }
+        let mut col = cell.col + cell.col_span;
+        while cell.text_width - 2.0 * CELL_PADDING_PX < needed
+            && !occupied.contains(&(col, cell.row))
+            && !layout.covered_by_merge(col, cell.row)
+        {
+            let Some(edge) = right_edge(col) else {
+                break;
+            };
+            cell.text_width = edge - cell.x;
+            col += 1;
+        }
+    }
+}
+
+fn char_width(font: &TextStyle) -> f64 {
+    let factor = if font.bold { 0.55 } else { 0.5 };
+    font.size_px() * factor
+}
+
+/// Rough advance width of `text`; good enough to decide overflow and wraps.
+pub(crate) fn text_width(text: &str, font: &TextStyle) -> f64 {
+    let base = char_width(font);
+    text.chars()
+        .map(|ch| match ch {
+            'i' | 'l' | 'j' | 'I' | '.' | ',' | ':' | ';' | '\'' | '|' | '!' => base * 0.5,
+            'f' | 't' | 'r' | ' ' | '(' | ')' | '-' => base * 0.7,
+            'm' | 'w' | 'M' | 'W' | '@' | '%' => base * 1.5,
+            c if c.is_ascii_uppercase() => base * 1.2,
+            c if c.is_ascii() => base,
+            // CJK and other wide glyphs.
+            _ => base * 2.0,
+        })
+        .sum()
+}
+
+fn wrap_lines(text: &str, width: f64, font: &TextStyle) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{line} {word}")
+            };
+            if !line.is_empty() && text_width(&candidate, font) > width {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// `AARRGGBB` (or `RRGGBB`) to CSS `#RRGGBB`; theme and indexed colors
+/// without an explicit value resolve to `None`.
+pub(crate) fn css_color(argb: &str) -> Option<String> {
+    let hex = argb.trim().trim_start_matches('#');
+    let rgb = match hex.len() {
+        8 => &hex[2..],
+        6 => hex,
+        _ => return None,
+    };
+    rgb.chars()
+        .all(|c| c.is_ascii_hexdigit())
+        .then(|| format!("#{}", rgb.to_ascii_uppercase()))
+}
+
+pub(crate) fn fill_color(fill: &FillDescriptor) -> Option<String> {
+    match fill {
+        FillDescriptor::Pattern(pattern) => {
+            if pattern
+                .pattern_type
+                .as_deref()
+                .is_none_or(|kind| kind.eq_ignore_ascii_case("none"))
+                && pattern.foreground_color.is_none()
+            {
+                return None;
+            }
+            pattern
+                .foreground_color
+                .as_deref()
+                .and_then(css_color)
+                .or_else(|| pattern.background_color.as_deref().and_then(css_color))
+        }
+        // Gradients are drawn with their first stop.
+        FillDescriptor::Gradient(gradient) => gradient
+            .stops
+            .first()
+            .and_then(|stop| css_color(&stop.color)),
+    }
+}
+
+fn borders_from(style: &StyleDescriptor) -> Borders {
+    let Some(borders) = &style.borders else {
+        return Default::default();
+    };
+    [
+        border_line(borders.top.as_ref()),
+        border_line(borders.right.as_ref()),
+        border_line(borders.bottom.as_ref()),
+        border_line(borders.left.as_ref()),
+    ]
+}
+
+fn border_line(side: Option<&BorderSideDescriptor>) -> Option<BorderLine> {
+    let side = side?;
+    let kind = side.style.as_deref()?;
+    let (width, dash, double) = match kind {
+        "none" | "" => return None,
+        "hair" => (1.0, Some("1 1"), false),
+        "thin" => (1.0, None, false),
+        "dotted" => (1.0, Some("1 2"), false),
+        "dashed" => (1.0, Some("3 2"), false),
+        "dashDot" | "slantDashDot" => (1.0, Some("4 2 1 2"), false),
+        "dashDotDot" => (1.0, Some("4 2 1 2 1 2"), false),
+        "medium" => (2.0, None, false),
+        "mediumDashed" => (2.0, Some("5 3"), false),
+        "mediumDashDot" => (2.0, Some("5 3 2 3"), false),
+        "mediumDashDotDot" => (2.0, Some("5 3 2 3 2 3"), false),
+        "double" => (3.0, None, true),
+        _ => (3.0, None, false),
+    };
+    let color = side
+        .color
+        .as_deref()
+        .and_then(css_color)
+        .unwrap_or_else(|| DEFAULT_TEXT_COLOR.to_string());
+    Some(BorderLine {
+        width,
+        color,
+        dash,
+        double,
+    })
+}
+
+/// Escape text for HTML and SVG output.
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use umya_spreadsheet::new_file;
+
+    fn area(range: &str) -> Area {
+        Area::parse(range).unwrap()
+    }
+
+    #[test]
+    fn tracks_follow_widths_heights_and_hidden_rows() {
+        let mut book = new_file();
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.get_column_dimension_mut("B").set_width(20.0);
+        sheet.get_row_dimension_mut(&2).set_height(30.0);
+        sheet.get_row_dimension_mut(&3).set_hidden(true);
+
+        let layout = layout_sheet(sheet, area("A1:C3"), "A1:C3", RenderOptions::default());
+        let widths: Vec<f64> = layout.columns.iter().map(|t| t.size).collect();
+        let heights: Vec<f64> = layout.rows.iter().map(|t| t.size).collect();
+        assert_eq!(widths, vec![64.0, 140.0, 64.0]);
+        assert_eq!(heights, vec![20.0, 40.0, 0.0]);
+        assert_eq!(layout.columns[0].offset, layout.header_width);
+        assert_eq!(layout.width, layout.header_width + 268.0);
+
+        let bare = layout_sheet(
+            sheet,
+            area("A1:C3"),
+            "A1:C3",
+            RenderOptions {
+                headers: false,
+                gridlines: false,
+            },
+        );
+        assert_eq!(bare.width, 268.0);
+        assert_eq!(bare.height, 60.0);
+    }
+
+    #[test]
+    fn merges_draw_once_and_text_spills_into_empty_cells() {
+        let mut book = new_file();
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.get_cell_mut("A1").set_value("Quarterly summary");
+        sheet.add_merge_cells("A1:C1");
+        sheet
+            .get_cell_mut("A2")
+            .set_value("A label long enough to overflow");
+        sheet.get_cell_mut("C2").set_value("x");
+        sheet.get_cell_mut("A3").set_value_number(1234567890.5);
+
+        let layout = layout_sheet(sheet, area("A1:C3"), "A1:C3", RenderOptions::default());
+        let anchor = layout.cells.iter().find(|c| c.row == 1).unwrap();
+        assert_eq!(anchor.col_span, 3);
+        assert_eq!(anchor.width, 192.0);
+        assert_eq!(layout.cells.iter().filter(|c| c.row == 1).count(), 1);
+        assert!(!layout.vertical_gridline_at(1, 1));
+        assert!(layout.vertical_gridline_at(1, 2));
+
+        let spill = layout
+            .cells
+            .iter()
+            .find(|c| c.row == 2 && c.col == 1)
+            .unwrap();
+        // B2 is empty, C2 is not: the label runs to the end of B.
+        assert_eq!(spill.text_width, 128.0);
+
+        let number = layout
+            .cells
+            .iter()
+            .find(|c| c.row == 3 && c.col == 1)
+            .unwrap();
+        assert_eq!(number.halign, HAlign::Right);
+        assert!(number.text.chars().all(|ch| ch == '#'));
+    }
+
+    #[test]
+    fn colors_convert_from_argb() {
+        assert_eq!(css_color("FF4472C4").as_deref(), Some("#4472C4"));
+        assert_eq!(css_color("#00b050").as_deref(), Some("#00B050"));
+        assert_eq!(css_color(""), None);
+    }
+}
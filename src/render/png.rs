@@ -0,0 +1,48 @@
+//! PNG output: the SVG rasterized with resvg.
+//!
+//! Fonts come from the system plus the bundled DejaVu Sans Condensed, so
+//! text renders the same on hosts without any fonts installed.
+
+use super::{SheetLayout, svg::render_svg};
+use anyhow::{Result, anyhow};
+use resvg::{tiny_skia, usvg};
+use std::sync::{Arc, OnceLock};
+
+const BUNDLED_FONTS: [&[u8]; 2] = [
+    include_bytes!("../../assets/fonts/DejaVuSansCondensed.ttf"),
+    include_bytes!("../../assets/fonts/DejaVuSansCondensed-Bold.ttf"),
+];
+const BUNDLED_FAMILY: &str = "DejaVu Sans Condensed";
+
+fn font_database() -> Arc<usvg::fontdb::Database> {
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    FONTS
+        .get_or_init(|| {
+            let mut db = usvg::fontdb::Database::new();
+            db.load_system_fonts();
+            for font in BUNDLED_FONTS {
+                db.load_font_data(font.to_vec());
+            }
+            db.set_sans_serif_family(BUNDLED_FAMILY);
+            Arc::new(db)
+        })
+        .clone()
+}
+
+pub fn render_png(layout: &SheetLayout) -> Result<Vec<u8>> {
+    let svg = render_svg(layout);
+    let options = usvg::Options {
+        fontdb: font_database(),
+        font_family: BUNDLED_FAMILY.to_string(),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_str(&svg, &options)
+        .map_err(|e| anyhow!("failed to parse rendered svg: {}", e))?;
+    let size = tree.size().to_int_size();
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| anyhow!("cannot allocate a {}x{} image", size.width(), size.height()))?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    pixmap
+        .encode_png()
+        .map_err(|e| anyhow!("failed to encode png: {}", e))
+}
@@ -0,0 +1,310 @@
+//! SVG output. Also the input to PNG rasterization, so everything is drawn
+//! with plain shapes and `<text>`.
+
+use super::{
+    Borders, CELL_PADDING_PX, CellBox, GRIDLINE_COLOR, HAlign, HEADER_FILL, HEADER_LINE,
+    HEADER_TEXT, SheetLayout, VAlign, escape_xml,
+};
+use std::fmt::Write;
+
+/// Requested family first, then metric-compatible and bundled fallbacks.
+pub(crate) const FONT_FALLBACKS: &str =
+    "Carlito, Arial, 'Liberation Sans', 'DejaVu Sans Condensed', sans-serif";
+
+pub fn render_svg(layout: &SheetLayout) -> String {
+    let (width, height) = (layout.width_px(), layout.height_px());
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    let _ = writeln!(
+        out,
+        r#"<title>{} {}</title>"#,
+        escape_xml(&layout.sheet_name),
+        escape_xml(&layout.range)
+    );
+    let _ = writeln!(
+        out,
+        r##"<rect x="0" y="0" width="{width}" height="{height}" fill="#FFFFFF"/>"##
+    );
+
+    if layout.options.gridlines {
+        gridlines(&mut out, layout);
+    }
+    for cell in &layout.cells {
+        background(&mut out, cell);
+    }
+    for (i, cell) in layout.cells.iter().enumerate() {
+        text(&mut out, cell, i);
+    }
+    for cell in &layout.cells {
+        borders(&mut out, cell);
+    }
+    if layout.options.headers {
+        headers(&mut out, layout);
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+fn gridlines(out: &mut String, layout: &SheetLayout) {
+    let _ = writeln!(
+        out,
+        r#"<g stroke="{GRIDLINE_COLOR}" stroke-width="1" shape-rendering="crispEdges">"#
+    );
+    for col in &layout.columns {
+        if col.size == 0.0 {
+            continue;
+        }
+        let x = col.offset + col.size - 0.5;
+        for row in &layout.rows {
+            if row.size > 0.0 && layout.vertical_gridline_at(col.index, row.index) {
+                line(out, x, row.offset, x, row.offset + row.size);
+            }
+        }
+    }
+    for row in &layout.rows {
+        if row.size == 0.0 {
+            continue;
+        }
+        let y = row.offset + row.size - 0.5;
+        for col in &layout.columns {
+            if col.size > 0.0 && layout.horizontal_gridline_at(col.index, row.index) {
+                line(out, col.offset, y, col.offset + col.size, y);
+            }
+        }
+    }
+    out.push_str("</g>\n");
+}
+
+fn line(out: &mut String, x1: f64, y1: f64, x2: f64, y2: f64) {
+    let _ = writeln!(out, r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}"/>"#);
+}
+
+fn background(out: &mut String, cell: &CellBox) {
+    if cell.width == 0.0 || cell.height == 0.0 {
+        return;
+    }
+    // Merged areas are painted white so the gridlines inside them vanish.
+    let fill = cell
+        .fill
+        .as_deref()
+        .or((cell.col_span > 1 || cell.row_span > 1).then_some("#FFFFFF"));
+    if let Some(fill) = fill {
+        let _ = writeln!(
+            out,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{fill}"/>"#,
+            cell.x,
+            cell.y,
+            (cell.width - 1.0).max(0.0),
+            (cell.height - 1.0).max(0.0)
+        );
+    }
+    if let Some(bar) = &cell.data_bar {
+        let _ = writeln!(
+            out,
+            r#"<rect x="{}" y="{}" width="{:.1}" height="{}" fill="{}" fill-opacity="0.75"/>"#,
+            cell.x + 1.0,
+            cell.y + 2.0,
+            ((cell.width - 3.0) * bar.fraction).max(0.0),
+            (cell.height - 5.0).max(0.0),
+            bar.color
+        );
+    }
+}
+
+fn text(out: &mut String, cell: &CellBox, index: usize) {
+    if (cell.text.is_empty() && cell.icon.is_none()) || cell.width == 0.0 || cell.height == 0.0 {
+        return;
+    }
+    let font = &cell.font;
+    let size = font.size_px();
+    let line_height = size * 1.2;
+    let icon_width = if cell.icon.is_some() { size * 0.9 } else { 0.0 };
+
+    if let Some(color) = &cell.icon {
+        let r = size * 0.3;
+        let _ = writeln!(
+            out,
+            r#"<circle cx="{:.1}" cy="{:.1}" r="{r:.1}" fill="{color}"/>"#,
+            cell.x + CELL_PADDING_PX + r,
+            cell.y + cell.height / 2.0
+        );
+    }
+    if cell.text.is_empty() {
+        return;
+    }
+
+    // Clip to the cell, or to the empty cells the text spills into.
+    let clip_id = format!("c{index}");
+    let clip_width = cell.text_width.max(cell.width);
+    let _ = writeln!(
+        out,
+        r#"<clipPath id="{clip_id}"><rect x="{}" y="{}" width="{}" height="{}"/></clipPath>"#,
+        cell.x, cell.y, clip_width, cell.height
+    );
+
+    let (x, anchor) = match cell.halign {
+        HAlign::Left => (cell.x + CELL_PADDING_PX + icon_width, "start"),
+        HAlign::Center => (cell.x + cell.width / 2.0, "middle"),
+        HAlign::Right => (cell.x + cell.width - CELL_PADDING_PX, "end"),
+    };
+    let block = line_height * (cell.lines.len().max(1) - 1) as f64;
+    let first_baseline = match cell.valign {
+        VAlign::Top => cell.y + 2.0 + size * 0.85,
+        VAlign::Center => cell.y + (cell.height - block) / 2.0 + size * 0.35,
+        VAlign::Bottom => cell.y + cell.height - 4.0 - block,
+    };
+
+    let mut decoration = Vec::new();
+    if font.underline {
+        decoration.push("underline");
+    }
+    if font.strikethrough {
+        decoration.push("line-through");
+    }
+    let _ = write!(
+        out,
+        r#"<text clip-path="url(#{clip_id})" x="{x:.1}" y="{first_baseline:.1}" text-anchor="{anchor}" font-family="{}, {FONT_FALLBACKS}" font-size="{size:.1}" fill="{}""#,
+        escape_xml(&font.family.replace('"', "")),
+        font.color
+    );
+    if font.bold {
+        out.push_str(r#" font-weight="bold""#);
+    }
+    if font.italic {
+        out.push_str(r#" font-style="italic""#);
+    }
+    if !decoration.is_empty() {
+        let _ = write!(out, r#" text-decoration="{}""#, decoration.join(" "));
+    }
+    out.push('>');
+    if cell.lines.len() <= 1 {
+        out.push_str(&escape_xml(&cell.text));
+    } else {
+        for (i, line) in cell.lines.iter().enumerate() {
+            let dy = if i == 0 { 0.0 } else { line_height };
+            let _ = write!(
+                out,
+                r#"<tspan x="{x:.1}" dy="{dy:.1}">{}</tspan>"#,
+                escape_xml(line)
+            );
+        }
+    }
+    out.push_str("</text>\n");
+}
+
+fn borders(out: &mut String, cell: &CellBox) {
+    let (x1, y1) = (cell.x, cell.y);
+    let (x2, y2) = (cell.x + cell.width, cell.y + cell.height);
+    let edges: [(f64, f64, f64, f64); 4] = [
+        (x1, y1, x2, y1),
+        (x2, y1, x2, y2),
+        (x1, y2, x2, y2),
+        (x1, y1, x1, y2),
+    ];
+    let sides: &Borders = &cell.borders;
+    for (side, (ax, ay, bx, by)) in sides.iter().zip(edges) {
+        let Some(side) = side else {
+            continue;
+        };
+        // Centre the stroke on the cell edge, like Excel.
+        let (ax, ay, bx, by) = if ay == by {
+            (ax, ay - 0.5, bx, by - 0.5)
+        } else {
+            (ax - 0.5, ay, bx - 0.5, by)
+        };
+        let mut attrs = format!(
+            r#"stroke="{}" stroke-width="{}""#,
+            side.color,
+            if side.double { 1.0 } else { side.width }
+        );
+        if let Some(dash) = side.dash {
+            let _ = write!(attrs, r#" stroke-dasharray="{dash}""#);
+        }
+        if side.double {
+            let (dx, dy) = if ay == by { (0.0, 1.0) } else { (1.0, 0.0) };
+            let _ = writeln!(
+                out,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" {attrs}/>"#,
+                ax - dx,
+                ay - dy,
+                bx - dx,
+                by - dy
+            );
+            let _ = writeln!(
+                out,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" {attrs}/>"#,
+                ax + dx,
+                ay + dy,
+                bx + dx,
+                by + dy
+            );
+        } else {
+            let _ = writeln!(
+                out,
+                r#"<line x1="{ax}" y1="{ay}" x2="{bx}" y2="{by}" {attrs}/>"#
+            );
+        }
+    }
+}
+
+fn headers(out: &mut String, layout: &SheetLayout) {
+    let (hw, hh) = (layout.header_width, layout.header_height);
+    let _ = writeln!(
+        out,
+        r#"<g font-family="{FONT_FALLBACKS}" font-size="12" fill="{HEADER_TEXT}" text-anchor="middle">"#
+    );
+    let _ = writeln!(
+        out,
+        r#"<rect x="0" y="0" width="{}" height="{hh}" fill="{HEADER_FILL}"/>"#,
+        layout.width
+    );
+    let _ = writeln!(
+        out,
+        r#"<rect x="0" y="0" width="{hw}" height="{}" fill="{HEADER_FILL}"/>"#,
+        layout.height
+    );
+    for col in layout.columns.iter().filter(|t| t.size > 0.0) {
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}">{}</text>"#,
+            col.offset + col.size / 2.0,
+            hh - 5.0,
+            layout.column_label(col)
+        );
+        let _ = writeln!(
+            out,
+            r#"<line x1="{x}" y1="0" x2="{x}" y2="{hh}" stroke="{HEADER_LINE}"/>"#,
+            x = col.offset + col.size - 0.5
+        );
+    }
+    for row in layout.rows.iter().filter(|t| t.size > 0.0) {
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.1}" y="{:.1}">{}</text>"#,
+            hw / 2.0,
+            row.offset + row.size / 2.0 + 4.0,
+            row.index
+        );
+        let _ = writeln!(
+            out,
+            r#"<line x1="0" y1="{y}" x2="{hw}" y2="{y}" stroke="{HEADER_LINE}"/>"#,
+            y = row.offset + row.size - 0.5
+        );
+    }
+    let _ = writeln!(
+        out,
+        r#"<line x1="0" y1="{y}" x2="{}" y2="{y}" stroke="{HEADER_LINE}"/>"#,
+        layout.width,
+        y = hh - 0.5
+    );
+    let _ = writeln!(
+        out,
+        r#"<line x1="{x}" y1="0" x2="{x}" y2="{}" stroke="{HEADER_LINE}"/>"#,
+        layout.height,
+        x = hw - 0.5
+    );
+    out.push_str("</g>\n");
+}
@@ -0,0 +1,695 @@
+//! MCP resources: workbooks, sheets, fork changesets and ggen artifacts
+//! addressed by URI, plus change subscriptions.
+//!
+//! - `workbook://{workbook_or_fork_id}` describes a workbook and links its
+//!   sheets.
+//! - `workbook://{workbook_or_fork_id}/sheet/{sheet_name}` is the
+//!   `sheet_overview` payload for one sheet.
+//! - `fork://{fork_id}/changeset` is the first page of `get_changeset`.
+//! - `ggen://receipts/{file}` and `ggen://reports/{file}` are generation
+//!   receipts and sync reports under the workspace root.
+//!
+//! Path segments are percent-encoded, so sheet names with spaces or
+//! punctuation round-trip. Subscribed URIs backed by a fork are notified when
+//! the fork's version is bumped; those backed by a file on disk are polled for
+//! changes.
+
+use crate::model::{WorkbookDescription, WorkbookId};
+use crate::state::AppState;
+use crate::tools;
+use anyhow::{Context, Result, anyhow, bail};
+use parking_lot::Mutex;
+use rmcp::RoleServer;
+use rmcp::model::{
+    AnnotateAble, RawResource, RawResourceTemplate, Resource, ResourceTemplate,
+    ResourceUpdatedNotificationParam,
+};
+use rmcp::service::Peer;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, SystemTime};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// How often subscribed files are checked for changes on disk.
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Directories, relative to the workspace root, that hold ggen artifacts.
+const RECEIPT_DIRS: &[&str] = &[".ggen/receipts", "ggen.out/receipts"];
+const REPORT_DIRS: &[&str] = &["ggen.out/reports"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactKind {
+    Receipt,
+    Report,
+}
+
+impl ArtifactKind {
+    fn segment(self) -> &'static str {
+        match self {
+            ArtifactKind::Receipt => "receipts",
+            ArtifactKind::Report => "reports",
+        }
+    }
+
+    fn dirs(self) -> &'static [&'static str] {
+        match self {
+            ArtifactKind::Receipt => RECEIPT_DIRS,
+            ArtifactKind::Report => REPORT_DIRS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceUri {
+    Workbook(WorkbookId),
+    Sheet {
+        workbook_id: WorkbookId,
+        sheet_name: String,
+    },
+    Changeset {
+        fork_id: String,
+    },
+    Artifact {
+        kind: ArtifactKind,
+        file_name: String,
+    },
+}
+
+impl ResourceUri {
+    pub fn parse(uri: &str) -> Result<Self> {
+        let (scheme, rest) = uri
+            .split_once("://")
+            .ok_or_else(|| anyhow!("resource uri '{uri}' has no scheme"))?;
+        let segments: Vec<&str> = rest.split('/').collect();
+        Ok(match (scheme, segments.as_slice()) {
+            ("workbook", [id]) => ResourceUri::Workbook(WorkbookId(decode_segment(id)?)),
+            ("workbook", [id, "sheet", name]) => ResourceUri::Sheet {
+                workbook_id: WorkbookId(decode_segment(id)?),
+                sheet_name: decode_segment(name)?,
+            },
+            ("fork", [id, "changeset"]) => ResourceUri::Changeset {
+                fork_id: decode_segment(id)?,
+            },
+            ("ggen", [dir, file]) => {
+                let kind = match *dir {
+                    "receipts" => ArtifactKind::Receipt,
+                    "reports" => ArtifactKind::Report,
+                    other => bail!("unknown ggen artifact kind '{other}' in '{uri}'"),
+                };
+                let file_name = decode_segment(file)?;
+                if file_name.starts_with('.') || file_name.contains(['/', '\\']) {
+                    bail!("invalid artifact file name '{file_name}'");
+                }
+                ResourceUri::Artifact { kind, file_name }
+            }
+            _ => bail!(
+                "unsupported resource uri '{uri}'; expected workbook://{{id}}, \
+                 workbook://{{id}}/sheet/{{name}}, fork://{{id}}/changeset or ggen://receipts|reports/{{file}}"
+            ),
+        })
+    }
+
+    /// The fork whose version changes affect this resource, if any. Workbook
+    /// and sheet URIs name a fork when their id is a fork id.
+    fn fork_id<'a>(&'a self, state: &AppState) -> Option<&'a str> {
+        match self {
+            ResourceUri::Changeset { fork_id } => Some(fork_id),
+            ResourceUri::Workbook(id)
+            | ResourceUri::Sheet {
+                workbook_id: id, ..
+            } => is_fork(state, id.as_str()).then_some(id.as_str()),
+            ResourceUri::Artifact { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for ResourceUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceUri::Workbook(id) => write!(f, "workbook://{}", encode_segment(id.as_str())),
+            ResourceUri::Sheet {
+                workbook_id,
+                sheet_name,
+            } => write!(
+                f,
+                "workbook://{}/sheet/{}",
+                encode_segment(workbook_id.as_str()),
+                encode_segment(sheet_name)
+            ),
+            ResourceUri::Changeset { fork_id } => {
+                write!(f, "fork://{}/changeset", encode_segment(fork_id))
+            }
+            ResourceUri::Artifact { kind, file_name } => {
+                write!(f, "ggen://{}/{}", kind.segment(), encode_segment(file_name))
+            }
+        }
+    }
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters.
+fn encode_segment(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+fn decode_segment(value: &str) -> Result<String> {
+    if value.is_empty() {
+        bail!("resource uri has an empty path segment");
+    }
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = value
+                .get(i + 1..i + 3)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+                .ok_or_else(|| anyhow!("invalid percent-encoding in '{value}'"))?;
+            out.push(hex);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).map_err(|_| anyhow!("percent-encoded '{value}' is not UTF-8"))
+}
+
+#[cfg(feature = "recalc")]
+fn is_fork(state: &AppState, id: &str) -> bool {
+    state
+        .fork_registry()
+        .is_some_and(|registry| registry.get_fork_path(id).is_some())
+}
+
+#[cfg(not(feature = "recalc"))]
+fn is_fork(_state: &AppState, _id: &str) -> bool {
+    false
+}
+
+fn resource(uri: &ResourceUri, name: String, description: String, mime_type: &str) -> Resource {
+    let mut raw = RawResource::new(uri.to_string(), name);
+    raw.description = Some(description);
+    raw.mime_type = Some(mime_type.to_string());
+    raw.no_annotation()
+}
+
+fn template(
+    uri_template: &str,
+    name: &str,
+    description: &str,
+    mime_type: &str,
+) -> ResourceTemplate {
+    RawResourceTemplate {
+        uri_template: uri_template.to_string(),
+        name: name.to_string(),
+        title: None,
+        description: Some(description.to_string()),
+        mime_type: Some(mime_type.to_string()),
+        icons: None,
+    }
+    .no_annotation()
+}
+
+pub fn resource_templates() -> Vec<ResourceTemplate> {
+    vec![
+        template(
+            "workbook://{workbook_or_fork_id}",
+            "workbook",
+            "describe_workbook payload plus links to each sheet",
+            "application/json",
+        ),
+        template(
+            "workbook://{workbook_or_fork_id}/sheet/{sheet_name}",
+            "sheet",
+            "sheet_overview payload for one sheet (percent-encode the sheet name)",
+            "application/json",
+        ),
+        template(
+            "fork://{fork_id}/changeset",
+            "fork changeset",
+            "First page of get_changeset for a fork",
+            "application/json",
+        ),
+        template(
+            "ggen://receipts/{file}",
+            "ggen receipt",
+            "Generation receipt under .ggen/receipts or ggen.out/receipts",
+            "application/json",
+        ),
+        template(
+            "ggen://reports/{file}",
+            "ggen report",
+            "Sync report under ggen.out/reports",
+            "text/markdown",
+        ),
+    ]
+}
+
+/// Every workbook, fork and ggen artifact currently in the workspace.
+pub fn list_resources(state: &AppState) -> Result<Vec<Resource>> {
+    let mut resources = Vec::new();
+    let listed = state.list_workbooks(Default::default())?;
+    for workbook in listed.workbooks {
+        resources.push(resource(
+            &ResourceUri::Workbook(workbook.workbook_id),
+            workbook.slug,
+            format!("Workbook {}", workbook.path),
+            "application/json",
+        ));
+    }
+
+    #[cfg(feature = "recalc")]
+    if let Some(registry) = state.fork_registry() {
+        for fork in registry.list_forks() {
+            let id = WorkbookId(fork.fork_id.clone());
+            resources.push(resource(
+                &ResourceUri::Workbook(id),
+                fork.fork_id.clone(),
+                format!("Fork of {}", fork.base_path),
+                "application/json",
+            ));
+            resources.push(resource(
+                &ResourceUri::Changeset {
+                    fork_id: fork.fork_id.clone(),
+                },
+                format!("{} changeset", fork.fork_id),
+                format!(
+                    "Changes in fork {} against {}",
+                    fork.fork_id, fork.base_path
+                ),
+                "application/json",
+            ));
+        }
+    }
+
+    for kind in [ArtifactKind::Receipt, ArtifactKind::Report] {
+        for (dir, file_name) in artifact_files(state, kind) {
+            let mime_type = artifact_mime(&file_name);
+            resources.push(resource(
+                &ResourceUri::Artifact {
+                    kind,
+                    file_name: file_name.clone(),
+                },
+                file_name,
+                format!("ggen {} in {dir}", kind.segment()),
+                mime_type,
+            ));
+        }
+    }
+    Ok(resources)
+}
+
+/// Artifact files of `kind` with the directory each was found in. A name in
+/// more than one directory is listed once, from the first.
+pub fn artifact_files(state: &AppState, kind: ArtifactKind) -> Vec<(&'static str, String)> {
+    let root = state.config().workspace_root.clone();
+    let mut seen = BTreeSet::new();
+    let mut files = Vec::new();
+    for dir in kind.dirs() {
+        let Ok(entries) = std::fs::read_dir(root.join(dir)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            if entry.path().is_file() && seen.insert(file_name.clone()) {
+                files.push((*dir, file_name));
+            }
+        }
+    }
+    files
+}
+
+fn artifact_mime(file_name: &str) -> &'static str {
+    match Path::new(file_name).extension().and_then(|e| e.to_str()) {
+        Some("json") => "application/json",
+        Some("md") => "text/markdown",
+        _ => "text/plain",
+    }
+}
+
+fn artifact_path(root: &Path, kind: ArtifactKind, file_name: &str) -> Option<PathBuf> {
+    kind.dirs()
+        .iter()
+        .map(|dir| root.join(dir).join(file_name))
+        .find(|path| path.is_file())
+}
+
+#[derive(Debug, Serialize)]
+pub struct SheetLink {
+    pub name: String,
+    pub uri: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkbookResource {
+    #[serde(flatten)]
+    pub description: WorkbookDescription,
+    pub sheets: Vec<SheetLink>,
+}
+
+pub async fn read_workbook(
+    state: Arc<AppState>,
+    workbook_id: WorkbookId,
+) -> Result<WorkbookResource> {
+    let description = tools::describe_workbook(
+        state.clone(),
+        tools::DescribeWorkbookParams {
+            workbook_or_fork_id: workbook_id.clone(),
+        },
+    )
+    .await?;
+    let workbook = state.open_workbook(&workbook_id).await?;
+    let sheets = workbook
+        .sheet_names()
+        .into_iter()
+        .map(|name| SheetLink {
+            uri: ResourceUri::Sheet {
+                workbook_id: workbook_id.clone(),
+                sheet_name: name.clone(),
+            }
+            .to_string(),
+            name,
+        })
+        .collect();
+    Ok(WorkbookResource {
+        description,
+        sheets,
+    })
+}
+
+pub async fn read_sheet(
+    state: Arc<AppState>,
+    workbook_id: WorkbookId,
+    sheet_name: String,
+) -> Result<crate::model::SheetOverviewResponse> {
+    tools::sheet_overview(
+        state,
+        tools::SheetOverviewParams {
+            workbook_or_fork_id: workbook_id,
+            sheet_name,
+            max_regions: None,
+            max_headers: None,
+            include_headers: None,
+        },
+    )
+    .await
+}
+
+#[cfg(feature = "recalc")]
+pub async fn read_changeset(
+    state: Arc<AppState>,
+    fork_id: String,
+) -> Result<tools::fork::GetChangesetResponse> {
+    tools::fork::get_changeset(
+        state,
+        tools::fork::GetChangesetParams {
+            fork_id,
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+#[cfg(not(feature = "recalc"))]
+pub async fn read_changeset(_state: Arc<AppState>, fork_id: String) -> Result<()> {
+    bail!("fork '{fork_id}' unavailable: built without the recalc feature")
+}
+
+pub async fn read_artifact(
+    state: Arc<AppState>,
+    kind: ArtifactKind,
+    file_name: String,
+) -> Result<String> {
+    let root = state.config().workspace_root.clone();
+    let path = artifact_path(&root, kind, &file_name)
+        .ok_or_else(|| anyhow!("no ggen {} named '{file_name}'", kind.segment()))?;
+    tokio::fs::read_to_string(&path)
+        .await
+        .with_context(|| format!("failed to read {}", path.display()))
+}
+
+/// Modification time and size, or `None` while the file is missing.
+type FileStamp = Option<(Option<SystemTime>, u64)>;
+
+fn stamp(path: &Path) -> FileStamp {
+    std::fs::metadata(path)
+        .ok()
+        .map(|meta| (meta.modified().ok(), meta.len()))
+}
+
+#[derive(Debug)]
+struct Watch {
+    fork_id: Option<String>,
+    file: Option<(PathBuf, FileStamp)>,
+}
+
+/// A change the notifier maps onto the subscribed URIs it affects.
+enum ChangeEvent {
+    Fork(String),
+    /// The fork change feed lagged, so every fork-backed URI may be stale.
+    AnyFork,
+    File(PathBuf),
+}
+
+/// Resource subscriptions for one client session. The first subscription
+/// starts a notifier task that lives until the session is dropped.
+#[derive(Default)]
+pub struct ResourceSubscriptions {
+    watches: Mutex<HashMap<String, Watch>>,
+    notifier: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl ResourceSubscriptions {
+    pub async fn subscribe(
+        self: &Arc<Self>,
+        state: Arc<AppState>,
+        peer: Peer<RoleServer>,
+        uri: &str,
+    ) -> Result<()> {
+        let parsed = ResourceUri::parse(uri)?;
+        let fork_id = parsed.fork_id(&state).map(str::to_string);
+        let path = match &parsed {
+            ResourceUri::Changeset { fork_id } => {
+                if !is_fork(&state, fork_id) {
+                    bail!("fork not found: {fork_id}");
+                }
+                None
+            }
+            _ if fork_id.is_some() => None,
+            ResourceUri::Workbook(id)
+            | ResourceUri::Sheet {
+                workbook_id: id, ..
+            } => Some(state.open_workbook(id).await?.path.clone()),
+            ResourceUri::Artifact { kind, file_name } => {
+                let root = state.config().workspace_root.clone();
+                // Watch a receipt that does not exist yet in the first
+                // directory ggen writes it to.
+                Some(
+                    artifact_path(&root, *kind, file_name)
+                        .unwrap_or_else(|| root.join(kind.dirs()[0]).join(file_name)),
+                )
+            }
+        };
+        let file = path.map(|path| {
+            let stamp = stamp(&path);
+            (path, stamp)
+        });
+        self.watches
+            .lock()
+            .insert(uri.to_string(), Watch { fork_id, file });
+
+        let mut notifier = self.notifier.lock();
+        if notifier.as_ref().is_none_or(|task| task.is_finished()) {
+            *notifier = Some(tokio::spawn(notify_loop(Arc::downgrade(self), state, peer)));
+        }
+        Ok(())
+    }
+
+    pub fn unsubscribe(&self, uri: &str) {
+        self.watches.lock().remove(uri);
+    }
+
+    fn affected(&self, event: &ChangeEvent) -> Vec<String> {
+        let watches = self.watches.lock();
+        let mut uris: Vec<String> = watches
+            .iter()
+            .filter(|(_, watch)| match event {
+                ChangeEvent::Fork(id) => watch.fork_id.as_deref() == Some(id.as_str()),
+                ChangeEvent::AnyFork => watch.fork_id.is_some(),
+                ChangeEvent::File(path) => watch.file.as_ref().is_some_and(|(p, _)| p == path),
+            })
+            .map(|(uri, _)| uri.clone())
+            .collect();
+        uris.sort();
+        uris
+    }
+
+    /// Restamp watched files and return the paths whose stamp changed.
+    fn changed_files(&self) -> Vec<PathBuf> {
+        let watched: Vec<(PathBuf, FileStamp)> = {
+            let watches = self.watches.lock();
+            let mut files: Vec<_> = watches.values().filter_map(|w| w.file.clone()).collect();
+            files.sort_by(|a, b| a.0.cmp(&b.0));
+            files.dedup_by(|a, b| a.0 == b.0);
+            files
+        };
+        let mut changed = Vec::new();
+        for (path, old) in watched {
+            let new = stamp(&path);
+            if new == old {
+                continue;
+            }
+            for watch in self.watches.lock().values_mut() {
+                if let Some((p, s)) = &mut watch.file
+                    && *p == path
+                {
+                    *s = new;
+                }
+            }
+            changed.push(path);
+        }
+        changed
+    }
+}
+
+impl Drop for ResourceSubscriptions {
+    fn drop(&mut self) {
+        if let Some(task) = self.notifier.get_mut().take() {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(feature = "recalc")]
+fn fork_changes(state: &AppState) -> Option<broadcast::Receiver<String>> {
+    state
+        .fork_registry()
+        .map(|registry| registry.subscribe_changes())
+}
+
+#[cfg(not(feature = "recalc"))]
+fn fork_changes(_state: &AppState) -> Option<broadcast::Receiver<String>> {
+    None
+}
+
+async fn next_fork_change(
+    changes: &mut Option<broadcast::Receiver<String>>,
+) -> Result<String, RecvError> {
+    match changes {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn notify_loop(
+    subscriptions: Weak<ResourceSubscriptions>,
+    state: Arc<AppState>,
+    peer: Peer<RoleServer>,
+) {
+    let mut fork_changes = fork_changes(&state);
+    let mut interval = tokio::time::interval(WATCH_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        let received = tokio::select! {
+            received = next_fork_change(&mut fork_changes) => Some(received),
+            _ = interval.tick() => None,
+        };
+        let Some(subscriptions) = subscriptions.upgrade() else {
+            return;
+        };
+        let events = match received {
+            Some(Ok(fork_id)) => vec![ChangeEvent::Fork(fork_id)],
+            Some(Err(RecvError::Lagged(_))) => vec![ChangeEvent::AnyFork],
+            Some(Err(RecvError::Closed)) => {
+                fork_changes = None;
+                continue;
+            }
+            None => subscriptions
+                .changed_files()
+                .into_iter()
+                .map(|path| {
+                    state.evict_by_path(&path);
+                    ChangeEvent::File(path)
+                })
+                .collect(),
+        };
+        let uris: BTreeSet<String> = events
+            .iter()
+            .flat_map(|event| subscriptions.affected(event))
+            .collect();
+        drop(subscriptions);
+
+        for uri in uris {
+            debug!(uri = %uri, "notifying resource update");
+            if let Err(error) = peer
+                .notify_resource_updated(ResourceUpdatedNotificationParam { uri })
+                .await
+            {
+                debug!(?error, "resource notification failed; stopping notifier");
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resource_uris_round_trip() {
+        for uri in [
+            ResourceUri::Workbook(WorkbookId("wb-1".into())),
+            ResourceUri::Sheet {
+                workbook_id: WorkbookId("fork-abc".into()),
+                sheet_name: "Q1 / Plan %".into(),
+            },
+            ResourceUri::Changeset {
+                fork_id: "fork-abc".into(),
+            },
+            ResourceUri::Artifact {
+                kind: ArtifactKind::Report,
+                file_name: "2024-01-01.md".into(),
+            },
+        ] {
+            let text = uri.to_string();
+            assert_eq!(ResourceUri::parse(&text).unwrap(), uri, "{text}");
+        }
+        assert_eq!(
+            ResourceUri::Sheet {
+                workbook_id: WorkbookId("wb".into()),
+                sheet_name: "Q1 / Plan".into(),
+            }
+            .to_string(),
+            "workbook://wb/sheet/Q1%20%2F%20Plan"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_and_unsafe_uris() {
+        for uri in [
+            "file:///etc/passwd",
+            "workbook://",
+            "workbook://wb/table/t1",
+            "fork://f1",
+            "ggen://receipts/..%2Fsecret",
+            "ggen://templates/a.tera",
+            "workbook://wb/sheet/%ZZ",
+        ] {
+            assert!(ResourceUri::parse(uri).is_err(), "{uri}");
+        }
+    }
+}
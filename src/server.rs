@@ -1,24 +1,39 @@
+use crate::audit;
+use crate::auth::{AccessDeniedError, Principal};
+use crate::completion;
 use crate::config::ServerConfig;
 use crate::error::{ErrorCode, McpError as CustomMcpError, to_rmcp_error};
 use crate::model::{
-    CloseWorkbookResponse, FindFormulaResponse, FindValueResponse, FormulaTraceResponse,
-    ManifestStubResponse, NamedRangesResponse, RangeValuesResponse, ReadTableResponse,
-    SheetFormulaMapResponse, SheetListResponse, SheetOverviewResponse, SheetPageResponse,
-    SheetStatisticsResponse, SheetStylesResponse, TableProfileResponse, VolatileScanResponse,
-    WorkbookDescription, WorkbookListResponse, WorkbookStyleSummaryResponse,
-    WorkbookSummaryResponse,
+    CloseWorkbookResponse, DataValidationsResponse, FindFormulaResponse, FindValueResponse,
+    FormulaTraceResponse, ManifestStubResponse, NamedRangesResponse, RangeValuesResponse,
+    ReadTableResponse, SheetCommentsResponse, SheetFormulaMapResponse, SheetListResponse,
+    SheetOverviewResponse, SheetPageResponse, SheetStatisticsResponse, SheetStylesResponse,
+    TableProfileResponse, ValidationViolationsResponse, VolatileScanResponse, WorkbookDescription,
+    WorkbookListResponse, WorkbookStyleSummaryResponse, WorkbookSummaryResponse,
 };
+use crate::progress::{CancelledError, ProgressReporter};
+use crate::prompts;
+use crate::resources::{self, ArtifactKind, ResourceSubscriptions, ResourceUri};
 use crate::state::AppState;
 use crate::tools;
 use anyhow::{Result, anyhow};
 use rmcp::{
-    ErrorData as McpError, Json, ServerHandler, ServiceExt,
-    handler::server::{router::tool::ToolRouter, wrapper::Parameters},
-    model::{Implementation, ServerCapabilities, ServerInfo},
-    tool, tool_handler, tool_router,
+    ErrorData as McpError, Json, RoleServer, ServerHandler, ServiceExt,
+    handler::server::{router::tool::ToolRouter, tool::ToolCallContext, wrapper::Parameters},
+    model::{
+        CallToolRequestParam, CallToolResult, CompleteRequestParam, CompleteResult, CompletionInfo,
+        GetPromptRequestParam, GetPromptResult, Implementation, ListPromptsResult,
+        ListResourceTemplatesResult, ListResourcesResult, ListToolsResult, PaginatedRequestParam,
+        PromptMessage, PromptMessageRole, ReadResourceRequestParam, ReadResourceResult, Reference,
+        ResourceContents, ServerCapabilities, ServerInfo, SubscribeRequestParam,
+        UnsubscribeRequestParam,
+    },
+    service::RequestContext,
+    tool, tool_router,
     transport::stdio,
 };
 use serde::Serialize;
+use std::collections::HashMap;
 use std::future::Future;
 use std::sync::Arc;
 use thiserror::Error;
@@ -46,6 +61,29 @@ unstructured sheets. Prefer read_table for tabular data.
 Use direction='right' or 'below' hints.
 - find_formula: Search formulas. Default returns no context and only first 50 matches. \
 Use include_context=true for header+cell snapshots, and use limit/offset to page.
+- sheet_comments: Reviewer notes and threaded comments with replies. Use unresolved_only=true for open feedback.
+- data_validations: Dropdown lists and input rules per range, with resolved list entries. \
+find_validation_violations lists existing cells whose values break those rules.
+- list_charts: Charts per sheet with type, titles, anchor and series ranges. \
+chart_data returns the plotted numbers for one chart (sheet_name + chart_index).
+- list_pivot_tables: Pivot tables per sheet with location, source range/table, row/column/page fields, \
+value fields with aggregation and filters. pivot_data recomputes a pivot's figures from its cache records \
+(sheet_name + pivot_name), so no recalculation is needed.
+- diff_workbooks: {base_id, target_id, base_checkpoint_id?, target_checkpoint_id?, key_column?, header_row?}. \
+Compares any two workbooks (or, with recalc enabled, forks and fork checkpoints) with get_changeset's filters and paging. \
+With key_column (header text or letter) rows are matched by key, so sorted or inserted rows report \
+row_added/row_deleted/row_modified instead of shifted cells.
+- render_sheet: {workbook_or_fork_id, sheet_name, range?, format?: png|svg|html, headers?, gridlines?}. \
+Draws a range natively (no LibreOffice needed) with number formats, styles, merges and conditional-format colors. \
+Writes the file under workspace_root/screenshots/ and returns it inline.
+
+RESOURCES: workbook://{id} (describe_workbook + sheet links), workbook://{id}/sheet/{name} (sheet_overview; \
+percent-encode the name), fork://{fork_id}/changeset and ggen://receipts|reports/{file}. \
+Subscribe to be notified when a fork is edited or a workbook changes on disk.
+
+PROMPTS: profile_workbook, safe_fork_edit, add_entity_and_regenerate and run_definition_of_done \
+walk through common workflows step by step. Prompt arguments and resource template variables \
+support completion; matching ignores case and separators and also accepts word starts.
 
 RANGES: Use A1 notation (e.g., A1:C10). Prefer region_id when available.
 
@@ -80,27 +118,56 @@ Fork-based editing allows 'what-if' analysis without modifying original files.
 WORKFLOW:
 1) create_fork: Create editable copy of a workbook. Returns fork_id.
 2) Optional: checkpoint_fork before large edits.
-3) edit_batch/transform_batch/style_batch/structure_batch/apply_formula_pattern: Apply edits to the fork.
-4) recalculate: Trigger LibreOffice to recompute all formulas.
+3) edit_batch/transform_batch/style_batch/structure_batch/comment_batch/validation_batch/conditional_format_batch/chart_batch/sheet_settings_batch/apply_formula_pattern: Apply edits to the fork.
+4) recalculate: Recompute all formulas (LibreOffice, or the in-process evaluator when configured).
 5) get_changeset: Diff fork against original. Use filters/limit/offset to keep it small.
-   Optional: screenshot_sheet to capture a visual view of a range (original or fork).
+   Optional: render_sheet (or screenshot_sheet, via LibreOffice) to capture a visual view of a range (original or fork).
 6) save_fork: Write changes to file.
 7) discard_fork: Delete fork without saving.
 
 SAFETY:
 - checkpoint_fork before large/structural edits; restore_checkpoint to rollback if needed.
-- Tools with mode='preview' create staged changes (transform_batch/style_batch/structure_batch/apply_formula_pattern); use list_staged_changes + apply_staged_change/discard_staged_change.
+- Tools with mode='preview' create staged changes (transform_batch/style_batch/structure_batch/comment_batch/validation_batch/conditional_format_batch/chart_batch/sheet_settings_batch/apply_formula_pattern); use list_staged_changes + apply_staged_change/discard_staged_change.
 
 TOOL DETAILS:
-- create_fork: Only .xlsx supported. Returns fork_id for subsequent operations.
+- create_fork: .xlsx, .ods, .csv and .tsv supported (non-xlsx bases are converted to an xlsx fork). Returns fork_id for subsequent operations.
 - edit_batch: {fork_id, sheet_name, edits:[{address, value, is_formula}]}. \
 Formulas should NOT include leading '='.
+- structure_batch table ops: create_table {sheet_name, range, table_name?, style?} uses the first row as headers \
+(blank or duplicate headers are renamed). resize_table keeps the header row. set_table_totals {table_name, show, \
+functions?: {column: sum|average|count|count_nums|max|min|std_dev|var|none}, label?} writes SUBTOTAL formulas.
 - transform_batch: Range-first clear/fill/replace. Prefer for bulk edits (blank/fill/rename) to avoid per-cell edit_batch bloat.
+- comment_batch: {fork_id, ops:[{sheet_name, address, kind, ...}]}. kind: add (note, or thread with threaded=true), \
+reply, edit, resolve (resolved=false reopens) or delete. Use sheet_comments to read existing feedback first.
+- validation_batch: {fork_id, ops:[{sheet_name, target, kind: add|modify|remove, rule?}]}. target is a range, region or cells. \
+rule: {validation_type, operator?, formula1?, formula2?, list_values?, error_style?, error_title?, error_message?, prompt?}. \
+Existing values that break the new rules are reported in summary.warnings, not changed.
+- conditional_format_batch: {fork_id, ops:[{sheet_name, target, kind, style?, stop_if_true?}]}. target is a range, region or cells. \
+kind: cell_is {operator, formula, formula2?}, expression {formula}, color_scale {colors, points?}, data_bar {color, min?, max?}, \
+icon_set {thresholds?}, top_n {rank, bottom?, percent?}, duplicate {unique?} or clear. \
+style is a style_batch patch used as the rule's format (required except for scales, bars and icons). New rules take top priority.
+- chart_batch: {fork_id, ops:[{kind: create|update|delete, sheet_name, ...}]}. create takes chart_type \
+(column|bar|line|pie|scatter|area) plus either source (a range; first column/row are categories, header row/column \
+are series names, series_in_rows?) or series [{values, categories?, name_ref?, name?}], and anchor?/anchor_to?, \
+title?, x_axis_title?, y_axis_title?. update/delete take chart_index from list_charts; update keeps styling.
+- sheet_settings_batch: {fork_id, ops:[{kind, sheet_name?, ...}]}. kind: freeze_panes {rows, cols}, \
+split_panes {x_points, y_points}, set_visibility {state: visible|hidden|very_hidden}, set_tab_color {color?}, \
+set_view {zoom?, show_gridlines?}, group/ungroup {range: whole rows 5:9 or columns C:E, collapsed?}, \
+set_collapsed {range, collapsed}, protect_sheet {password?, allow?}, unprotect_sheet, \
+protect_workbook {password?, lock_structure?, lock_windows?}, unprotect_workbook, \
+set_cell_lock {range, locked, hide_formulas?}. Cells are locked by default: unlock input cells, then protect the sheet. \
+sheet_overview reports the resulting settings.
 - recalculate: Required after edit_batch to update formula results. \
 May take several seconds for complex workbooks.
 - get_changeset: Returns a paged diff + summary. Use limit/offset to page. \
 Use include_types/exclude_types/include_subtypes/exclude_subtypes to filter (e.g. exclude_subtypes=['recalc_result']). \
-Use summary_only=true when you only need counts.
+Use summary_only=true when you only need counts. \
+Besides cells, tables and names it reports style_changed (ranges grouped by before/after style), \
+comment_*, validation_*, conditional_format_*, chart_*, merge_added/merge_removed and \
+sheet_added/sheet_deleted/sheet_moved/sheet_visibility_changed/freeze_panes_changed.
+- merge_forks: {fork_a, fork_b, policy?, sheet_name?}. Three-way merge of fork_b into fork_a (both forked from the same workbook). \
+Non-overlapping cell, name and table changes are applied to fork_a; conflicts list base/fork_a/fork_b values per cell. \
+policy: prefer_a | prefer_b | stage (default; stages fork_b's side as a change_id for apply_staged_change).
 - screenshot_sheet: {workbook_or_fork_id, sheet_name, range?}. Renders a cropped PNG for inspecting an area visually.
   workbook_or_fork_id may be either a real workbook_id OR a fork_id (to screenshot an edited fork).
   Returns a file:// URI under workspace_root/screenshots/ (Docker default: /data/screenshots/).
@@ -110,6 +177,8 @@ Use summary_only=true when you only need counts.
   If target_path is relative, it is resolved under workspace_root (Docker default: `/data`).
   Overwriting original requires server --allow-overwrite flag.
   Use drop_fork=false to keep fork active after saving (default: true drops fork).
+  The target extension picks the format: .xlsx, .ods (values, formulas and named ranges only),
+  or .csv/.tsv (exports one sheet; pass sheet_name, default first sheet).
   Validates base file unchanged since fork creation.
 - get_edits: List all edits applied to a fork (before recalculate).
 - list_forks: See all active forks.
@@ -124,7 +193,7 @@ Use summary_only=true when you only need counts.
 BEST PRACTICES:
 - Always recalculate after edit_batch before get_changeset.
 - Review changeset before save_fork to verify expected changes.
-- Use screenshot_sheet for quick visual inspection; save_fork is ONLY for exporting a workbook file.
+- Use render_sheet (or screenshot_sheet) for quick visual inspection; save_fork is ONLY for exporting a workbook file.
 - Discard forks when done to free resources (auto-cleanup after 1 hour).
 - For large edits, batch multiple cells in single edit_batch call.";
 
@@ -150,6 +219,10 @@ fn build_instructions(recalc_enabled: bool, vba_enabled: bool) -> String {
 pub struct SpreadsheetServer {
     pub state: Arc<AppState>,
     tool_router: ToolRouter<SpreadsheetServer>,
+    /// Resource subscriptions of this client session.
+    resources: Arc<ResourceSubscriptions>,
+    /// Per-identity states when the HTTP transport authenticates clients.
+    principal_states: Option<Arc<HashMap<String, Arc<AppState>>>>,
 }
 
 impl SpreadsheetServer {
@@ -178,9 +251,56 @@ impl SpreadsheetServer {
         Self {
             state,
             tool_router: router,
+            resources: Arc::default(),
+            principal_states: None,
         }
     }
 
+    /// Serve each authenticated principal from its own state, keyed by
+    /// identity name. Requests without a principal are then rejected.
+    pub fn with_principal_states(mut self, states: Arc<HashMap<String, Arc<AppState>>>) -> Self {
+        self.principal_states = Some(states);
+        self
+    }
+
+    /// Names of every tool this server routes, before `enabled_tools` applies.
+    pub fn tool_names(&self) -> Vec<String> {
+        self.tool_router
+            .list_all()
+            .into_iter()
+            .map(|tool| tool.name.to_string())
+            .collect()
+    }
+
+    /// This server with the state of the request's principal, if the HTTP
+    /// transport authenticated one.
+    fn scoped(
+        &self,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<(Self, Option<Arc<Principal>>), McpError> {
+        let Some(states) = &self.principal_states else {
+            return Ok((self.clone(), None));
+        };
+        let principal = context
+            .extensions
+            .get::<axum::http::request::Parts>()
+            .and_then(|parts| parts.extensions.get::<Arc<Principal>>())
+            .cloned()
+            .ok_or_else(|| McpError::invalid_request("request is not authenticated", None))?;
+        let state = states.get(&principal.name).cloned().ok_or_else(|| {
+            McpError::invalid_request(format!("unknown principal '{}'", principal.name), None)
+        })?;
+        let server = Self {
+            state,
+            ..self.clone()
+        };
+        Ok((server, Some(principal)))
+    }
+
+    fn principal_name(principal: &Option<Arc<Principal>>) -> Option<String> {
+        principal.as_ref().map(|principal| principal.name.clone())
+    }
+
     pub async fn run_stdio(self) -> Result<()> {
         let service = self
             .serve(stdio())
@@ -264,6 +384,57 @@ impl SpreadsheetServer {
         }
     }
 
+    /// Read a resource as JSON (or the artifact's own text), gated and limited
+    /// like the tool that produces the same payload.
+    async fn read_resource_text(&self, uri: &ResourceUri) -> Result<String> {
+        let state = self.state.clone();
+        match uri.clone() {
+            ResourceUri::Workbook(workbook_id) => {
+                self.ensure_tool_enabled("describe_workbook")?;
+                let payload = self
+                    .run_tool_with_timeout(
+                        "describe_workbook",
+                        resources::read_workbook(state, workbook_id),
+                    )
+                    .await?;
+                Ok(serde_json::to_string(&payload)?)
+            }
+            ResourceUri::Sheet {
+                workbook_id,
+                sheet_name,
+            } => {
+                self.ensure_tool_enabled("sheet_overview")?;
+                let payload = self
+                    .run_tool_with_timeout(
+                        "sheet_overview",
+                        resources::read_sheet(state, workbook_id, sheet_name),
+                    )
+                    .await?;
+                Ok(serde_json::to_string(&payload)?)
+            }
+            ResourceUri::Changeset { fork_id } => {
+                #[cfg(feature = "recalc")]
+                self.ensure_recalc_enabled("get_changeset")?;
+                let payload = self
+                    .run_tool_with_timeout(
+                        "get_changeset",
+                        resources::read_changeset(state, fork_id),
+                    )
+                    .await?;
+                Ok(serde_json::to_string(&payload)?)
+            }
+            ResourceUri::Artifact { kind, file_name } => {
+                let tool = match kind {
+                    ArtifactKind::Receipt => "verify_receipt",
+                    ArtifactKind::Report => "sync_ggen",
+                };
+                self.ensure_tool_enabled(tool)?;
+                self.run_tool_with_timeout(tool, resources::read_artifact(state, kind, file_name))
+                    .await
+            }
+        }
+    }
+
     fn ensure_response_size<T: Serialize>(&self, tool: &str, value: &T) -> Result<()> {
         let Some(limit) = self.state.config().max_response_bytes() else {
             return Ok(());
@@ -428,7 +599,7 @@ impl SpreadsheetServer {
 
     #[tool(
         name = "range_values",
-        description = "Fetch raw values for specific ranges"
+        description = "Fetch values for specific ranges (raw, formatted or both via value_mode)"
     )]
     pub async fn range_values(
         &self,
@@ -445,6 +616,220 @@ impl SpreadsheetServer {
         .map_err(to_mcp_error)
     }
 
+    #[tool(
+        name = "sheet_comments",
+        description = "List cell notes and threaded comments (author, text, anchor cell, replies, resolved state). \
+Paged with limit/offset; omit sheet_name to cover every sheet."
+    )]
+    pub async fn sheet_comments(
+        &self,
+        Parameters(params): Parameters<tools::SheetCommentsParams>,
+    ) -> Result<Json<SheetCommentsResponse>, McpError> {
+        self.ensure_tool_enabled("sheet_comments")
+            .map_err(to_mcp_error)?;
+        self.run_tool_with_timeout(
+            "sheet_comments",
+            tools::sheet_comments(self.state.clone(), params),
+        )
+        .await
+        .map(Json)
+        .map_err(to_mcp_error)
+    }
+
+    #[tool(
+        name = "data_validations",
+        description = "List data-validation rules (dropdown lists, numeric/date/text-length bounds, custom formulas) \
+with their ranges, list sources (inline or range-backed, with entries) and input/error messages."
+    )]
+    pub async fn data_validations(
+        &self,
+        Parameters(params): Parameters<tools::DataValidationsParams>,
+    ) -> Result<Json<DataValidationsResponse>, McpError> {
+        self.ensure_tool_enabled("data_validations")
+            .map_err(to_mcp_error)?;
+        self.run_tool_with_timeout(
+            "data_validations",
+            tools::data_validations(self.state.clone(), params),
+        )
+        .await
+        .map(Json)
+        .map_err(to_mcp_error)
+    }
+
+    #[tool(
+        name = "find_validation_violations",
+        description = "Find cells whose current values break their data-validation rule (not in list, out of bounds, \
+wrong type or length). Rules that need a formula engine (custom formulas, computed lists) are listed as unchecked."
+    )]
+    pub async fn find_validation_violations(
+        &self,
+        Parameters(params): Parameters<tools::FindValidationViolationsParams>,
+    ) -> Result<Json<ValidationViolationsResponse>, McpError> {
+        self.ensure_tool_enabled("find_validation_violations")
+            .map_err(to_mcp_error)?;
+        self.run_tool_with_timeout(
+            "find_validation_violations",
+            tools::find_validation_violations(self.state.clone(), params),
+        )
+        .await
+        .map(Json)
+        .map_err(to_mcp_error)
+    }
+
+    #[tool(
+        name = "list_charts",
+        description = "List charts per sheet: anchor cells, type, title, axis titles and series with their \
+category/value ranges resolved to sheet ranges"
+    )]
+    pub async fn list_charts(
+        &self,
+        Parameters(params): Parameters<tools::charts::ListChartsParams>,
+    ) -> Result<Json<tools::charts::ListChartsResponse>, McpError> {
+        self.ensure_tool_enabled("list_charts")
+            .map_err(to_mcp_error)?;
+        self.run_tool_with_timeout(
+            "list_charts",
+            tools::charts::list_charts(self.state.clone(), params),
+        )
+        .await
+        .map(Json)
+        .map_err(to_mcp_error)
+    }
+
+    #[tool(
+        name = "chart_data",
+        description = "Plotted numbers and category labels for each series of one chart, read from the \
+referenced cells (falls back to the chart's cached points)"
+    )]
+    pub async fn chart_data(
+        &self,
+        Parameters(params): Parameters<tools::charts::ChartDataParams>,
+    ) -> Result<Json<tools::charts::ChartDataResponse>, McpError> {
+        self.ensure_tool_enabled("chart_data")
+            .map_err(to_mcp_error)?;
+        self.run_tool_with_timeout(
+            "chart_data",
+            tools::charts::chart_data(self.state.clone(), params),
+        )
+        .await
+        .map(Json)
+        .map_err(to_mcp_error)
+    }
+
+    #[tool(
+        name = "list_pivot_tables",
+        description = "List pivot tables per sheet: output location, source range or table, row/column/page \
+fields, value fields with their aggregation, filters and cache record count"
+    )]
+    pub async fn list_pivot_tables(
+        &self,
+        Parameters(params): Parameters<tools::pivots::ListPivotTablesParams>,
+    ) -> Result<Json<tools::pivots::ListPivotTablesResponse>, McpError> {
+        self.ensure_tool_enabled("list_pivot_tables")
+            .map_err(to_mcp_error)?;
+        self.run_tool_with_timeout(
+            "list_pivot_tables",
+            tools::pivots::list_pivot_tables(self.state.clone(), params),
+        )
+        .await
+        .map(Json)
+        .map_err(to_mcp_error)
+    }
+
+    #[tool(
+        name = "pivot_data",
+        description = "Figures of one pivot table recomputed from its pivot cache records (as of the last \
+refresh): one row per row/column item combination plus grand totals"
+    )]
+    pub async fn pivot_data(
+        &self,
+        Parameters(params): Parameters<tools::pivots::PivotDataParams>,
+    ) -> Result<Json<tools::pivots::PivotDataResponse>, McpError> {
+        self.ensure_tool_enabled("pivot_data")
+            .map_err(to_mcp_error)?;
+        self.run_tool_with_timeout(
+            "pivot_data",
+            tools::pivots::pivot_data(self.state.clone(), params),
+        )
+        .await
+        .map(Json)
+        .map_err(to_mcp_error)
+    }
+
+    #[tool(
+        name = "render_sheet",
+        description = "Render a range as PNG, SVG or HTML without LibreOffice: formatted values, fonts, fills, borders, \
+merged cells, column widths, row heights and conditional-format colors. Returns a file URI plus the image or markup. \
+Max range: 100 rows x 30 columns. Default: A1:M40."
+    )]
+    pub async fn render_sheet(
+        &self,
+        Parameters(params): Parameters<tools::render::RenderSheetParams>,
+    ) -> Result<rmcp::model::CallToolResult, McpError> {
+        use base64::Engine;
+        use rmcp::model::Content;
+        use tools::render::RenderFormat;
+
+        self.ensure_tool_enabled("render_sheet")
+            .map_err(to_mcp_error)?;
+
+        let result = async {
+            let format = params.format;
+            let response = self
+                .run_tool_with_timeout(
+                    "render_sheet",
+                    tools::render::render_sheet(self.state.clone(), params),
+                )
+                .await?;
+
+            let fs_path = response
+                .output_path
+                .strip_prefix("file://")
+                .ok_or_else(|| anyhow!("unexpected render output_path"))?;
+            let bytes = tokio::fs::read(fs_path)
+                .await
+                .map_err(|e| anyhow!("failed to read rendered output: {}", e))?;
+
+            if let Some(limit) = self.state.config().max_response_bytes() {
+                let body_len = match format {
+                    RenderFormat::Png => bytes.len().div_ceil(3) * 4,
+                    RenderFormat::Svg | RenderFormat::Html => bytes.len(),
+                };
+                let meta = serde_json::to_vec(&response)
+                    .map_err(|e| anyhow!("failed to serialize response: {}", e))?;
+                let estimated = body_len + meta.len() + response.output_path.len();
+                if estimated > limit {
+                    return Err(ResponseTooLargeError::new("render_sheet", estimated, limit).into());
+                }
+            }
+
+            let mut content = Vec::new();
+            match format {
+                RenderFormat::Png => {
+                    let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+                    content.push(Content::image(data, format.mime_type()));
+                }
+                RenderFormat::Svg | RenderFormat::Html => {
+                    content.push(Content::text(String::from_utf8_lossy(&bytes).into_owned()));
+                }
+            }
+            content.push(Content::text(response.output_path.clone()));
+
+            let structured_content = serde_json::to_value(&response)
+                .map_err(|e| anyhow!("failed to serialize response: {}", e))?;
+
+            Ok(rmcp::model::CallToolResult {
+                content,
+                structured_content: Some(structured_content),
+                is_error: Some(false),
+                meta: None,
+            })
+        }
+        .await;
+
+        result.map_err(to_mcp_error)
+    }
+
     #[tool(
         name = "sheet_statistics",
         description = "Get aggregated sheet statistics"
@@ -625,6 +1010,25 @@ impl SpreadsheetServer {
         .map(Json)
         .map_err(to_mcp_error)
     }
+
+    #[tool(
+        name = "diff_workbooks",
+        description = "Diff any two workbooks, forks or fork checkpoints. Same filters/paging as get_changeset; key_column aligns rows by a key column and columns by header instead of by address."
+    )]
+    pub async fn diff_workbooks(
+        &self,
+        Parameters(params): Parameters<tools::diff::DiffWorkbooksParams>,
+    ) -> Result<Json<tools::diff::DiffWorkbooksResponse>, McpError> {
+        self.ensure_tool_enabled("diff_workbooks")
+            .map_err(to_mcp_error)?;
+        self.run_tool_with_timeout(
+            "diff_workbooks",
+            tools::diff::diff_workbooks(self.state.clone(), params),
+        )
+        .await
+        .map(Json)
+        .map_err(to_mcp_error)
+    }
 }
 
 #[tool_router(router = vba_tool_router)]
@@ -833,12 +1237,17 @@ impl SpreadsheetServer {
     pub async fn sync_ggen_tool(
         &self,
         Parameters(params): Parameters<tools::ggen_sync::SyncGgenParams>,
+        context: RequestContext<RoleServer>,
     ) -> Result<Json<tools::ggen_sync::SyncGgenResponse>, McpError> {
         self.ensure_tool_enabled("sync_ggen")
             .map_err(to_mcp_error)?;
         self.run_tool_with_timeout(
             "sync_ggen",
-            tools::ggen_sync::sync_ggen(self.state.clone(), params),
+            tools::ggen_sync::sync_ggen_with_progress(
+                self.state.clone(),
+                params,
+                ProgressReporter::from_context(&context),
+            ),
         )
         .await
         .map(Json)
@@ -871,12 +1280,17 @@ impl SpreadsheetServer {
     pub async fn validate_definition_of_done_tool(
         &self,
         Parameters(params): Parameters<tools::dod::ValidateDefinitionOfDoneParams>,
+        context: RequestContext<RoleServer>,
     ) -> Result<Json<tools::dod::ValidateDefinitionOfDoneResponse>, McpError> {
         self.ensure_tool_enabled("validate_definition_of_done")
             .map_err(to_mcp_error)?;
         self.run_tool_with_timeout(
             "validate_definition_of_done",
-            tools::dod::validate_definition_of_done(self.state.clone(), params),
+            tools::dod::validate_definition_of_done_with_progress(
+                self.state.clone(),
+                params,
+                ProgressReporter::from_context(&context),
+            ),
         )
         .await
         .map(Json)
@@ -1075,12 +1489,17 @@ Mode: preview or apply (default apply)."
     pub async fn transform_batch(
         &self,
         Parameters(params): Parameters<tools::fork::TransformBatchParams>,
+        context: RequestContext<RoleServer>,
     ) -> Result<Json<tools::fork::TransformBatchResponse>, McpError> {
         self.ensure_recalc_enabled("transform_batch")
             .map_err(to_mcp_error)?;
         self.run_tool_with_timeout(
             "transform_batch",
-            tools::fork::transform_batch(self.state.clone(), params),
+            tools::fork::transform_batch_with_progress(
+                self.state.clone(),
+                params,
+                ProgressReporter::from_context(&context),
+            ),
         )
         .await
         .map(Json)
@@ -1107,6 +1526,111 @@ Mode: preview or apply (default apply). Op mode: merge (default), set, or clear.
         .map_err(to_mcp_error)
     }
 
+    #[tool(
+        name = "comment_batch",
+        description = "Add, reply to, edit, resolve or delete cell comments in a fork. \
+Ops: {sheet_name, address, kind: add|reply|edit|resolve|delete, text?, author?, threaded?, resolved?}. \
+Replying to or resolving a note turns it into a threaded comment. Mode: preview or apply (default apply)."
+    )]
+    pub async fn comment_batch(
+        &self,
+        Parameters(params): Parameters<tools::fork::CommentBatchParams>,
+    ) -> Result<Json<tools::fork::CommentBatchResponse>, McpError> {
+        self.ensure_recalc_enabled("comment_batch")
+            .map_err(to_mcp_error)?;
+        self.run_tool_with_timeout(
+            "comment_batch",
+            tools::fork::comment_batch(self.state.clone(), params),
+        )
+        .await
+        .map(Json)
+        .map_err(to_mcp_error)
+    }
+
+    #[tool(
+        name = "validation_batch",
+        description = "Add, modify or remove data-validation rules in a fork. \
+Ops: {sheet_name, target: range|region|cells, kind: add|modify|remove, rule?}. \
+Adding over cells that already have a rule replaces it there. Mode: preview or apply (default apply)."
+    )]
+    pub async fn validation_batch(
+        &self,
+        Parameters(params): Parameters<tools::fork::ValidationBatchParams>,
+    ) -> Result<Json<tools::fork::ValidationBatchResponse>, McpError> {
+        self.ensure_recalc_enabled("validation_batch")
+            .map_err(to_mcp_error)?;
+        self.run_tool_with_timeout(
+            "validation_batch",
+            tools::fork::validation_batch(self.state.clone(), params),
+        )
+        .await
+        .map(Json)
+        .map_err(to_mcp_error)
+    }
+
+    #[tool(
+        name = "conditional_format_batch",
+        description = "Add or clear conditional formatting in a fork. \
+Ops: {sheet_name, target: range|region|cells, kind: cell_is|expression|color_scale|data_bar|icon_set|top_n|duplicate|clear, style?}. \
+style is a StylePatch used as the differential format. Mode: preview or apply (default apply)."
+    )]
+    pub async fn conditional_format_batch(
+        &self,
+        Parameters(params): Parameters<tools::fork::ConditionalFormatBatchParams>,
+    ) -> Result<Json<tools::fork::ConditionalFormatBatchResponse>, McpError> {
+        self.ensure_recalc_enabled("conditional_format_batch")
+            .map_err(to_mcp_error)?;
+        self.run_tool_with_timeout(
+            "conditional_format_batch",
+            tools::fork::conditional_format_batch(self.state.clone(), params),
+        )
+        .await
+        .map(Json)
+        .map_err(to_mcp_error)
+    }
+
+    #[tool(
+        name = "chart_batch",
+        description = "Create, update or delete charts in a fork. \
+Ops: {kind: create|update|delete, sheet_name, ...}. create needs chart_type and either source or series; \
+update/delete take chart_index from list_charts. Mode: preview or apply (default apply)."
+    )]
+    pub async fn chart_batch(
+        &self,
+        Parameters(params): Parameters<tools::fork::ChartBatchParams>,
+    ) -> Result<Json<tools::fork::ChartBatchResponse>, McpError> {
+        self.ensure_recalc_enabled("chart_batch")
+            .map_err(to_mcp_error)?;
+        self.run_tool_with_timeout(
+            "chart_batch",
+            tools::fork::chart_batch(self.state.clone(), params),
+        )
+        .await
+        .map(Json)
+        .map_err(to_mcp_error)
+    }
+
+    #[tool(
+        name = "sheet_settings_batch",
+        description = "Sheet presentation and protection in a fork: freeze or split panes, hide/very-hide sheets, \
+tab color, zoom, gridlines, row/column outline groups and collapse state, sheet and workbook protection, \
+and locked/unlocked cells. Ops: {kind, sheet_name?, ...}. Mode: preview or apply (default apply)."
+    )]
+    pub async fn sheet_settings_batch(
+        &self,
+        Parameters(params): Parameters<tools::fork::SheetSettingsBatchParams>,
+    ) -> Result<Json<tools::fork::SheetSettingsBatchResponse>, McpError> {
+        self.ensure_recalc_enabled("sheet_settings_batch")
+            .map_err(to_mcp_error)?;
+        self.run_tool_with_timeout(
+            "sheet_settings_batch",
+            tools::fork::sheet_settings_batch(self.state.clone(), params),
+        )
+        .await
+        .map(Json)
+        .map_err(to_mcp_error)
+    }
+
     #[tool(
         name = "apply_formula_pattern",
         description = "Autofill-like formula pattern application over a target range in a fork. \
@@ -1131,9 +1655,13 @@ fill_direction: down, right, both (default both)."
 
     #[tool(
         name = "structure_batch",
-        description = "Apply structural edits to a fork (rows/cols/sheets). \
-Mode: preview or apply (default apply). Note: structural edits may not fully rewrite formulas/named ranges like Excel; \
-run recalculate and review get_changeset after applying."
+        description = "Apply structural edits to a fork (rows/cols/sheets/tables). \
+Mode: preview or apply (default apply). References are maintained like Excel: formulas, defined names, print areas, \
+tables, conditional formats, data validations, merges and autofilters follow inserted/deleted rows and columns, \
+moved ranges and renamed sheets; references to deleted sheets become #REF!. \
+Table ops: create_table, rename_table, resize_table, add_table_column, remove_table_column, set_table_totals, \
+set_table_style; structured references follow renamed tables and turn into #REF! for removed columns. \
+The response lists every rewrite. Run recalculate after applying."
     )]
     pub async fn structure_batch(
         &self,
@@ -1185,19 +1713,43 @@ run recalculate and review get_changeset after applying."
         .map_err(to_mcp_error)
     }
 
+    #[tool(
+        name = "merge_forks",
+        description = "Three-way merge of fork_b into fork_a against their shared base. Applies non-overlapping changes, reports per-cell conflicts with both values; policy prefer_a, prefer_b or stage (conflicts staged on fork_a)."
+    )]
+    pub async fn merge_forks(
+        &self,
+        Parameters(params): Parameters<tools::merge::MergeForksParams>,
+    ) -> Result<Json<tools::merge::MergeForksResponse>, McpError> {
+        self.ensure_recalc_enabled("merge_forks")
+            .map_err(to_mcp_error)?;
+        self.run_tool_with_timeout(
+            "merge_forks",
+            tools::merge::merge_forks(self.state.clone(), params),
+        )
+        .await
+        .map(Json)
+        .map_err(to_mcp_error)
+    }
+
     #[tool(
         name = "recalculate",
-        description = "Recalculate all formulas in a fork using LibreOffice"
+        description = "Recalculate all formulas in a fork (LibreOffice or native evaluator). Cells that cannot be evaluated are listed under unsupported and keep their previous values."
     )]
     pub async fn recalculate(
         &self,
         Parameters(params): Parameters<tools::fork::RecalculateParams>,
+        context: RequestContext<RoleServer>,
     ) -> Result<Json<tools::fork::RecalculateResponse>, McpError> {
         self.ensure_recalc_enabled("recalculate")
             .map_err(to_mcp_error)?;
         self.run_tool_with_timeout(
             "recalculate",
-            tools::fork::recalculate(self.state.clone(), params),
+            tools::fork::recalculate_with_progress(
+                self.state.clone(),
+                params,
+                ProgressReporter::from_context(&context),
+            ),
         )
         .await
         .map(Json)
@@ -1561,7 +2113,6 @@ Token-efficient (250 token savings). Operation-based dispatch."
     }
 }
 
-#[tool_handler(router = self.tool_router)]
 impl ServerHandler for SpreadsheetServer {
     fn get_info(&self) -> ServerInfo {
         let recalc_enabled = {
@@ -1578,12 +2129,196 @@ impl ServerHandler for SpreadsheetServer {
         let vba_enabled = self.state.config().vba_enabled;
 
         ServerInfo {
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .enable_prompts()
+                .enable_completions()
+                .build(),
             server_info: Implementation::from_build_env(),
             instructions: Some(build_instructions(recalc_enabled, vba_enabled)),
             ..ServerInfo::default()
         }
     }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let (server, principal) = self.scoped(&context)?;
+        if let Some(principal) = &principal
+            && !principal.may_call(&request.name)
+        {
+            return Err(to_mcp_error(
+                AccessDeniedError {
+                    principal: principal.name.clone(),
+                    tool: request.name.to_string(),
+                }
+                .into(),
+            ));
+        }
+        let call = server
+            .tool_router
+            .call(ToolCallContext::new(&server, request, context));
+        audit::scope_principal(Self::principal_name(&principal), call).await
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        let (server, principal) = self.scoped(&context)?;
+        let mut tools = server.tool_router.list_all();
+        if principal.is_some() {
+            let config = server.state.config();
+            tools.retain(|tool| config.is_tool_enabled(&tool.name));
+        }
+        Ok(ListToolsResult::with_all_items(tools))
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let (server, _) = self.scoped(&context)?;
+        let listed = resources::list_resources(&server.state).map_err(to_mcp_error)?;
+        Ok(ListResourcesResult::with_all_items(listed))
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, McpError> {
+        Ok(ListResourceTemplatesResult::with_all_items(
+            resources::resource_templates(),
+        ))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let (server, principal) = self.scoped(&context)?;
+        let uri = ResourceUri::parse(&request.uri)
+            .map_err(|error| McpError::invalid_params(error.to_string(), None))?;
+        let text = audit::scope_principal(
+            Self::principal_name(&principal),
+            server.read_resource_text(&uri),
+        )
+        .await
+        .map_err(to_mcp_error)?;
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::text(text, request.uri)],
+        })
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        let (server, _) = self.scoped(&context)?;
+        self.resources
+            .subscribe(server.state.clone(), context.peer.clone(), &request.uri)
+            .await
+            .map_err(to_mcp_error)
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.resources.unsubscribe(&request.uri);
+        Ok(())
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        let (server, _) = self.scoped(&context)?;
+        Ok(ListPromptsResult::with_all_items(prompts::list_prompts(
+            &server.state.config(),
+        )))
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        let (server, _) = self.scoped(&context)?;
+        let arguments: HashMap<String, String> = request
+            .arguments
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, value)| match value {
+                serde_json::Value::String(text) => (key, text),
+                other => (key, other.to_string()),
+            })
+            .collect();
+        let rendered = prompts::render_prompt(&server.state, &request.name, &arguments)
+            .await
+            .map_err(|error| McpError::invalid_params(error.to_string(), None))?;
+        Ok(GetPromptResult {
+            description: Some(rendered.description),
+            messages: vec![PromptMessage::new_text(
+                PromptMessageRole::User,
+                rendered.text,
+            )],
+        })
+    }
+
+    async fn complete(
+        &self,
+        request: CompleteRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CompleteResult, McpError> {
+        const MAX_COMPLETIONS: usize = 100;
+
+        let (server, _) = self.scoped(&context)?;
+        let context = request
+            .context
+            .and_then(|context| context.arguments)
+            .unwrap_or_default();
+        let mut values = match &request.r#ref {
+            Reference::Prompt(prompt) => prompts::complete_argument(
+                &server.state,
+                &prompt.name,
+                &request.argument.name,
+                &request.argument.value,
+                &context,
+            )
+            .await
+            .map_err(|error| McpError::invalid_params(error.to_string(), None))?,
+            Reference::Resource(resource) => completion::complete_resource(
+                &server.state,
+                &resource.uri,
+                &request.argument.name,
+                &request.argument.value,
+                &context,
+            )
+            .await
+            .map_err(|error| McpError::invalid_params(error.to_string(), None))?,
+        };
+        let total = values.len();
+        values.truncate(MAX_COMPLETIONS);
+        Ok(CompleteResult {
+            completion: CompletionInfo {
+                values,
+                total: Some(total as u32),
+                has_more: Some(total > MAX_COMPLETIONS),
+            },
+        })
+    }
 }
 
 fn to_mcp_error(error: anyhow::Error) -> McpError {
@@ -1625,6 +2360,22 @@ fn to_mcp_error(error: anyhow::Error) -> McpError {
         return to_rmcp_error(custom_error);
     }
 
+    if let Some(denied) = error.downcast_ref::<AccessDeniedError>() {
+        let custom_error = CustomMcpError::builder(ErrorCode::PermissionDenied)
+            .message(denied.to_string())
+            .operation(&denied.tool)
+            .suggestion("Ask the server operator to grant the tool to this identity")
+            .build_and_track();
+        return to_rmcp_error(custom_error);
+    }
+
+    if error.downcast_ref::<CancelledError>().is_some() {
+        let custom_error = CustomMcpError::builder(ErrorCode::RequestCancelled)
+            .message("Request cancelled by client; partial changes were rolled back")
+            .build_and_track();
+        return to_rmcp_error(custom_error);
+    }
+
     #[cfg(feature = "recalc")]
     if error.downcast_ref::<RecalcDisabledError>().is_some() {
         let custom_error = CustomMcpError::builder(ErrorCode::ToolDisabled)
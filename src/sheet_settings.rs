@@ -0,0 +1,550 @@
+//! Sheet-level presentation and protection: visibility, tab color, panes,
+//! zoom, gridlines, outline groups and protection, read from an xlsx package.
+//!
+//! The sheet XML is streamed with cell contents skipped; only row and column
+//! attributes and the style index of each cell are looked at. Styles are
+//! consulted for the cell formats that clear the `locked` flag, so unlocked
+//! ranges can be reported alongside the sheet's protection.
+
+use crate::backends::is_ooxml;
+use crate::charts::XmlNode;
+use crate::comments::{read_string, sheet_parts};
+use crate::styles::compress_positions_to_ranges;
+use crate::utils::column_number_to_name;
+use anyhow::Result;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::path::Path;
+use zip::ZipArchive;
+
+/// Unlocked ranges listed per sheet before the list is cut short.
+const UNLOCKED_RANGE_LIMIT: usize = 50;
+
+/// Actions a protected sheet can permit, with their `sheetProtection`
+/// attribute. The editing actions are blocked unless the attribute is `0`;
+/// the two selection actions are permitted unless it is `1`.
+pub(crate) const PROTECTION_ACTIONS: &[(&str, &str)] = &[
+    ("select_locked_cells", "selectLockedCells"),
+    ("select_unlocked_cells", "selectUnlockedCells"),
+    ("format_cells", "formatCells"),
+    ("format_columns", "formatColumns"),
+    ("format_rows", "formatRows"),
+    ("insert_columns", "insertColumns"),
+    ("insert_rows", "insertRows"),
+    ("insert_hyperlinks", "insertHyperlinks"),
+    ("delete_columns", "deleteColumns"),
+    ("delete_rows", "deleteRows"),
+    ("sort", "sort"),
+    ("auto_filter", "autoFilter"),
+    ("pivot_tables", "pivotTables"),
+];
+
+pub(crate) fn is_selection_action(attr: &str) -> bool {
+    attr.starts_with("select")
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FrozenPane {
+    pub rows: u32,
+    pub cols: u32,
+    pub top_left_cell: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SplitPane {
+    /// Distance of the vertical split from the left edge, in points.
+    pub x_points: f64,
+    /// Distance of the horizontal split from the top edge, in points.
+    pub y_points: f64,
+    pub top_left_cell: Option<String>,
+}
+
+/// A run of rows (`5:9`) or columns (`C:E`) at or above an outline level.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct OutlineGroup {
+    pub range: String,
+    pub level: u8,
+    pub collapsed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SheetProtectionInfo {
+    pub password_set: bool,
+    /// Actions still permitted, e.g. `select_unlocked_cells`, `format_cells`, `sort`.
+    pub allowed: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SheetSettings {
+    /// visible | hidden | very_hidden
+    pub state: String,
+    /// ARGB hex, or `theme:<index>` for theme colors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab_color: Option<String>,
+    /// Zoom percentage.
+    pub zoom: u32,
+    pub show_gridlines: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frozen: Option<FrozenPane>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split: Option<SplitPane>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub row_groups: Vec<OutlineGroup>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub column_groups: Vec<OutlineGroup>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protection: Option<SheetProtectionInfo>,
+    /// Cells whose format clears the `locked` flag; editable once the sheet
+    /// is protected. Cells never written are not listed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unlocked_ranges: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unlocked_ranges_truncated: bool,
+}
+
+impl Default for SheetSettings {
+    fn default() -> Self {
+        Self {
+            state: "visible".to_string(),
+            tab_color: None,
+            zoom: 100,
+            show_gridlines: true,
+            frozen: None,
+            split: None,
+            row_groups: Vec::new(),
+            column_groups: Vec::new(),
+            protection: None,
+            unlocked_ranges: Vec::new(),
+            unlocked_ranges_truncated: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct WorkbookProtectionInfo {
+    /// Sheets cannot be added, removed, renamed, moved or unhidden.
+    pub lock_structure: bool,
+    pub lock_windows: bool,
+    pub password_set: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorkbookSettings {
+    pub protection: Option<WorkbookProtectionInfo>,
+    pub sheets: Vec<(String, SheetSettings)>,
+}
+
+/// Workbook protection alone, without reading any sheet.
+pub fn read_workbook_protection(path: &Path) -> Result<Option<WorkbookProtectionInfo>> {
+    if !is_ooxml(path) {
+        return Ok(None);
+    }
+    let mut zip = ZipArchive::new(File::open(path)?)?;
+    Ok(read_workbook_part(&mut zip)?.0)
+}
+
+/// Settings of every sheet in workbook order, or of `sheet_name` alone.
+/// Packages other than xlsx/xlsm report nothing.
+pub fn read_workbook_settings(path: &Path, sheet_name: Option<&str>) -> Result<WorkbookSettings> {
+    if !is_ooxml(path) {
+        return Ok(WorkbookSettings::default());
+    }
+    let mut zip = ZipArchive::new(File::open(path)?)?;
+    let (protection, states) = read_workbook_part(&mut zip)?;
+    let mut settings = WorkbookSettings {
+        protection,
+        sheets: Vec::new(),
+    };
+    let unlocked_xfs = match read_string(&mut zip, "xl/styles.xml")? {
+        Some(xml) => unlocked_formats(&XmlNode::parse(&xml)?),
+        None => HashSet::new(),
+    };
+    for (name, sheet_path) in sheet_parts(&mut zip)? {
+        if sheet_name.is_some_and(|wanted| wanted != name) {
+            continue;
+        }
+        let mut sheet = match read_string(&mut zip, &sheet_path)? {
+            Some(xml) => parse_sheet_settings(&xml, &unlocked_xfs)?,
+            None => SheetSettings::default(),
+        };
+        if let Some(state) = states.get(&name) {
+            sheet.state = state.clone();
+        }
+        settings.sheets.push((name, sheet));
+    }
+    Ok(settings)
+}
+
+/// Workbook protection and the state of every sheet that is not visible.
+fn read_workbook_part(
+    zip: &mut ZipArchive<File>,
+) -> Result<(Option<WorkbookProtectionInfo>, BTreeMap<String, String>)> {
+    let mut states = BTreeMap::new();
+    let Some(xml) = read_string(zip, "xl/workbook.xml")? else {
+        return Ok((None, states));
+    };
+    let root = XmlNode::parse(&xml)?;
+    let Some(workbook) = root.child("workbook") else {
+        return Ok((None, states));
+    };
+    if let Some(sheets) = workbook.child("sheets") {
+        for sheet in sheets.children_named("sheet") {
+            if let (Some(name), Some(state)) = (sheet.attr("name"), sheet.attr("state")) {
+                states.insert(name.to_string(), state_name(state));
+            }
+        }
+    }
+    Ok((
+        workbook
+            .child("workbookProtection")
+            .map(workbook_protection),
+        states,
+    ))
+}
+
+fn state_name(state: &str) -> String {
+    match state {
+        "veryHidden" => "very_hidden".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn flag(value: Option<&str>) -> Option<bool> {
+    value.map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
+fn workbook_protection(node: &XmlNode) -> WorkbookProtectionInfo {
+    WorkbookProtectionInfo {
+        lock_structure: flag(node.attr("lockStructure")).unwrap_or(false),
+        lock_windows: flag(node.attr("lockWindows")).unwrap_or(false),
+        password_set: node.attr("workbookPassword").is_some()
+            || node.attr("workbookHashValue").is_some(),
+    }
+}
+
+/// Indexes into `cellXfs` of formats whose protection clears `locked`.
+pub(crate) fn unlocked_formats(styles: &XmlNode) -> HashSet<u32> {
+    let Some(xfs) = styles
+        .child("styleSheet")
+        .and_then(|sheet| sheet.child("cellXfs"))
+    else {
+        return HashSet::new();
+    };
+    xfs.children_named("xf")
+        .enumerate()
+        .filter(|(_, xf)| {
+            xf.child("protection")
+                .is_some_and(|p| flag(p.attr("locked")) == Some(false))
+        })
+        .map(|(idx, _)| idx as u32)
+        .collect()
+}
+
+fn attrs(e: &BytesStart) -> Result<BTreeMap<String, String>> {
+    let mut out = BTreeMap::new();
+    for attr in e.attributes() {
+        let attr = attr?;
+        out.insert(
+            String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string(),
+            attr.unescape_value()?.to_string(),
+        );
+    }
+    Ok(out)
+}
+
+/// Column and row of an `A1` reference.
+pub(crate) fn split_cell_ref(reference: &str) -> Option<(u32, u32)> {
+    let letters = reference
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(reference.len());
+    if letters == 0 {
+        return None;
+    }
+    let col = reference[..letters].bytes().try_fold(0u32, |acc, b| {
+        acc.checked_mul(26)?
+            .checked_add(u32::from(b.to_ascii_uppercase().checked_sub(b'A')?) + 1)
+    })?;
+    let row = reference[letters..].parse().ok()?;
+    Some((col, row))
+}
+
+#[derive(Default)]
+struct OutlineEntry {
+    level: u8,
+    hidden: bool,
+}
+
+pub(crate) fn parse_sheet_settings(
+    xml: &str,
+    unlocked_xfs: &HashSet<u32>,
+) -> Result<SheetSettings> {
+    let mut settings = SheetSettings::default();
+    let mut reader = Reader::from_str(xml);
+    let mut rows: BTreeMap<u32, OutlineEntry> = BTreeMap::new();
+    let mut cols: BTreeMap<u32, OutlineEntry> = BTreeMap::new();
+    let mut unlocked_cells: Vec<(u32, u32)> = Vec::new();
+    let mut unlocked_cols: Vec<(u32, u32)> = Vec::new();
+    let mut unlocked_rows: Vec<u32> = Vec::new();
+    let mut in_first_view = false;
+    let mut seen_view = false;
+    let mut row = 0u32;
+    let mut col = 0u32;
+
+    loop {
+        let event = reader.read_event()?;
+        let is_start = matches!(event, Event::Start(_));
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => match e.local_name().as_ref() {
+                b"tabColor" => {
+                    let a = attrs(e)?;
+                    settings.tab_color = a
+                        .get("rgb")
+                        .cloned()
+                        .or_else(|| a.get("theme").map(|t| format!("theme:{t}")));
+                }
+                b"sheetView" if !seen_view => {
+                    seen_view = true;
+                    in_first_view = is_start;
+                    let a = attrs(e)?;
+                    if let Some(zoom) = a.get("zoomScale").and_then(|z| z.parse().ok()) {
+                        settings.zoom = zoom;
+                    }
+                    settings.show_gridlines =
+                        flag(a.get("showGridLines").map(String::as_str)).unwrap_or(true);
+                }
+                b"pane" if in_first_view => {
+                    let a = attrs(e)?;
+                    let split = |key: &str| -> f64 {
+                        a.get(key).and_then(|v| v.parse().ok()).unwrap_or(0.0)
+                    };
+                    let top_left_cell = a.get("topLeftCell").cloned();
+                    match a.get("state").map(String::as_str) {
+                        Some("frozen" | "frozenSplit") => {
+                            settings.frozen = Some(FrozenPane {
+                                rows: split("ySplit") as u32,
+                                cols: split("xSplit") as u32,
+                                top_left_cell,
+                            })
+                        }
+                        _ => {
+                            settings.split = Some(SplitPane {
+                                x_points: split("xSplit") / 20.0,
+                                y_points: split("ySplit") / 20.0,
+                                top_left_cell,
+                            })
+                        }
+                    }
+                }
+                b"col" => {
+                    let a = attrs(e)?;
+                    let number = |key: &str| a.get(key).and_then(|v| v.parse::<u32>().ok());
+                    let (Some(min), Some(max)) = (number("min"), number("max")) else {
+                        continue;
+                    };
+                    let level = a
+                        .get("outlineLevel")
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(0);
+                    let hidden = flag(a.get("hidden").map(String::as_str)).unwrap_or(false);
+                    if level > 0 {
+                        for c in min..=max {
+                            cols.insert(c, OutlineEntry { level, hidden });
+                        }
+                    }
+                    if number("style").is_some_and(|s| unlocked_xfs.contains(&s)) {
+                        unlocked_cols.push((min, max));
+                    }
+                }
+                b"row" => {
+                    let a = attrs(e)?;
+                    row = a.get("r").and_then(|v| v.parse().ok()).unwrap_or(row + 1);
+                    col = 0;
+                    let level = a
+                        .get("outlineLevel")
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(0);
+                    if level > 0 {
+                        let hidden = flag(a.get("hidden").map(String::as_str)).unwrap_or(false);
+                        rows.insert(row, OutlineEntry { level, hidden });
+                    }
+                    if flag(a.get("customFormat").map(String::as_str)) == Some(true)
+                        && a.get("s")
+                            .and_then(|s| s.parse().ok())
+                            .is_some_and(|s| unlocked_xfs.contains(&s))
+                    {
+                        unlocked_rows.push(row);
+                    }
+                }
+                b"c" => {
+                    let a = attrs(e)?;
+                    col = a
+                        .get("r")
+                        .and_then(|r| split_cell_ref(r))
+                        .map(|(c, _)| c)
+                        .unwrap_or(col + 1);
+                    if a.get("s")
+                        .and_then(|s| s.parse().ok())
+                        .is_some_and(|s| unlocked_xfs.contains(&s))
+                    {
+                        unlocked_cells.push((row, col));
+                    }
+                    if is_start {
+                        reader.read_to_end(e.name())?;
+                    }
+                }
+                b"sheetProtection" => {
+                    let a = attrs(e)?;
+                    if flag(a.get("sheet").map(String::as_str)) == Some(true) {
+                        let allowed = PROTECTION_ACTIONS
+                            .iter()
+                            .filter(|(_, attr)| {
+                                let value = flag(a.get(*attr).map(String::as_str));
+                                if is_selection_action(attr) {
+                                    value != Some(true)
+                                } else {
+                                    value == Some(false)
+                                }
+                            })
+                            .map(|(name, _)| name.to_string())
+                            .collect();
+                        settings.protection = Some(SheetProtectionInfo {
+                            password_set: a.contains_key("password") || a.contains_key("hashValue"),
+                            allowed,
+                        });
+                    }
+                }
+                _ => {}
+            },
+            Event::End(ref e) if e.local_name().as_ref() == b"sheetView" => {
+                in_first_view = false;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    settings.row_groups = outline_groups(&rows, |r| r.to_string());
+    settings.column_groups = outline_groups(&cols, column_number_to_name);
+    // Whole columns and rows first, then the cells outside them.
+    unlocked_cells.retain(|(r, c)| {
+        !unlocked_rows.contains(r)
+            && !unlocked_cols
+                .iter()
+                .any(|(min, max)| (*min..=*max).contains(c))
+    });
+    let (cell_ranges, truncated) =
+        compress_positions_to_ranges(&unlocked_cells, UNLOCKED_RANGE_LIMIT);
+    let mut row_runs: Vec<(u32, u32)> = Vec::new();
+    for r in unlocked_rows {
+        match row_runs.last_mut() {
+            Some((_, end)) if *end + 1 == r => *end = r,
+            _ => row_runs.push((r, r)),
+        }
+    }
+    let mut ranges: Vec<String> = unlocked_cols
+        .iter()
+        .map(|(min, max)| {
+            format!(
+                "{}:{}",
+                column_number_to_name(*min),
+                column_number_to_name(*max)
+            )
+        })
+        .chain(row_runs.iter().map(|(start, end)| format!("{start}:{end}")))
+        .collect();
+    ranges.extend(cell_ranges);
+    settings.unlocked_ranges_truncated = truncated || ranges.len() > UNLOCKED_RANGE_LIMIT;
+    ranges.truncate(UNLOCKED_RANGE_LIMIT);
+    settings.unlocked_ranges = ranges;
+    Ok(settings)
+}
+
+/// Runs of consecutive rows/columns at each outline level, outermost first.
+/// A group counts as collapsed when all of its members are hidden.
+fn outline_groups(
+    entries: &BTreeMap<u32, OutlineEntry>,
+    label: impl Fn(u32) -> String,
+) -> Vec<OutlineGroup> {
+    let max_level = entries.values().map(|e| e.level).max().unwrap_or(0);
+    let mut groups = Vec::new();
+    for level in 1..=max_level {
+        let mut run: Option<(u32, u32, bool)> = None;
+        let mut flush = |run: &mut Option<(u32, u32, bool)>| {
+            if let Some((start, end, hidden)) = run.take() {
+                groups.push(OutlineGroup {
+                    range: format!("{}:{}", label(start), label(end)),
+                    level,
+                    collapsed: hidden,
+                });
+            }
+        };
+        for (&idx, entry) in entries.iter().filter(|(_, e)| e.level >= level) {
+            match run.as_mut() {
+                Some((_, end, hidden)) if *end + 1 == idx => {
+                    *end = idx;
+                    *hidden &= entry.hidden;
+                }
+                _ => {
+                    flush(&mut run);
+                    run = Some((idx, idx, entry.hidden));
+                }
+            }
+        }
+        flush(&mut run);
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_views_outlines_and_protection() {
+        let xml = r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+  <sheetPr><tabColor rgb="FF00B050"/></sheetPr>
+  <sheetViews><sheetView showGridLines="0" zoomScale="85" workbookViewId="0">
+    <pane xSplit="1" ySplit="2" topLeftCell="B3" activePane="bottomRight" state="frozen"/>
+  </sheetView></sheetViews>
+  <cols><col min="3" max="4" width="9" outlineLevel="1" hidden="1"/><col min="6" max="6" width="9" style="1"/></cols>
+  <sheetData>
+    <row r="2"><c r="A2" s="1"><v>1</v></c><c r="B2" s="1"/><c r="C2"><f>A2</f><v>1</v></c></row>
+    <row r="3" outlineLevel="1"><c r="B3" s="1"/></row>
+    <row r="4" outlineLevel="2"/>
+    <row r="5" outlineLevel="1"/>
+  </sheetData>
+  <sheetProtection sheet="1" objects="1" scenarios="1" password="83AF" formatCells="0" selectLockedCells="1"/>
+</worksheet>"#;
+        let settings = parse_sheet_settings(xml, &HashSet::from([1])).unwrap();
+        assert_eq!(settings.tab_color.as_deref(), Some("FF00B050"));
+        assert_eq!(settings.zoom, 85);
+        assert!(!settings.show_gridlines);
+        assert_eq!(
+            settings.frozen,
+            Some(FrozenPane {
+                rows: 2,
+                cols: 1,
+                top_left_cell: Some("B3".into()),
+            })
+        );
+        let rows: Vec<_> = settings
+            .row_groups
+            .iter()
+            .map(|g| (g.range.as_str(), g.level, g.collapsed))
+            .collect();
+        assert_eq!(rows, vec![("3:5", 1, false), ("4:4", 2, false)]);
+        assert_eq!(settings.column_groups[0].range, "C:D");
+        assert!(settings.column_groups[0].collapsed);
+        let protection = settings.protection.unwrap();
+        assert!(protection.password_set);
+        assert_eq!(
+            protection.allowed,
+            vec!["select_unlocked_cells", "format_cells"]
+        );
+        assert_eq!(settings.unlocked_ranges, vec!["F:F", "A2:B2", "B3"]);
+    }
+}
@@ -1,12 +1,13 @@
-use crate::config::ServerConfig;
+use crate::config::{RecalcBackendKind, ServerConfig};
 #[cfg(feature = "recalc")]
 use crate::fork::{ForkConfig, ForkRegistry};
 use crate::model::{WorkbookId, WorkbookListResponse};
 use crate::ontology::{CacheStats as OntologyCacheStats, OntologyCache, QueryCache};
+use crate::progress::ProgressReporter;
 #[cfg(feature = "recalc")]
 use crate::recalc::{
-    GlobalRecalcLock, GlobalScreenshotLock, LibreOfficeBackend, RecalcBackend, RecalcConfig,
-    create_executor,
+    ExecutorStrategy, GlobalRecalcLock, GlobalScreenshotLock, LibreOfficeBackend, NativeBackend,
+    RecalcBackend, RecalcConfig, create_executor,
 };
 use crate::sparql::cache::{CacheConfig as QueryCacheConfig, QueryResultCache};
 use crate::tools::filters::WorkbookFilter;
@@ -85,6 +86,20 @@ impl Default for CacheWarmingConfig {
 
 impl AppState {
     pub fn new(config: Arc<ServerConfig>) -> Self {
+        Self::build(config, None)
+    }
+
+    /// State for a narrower config (such as an HTTP identity's) that shares
+    /// this state's recalc backend and locks, so soffice processes and
+    /// `max_concurrent_recalcs` stay server-wide. Forks stay per state.
+    pub fn scoped(&self, config: Arc<ServerConfig>) -> Self {
+        Self::build(config, Some(self))
+    }
+
+    fn build(config: Arc<ServerConfig>, shared: Option<&AppState>) -> Self {
+        #[cfg(not(feature = "recalc"))]
+        let _ = shared;
+
         // TPS: Fail-fast with descriptive error if cache capacity is invalid
         // Using .max(1) ensures value is at least 1, so unwrap is safe, but expect provides better error message
         let capacity = NonZeroUsize::new(config.cache_capacity.max(1))
@@ -126,7 +141,14 @@ impl AppState {
         #[cfg(feature = "recalc")]
         let (fork_registry, recalc_backend, recalc_semaphore, screenshot_semaphore) =
             if config.recalc_enabled {
-                let fork_config = ForkConfig::default();
+                let fork_config = match &config.fork_store_dir {
+                    Some(dir) => ForkConfig {
+                        fork_dir: dir.clone(),
+                        durable: true,
+                        ..ForkConfig::default()
+                    },
+                    None => ForkConfig::default(),
+                };
                 let registry = ForkRegistry::new(fork_config)
                     .map(Arc::new)
                     .map_err(|e| tracing::warn!("failed to init fork registry: {}", e))
@@ -136,18 +158,23 @@ impl AppState {
                     registry.clone().start_cleanup_task();
                 }
 
-                let executor = create_executor(&RecalcConfig::default());
-                let backend: Arc<dyn RecalcBackend> = Arc::new(LibreOfficeBackend::new(executor));
-                let backend = if backend.is_available() {
-                    Some(backend)
-                } else {
-                    tracing::warn!("recalc backend not available (soffice not found)");
-                    None
+                // Scoped states reuse the parent's soffice processes and locks
+                let shared_recalc = shared.and_then(|parent| {
+                    Some((
+                        parent.recalc_backend.clone(),
+                        parent.recalc_semaphore.clone()?,
+                        parent.screenshot_semaphore.clone()?,
+                    ))
+                });
+                let (backend, semaphore, screenshot_semaphore) = match shared_recalc {
+                    Some(shared) => shared,
+                    None => (
+                        create_recalc_backend(&config),
+                        GlobalRecalcLock::new(config.max_concurrent_recalcs),
+                        GlobalScreenshotLock::new(),
+                    ),
                 };
 
-                let semaphore = GlobalRecalcLock::new(config.max_concurrent_recalcs);
-                let screenshot_semaphore = GlobalScreenshotLock::new();
-
                 (
                     registry,
                     backend,
@@ -503,6 +530,17 @@ impl AppState {
     /// Warm up the cache by pre-loading frequently used workbooks
     /// This eliminates cold-start latency for common operations
     pub async fn warm_cache(&self, config: CacheWarmingConfig) -> Result<CacheWarmingResult> {
+        self.warm_cache_with_progress(config, &ProgressReporter::none())
+            .await
+    }
+
+    /// Warm the cache, reporting progress per workbook and stopping between
+    /// workbooks on cancellation (loaded workbooks simply stay cached)
+    pub async fn warm_cache_with_progress(
+        &self,
+        config: CacheWarmingConfig,
+        progress: &ProgressReporter,
+    ) -> Result<CacheWarmingResult> {
         if !config.enabled {
             debug!("cache warming disabled");
             return Ok(CacheWarmingResult::default());
@@ -527,7 +565,11 @@ impl AppState {
         let mut failed = 0;
         let mut errors = Vec::new();
 
-        for workbook_id in workbooks_to_warm.iter().take(config.max_workbooks) {
+        let total = workbooks_to_warm.len().min(config.max_workbooks);
+        for (index, workbook_id) in workbooks_to_warm.iter().take(total).enumerate() {
+            progress.check_cancelled()?;
+            progress.report(index, total, format!("loading {workbook_id}"));
+
             // Check timeout
             if start_time.elapsed().as_secs() >= config.timeout_secs {
                 warn!(
@@ -625,6 +667,36 @@ impl AppState {
     }
 }
 
+#[cfg(feature = "recalc")]
+fn create_recalc_backend(config: &ServerConfig) -> Option<Arc<dyn RecalcBackend>> {
+    match config.recalc_backend {
+        RecalcBackendKind::Native => Some(Arc::new(NativeBackend::new())),
+        RecalcBackendKind::Libreoffice | RecalcBackendKind::Auto => {
+            let strategy = if config.recalc_pooled {
+                ExecutorStrategy::Pooled
+            } else {
+                ExecutorStrategy::FireAndForget
+            };
+            // Pool slots match the GlobalRecalcLock permits.
+            let executor = create_executor(&RecalcConfig {
+                strategy,
+                pool_size: config.max_concurrent_recalcs,
+                ..RecalcConfig::default()
+            });
+            let libreoffice: Arc<dyn RecalcBackend> = Arc::new(LibreOfficeBackend::new(executor));
+            if libreoffice.is_available() {
+                Some(libreoffice)
+            } else if config.recalc_backend == RecalcBackendKind::Auto {
+                tracing::warn!("soffice not found; falling back to native formula evaluator");
+                Some(Arc::new(NativeBackend::new()))
+            } else {
+                tracing::warn!("recalc backend not available (soffice not found)");
+                None
+            }
+        }
+    }
+}
+
 struct LocatedWorkbook {
     workbook_id: WorkbookId,
     short_id: String,
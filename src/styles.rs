@@ -362,6 +362,13 @@ pub fn apply_style_patch(current: &Style, patch: &StylePatch, mode: StylePatchMo
     }
 }
 
+/// Build a fresh `Style` carrying exactly the formatting in `desc`.
+pub fn style_from_descriptor(desc: &StyleDescriptor) -> Style {
+    let mut style = Style::default();
+    apply_descriptor_to_style(&mut style, desc);
+    style
+}
+
 fn merge_style_patch(desc: &mut StyleDescriptor, patch: &StylePatch) {
     if let Some(font_patch) = &patch.font {
         match font_patch {
@@ -0,0 +1,354 @@
+use crate::charts::{self, CachedRef, ChartDef, SeriesDef};
+use crate::data_validation::Area;
+use crate::model::WorkbookId;
+use crate::number_format::cell_display_text;
+use crate::state::AppState;
+use anyhow::{Result, anyhow};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use umya_spreadsheet::{Spreadsheet, Worksheet};
+
+const DEFAULT_MAX_POINTS: u32 = 1000;
+const MAX_POINTS_LIMIT: u32 = 10_000;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListChartsParams {
+    #[serde(alias = "workbook_id")]
+    pub workbook_or_fork_id: WorkbookId,
+    /// Limit to one sheet; all sheets when omitted.
+    #[serde(default)]
+    pub sheet_name: Option<String>,
+}
+
+fn default_max_points() -> u32 {
+    DEFAULT_MAX_POINTS
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ChartDataParams {
+    #[serde(alias = "workbook_id")]
+    pub workbook_or_fork_id: WorkbookId,
+    pub sheet_name: String,
+    /// Position of the chart on its sheet, as reported by list_charts.
+    #[serde(default)]
+    pub chart_index: u32,
+    /// Points returned per series (1..=10000).
+    #[serde(default = "default_max_points")]
+    pub max_points: u32,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ChartAnchor {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ChartAxisInfo {
+    /// category | value | date | series
+    pub kind: String,
+    pub position: Option<String>,
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub deleted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ChartRangeRef {
+    /// The reference as written in the chart, e.g. `Data!$B$2:$B$9`.
+    pub formula: String,
+    /// Resolved areas as `Sheet!A1:B2`; empty when the reference points
+    /// outside the workbook.
+    pub ranges: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ChartSeriesInfo {
+    pub index: u32,
+    pub chart_type: String,
+    pub name: Option<String>,
+    pub name_ref: Option<ChartRangeRef>,
+    pub categories: Option<ChartRangeRef>,
+    pub values: Option<ChartRangeRef>,
+    pub point_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ChartSummary {
+    pub sheet_name: String,
+    /// Position among the sheet's charts; pass to chart_data.
+    pub chart_index: u32,
+    pub name: Option<String>,
+    pub chart_type: String,
+    pub anchor: ChartAnchor,
+    pub title: Option<String>,
+    pub axes: Vec<ChartAxisInfo>,
+    pub series: Vec<ChartSeriesInfo>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ListChartsResponse {
+    pub workbook_id: WorkbookId,
+    pub workbook_short_id: String,
+    pub charts: Vec<ChartSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ChartSeriesData {
+    pub index: u32,
+    pub name: Option<String>,
+    /// Category labels as displayed, or x values for scatter charts.
+    pub categories: Vec<String>,
+    /// Plotted numbers; `null` for blanks and text.
+    pub values: Vec<Option<f64>>,
+    /// `cells` when read from the sheet, `cache` when only the chart's
+    /// cached points were available.
+    pub source: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ChartDataResponse {
+    pub workbook_id: WorkbookId,
+    pub workbook_short_id: String,
+    pub sheet_name: String,
+    pub chart_index: u32,
+    pub chart_type: String,
+    pub title: Option<String>,
+    pub series: Vec<ChartSeriesData>,
+}
+
+pub async fn list_charts(
+    state: Arc<AppState>,
+    params: ListChartsParams,
+) -> Result<ListChartsResponse> {
+    let workbook = state.open_workbook(&params.workbook_or_fork_id).await?;
+    let defs = read_sheet_charts(&workbook.path, params.sheet_name.as_deref()).await?;
+
+    let charts = workbook.with_spreadsheet(|book| {
+        let mut out = Vec::new();
+        for (sheet_name, sheet_charts) in &defs {
+            let Some(host) = book.get_sheet_by_name(sheet_name) else {
+                continue;
+            };
+            for (idx, chart) in sheet_charts.iter().enumerate() {
+                out.push(summarize(book, host, sheet_name, idx as u32, chart));
+            }
+        }
+        out
+    })?;
+
+    Ok(ListChartsResponse {
+        workbook_id: workbook.id.clone(),
+        workbook_short_id: workbook.short_id.clone(),
+        charts,
+    })
+}
+
+pub async fn chart_data(
+    state: Arc<AppState>,
+    params: ChartDataParams,
+) -> Result<ChartDataResponse> {
+    let workbook = state.open_workbook(&params.workbook_or_fork_id).await?;
+    let max_points = params.max_points.clamp(1, MAX_POINTS_LIMIT) as usize;
+    let defs = read_sheet_charts(&workbook.path, Some(&params.sheet_name)).await?;
+    let chart = defs
+        .into_iter()
+        .flat_map(|(_, charts)| charts)
+        .nth(params.chart_index as usize)
+        .ok_or_else(|| {
+            anyhow!(
+                "sheet '{}' has no chart at index {}; use list_charts",
+                params.sheet_name,
+                params.chart_index
+            )
+        })?;
+
+    let series = workbook.with_spreadsheet(|book| {
+        let host = book
+            .get_sheet_by_name(&params.sheet_name)
+            .ok_or_else(|| anyhow!("sheet '{}' not found", params.sheet_name))?;
+        Ok::<_, anyhow::Error>(
+            chart
+                .series
+                .iter()
+                .map(|series| series_data(book, host, series, max_points))
+                .collect::<Vec<_>>(),
+        )
+    })??;
+
+    Ok(ChartDataResponse {
+        workbook_id: workbook.id.clone(),
+        workbook_short_id: workbook.short_id.clone(),
+        sheet_name: params.sheet_name,
+        chart_index: params.chart_index,
+        chart_type: chart.chart_type,
+        title: chart.title,
+        series,
+    })
+}
+
+async fn read_sheet_charts(
+    path: &std::path::Path,
+    sheet_name: Option<&str>,
+) -> Result<Vec<(String, Vec<ChartDef>)>> {
+    let path = path.to_path_buf();
+    let mut defs = tokio::task::spawn_blocking(move || charts::read_charts(&path)).await??;
+    if let Some(sheet_name) = sheet_name {
+        defs.retain(|(name, _)| name == sheet_name);
+    }
+    Ok(defs)
+}
+
+fn summarize(
+    book: &Spreadsheet,
+    host: &Worksheet,
+    sheet_name: &str,
+    chart_index: u32,
+    chart: &ChartDef,
+) -> ChartSummary {
+    ChartSummary {
+        sheet_name: sheet_name.to_string(),
+        chart_index,
+        name: chart.name.clone(),
+        chart_type: chart.chart_type.clone(),
+        anchor: ChartAnchor {
+            from: chart.placement.from.clone(),
+            to: chart.placement.to.clone(),
+        },
+        title: chart.title.clone(),
+        axes: chart
+            .axes
+            .iter()
+            .map(|axis| ChartAxisInfo {
+                kind: axis.kind.clone(),
+                position: axis.position.clone(),
+                title: axis.title.clone(),
+                deleted: axis.deleted,
+            })
+            .collect(),
+        series: chart
+            .series
+            .iter()
+            .map(|series| {
+                let values = resolve(book, host, &series.values);
+                let point_count = values
+                    .as_ref()
+                    .map(|areas| areas.iter().map(|(_, a)| area_len(a)).sum())
+                    .unwrap_or(series.values.cache.len());
+                ChartSeriesInfo {
+                    index: series.index,
+                    chart_type: series.chart_type.clone(),
+                    name: series_name(book, host, series),
+                    name_ref: range_ref(book, host, &series.name),
+                    categories: range_ref(book, host, &series.categories),
+                    values: range_ref(book, host, &series.values),
+                    point_count,
+                }
+            })
+            .collect(),
+    }
+}
+
+fn series_data(
+    book: &Spreadsheet,
+    host: &Worksheet,
+    series: &SeriesDef,
+    max_points: usize,
+) -> ChartSeriesData {
+    let (values, from_cells) = match resolve(book, host, &series.values) {
+        Some(areas) => (
+            cells(&areas)
+                .map(|cell| cell.and_then(|c| c.get_value_number()))
+                .collect::<Vec<_>>(),
+            true,
+        ),
+        None => (
+            series
+                .values
+                .cache
+                .iter()
+                .map(|v| v.as_deref().and_then(|v| v.parse().ok()))
+                .collect(),
+            false,
+        ),
+    };
+    let categories: Vec<String> = match resolve(book, host, &series.categories) {
+        Some(areas) => cells(&areas)
+            .map(|cell| cell.and_then(cell_display_text).unwrap_or_default())
+            .collect(),
+        None => series
+            .categories
+            .cache
+            .iter()
+            .map(|v| v.clone().unwrap_or_default())
+            .collect(),
+    };
+    let truncated = values.len() > max_points || categories.len() > max_points;
+    ChartSeriesData {
+        index: series.index,
+        name: series_name(book, host, series),
+        categories: categories.into_iter().take(max_points).collect(),
+        values: values.into_iter().take(max_points).collect(),
+        source: if from_cells { "cells" } else { "cache" }.to_string(),
+        truncated,
+    }
+}
+
+/// The series name from its cell, falling back to the cached or literal text.
+fn series_name(book: &Spreadsheet, host: &Worksheet, series: &SeriesDef) -> Option<String> {
+    if let Some(areas) = resolve(book, host, &series.name) {
+        let text: Vec<String> = cells(&areas)
+            .filter_map(|cell| cell.and_then(cell_display_text))
+            .collect();
+        if !text.is_empty() {
+            return Some(text.join(" "));
+        }
+    }
+    series
+        .name
+        .cache
+        .iter()
+        .flatten()
+        .next()
+        .cloned()
+        .or_else(|| series.name_literal.clone())
+}
+
+fn resolve<'a>(
+    book: &'a Spreadsheet,
+    host: &'a Worksheet,
+    reference: &CachedRef,
+) -> Option<Vec<(&'a Worksheet, Area)>> {
+    charts::resolve_series_ref(book, host, reference.formula.as_deref()?)
+}
+
+fn range_ref(book: &Spreadsheet, host: &Worksheet, reference: &CachedRef) -> Option<ChartRangeRef> {
+    let formula = reference.formula.clone()?;
+    let ranges = resolve(book, host, reference)
+        .map(|areas| {
+            areas
+                .iter()
+                .map(|(sheet, area)| charts::qualified_range(sheet, area))
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(ChartRangeRef { formula, ranges })
+}
+
+fn area_len(area: &Area) -> usize {
+    ((area.max_row - area.min_row + 1) * (area.max_col - area.min_col + 1)) as usize
+}
+
+/// Cells of each area in row-major order, `None` for cells never written.
+fn cells<'a>(
+    areas: &'a [(&'a Worksheet, Area)],
+) -> impl Iterator<Item = Option<&'a umya_spreadsheet::Cell>> + 'a {
+    areas.iter().flat_map(|(sheet, area)| {
+        (area.min_row..=area.max_row).flat_map(move |row| {
+            (area.min_col..=area.max_col).map(move |col| sheet.get_cell((col, row)))
+        })
+    })
+}
@@ -0,0 +1,557 @@
+//! `diff_workbooks`: changeset between any two workbooks, forks or checkpoints.
+//!
+//! Uses the same change model, filters and paging as `get_changeset` (the
+//! paging helpers live here so the tool works without the `recalc` feature,
+//! which only adds fork and checkpoint sides). With `key_column` set, sheets
+//! present on both sides are compared row-by-row (rows matched by key,
+//! columns by header) instead of cell-by-address.
+
+use crate::backends::is_ooxml;
+use crate::diff::keyed::{KeyedDiffOptions, diff_sheet_by_key};
+use crate::diff::{Change, RowChange, calculate_changeset};
+use crate::model::WorkbookId;
+use crate::state::AppState;
+use crate::validation::validate_pagination;
+use anyhow::{Result, anyhow, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+fn default_diff_limit() -> u32 {
+    200
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DiffWorkbooksParams {
+    /// Workbook id or fork id for the "before" side.
+    pub base_id: WorkbookId,
+    /// Workbook id or fork id for the "after" side.
+    pub target_id: WorkbookId,
+    /// Use this checkpoint of `base_id` (a fork) instead of its current state.
+    #[serde(default)]
+    pub base_checkpoint_id: Option<String>,
+    /// Use this checkpoint of `target_id` (a fork) instead of its current state.
+    #[serde(default)]
+    pub target_checkpoint_id: Option<String>,
+    pub sheet_name: Option<String>,
+    /// Align rows by this column (header text or column letter) instead of by address.
+    #[serde(default)]
+    pub key_column: Option<String>,
+    /// Row holding the headers when `key_column` is set (default 1).
+    #[serde(default)]
+    pub header_row: Option<u32>,
+    #[serde(default = "default_diff_limit")]
+    pub limit: u32,
+    #[serde(default)]
+    pub offset: u32,
+    #[serde(default)]
+    pub summary_only: bool,
+    #[serde(default)]
+    pub include_types: Option<Vec<String>>,
+    #[serde(default)]
+    pub exclude_types: Option<Vec<String>>,
+    #[serde(default)]
+    pub include_subtypes: Option<Vec<String>>,
+    #[serde(default)]
+    pub exclude_subtypes: Option<Vec<String>>,
+}
+
+impl<'a> From<&'a DiffWorkbooksParams> for ChangesetQuery<'a> {
+    fn from(params: &'a DiffWorkbooksParams) -> Self {
+        Self {
+            include_types: &params.include_types,
+            exclude_types: &params.exclude_types,
+            include_subtypes: &params.include_subtypes,
+            exclude_subtypes: &params.exclude_subtypes,
+            limit: params.limit,
+            offset: params.offset,
+            summary_only: params.summary_only,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DiffWorkbooksResponse {
+    pub base_workbook: String,
+    pub target_workbook: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_column: Option<String>,
+    pub changes: Vec<Change>,
+    pub summary: ChangesetSummary,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ChangesetSummary {
+    pub total_changes: u32,
+    pub returned_changes: u32,
+    pub truncated: bool,
+    pub next_offset: Option<u32>,
+    pub counts_by_kind: BTreeMap<String, u32>,
+    pub counts_by_type: BTreeMap<String, u32>,
+    pub counts_by_subtype: BTreeMap<String, u32>,
+    pub affected_sheets: Vec<String>,
+}
+
+fn normalize_filter(values: &Option<Vec<String>>) -> Option<BTreeSet<String>> {
+    values.as_ref().map(|items| {
+        items
+            .iter()
+            .map(|s| s.to_ascii_lowercase())
+            .collect::<BTreeSet<_>>()
+    })
+}
+
+fn change_kind_key(change: &crate::diff::Change) -> &'static str {
+    match change {
+        crate::diff::Change::Cell(_) => "cell",
+        crate::diff::Change::Table(_) => "table",
+        crate::diff::Change::Name(_) => "name",
+        crate::diff::Change::Row(_) => "row",
+        crate::diff::Change::Style(_) => "style",
+        crate::diff::Change::Comment(_) => "comment",
+        crate::diff::Change::Chart(_) => "chart",
+        crate::diff::Change::DataValidation(_) => "data_validation",
+        crate::diff::Change::ConditionalFormat(_) => "conditional_format",
+        crate::diff::Change::Merge(_) => "merge",
+        crate::diff::Change::Sheet(_) => "sheet",
+    }
+}
+
+fn change_type_key(change: &crate::diff::Change) -> &'static str {
+    use crate::diff::charts::ChartDiff;
+    use crate::diff::comments::CommentDiff;
+    use crate::diff::keyed::RowDiff;
+    use crate::diff::merge::CellDiff;
+    use crate::diff::sheet_parts::{ConditionalFormatDiff, MergeDiff, ValidationDiff};
+    use crate::diff::sheets::SheetDiff;
+    use crate::diff::styles::StyleDiff;
+    match change {
+        crate::diff::Change::Cell(cell) => match &cell.diff {
+            CellDiff::Added { .. } => "added",
+            CellDiff::Deleted { .. } => "deleted",
+            CellDiff::Modified { .. } => "modified",
+        },
+        crate::diff::Change::Table(table) => match table {
+            crate::diff::tables::TableDiff::TableAdded { .. } => "table_added",
+            crate::diff::tables::TableDiff::TableDeleted { .. } => "table_deleted",
+            crate::diff::tables::TableDiff::TableModified { .. } => "table_modified",
+        },
+        crate::diff::Change::Name(name) => match name {
+            crate::diff::names::NameDiff::NameAdded { .. } => "name_added",
+            crate::diff::names::NameDiff::NameDeleted { .. } => "name_deleted",
+            crate::diff::names::NameDiff::NameModified { .. } => "name_modified",
+        },
+        crate::diff::Change::Row(row) => match &row.diff {
+            RowDiff::RowAdded { .. } => "row_added",
+            RowDiff::RowDeleted { .. } => "row_deleted",
+            RowDiff::RowModified { .. } => "row_modified",
+            RowDiff::ColumnAdded { .. } => "column_added",
+            RowDiff::ColumnDeleted { .. } => "column_deleted",
+        },
+        crate::diff::Change::Style(style) => match style {
+            StyleDiff::StyleChanged { .. } => "style_changed",
+        },
+        crate::diff::Change::Comment(comment) => match comment {
+            CommentDiff::CommentAdded { .. } => "comment_added",
+            CommentDiff::CommentDeleted { .. } => "comment_deleted",
+            CommentDiff::CommentModified { .. } => "comment_modified",
+            CommentDiff::CommentResolved { .. } => "comment_resolved",
+        },
+        crate::diff::Change::Chart(chart) => match chart {
+            ChartDiff::ChartAdded { .. } => "chart_added",
+            ChartDiff::ChartDeleted { .. } => "chart_deleted",
+            ChartDiff::ChartModified { .. } => "chart_modified",
+        },
+        crate::diff::Change::DataValidation(validation) => match validation {
+            ValidationDiff::ValidationAdded { .. } => "validation_added",
+            ValidationDiff::ValidationDeleted { .. } => "validation_deleted",
+            ValidationDiff::ValidationModified { .. } => "validation_modified",
+        },
+        crate::diff::Change::ConditionalFormat(cf) => match cf {
+            ConditionalFormatDiff::ConditionalFormatAdded { .. } => "conditional_format_added",
+            ConditionalFormatDiff::ConditionalFormatDeleted { .. } => "conditional_format_deleted",
+            ConditionalFormatDiff::ConditionalFormatModified { .. } => {
+                "conditional_format_modified"
+            }
+        },
+        crate::diff::Change::Merge(merge) => match merge {
+            MergeDiff::MergeAdded { .. } => "merge_added",
+            MergeDiff::MergeRemoved { .. } => "merge_removed",
+        },
+        crate::diff::Change::Sheet(sheet) => match sheet {
+            SheetDiff::SheetAdded { .. } => "sheet_added",
+            SheetDiff::SheetDeleted { .. } => "sheet_deleted",
+            SheetDiff::SheetMoved { .. } => "sheet_moved",
+            SheetDiff::SheetVisibilityChanged { .. } => "sheet_visibility_changed",
+            SheetDiff::FreezePanesChanged { .. } => "freeze_panes_changed",
+        },
+    }
+}
+
+fn change_subtype_key(change: &crate::diff::Change) -> Option<&'static str> {
+    use crate::diff::keyed::RowDiff;
+    use crate::diff::merge::{CellDiff, ModificationType};
+    let subtype = match change {
+        crate::diff::Change::Cell(cell) => match &cell.diff {
+            CellDiff::Modified { subtype, .. } => subtype,
+            _ => return None,
+        },
+        crate::diff::Change::Row(row) => match &row.diff {
+            RowDiff::RowModified { subtype, .. } => subtype,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(match subtype {
+        ModificationType::FormulaEdit => "formula_edit",
+        ModificationType::RecalcResult => "recalc_result",
+        ModificationType::ValueEdit => "value_edit",
+        ModificationType::StyleEdit => "style_edit",
+    })
+}
+
+fn change_sheet_name(change: &crate::diff::Change) -> Option<&str> {
+    match change {
+        crate::diff::Change::Cell(cell) => Some(cell.sheet.as_str()),
+        crate::diff::Change::Table(table) => match table {
+            crate::diff::tables::TableDiff::TableAdded { sheet, .. }
+            | crate::diff::tables::TableDiff::TableDeleted { sheet, .. }
+            | crate::diff::tables::TableDiff::TableModified { sheet, .. } => Some(sheet.as_str()),
+        },
+        crate::diff::Change::Name(name) => match name {
+            crate::diff::names::NameDiff::NameAdded { scope_sheet, .. }
+            | crate::diff::names::NameDiff::NameDeleted { scope_sheet, .. }
+            | crate::diff::names::NameDiff::NameModified { scope_sheet, .. } => {
+                scope_sheet.as_deref()
+            }
+        },
+        crate::diff::Change::Row(row) => Some(row.sheet.as_str()),
+        crate::diff::Change::Style(style) => Some(style.sheet()),
+        crate::diff::Change::Comment(comment) => Some(comment.sheet()),
+        crate::diff::Change::Chart(chart) => Some(chart.sheet()),
+        crate::diff::Change::DataValidation(validation) => Some(validation.sheet()),
+        crate::diff::Change::ConditionalFormat(cf) => Some(cf.sheet()),
+        crate::diff::Change::Merge(merge) => Some(merge.sheet()),
+        crate::diff::Change::Sheet(sheet) => Some(sheet.sheet()),
+    }
+}
+
+fn change_passes_filters(
+    change: &crate::diff::Change,
+    include_types: &Option<BTreeSet<String>>,
+    exclude_types: &Option<BTreeSet<String>>,
+    include_subtypes: &Option<BTreeSet<String>>,
+    exclude_subtypes: &Option<BTreeSet<String>>,
+) -> bool {
+    let type_key = change_type_key(change);
+    let subtype_key = change_subtype_key(change);
+
+    if let Some(include) = include_types
+        && !include.contains(type_key)
+    {
+        return false;
+    }
+    if let Some(exclude) = exclude_types
+        && exclude.contains(type_key)
+    {
+        return false;
+    }
+
+    if let Some(include) = include_subtypes
+        && subtype_key.is_none_or(|subtype| !include.contains(subtype))
+    {
+        return false;
+    }
+    if let Some(exclude) = exclude_subtypes
+        && subtype_key.is_some_and(|subtype| exclude.contains(subtype))
+    {
+        return false;
+    }
+
+    true
+}
+
+/// Filter and paging knobs shared by `get_changeset` and `diff_workbooks`.
+pub(crate) struct ChangesetQuery<'a> {
+    pub include_types: &'a Option<Vec<String>>,
+    pub exclude_types: &'a Option<Vec<String>>,
+    pub include_subtypes: &'a Option<Vec<String>>,
+    pub exclude_subtypes: &'a Option<Vec<String>>,
+    pub limit: u32,
+    pub offset: u32,
+    pub summary_only: bool,
+}
+
+/// Apply type/subtype filters, count what is left and return the requested page.
+pub(crate) fn page_changeset(
+    raw_changes: Vec<crate::diff::Change>,
+    query: &ChangesetQuery<'_>,
+) -> Result<(Vec<crate::diff::Change>, ChangesetSummary)> {
+    let include_types = normalize_filter(query.include_types);
+    let exclude_types = normalize_filter(query.exclude_types);
+    let include_subtypes = normalize_filter(query.include_subtypes);
+    let exclude_subtypes = normalize_filter(query.exclude_subtypes);
+
+    let mut affected_sheets: BTreeSet<String> = BTreeSet::new();
+    let mut counts_by_kind: BTreeMap<String, u32> = BTreeMap::new();
+    let mut counts_by_type: BTreeMap<String, u32> = BTreeMap::new();
+    let mut counts_by_subtype: BTreeMap<String, u32> = BTreeMap::new();
+
+    let mut filtered: Vec<crate::diff::Change> = Vec::new();
+    for change in raw_changes {
+        if !change_passes_filters(
+            &change,
+            &include_types,
+            &exclude_types,
+            &include_subtypes,
+            &exclude_subtypes,
+        ) {
+            continue;
+        }
+
+        *counts_by_kind
+            .entry(change_kind_key(&change).to_string())
+            .or_default() += 1;
+        *counts_by_type
+            .entry(change_type_key(&change).to_string())
+            .or_default() += 1;
+        if let Some(subtype) = change_subtype_key(&change) {
+            *counts_by_subtype.entry(subtype.to_string()).or_default() += 1;
+        }
+        if let Some(sheet) = change_sheet_name(&change) {
+            affected_sheets.insert(sheet.to_string());
+        }
+
+        filtered.push(change);
+    }
+
+    let limit = query.limit.clamp(1, 2000) as usize;
+    let offset = query.offset as usize;
+
+    // Validate pagination parameters to prevent overflow
+    validate_pagination(offset, limit)
+        .map_err(|e| anyhow!("pagination validation failed: {}", e))?;
+
+    let total = filtered.len();
+
+    let (returned_changes, changes, truncated, next_offset) = if query.summary_only {
+        (0u32, Vec::new(), false, None)
+    } else {
+        let end = offset.saturating_add(limit);
+        let truncated = end < total;
+        let next_offset = truncated.then_some(end as u32);
+        let changes: Vec<_> = filtered.into_iter().skip(offset).take(limit).collect();
+        (changes.len() as u32, changes, truncated, next_offset)
+    };
+
+    let summary = ChangesetSummary {
+        total_changes: total as u32,
+        returned_changes,
+        truncated,
+        next_offset,
+        counts_by_kind,
+        counts_by_type,
+        counts_by_subtype,
+        affected_sheets: affected_sheets.into_iter().collect(),
+    };
+    Ok((changes, summary))
+}
+
+struct DiffSide {
+    label: String,
+    path: PathBuf,
+}
+
+pub async fn diff_workbooks(
+    state: Arc<AppState>,
+    params: DiffWorkbooksParams,
+) -> Result<DiffWorkbooksResponse> {
+    let scratch = tempfile::tempdir()?;
+    let base = resolve_side(
+        &state,
+        &params.base_id,
+        params.base_checkpoint_id.as_deref(),
+        &scratch.path().join("base.xlsx"),
+    )
+    .await?;
+    let target = resolve_side(
+        &state,
+        &params.target_id,
+        params.target_checkpoint_id.as_deref(),
+        &scratch.path().join("target.xlsx"),
+    )
+    .await?;
+
+    let key_options = params.key_column.as_ref().map(|key| KeyedDiffOptions {
+        key_column: key.clone(),
+        header_row: params.header_row.unwrap_or(1).max(1),
+    });
+
+    let (raw_changes, warnings) = tokio::task::spawn_blocking({
+        let base_path = base.path.clone();
+        let target_path = target.path.clone();
+        let sheet_filter = params.sheet_name.clone();
+        move || {
+            collect_changes(
+                &base_path,
+                &target_path,
+                sheet_filter.as_deref(),
+                key_options.as_ref(),
+            )
+        }
+    })
+    .await??;
+
+    let (changes, summary) = page_changeset(raw_changes, &ChangesetQuery::from(&params))?;
+
+    Ok(DiffWorkbooksResponse {
+        base_workbook: base.label,
+        target_workbook: target.label,
+        key_column: params.key_column,
+        changes,
+        summary,
+        warnings,
+    })
+}
+
+/// Resolve one side of the diff to an xlsx file. Non-xlsx workbooks are
+/// written to `convert_to` so the zip-level diff can read them.
+async fn resolve_side(
+    state: &Arc<AppState>,
+    id: &WorkbookId,
+    checkpoint_id: Option<&str>,
+    convert_to: &Path,
+) -> Result<DiffSide> {
+    if let Some(checkpoint_id) = checkpoint_id {
+        return resolve_checkpoint(state, id, checkpoint_id);
+    }
+
+    let workbook = match state.open_workbook(id).await {
+        Ok(workbook) => workbook,
+        Err(e) if !forks_available(state) && id.as_str().starts_with("fork-") => {
+            return Err(e.context(format!(
+                "'{}' looks like a fork id, but forks need recalc to be enabled",
+                id.as_str()
+            )));
+        }
+        Err(e) => return Err(e),
+    };
+    let label = workbook.path.display().to_string();
+    if is_ooxml(&workbook.path) {
+        return Ok(DiffSide {
+            label,
+            path: workbook.path.clone(),
+        });
+    }
+
+    let converted = convert_to.to_path_buf();
+    tokio::task::spawn_blocking({
+        let converted = converted.clone();
+        move || {
+            workbook
+                .with_spreadsheet(|book| umya_spreadsheet::writer::xlsx::write(book, &converted))?
+                .map_err(|e| anyhow!("failed to convert workbook for diff: {}", e))
+        }
+    })
+    .await??;
+    Ok(DiffSide {
+        label,
+        path: converted,
+    })
+}
+
+#[cfg(feature = "recalc")]
+fn resolve_checkpoint(state: &AppState, id: &WorkbookId, checkpoint_id: &str) -> Result<DiffSide> {
+    let registry = state
+        .fork_registry()
+        .ok_or_else(|| anyhow!("fork registry not available (recalc disabled?)"))?;
+    let checkpoint = registry
+        .list_checkpoints(id.as_str())?
+        .into_iter()
+        .find(|c| c.checkpoint_id == checkpoint_id)
+        .ok_or_else(|| anyhow!("checkpoint not found: {}", checkpoint_id))?;
+    Ok(DiffSide {
+        label: format!("{}@{}", id.as_str(), checkpoint_id),
+        path: checkpoint.snapshot_path,
+    })
+}
+
+#[cfg(not(feature = "recalc"))]
+fn resolve_checkpoint(_state: &AppState, id: &WorkbookId, checkpoint_id: &str) -> Result<DiffSide> {
+    bail!(
+        "cannot diff checkpoint '{}' of '{}': forks and checkpoints need the recalc feature",
+        checkpoint_id,
+        id.as_str()
+    )
+}
+
+#[cfg(feature = "recalc")]
+fn forks_available(state: &AppState) -> bool {
+    state.fork_registry().is_some()
+}
+
+#[cfg(not(feature = "recalc"))]
+fn forks_available(_state: &AppState) -> bool {
+    false
+}
+
+fn collect_changes(
+    base_path: &Path,
+    target_path: &Path,
+    sheet_filter: Option<&str>,
+    key_options: Option<&KeyedDiffOptions>,
+) -> Result<(Vec<Change>, Vec<String>)> {
+    let mut changes = calculate_changeset(base_path, target_path, sheet_filter)?;
+    let Some(options) = key_options else {
+        return Ok((changes, Vec::new()));
+    };
+
+    let base_book = crate::backends::read_spreadsheet(base_path)?;
+    let target_book = crate::backends::read_spreadsheet(target_path)?;
+
+    let mut keyed_sheets: BTreeSet<String> = BTreeSet::new();
+    let mut row_changes = Vec::new();
+    let mut warnings = Vec::new();
+    for target_sheet in target_book.get_sheet_collection() {
+        let name = target_sheet.get_name();
+        if sheet_filter.is_some_and(|filter| filter != name) {
+            continue;
+        }
+        let Some(base_sheet) = base_book.get_sheet_by_name(name) else {
+            continue;
+        };
+        match diff_sheet_by_key(base_sheet, target_sheet, options) {
+            Ok(diffs) => {
+                keyed_sheets.insert(name.to_string());
+                row_changes.extend(diffs.into_iter().map(|diff| {
+                    Change::Row(RowChange {
+                        sheet: name.to_string(),
+                        diff,
+                    })
+                }));
+            }
+            // A single named sheet that cannot be keyed is a caller error; in a
+            // multi-sheet diff the other sheets fall back to address alignment.
+            Err(e) if sheet_filter.is_some() => return Err(e),
+            Err(e) => warnings.push(format!("{}; compared '{}' by address", e, name)),
+        }
+    }
+    if let Some(sheet) = sheet_filter
+        && keyed_sheets.is_empty()
+    {
+        bail!(
+            "sheet '{}' must exist in both workbooks to align by key",
+            sheet
+        );
+    }
+
+    changes.retain(
+        |change| !matches!(change, Change::Cell(cell) if keyed_sheets.contains(&cell.sheet)),
+    );
+    changes.extend(row_changes);
+    Ok((changes, warnings))
+}
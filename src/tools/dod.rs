@@ -19,6 +19,7 @@ use crate::dod::scoring::Scorer;
 use crate::dod::check::CheckContext;
 use crate::dod::types::{CheckStatus, DodCheckResult};
 use crate::dod::verdict::VerdictRenderer;
+use crate::progress::ProgressReporter;
 use crate::state::AppState;
 use crate::validation::validate_path_safe;
 use anyhow::{Context, Result, anyhow};
@@ -32,8 +33,17 @@ use std::sync::Arc;
 
 /// Validate Definition of Done
 pub async fn validate_definition_of_done(
+    state: Arc<AppState>,
+    params: ValidateDefinitionOfDoneParams,
+) -> Result<ValidateDefinitionOfDoneResponse> {
+    validate_definition_of_done_with_progress(state, params, ProgressReporter::none()).await
+}
+
+/// Validate Definition of Done, reporting progress after each check
+pub async fn validate_definition_of_done_with_progress(
     _state: Arc<AppState>,
     params: ValidateDefinitionOfDoneParams,
+    progress: ProgressReporter,
 ) -> Result<ValidateDefinitionOfDoneResponse> {
     let _span = audit_tool("validate_definition_of_done", &params);
 
@@ -43,7 +53,7 @@ pub async fn validate_definition_of_done(
     }
 
     // Execute validation
-    DodValidator::validate(params).await
+    DodValidator::validate_with_progress(params, &progress).await
 }
 
 // =============================================================================
@@ -182,6 +192,14 @@ impl DodValidator {
     /// Validate Definition of Done
     pub async fn validate(
         params: ValidateDefinitionOfDoneParams,
+    ) -> Result<ValidateDefinitionOfDoneResponse> {
+        Self::validate_with_progress(params, &ProgressReporter::none()).await
+    }
+
+    /// Validate Definition of Done, reporting progress after each check
+    pub async fn validate_with_progress(
+        params: ValidateDefinitionOfDoneParams,
+        progress: &ProgressReporter,
     ) -> Result<ValidateDefinitionOfDoneResponse> {
         let start = std::time::Instant::now();
 
@@ -208,7 +226,7 @@ impl DodValidator {
 
         // 5. Execute checks
         let check_results = executor
-            .execute_all(&context)
+            .execute_all_with_progress(&context, progress)
             .await
             .context("Failed to execute DoD checks")?;
 
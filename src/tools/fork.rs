@@ -1,21 +1,40 @@
-use crate::fork::{ChangeSummary, EditOp, StagedChange, StagedOp};
+use crate::data_validation::{
+    Area, format_sqref, operator_name, parse_sqref, scan_violations, subtract_sqref,
+};
+use crate::fork::{ChangeSummary, EditOp, StagedChange, StagedOp, TempFileGuard};
 use crate::formula::pattern::{RelativeMode, parse_base_formula, shift_formula_ast};
 use crate::model::{StylePatch, WorkbookId};
+use crate::progress::{CancelledError, ProgressReporter};
 use crate::state::AppState;
+use crate::tools::diff::{ChangesetQuery, ChangesetSummary, page_changeset};
 use crate::utils::make_short_random_id;
 use crate::validation::{
     DEFAULT_MAX_PNG_AREA_PX, DEFAULT_MAX_PNG_DIM_PX, MAX_SCREENSHOT_COLS, MAX_SCREENSHOT_ROWS,
-    validate_pagination,
 };
 use anyhow::{Result, anyhow, bail};
 use chrono::Utc;
-use formualizer_parse::tokenizer::Tokenizer;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use umya_spreadsheet::structs::{
+    DataValidation, DataValidationOperatorValues, DataValidationValues, DataValidations,
+};
+
+mod charts;
+mod conditional_formats;
+mod package;
+mod references;
+mod sheet_settings;
+mod tables;
+
+pub use charts::{ChartKind, ChartOp, SeriesSpec};
+pub use conditional_formats::{CfThreshold, ConditionalFormatRuleSpec};
+pub use references::{ReferenceRewrite, RewriteKind};
+use references::{RewriteLog, StructureAxis, StructureEdit};
+pub use sheet_settings::{SheetSettingsOp, SheetState};
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct CreateForkParams {
@@ -224,6 +243,17 @@ struct TransformBatchStagedPayload {
 pub async fn transform_batch(
     state: Arc<AppState>,
     params: TransformBatchParams,
+) -> Result<TransformBatchResponse> {
+    transform_batch_with_progress(state, params, ProgressReporter::none()).await
+}
+
+/// [`transform_batch`] reporting progress per op. The workbook is written
+/// only after every op has applied, so a cancelled batch leaves the fork
+/// unchanged and stages nothing.
+pub async fn transform_batch_with_progress(
+    state: Arc<AppState>,
+    params: TransformBatchParams,
+    progress: ProgressReporter,
 ) -> Result<TransformBatchResponse> {
     let registry = state
         .fork_registry()
@@ -326,16 +356,23 @@ pub async fn transform_batch(
 
     if mode == "preview" {
         let change_id = make_short_random_id("chg", 12);
-        let snapshot_path = stage_snapshot_path(&params.fork_id, &change_id);
-        fs::create_dir_all(snapshot_path.parent().unwrap())?;
+        let snapshot_path = registry.staged_snapshot_path(&params.fork_id, &change_id)?;
         fs::copy(&work_path, &snapshot_path)?;
 
         let snapshot_for_apply = snapshot_path.clone();
-        let apply_result = tokio::task::spawn_blocking({
+        let apply_result = match tokio::task::spawn_blocking({
             let ops = resolved_ops.clone();
-            move || apply_transform_ops_to_file(&snapshot_for_apply, &ops)
+            let progress = progress.clone();
+            move || apply_transform_ops_to_file(&snapshot_for_apply, &ops, &progress)
         })
-        .await??;
+        .await?
+        {
+            Ok(result) => result,
+            Err(error) => {
+                let _ = fs::remove_file(&snapshot_path);
+                return Err(error);
+            }
+        };
 
         let mut summary = apply_result.summary;
         summary.op_kinds = vec!["transform_batch".to_string()];
@@ -369,7 +406,8 @@ pub async fn transform_batch(
         let apply_result = tokio::task::spawn_blocking({
             let ops = resolved_ops.clone();
             let work_path = work_path.clone();
-            move || apply_transform_ops_to_file(&work_path, &ops)
+            let progress = progress.clone();
+            move || apply_transform_ops_to_file(&work_path, &ops, &progress)
         })
         .await??;
 
@@ -377,6 +415,7 @@ pub async fn transform_batch(
         summary.op_kinds = vec!["transform_batch".to_string()];
 
         let _ = state.close_workbook(&fork_workbook_id);
+        let _ = registry.mark_modified(&params.fork_id);
 
         Ok(TransformBatchResponse {
             fork_id: params.fork_id,
@@ -470,8 +509,7 @@ pub async fn style_batch(
 
     if mode == "preview" {
         let change_id = make_short_random_id("chg", 12);
-        let snapshot_path = stage_snapshot_path(&params.fork_id, &change_id);
-        fs::create_dir_all(snapshot_path.parent().unwrap())?;
+        let snapshot_path = registry.staged_snapshot_path(&params.fork_id, &change_id)?;
         fs::copy(&work_path, &snapshot_path)?;
 
         let snapshot_path_for_apply = snapshot_path.clone();
@@ -521,6 +559,7 @@ pub async fn style_batch(
         summary.op_kinds = vec!["style_batch".to_string()];
 
         let _ = state.close_workbook(&fork_workbook_id);
+        let _ = registry.mark_modified(&params.fork_id);
 
         Ok(StyleBatchResponse {
             fork_id: params.fork_id,
@@ -533,46 +572,77 @@ pub async fn style_batch(
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
-pub struct ApplyFormulaPatternParams {
+pub struct CommentBatchParams {
     pub fork_id: String,
-    pub sheet_name: String,
-    pub target_range: String,
-    pub anchor_cell: String,
-    pub base_formula: String,
-    #[serde(default)]
-    pub fill_direction: Option<String>, // down|right|both (default both)
-    #[serde(default)]
-    pub relative_mode: Option<String>, // excel|abs_cols|abs_rows
+    pub ops: Vec<CommentOp>,
     #[serde(default)]
-    pub mode: Option<String>, // preview|apply (default apply)
+    pub mode: Option<String>, // "preview" | "apply" (default apply)
     pub label: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CommentOp {
+    pub sheet_name: String,
+    /// Anchor cell, e.g. "B2".
+    pub address: String,
+    #[serde(flatten)]
+    pub action: CommentAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CommentAction {
+    /// New note, or a new thread when `threaded` is set.
+    Add {
+        text: String,
+        #[serde(default)]
+        author: Option<String>,
+        #[serde(default)]
+        threaded: bool,
+    },
+    /// Reply to a thread. A note is turned into a thread first.
+    Reply {
+        text: String,
+        #[serde(default)]
+        author: Option<String>,
+    },
+    /// Replace the text of a note or of a thread's opening comment.
+    Edit {
+        text: String,
+        #[serde(default)]
+        author: Option<String>,
+    },
+    /// Resolve (or reopen with resolved=false) a thread. A note is turned
+    /// into a thread first.
+    Resolve {
+        #[serde(default = "default_resolved")]
+        resolved: bool,
+    },
+    Delete,
+}
+
+fn default_resolved() -> bool {
+    true
+}
+
 #[derive(Debug, Serialize, JsonSchema)]
-pub struct ApplyFormulaPatternResponse {
+pub struct CommentBatchResponse {
     pub fork_id: String,
-    pub sheet_name: String,
-    pub target_range: String,
     pub mode: String,
     pub change_id: Option<String>,
-    pub cells_filled: u64,
+    pub ops_applied: usize,
     pub summary: ChangeSummary,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct ApplyFormulaPatternStagedPayload {
-    sheet_name: String,
-    target_range: String,
-    anchor_cell: String,
-    base_formula: String,
-    fill_direction: Option<String>,
-    relative_mode: Option<String>,
+struct CommentBatchStagedPayload {
+    ops: Vec<CommentOp>,
 }
 
-pub async fn apply_formula_pattern(
+pub async fn comment_batch(
     state: Arc<AppState>,
-    params: ApplyFormulaPatternParams,
-) -> Result<ApplyFormulaPatternResponse> {
+    params: CommentBatchParams,
+) -> Result<CommentBatchResponse> {
     let registry = state
         .fork_registry()
         .ok_or_else(|| anyhow!("fork registry not available"))?;
@@ -580,20 +650,6 @@ pub async fn apply_formula_pattern(
     let fork_ctx = registry.get_fork(&params.fork_id)?;
     let work_path = fork_ctx.work_path.clone();
 
-    let bounds = parse_range_bounds(&params.target_range)?;
-    let (anchor_col, anchor_row) = parse_cell_ref(&params.anchor_cell)?;
-    validate_formula_pattern_bounds(
-        &bounds,
-        anchor_col,
-        anchor_row,
-        params.fill_direction.as_deref(),
-    )?;
-
-    let fork_workbook_id = WorkbookId(params.fork_id.clone());
-    let workbook = state.open_workbook(&fork_workbook_id).await?;
-    let _ = workbook.with_sheet(&params.sheet_name, |_| Ok::<_, anyhow::Error>(()))?;
-
-    let relative_mode = RelativeMode::parse(params.relative_mode.as_deref())?;
     let mode = params
         .mode
         .as_deref()
@@ -602,46 +658,21 @@ pub async fn apply_formula_pattern(
 
     if mode == "preview" {
         let change_id = make_short_random_id("chg", 12);
-        let snapshot_path = stage_snapshot_path(&params.fork_id, &change_id);
-        fs::create_dir_all(snapshot_path.parent().unwrap())?;
+        let snapshot_path = registry.staged_snapshot_path(&params.fork_id, &change_id)?;
         fs::copy(&work_path, &snapshot_path)?;
 
-        let sheet_name = params.sheet_name.clone();
-        let target_range = params.target_range.clone();
-        let anchor_cell = params.anchor_cell.clone();
-        let base_formula = params.base_formula.clone();
-        let fill_direction = params.fill_direction.clone();
-        let relative_mode_str = params.relative_mode.clone();
-        let snapshot_for_apply = snapshot_path.clone();
-        let sheet_name_for_apply = sheet_name.clone();
-        let target_range_for_apply = target_range.clone();
-        let base_formula_for_apply = base_formula.clone();
-
-        let apply_result = tokio::task::spawn_blocking(move || {
-            apply_formula_pattern_to_file(
-                &snapshot_for_apply,
-                &sheet_name_for_apply,
-                &target_range_for_apply,
-                anchor_col,
-                anchor_row,
-                &base_formula_for_apply,
-                relative_mode,
-            )
+        let apply_result = tokio::task::spawn_blocking({
+            let ops = params.ops.clone();
+            let snapshot_path = snapshot_path.clone();
+            move || apply_comment_ops_to_file(&snapshot_path, &ops)
         })
         .await??;
 
-        let mut summary = apply_result.summary;
-        summary.op_kinds = vec!["apply_formula_pattern".to_string()];
-
+        let summary = apply_result.summary;
         let staged_op = StagedOp {
-            kind: "apply_formula_pattern".to_string(),
-            payload: serde_json::to_value(ApplyFormulaPatternStagedPayload {
-                sheet_name: sheet_name.clone(),
-                target_range: target_range.clone(),
-                anchor_cell: anchor_cell.clone(),
-                base_formula: base_formula.clone(),
-                fill_direction,
-                relative_mode: relative_mode_str,
+            kind: "comment_batch".to_string(),
+            payload: serde_json::to_value(CommentBatchStagedPayload {
+                ops: params.ops.clone(),
             })?,
         };
 
@@ -656,149 +687,1532 @@ pub async fn apply_formula_pattern(
 
         registry.add_staged_change(&params.fork_id, staged)?;
 
-        Ok(ApplyFormulaPatternResponse {
+        Ok(CommentBatchResponse {
             fork_id: params.fork_id,
-            sheet_name,
-            target_range,
             mode,
             change_id: Some(change_id),
-            cells_filled: apply_result.cells_filled,
+            ops_applied: apply_result.ops_applied,
             summary,
         })
     } else {
-        let sheet_name = params.sheet_name.clone();
-        let target_range = params.target_range.clone();
-        let base_formula = params.base_formula.clone();
-        let sheet_name_for_apply = sheet_name.clone();
-        let target_range_for_apply = target_range.clone();
-        let base_formula_for_apply = base_formula.clone();
-        let apply_result = tokio::task::spawn_blocking(move || {
-            apply_formula_pattern_to_file(
-                &work_path,
-                &sheet_name_for_apply,
-                &target_range_for_apply,
-                anchor_col,
-                anchor_row,
-                &base_formula_for_apply,
-                relative_mode,
-            )
+        let apply_result = tokio::task::spawn_blocking({
+            let ops = params.ops.clone();
+            move || apply_comment_ops_to_file(&work_path, &ops)
         })
         .await??;
 
-        let mut summary = apply_result.summary;
-        summary.op_kinds = vec!["apply_formula_pattern".to_string()];
-
+        let fork_workbook_id = WorkbookId(params.fork_id.clone());
         let _ = state.close_workbook(&fork_workbook_id);
+        let _ = registry.mark_modified(&params.fork_id);
 
-        Ok(ApplyFormulaPatternResponse {
+        Ok(CommentBatchResponse {
             fork_id: params.fork_id,
-            sheet_name,
-            target_range,
             mode,
             change_id: None,
-            cells_filled: apply_result.cells_filled,
-            summary,
+            ops_applied: apply_result.ops_applied,
+            summary: apply_result.summary,
         })
     }
 }
 
-struct FormulaPatternApplyResult {
-    cells_filled: u64,
+const DEFAULT_COMMENT_AUTHOR: &str = "spreadsheet-mcp";
+
+struct CommentApplyResult {
+    ops_applied: usize,
     summary: ChangeSummary,
 }
 
-fn apply_formula_pattern_to_file(
-    path: &Path,
-    sheet_name: &str,
-    target_range: &str,
-    anchor_col: u32,
-    anchor_row: u32,
-    base_formula: &str,
-    relative_mode: RelativeMode,
-) -> Result<FormulaPatternApplyResult> {
-    let ast = parse_base_formula(base_formula)?;
-    let bounds = parse_range_bounds(target_range)?;
+fn apply_comment_ops_to_file(path: &Path, ops: &[CommentOp]) -> Result<CommentApplyResult> {
+    use crate::comments::{PLACEHOLDER_AUTHOR_PREFIX, ThreadedComments, placeholder_text};
+    use umya_spreadsheet::structs::Comment;
 
+    let mut threads = ThreadedComments::read(path)?;
     let mut book = umya_spreadsheet::reader::xlsx::read(path)?;
-    let sheet = book
-        .get_sheet_by_name_mut(sheet_name)
-        .ok_or_else(|| anyhow!("sheet '{}' not found", sheet_name))?;
-
-    let mut cells_filled: u64 = 0;
-    for row in bounds.min_row..=bounds.max_row {
-        for col in bounds.min_col..=bounds.max_col {
-            let delta_col = col as i32 - anchor_col as i32;
-            let delta_row = row as i32 - anchor_row as i32;
-            let shifted = shift_formula_ast(&ast, delta_col, delta_row, relative_mode)?;
-            let shifted_for_umya = shifted.strip_prefix('=').unwrap_or(&shifted);
-            let addr = crate::utils::cell_address(col, row);
-            sheet
-                .get_cell_mut(addr.as_str())
-                .set_formula(shifted_for_umya.to_string());
-            cells_filled += 1;
-        }
-    }
-
-    umya_spreadsheet::writer::xlsx::write(&book, path)?;
 
-    let mut counts = BTreeMap::new();
-    counts.insert("cells_filled".to_string(), cells_filled);
+    let mut sheets: BTreeSet<String> = BTreeSet::new();
+    let mut affected_bounds: Vec<String> = Vec::new();
+    let mut counts: BTreeMap<String, u64> = BTreeMap::new();
 
-    let summary = ChangeSummary {
-        op_kinds: vec!["apply_formula_pattern".to_string()],
-        affected_sheets: vec![sheet_name.to_string()],
-        affected_bounds: vec![target_range.to_string()],
-        counts,
-        warnings: Vec::new(),
-    };
+    for op in ops {
+        let sheet_name = op.sheet_name.as_str();
+        let address = op.address.trim().replace('$', "").to_ascii_uppercase();
+        parse_cell_ref(&address)?;
+        let sheet = book
+            .get_sheet_by_name_mut(sheet_name)
+            .ok_or_else(|| anyhow!("sheet '{}' not found", sheet_name))?;
 
-    Ok(FormulaPatternApplyResult {
-        cells_filled,
-        summary,
-    })
-}
+        let has_thread = threads.thread(sheet_name, &address).is_some();
+        let note = sheet
+            .get_comments()
+            .iter()
+            .find(|c| c.get_coordinate().to_string() == address)
+            .map(|c| {
+                (
+                    c.get_author().to_string(),
+                    c.get_text().get_text().to_string(),
+                )
+            });
+        let missing = || anyhow!("no comment at {}!{}", sheet_name, address);
+
+        // Reply/resolve need a thread; a note is converted into one, as Excel does.
+        if matches!(
+            op.action,
+            CommentAction::Reply { .. } | CommentAction::Resolve { .. }
+        ) && !has_thread
+        {
+            let (author, text) = note.clone().ok_or_else(missing)?;
+            let author = if author.is_empty() {
+                DEFAULT_COMMENT_AUTHOR
+            } else {
+                author.as_str()
+            };
+            threads.add_thread(sheet_name, &address, author, &text);
+        }
 
-fn validate_formula_pattern_bounds(
-    bounds: &ScreenshotBounds,
-    anchor_col: u32,
-    anchor_row: u32,
-    fill_direction: Option<&str>,
-) -> Result<()> {
-    if anchor_col < bounds.min_col
-        || anchor_col > bounds.max_col
-        || anchor_row < bounds.min_row
-        || anchor_row > bounds.max_row
-    {
-        let bounds_range = format!(
-            "{}:{}",
-            crate::utils::cell_address(bounds.min_col, bounds.min_row),
-            crate::utils::cell_address(bounds.max_col, bounds.max_row)
-        );
-        bail!(
-            "anchor_cell must be inside target_range (anchor {} not within {})",
-            crate::utils::cell_address(anchor_col, anchor_row),
-            bounds_range
-        );
-    }
+        let count_key = match &op.action {
+            CommentAction::Add {
+                text,
+                author,
+                threaded,
+            } => {
+                if has_thread || note.is_some() {
+                    bail!(
+                        "{}!{} already has a comment; use edit or reply",
+                        sheet_name,
+                        address
+                    );
+                }
+                let author = author.as_deref().unwrap_or(DEFAULT_COMMENT_AUTHOR);
+                if *threaded {
+                    threads.add_thread(sheet_name, &address, author, text);
+                } else {
+                    let mut comment = Comment::default();
+                    comment.new_comment(address.as_str());
+                    comment.set_author(author);
+                    comment.set_text_string(text);
+                    sheet.add_comments(comment);
+                }
+                "comments_added"
+            }
+            CommentAction::Reply { text, author } => {
+                let author = author.as_deref().unwrap_or(DEFAULT_COMMENT_AUTHOR);
+                threads.add_reply(sheet_name, &address, author, text);
+                "replies_added"
+            }
+            CommentAction::Edit { text, author } => {
+                if has_thread {
+                    let person_id = author.as_deref().map(|a| threads.person_id(a));
+                    let root = threads.root_mut(sheet_name, &address).ok_or_else(missing)?;
+                    root.text = text.clone();
+                    if let Some(person_id) = person_id {
+                        root.person_id = person_id;
+                    }
+                } else {
+                    let comment = sheet
+                        .get_comments_mut()
+                        .iter_mut()
+                        .find(|c| c.get_coordinate().to_string() == address)
+                        .ok_or_else(missing)?;
+                    comment.set_text_string(text);
+                    if let Some(author) = author {
+                        comment.set_author(author);
+                    }
+                }
+                "comments_edited"
+            }
+            CommentAction::Resolve { resolved } => {
+                threads
+                    .root_mut(sheet_name, &address)
+                    .ok_or_else(missing)?
+                    .done = *resolved;
+                if *resolved {
+                    "comments_resolved"
+                } else {
+                    "comments_reopened"
+                }
+            }
+            CommentAction::Delete => {
+                if !threads.remove_thread(sheet_name, &address) && note.is_none() {
+                    return Err(missing());
+                }
+                sheet
+                    .get_comments_mut()
+                    .retain(|c| c.get_coordinate().to_string() != address);
+                "comments_deleted"
+            }
+        };
 
-    if bounds.min_col != anchor_col || bounds.min_row != anchor_row {
-        bail!("target_range must start at anchor_cell (anchor should be top-left of fill range)");
+        *counts.entry(count_key.to_string()).or_insert(0) += 1;
+        sheets.insert(sheet_name.to_string());
+        affected_bounds.push(address);
     }
 
-    let dir = fill_direction.unwrap_or("both").to_ascii_lowercase();
-    match dir.as_str() {
-        "down" => {
-            if bounds.min_col != bounds.max_col {
-                bail!("fill_direction=down requires a single-column target_range");
-            }
-        }
-        "right" => {
-            if bounds.min_row != bounds.max_row {
-                bail!("fill_direction=right requires a single-row target_range");
+    // Keep each thread's fallback note in step with the thread.
+    for sheet_name in &sheets {
+        let Some(sheet) = book.get_sheet_by_name_mut(sheet_name) else {
+            continue;
+        };
+        sheet.get_comments_mut().retain(|c| {
+            !c.get_author().starts_with(PLACEHOLDER_AUTHOR_PREFIX)
+                || threads
+                    .thread(sheet_name, &c.get_coordinate().to_string())
+                    .is_some()
+        });
+        for thread in threads.threads(sheet_name) {
+            let author = format!("{PLACEHOLDER_AUTHOR_PREFIX}{}", thread.root.id);
+            let text = placeholder_text(&threads, thread);
+            match sheet
+                .get_comments_mut()
+                .iter_mut()
+                .find(|c| c.get_coordinate().to_string() == thread.root.address)
+            {
+                Some(comment) => {
+                    comment.set_author(author);
+                    comment.set_text_string(text);
+                }
+                None => {
+                    let mut comment = Comment::default();
+                    comment.new_comment(thread.root.address.as_str());
+                    comment.set_author(author);
+                    comment.set_text_string(text);
+                    sheet.add_comments(comment);
+                }
             }
         }
-        "both" => {}
-        other => bail!("invalid fill_direction: {}", other),
+    }
+
+    umya_spreadsheet::writer::xlsx::write(&book, path)?;
+    threads.write_into(path)?;
+
+    Ok(CommentApplyResult {
+        ops_applied: ops.len(),
+        summary: ChangeSummary {
+            op_kinds: vec!["comment_batch".to_string()],
+            affected_sheets: sheets.into_iter().collect(),
+            affected_bounds,
+            counts,
+            warnings: Vec::new(),
+        },
+    })
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ValidationBatchParams {
+    pub fork_id: String,
+    pub ops: Vec<ValidationOp>,
+    #[serde(default)]
+    pub mode: Option<String>, // "preview" | "apply" (default apply)
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ValidationOp {
+    pub sheet_name: String,
+    pub target: TransformTarget,
+    #[serde(flatten)]
+    pub action: ValidationAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ValidationAction {
+    /// New rule over the target. Cells already covered by another rule are
+    /// taken over by the new one, as Excel keeps one rule per cell.
+    Add { rule: ValidationRuleSpec },
+    /// Patch every rule covering a target cell; omitted fields are kept.
+    Modify { rule: ValidationRuleSpec },
+    /// Clear validation from the target cells, shrinking or dropping rules.
+    Remove,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ValidationRuleSpec {
+    /// list | whole | decimal | date | time | text_length | custom | any.
+    /// Required when adding.
+    #[serde(default)]
+    pub validation_type: Option<String>,
+    /// between | not_between | equal | not_equal | greater_than | less_than |
+    /// greater_than_or_equal | less_than_or_equal (default between).
+    #[serde(default)]
+    pub operator: Option<String>,
+    /// Bound, list source (e.g. "Lists!$A$1:$A$9") or custom formula; a
+    /// leading "=" is optional.
+    #[serde(default)]
+    pub formula1: Option<String>,
+    #[serde(default)]
+    pub formula2: Option<String>,
+    /// Inline dropdown entries, written as a quoted `formula1`.
+    #[serde(default)]
+    pub list_values: Option<Vec<String>>,
+    #[serde(default)]
+    pub allow_blank: Option<bool>,
+    #[serde(default)]
+    pub prompt_title: Option<String>,
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// stop | warning | information
+    #[serde(default)]
+    pub error_style: Option<String>,
+    #[serde(default)]
+    pub error_title: Option<String>,
+    #[serde(default)]
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ValidationBatchResponse {
+    pub fork_id: String,
+    pub mode: String,
+    pub change_id: Option<String>,
+    pub ops_applied: usize,
+    pub summary: ChangeSummary,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ValidationBatchStagedPayload {
+    ops: Vec<ValidationOp>,
+}
+
+pub async fn validation_batch(
+    state: Arc<AppState>,
+    params: ValidationBatchParams,
+) -> Result<ValidationBatchResponse> {
+    let registry = state
+        .fork_registry()
+        .ok_or_else(|| anyhow!("fork registry not available"))?;
+
+    let fork_ctx = registry.get_fork(&params.fork_id)?;
+    let work_path = fork_ctx.work_path.clone();
+
+    // Resolve any region targets against current fork regions.
+    let fork_workbook_id = WorkbookId(params.fork_id.clone());
+    let workbook = state.open_workbook(&fork_workbook_id).await?;
+    let mut resolved_ops = Vec::with_capacity(params.ops.len());
+    for op in &params.ops {
+        let mut resolved = op.clone();
+        if let TransformTarget::Region { region_id } = &op.target {
+            let metrics = workbook.get_sheet_metrics(&op.sheet_name)?;
+            let regions = metrics.detected_regions();
+            let region = regions.iter().find(|r| r.id == *region_id).ok_or_else(|| {
+                anyhow!(
+                    "region_id {} not found on sheet '{}'",
+                    region_id,
+                    op.sheet_name
+                )
+            })?;
+            resolved.target = TransformTarget::Range {
+                range: region.bounds.clone(),
+            };
+        }
+        resolved_ops.push(resolved);
+    }
+
+    let mode = params
+        .mode
+        .as_deref()
+        .unwrap_or("apply")
+        .to_ascii_lowercase();
+
+    if mode == "preview" {
+        let change_id = make_short_random_id("chg", 12);
+        let snapshot_path = registry.staged_snapshot_path(&params.fork_id, &change_id)?;
+        fs::copy(&work_path, &snapshot_path)?;
+
+        let apply_result = tokio::task::spawn_blocking({
+            let ops = resolved_ops.clone();
+            let snapshot_path = snapshot_path.clone();
+            move || apply_validation_ops_to_file(&snapshot_path, &ops)
+        })
+        .await??;
+
+        let summary = apply_result.summary;
+        let staged_op = StagedOp {
+            kind: "validation_batch".to_string(),
+            payload: serde_json::to_value(ValidationBatchStagedPayload {
+                ops: resolved_ops.clone(),
+            })?,
+        };
+
+        let staged = StagedChange {
+            change_id: change_id.clone(),
+            created_at: Utc::now(),
+            label: params.label.clone(),
+            ops: vec![staged_op],
+            summary: summary.clone(),
+            fork_path_snapshot: Some(snapshot_path),
+        };
+
+        registry.add_staged_change(&params.fork_id, staged)?;
+
+        Ok(ValidationBatchResponse {
+            fork_id: params.fork_id,
+            mode,
+            change_id: Some(change_id),
+            ops_applied: apply_result.ops_applied,
+            summary,
+        })
+    } else {
+        let apply_result = tokio::task::spawn_blocking({
+            let ops = resolved_ops.clone();
+            move || apply_validation_ops_to_file(&work_path, &ops)
+        })
+        .await??;
+
+        let _ = state.close_workbook(&fork_workbook_id);
+        let _ = registry.mark_modified(&params.fork_id);
+
+        Ok(ValidationBatchResponse {
+            fork_id: params.fork_id,
+            mode,
+            change_id: None,
+            ops_applied: apply_result.ops_applied,
+            summary: apply_result.summary,
+        })
+    }
+}
+
+/// Violations listed per rule in the batch warnings.
+const MAX_REPORTED_VIOLATIONS: usize = 3;
+
+struct ValidationApplyResult {
+    ops_applied: usize,
+    summary: ChangeSummary,
+}
+
+fn validation_target_areas(target: &TransformTarget) -> Result<Vec<Area>> {
+    let areas = match target {
+        TransformTarget::Range { range } => {
+            let areas = parse_sqref(range);
+            if areas.is_empty() {
+                bail!("invalid range '{}'", range);
+            }
+            areas
+        }
+        TransformTarget::Cells { cells } => cells
+            .iter()
+            .map(|cell| {
+                Area::parse(cell)
+                    .filter(Area::is_single_cell)
+                    .ok_or_else(|| anyhow!("invalid cell address '{}'", cell))
+            })
+            .collect::<Result<_>>()?,
+        TransformTarget::Region { .. } => {
+            bail!("region_id targets must be resolved before apply_validation_ops_to_file")
+        }
+    };
+    Ok(areas)
+}
+
+fn strip_formula(formula: &str) -> String {
+    let formula = formula.trim();
+    formula.strip_prefix('=').unwrap_or(formula).to_string()
+}
+
+/// Write the fields present in `spec` onto `dv`, then check the result is a
+/// rule Excel would accept.
+fn apply_validation_spec(dv: &mut DataValidation, spec: &ValidationRuleSpec) -> Result<()> {
+    use crate::data_validation::{
+        MAX_INLINE_LIST_LEN, parse_error_style, parse_operator, parse_type, uses_operator,
+    };
+
+    if let Some(kind) = &spec.validation_type {
+        dv.set_type(parse_type(kind)?);
+    }
+    if let Some(operator) = &spec.operator {
+        dv.set_operator(parse_operator(operator)?);
+    }
+    if let Some(values) = &spec.list_values {
+        if spec.formula1.is_some() {
+            bail!("give either list_values or formula1, not both");
+        }
+        if values.iter().any(|v| v.contains(',') || v.contains('"')) {
+            bail!("inline list entries cannot contain commas or quotes; use a range source");
+        }
+        let joined = values.join(",");
+        if joined.chars().count() > MAX_INLINE_LIST_LEN {
+            bail!(
+                "inline list is longer than {} characters; use a range source",
+                MAX_INLINE_LIST_LEN
+            );
+        }
+        dv.set_type(DataValidationValues::List);
+        dv.set_formula1(format!("\"{}\"", joined));
+    }
+    if let Some(formula) = &spec.formula1 {
+        dv.set_formula1(strip_formula(formula));
+    }
+    if let Some(formula) = &spec.formula2 {
+        dv.set_formula2(strip_formula(formula));
+    }
+    if let Some(allow_blank) = spec.allow_blank {
+        dv.set_allow_blank(allow_blank);
+    }
+    if let Some(title) = &spec.prompt_title {
+        dv.set_prompt_title(title.clone());
+    }
+    if let Some(prompt) = &spec.prompt {
+        dv.set_prompt(prompt.clone());
+    }
+    if spec.prompt_title.is_some() || spec.prompt.is_some() {
+        dv.set_show_input_message(true);
+    }
+    if let Some(style) = &spec.error_style {
+        dv.set_error_style(parse_error_style(style)?);
+    }
+    if let Some(title) = &spec.error_title {
+        dv.set_error_title(title.clone());
+    }
+    if let Some(message) = &spec.error_message {
+        dv.set_error_message(message.clone());
+    }
+
+    let kind = dv.get_type().clone();
+    if matches!(kind, DataValidationValues::None) {
+        dv.set_formula1("");
+        dv.set_formula2("");
+        return Ok(());
+    }
+    if dv.get_formula1().is_empty() {
+        bail!(
+            "{} validation needs formula1{}",
+            crate::data_validation::type_name(&kind),
+            if matches!(kind, DataValidationValues::List) {
+                " or list_values"
+            } else {
+                ""
+            }
+        );
+    }
+    if uses_operator(&kind) {
+        let ranged = matches!(
+            dv.get_operator(),
+            DataValidationOperatorValues::Between | DataValidationOperatorValues::NotBetween
+        );
+        if ranged && dv.get_formula2().is_empty() {
+            bail!(
+                "operator '{}' needs formula2",
+                operator_name(dv.get_operator())
+            );
+        }
+        if !ranged {
+            dv.set_formula2("");
+        }
+    } else {
+        dv.set_formula2("");
+    }
+    Ok(())
+}
+
+fn apply_validation_ops_to_file(
+    path: &Path,
+    ops: &[ValidationOp],
+) -> Result<ValidationApplyResult> {
+    let mut book = umya_spreadsheet::reader::xlsx::read(path)?;
+
+    let mut sheets: BTreeSet<String> = BTreeSet::new();
+    let mut affected_bounds: Vec<String> = Vec::new();
+    let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+    // Final sqref of every rule added or modified, per sheet, for the violation check.
+    let mut authored: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+
+    for op in ops {
+        let sheet_name = op.sheet_name.as_str();
+        let areas = validation_target_areas(&op.target)?;
+        let sqref = format_sqref(&areas);
+        let sheet = book
+            .get_sheet_by_name_mut(sheet_name)
+            .ok_or_else(|| anyhow!("sheet '{}' not found", sheet_name))?;
+        let mut rules: Vec<DataValidation> = sheet
+            .get_data_validations()
+            .map(|v| v.get_data_validation_list().to_vec())
+            .unwrap_or_default();
+        let touched = authored.entry(sheet_name.to_string()).or_default();
+
+        match &op.action {
+            ValidationAction::Add { rule } => {
+                if rule.validation_type.is_none() && rule.list_values.is_none() {
+                    bail!("adding a rule needs validation_type");
+                }
+                let mut dv = DataValidation::default();
+                dv.set_allow_blank(true);
+                dv.set_show_error_message(true);
+                apply_validation_spec(&mut dv, rule)?;
+                dv.get_sequence_of_references_mut().set_sqref(sqref.clone());
+
+                let replaced = clear_validation_areas(&mut rules, &areas, touched);
+                if replaced > 0 {
+                    *counts.entry("rules_replaced".to_string()).or_insert(0) += replaced;
+                }
+                rules.push(dv);
+                touched.insert(sqref.clone());
+                *counts.entry("rules_added".to_string()).or_insert(0) += 1;
+            }
+            ValidationAction::Modify { rule } => {
+                let mut modified = 0u64;
+                for dv in rules.iter_mut() {
+                    let covered = dv.get_sequence_of_references().get_sqref();
+                    if !parse_sqref(&covered)
+                        .iter()
+                        .any(|a| areas.iter().any(|b| a.intersects(b)))
+                    {
+                        continue;
+                    }
+                    apply_validation_spec(dv, rule)?;
+                    touched.insert(covered);
+                    modified += 1;
+                }
+                if modified == 0 {
+                    bail!("no data validation covers {}!{}", sheet_name, sqref);
+                }
+                *counts.entry("rules_modified".to_string()).or_insert(0) += modified;
+            }
+            ValidationAction::Remove => {
+                let dropped = clear_validation_areas(&mut rules, &areas, touched);
+                *counts.entry("rules_removed".to_string()).or_insert(0) += dropped;
+            }
+        }
+
+        if rules.is_empty() {
+            sheet.remove_data_validations();
+        } else {
+            let mut validations = DataValidations::default();
+            *validations.get_data_validation_list_mut() = rules;
+            sheet.set_data_validations(validations);
+        }
+        sheets.insert(sheet_name.to_string());
+        affected_bounds.push(format!("{}!{}", sheet_name, sqref));
+    }
+
+    // Existing values are not touched; flag the ones the new rules reject.
+    let mut warnings = Vec::new();
+    for (sheet_name, ranges) in &authored {
+        let Some(sheet) = book.get_sheet_by_name(sheet_name) else {
+            continue;
+        };
+        let scan = scan_violations(&book, sheet, MAX_REPORTED_VIOLATIONS, |dv| {
+            ranges.contains(&dv.get_sequence_of_references().get_sqref())
+        });
+        if scan.total > 0 {
+            let examples: Vec<String> = scan
+                .violations
+                .iter()
+                .map(|v| format!("{} = {:?} ({})", v.address, v.value, v.reason))
+                .collect();
+            warnings.push(format!(
+                "{} existing value(s) on '{}' violate the edited rules, e.g. {}",
+                scan.total,
+                sheet_name,
+                examples.join("; ")
+            ));
+            *counts.entry("existing_violations".to_string()).or_insert(0) += scan.total as u64;
+        }
+        warnings.extend(
+            scan.unchecked_rules
+                .into_iter()
+                .map(|rule| format!("existing values not checked against {}", rule)),
+        );
+    }
+
+    umya_spreadsheet::writer::xlsx::write(&book, path)?;
+
+    Ok(ValidationApplyResult {
+        ops_applied: ops.len(),
+        summary: ChangeSummary {
+            op_kinds: vec!["validation_batch".to_string()],
+            affected_sheets: sheets.into_iter().collect(),
+            affected_bounds,
+            counts,
+            warnings,
+        },
+    })
+}
+
+/// Take `areas` out of every rule, dropping rules left empty. Returns how
+/// many rules were dropped; shrunk rules are recorded in `touched` only when
+/// they were authored earlier in the batch.
+fn clear_validation_areas(
+    rules: &mut Vec<DataValidation>,
+    areas: &[Area],
+    touched: &mut BTreeSet<String>,
+) -> u64 {
+    let mut dropped = 0u64;
+    rules.retain_mut(|dv| {
+        let before = dv.get_sequence_of_references().get_sqref();
+        match subtract_sqref(&before, areas) {
+            Some(after) if after == before => true,
+            Some(after) => {
+                if touched.remove(&before) {
+                    touched.insert(after.clone());
+                }
+                dv.get_sequence_of_references_mut().set_sqref(after);
+                true
+            }
+            None => {
+                touched.remove(&before);
+                dropped += 1;
+                false
+            }
+        }
+    });
+    dropped
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ConditionalFormatBatchParams {
+    pub fork_id: String,
+    pub ops: Vec<ConditionalFormatOp>,
+    #[serde(default)]
+    pub mode: Option<String>, // "preview" | "apply" (default apply)
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ConditionalFormatOp {
+    pub sheet_name: String,
+    pub target: StyleTarget,
+    #[serde(flatten)]
+    pub rule: ConditionalFormatRuleSpec,
+    /// Differential format applied when the rule matches. Required for
+    /// cell_is, expression, top_n and duplicate rules.
+    #[serde(default)]
+    pub style: Option<StylePatch>,
+    #[serde(default)]
+    pub stop_if_true: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ConditionalFormatBatchResponse {
+    pub fork_id: String,
+    pub mode: String,
+    pub change_id: Option<String>,
+    pub ops_applied: usize,
+    pub summary: ChangeSummary,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ConditionalFormatBatchStagedPayload {
+    ops: Vec<ConditionalFormatOp>,
+}
+
+pub async fn conditional_format_batch(
+    state: Arc<AppState>,
+    params: ConditionalFormatBatchParams,
+) -> Result<ConditionalFormatBatchResponse> {
+    let registry = state
+        .fork_registry()
+        .ok_or_else(|| anyhow!("fork registry not available"))?;
+
+    let fork_ctx = registry.get_fork(&params.fork_id)?;
+    let work_path = fork_ctx.work_path.clone();
+
+    // Resolve any region targets against current fork regions.
+    let fork_workbook_id = WorkbookId(params.fork_id.clone());
+    let workbook = state.open_workbook(&fork_workbook_id).await?;
+    let mut resolved_ops = Vec::with_capacity(params.ops.len());
+    for op in &params.ops {
+        let mut resolved = op.clone();
+        if let StyleTarget::Region { region_id } = &op.target {
+            let metrics = workbook.get_sheet_metrics(&op.sheet_name)?;
+            let regions = metrics.detected_regions();
+            let region = regions.iter().find(|r| r.id == *region_id).ok_or_else(|| {
+                anyhow!(
+                    "region_id {} not found on sheet '{}'",
+                    region_id,
+                    op.sheet_name
+                )
+            })?;
+            resolved.target = StyleTarget::Range {
+                range: region.bounds.clone(),
+            };
+        }
+        resolved_ops.push(resolved);
+    }
+
+    let mode = params
+        .mode
+        .as_deref()
+        .unwrap_or("apply")
+        .to_ascii_lowercase();
+
+    if mode == "preview" {
+        let change_id = make_short_random_id("chg", 12);
+        let snapshot_path = registry.staged_snapshot_path(&params.fork_id, &change_id)?;
+        fs::copy(&work_path, &snapshot_path)?;
+
+        let apply_result = tokio::task::spawn_blocking({
+            let ops = resolved_ops.clone();
+            let snapshot_path = snapshot_path.clone();
+            move || apply_conditional_format_ops_to_file(&snapshot_path, &ops)
+        })
+        .await??;
+
+        let summary = apply_result.summary;
+        let staged_op = StagedOp {
+            kind: "conditional_format_batch".to_string(),
+            payload: serde_json::to_value(ConditionalFormatBatchStagedPayload {
+                ops: resolved_ops.clone(),
+            })?,
+        };
+
+        let staged = StagedChange {
+            change_id: change_id.clone(),
+            created_at: Utc::now(),
+            label: params.label.clone(),
+            ops: vec![staged_op],
+            summary: summary.clone(),
+            fork_path_snapshot: Some(snapshot_path),
+        };
+
+        registry.add_staged_change(&params.fork_id, staged)?;
+
+        Ok(ConditionalFormatBatchResponse {
+            fork_id: params.fork_id,
+            mode,
+            change_id: Some(change_id),
+            ops_applied: apply_result.ops_applied,
+            summary,
+        })
+    } else {
+        let apply_result = tokio::task::spawn_blocking({
+            let ops = resolved_ops.clone();
+            move || apply_conditional_format_ops_to_file(&work_path, &ops)
+        })
+        .await??;
+
+        let _ = state.close_workbook(&fork_workbook_id);
+        let _ = registry.mark_modified(&params.fork_id);
+
+        Ok(ConditionalFormatBatchResponse {
+            fork_id: params.fork_id,
+            mode,
+            change_id: None,
+            ops_applied: apply_result.ops_applied,
+            summary: apply_result.summary,
+        })
+    }
+}
+
+struct ConditionalFormatApplyResult {
+    ops_applied: usize,
+    summary: ChangeSummary,
+}
+
+fn conditional_format_target_areas(target: &StyleTarget) -> Result<Vec<Area>> {
+    let areas = match target {
+        StyleTarget::Range { range } => {
+            let areas = parse_sqref(range);
+            if areas.is_empty() {
+                bail!("invalid range '{}'", range);
+            }
+            areas
+        }
+        StyleTarget::Cells { cells } => cells
+            .iter()
+            .map(|cell| {
+                Area::parse(cell)
+                    .filter(Area::is_single_cell)
+                    .ok_or_else(|| anyhow!("invalid cell address '{}'", cell))
+            })
+            .collect::<Result<_>>()?,
+        StyleTarget::Region { .. } => {
+            bail!("region_id targets must be resolved before apply_conditional_format_ops_to_file")
+        }
+    };
+    Ok(areas)
+}
+
+fn apply_conditional_format_ops_to_file(
+    path: &Path,
+    ops: &[ConditionalFormatOp],
+) -> Result<ConditionalFormatApplyResult> {
+    let mut book = umya_spreadsheet::reader::xlsx::read(path)?;
+
+    let mut sheets: BTreeSet<String> = BTreeSet::new();
+    let mut affected_bounds: Vec<String> = Vec::new();
+    let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+
+    for op in ops {
+        let sheet_name = op.sheet_name.as_str();
+        let areas = conditional_format_target_areas(&op.target)?;
+        let sqref = format_sqref(&areas);
+        let rule =
+            conditional_formats::build_rule(&op.rule, &areas, op.style.as_ref(), op.stop_if_true)?;
+        let sheet = book
+            .get_sheet_by_name_mut(sheet_name)
+            .ok_or_else(|| anyhow!("sheet '{}' not found", sheet_name))?;
+
+        let count = match rule {
+            Some(rule) => {
+                conditional_formats::insert_rule(sheet, &sqref, rule);
+                1
+            }
+            None => conditional_formats::clear_areas(sheet, &areas),
+        };
+        *counts
+            .entry(conditional_formats::spec_count_key(&op.rule).to_string())
+            .or_insert(0) += count;
+        sheets.insert(sheet_name.to_string());
+        affected_bounds.push(format!("{}!{}", sheet_name, sqref));
+    }
+
+    umya_spreadsheet::writer::xlsx::write(&book, path)?;
+
+    Ok(ConditionalFormatApplyResult {
+        ops_applied: ops.len(),
+        summary: ChangeSummary {
+            op_kinds: vec!["conditional_format_batch".to_string()],
+            affected_sheets: sheets.into_iter().collect(),
+            affected_bounds,
+            counts,
+            warnings: Vec::new(),
+        },
+    })
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ChartBatchParams {
+    pub fork_id: String,
+    pub ops: Vec<ChartOp>,
+    #[serde(default)]
+    pub mode: Option<String>, // "preview" | "apply" (default apply)
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ChartBatchResponse {
+    pub fork_id: String,
+    pub mode: String,
+    pub change_id: Option<String>,
+    pub ops_applied: usize,
+    pub summary: ChangeSummary,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChartBatchStagedPayload {
+    ops: Vec<ChartOp>,
+}
+
+pub async fn chart_batch(
+    state: Arc<AppState>,
+    params: ChartBatchParams,
+) -> Result<ChartBatchResponse> {
+    let registry = state
+        .fork_registry()
+        .ok_or_else(|| anyhow!("fork registry not available"))?;
+
+    let fork_ctx = registry.get_fork(&params.fork_id)?;
+    let work_path = fork_ctx.work_path.clone();
+
+    let mode = params
+        .mode
+        .as_deref()
+        .unwrap_or("apply")
+        .to_ascii_lowercase();
+
+    if mode == "preview" {
+        let change_id = make_short_random_id("chg", 12);
+        let snapshot_path = registry.staged_snapshot_path(&params.fork_id, &change_id)?;
+        fs::copy(&work_path, &snapshot_path)?;
+
+        let apply_result = tokio::task::spawn_blocking({
+            let ops = params.ops.clone();
+            let snapshot_path = snapshot_path.clone();
+            move || apply_chart_ops_to_file(&snapshot_path, &ops)
+        })
+        .await??;
+
+        let summary = apply_result.summary;
+        let staged_op = StagedOp {
+            kind: "chart_batch".to_string(),
+            payload: serde_json::to_value(ChartBatchStagedPayload {
+                ops: params.ops.clone(),
+            })?,
+        };
+
+        let staged = StagedChange {
+            change_id: change_id.clone(),
+            created_at: Utc::now(),
+            label: params.label.clone(),
+            ops: vec![staged_op],
+            summary: summary.clone(),
+            fork_path_snapshot: Some(snapshot_path),
+        };
+
+        registry.add_staged_change(&params.fork_id, staged)?;
+
+        Ok(ChartBatchResponse {
+            fork_id: params.fork_id,
+            mode,
+            change_id: Some(change_id),
+            ops_applied: apply_result.ops_applied,
+            summary,
+        })
+    } else {
+        let apply_result = tokio::task::spawn_blocking({
+            let ops = params.ops.clone();
+            move || apply_chart_ops_to_file(&work_path, &ops)
+        })
+        .await??;
+
+        let fork_workbook_id = WorkbookId(params.fork_id.clone());
+        let _ = state.close_workbook(&fork_workbook_id);
+        let _ = registry.mark_modified(&params.fork_id);
+
+        Ok(ChartBatchResponse {
+            fork_id: params.fork_id,
+            mode,
+            change_id: None,
+            ops_applied: apply_result.ops_applied,
+            summary: apply_result.summary,
+        })
+    }
+}
+
+struct ChartApplyResult {
+    ops_applied: usize,
+    summary: ChangeSummary,
+}
+
+fn apply_chart_ops_to_file(path: &Path, ops: &[ChartOp]) -> Result<ChartApplyResult> {
+    // Read-only: series references are resolved and cached from the cells.
+    let book = umya_spreadsheet::reader::xlsx::read(path)?;
+    let mut package = package::Package::open(path)?;
+
+    let mut sheets: BTreeSet<String> = BTreeSet::new();
+    let mut affected_bounds: Vec<String> = Vec::new();
+    let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+    let mut warnings: Vec<String> = Vec::new();
+
+    for op in ops {
+        let sheet_name = op.sheet_name();
+        match op {
+            ChartOp::Create {
+                chart_type,
+                source,
+                series_in_rows,
+                series,
+                anchor,
+                anchor_to,
+                title,
+                x_axis_title,
+                y_axis_title,
+                ..
+            } => {
+                let resolved = match (source, series) {
+                    (Some(source), None) => {
+                        charts::series_from_source(&book, sheet_name, source, *series_in_rows)?
+                    }
+                    (None, Some(series)) => charts::resolve_series(&book, sheet_name, series)?,
+                    _ => bail!("create needs exactly one of 'source' or 'series'"),
+                };
+                if *chart_type == ChartKind::Pie && resolved.len() > 1 {
+                    warnings.push(format!(
+                        "pie chart at {}!{} shows only its first of {} series",
+                        sheet_name,
+                        anchor,
+                        resolved.len()
+                    ));
+                }
+                if *chart_type == ChartKind::Pie
+                    && (x_axis_title.is_some() || y_axis_title.is_some())
+                {
+                    warnings.push(format!(
+                        "pie chart at {}!{} has no axes; axis titles were ignored",
+                        sheet_name, anchor
+                    ));
+                }
+                let bounds = package.create(
+                    sheet_name,
+                    *chart_type,
+                    &resolved,
+                    anchor,
+                    anchor_to.as_deref(),
+                    &charts::ChartTitles {
+                        title: title.clone(),
+                        x_axis: x_axis_title.clone(),
+                        y_axis: y_axis_title.clone(),
+                    },
+                )?;
+                affected_bounds.push(format!("{}!{}", sheet_name, bounds));
+                *counts.entry("charts_created".to_string()).or_insert(0) += 1;
+            }
+            ChartOp::Update {
+                chart_index,
+                title,
+                x_axis_title,
+                y_axis_title,
+                series,
+                ..
+            } => {
+                let resolved = series
+                    .as_deref()
+                    .map(|series| charts::resolve_series(&book, sheet_name, series))
+                    .transpose()?;
+                warnings.extend(package.update(
+                    sheet_name,
+                    *chart_index,
+                    &charts::ChartTitles {
+                        title: title.clone(),
+                        x_axis: x_axis_title.clone(),
+                        y_axis: y_axis_title.clone(),
+                    },
+                    resolved.as_deref(),
+                )?);
+                *counts.entry("charts_updated".to_string()).or_insert(0) += 1;
+            }
+            ChartOp::Delete { chart_index, .. } => {
+                package.delete(sheet_name, *chart_index)?;
+                *counts.entry("charts_deleted".to_string()).or_insert(0) += 1;
+            }
+        }
+        sheets.insert(sheet_name.to_string());
+    }
+
+    package.save(path)?;
+
+    Ok(ChartApplyResult {
+        ops_applied: ops.len(),
+        summary: ChangeSummary {
+            op_kinds: vec!["chart_batch".to_string()],
+            affected_sheets: sheets.into_iter().collect(),
+            affected_bounds,
+            counts,
+            warnings,
+        },
+    })
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SheetSettingsBatchParams {
+    pub fork_id: String,
+    pub ops: Vec<SheetSettingsOp>,
+    #[serde(default)]
+    pub mode: Option<String>, // "preview" | "apply" (default apply)
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SheetSettingsBatchResponse {
+    pub fork_id: String,
+    pub mode: String,
+    pub change_id: Option<String>,
+    pub ops_applied: usize,
+    pub summary: ChangeSummary,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SheetSettingsBatchStagedPayload {
+    ops: Vec<SheetSettingsOp>,
+}
+
+pub async fn sheet_settings_batch(
+    state: Arc<AppState>,
+    params: SheetSettingsBatchParams,
+) -> Result<SheetSettingsBatchResponse> {
+    let registry = state
+        .fork_registry()
+        .ok_or_else(|| anyhow!("fork registry not available"))?;
+
+    let fork_ctx = registry.get_fork(&params.fork_id)?;
+    let work_path = fork_ctx.work_path.clone();
+
+    let mode = params
+        .mode
+        .as_deref()
+        .unwrap_or("apply")
+        .to_ascii_lowercase();
+
+    if mode == "preview" {
+        let change_id = make_short_random_id("chg", 12);
+        let snapshot_path = registry.staged_snapshot_path(&params.fork_id, &change_id)?;
+        fs::copy(&work_path, &snapshot_path)?;
+
+        let apply_result = tokio::task::spawn_blocking({
+            let ops = params.ops.clone();
+            let snapshot_path = snapshot_path.clone();
+            move || apply_sheet_settings_ops_to_file(&snapshot_path, &ops)
+        })
+        .await??;
+
+        let summary = apply_result.summary;
+        let staged_op = StagedOp {
+            kind: "sheet_settings_batch".to_string(),
+            payload: serde_json::to_value(SheetSettingsBatchStagedPayload {
+                ops: params.ops.clone(),
+            })?,
+        };
+
+        let staged = StagedChange {
+            change_id: change_id.clone(),
+            created_at: Utc::now(),
+            label: params.label.clone(),
+            ops: vec![staged_op],
+            summary: summary.clone(),
+            fork_path_snapshot: Some(snapshot_path),
+        };
+
+        registry.add_staged_change(&params.fork_id, staged)?;
+
+        Ok(SheetSettingsBatchResponse {
+            fork_id: params.fork_id,
+            mode,
+            change_id: Some(change_id),
+            ops_applied: apply_result.ops_applied,
+            summary,
+        })
+    } else {
+        let apply_result = tokio::task::spawn_blocking({
+            let ops = params.ops.clone();
+            move || apply_sheet_settings_ops_to_file(&work_path, &ops)
+        })
+        .await??;
+
+        let fork_workbook_id = WorkbookId(params.fork_id.clone());
+        let _ = state.close_workbook(&fork_workbook_id);
+        let _ = registry.mark_modified(&params.fork_id);
+
+        Ok(SheetSettingsBatchResponse {
+            fork_id: params.fork_id,
+            mode,
+            change_id: None,
+            ops_applied: apply_result.ops_applied,
+            summary: apply_result.summary,
+        })
+    }
+}
+
+struct SheetSettingsApplyResult {
+    ops_applied: usize,
+    summary: ChangeSummary,
+}
+
+fn apply_sheet_settings_ops_to_file(
+    path: &Path,
+    ops: &[SheetSettingsOp],
+) -> Result<SheetSettingsApplyResult> {
+    let mut package = package::Package::open(path)?;
+
+    let mut sheets: BTreeSet<String> = BTreeSet::new();
+    let mut affected_bounds: Vec<String> = Vec::new();
+    let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+    let mut unlocked: BTreeSet<String> = BTreeSet::new();
+
+    for op in ops {
+        let changed = package.apply_sheet_settings(op)?;
+        *counts.entry(op.kind().to_string()).or_insert(0) += 1;
+        if changed > 0 {
+            *counts.entry("cells_changed".to_string()).or_insert(0) += changed;
+        }
+        if let Some(sheet_name) = op.sheet_name() {
+            sheets.insert(sheet_name.to_string());
+            if let Some(range) = op.range() {
+                affected_bounds.push(format!("{}!{}", sheet_name, range));
+            }
+        }
+        if let SheetSettingsOp::SetCellLock {
+            sheet_name,
+            locked: false,
+            ..
+        } = op
+        {
+            unlocked.insert(sheet_name.clone());
+        }
+    }
+
+    package.save(path)?;
+
+    // Unlocking only matters once the sheet is protected.
+    let mut warnings = Vec::new();
+    for sheet_name in unlocked {
+        let settings =
+            crate::sheet_settings::read_workbook_settings(path, Some(sheet_name.as_str()))?;
+        if settings.sheets.iter().any(|(_, s)| s.protection.is_none()) {
+            warnings.push(format!(
+                "sheet '{}' is not protected, so cell locks have no effect until protect_sheet",
+                sheet_name
+            ));
+        }
+    }
+
+    Ok(SheetSettingsApplyResult {
+        ops_applied: ops.len(),
+        summary: ChangeSummary {
+            op_kinds: vec!["sheet_settings_batch".to_string()],
+            affected_sheets: sheets.into_iter().collect(),
+            affected_bounds,
+            counts,
+            warnings,
+        },
+    })
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ApplyFormulaPatternParams {
+    pub fork_id: String,
+    pub sheet_name: String,
+    pub target_range: String,
+    pub anchor_cell: String,
+    pub base_formula: String,
+    #[serde(default)]
+    pub fill_direction: Option<String>, // down|right|both (default both)
+    #[serde(default)]
+    pub relative_mode: Option<String>, // excel|abs_cols|abs_rows
+    #[serde(default)]
+    pub mode: Option<String>, // preview|apply (default apply)
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ApplyFormulaPatternResponse {
+    pub fork_id: String,
+    pub sheet_name: String,
+    pub target_range: String,
+    pub mode: String,
+    pub change_id: Option<String>,
+    pub cells_filled: u64,
+    pub summary: ChangeSummary,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ApplyFormulaPatternStagedPayload {
+    sheet_name: String,
+    target_range: String,
+    anchor_cell: String,
+    base_formula: String,
+    fill_direction: Option<String>,
+    relative_mode: Option<String>,
+}
+
+pub async fn apply_formula_pattern(
+    state: Arc<AppState>,
+    params: ApplyFormulaPatternParams,
+) -> Result<ApplyFormulaPatternResponse> {
+    let registry = state
+        .fork_registry()
+        .ok_or_else(|| anyhow!("fork registry not available"))?;
+
+    let fork_ctx = registry.get_fork(&params.fork_id)?;
+    let work_path = fork_ctx.work_path.clone();
+
+    let bounds = parse_range_bounds(&params.target_range)?;
+    let (anchor_col, anchor_row) = parse_cell_ref(&params.anchor_cell)?;
+    validate_formula_pattern_bounds(
+        &bounds,
+        anchor_col,
+        anchor_row,
+        params.fill_direction.as_deref(),
+    )?;
+
+    let fork_workbook_id = WorkbookId(params.fork_id.clone());
+    let workbook = state.open_workbook(&fork_workbook_id).await?;
+    let _ = workbook.with_sheet(&params.sheet_name, |_| Ok::<_, anyhow::Error>(()))?;
+
+    let relative_mode = RelativeMode::parse(params.relative_mode.as_deref())?;
+    let mode = params
+        .mode
+        .as_deref()
+        .unwrap_or("apply")
+        .to_ascii_lowercase();
+
+    if mode == "preview" {
+        let change_id = make_short_random_id("chg", 12);
+        let snapshot_path = registry.staged_snapshot_path(&params.fork_id, &change_id)?;
+        fs::copy(&work_path, &snapshot_path)?;
+
+        let sheet_name = params.sheet_name.clone();
+        let target_range = params.target_range.clone();
+        let anchor_cell = params.anchor_cell.clone();
+        let base_formula = params.base_formula.clone();
+        let fill_direction = params.fill_direction.clone();
+        let relative_mode_str = params.relative_mode.clone();
+        let snapshot_for_apply = snapshot_path.clone();
+        let sheet_name_for_apply = sheet_name.clone();
+        let target_range_for_apply = target_range.clone();
+        let base_formula_for_apply = base_formula.clone();
+
+        let apply_result = tokio::task::spawn_blocking(move || {
+            apply_formula_pattern_to_file(
+                &snapshot_for_apply,
+                &sheet_name_for_apply,
+                &target_range_for_apply,
+                anchor_col,
+                anchor_row,
+                &base_formula_for_apply,
+                relative_mode,
+            )
+        })
+        .await??;
+
+        let mut summary = apply_result.summary;
+        summary.op_kinds = vec!["apply_formula_pattern".to_string()];
+
+        let staged_op = StagedOp {
+            kind: "apply_formula_pattern".to_string(),
+            payload: serde_json::to_value(ApplyFormulaPatternStagedPayload {
+                sheet_name: sheet_name.clone(),
+                target_range: target_range.clone(),
+                anchor_cell: anchor_cell.clone(),
+                base_formula: base_formula.clone(),
+                fill_direction,
+                relative_mode: relative_mode_str,
+            })?,
+        };
+
+        let staged = StagedChange {
+            change_id: change_id.clone(),
+            created_at: Utc::now(),
+            label: params.label.clone(),
+            ops: vec![staged_op],
+            summary: summary.clone(),
+            fork_path_snapshot: Some(snapshot_path),
+        };
+
+        registry.add_staged_change(&params.fork_id, staged)?;
+
+        Ok(ApplyFormulaPatternResponse {
+            fork_id: params.fork_id,
+            sheet_name,
+            target_range,
+            mode,
+            change_id: Some(change_id),
+            cells_filled: apply_result.cells_filled,
+            summary,
+        })
+    } else {
+        let sheet_name = params.sheet_name.clone();
+        let target_range = params.target_range.clone();
+        let base_formula = params.base_formula.clone();
+        let sheet_name_for_apply = sheet_name.clone();
+        let target_range_for_apply = target_range.clone();
+        let base_formula_for_apply = base_formula.clone();
+        let apply_result = tokio::task::spawn_blocking(move || {
+            apply_formula_pattern_to_file(
+                &work_path,
+                &sheet_name_for_apply,
+                &target_range_for_apply,
+                anchor_col,
+                anchor_row,
+                &base_formula_for_apply,
+                relative_mode,
+            )
+        })
+        .await??;
+
+        let mut summary = apply_result.summary;
+        summary.op_kinds = vec!["apply_formula_pattern".to_string()];
+
+        let _ = state.close_workbook(&fork_workbook_id);
+        let _ = registry.mark_modified(&params.fork_id);
+
+        Ok(ApplyFormulaPatternResponse {
+            fork_id: params.fork_id,
+            sheet_name,
+            target_range,
+            mode,
+            change_id: None,
+            cells_filled: apply_result.cells_filled,
+            summary,
+        })
+    }
+}
+
+struct FormulaPatternApplyResult {
+    cells_filled: u64,
+    summary: ChangeSummary,
+}
+
+fn apply_formula_pattern_to_file(
+    path: &Path,
+    sheet_name: &str,
+    target_range: &str,
+    anchor_col: u32,
+    anchor_row: u32,
+    base_formula: &str,
+    relative_mode: RelativeMode,
+) -> Result<FormulaPatternApplyResult> {
+    let ast = parse_base_formula(base_formula)?;
+    let bounds = parse_range_bounds(target_range)?;
+
+    let mut book = umya_spreadsheet::reader::xlsx::read(path)?;
+    let sheet = book
+        .get_sheet_by_name_mut(sheet_name)
+        .ok_or_else(|| anyhow!("sheet '{}' not found", sheet_name))?;
+
+    let mut cells_filled: u64 = 0;
+    for row in bounds.min_row..=bounds.max_row {
+        for col in bounds.min_col..=bounds.max_col {
+            let delta_col = col as i32 - anchor_col as i32;
+            let delta_row = row as i32 - anchor_row as i32;
+            let shifted = shift_formula_ast(&ast, delta_col, delta_row, relative_mode)?;
+            let shifted_for_umya = shifted.strip_prefix('=').unwrap_or(&shifted);
+            let addr = crate::utils::cell_address(col, row);
+            sheet
+                .get_cell_mut(addr.as_str())
+                .set_formula(shifted_for_umya.to_string());
+            cells_filled += 1;
+        }
+    }
+
+    umya_spreadsheet::writer::xlsx::write(&book, path)?;
+
+    let mut counts = BTreeMap::new();
+    counts.insert("cells_filled".to_string(), cells_filled);
+
+    let summary = ChangeSummary {
+        op_kinds: vec!["apply_formula_pattern".to_string()],
+        affected_sheets: vec![sheet_name.to_string()],
+        affected_bounds: vec![target_range.to_string()],
+        counts,
+        warnings: Vec::new(),
+    };
+
+    Ok(FormulaPatternApplyResult {
+        cells_filled,
+        summary,
+    })
+}
+
+fn validate_formula_pattern_bounds(
+    bounds: &ScreenshotBounds,
+    anchor_col: u32,
+    anchor_row: u32,
+    fill_direction: Option<&str>,
+) -> Result<()> {
+    if anchor_col < bounds.min_col
+        || anchor_col > bounds.max_col
+        || anchor_row < bounds.min_row
+        || anchor_row > bounds.max_row
+    {
+        let bounds_range = format!(
+            "{}:{}",
+            crate::utils::cell_address(bounds.min_col, bounds.min_row),
+            crate::utils::cell_address(bounds.max_col, bounds.max_row)
+        );
+        bail!(
+            "anchor_cell must be inside target_range (anchor {} not within {})",
+            crate::utils::cell_address(anchor_col, anchor_row),
+            bounds_range
+        );
+    }
+
+    if bounds.min_col != anchor_col || bounds.min_row != anchor_row {
+        bail!("target_range must start at anchor_cell (anchor should be top-left of fill range)");
+    }
+
+    let dir = fill_direction.unwrap_or("both").to_ascii_lowercase();
+    match dir.as_str() {
+        "down" => {
+            if bounds.min_col != bounds.max_col {
+                bail!("fill_direction=down requires a single-column target_range");
+            }
+        }
+        "right" => {
+            if bounds.min_row != bounds.max_row {
+                bail!("fill_direction=right requires a single-row target_range");
+            }
+        }
+        "both" => {}
+        other => bail!("invalid fill_direction: {}", other),
     }
     Ok(())
 }
@@ -865,6 +2279,60 @@ pub enum StructureOp {
         include_styles: bool,
         include_formulas: bool,
     },
+    /// Turn a range into a table; its first row becomes the header row.
+    CreateTable {
+        sheet_name: String,
+        range: String,
+        #[serde(default)]
+        table_name: Option<String>,
+        #[serde(default)]
+        style: Option<String>,
+    },
+    RenameTable {
+        table_name: String,
+        new_name: String,
+    },
+    /// The header row must stay on the same row and the new range must
+    /// overlap the current one.
+    ResizeTable {
+        table_name: String,
+        range: String,
+    },
+    AddTableColumn {
+        table_name: String,
+        column_name: String,
+        /// 0-based column position (default: after the last column).
+        #[serde(default)]
+        position: Option<u32>,
+    },
+    RemoveTableColumn {
+        table_name: String,
+        column_name: String,
+    },
+    /// Show or hide the totals row. `functions` maps column names to sum |
+    /// average | count | count_nums | max | min | std_dev | var | none.
+    SetTableTotals {
+        table_name: String,
+        show: bool,
+        #[serde(default)]
+        functions: BTreeMap<String, String>,
+        #[serde(default)]
+        label: Option<String>,
+    },
+    /// Change the table style (e.g. "TableStyleLight9", or "none") and banding.
+    SetTableStyle {
+        table_name: String,
+        #[serde(default)]
+        style: Option<String>,
+        #[serde(default)]
+        show_row_stripes: Option<bool>,
+        #[serde(default)]
+        show_column_stripes: Option<bool>,
+        #[serde(default)]
+        show_first_column: Option<bool>,
+        #[serde(default)]
+        show_last_column: Option<bool>,
+    },
 }
 
 #[derive(Debug, Serialize, JsonSchema)]
@@ -874,6 +2342,17 @@ pub struct StructureBatchResponse {
     pub change_id: Option<String>,
     pub ops_applied: usize,
     pub summary: ChangeSummary,
+    /// Every formula, name and range-bearing object whose references changed.
+    pub rewrites: Vec<ReferenceRewrite>,
+    pub rewrites_truncated: bool,
+}
+
+const MAX_REPORTED_REWRITES: usize = 500;
+
+fn truncate_rewrites(mut rewrites: Vec<ReferenceRewrite>) -> (Vec<ReferenceRewrite>, bool) {
+    let truncated = rewrites.len() > MAX_REPORTED_REWRITES;
+    rewrites.truncate(MAX_REPORTED_REWRITES);
+    (rewrites, truncated)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -900,8 +2379,7 @@ pub async fn structure_batch(
 
     if mode == "preview" {
         let change_id = make_short_random_id("chg", 12);
-        let snapshot_path = stage_snapshot_path(&params.fork_id, &change_id);
-        fs::create_dir_all(snapshot_path.parent().unwrap())?;
+        let snapshot_path = registry.staged_snapshot_path(&params.fork_id, &change_id)?;
         fs::copy(&work_path, &snapshot_path)?;
 
         let snapshot_for_apply = snapshot_path.clone();
@@ -954,12 +2432,15 @@ pub async fn structure_batch(
 
         registry.add_staged_change(&params.fork_id, staged)?;
 
+        let (rewrites, rewrites_truncated) = truncate_rewrites(apply_result.rewrites);
         Ok(StructureBatchResponse {
             fork_id: params.fork_id,
             mode,
             change_id: Some(change_id),
             ops_applied: apply_result.ops_applied,
             summary,
+            rewrites,
+            rewrites_truncated,
         })
     } else {
         let ops_for_apply = params.ops.clone();
@@ -973,13 +2454,17 @@ pub async fn structure_batch(
 
         let fork_workbook_id = WorkbookId(params.fork_id.clone());
         let _ = state.close_workbook(&fork_workbook_id);
+        let _ = registry.mark_modified(&params.fork_id);
 
+        let (rewrites, rewrites_truncated) = truncate_rewrites(apply_result.rewrites);
         Ok(StructureBatchResponse {
             fork_id: params.fork_id,
             mode,
             change_id: None,
             ops_applied: apply_result.ops_applied,
             summary,
+            rewrites,
+            rewrites_truncated,
         })
     }
 }
@@ -987,6 +2472,7 @@ pub async fn structure_batch(
 struct StructureApplyResult {
     ops_applied: usize,
     summary: ChangeSummary,
+    rewrites: Vec<ReferenceRewrite>,
 }
 
 fn apply_structure_ops_to_file(path: &Path, ops: &[StructureOp]) -> Result<StructureApplyResult> {
@@ -995,9 +2481,8 @@ fn apply_structure_ops_to_file(path: &Path, ops: &[StructureOp]) -> Result<Struc
     let mut affected_sheets: BTreeSet<String> = BTreeSet::new();
     let affected_bounds: Vec<String> = Vec::new();
     let mut counts: BTreeMap<String, u64> = BTreeMap::new();
-    let mut warnings: Vec<String> = vec![
-        "Structural edits may not fully rewrite formulas/named ranges like Excel. After apply, run recalculate and review get_changeset.".to_string(),
-    ];
+    let mut warnings: Vec<String> = Vec::new();
+    let mut log = RewriteLog::default();
 
     for op in ops {
         match op {
@@ -1009,15 +2494,16 @@ fn apply_structure_ops_to_file(path: &Path, ops: &[StructureOp]) -> Result<Struc
                 if *at_row == 0 || *count == 0 {
                     bail!("insert_rows requires at_row>=1 and count>=1");
                 }
-                {
-                    let sheet = book
-                        .get_sheet_by_name_mut(sheet_name)
-                        .ok_or_else(|| anyhow!("sheet '{}' not found", sheet_name))?;
-                    sheet.insert_new_row(at_row, count);
-                }
-                rewrite_formulas_for_sheet_row_insert(&mut book, sheet_name, *at_row, *count)?;
-                rewrite_defined_name_formulas_for_sheet_row_insert(
-                    &mut book, sheet_name, *at_row, *count,
+                references::shift_sheet(
+                    &mut book,
+                    sheet_name,
+                    StructureAxis::Row,
+                    StructureEdit::Insert {
+                        at: *at_row,
+                        count: *count,
+                    },
+                    &mut log,
+                    |sheet| sheet.insert_new_row(at_row, count),
                 )?;
                 affected_sheets.insert(sheet_name.clone());
                 counts
@@ -1033,15 +2519,16 @@ fn apply_structure_ops_to_file(path: &Path, ops: &[StructureOp]) -> Result<Struc
                 if *start_row == 0 || *count == 0 {
                     bail!("delete_rows requires start_row>=1 and count>=1");
                 }
-                {
-                    let sheet = book
-                        .get_sheet_by_name_mut(sheet_name)
-                        .ok_or_else(|| anyhow!("sheet '{}' not found", sheet_name))?;
-                    sheet.remove_row(start_row, count);
-                }
-                rewrite_formulas_for_sheet_row_delete(&mut book, sheet_name, *start_row, *count)?;
-                rewrite_defined_name_formulas_for_sheet_row_delete(
-                    &mut book, sheet_name, *start_row, *count,
+                references::shift_sheet(
+                    &mut book,
+                    sheet_name,
+                    StructureAxis::Row,
+                    StructureEdit::Delete {
+                        start: *start_row,
+                        count: *count,
+                    },
+                    &mut log,
+                    |sheet| sheet.remove_row(start_row, count),
                 )?;
                 affected_sheets.insert(sheet_name.clone());
                 counts
@@ -1060,15 +2547,16 @@ fn apply_structure_ops_to_file(path: &Path, ops: &[StructureOp]) -> Result<Struc
                 let col_letters = normalize_col_letters(at_col)?;
                 let root_col =
                     umya_spreadsheet::helper::coordinate::column_index_from_string(&col_letters);
-                {
-                    let sheet = book
-                        .get_sheet_by_name_mut(sheet_name)
-                        .ok_or_else(|| anyhow!("sheet '{}' not found", sheet_name))?;
-                    sheet.insert_new_column(&col_letters, count);
-                }
-                rewrite_formulas_for_sheet_col_insert(&mut book, sheet_name, root_col, *count)?;
-                rewrite_defined_name_formulas_for_sheet_col_insert(
-                    &mut book, sheet_name, root_col, *count,
+                references::shift_sheet(
+                    &mut book,
+                    sheet_name,
+                    StructureAxis::Col,
+                    StructureEdit::Insert {
+                        at: root_col,
+                        count: *count,
+                    },
+                    &mut log,
+                    |sheet| sheet.insert_new_column(&col_letters, count),
                 )?;
                 affected_sheets.insert(sheet_name.clone());
                 counts
@@ -1087,15 +2575,16 @@ fn apply_structure_ops_to_file(path: &Path, ops: &[StructureOp]) -> Result<Struc
                 let col_letters = normalize_col_letters(start_col)?;
                 let root_col =
                     umya_spreadsheet::helper::coordinate::column_index_from_string(&col_letters);
-                {
-                    let sheet = book
-                        .get_sheet_by_name_mut(sheet_name)
-                        .ok_or_else(|| anyhow!("sheet '{}' not found", sheet_name))?;
-                    sheet.remove_column(&col_letters, count);
-                }
-                rewrite_formulas_for_sheet_col_delete(&mut book, sheet_name, root_col, *count)?;
-                rewrite_defined_name_formulas_for_sheet_col_delete(
-                    &mut book, sheet_name, root_col, *count,
+                references::shift_sheet(
+                    &mut book,
+                    sheet_name,
+                    StructureAxis::Col,
+                    StructureEdit::Delete {
+                        start: root_col,
+                        count: *count,
+                    },
+                    &mut log,
+                    |sheet| sheet.remove_column(&col_letters, count),
                 )?;
                 affected_sheets.insert(sheet_name.clone());
                 counts
@@ -1118,8 +2607,7 @@ fn apply_structure_ops_to_file(path: &Path, ops: &[StructureOp]) -> Result<Struc
                 book.set_sheet_name(sheet_index, new_name.to_string())
                     .map_err(|e| anyhow!("failed to rename sheet '{}': {}", old_name, e))?;
 
-                rewrite_formulas_for_sheet_rename(&mut book, old_name, new_name)?;
-                rewrite_defined_name_formulas_for_sheet_rename(&mut book, old_name, new_name)?;
+                references::rename_sheet(&mut book, old_name, new_name, &mut log)?;
 
                 affected_sheets.insert(old_name.to_string());
                 affected_sheets.insert(new_name.to_string());
@@ -1149,6 +2637,7 @@ fn apply_structure_ops_to_file(path: &Path, ops: &[StructureOp]) -> Result<Struc
                         let sheets = book.get_sheet_collection_mut();
                         let created = sheets.remove(len - 1);
                         sheets.insert(desired, created);
+                        references::sheet_inserted_at(&mut book, desired);
                     }
                 }
 
@@ -1166,8 +2655,7 @@ fn apply_structure_ops_to_file(path: &Path, ops: &[StructureOp]) -> Result<Struc
                 if book.get_sheet_collection_no_check().len() <= 1 {
                     bail!("cannot delete the last remaining sheet");
                 }
-                book.remove_sheet_by_name(name_trimmed)
-                    .map_err(|e| anyhow!("failed to delete sheet '{}': {}", name_trimmed, e))?;
+                references::delete_sheet(&mut book, name_trimmed, &mut log)?;
                 affected_sheets.insert(name_trimmed.to_string());
                 counts
                     .entry("sheets_deleted".to_string())
@@ -1224,23 +2712,132 @@ fn apply_structure_ops_to_file(path: &Path, ops: &[StructureOp]) -> Result<Struc
                     *include_formulas,
                     true,
                 )?;
-                affected_sheets.insert(sheet_name.clone());
-                affected_sheets.insert(dest_sheet_name.to_string());
-                counts
-                    .entry("cells_moved".to_string())
-                    .and_modify(|v| *v += result.cells_written)
-                    .or_insert(result.cells_written);
-                counts
-                    .entry("ranges_moved".to_string())
-                    .and_modify(|v| *v += 1)
-                    .or_insert(1);
-                warnings.extend(result.warnings);
+                references::repoint_moved_range(
+                    &mut book,
+                    sheet_name,
+                    dest_sheet_name,
+                    src_range,
+                    dest_anchor,
+                    &mut log,
+                )?;
+                affected_sheets.insert(sheet_name.clone());
+                affected_sheets.insert(dest_sheet_name.to_string());
+                counts
+                    .entry("cells_moved".to_string())
+                    .and_modify(|v| *v += result.cells_written)
+                    .or_insert(result.cells_written);
+                counts
+                    .entry("ranges_moved".to_string())
+                    .and_modify(|v| *v += 1)
+                    .or_insert(1);
+                warnings.extend(result.warnings);
+            }
+            StructureOp::CreateTable {
+                sheet_name,
+                range,
+                table_name,
+                style,
+            } => {
+                tables::create_table(
+                    &mut book,
+                    sheet_name,
+                    range,
+                    table_name.as_deref(),
+                    style.as_deref(),
+                    &mut warnings,
+                )?;
+                affected_sheets.insert(sheet_name.clone());
+                *counts.entry("tables_created".to_string()).or_insert(0) += 1;
+            }
+            StructureOp::RenameTable {
+                table_name,
+                new_name,
+            } => {
+                let sheet = tables::rename_table(&mut book, table_name, new_name, &mut log)?;
+                affected_sheets.insert(sheet);
+                *counts.entry("tables_renamed".to_string()).or_insert(0) += 1;
+            }
+            StructureOp::ResizeTable { table_name, range } => {
+                let sheet =
+                    tables::resize_table(&mut book, table_name, range, &mut log, &mut warnings)?;
+                affected_sheets.insert(sheet);
+                *counts.entry("tables_resized".to_string()).or_insert(0) += 1;
+            }
+            StructureOp::AddTableColumn {
+                table_name,
+                column_name,
+                position,
+            } => {
+                let sheet = tables::add_table_column(
+                    &mut book,
+                    table_name,
+                    column_name,
+                    *position,
+                    &mut log,
+                )?;
+                affected_sheets.insert(sheet);
+                *counts.entry("table_columns_added".to_string()).or_insert(0) += 1;
+            }
+            StructureOp::RemoveTableColumn {
+                table_name,
+                column_name,
+            } => {
+                let sheet =
+                    tables::remove_table_column(&mut book, table_name, column_name, &mut log)?;
+                affected_sheets.insert(sheet);
+                *counts
+                    .entry("table_columns_removed".to_string())
+                    .or_insert(0) += 1;
+            }
+            StructureOp::SetTableTotals {
+                table_name,
+                show,
+                functions,
+                label,
+            } => {
+                let sheet = tables::set_table_totals(
+                    &mut book,
+                    table_name,
+                    *show,
+                    functions,
+                    label.as_deref(),
+                    &mut log,
+                )?;
+                affected_sheets.insert(sheet);
+                *counts
+                    .entry("table_totals_updated".to_string())
+                    .or_insert(0) += 1;
+            }
+            StructureOp::SetTableStyle {
+                table_name,
+                style,
+                show_row_stripes,
+                show_column_stripes,
+                show_first_column,
+                show_last_column,
+            } => {
+                let sheet = tables::set_table_style(
+                    &mut book,
+                    table_name,
+                    tables::TableStyleChange {
+                        style: style.as_deref(),
+                        show_row_stripes: *show_row_stripes,
+                        show_column_stripes: *show_column_stripes,
+                        show_first_column: *show_first_column,
+                        show_last_column: *show_last_column,
+                    },
+                )?;
+                affected_sheets.insert(sheet);
+                *counts
+                    .entry("table_styles_updated".to_string())
+                    .or_insert(0) += 1;
             }
         }
     }
 
     umya_spreadsheet::writer::xlsx::write(&book, path)?;
 
+    log.add_counts(&mut counts);
     let summary = ChangeSummary {
         op_kinds: vec!["structure_batch".to_string()],
         affected_sheets: affected_sheets.into_iter().collect(),
@@ -1252,6 +2849,7 @@ fn apply_structure_ops_to_file(path: &Path, ops: &[StructureOp]) -> Result<Struc
     Ok(StructureApplyResult {
         ops_applied: ops.len(),
         summary,
+        rewrites: log.rewrites,
     })
 }
 
@@ -1379,7 +2977,11 @@ fn copy_or_move_range(
                 let mut set_value = true;
                 let mut dest_formula: Option<String> = None;
 
-                if include_formulas && src_cell.is_formula() {
+                if include_formulas && src_cell.is_formula() && clear_source {
+                    // A cut keeps formulas verbatim; references are re-pointed afterwards.
+                    dest_formula = Some(src_cell.get_formula().to_string());
+                    set_value = false;
+                } else if include_formulas && src_cell.is_formula() {
                     let src_formula = src_cell.get_formula().to_string();
                     match parse_base_formula(&src_formula).and_then(|ast| {
                         shift_formula_ast(&ast, delta_col, delta_row, RelativeMode::Excel)
@@ -1428,620 +3030,100 @@ fn copy_or_move_range(
             }
         }
     } else {
-        let (src_sheet, dest_sheet) = if src_sheet_index < dest_sheet_index {
-            let (left, right) = sheets.split_at_mut(dest_sheet_index);
-            (&mut left[src_sheet_index], &mut right[0])
-        } else {
-            let (left, right) = sheets.split_at_mut(src_sheet_index);
-            (&mut right[0], &mut left[dest_sheet_index])
-        };
-
-        for row in 0..height {
-            for col in 0..width {
-                let src_col = src_bounds.min_col + col;
-                let src_row = src_bounds.min_row + row;
-                let dest_col = dest_start_col + col;
-                let dest_row = dest_start_row + row;
-
-                let Some(src_cell) = src_sheet.get_cell((src_col, src_row)) else {
-                    dest_sheet.remove_cell((dest_col, dest_row));
-                    continue;
-                };
-
-                let mut set_value = true;
-                let mut dest_formula: Option<String> = None;
-
-                if include_formulas && src_cell.is_formula() {
-                    let src_formula = src_cell.get_formula().to_string();
-                    match parse_base_formula(&src_formula).and_then(|ast| {
-                        shift_formula_ast(&ast, delta_col, delta_row, RelativeMode::Excel)
-                    }) {
-                        Ok(shifted) => {
-                            let shifted = shifted.strip_prefix('=').unwrap_or(&shifted).to_string();
-                            dest_formula = Some(shifted);
-                            set_value = false;
-                        }
-                        Err(_) => {
-                            dest_formula = Some(src_formula);
-                            set_value = false;
-                            formula_shift_failures += 1;
-                        }
-                    }
-                } else if !include_formulas && src_cell.is_formula() {
-                    formula_value_copies += 1;
-                }
-
-                let src_value = src_cell.get_value().to_string();
-                let src_style = src_cell.get_style().clone();
-
-                let dest_cell = dest_sheet.get_cell_mut((dest_col, dest_row));
-                if include_styles {
-                    dest_cell.set_style(src_style);
-                }
-
-                dest_cell.get_cell_value_mut().remove_formula();
-                if let Some(formula) = dest_formula {
-                    dest_cell.set_formula(formula);
-                    dest_cell.set_formula_result_default("");
-                }
-                if set_value {
-                    dest_cell.set_value(src_value);
-                }
-            }
-        }
-
-        if clear_source {
-            for row in 0..height {
-                for col in 0..width {
-                    let src_col = src_bounds.min_col + col;
-                    let src_row = src_bounds.min_row + row;
-                    src_sheet.remove_cell((src_col, src_row));
-                }
-            }
-        }
-    }
-
-    if include_formulas && formula_shift_failures > 0 {
-        warnings.push(format!(
-            "Failed to shift {} formula(s); copied original formula text.",
-            formula_shift_failures
-        ));
-    }
-    if !include_formulas && formula_value_copies > 0 {
-        warnings.push(format!(
-            "Copied cached values for {} formula cell(s) (include_formulas=false); run recalculate for fresh results.",
-            formula_value_copies
-        ));
-    }
-
-    Ok(CopyMoveApplyResult {
-        cells_written: width as u64 * height as u64,
-        warnings,
-    })
-}
-
-fn rewrite_formulas_for_sheet_rename(
-    book: &mut umya_spreadsheet::Spreadsheet,
-    old_name: &str,
-    new_name: &str,
-) -> Result<()> {
-    let new_prefix = format_sheet_prefix_for_formula(new_name);
-
-    for sheet in book.get_sheet_collection_mut().iter_mut() {
-        for cell in sheet.get_cell_collection_mut() {
-            if !cell.is_formula() {
-                continue;
-            }
-            let formula_text = cell.get_formula();
-            if formula_text.is_empty() {
-                continue;
-            }
-            let formula_with_equals = if formula_text.starts_with('=') {
-                formula_text.to_string()
-            } else {
-                format!("={}", formula_text)
-            };
-
-            let tokenizer = Tokenizer::new(&formula_with_equals)
-                .map_err(|e| anyhow!("failed to tokenize formula: {}", e.message))?;
-
-            let tokens = tokenizer.items;
-            let mut out = String::with_capacity(formula_with_equals.len());
-            let mut cursor = 0usize;
-
-            for token in &tokens {
-                if token.start > cursor {
-                    out.push_str(&formula_with_equals[cursor..token.start]);
-                }
-
-                let mut value = token.value.clone();
-                if token.subtype == formualizer_parse::TokenSubType::Range
-                    && value.contains('!')
-                    && let Some((sheet_part, tail)) = value.split_once('!')
-                    && sheet_part_matches(sheet_part, old_name)
-                {
-                    value = format!("{}{}", new_prefix, tail);
-                }
-
-                out.push_str(&value);
-                cursor = token.end;
-            }
-
-            if cursor < formula_with_equals.len() {
-                out.push_str(&formula_with_equals[cursor..]);
-            }
-
-            let new_formula = out.strip_prefix('=').unwrap_or(&out);
-            cell.set_formula(new_formula.to_string());
-        }
-    }
-
-    Ok(())
-}
-
-fn rewrite_defined_name_formulas_for_sheet_rename(
-    book: &mut umya_spreadsheet::Spreadsheet,
-    old_name: &str,
-    new_name: &str,
-) -> Result<()> {
-    let new_prefix = format_sheet_prefix_for_formula(new_name);
-
-    for defined in book.get_defined_names_mut() {
-        let refers_to = defined.get_address();
-        let trimmed = refers_to.trim();
-        let had_equals = trimmed.starts_with('=');
-        let looks_like_formula = had_equals || trimmed.contains('(');
-        if !looks_like_formula {
-            continue;
-        }
-
-        let formula_in = if had_equals {
-            trimmed.to_string()
-        } else {
-            format!("={}", trimmed)
-        };
-
-        let tokenizer = Tokenizer::new(&formula_in)
-            .map_err(|e| anyhow!("failed to tokenize formula: {}", e.message))?;
-        let tokens = tokenizer.items;
-
-        let mut out = String::with_capacity(formula_in.len());
-        let mut cursor = 0usize;
-        let mut changed = false;
-
-        for token in &tokens {
-            if token.start > cursor {
-                out.push_str(&formula_in[cursor..token.start]);
-            }
-
-            let mut value = token.value.clone();
-            if token.subtype == formualizer_parse::TokenSubType::Range
-                && value.contains('!')
-                && let Some((sheet_part, tail)) = value.split_once('!')
-                && sheet_part_matches(sheet_part, old_name)
-            {
-                value = format!("{}{}", new_prefix, tail);
-                changed = true;
-            }
-
-            out.push_str(&value);
-            cursor = token.end;
-        }
-
-        if cursor < formula_in.len() {
-            out.push_str(&formula_in[cursor..]);
-        }
-
-        if changed {
-            let out_final = if had_equals {
-                out
-            } else {
-                out.strip_prefix('=').unwrap_or(&out).to_string()
-            };
-            defined.set_address(out_final);
-        }
-    }
-
-    Ok(())
-}
-
-fn rewrite_defined_name_formulas_for_sheet_col_insert(
-    book: &mut umya_spreadsheet::Spreadsheet,
-    sheet_name: &str,
-    at_col: u32,
-    count: u32,
-) -> Result<()> {
-    rewrite_defined_name_formulas_for_sheet_structure_change(
-        book,
-        sheet_name,
-        StructureAxis::Col,
-        StructureEdit::Insert { at: at_col, count },
-    )
-}
-
-fn rewrite_defined_name_formulas_for_sheet_col_delete(
-    book: &mut umya_spreadsheet::Spreadsheet,
-    sheet_name: &str,
-    start_col: u32,
-    count: u32,
-) -> Result<()> {
-    rewrite_defined_name_formulas_for_sheet_structure_change(
-        book,
-        sheet_name,
-        StructureAxis::Col,
-        StructureEdit::Delete {
-            start: start_col,
-            count,
-        },
-    )
-}
-
-fn rewrite_defined_name_formulas_for_sheet_row_insert(
-    book: &mut umya_spreadsheet::Spreadsheet,
-    sheet_name: &str,
-    at_row: u32,
-    count: u32,
-) -> Result<()> {
-    rewrite_defined_name_formulas_for_sheet_structure_change(
-        book,
-        sheet_name,
-        StructureAxis::Row,
-        StructureEdit::Insert { at: at_row, count },
-    )
-}
-
-fn rewrite_defined_name_formulas_for_sheet_row_delete(
-    book: &mut umya_spreadsheet::Spreadsheet,
-    sheet_name: &str,
-    start_row: u32,
-    count: u32,
-) -> Result<()> {
-    rewrite_defined_name_formulas_for_sheet_structure_change(
-        book,
-        sheet_name,
-        StructureAxis::Row,
-        StructureEdit::Delete {
-            start: start_row,
-            count,
-        },
-    )
-}
-
-fn rewrite_defined_name_formulas_for_sheet_structure_change(
-    book: &mut umya_spreadsheet::Spreadsheet,
-    sheet_name: &str,
-    axis: StructureAxis,
-    edit: StructureEdit,
-) -> Result<()> {
-    for defined in book.get_defined_names_mut() {
-        let refers_to = defined.get_address();
-        let trimmed = refers_to.trim();
-        let had_equals = trimmed.starts_with('=');
-        let looks_like_formula = had_equals || trimmed.contains('(');
-        if !looks_like_formula {
-            continue;
-        }
-
-        let formula_in = if had_equals {
-            trimmed.to_string()
-        } else {
-            format!("={}", trimmed)
-        };
-
-        let tokenizer = Tokenizer::new(&formula_in)
-            .map_err(|e| anyhow!("failed to tokenize formula: {}", e.message))?;
-        let tokens = tokenizer.items;
-
-        let mut out = String::with_capacity(formula_in.len());
-        let mut cursor = 0usize;
-        let mut changed = false;
-
-        for token in &tokens {
-            if token.start > cursor {
-                out.push_str(&formula_in[cursor..token.start]);
-            }
-
-            let mut value = token.value.clone();
-            if token.subtype == formualizer_parse::TokenSubType::Range
-                && value.contains('!')
-                && let Some((sheet_part, coord_part)) = value.split_once('!')
-                && sheet_part_matches(sheet_part, sheet_name)
-            {
-                let adjusted = adjust_ref_coord_part(coord_part, axis, edit)?;
-                value = format!("{sheet_part}!{adjusted}");
-                changed = true;
-            }
-
-            out.push_str(&value);
-            cursor = token.end;
-        }
-
-        if cursor < formula_in.len() {
-            out.push_str(&formula_in[cursor..]);
-        }
-
-        if changed {
-            let out_final = if had_equals {
-                out
-            } else {
-                out.strip_prefix('=').unwrap_or(&out).to_string()
-            };
-            defined.set_address(out_final);
-        }
-    }
-
-    Ok(())
-}
-
-fn rewrite_formulas_for_sheet_col_insert(
-    book: &mut umya_spreadsheet::Spreadsheet,
-    sheet_name: &str,
-    at_col: u32,
-    count: u32,
-) -> Result<()> {
-    rewrite_formulas_for_sheet_structure_change(
-        book,
-        sheet_name,
-        StructureAxis::Col,
-        StructureEdit::Insert { at: at_col, count },
-    )
-}
-
-fn rewrite_formulas_for_sheet_col_delete(
-    book: &mut umya_spreadsheet::Spreadsheet,
-    sheet_name: &str,
-    start_col: u32,
-    count: u32,
-) -> Result<()> {
-    rewrite_formulas_for_sheet_structure_change(
-        book,
-        sheet_name,
-        StructureAxis::Col,
-        StructureEdit::Delete {
-            start: start_col,
-            count,
-        },
-    )
-}
-
-fn rewrite_formulas_for_sheet_row_insert(
-    book: &mut umya_spreadsheet::Spreadsheet,
-    sheet_name: &str,
-    at_row: u32,
-    count: u32,
-) -> Result<()> {
-    rewrite_formulas_for_sheet_structure_change(
-        book,
-        sheet_name,
-        StructureAxis::Row,
-        StructureEdit::Insert { at: at_row, count },
-    )
-}
-
-fn rewrite_formulas_for_sheet_row_delete(
-    book: &mut umya_spreadsheet::Spreadsheet,
-    sheet_name: &str,
-    start_row: u32,
-    count: u32,
-) -> Result<()> {
-    rewrite_formulas_for_sheet_structure_change(
-        book,
-        sheet_name,
-        StructureAxis::Row,
-        StructureEdit::Delete {
-            start: start_row,
-            count,
-        },
-    )
-}
-
-#[derive(Debug, Clone, Copy)]
-enum StructureAxis {
-    Row,
-    Col,
-}
-
-#[derive(Debug, Clone, Copy)]
-enum StructureEdit {
-    Insert { at: u32, count: u32 },
-    Delete { start: u32, count: u32 },
-}
-
-fn rewrite_formulas_for_sheet_structure_change(
-    book: &mut umya_spreadsheet::Spreadsheet,
-    sheet_name: &str,
-    axis: StructureAxis,
-    edit: StructureEdit,
-) -> Result<()> {
-    for sheet in book.get_sheet_collection_mut().iter_mut() {
-        if sheet.get_name() == sheet_name {
-            continue;
-        }
-        for cell in sheet.get_cell_collection_mut() {
-            if !cell.is_formula() {
-                continue;
-            }
-            let formula_text = cell.get_formula();
-            if formula_text.is_empty() {
-                continue;
-            }
-            let formula_with_equals = if formula_text.starts_with('=') {
-                formula_text.to_string()
-            } else {
-                format!("={}", formula_text)
-            };
-            let tokenizer = Tokenizer::new(&formula_with_equals)
-                .map_err(|e| anyhow!("failed to tokenize formula: {}", e.message))?;
-            let tokens = tokenizer.items;
-
-            let mut out = String::with_capacity(formula_with_equals.len());
-            let mut cursor = 0usize;
-            let mut changed = false;
-
-            for token in &tokens {
-                if token.start > cursor {
-                    out.push_str(&formula_with_equals[cursor..token.start]);
-                }
-
-                let mut value = token.value.clone();
-                if token.subtype == formualizer_parse::TokenSubType::Range
-                    && value.contains('!')
-                    && let Some((sheet_part, coord_part)) = value.split_once('!')
-                    && sheet_part_matches(sheet_part, sheet_name)
-                {
-                    let adjusted = adjust_ref_coord_part(coord_part, axis, edit)?;
-                    value = format!("{sheet_part}!{adjusted}");
-                    changed = true;
-                }
-
-                out.push_str(&value);
-                cursor = token.end;
-            }
-
-            if cursor < formula_with_equals.len() {
-                out.push_str(&formula_with_equals[cursor..]);
-            }
-
-            if changed {
-                let new_formula = out.strip_prefix('=').unwrap_or(&out);
-                cell.set_formula(new_formula.to_string());
-            }
-        }
-    }
-    Ok(())
-}
-
-fn adjust_ref_coord_part(
-    coord_part: &str,
-    axis: StructureAxis,
-    edit: StructureEdit,
-) -> Result<String> {
-    if coord_part == "#REF!" {
-        return Ok(coord_part.to_string());
-    }
-    if let Some((start, end)) = coord_part.split_once(':') {
-        let start_adj = adjust_ref_segment(start, axis, edit)?;
-        let end_adj = adjust_ref_segment(end, axis, edit)?;
-        if start_adj == "#REF!" || end_adj == "#REF!" {
-            return Ok("#REF!".to_string());
-        }
-        Ok(format!("{start_adj}:{end_adj}"))
-    } else {
-        Ok(adjust_ref_segment(coord_part, axis, edit)?)
-    }
-}
-
-fn adjust_ref_segment(segment: &str, axis: StructureAxis, edit: StructureEdit) -> Result<String> {
-    use umya_spreadsheet::helper::coordinate::{
-        coordinate_from_index_with_lock, index_from_coordinate, string_from_column_index,
-    };
+        let (src_sheet, dest_sheet) = if src_sheet_index < dest_sheet_index {
+            let (left, right) = sheets.split_at_mut(dest_sheet_index);
+            (&mut left[src_sheet_index], &mut right[0])
+        } else {
+            let (left, right) = sheets.split_at_mut(src_sheet_index);
+            (&mut right[0], &mut left[dest_sheet_index])
+        };
 
-    let (col, row, col_lock, row_lock) = index_from_coordinate(segment);
-    let mut col = col;
-    let mut row = row;
+        for row in 0..height {
+            for col in 0..width {
+                let src_col = src_bounds.min_col + col;
+                let src_row = src_bounds.min_row + row;
+                let dest_col = dest_start_col + col;
+                let dest_row = dest_start_row + row;
 
-    match axis {
-        StructureAxis::Col => {
-            if let Some(c) = col {
-                col = match edit {
-                    StructureEdit::Insert { at, count } => Some(adjust_insert(c, at, count)),
-                    StructureEdit::Delete { start, count } => adjust_delete(c, start, count),
-                };
-            }
-        }
-        StructureAxis::Row => {
-            if let Some(r) = row {
-                row = match edit {
-                    StructureEdit::Insert { at, count } => Some(adjust_insert(r, at, count)),
-                    StructureEdit::Delete { start, count } => adjust_delete(r, start, count),
+                let Some(src_cell) = src_sheet.get_cell((src_col, src_row)) else {
+                    dest_sheet.remove_cell((dest_col, dest_row));
+                    continue;
                 };
-            }
-        }
-    }
 
-    if col.is_none() && row.is_none() {
-        return Ok("#REF!".to_string());
-    }
+                let mut set_value = true;
+                let mut dest_formula: Option<String> = None;
 
-    match (col, row) {
-        (Some(c), Some(r)) => Ok(coordinate_from_index_with_lock(
-            &c,
-            &r,
-            &col_lock.unwrap_or(false),
-            &row_lock.unwrap_or(false),
-        )),
-        (Some(c), None) => {
-            let col_str = string_from_column_index(&c);
-            Ok(format!(
-                "{}{}",
-                if col_lock.unwrap_or(false) { "$" } else { "" },
-                col_str
-            ))
-        }
-        (None, Some(r)) => Ok(format!(
-            "{}{}",
-            if row_lock.unwrap_or(false) { "$" } else { "" },
-            r
-        )),
-        (None, None) => Ok("#REF!".to_string()),
-    }
-}
+                if include_formulas && src_cell.is_formula() && clear_source {
+                    // A cut keeps formulas verbatim; references are re-pointed afterwards.
+                    dest_formula = Some(src_cell.get_formula().to_string());
+                    set_value = false;
+                } else if include_formulas && src_cell.is_formula() {
+                    let src_formula = src_cell.get_formula().to_string();
+                    match parse_base_formula(&src_formula).and_then(|ast| {
+                        shift_formula_ast(&ast, delta_col, delta_row, RelativeMode::Excel)
+                    }) {
+                        Ok(shifted) => {
+                            let shifted = shifted.strip_prefix('=').unwrap_or(&shifted).to_string();
+                            dest_formula = Some(shifted);
+                            set_value = false;
+                        }
+                        Err(_) => {
+                            dest_formula = Some(src_formula);
+                            set_value = false;
+                            formula_shift_failures += 1;
+                        }
+                    }
+                } else if !include_formulas && src_cell.is_formula() {
+                    formula_value_copies += 1;
+                }
 
-fn adjust_insert(value: u32, at: u32, count: u32) -> u32 {
-    if value >= at { value + count } else { value }
-}
+                let src_value = src_cell.get_value().to_string();
+                let src_style = src_cell.get_style().clone();
 
-fn adjust_delete(value: u32, start: u32, count: u32) -> Option<u32> {
-    let end = start.saturating_add(count.saturating_sub(1));
-    if value >= start && value <= end {
-        None
-    } else if value > end {
-        Some(value - count)
-    } else {
-        Some(value)
-    }
-}
+                let dest_cell = dest_sheet.get_cell_mut((dest_col, dest_row));
+                if include_styles {
+                    dest_cell.set_style(src_style);
+                }
 
-fn sheet_part_matches(sheet_part: &str, old_name: &str) -> bool {
-    let trimmed = sheet_part.trim();
-    if let Some(stripped) = trimmed.strip_prefix('\'')
-        && let Some(inner) = stripped.strip_suffix('\'')
-    {
-        return inner.replace("''", "'") == old_name;
-    }
-    trimmed == old_name
-}
+                dest_cell.get_cell_value_mut().remove_formula();
+                if let Some(formula) = dest_formula {
+                    dest_cell.set_formula(formula);
+                    dest_cell.set_formula_result_default("");
+                }
+                if set_value {
+                    dest_cell.set_value(src_value);
+                }
+            }
+        }
 
-fn format_sheet_prefix_for_formula(sheet_name: &str) -> String {
-    if sheet_name_needs_quoting_for_formula(sheet_name) {
-        let escaped = sheet_name.replace('\'', "''");
-        format!("'{escaped}'!")
-    } else {
-        format!("{sheet_name}!")
+        if clear_source {
+            for row in 0..height {
+                for col in 0..width {
+                    let src_col = src_bounds.min_col + col;
+                    let src_row = src_bounds.min_row + row;
+                    src_sheet.remove_cell((src_col, src_row));
+                }
+            }
+        }
     }
-}
 
-fn sheet_name_needs_quoting_for_formula(name: &str) -> bool {
-    if name.is_empty() {
-        return false;
-    }
-    let bytes = name.as_bytes();
-    if bytes[0].is_ascii_digit() {
-        return true;
+    if include_formulas && formula_shift_failures > 0 {
+        warnings.push(format!(
+            "Failed to shift {} formula(s); copied original formula text.",
+            formula_shift_failures
+        ));
     }
-    for &byte in bytes {
-        match byte {
-            b' ' | b'!' | b'"' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+'
-            | b',' | b'-' | b'.' | b'/' | b':' | b';' | b'<' | b'=' | b'>' | b'?' | b'@' | b'['
-            | b'\\' | b']' | b'^' | b'`' | b'{' | b'|' | b'}' | b'~' => return true,
-            _ => {}
-        }
+    if !include_formulas && formula_value_copies > 0 {
+        warnings.push(format!(
+            "Copied cached values for {} formula cell(s) (include_formulas=false); run recalculate for fresh results.",
+            formula_value_copies
+        ));
     }
-    let upper = name.to_uppercase();
-    matches!(
-        upper.as_str(),
-        "TRUE" | "FALSE" | "NULL" | "REF" | "DIV" | "NAME" | "NUM" | "VALUE" | "N/A"
-    )
+
+    Ok(CopyMoveApplyResult {
+        cells_written: width as u64 * height as u64,
+        warnings,
+    })
 }
 
 struct StyleApplyResult {
@@ -2049,16 +3131,16 @@ struct StyleApplyResult {
     summary: ChangeSummary,
 }
 
-fn stage_snapshot_path(fork_id: &str, change_id: &str) -> PathBuf {
-    PathBuf::from("/tmp/mcp-staged").join(format!("{fork_id}_{change_id}.xlsx"))
-}
-
 struct TransformApplyResult {
     ops_applied: usize,
     summary: ChangeSummary,
 }
 
-fn apply_transform_ops_to_file(path: &Path, ops: &[TransformOp]) -> Result<TransformApplyResult> {
+fn apply_transform_ops_to_file(
+    path: &Path,
+    ops: &[TransformOp],
+    progress: &ProgressReporter,
+) -> Result<TransformApplyResult> {
     let mut book = umya_spreadsheet::reader::xlsx::read(path)?;
 
     let mut sheets: BTreeSet<String> = BTreeSet::new();
@@ -2074,7 +3156,13 @@ fn apply_transform_ops_to_file(path: &Path, ops: &[TransformOp]) -> Result<Trans
     let mut cells_value_replaced: u64 = 0;
     let mut cells_formula_replaced: u64 = 0;
 
-    for op in ops {
+    for (index, op) in ops.iter().enumerate() {
+        progress.check_cancelled()?;
+        progress.report(
+            index,
+            ops.len(),
+            format!("op {} of {}", index + 1, ops.len()),
+        );
         match op {
             TransformOp::ClearRange {
                 sheet_name,
@@ -2361,7 +3449,9 @@ fn apply_transform_ops_to_file(path: &Path, ops: &[TransformOp]) -> Result<Trans
         }
     }
 
+    progress.check_cancelled()?;
     umya_spreadsheet::writer::xlsx::write(&book, path)?;
+    progress.report(ops.len(), ops.len(), "workbook written");
 
     let mut counts = BTreeMap::new();
     counts.insert("cells_touched".to_string(), cells_touched);
@@ -2567,18 +3657,6 @@ impl Default for GetChangesetParams {
     }
 }
 
-#[derive(Debug, Serialize, JsonSchema)]
-pub struct ChangesetSummary {
-    pub total_changes: u32,
-    pub returned_changes: u32,
-    pub truncated: bool,
-    pub next_offset: Option<u32>,
-    pub counts_by_kind: BTreeMap<String, u32>,
-    pub counts_by_type: BTreeMap<String, u32>,
-    pub counts_by_subtype: BTreeMap<String, u32>,
-    pub affected_sheets: Vec<String>,
-}
-
 #[derive(Debug, Serialize, JsonSchema)]
 pub struct GetChangesetResponse {
     pub fork_id: String,
@@ -2587,111 +3665,18 @@ pub struct GetChangesetResponse {
     pub summary: ChangesetSummary,
 }
 
-fn normalize_filter(values: &Option<Vec<String>>) -> Option<BTreeSet<String>> {
-    values.as_ref().map(|items| {
-        items
-            .iter()
-            .map(|s| s.to_ascii_lowercase())
-            .collect::<BTreeSet<_>>()
-    })
-}
-
-fn change_kind_key(change: &crate::diff::Change) -> &'static str {
-    match change {
-        crate::diff::Change::Cell(_) => "cell",
-        crate::diff::Change::Table(_) => "table",
-        crate::diff::Change::Name(_) => "name",
-    }
-}
-
-fn change_type_key(change: &crate::diff::Change) -> &'static str {
-    use crate::diff::merge::CellDiff;
-    match change {
-        crate::diff::Change::Cell(cell) => match &cell.diff {
-            CellDiff::Added { .. } => "added",
-            CellDiff::Deleted { .. } => "deleted",
-            CellDiff::Modified { .. } => "modified",
-        },
-        crate::diff::Change::Table(table) => match table {
-            crate::diff::tables::TableDiff::TableAdded { .. } => "table_added",
-            crate::diff::tables::TableDiff::TableDeleted { .. } => "table_deleted",
-            crate::diff::tables::TableDiff::TableModified { .. } => "table_modified",
-        },
-        crate::diff::Change::Name(name) => match name {
-            crate::diff::names::NameDiff::NameAdded { .. } => "name_added",
-            crate::diff::names::NameDiff::NameDeleted { .. } => "name_deleted",
-            crate::diff::names::NameDiff::NameModified { .. } => "name_modified",
-        },
-    }
-}
-
-fn change_subtype_key(change: &crate::diff::Change) -> Option<&'static str> {
-    use crate::diff::merge::{CellDiff, ModificationType};
-    match change {
-        crate::diff::Change::Cell(cell) => match &cell.diff {
-            CellDiff::Modified { subtype, .. } => Some(match subtype {
-                ModificationType::FormulaEdit => "formula_edit",
-                ModificationType::RecalcResult => "recalc_result",
-                ModificationType::ValueEdit => "value_edit",
-                ModificationType::StyleEdit => "style_edit",
-            }),
-            _ => None,
-        },
-        _ => None,
-    }
-}
-
-fn change_sheet_name(change: &crate::diff::Change) -> Option<&str> {
-    match change {
-        crate::diff::Change::Cell(cell) => Some(cell.sheet.as_str()),
-        crate::diff::Change::Table(table) => match table {
-            crate::diff::tables::TableDiff::TableAdded { sheet, .. }
-            | crate::diff::tables::TableDiff::TableDeleted { sheet, .. }
-            | crate::diff::tables::TableDiff::TableModified { sheet, .. } => Some(sheet.as_str()),
-        },
-        crate::diff::Change::Name(name) => match name {
-            crate::diff::names::NameDiff::NameAdded { scope_sheet, .. }
-            | crate::diff::names::NameDiff::NameDeleted { scope_sheet, .. }
-            | crate::diff::names::NameDiff::NameModified { scope_sheet, .. } => {
-                scope_sheet.as_deref()
-            }
-        },
-    }
-}
-
-fn change_passes_filters(
-    change: &crate::diff::Change,
-    include_types: &Option<BTreeSet<String>>,
-    exclude_types: &Option<BTreeSet<String>>,
-    include_subtypes: &Option<BTreeSet<String>>,
-    exclude_subtypes: &Option<BTreeSet<String>>,
-) -> bool {
-    let type_key = change_type_key(change);
-    let subtype_key = change_subtype_key(change);
-
-    if let Some(include) = include_types
-        && !include.contains(type_key)
-    {
-        return false;
-    }
-    if let Some(exclude) = exclude_types
-        && exclude.contains(type_key)
-    {
-        return false;
-    }
-
-    if let Some(include) = include_subtypes
-        && subtype_key.is_none_or(|subtype| !include.contains(subtype))
-    {
-        return false;
-    }
-    if let Some(exclude) = exclude_subtypes
-        && subtype_key.is_some_and(|subtype| exclude.contains(subtype))
-    {
-        return false;
+impl<'a> From<&'a GetChangesetParams> for ChangesetQuery<'a> {
+    fn from(params: &'a GetChangesetParams) -> Self {
+        Self {
+            include_types: &params.include_types,
+            exclude_types: &params.exclude_types,
+            include_subtypes: &params.include_subtypes,
+            exclude_subtypes: &params.exclude_subtypes,
+            limit: params.limit,
+            offset: params.offset,
+            summary_only: params.summary_only,
+        }
     }
-
-    true
 }
 
 pub async fn get_changeset(
@@ -2705,80 +3690,14 @@ pub async fn get_changeset(
     let fork_ctx = registry.get_fork(&params.fork_id)?;
 
     let raw_changes = tokio::task::spawn_blocking({
-        let base_path = fork_ctx.base_path.clone();
+        let base_path = fork_ctx.diff_base_path().to_path_buf();
         let work_path = fork_ctx.work_path.clone();
         let sheet_filter = params.sheet_name.clone();
         move || crate::diff::calculate_changeset(&base_path, &work_path, sheet_filter.as_deref())
     })
     .await??;
 
-    let include_types = normalize_filter(&params.include_types);
-    let exclude_types = normalize_filter(&params.exclude_types);
-    let include_subtypes = normalize_filter(&params.include_subtypes);
-    let exclude_subtypes = normalize_filter(&params.exclude_subtypes);
-
-    let mut affected_sheets: BTreeSet<String> = BTreeSet::new();
-    let mut counts_by_kind: BTreeMap<String, u32> = BTreeMap::new();
-    let mut counts_by_type: BTreeMap<String, u32> = BTreeMap::new();
-    let mut counts_by_subtype: BTreeMap<String, u32> = BTreeMap::new();
-
-    let mut filtered: Vec<crate::diff::Change> = Vec::new();
-    for change in raw_changes {
-        if !change_passes_filters(
-            &change,
-            &include_types,
-            &exclude_types,
-            &include_subtypes,
-            &exclude_subtypes,
-        ) {
-            continue;
-        }
-
-        *counts_by_kind
-            .entry(change_kind_key(&change).to_string())
-            .or_default() += 1;
-        *counts_by_type
-            .entry(change_type_key(&change).to_string())
-            .or_default() += 1;
-        if let Some(subtype) = change_subtype_key(&change) {
-            *counts_by_subtype.entry(subtype.to_string()).or_default() += 1;
-        }
-        if let Some(sheet) = change_sheet_name(&change) {
-            affected_sheets.insert(sheet.to_string());
-        }
-
-        filtered.push(change);
-    }
-
-    let limit = params.limit.clamp(1, 2000) as usize;
-    let offset = params.offset as usize;
-
-    // Validate pagination parameters to prevent overflow
-    validate_pagination(offset, limit)
-        .map_err(|e| anyhow!("pagination validation failed: {}", e))?;
-
-    let total = filtered.len();
-
-    let (returned_changes, changes, truncated, next_offset) = if params.summary_only {
-        (0u32, Vec::new(), false, None)
-    } else {
-        let end = offset.saturating_add(limit);
-        let truncated = end < total;
-        let next_offset = truncated.then_some(end as u32);
-        let changes: Vec<_> = filtered.into_iter().skip(offset).take(limit).collect();
-        (changes.len() as u32, changes, truncated, next_offset)
-    };
-
-    let summary = ChangesetSummary {
-        total_changes: total as u32,
-        returned_changes,
-        truncated,
-        next_offset,
-        counts_by_kind,
-        counts_by_type,
-        counts_by_subtype,
-        affected_sheets: affected_sheets.into_iter().collect(),
-    };
+    let (changes, summary) = page_changeset(raw_changes, &ChangesetQuery::from(&params))?;
 
     Ok(GetChangesetResponse {
         fork_id: params.fork_id,
@@ -2804,12 +3723,29 @@ pub struct RecalculateResponse {
     pub fork_id: String,
     pub duration_ms: u64,
     pub backend: String,
+    /// Formula cells the backend could not evaluate; they keep their previous cached values.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unsupported: Vec<crate::recalc::UnsupportedFormula>,
 }
 
 pub async fn recalculate(
     state: Arc<AppState>,
     params: RecalculateParams,
 ) -> Result<RecalculateResponse> {
+    recalculate_with_progress(state, params, ProgressReporter::none()).await
+}
+
+/// [`recalculate`] reporting progress per phase. The backend works on a
+/// scratch copy that replaces the fork only when the run completes without
+/// cancellation. A running backend job is not interrupted, since LibreOffice
+/// instances cannot be stopped mid-document safely.
+pub async fn recalculate_with_progress(
+    state: Arc<AppState>,
+    params: RecalculateParams,
+    progress: ProgressReporter,
+) -> Result<RecalculateResponse> {
+    const PHASES: usize = 3;
+
     let registry = state
         .fork_registry()
         .ok_or_else(|| anyhow!("fork registry not available"))?;
@@ -2824,21 +3760,39 @@ pub async fn recalculate(
 
     let fork_ctx = registry.get_fork(&params.fork_id)?;
 
-    let _permit = semaphore
-        .0
-        .acquire()
-        .await
-        .map_err(|e| anyhow!("failed to acquire recalc permit: {}", e))?;
+    progress.report(0, PHASES, "waiting for a recalc slot");
+    let _permit = tokio::select! {
+        permit = semaphore.0.acquire() => {
+            permit.map_err(|e| anyhow!("failed to acquire recalc permit: {}", e))?
+        }
+        _ = progress.cancelled() => return Err(CancelledError.into()),
+    };
 
-    let result = backend.recalculate(&fork_ctx.work_path).await?;
+    progress.report(1, PHASES, "recalculating");
+    // The guard removes the scratch copy if this future is dropped by a
+    // timeout or cancellation mid-recalc
+    let scratch = TempFileGuard::new(fork_ctx.work_path.with_extension("recalc.xlsx"));
+    fs::copy(&fork_ctx.work_path, scratch.path())?;
+    let result = match backend.recalculate(scratch.path()).await {
+        Ok(result) if !progress.is_cancelled() => result,
+        outcome => {
+            outcome?;
+            return Err(CancelledError.into());
+        }
+    };
+    fs::rename(scratch.path(), &fork_ctx.work_path)?;
+    scratch.disarm();
+    progress.report(PHASES, PHASES, "recalculated");
 
     let fork_workbook_id = WorkbookId(params.fork_id.clone());
     let _ = state.close_workbook(&fork_workbook_id);
+    let _ = registry.mark_modified(&params.fork_id);
 
     Ok(RecalculateResponse {
         fork_id: params.fork_id,
         duration_ms: result.duration_ms,
         backend: result.executor_type.to_string(),
+        unsupported: result.unsupported,
     })
 }
 
@@ -2910,8 +3864,14 @@ pub async fn discard_fork(
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct SaveForkParams {
     pub fork_id: String,
-    /// Target path to save to. If omitted, saves to original location (requires --allow-overwrite).
+    /// Target path to save to. If omitted, saves to original location (requires --allow-overwrite;
+    /// `.ods` originals must be named explicitly, since styles are not written back).
+    /// The extension picks the format: `.xlsx`, `.ods`, or `.csv`/`.tsv` (one sheet).
     pub target_path: Option<String>,
+    /// Sheet to export when saving to `.csv`/`.tsv` (defaults to the first sheet).
+    /// Rejected for `.xlsx` and `.ods` targets.
+    #[serde(default)]
+    pub sheet_name: Option<String>,
     /// If true, discard the fork after saving. If false, fork remains active for further edits.
     #[serde(default = "default_drop_fork")]
     pub drop_fork: bool,
@@ -2926,6 +3886,8 @@ pub struct SaveForkResponse {
     pub fork_id: String,
     pub saved_to: String,
     pub fork_dropped: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
 pub async fn save_fork(state: Arc<AppState>, params: SaveForkParams) -> Result<SaveForkResponse> {
@@ -2937,6 +3899,7 @@ pub async fn save_fork(state: Arc<AppState>, params: SaveForkParams) -> Result<S
     let config = state.config();
     let workspace_root = &config.workspace_root;
 
+    let explicit_target = params.target_path.is_some();
     let (target, is_overwrite) = match params.target_path {
         Some(p) => {
             let resolved = config.resolve_path(&p);
@@ -2946,6 +3909,24 @@ pub async fn save_fork(state: Arc<AppState>, params: SaveForkParams) -> Result<S
         None => (fork_ctx.base_path.clone(), true),
     };
 
+    // The ODS writer keeps values, formulas and named ranges but no styles
+    let saves_ods = target
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ods"));
+    if saves_ods && is_overwrite && !explicit_target {
+        return Err(anyhow!(
+            "saving over the original .ods drops its cell styles; pass target_path explicitly to overwrite it anyway"
+        ));
+    }
+    let mut warnings = Vec::new();
+    if saves_ods {
+        warnings.push(
+            ".ods output keeps values, formulas and named ranges but not cell styles or formatting"
+                .to_string(),
+        );
+    }
+
     if is_overwrite && !config.allow_overwrite {
         return Err(anyhow!(
             "overwriting original file is disabled. Use --allow-overwrite flag or specify a different target_path"
@@ -2953,7 +3934,13 @@ pub async fn save_fork(state: Arc<AppState>, params: SaveForkParams) -> Result<S
     }
 
     let base_path = fork_ctx.base_path.clone();
-    registry.save_fork(&params.fork_id, &target, workspace_root, params.drop_fork)?;
+    registry.save_fork_as(
+        &params.fork_id,
+        &target,
+        workspace_root,
+        params.drop_fork,
+        params.sheet_name.as_deref(),
+    )?;
 
     if is_overwrite {
         state.evict_by_path(&base_path);
@@ -2963,6 +3950,7 @@ pub async fn save_fork(state: Arc<AppState>, params: SaveForkParams) -> Result<S
         fork_id: params.fork_id,
         saved_to: target.display().to_string(),
         fork_dropped: params.drop_fork,
+        warnings,
     })
 }
 
@@ -3067,6 +4055,7 @@ pub async fn restore_checkpoint(
     let checkpoint = registry.restore_checkpoint(&params.fork_id, &params.checkpoint_id)?;
     let fork_workbook_id = WorkbookId(params.fork_id.clone());
     let _ = state.close_workbook(&fork_workbook_id);
+    let _ = registry.mark_modified(&params.fork_id);
 
     Ok(RestoreCheckpointResponse {
         fork_id: params.fork_id,
@@ -3239,6 +4228,62 @@ pub async fn apply_staged_change(
 
                 ops_applied += 1;
             }
+            "validation_batch" => {
+                let payload: ValidationBatchStagedPayload =
+                    serde_json::from_value(op.payload.clone())
+                        .map_err(|e| anyhow!("invalid validation_batch payload: {}", e))?;
+
+                tokio::task::spawn_blocking({
+                    let ops = payload.ops.clone();
+                    let work_path = work_path.clone();
+                    move || apply_validation_ops_to_file(&work_path, &ops)
+                })
+                .await??;
+
+                ops_applied += 1;
+            }
+            "conditional_format_batch" => {
+                let payload: ConditionalFormatBatchStagedPayload =
+                    serde_json::from_value(op.payload.clone())
+                        .map_err(|e| anyhow!("invalid conditional_format_batch payload: {}", e))?;
+
+                tokio::task::spawn_blocking({
+                    let ops = payload.ops.clone();
+                    let work_path = work_path.clone();
+                    move || apply_conditional_format_ops_to_file(&work_path, &ops)
+                })
+                .await??;
+
+                ops_applied += 1;
+            }
+            "chart_batch" => {
+                let payload: ChartBatchStagedPayload =
+                    serde_json::from_value(op.payload.clone())
+                        .map_err(|e| anyhow!("invalid chart_batch payload: {}", e))?;
+
+                tokio::task::spawn_blocking({
+                    let ops = payload.ops.clone();
+                    let work_path = work_path.clone();
+                    move || apply_chart_ops_to_file(&work_path, &ops)
+                })
+                .await??;
+
+                ops_applied += 1;
+            }
+            "sheet_settings_batch" => {
+                let payload: SheetSettingsBatchStagedPayload =
+                    serde_json::from_value(op.payload.clone())
+                        .map_err(|e| anyhow!("invalid sheet_settings_batch payload: {}", e))?;
+
+                tokio::task::spawn_blocking({
+                    let ops = payload.ops.clone();
+                    let work_path = work_path.clone();
+                    move || apply_sheet_settings_ops_to_file(&work_path, &ops)
+                })
+                .await??;
+
+                ops_applied += 1;
+            }
             "transform_batch" => {
                 let payload: TransformBatchStagedPayload =
                     serde_json::from_value(op.payload.clone())
@@ -3247,7 +4292,7 @@ pub async fn apply_staged_change(
                 tokio::task::spawn_blocking({
                     let ops = payload.ops.clone();
                     let work_path = work_path.clone();
-                    move || apply_transform_ops_to_file(&work_path, &ops)
+                    move || apply_transform_ops_to_file(&work_path, &ops, &ProgressReporter::none())
                 })
                 .await??;
 
@@ -3303,6 +4348,29 @@ pub async fn apply_staged_change(
 
                 ops_applied += 1;
             }
+            "comment_batch" => {
+                let payload: CommentBatchStagedPayload = serde_json::from_value(op.payload.clone())
+                    .map_err(|e| anyhow!("invalid comment_batch payload: {}", e))?;
+
+                tokio::task::spawn_blocking({
+                    let ops = payload.ops.clone();
+                    let work_path = work_path.clone();
+                    move || apply_comment_ops_to_file(&work_path, &ops)
+                })
+                .await??;
+
+                ops_applied += 1;
+            }
+            "merge_forks" => {
+                tokio::task::spawn_blocking({
+                    let payload = op.payload.clone();
+                    let work_path = work_path.clone();
+                    move || crate::tools::merge::apply_staged_merge(&work_path, payload)
+                })
+                .await??;
+
+                ops_applied += 1;
+            }
             other => {
                 return Err(anyhow!("unsupported staged op kind: {}", other));
             }
@@ -3312,6 +4380,7 @@ pub async fn apply_staged_change(
     registry.discard_staged_change(&params.fork_id, &params.change_id)?;
     let fork_workbook_id = WorkbookId(params.fork_id.clone());
     let _ = state.close_workbook(&fork_workbook_id);
+    let _ = registry.mark_modified(&params.fork_id);
 
     Ok(ApplyStagedChangeResponse {
         fork_id: params.fork_id,
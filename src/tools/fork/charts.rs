@@ -0,0 +1,1130 @@
+//! Chart edits for `chart_batch`.
+//!
+//! umya's chart model cannot be edited safely, so charts are changed at
+//! the package level, as threaded comments are: a new chart gets its own
+//! chart part, an anchor in the sheet's drawing and the relationships and
+//! content types that tie them together. Updates edit the existing chart
+//! part in place, so styling the chart already carries is kept.
+
+use super::package::Package;
+use crate::charts::{CHART_REL, DRAWING_REL, XmlNode, plot_type_name};
+use crate::comments::rels_path_for;
+use crate::data_validation::{Area, resolve_reference};
+use crate::number_format::cell_display_text;
+use crate::utils::column_number_to_name;
+use anyhow::{Result, anyhow, bail};
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use umya_spreadsheet::{Spreadsheet, Worksheet};
+
+const CHART_NS: &str = "http://schemas.openxmlformats.org/drawingml/2006/chart";
+const DRAWINGML_NS: &str = "http://schemas.openxmlformats.org/drawingml/2006/main";
+const SHEET_DRAWING_NS: &str =
+    "http://schemas.openxmlformats.org/drawingml/2006/spreadsheetDrawing";
+const REL_NS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+const CHART_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.drawingml.chart+xml";
+const DRAWING_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.drawing+xml";
+
+/// Default chart size in cells when no bottom-right anchor is given.
+const DEFAULT_WIDTH_COLS: u32 = 8;
+const DEFAULT_HEIGHT_ROWS: u32 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartKind {
+    Column,
+    Bar,
+    Line,
+    Pie,
+    Scatter,
+    Area,
+}
+
+impl ChartKind {
+    fn uses_xy(self) -> bool {
+        self == ChartKind::Scatter
+    }
+}
+
+/// One plotted series. References may be sheet-qualified; unqualified
+/// ones point at the op's sheet.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SeriesSpec {
+    /// Values, e.g. `Data!B2:B13` (y values for scatter charts).
+    pub values: String,
+    /// Category labels (x values for scatter charts).
+    #[serde(default)]
+    pub categories: Option<String>,
+    /// Cell holding the series name.
+    #[serde(default)]
+    pub name_ref: Option<String>,
+    /// Literal series name, used when there is no `name_ref`.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChartOp {
+    /// Add a chart to `sheet_name`, plotting either `source` or `series`.
+    Create {
+        sheet_name: String,
+        chart_type: ChartKind,
+        /// Table-shaped range: labels in the first column (x values for
+        /// scatter), one series per remaining column, names in the first row.
+        #[serde(default)]
+        source: Option<String>,
+        /// Take series from the rows of `source` instead of its columns.
+        #[serde(default)]
+        series_in_rows: bool,
+        /// Explicit series, instead of `source`.
+        #[serde(default)]
+        series: Option<Vec<SeriesSpec>>,
+        /// Top-left cell of the chart, e.g. "E2".
+        anchor: String,
+        /// Bottom-right cell; defaults to 8 columns by 15 rows from `anchor`.
+        #[serde(default)]
+        anchor_to: Option<String>,
+        #[serde(default)]
+        title: Option<String>,
+        /// Title of the horizontal axis.
+        #[serde(default)]
+        x_axis_title: Option<String>,
+        /// Title of the vertical axis.
+        #[serde(default)]
+        y_axis_title: Option<String>,
+    },
+    /// Change the chart at `chart_index` (as listed by list_charts). Empty
+    /// strings remove a title; `series` replaces the plotted series in order.
+    Update {
+        sheet_name: String,
+        chart_index: u32,
+        #[serde(default)]
+        title: Option<String>,
+        #[serde(default)]
+        x_axis_title: Option<String>,
+        #[serde(default)]
+        y_axis_title: Option<String>,
+        #[serde(default)]
+        series: Option<Vec<SeriesSpec>>,
+    },
+    Delete {
+        sheet_name: String,
+        chart_index: u32,
+    },
+}
+
+impl ChartOp {
+    pub fn sheet_name(&self) -> &str {
+        match self {
+            ChartOp::Create { sheet_name, .. }
+            | ChartOp::Update { sheet_name, .. }
+            | ChartOp::Delete { sheet_name, .. } => sheet_name,
+        }
+    }
+}
+
+/// A series reference resolved against the workbook, with its cached points.
+pub(super) struct ResolvedRef {
+    formula: String,
+    text: Vec<Option<String>>,
+    numbers: Vec<Option<f64>>,
+}
+
+pub(super) struct ResolvedSeries {
+    name_ref: Option<ResolvedRef>,
+    name: Option<String>,
+    categories: Option<ResolvedRef>,
+    values: ResolvedRef,
+}
+
+/// A chart on a sheet, located the way list_charts numbers them.
+struct ChartSlot {
+    drawing: String,
+    rel_id: String,
+    part: String,
+}
+
+impl Package {
+    fn sheet_drawing(&mut self, sheet_path: &str) -> Result<Option<String>> {
+        Ok(self
+            .relationships(sheet_path)?
+            .into_values()
+            .find(|(kind, _)| kind == DRAWING_REL)
+            .map(|(_, target)| target))
+    }
+
+    /// The sheet's charts in list_charts order.
+    fn sheet_charts(&mut self, sheet_path: &str) -> Result<Vec<ChartSlot>> {
+        let mut slots = Vec::new();
+        let drawings: Vec<String> = self
+            .relationships(sheet_path)?
+            .into_values()
+            .filter(|(kind, _)| kind == DRAWING_REL)
+            .map(|(_, target)| target)
+            .collect();
+        for drawing in drawings {
+            let Some(xml) = self.read(&drawing)? else {
+                continue;
+            };
+            let rels = self.relationships(&drawing)?;
+            for (_, _, rel_id) in crate::charts::drawing_frames(&XmlNode::parse(&xml)?) {
+                if let Some((_, part)) = rels.get(&rel_id).filter(|(kind, _)| kind == CHART_REL) {
+                    slots.push(ChartSlot {
+                        drawing: drawing.clone(),
+                        rel_id: rel_id.clone(),
+                        part: part.clone(),
+                    });
+                }
+            }
+        }
+        Ok(slots)
+    }
+
+    fn chart_slot(&mut self, sheet_name: &str, chart_index: u32) -> Result<ChartSlot> {
+        let sheet_path = self.sheet_path(sheet_name)?;
+        self.sheet_charts(&sheet_path)?
+            .into_iter()
+            .nth(chart_index as usize)
+            .ok_or_else(|| {
+                anyhow!(
+                    "sheet '{}' has no chart at index {}; use list_charts",
+                    sheet_name,
+                    chart_index
+                )
+            })
+    }
+
+    /// Add a chart; returns the anchor range it occupies.
+    pub fn create(
+        &mut self,
+        sheet_name: &str,
+        kind: ChartKind,
+        series: &[ResolvedSeries],
+        anchor: &str,
+        anchor_to: Option<&str>,
+        titles: &ChartTitles,
+    ) -> Result<String> {
+        if series.is_empty() {
+            bail!("chart on '{}' has no series to plot", sheet_name);
+        }
+        let from = Area::parse(anchor)
+            .filter(Area::is_single_cell)
+            .ok_or_else(|| anyhow!("invalid anchor cell '{}'", anchor))?;
+        let to = match anchor_to {
+            Some(cell) => Area::parse(cell)
+                .filter(Area::is_single_cell)
+                .ok_or_else(|| anyhow!("invalid anchor_to cell '{}'", cell))?,
+            None => Area {
+                min_col: from.min_col + DEFAULT_WIDTH_COLS,
+                min_row: from.min_row + DEFAULT_HEIGHT_ROWS,
+                max_col: from.min_col + DEFAULT_WIDTH_COLS,
+                max_row: from.min_row + DEFAULT_HEIGHT_ROWS,
+            },
+        };
+        if to.min_col <= from.min_col || to.min_row <= from.min_row {
+            bail!("anchor_to must be below and right of anchor '{}'", anchor);
+        }
+
+        let sheet_path = self.sheet_path(sheet_name)?;
+        let chart_part = self.next_part("xl/charts/chart");
+        self.write(&chart_part, chart_space_xml(kind, series, titles));
+        self.add_override(&chart_part, CHART_CONTENT_TYPE)?;
+
+        let drawing = match self.sheet_drawing(&sheet_path)? {
+            Some(drawing) => drawing,
+            None => {
+                let drawing = self.next_part("xl/drawings/drawing");
+                self.write(
+                    &drawing,
+                    format!(
+                        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<xdr:wsDr xmlns:xdr=\"{SHEET_DRAWING_NS}\" xmlns:a=\"{DRAWINGML_NS}\"></xdr:wsDr>"
+                    ),
+                );
+                self.add_override(&drawing, DRAWING_CONTENT_TYPE)?;
+                let rel_id = self.add_relationship(&sheet_path, DRAWING_REL, &drawing)?;
+                let sheet_xml = self
+                    .read(&sheet_path)?
+                    .ok_or_else(|| anyhow!("missing worksheet part {}", sheet_path))?;
+                self.write(&sheet_path, insert_drawing_element(&sheet_xml, &rel_id)?);
+                drawing
+            }
+        };
+
+        let rel_id = self.add_relationship(&drawing, CHART_REL, &chart_part)?;
+        let xml = self
+            .read(&drawing)?
+            .ok_or_else(|| anyhow!("missing drawing part {}", drawing))?;
+        let mut root = XmlNode::parse(&xml)?;
+        let mut ids = Vec::new();
+        root.find_all("cNvPr", &mut ids);
+        let next_id = ids
+            .iter()
+            .filter_map(|node| node.attr("id")?.parse::<u32>().ok())
+            .max()
+            .unwrap_or(1)
+            + 1;
+        let ws_dr = root
+            .child_mut("wsDr")
+            .ok_or_else(|| anyhow!("{} is not a sheet drawing", drawing))?;
+        let mut anchor_node = XmlNode::parse_element(&anchor_xml(&from, &to, next_id, &rel_id))?;
+        // Drop declarations the drawing already makes.
+        anchor_node.attrs.retain(|attr| !ws_dr.attrs.contains(attr));
+        ws_dr.children.push(anchor_node);
+        self.write(&drawing, root.to_document());
+
+        Ok(format!("{}:{}", from.to_a1(), to.to_a1()))
+    }
+
+    /// Edit titles and series of an existing chart; returns warnings.
+    pub fn update(
+        &mut self,
+        sheet_name: &str,
+        chart_index: u32,
+        titles: &ChartTitles,
+        series: Option<&[ResolvedSeries]>,
+    ) -> Result<Vec<String>> {
+        let slot = self.chart_slot(sheet_name, chart_index)?;
+        let xml = self
+            .read(&slot.part)?
+            .ok_or_else(|| anyhow!("missing chart part {}", slot.part))?;
+        let mut root = XmlNode::parse(&xml)?;
+        let space = root
+            .child_mut("chartSpace")
+            .ok_or_else(|| anyhow!("{} is not a chart part", slot.part))?;
+        if !space.qname.starts_with("c:") {
+            bail!(
+                "chart {} uses an unexpected namespace prefix; it cannot be edited",
+                slot.part
+            );
+        }
+        let chart = space
+            .child_mut("chart")
+            .ok_or_else(|| anyhow!("{} has no chart element", slot.part))?;
+
+        let mut warnings = Vec::new();
+        if let Some(title) = &titles.title {
+            set_chart_title(chart, title)?;
+        }
+        let plot_area = chart
+            .child_mut("plotArea")
+            .ok_or_else(|| anyhow!("{} has no plot area", slot.part))?;
+        for (horizontal, text) in [(true, &titles.x_axis), (false, &titles.y_axis)] {
+            if let Some(text) = text
+                && !set_axis_title(plot_area, horizontal, text)?
+            {
+                warnings.push(format!(
+                    "chart {} on '{}' has no {} axis; its title was not set",
+                    chart_index,
+                    sheet_name,
+                    if horizontal { "horizontal" } else { "vertical" }
+                ));
+            }
+        }
+        if let Some(series) = series {
+            replace_series(plot_area, series)?;
+        }
+        self.write(&slot.part, root.to_document());
+        Ok(warnings)
+    }
+
+    /// Remove a chart, its anchor and the parts only it used.
+    pub fn delete(&mut self, sheet_name: &str, chart_index: u32) -> Result<()> {
+        let slot = self.chart_slot(sheet_name, chart_index)?;
+        let xml = self
+            .read(&slot.drawing)?
+            .ok_or_else(|| anyhow!("missing drawing part {}", slot.drawing))?;
+        let mut root = XmlNode::parse(&xml)?;
+        if let Some(ws_dr) = root.child_mut("wsDr") {
+            ws_dr.children.retain(|anchor| {
+                anchor
+                    .find("chart")
+                    .and_then(|chart| chart.attr("id"))
+                    .is_none_or(|id| id != slot.rel_id)
+            });
+        }
+        self.write(&slot.drawing, root.to_document());
+        self.remove_relationship(&slot.drawing, &slot.rel_id)?;
+
+        // Chart style and color parts hang off the chart part.
+        let mut parts = vec![slot.part.clone()];
+        parts.extend(
+            self.relationships(&slot.part)?
+                .into_values()
+                .map(|(_, target)| target)
+                .filter(|target| target.starts_with("xl/charts/")),
+        );
+        for part in &parts {
+            self.remove(part);
+        }
+        self.remove(&rels_path_for(&slot.part));
+        self.remove_overrides(&parts)
+    }
+}
+
+#[derive(Debug, Default)]
+pub(super) struct ChartTitles {
+    pub title: Option<String>,
+    pub x_axis: Option<String>,
+    pub y_axis: Option<String>,
+}
+
+/// Resolve explicit series specs against the workbook.
+pub(super) fn resolve_series(
+    book: &Spreadsheet,
+    sheet_name: &str,
+    specs: &[SeriesSpec],
+) -> Result<Vec<ResolvedSeries>> {
+    let host = book
+        .get_sheet_by_name(sheet_name)
+        .ok_or_else(|| anyhow!("sheet '{}' not found", sheet_name))?;
+    specs
+        .iter()
+        .map(|spec| {
+            Ok(ResolvedSeries {
+                name_ref: spec
+                    .name_ref
+                    .as_deref()
+                    .map(|r| resolve_ref(book, host, r))
+                    .transpose()?,
+                name: spec.name.clone(),
+                categories: spec
+                    .categories
+                    .as_deref()
+                    .map(|r| resolve_ref(book, host, r))
+                    .transpose()?,
+                values: resolve_ref(book, host, &spec.values)?,
+            })
+        })
+        .collect()
+}
+
+/// Split a table-shaped `source` into series: labels in the first column
+/// (or row), one series per remaining column (or row), names in the first
+/// row (or column).
+pub(super) fn series_from_source(
+    book: &Spreadsheet,
+    sheet_name: &str,
+    source: &str,
+    in_rows: bool,
+) -> Result<Vec<ResolvedSeries>> {
+    let host = book
+        .get_sheet_by_name(sheet_name)
+        .ok_or_else(|| anyhow!("sheet '{}' not found", sheet_name))?;
+    let (sheet, area) = resolve_reference(book, host, source)
+        .ok_or_else(|| anyhow!("cannot resolve chart source '{}'", source))?;
+    let (across, down) = if in_rows {
+        (
+            area.max_row - area.min_row + 1,
+            area.max_col - area.min_col + 1,
+        )
+    } else {
+        (
+            area.max_col - area.min_col + 1,
+            area.max_row - area.min_row + 1,
+        )
+    };
+    if down < 2 {
+        bail!(
+            "chart source '{}' needs a header plus at least one data {}",
+            source,
+            if in_rows { "column" } else { "row" }
+        );
+    }
+    // `line(i, from, to)`: the i-th column (or row) of the source, between
+    // offsets `from` and `to` along it.
+    let line = |i: u32, from: u32, to: u32| -> Area {
+        if in_rows {
+            Area {
+                min_col: area.min_col + from,
+                max_col: area.min_col + to,
+                min_row: area.min_row + i,
+                max_row: area.min_row + i,
+            }
+        } else {
+            Area {
+                min_col: area.min_col + i,
+                max_col: area.min_col + i,
+                min_row: area.min_row + from,
+                max_row: area.min_row + to,
+            }
+        }
+    };
+    let labels = (across > 1).then(|| resolved_area(sheet, line(0, 1, down - 1)));
+    let first_series = u32::from(across > 1);
+    Ok((first_series..across)
+        .map(|i| ResolvedSeries {
+            name_ref: Some(resolved_area(sheet, line(i, 0, 0))),
+            name: None,
+            categories: labels.as_ref().map(|l| ResolvedRef {
+                formula: l.formula.clone(),
+                text: l.text.clone(),
+                numbers: l.numbers.clone(),
+            }),
+            values: resolved_area(sheet, line(i, 1, down - 1)),
+        })
+        .collect())
+}
+
+fn resolve_ref(book: &Spreadsheet, host: &Worksheet, reference: &str) -> Result<ResolvedRef> {
+    let areas = crate::charts::resolve_series_ref(book, host, reference)
+        .ok_or_else(|| anyhow!("cannot resolve chart range '{}'", reference))?;
+    let mut resolved = ResolvedRef {
+        formula: String::new(),
+        text: Vec::new(),
+        numbers: Vec::new(),
+    };
+    let mut formulas = Vec::new();
+    for (sheet, area) in areas {
+        let part = resolved_area(sheet, area);
+        formulas.push(part.formula);
+        resolved.text.extend(part.text);
+        resolved.numbers.extend(part.numbers);
+    }
+    resolved.formula = if formulas.len() == 1 {
+        formulas.remove(0)
+    } else {
+        format!("({})", formulas.join(","))
+    };
+    Ok(resolved)
+}
+
+fn resolved_area(sheet: &Worksheet, area: Area) -> ResolvedRef {
+    let mut text = Vec::new();
+    let mut numbers = Vec::new();
+    for row in area.min_row..=area.max_row {
+        for col in area.min_col..=area.max_col {
+            let cell = sheet.get_cell((col, row));
+            text.push(cell.and_then(cell_display_text));
+            numbers.push(cell.and_then(|c| c.get_value_number()));
+        }
+    }
+    ResolvedRef {
+        formula: absolute_ref(sheet.get_name(), &area),
+        text,
+        numbers,
+    }
+}
+
+/// `'My Sheet'!$A$1:$B$9`, the form chart parts use.
+fn absolute_ref(sheet_name: &str, area: &Area) -> String {
+    let plain = sheet_name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !sheet_name.starts_with(|c: char| c.is_ascii_digit());
+    let sheet = if plain {
+        sheet_name.to_string()
+    } else {
+        format!("'{}'", sheet_name.replace('\'', "''"))
+    };
+    let cell = |col: u32, row: u32| format!("${}${}", column_number_to_name(col), row);
+    if area.is_single_cell() {
+        format!("{}!{}", sheet, cell(area.min_col, area.min_row))
+    } else {
+        format!(
+            "{}!{}:{}",
+            sheet,
+            cell(area.min_col, area.min_row),
+            cell(area.max_col, area.max_row)
+        )
+    }
+}
+
+fn escape(value: &str) -> String {
+    quick_xml::escape::escape(value).to_string()
+}
+
+/// Parse `c:`/`a:` prefixed elements written without their declarations.
+fn fragment(xml: &str) -> Result<Vec<XmlNode>> {
+    Ok(XmlNode::parse_element(&format!(
+        "<c:fragment xmlns:c=\"{CHART_NS}\" xmlns:a=\"{DRAWINGML_NS}\">{xml}</c:fragment>"
+    ))?
+    .children)
+}
+
+fn fragment_element(xml: &str) -> Result<XmlNode> {
+    fragment(xml)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("empty chart fragment"))
+}
+
+fn title_xml(text: &str) -> String {
+    let paragraphs: String = text
+        .split('\n')
+        .map(|line| {
+            format!(
+                "<a:p><a:pPr><a:defRPr/></a:pPr><a:r><a:t>{}</a:t></a:r></a:p>",
+                escape(line)
+            )
+        })
+        .collect();
+    format!(
+        "<c:title><c:tx><c:rich><a:bodyPr/><a:lstStyle/>{paragraphs}</c:rich></c:tx>\
+<c:overlay val=\"0\"/></c:title>"
+    )
+}
+
+fn str_cache(points: &[Option<String>]) -> String {
+    let pts: String = points
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, v)| {
+            v.as_ref()
+                .map(|v| format!("<c:pt idx=\"{idx}\"><c:v>{}</c:v></c:pt>", escape(v)))
+        })
+        .collect();
+    format!(
+        "<c:strCache><c:ptCount val=\"{}\"/>{pts}</c:strCache>",
+        points.len()
+    )
+}
+
+fn num_cache(points: &[Option<f64>]) -> String {
+    let pts: String = points
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, v)| v.map(|v| format!("<c:pt idx=\"{idx}\"><c:v>{v}</c:v></c:pt>")))
+        .collect();
+    format!(
+        "<c:numCache><c:formatCode>General</c:formatCode><c:ptCount val=\"{}\"/>{pts}</c:numCache>",
+        points.len()
+    )
+}
+
+fn tx_xml(series: &ResolvedSeries) -> Option<String> {
+    match (&series.name_ref, &series.name) {
+        (Some(name_ref), _) => Some(format!(
+            "<c:tx><c:strRef><c:f>{}</c:f>{}</c:strRef></c:tx>",
+            escape(&name_ref.formula),
+            str_cache(&name_ref.text[..name_ref.text.len().min(1)])
+        )),
+        (None, Some(name)) => Some(format!("<c:tx><c:v>{}</c:v></c:tx>", escape(name))),
+        (None, None) => None,
+    }
+}
+
+/// `cat`/`val`, or `xVal`/`yVal` for scatter, with their caches.
+fn data_xml(series: &ResolvedSeries, xy: bool) -> (Option<String>, String) {
+    let categories = series.categories.as_ref().map(|cat| {
+        if xy {
+            format!(
+                "<c:xVal><c:numRef><c:f>{}</c:f>{}</c:numRef></c:xVal>",
+                escape(&cat.formula),
+                num_cache(&cat.numbers)
+            )
+        } else {
+            format!(
+                "<c:cat><c:strRef><c:f>{}</c:f>{}</c:strRef></c:cat>",
+                escape(&cat.formula),
+                str_cache(&cat.text)
+            )
+        }
+    });
+    let tag = if xy { "yVal" } else { "val" };
+    let values = format!(
+        "<c:{tag}><c:numRef><c:f>{}</c:f>{}</c:numRef></c:{tag}>",
+        escape(&series.values.formula),
+        num_cache(&series.values.numbers)
+    );
+    (categories, values)
+}
+
+fn series_xml(kind: ChartKind, index: usize, series: &ResolvedSeries) -> String {
+    let (categories, values) = data_xml(series, kind.uses_xy());
+    let styling = match kind {
+        ChartKind::Column | ChartKind::Bar => "<c:invertIfNegative val=\"0\"/>",
+        ChartKind::Line => "<c:marker><c:symbol val=\"none\"/></c:marker>",
+        // Markers only, no connecting lines.
+        ChartKind::Scatter => {
+            "<c:spPr><a:ln w=\"19050\"><a:noFill/></a:ln></c:spPr><c:marker><c:symbol val=\"circle\"/></c:marker>"
+        }
+        ChartKind::Pie | ChartKind::Area => "",
+    };
+    let smooth = match kind {
+        ChartKind::Line | ChartKind::Scatter => "<c:smooth val=\"0\"/>",
+        _ => "",
+    };
+    format!(
+        "<c:ser><c:idx val=\"{index}\"/><c:order val=\"{index}\"/>{}{styling}{}{values}{smooth}</c:ser>",
+        tx_xml(series).unwrap_or_default(),
+        categories.unwrap_or_default()
+    )
+}
+
+const CAT_AX_ID: u32 = 500_000_001;
+const VAL_AX_ID: u32 = 500_000_002;
+
+fn axis_xml(
+    tag: &str,
+    id: u32,
+    cross_id: u32,
+    position: &str,
+    gridlines: bool,
+    title: Option<&str>,
+    tail: &str,
+) -> String {
+    format!(
+        "<c:{tag}><c:axId val=\"{id}\"/><c:scaling><c:orientation val=\"minMax\"/></c:scaling>\
+<c:delete val=\"0\"/><c:axPos val=\"{position}\"/>{}{}\
+<c:numFmt formatCode=\"General\" sourceLinked=\"1\"/><c:majorTickMark val=\"out\"/>\
+<c:minorTickMark val=\"none\"/><c:tickLblPos val=\"nextTo\"/><c:crossAx val=\"{cross_id}\"/>\
+<c:crosses val=\"autoZero\"/>{tail}</c:{tag}>",
+        if gridlines { "<c:majorGridlines/>" } else { "" },
+        title.map(title_xml).unwrap_or_default()
+    )
+}
+
+fn chart_space_xml(kind: ChartKind, series: &[ResolvedSeries], titles: &ChartTitles) -> String {
+    let ser: String = series
+        .iter()
+        .enumerate()
+        .map(|(idx, s)| series_xml(kind, idx, s))
+        .collect();
+    let ax_ids = format!("<c:axId val=\"{CAT_AX_ID}\"/><c:axId val=\"{VAL_AX_ID}\"/>");
+    let plot = match kind {
+        ChartKind::Column | ChartKind::Bar => format!(
+            "<c:barChart><c:barDir val=\"{}\"/><c:grouping val=\"clustered\"/>\
+<c:varyColors val=\"0\"/>{ser}<c:gapWidth val=\"150\"/>{ax_ids}</c:barChart>",
+            if kind == ChartKind::Bar { "bar" } else { "col" }
+        ),
+        ChartKind::Line => format!(
+            "<c:lineChart><c:grouping val=\"standard\"/><c:varyColors val=\"0\"/>{ser}\
+<c:marker val=\"1\"/>{ax_ids}</c:lineChart>"
+        ),
+        ChartKind::Area => format!(
+            "<c:areaChart><c:grouping val=\"standard\"/><c:varyColors val=\"0\"/>{ser}{ax_ids}</c:areaChart>"
+        ),
+        ChartKind::Scatter => format!(
+            "<c:scatterChart><c:scatterStyle val=\"lineMarker\"/><c:varyColors val=\"0\"/>{ser}{ax_ids}</c:scatterChart>"
+        ),
+        ChartKind::Pie => format!(
+            "<c:pieChart><c:varyColors val=\"1\"/>{ser}<c:firstSliceAng val=\"0\"/></c:pieChart>"
+        ),
+    };
+    let (x, y) = (titles.x_axis.as_deref(), titles.y_axis.as_deref());
+    let category_tail = "<c:auto val=\"1\"/><c:lblAlgn val=\"ctr\"/><c:lblOffset val=\"100\"/><c:noMultiLvlLbl val=\"0\"/>";
+    let axes = match kind {
+        ChartKind::Pie => String::new(),
+        ChartKind::Bar => {
+            axis_xml("catAx", CAT_AX_ID, VAL_AX_ID, "l", false, y, category_tail)
+                + &axis_xml(
+                    "valAx",
+                    VAL_AX_ID,
+                    CAT_AX_ID,
+                    "b",
+                    true,
+                    x,
+                    "<c:crossBetween val=\"between\"/>",
+                )
+        }
+        ChartKind::Scatter => {
+            axis_xml(
+                "valAx",
+                CAT_AX_ID,
+                VAL_AX_ID,
+                "b",
+                false,
+                x,
+                "<c:crossBetween val=\"midCat\"/>",
+            ) + &axis_xml(
+                "valAx",
+                VAL_AX_ID,
+                CAT_AX_ID,
+                "l",
+                true,
+                y,
+                "<c:crossBetween val=\"midCat\"/>",
+            )
+        }
+        ChartKind::Column | ChartKind::Line | ChartKind::Area => {
+            axis_xml("catAx", CAT_AX_ID, VAL_AX_ID, "b", false, x, category_tail)
+                + &axis_xml(
+                    "valAx",
+                    VAL_AX_ID,
+                    CAT_AX_ID,
+                    "l",
+                    true,
+                    y,
+                    "<c:crossBetween val=\"between\"/>",
+                )
+        }
+    };
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<c:chartSpace xmlns:c=\"{CHART_NS}\" xmlns:a=\"{DRAWINGML_NS}\" xmlns:r=\"{REL_NS}\">\
+<c:roundedCorners val=\"0\"/><c:chart>{}<c:autoTitleDeleted val=\"0\"/>\
+<c:plotArea><c:layout/>{plot}{axes}</c:plotArea>\
+<c:legend><c:legendPos val=\"b\"/><c:overlay val=\"0\"/></c:legend>\
+<c:plotVisOnly val=\"1\"/><c:dispBlanksAs val=\"gap\"/></c:chart></c:chartSpace>",
+        titles.title.as_deref().map(title_xml).unwrap_or_default()
+    )
+}
+
+fn anchor_xml(from: &Area, to: &Area, id: u32, rel_id: &str) -> String {
+    let marker = |tag: &str, area: &Area| {
+        format!(
+            "<xdr:{tag}><xdr:col>{}</xdr:col><xdr:colOff>0</xdr:colOff>\
+<xdr:row>{}</xdr:row><xdr:rowOff>0</xdr:rowOff></xdr:{tag}>",
+            area.min_col - 1,
+            area.min_row - 1
+        )
+    };
+    format!(
+        "<xdr:twoCellAnchor xmlns:xdr=\"{SHEET_DRAWING_NS}\" xmlns:a=\"{DRAWINGML_NS}\">{}{}\
+<xdr:graphicFrame macro=\"\"><xdr:nvGraphicFramePr><xdr:cNvPr id=\"{id}\" name=\"Chart {}\"/>\
+<xdr:cNvGraphicFramePr/></xdr:nvGraphicFramePr>\
+<xdr:xfrm><a:off x=\"0\" y=\"0\"/><a:ext cx=\"0\" cy=\"0\"/></xdr:xfrm>\
+<a:graphic><a:graphicData uri=\"{CHART_NS}\">\
+<c:chart xmlns:c=\"{CHART_NS}\" xmlns:r=\"{REL_NS}\" r:id=\"{rel_id}\"/>\
+</a:graphicData></a:graphic></xdr:graphicFrame><xdr:clientData/></xdr:twoCellAnchor>",
+        marker("from", from),
+        marker("to", to),
+        id - 1
+    )
+}
+
+/// Worksheet children that must come after `<drawing>`.
+const AFTER_DRAWING: &[&[u8]] = &[
+    b"legacyDrawing",
+    b"legacyDrawingHF",
+    b"drawingHF",
+    b"picture",
+    b"oleObjects",
+    b"controls",
+    b"webPublishItems",
+    b"tableParts",
+    b"extLst",
+];
+
+/// Insert `<drawing r:id=".."/>` where the worksheet schema wants it.
+fn insert_drawing_element(sheet_xml: &str, rel_id: &str) -> Result<String> {
+    let mut reader = Reader::from_str(sheet_xml);
+    let mut depth = 0usize;
+    let mut insert_at = None;
+    loop {
+        let position = reader.buffer_position();
+        match reader.read_event()? {
+            Event::Start(ref e) => {
+                if depth == 1 && AFTER_DRAWING.contains(&e.local_name().as_ref()) {
+                    insert_at = Some(position);
+                    break;
+                }
+                depth += 1;
+            }
+            Event::Empty(ref e) => {
+                if depth == 1 && AFTER_DRAWING.contains(&e.local_name().as_ref()) {
+                    insert_at = Some(position);
+                    break;
+                }
+            }
+            Event::End(_) => {
+                depth -= 1;
+                if depth == 0 {
+                    insert_at = Some(position);
+                    break;
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    let at = insert_at.ok_or_else(|| anyhow!("worksheet part has no closing tag"))?;
+    let mut out = sheet_xml.to_string();
+    out.insert_str(
+        at,
+        &format!("<drawing xmlns:r=\"{REL_NS}\" r:id=\"{rel_id}\"/>"),
+    );
+    Ok(out)
+}
+
+/// Replace the chart title; an empty title removes it.
+fn set_chart_title(chart: &mut XmlNode, text: &str) -> Result<()> {
+    chart.children.retain(|c| c.name != "title");
+    if text.is_empty() {
+        match chart.child_mut("autoTitleDeleted") {
+            Some(flag) => flag.set_attr("val", "1"),
+            None => chart
+                .children
+                .insert(0, fragment_element("<c:autoTitleDeleted val=\"1\"/>")?),
+        }
+        return Ok(());
+    }
+    chart
+        .children
+        .insert(0, fragment_element(&title_xml(text))?);
+    if let Some(flag) = chart.child_mut("autoTitleDeleted") {
+        flag.set_attr("val", "0");
+    }
+    Ok(())
+}
+
+/// Title the first horizontal (bottom/top) or vertical (left/right) axis.
+/// Returns false when the chart has no such axis.
+fn set_axis_title(plot_area: &mut XmlNode, horizontal: bool, text: &str) -> Result<bool> {
+    let positions: &[&str] = if horizontal { &["b", "t"] } else { &["l", "r"] };
+    let Some(axis) = plot_area.children.iter_mut().find(|node| {
+        matches!(node.name.as_str(), "catAx" | "valAx" | "dateAx" | "serAx")
+            && node.val("axPos").is_some_and(|p| positions.contains(&p))
+    }) else {
+        return Ok(false);
+    };
+    axis.children.retain(|c| c.name != "title");
+    if !text.is_empty() {
+        let at = axis
+            .children
+            .iter()
+            .rposition(|c| {
+                matches!(
+                    c.name.as_str(),
+                    "axId" | "scaling" | "delete" | "axPos" | "majorGridlines" | "minorGridlines"
+                )
+            })
+            .map_or(0, |i| i + 1);
+        axis.children
+            .insert(at, fragment_element(&title_xml(text))?);
+    }
+    Ok(true)
+}
+
+/// Point the chart's series, in plot order, at `series`. Existing series
+/// keep their styling; extra ones are added to the last plot and surplus
+/// ones are dropped.
+fn replace_series(plot_area: &mut XmlNode, series: &[ResolvedSeries]) -> Result<()> {
+    if series.is_empty() {
+        bail!("a chart needs at least one series");
+    }
+    let plots: Vec<usize> = plot_area
+        .children
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| plot_type_name(node).is_some())
+        .map(|(idx, _)| idx)
+        .collect();
+    let Some(&last_plot) = plots.last() else {
+        bail!("chart has no plot to hold series");
+    };
+    let mut next_index = plots
+        .iter()
+        .flat_map(|&p| plot_area.children[p].children_named("ser"))
+        .filter_map(|ser| ser.val("idx")?.parse::<usize>().ok())
+        .max()
+        .map_or(0, |max| max + 1);
+
+    let mut remaining = series.iter();
+    for &p in &plots {
+        let plot = &mut plot_area.children[p];
+        let xy = matches!(plot.name.as_str(), "scatterChart" | "bubbleChart");
+        let mut kept = Vec::with_capacity(plot.children.len());
+        for mut node in std::mem::take(&mut plot.children) {
+            if node.name != "ser" {
+                kept.push(node);
+                continue;
+            }
+            if let Some(spec) = remaining.next() {
+                update_series_node(&mut node, spec, xy)?;
+                kept.push(node);
+            }
+        }
+        plot.children = kept;
+    }
+
+    let plot = &mut plot_area.children[last_plot];
+    let kind = match plot_type_name(plot).as_deref() {
+        Some("scatter") => ChartKind::Scatter,
+        Some("line" | "line_3d") => ChartKind::Line,
+        Some("bar" | "column" | "bar_3d" | "column_3d") => ChartKind::Column,
+        _ => ChartKind::Area,
+    };
+    for spec in remaining {
+        let node = fragment_element(&series_xml(kind, next_index, spec))?;
+        next_index += 1;
+        // New series go after the existing ones, ahead of plot settings.
+        let at = plot
+            .children
+            .iter()
+            .rposition(|c| c.name == "ser")
+            .map(|i| i + 1)
+            .or_else(|| {
+                plot.children.iter().position(|c| {
+                    !matches!(
+                        c.name.as_str(),
+                        "barDir" | "grouping" | "varyColors" | "scatterStyle" | "radarStyle"
+                    )
+                })
+            })
+            .unwrap_or(plot.children.len());
+        plot.children.insert(at, node);
+    }
+    Ok(())
+}
+
+fn update_series_node(ser: &mut XmlNode, spec: &ResolvedSeries, xy: bool) -> Result<()> {
+    ser.children
+        .retain(|c| !matches!(c.name.as_str(), "tx" | "cat" | "val" | "xVal" | "yVal"));
+    let (categories, values) = data_xml(spec, xy);
+    let mut data = Vec::new();
+    if let Some(categories) = categories {
+        data.push(fragment_element(&categories)?);
+    }
+    data.push(fragment_element(&values)?);
+    // Data references sit before the trailing smooth/shape/extLst settings.
+    let at = ser
+        .children
+        .iter()
+        .position(|c| {
+            matches!(
+                c.name.as_str(),
+                "smooth" | "shape" | "bubbleSize" | "extLst"
+            )
+        })
+        .unwrap_or(ser.children.len());
+    for (offset, node) in data.into_iter().enumerate() {
+        ser.children.insert(at + offset, node);
+    }
+    if let Some(tx) = tx_xml(spec) {
+        let at = ser
+            .children
+            .iter()
+            .position(|c| !matches!(c.name.as_str(), "idx" | "order"))
+            .unwrap_or(ser.children.len());
+        ser.children.insert(at, fragment_element(&tx)?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(values: &str, name: Option<&str>) -> ResolvedSeries {
+        ResolvedSeries {
+            name_ref: None,
+            name: name.map(str::to_string),
+            categories: Some(ResolvedRef {
+                formula: "Data!$A$2:$A$3".to_string(),
+                text: vec![Some("Q1".to_string()), Some("Q2".to_string())],
+                numbers: vec![None, None],
+            }),
+            values: ResolvedRef {
+                formula: values.to_string(),
+                text: vec![Some("1".to_string()), None],
+                numbers: vec![Some(1.0), None],
+            },
+        }
+    }
+
+    #[test]
+    fn built_charts_read_back_with_titles_and_series() {
+        let titles = ChartTitles {
+            title: Some("Sales & Costs".to_string()),
+            x_axis: Some("Quarter".to_string()),
+            y_axis: Some("USD".to_string()),
+        };
+        let xml = chart_space_xml(
+            ChartKind::Bar,
+            &[series("Data!$B$2:$B$3", Some("Sales"))],
+            &titles,
+        );
+        let chart = crate::charts::parse_chart_space(&XmlNode::parse(&xml).unwrap());
+        assert_eq!(chart.chart_type, "bar");
+        assert_eq!(chart.title.as_deref(), Some("Sales & Costs"));
+        assert_eq!(chart.series[0].name_literal.as_deref(), Some("Sales"));
+        assert_eq!(
+            chart.series[0].values.cache,
+            vec![Some("1".to_string()), None]
+        );
+        // Horizontal bars put values along the bottom.
+        let bottom = chart
+            .axes
+            .iter()
+            .find(|a| a.position.as_deref() == Some("bottom"));
+        assert_eq!(bottom.unwrap().kind, "value");
+        assert_eq!(bottom.unwrap().title.as_deref(), Some("Quarter"));
+    }
+
+    #[test]
+    fn updates_keep_styling_and_replace_series_in_order() {
+        let xml = chart_space_xml(
+            ChartKind::Line,
+            &[
+                series("Data!$B$2:$B$3", Some("A")),
+                series("Data!$C$2:$C$3", Some("B")),
+            ],
+            &ChartTitles::default(),
+        );
+        let mut root = XmlNode::parse(&xml).unwrap();
+        let chart = root
+            .child_mut("chartSpace")
+            .unwrap()
+            .child_mut("chart")
+            .unwrap();
+        set_chart_title(chart, "Trend").unwrap();
+        let plot_area = chart.child_mut("plotArea").unwrap();
+        assert!(set_axis_title(plot_area, false, "Units").unwrap());
+        replace_series(
+            plot_area,
+            &[
+                series("Data!$D$2:$D$3", Some("D")),
+                series("Data!$E$2:$E$3", None),
+                series("Data!$F$2:$F$3", Some("F")),
+            ],
+        )
+        .unwrap();
+
+        let chart = crate::charts::parse_chart_space(&XmlNode::parse(&root.to_document()).unwrap());
+        assert_eq!(chart.title.as_deref(), Some("Trend"));
+        let values: Vec<_> = chart
+            .series
+            .iter()
+            .map(|s| s.values.formula.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            values,
+            ["Data!$D$2:$D$3", "Data!$E$2:$E$3", "Data!$F$2:$F$3"]
+        );
+        assert_eq!(chart.series[1].name_literal, None);
+        assert_eq!(chart.series[2].index, 2);
+        assert_eq!(chart.axes[1].title.as_deref(), Some("Units"));
+
+        let plot_area = root
+            .child_mut("chartSpace")
+            .unwrap()
+            .child_mut("chart")
+            .unwrap()
+            .child_mut("plotArea")
+            .unwrap();
+        let line = plot_area.child("lineChart").unwrap();
+        // Series stay ahead of the plot-level marker and axis ids.
+        let names: Vec<_> = line.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "grouping",
+                "varyColors",
+                "ser",
+                "ser",
+                "ser",
+                "marker",
+                "axId",
+                "axId"
+            ]
+        );
+        let first = line.child("ser").unwrap();
+        assert!(first.child("marker").is_some());
+    }
+
+    #[test]
+    fn drawing_element_goes_before_table_parts() {
+        let sheet = r#"<worksheet xmlns="x"><sheetData><row r="1"/></sheetData><pageMargins left="0.7"/><tableParts count="1"><tablePart r:id="rId1"/></tableParts></worksheet>"#;
+        let out = insert_drawing_element(sheet, "rId2").unwrap();
+        let drawing = out.find("<drawing").unwrap();
+        assert!(drawing > out.find("<pageMargins").unwrap());
+        assert!(drawing < out.find("<tableParts").unwrap());
+
+        let bare = insert_drawing_element("<worksheet><sheetData/></worksheet>", "rId1").unwrap();
+        assert!(bare.ends_with(r#"r:id="rId1"/></worksheet>"#));
+    }
+}
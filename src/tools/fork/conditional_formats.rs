@@ -0,0 +1,404 @@
+//! Conditional-format rules for `conditional_format_batch`.
+//!
+//! Rules are built as umya structures. umya keeps a single `<formula>` per
+//! rule, so `between`/`not_between` cell-is rules are written as the
+//! equivalent expression anchored at the top-left cell of the target.
+
+use crate::data_validation::{Area, parse_sqref, subtract_sqref};
+use crate::model::StylePatch;
+use crate::styles::{StylePatchMode, apply_style_patch};
+use crate::utils::cell_address;
+use anyhow::{Result, anyhow, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use umya_spreadsheet::Worksheet;
+use umya_spreadsheet::structs::{
+    Color, ColorScale, ConditionalFormatValueObject, ConditionalFormatValueObjectValues,
+    ConditionalFormatValues, ConditionalFormatting, ConditionalFormattingOperatorValues,
+    ConditionalFormattingRule, DataBar, Formula, IconSet, Style,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConditionalFormatRuleSpec {
+    /// Compare the cell value: equal | not_equal | greater_than |
+    /// greater_than_or_equal | less_than | less_than_or_equal | between |
+    /// not_between. `formula2` is the upper bound for the range operators.
+    CellIs {
+        operator: String,
+        formula: String,
+        #[serde(default)]
+        formula2: Option<String>,
+    },
+    /// Formula evaluated relative to the top-left cell of the target,
+    /// e.g. "$C2>$D2".
+    Expression { formula: String },
+    /// Two or three colors from the lowest to the highest value. Points
+    /// default to min/max, with the 50th percentile as midpoint.
+    ColorScale {
+        colors: Vec<String>,
+        #[serde(default)]
+        points: Option<Vec<CfThreshold>>,
+    },
+    DataBar {
+        color: String,
+        #[serde(default)]
+        min: Option<CfThreshold>,
+        #[serde(default)]
+        max: Option<CfThreshold>,
+    },
+    /// Three traffic lights; thresholds default to 0/33/67 percent.
+    IconSet {
+        #[serde(default)]
+        thresholds: Option<Vec<CfThreshold>>,
+    },
+    /// Highlight the top (or bottom) `rank` values or percent.
+    TopN {
+        #[serde(default = "default_top_rank")]
+        rank: u32,
+        #[serde(default)]
+        bottom: bool,
+        #[serde(default)]
+        percent: bool,
+    },
+    /// Highlight duplicated values, or unique ones with `unique`.
+    Duplicate {
+        #[serde(default)]
+        unique: bool,
+    },
+    /// Remove conditional formatting from the target cells.
+    Clear,
+}
+
+fn default_top_rank() -> u32 {
+    10
+}
+
+/// A color-scale, data-bar or icon-set point.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CfThreshold {
+    /// min | max | num | percent | percentile | formula
+    pub kind: String,
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+impl CfThreshold {
+    fn new(kind: &str, value: Option<&str>) -> Self {
+        Self {
+            kind: kind.to_string(),
+            value: value.map(str::to_string),
+        }
+    }
+
+    fn to_cfvo(&self) -> Result<ConditionalFormatValueObject> {
+        let kind = match self.kind.to_ascii_lowercase().as_str() {
+            "min" => ConditionalFormatValueObjectValues::Min,
+            "max" => ConditionalFormatValueObjectValues::Max,
+            "num" | "number" => ConditionalFormatValueObjectValues::Number,
+            "percent" => ConditionalFormatValueObjectValues::Percent,
+            "percentile" => ConditionalFormatValueObjectValues::Percentile,
+            "formula" => ConditionalFormatValueObjectValues::Formula,
+            other => bail!(
+                "unknown threshold kind '{}' (expected min, max, num, percent, percentile or formula)",
+                other
+            ),
+        };
+        let needs_value = !matches!(
+            kind,
+            ConditionalFormatValueObjectValues::Min | ConditionalFormatValueObjectValues::Max
+        );
+        let mut cfvo = ConditionalFormatValueObject::default();
+        cfvo.set_type(kind);
+        match &self.value {
+            Some(value) => {
+                cfvo.set_val(strip_formula(value));
+            }
+            None if needs_value => bail!("threshold '{}' needs a value", self.kind),
+            None => {}
+        }
+        Ok(cfvo)
+    }
+}
+
+/// Which umya rule type a spec produces, for summary counts.
+pub(super) fn spec_count_key(spec: &ConditionalFormatRuleSpec) -> &'static str {
+    match spec {
+        ConditionalFormatRuleSpec::CellIs { .. } => "cell_is_rules_added",
+        ConditionalFormatRuleSpec::Expression { .. } => "expression_rules_added",
+        ConditionalFormatRuleSpec::ColorScale { .. } => "color_scales_added",
+        ConditionalFormatRuleSpec::DataBar { .. } => "data_bars_added",
+        ConditionalFormatRuleSpec::IconSet { .. } => "icon_sets_added",
+        ConditionalFormatRuleSpec::TopN { .. } => "top_n_rules_added",
+        ConditionalFormatRuleSpec::Duplicate { .. } => "duplicate_rules_added",
+        ConditionalFormatRuleSpec::Clear => "formats_cleared",
+    }
+}
+
+fn strip_formula(formula: &str) -> String {
+    let formula = formula.trim();
+    formula.strip_prefix('=').unwrap_or(formula).to_string()
+}
+
+fn formula(text: &str) -> Formula {
+    let mut formula = Formula::default();
+    formula.set_string_value(strip_formula(text));
+    formula
+}
+
+fn color(argb: &str) -> Color {
+    let mut color = Color::default();
+    color.set_argb(normalize_argb(argb));
+    color
+}
+
+/// Accept "#RRGGBB", "RRGGBB" or "AARRGGBB".
+pub(super) fn normalize_argb(value: &str) -> String {
+    let hex = value.trim().trim_start_matches('#').to_ascii_uppercase();
+    if hex.len() == 6 {
+        format!("FF{hex}")
+    } else {
+        hex
+    }
+}
+
+fn cell_is_operator(name: &str) -> Result<ConditionalFormattingOperatorValues> {
+    Ok(match name.to_ascii_lowercase().replace('_', "").as_str() {
+        "equal" => ConditionalFormattingOperatorValues::Equal,
+        "notequal" => ConditionalFormattingOperatorValues::NotEqual,
+        "greaterthan" => ConditionalFormattingOperatorValues::GreaterThan,
+        "greaterthanorequal" => ConditionalFormattingOperatorValues::GreaterThanOrEqual,
+        "lessthan" => ConditionalFormattingOperatorValues::LessThan,
+        "lessthanorequal" => ConditionalFormattingOperatorValues::LessThanOrEqual,
+        "between" => ConditionalFormattingOperatorValues::Between,
+        "notbetween" => ConditionalFormattingOperatorValues::NotBetween,
+        _ => bail!("unknown cell_is operator '{}'", name),
+    })
+}
+
+/// Build the rule for `spec` over `areas`. `Clear` has no rule.
+pub(super) fn build_rule(
+    spec: &ConditionalFormatRuleSpec,
+    areas: &[Area],
+    style: Option<&StylePatch>,
+    stop_if_true: bool,
+) -> Result<Option<ConditionalFormattingRule>> {
+    let anchor = areas
+        .first()
+        .map(|a| cell_address(a.min_col, a.min_row))
+        .ok_or_else(|| anyhow!("conditional format target is empty"))?;
+    let mut rule = ConditionalFormattingRule::default();
+    let mut takes_style = true;
+
+    match spec {
+        ConditionalFormatRuleSpec::CellIs {
+            operator: name,
+            formula: first,
+            formula2,
+        } => match cell_is_operator(name)? {
+            operator @ (ConditionalFormattingOperatorValues::Between
+            | ConditionalFormattingOperatorValues::NotBetween) => {
+                let second = formula2
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("cell_is '{}' needs formula2", name))?;
+                let (low, high) = (strip_formula(first), strip_formula(second));
+                let expression = match operator {
+                    ConditionalFormattingOperatorValues::Between => {
+                        format!("AND({anchor}>={low},{anchor}<={high})")
+                    }
+                    _ => format!("OR({anchor}<{low},{anchor}>{high})"),
+                };
+                rule.set_type(ConditionalFormatValues::Expression);
+                rule.set_formula(formula(&expression));
+            }
+            operator => {
+                rule.set_type(ConditionalFormatValues::CellIs);
+                rule.set_operator(operator);
+                rule.set_formula(formula(first));
+            }
+        },
+        ConditionalFormatRuleSpec::Expression { formula: text } => {
+            rule.set_type(ConditionalFormatValues::Expression);
+            rule.set_formula(formula(text));
+        }
+        ConditionalFormatRuleSpec::ColorScale { colors, points } => {
+            let points = match points {
+                Some(points) => points.clone(),
+                None if colors.len() == 3 => vec![
+                    CfThreshold::new("min", None),
+                    CfThreshold::new("percentile", Some("50")),
+                    CfThreshold::new("max", None),
+                ],
+                None => vec![CfThreshold::new("min", None), CfThreshold::new("max", None)],
+            };
+            if !(2..=3).contains(&colors.len()) || points.len() != colors.len() {
+                bail!("color_scale needs 2 or 3 colors and one point per color");
+            }
+            let mut scale = ColorScale::default();
+            for point in &points {
+                scale.add_cfvo_collection(point.to_cfvo()?);
+            }
+            for argb in colors {
+                scale.add_color_collection(color(argb));
+            }
+            rule.set_type(ConditionalFormatValues::ColorScale);
+            rule.set_color_scale(scale);
+            takes_style = false;
+        }
+        ConditionalFormatRuleSpec::DataBar {
+            color: argb,
+            min,
+            max,
+        } => {
+            let mut bar = DataBar::default();
+            let min = min.clone().unwrap_or_else(|| CfThreshold::new("min", None));
+            let max = max.clone().unwrap_or_else(|| CfThreshold::new("max", None));
+            bar.add_cfvo_collection(min.to_cfvo()?);
+            bar.add_cfvo_collection(max.to_cfvo()?);
+            bar.add_color_collection(color(argb));
+            rule.set_type(ConditionalFormatValues::DataBar);
+            rule.set_data_bar(bar);
+            takes_style = false;
+        }
+        ConditionalFormatRuleSpec::IconSet { thresholds } => {
+            let thresholds = thresholds.clone().unwrap_or_else(|| {
+                ["0", "33", "67"]
+                    .into_iter()
+                    .map(|v| CfThreshold::new("percent", Some(v)))
+                    .collect()
+            });
+            if thresholds.len() != 3 {
+                bail!("icon_set needs three thresholds");
+            }
+            let mut icons = IconSet::default();
+            for threshold in &thresholds {
+                icons.add_cfvo_collection(threshold.to_cfvo()?);
+            }
+            rule.set_type(ConditionalFormatValues::IconSet);
+            rule.set_icon_set(icons);
+            takes_style = false;
+        }
+        ConditionalFormatRuleSpec::TopN {
+            rank,
+            bottom,
+            percent,
+        } => {
+            if *rank == 0 {
+                bail!("top_n rank must be at least 1");
+            }
+            rule.set_type(ConditionalFormatValues::Top10);
+            rule.set_rank(*rank);
+            rule.set_bottom(*bottom);
+            rule.set_percent(*percent);
+        }
+        ConditionalFormatRuleSpec::Duplicate { unique } => {
+            rule.set_type(if *unique {
+                ConditionalFormatValues::UniqueValues
+            } else {
+                ConditionalFormatValues::DuplicateValues
+            });
+        }
+        ConditionalFormatRuleSpec::Clear => return Ok(None),
+    }
+
+    match (takes_style, style) {
+        (true, Some(patch)) => {
+            rule.set_style(apply_style_patch(
+                &Style::default(),
+                patch,
+                StylePatchMode::Set,
+            ));
+        }
+        (true, None) => bail!("this rule type needs a style to apply when it matches"),
+        (false, Some(_)) => bail!("color scales, data bars and icon sets take no style"),
+        (false, None) => {}
+    }
+    rule.set_stop_if_true(stop_if_true);
+    Ok(Some(rule))
+}
+
+/// Add `rule` over `sqref` ahead of every existing rule on the sheet, the
+/// way Excel ranks a newly created rule first.
+pub(super) fn insert_rule(sheet: &mut Worksheet, sqref: &str, mut rule: ConditionalFormattingRule) {
+    let mut formats = sheet.get_conditional_formatting_collection().to_vec();
+    for format in formats.iter_mut() {
+        for existing in format.get_conditional_collection_mut() {
+            let priority = *existing.get_priority();
+            existing.set_priority(priority + 1);
+        }
+    }
+    rule.set_priority(1);
+    let mut format = ConditionalFormatting::default();
+    format.get_sequence_of_references_mut().set_sqref(sqref);
+    format.add_conditional_collection(rule);
+    formats.push(format);
+    sheet.set_conditional_formatting_collection(formats);
+}
+
+/// Take `areas` out of every conditional format on the sheet, dropping the
+/// ones left empty. Returns how many blocks were shrunk or dropped.
+pub(super) fn clear_areas(sheet: &mut Worksheet, areas: &[Area]) -> u64 {
+    let mut changed = 0u64;
+    let mut formats = Vec::new();
+    for mut format in sheet.get_conditional_formatting_collection().to_vec() {
+        let before = format.get_sequence_of_references().get_sqref();
+        if !parse_sqref(&before)
+            .iter()
+            .any(|a| areas.iter().any(|b| a.intersects(b)))
+        {
+            formats.push(format);
+            continue;
+        }
+        changed += 1;
+        if let Some(after) = subtract_sqref(&before, areas) {
+            format.get_sequence_of_references_mut().set_sqref(after);
+            formats.push(format);
+        }
+    }
+    sheet.set_conditional_formatting_collection(formats);
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn between_becomes_an_anchored_expression() {
+        let areas = parse_sqref("C2:C9");
+        let spec = ConditionalFormatRuleSpec::CellIs {
+            operator: "between".to_string(),
+            formula: "=10".to_string(),
+            formula2: Some("20".to_string()),
+        };
+        let patch: StylePatch =
+            serde_json::from_value(serde_json::json!({"font": {"bold": true}})).unwrap();
+        let rule = build_rule(&spec, &areas, Some(&patch), false)
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            rule.get_type(),
+            ConditionalFormatValues::Expression
+        ));
+        assert_eq!(
+            rule.get_formula().unwrap().get_string_value(),
+            "AND(C2>=10,C2<=20)"
+        );
+
+        let missing_style = build_rule(&spec, &areas, None, false);
+        assert!(missing_style.is_err());
+    }
+
+    #[test]
+    fn scales_default_their_points() {
+        let areas = parse_sqref("B2:B9");
+        let spec = ConditionalFormatRuleSpec::ColorScale {
+            colors: vec!["#F8696B".into(), "FFEB84".into(), "FF63BE7B".into()],
+            points: None,
+        };
+        let rule = build_rule(&spec, &areas, None, false).unwrap().unwrap();
+        let scale = rule.get_color_scale().unwrap();
+        assert_eq!(scale.get_cfvo_collection().len(), 3);
+        assert_eq!(scale.get_color_collection()[0].get_argb(), "FFF8696B");
+    }
+}
@@ -0,0 +1,197 @@
+//! Package-level editing for fork ops that umya cannot express.
+//!
+//! Parts are read from the archive, rewritten in memory and written back
+//! once by [`Package::save`], so a batch touches the file a single time.
+
+use crate::charts::{XmlNode, parse_relationships};
+use crate::comments::{read_string, relative_target, rels_path_for, sheet_parts};
+use anyhow::{Result, anyhow};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use zip::ZipArchive;
+use zip::write::FileOptions;
+
+const PACKAGE_REL_NS: &str = "http://schemas.openxmlformats.org/package/2006/relationships";
+
+/// An xlsx package opened for edits.
+pub(super) struct Package {
+    zip: ZipArchive<File>,
+    sheets: Vec<(String, String)>,
+    changed: BTreeMap<String, String>,
+    removed: BTreeSet<String>,
+}
+
+impl Package {
+    pub(super) fn open(path: &Path) -> Result<Self> {
+        let mut zip = ZipArchive::new(File::open(path)?)?;
+        let sheets = sheet_parts(&mut zip)?;
+        Ok(Self {
+            zip,
+            sheets,
+            changed: BTreeMap::new(),
+            removed: BTreeSet::new(),
+        })
+    }
+
+    pub(super) fn read(&mut self, part: &str) -> Result<Option<String>> {
+        if self.removed.contains(part) {
+            return Ok(None);
+        }
+        if let Some(content) = self.changed.get(part) {
+            return Ok(Some(content.clone()));
+        }
+        read_string(&mut self.zip, part)
+    }
+
+    pub(super) fn write(&mut self, part: &str, content: String) {
+        self.removed.remove(part);
+        self.changed.insert(part.to_string(), content);
+    }
+
+    pub(super) fn remove(&mut self, part: &str) {
+        self.changed.remove(part);
+        self.removed.insert(part.to_string());
+    }
+
+    pub(super) fn exists(&self, part: &str) -> bool {
+        self.changed.contains_key(part)
+            || (!self.removed.contains(part) && self.zip.file_names().any(|name| name == part))
+    }
+
+    /// First free `{prefix}{n}.xml`, e.g. `xl/charts/chart3.xml`.
+    pub(super) fn next_part(&self, prefix: &str) -> String {
+        (1..)
+            .map(|n| format!("{prefix}{n}.xml"))
+            .find(|part| !self.exists(part))
+            .expect("unbounded part numbers")
+    }
+
+    pub(super) fn sheet_path(&self, sheet_name: &str) -> Result<String> {
+        self.sheets
+            .iter()
+            .find(|(name, _)| name == sheet_name)
+            .map(|(_, path)| path.clone())
+            .ok_or_else(|| anyhow!("sheet '{}' not found", sheet_name))
+    }
+
+    pub(super) fn relationships(
+        &mut self,
+        owner: &str,
+    ) -> Result<BTreeMap<String, (String, String)>> {
+        match self.read(&rels_path_for(owner))? {
+            Some(rels) => parse_relationships(owner, &rels),
+            None => Ok(BTreeMap::new()),
+        }
+    }
+
+    pub(super) fn add_relationship(
+        &mut self,
+        owner: &str,
+        rel_type: &str,
+        target: &str,
+    ) -> Result<String> {
+        let rels_path = rels_path_for(owner);
+        let xml = self.read(&rels_path)?.unwrap_or_else(|| {
+            format!("<Relationships xmlns=\"{PACKAGE_REL_NS}\"></Relationships>")
+        });
+        let mut root = XmlNode::parse(&xml)?;
+        let list = root
+            .child_mut("Relationships")
+            .ok_or_else(|| anyhow!("{} has no Relationships element", rels_path))?;
+        let next = list
+            .children_named("Relationship")
+            .filter_map(|rel| rel.attr("Id")?.strip_prefix("rId")?.parse::<u32>().ok())
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let id = format!("rId{next}");
+        list.children.push(XmlNode::parse_element(&format!(
+            "<Relationship Id=\"{id}\" Type=\"{rel_type}\" Target=\"{}\"/>",
+            relative_target(owner, target)
+        ))?);
+        self.write(&rels_path, root.to_document());
+        Ok(id)
+    }
+
+    pub(super) fn remove_relationship(&mut self, owner: &str, id: &str) -> Result<()> {
+        let rels_path = rels_path_for(owner);
+        let Some(xml) = self.read(&rels_path)? else {
+            return Ok(());
+        };
+        let mut root = XmlNode::parse(&xml)?;
+        if let Some(list) = root.child_mut("Relationships") {
+            list.children.retain(|rel| rel.attr("Id") != Some(id));
+        }
+        self.write(&rels_path, root.to_document());
+        Ok(())
+    }
+
+    fn content_types(&mut self) -> Result<XmlNode> {
+        let xml = self
+            .read("[Content_Types].xml")?
+            .ok_or_else(|| anyhow!("package has no [Content_Types].xml"))?;
+        XmlNode::parse(&xml)
+    }
+
+    pub(super) fn add_override(&mut self, part: &str, content_type: &str) -> Result<()> {
+        let mut root = self.content_types()?;
+        if let Some(types) = root.child_mut("Types") {
+            types.children.push(XmlNode::parse_element(&format!(
+                "<Override PartName=\"/{part}\" ContentType=\"{content_type}\"/>"
+            ))?);
+        }
+        self.write("[Content_Types].xml", root.to_document());
+        Ok(())
+    }
+
+    pub(super) fn remove_overrides(&mut self, parts: &[String]) -> Result<()> {
+        let mut root = self.content_types()?;
+        if let Some(types) = root.child_mut("Types") {
+            types.children.retain(|entry| {
+                entry.name != "Override"
+                    || !entry
+                        .attr("PartName")
+                        .is_some_and(|name| parts.iter().any(|p| name == format!("/{p}")))
+            });
+        }
+        self.write("[Content_Types].xml", root.to_document());
+        Ok(())
+    }
+
+    pub(super) fn save(mut self, path: &Path) -> Result<()> {
+        if self.changed.is_empty() && self.removed.is_empty() {
+            return Ok(());
+        }
+        let tmp = tempfile::NamedTempFile::new_in(path.parent().unwrap_or(Path::new(".")))?;
+        {
+            let mut writer = zip::ZipWriter::new(tmp.reopen()?);
+            let options =
+                FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+            for i in 0..self.zip.len() {
+                let mut entry = self.zip.by_index(i)?;
+                let name = entry.name().to_string();
+                if self.removed.contains(&name) {
+                    continue;
+                }
+                writer.start_file(name.as_str(), options)?;
+                match self.changed.remove(&name) {
+                    Some(content) => writer.write_all(content.as_bytes())?,
+                    None => {
+                        std::io::copy(&mut entry, &mut writer)?;
+                    }
+                }
+            }
+            // Parts that did not exist yet.
+            for (name, content) in &self.changed {
+                writer.start_file(name.as_str(), options)?;
+                writer.write_all(content.as_bytes())?;
+            }
+            writer.finish()?;
+        }
+        drop(self.zip);
+        tmp.persist(path)?;
+        Ok(())
+    }
+}
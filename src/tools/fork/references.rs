@@ -0,0 +1,1377 @@
+//! Reference maintenance for `structure_batch`.
+//!
+//! Row/column inserts and deletes, sheet renames and deletes, and range moves
+//! update every reference the way Excel does: cell formulas, defined names
+//! (including print areas), table ranges, conditional formats, data
+//! validations, merged cells and autofilters. umya shifts cells and some of
+//! these objects on its own, but not all of them, so the objects of the edited
+//! sheet are captured before the edit and written back with ranges computed
+//! here. Every rewritten object is recorded in a [`RewriteLog`].
+
+use super::{ScreenshotBounds, parse_cell_ref, parse_range_bounds};
+use anyhow::{Result, anyhow};
+use formualizer_parse::tokenizer::Tokenizer;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use umya_spreadsheet::helper::coordinate::{column_index_from_string, string_from_column_index};
+use umya_spreadsheet::structs::{
+    ConditionalFormatting, DataValidations, Range, Table, TableColumn,
+};
+use umya_spreadsheet::{Spreadsheet, Worksheet};
+
+const PRINT_AREA_NAME: &str = "_xlnm.Print_Area";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RewriteKind {
+    Formula,
+    DefinedName,
+    PrintArea,
+    Table,
+    ConditionalFormat,
+    DataValidation,
+    MergedCell,
+    AutoFilter,
+}
+
+impl RewriteKind {
+    fn count_key(self) -> &'static str {
+        match self {
+            RewriteKind::Formula => "formulas_rewritten",
+            RewriteKind::DefinedName => "defined_names_rewritten",
+            RewriteKind::PrintArea => "print_areas_rewritten",
+            RewriteKind::Table => "tables_rewritten",
+            RewriteKind::ConditionalFormat => "conditional_formats_rewritten",
+            RewriteKind::DataValidation => "data_validations_rewritten",
+            RewriteKind::MergedCell => "merged_cells_rewritten",
+            RewriteKind::AutoFilter => "auto_filters_rewritten",
+        }
+    }
+}
+
+/// One object whose reference text changed.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ReferenceRewrite {
+    pub kind: RewriteKind,
+    pub sheet: Option<String>,
+    /// Cell address for formulas; defined or table name for names and tables;
+    /// the covered range for conditional format and validation formulas.
+    pub target: Option<String>,
+    pub before: String,
+    /// `None` when the object was removed because everything it covered was deleted.
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct RewriteLog {
+    pub(super) rewrites: Vec<ReferenceRewrite>,
+}
+
+impl RewriteLog {
+    pub(super) fn record(
+        &mut self,
+        kind: RewriteKind,
+        sheet: Option<&str>,
+        target: Option<&str>,
+        before: &str,
+        after: Option<&str>,
+    ) {
+        self.rewrites.push(ReferenceRewrite {
+            kind,
+            sheet: sheet.map(str::to_string),
+            target: target.map(str::to_string),
+            before: before.to_string(),
+            after: after.map(str::to_string),
+        });
+    }
+
+    pub(super) fn add_counts(&self, counts: &mut BTreeMap<String, u64>) {
+        for rewrite in &self.rewrites {
+            *counts
+                .entry(rewrite.kind.count_key().to_string())
+                .or_insert(0) += 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum StructureAxis {
+    Row,
+    Col,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum StructureEdit {
+    Insert { at: u32, count: u32 },
+    Delete { start: u32, count: u32 },
+}
+
+// ---------------------------------------------------------------------------
+// Reference parsing
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy)]
+struct RefPart {
+    col: Option<u32>,
+    row: Option<u32>,
+    col_lock: bool,
+    row_lock: bool,
+}
+
+impl RefPart {
+    fn format(&self) -> String {
+        let mut out = String::new();
+        if let Some(col) = self.col {
+            if self.col_lock {
+                out.push('$');
+            }
+            out.push_str(&string_from_column_index(&col));
+        }
+        if let Some(row) = self.row {
+            if self.row_lock {
+                out.push('$');
+            }
+            out.push_str(&row.to_string());
+        }
+        out
+    }
+
+    fn is_cell(&self) -> bool {
+        self.col.is_some() && self.row.is_some()
+    }
+
+    fn same_shape(&self, other: &RefPart) -> bool {
+        self.col.is_some() == other.col.is_some() && self.row.is_some() == other.row.is_some()
+    }
+}
+
+/// Parse `A1`, `$A$1`, `A` or `5` (the last two only as halves of a range).
+fn parse_ref_part(segment: &str) -> Option<RefPart> {
+    let bytes = segment.as_bytes();
+    let mut i = 0;
+    let mut col_lock = false;
+    if bytes.first() == Some(&b'$') {
+        col_lock = true;
+        i += 1;
+    }
+    let letters_start = i;
+    while i < bytes.len() && bytes[i].is_ascii_alphabetic() {
+        i += 1;
+    }
+    let letters = &segment[letters_start..i];
+    let mut row_lock = false;
+    if i < bytes.len() && bytes[i] == b'$' {
+        row_lock = true;
+        i += 1;
+    }
+    let digits_start = i;
+    while i < bytes.len() && bytes[i].is_ascii_digit() {
+        i += 1;
+    }
+    let digits = &segment[digits_start..i];
+
+    if i != bytes.len() || letters.len() > 3 || (letters.is_empty() && digits.is_empty()) {
+        return None;
+    }
+    if letters.is_empty() {
+        // `$5`: the leading `$` locks the row.
+        if row_lock {
+            return None;
+        }
+        row_lock = col_lock;
+        col_lock = false;
+    } else if digits.is_empty() && row_lock {
+        return None;
+    }
+
+    let col =
+        (!letters.is_empty()).then(|| column_index_from_string(&letters.to_ascii_uppercase()));
+    let row = if digits.is_empty() {
+        None
+    } else {
+        match digits.parse::<u32>() {
+            Ok(0) | Err(_) => return None,
+            Ok(r) => Some(r),
+        }
+    };
+    Some(RefPart {
+        col,
+        row,
+        col_lock,
+        row_lock,
+    })
+}
+
+/// A single cell (`A1`) or a range of cells, whole columns or whole rows.
+/// Anything else (names, structured references, `#REF!`) is not an area.
+fn parse_area(area: &str) -> Option<(RefPart, Option<RefPart>)> {
+    match area.split_once(':') {
+        Some((start, end)) => {
+            let start = parse_ref_part(start)?;
+            let end = parse_ref_part(end)?;
+            start.same_shape(&end).then_some((start, Some(end)))
+        }
+        None => {
+            let cell = parse_ref_part(area)?;
+            cell.is_cell().then_some((cell, None))
+        }
+    }
+}
+
+fn format_area(start: &RefPart, end: Option<&RefPart>) -> String {
+    match end {
+        Some(end) => format!("{}:{}", start.format(), end.format()),
+        None => start.format(),
+    }
+}
+
+fn adjust_insert(value: u32, at: u32, count: u32) -> u32 {
+    if value >= at { value + count } else { value }
+}
+
+/// New position of one row/column index, `None` when it was deleted.
+fn adjust_index(value: u32, edit: StructureEdit) -> Option<u32> {
+    adjust_span(value, value, edit).map(|(start, _)| start)
+}
+
+/// Excel's rule for a span of rows/columns: inserts inside the span grow it,
+/// deletes shrink it, and it disappears only when all of it was deleted.
+fn adjust_span(start: u32, end: u32, edit: StructureEdit) -> Option<(u32, u32)> {
+    match edit {
+        StructureEdit::Insert { at, count } => Some((
+            adjust_insert(start, at, count),
+            adjust_insert(end, at, count),
+        )),
+        StructureEdit::Delete {
+            start: del_start,
+            count,
+        } => {
+            let del_end = del_start.saturating_add(count.saturating_sub(1));
+            if start >= del_start && end <= del_end {
+                return None;
+            }
+            let new_start = if start < del_start {
+                start
+            } else if start > del_end {
+                start - count
+            } else {
+                del_start
+            };
+            let new_end = if end < del_start {
+                end
+            } else if end > del_end {
+                end - count
+            } else {
+                del_start - 1
+            };
+            Some((new_start, new_end))
+        }
+    }
+}
+
+/// Shift an area for a row/column edit. Returns `None` when the whole area was
+/// deleted; text that is not an area is returned unchanged.
+fn shift_area(area: &str, axis: StructureAxis, edit: StructureEdit) -> Option<String> {
+    let Some((mut start, mut end)) = parse_area(area) else {
+        return Some(area.to_string());
+    };
+    let (first, last) = match axis {
+        StructureAxis::Row => (start.row, end.as_ref().map_or(start.row, |e| e.row)),
+        StructureAxis::Col => (start.col, end.as_ref().map_or(start.col, |e| e.col)),
+    };
+    let (Some(first), Some(last)) = (first, last) else {
+        return Some(area.to_string());
+    };
+    let (new_first, new_last) = adjust_span(first, last, edit)?;
+    match axis {
+        StructureAxis::Row => {
+            start.row = Some(new_first);
+            if let Some(end) = end.as_mut() {
+                end.row = Some(new_last);
+            }
+        }
+        StructureAxis::Col => {
+            start.col = Some(new_first);
+            if let Some(end) = end.as_mut() {
+                end.col = Some(new_last);
+            }
+        }
+    }
+    Some(format_area(&start, end.as_ref()))
+}
+
+/// Shift every area of a space-separated `sqref`, dropping deleted ones.
+fn shift_sqref(sqref: &str, axis: StructureAxis, edit: StructureEdit) -> Option<String> {
+    let parts: Vec<String> = sqref
+        .split_whitespace()
+        .filter_map(|area| shift_area(area, axis, edit))
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// Move an area that lies entirely inside `bounds` by the given offsets.
+fn move_area(
+    area: &str,
+    bounds: &ScreenshotBounds,
+    delta_col: i64,
+    delta_row: i64,
+) -> Option<String> {
+    let (start, end) = parse_area(area)?;
+    let parts = [Some(start), end];
+    let inside = parts
+        .iter()
+        .flatten()
+        .all(|part| match (part.col, part.row) {
+            (Some(col), Some(row)) => {
+                (bounds.min_col..=bounds.max_col).contains(&col)
+                    && (bounds.min_row..=bounds.max_row).contains(&row)
+            }
+            _ => false,
+        });
+    if !inside {
+        return None;
+    }
+    let shift = |part: RefPart| RefPart {
+        col: part.col.map(|c| (c as i64 + delta_col) as u32),
+        row: part.row.map(|r| (r as i64 + delta_row) as u32),
+        ..part
+    };
+    Some(format_area(&shift(start), end.map(shift).as_ref()))
+}
+
+// ---------------------------------------------------------------------------
+// Formula token rewriting
+// ---------------------------------------------------------------------------
+
+/// Rewrite the range tokens of a formula. `rewrite` receives each range token
+/// (e.g. `A1:B2`, `'My Sheet'!$A$1`) and returns its replacement, if any.
+/// Returns the new formula (keeping or omitting the leading `=` as the input
+/// did) only when something changed.
+fn map_range_tokens(
+    formula: &str,
+    mut rewrite: impl FnMut(&str) -> Option<String>,
+) -> Result<Option<String>> {
+    let trimmed = formula.trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+    let had_equals = trimmed.starts_with('=');
+    let formula_in = if had_equals {
+        trimmed.to_string()
+    } else {
+        format!("={}", trimmed)
+    };
+
+    let tokenizer = Tokenizer::new(&formula_in)
+        .map_err(|e| anyhow!("failed to tokenize formula: {}", e.message))?;
+
+    let mut out = String::with_capacity(formula_in.len());
+    let mut cursor = 0usize;
+    let mut changed = false;
+
+    for token in &tokenizer.items {
+        if token.start > cursor {
+            out.push_str(&formula_in[cursor..token.start]);
+        }
+        let mut value = token.value.clone();
+        if token.subtype == formualizer_parse::TokenSubType::Range
+            && let Some(replacement) = rewrite(&value)
+            && replacement != value
+        {
+            value = replacement;
+            changed = true;
+        }
+        out.push_str(&value);
+        cursor = token.end;
+    }
+    if cursor < formula_in.len() {
+        out.push_str(&formula_in[cursor..]);
+    }
+
+    if !changed {
+        return Ok(None);
+    }
+    Ok(Some(if had_equals {
+        out
+    } else {
+        out.strip_prefix('=').unwrap_or(&out).to_string()
+    }))
+}
+
+/// Rewrite formula cells on every sheet except `skip_sheet`. The callback gets
+/// the host sheet name, the cell position and a range token.
+fn rewrite_cell_formulas(
+    book: &mut Spreadsheet,
+    skip_sheet: Option<&str>,
+    log: &mut RewriteLog,
+    mut rewrite: impl FnMut(&str, (u32, u32), &str) -> Option<String>,
+) -> Result<()> {
+    for sheet in book.get_sheet_collection_mut().iter_mut() {
+        let host = sheet.get_name().to_string();
+        if skip_sheet == Some(host.as_str()) {
+            continue;
+        }
+        for cell in sheet.get_cell_collection_mut() {
+            if !cell.is_formula() {
+                continue;
+            }
+            let before = cell.get_formula().to_string();
+            let coordinate = cell.get_coordinate();
+            let pos = (*coordinate.get_col_num(), *coordinate.get_row_num());
+            let Some(after) = map_range_tokens(&before, |token| rewrite(&host, pos, token))? else {
+                continue;
+            };
+            let after = after.strip_prefix('=').unwrap_or(&after).to_string();
+            let address = crate::utils::cell_address(pos.0, pos.1);
+            log.record(
+                RewriteKind::Formula,
+                Some(&host),
+                Some(&address),
+                &before,
+                Some(&after),
+            );
+            cell.set_formula(after);
+        }
+    }
+    Ok(())
+}
+
+/// Rewrite conditional format and data validation formulas on every sheet
+/// except `skip_sheet`. The callback gets the host sheet name and a range token.
+fn rewrite_rule_formulas(
+    book: &mut Spreadsheet,
+    skip_sheet: Option<&str>,
+    log: &mut RewriteLog,
+    mut rewrite: impl FnMut(&str, &str) -> Option<String>,
+) -> Result<()> {
+    for sheet in book.get_sheet_collection_mut().iter_mut() {
+        let host = sheet.get_name().to_string();
+        if skip_sheet == Some(host.as_str()) {
+            continue;
+        }
+        rewrite_sheet_rules(sheet, log, |token| rewrite(&host, token))?;
+    }
+    Ok(())
+}
+
+/// Rewrite the rule formulas of one sheet's conditional formats and the
+/// `formula1`/`formula2` of its data validations, list sources included.
+/// Rewrites are logged against the covered range.
+fn rewrite_sheet_rules(
+    sheet: &mut Worksheet,
+    log: &mut RewriteLog,
+    mut rewrite: impl FnMut(&str) -> Option<String>,
+) -> Result<()> {
+    let host = sheet.get_name().to_string();
+
+    let mut formats = sheet.get_conditional_formatting_collection().to_vec();
+    let mut changed = false;
+    for format in formats.iter_mut() {
+        let sqref = format.get_sequence_of_references().get_sqref();
+        for rule in format.get_conditional_collection_mut() {
+            let Some(mut formula) = rule.get_formula().cloned() else {
+                continue;
+            };
+            let before = formula.get_string_value().to_string();
+            let Some(after) = map_range_tokens(&before, &mut rewrite)? else {
+                continue;
+            };
+            log.record(
+                RewriteKind::ConditionalFormat,
+                Some(&host),
+                Some(&sqref),
+                &before,
+                Some(&after),
+            );
+            formula.set_string_value(after);
+            rule.set_formula(formula);
+            changed = true;
+        }
+    }
+    if changed {
+        sheet.set_conditional_formatting_collection(formats);
+    }
+
+    let Some(mut validations) = sheet.get_data_validations().cloned() else {
+        return Ok(());
+    };
+    let mut changed = false;
+    for dv in validations.get_data_validation_list_mut() {
+        let sqref = dv.get_sequence_of_references().get_sqref();
+        for second in [false, true] {
+            let before = if second {
+                dv.get_formula2()
+            } else {
+                dv.get_formula1()
+            }
+            .to_string();
+            let Some(after) = map_range_tokens(&before, &mut rewrite)? else {
+                continue;
+            };
+            log.record(
+                RewriteKind::DataValidation,
+                Some(&host),
+                Some(&sqref),
+                &before,
+                Some(&after),
+            );
+            if second {
+                dv.set_formula2(after);
+            } else {
+                dv.set_formula1(after);
+            }
+            changed = true;
+        }
+    }
+    if changed {
+        sheet.set_data_validations(validations);
+    }
+    Ok(())
+}
+
+fn name_kind(name: &str) -> RewriteKind {
+    if name.eq_ignore_ascii_case(PRINT_AREA_NAME) {
+        RewriteKind::PrintArea
+    } else {
+        RewriteKind::DefinedName
+    }
+}
+
+/// `refers_to` of every workbook- and sheet-level defined name, taken before
+/// umya touches the book so rewrites always start from the original text.
+struct NameSnapshot {
+    workbook: Vec<String>,
+    sheets: Vec<Vec<String>>,
+}
+
+impl NameSnapshot {
+    fn capture(book: &Spreadsheet) -> Self {
+        Self {
+            workbook: book
+                .get_defined_names()
+                .iter()
+                .map(|d| d.get_address())
+                .collect(),
+            sheets: book
+                .get_sheet_collection_no_check()
+                .iter()
+                .map(|sheet| {
+                    sheet
+                        .get_defined_names()
+                        .iter()
+                        .map(|d| d.get_address())
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// Write back every name as `rewrite` maps its original text.
+    fn rewrite(
+        self,
+        book: &mut Spreadsheet,
+        log: &mut RewriteLog,
+        mut rewrite: impl FnMut(&str) -> Option<String>,
+    ) -> Result<()> {
+        let sheet_names: Vec<String> = book
+            .get_sheet_collection_no_check()
+            .iter()
+            .map(|s| s.get_name().to_string())
+            .collect();
+
+        for (defined, original) in book.get_defined_names_mut().iter_mut().zip(self.workbook) {
+            let scope = defined
+                .has_local_sheet_id()
+                .then(|| sheet_names.get(*defined.get_local_sheet_id() as usize))
+                .flatten();
+            let updated = map_range_tokens(&original, &mut rewrite)?.unwrap_or(original.clone());
+            if updated != original {
+                let name = defined.get_name().to_string();
+                log.record(
+                    name_kind(&name),
+                    scope.map(String::as_str),
+                    Some(&name),
+                    &original,
+                    Some(&updated),
+                );
+            }
+            if defined.get_address() != updated {
+                defined.set_address(updated);
+            }
+        }
+
+        for (sheet, originals) in book.get_sheet_collection_mut().iter_mut().zip(self.sheets) {
+            let scope = sheet.get_name().to_string();
+            for (defined, original) in sheet.get_defined_names_mut().iter_mut().zip(originals) {
+                let updated =
+                    map_range_tokens(&original, &mut rewrite)?.unwrap_or(original.clone());
+                if updated != original {
+                    let name = defined.get_name().to_string();
+                    log.record(
+                        name_kind(&name),
+                        Some(&scope),
+                        Some(&name),
+                        &original,
+                        Some(&updated),
+                    );
+                }
+                if defined.get_address() != updated {
+                    defined.set_address(updated);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split `Sheet!A1` into its sheet part and area; unqualified tokens have none.
+fn split_sheet(token: &str) -> (Option<&str>, &str) {
+    match token.split_once('!') {
+        Some((sheet_part, area)) => (Some(sheet_part), area),
+        None => (None, token),
+    }
+}
+
+fn refers_to_sheet(sheet_part: Option<&str>, host: &str, sheet_name: &str) -> bool {
+    match sheet_part {
+        Some(part) => sheet_part_matches(part, sheet_name),
+        None => host == sheet_name,
+    }
+}
+
+fn sheet_part_matches(sheet_part: &str, old_name: &str) -> bool {
+    let trimmed = sheet_part.trim();
+    if let Some(stripped) = trimmed.strip_prefix('\'')
+        && let Some(inner) = stripped.strip_suffix('\'')
+    {
+        return inner.replace("''", "'") == old_name;
+    }
+    trimmed == old_name
+}
+
+fn format_sheet_prefix_for_formula(sheet_name: &str) -> String {
+    if sheet_name_needs_quoting_for_formula(sheet_name) {
+        let escaped = sheet_name.replace('\'', "''");
+        format!("'{escaped}'!")
+    } else {
+        format!("{sheet_name}!")
+    }
+}
+
+fn sheet_name_needs_quoting_for_formula(name: &str) -> bool {
+    if name.is_empty() {
+        return false;
+    }
+    let bytes = name.as_bytes();
+    if bytes[0].is_ascii_digit() {
+        return true;
+    }
+    for &byte in bytes {
+        match byte {
+            b' ' | b'!' | b'"' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+'
+            | b',' | b'-' | b'.' | b'/' | b':' | b';' | b'<' | b'=' | b'>' | b'?' | b'@' | b'['
+            | b'\\' | b']' | b'^' | b'`' | b'{' | b'|' | b'}' | b'~' => return true,
+            _ => {}
+        }
+    }
+    let upper = name.to_uppercase();
+    matches!(
+        upper.as_str(),
+        "TRUE" | "FALSE" | "NULL" | "REF" | "DIV" | "NAME" | "NUM" | "VALUE" | "N/A"
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Operations
+// ---------------------------------------------------------------------------
+
+fn shift_token(
+    token: &str,
+    host: &str,
+    sheet_name: &str,
+    axis: StructureAxis,
+    edit: StructureEdit,
+) -> Option<String> {
+    let (sheet_part, area) = split_sheet(token);
+    if !refers_to_sheet(sheet_part, host, sheet_name) {
+        return None;
+    }
+    let shifted = shift_area(area, axis, edit).unwrap_or_else(|| "#REF!".to_string());
+    Some(match sheet_part {
+        Some(part) => format!("{part}!{shifted}"),
+        None => shifted,
+    })
+}
+
+/// Insert or delete rows/columns on `sheet_name` and rewrite every reference
+/// to the shifted cells. `edit_cells` performs the cell edit itself.
+pub(super) fn shift_sheet(
+    book: &mut Spreadsheet,
+    sheet_name: &str,
+    axis: StructureAxis,
+    edit: StructureEdit,
+    log: &mut RewriteLog,
+    edit_cells: impl FnOnce(&mut Worksheet),
+) -> Result<()> {
+    let names = NameSnapshot::capture(book);
+    let objects = SheetObjects::capture(
+        book.get_sheet_by_name(sheet_name)
+            .ok_or_else(|| anyhow!("sheet '{}' not found", sheet_name))?,
+    );
+    {
+        let sheet = book
+            .get_sheet_by_name_mut(sheet_name)
+            .ok_or_else(|| anyhow!("sheet '{}' not found", sheet_name))?;
+        edit_cells(sheet);
+        objects.restore(sheet, axis, edit, log)?;
+    }
+
+    let shift = |host: &str, token: &str| shift_token(token, host, sheet_name, axis, edit);
+    rewrite_cell_formulas(book, Some(sheet_name), log, |host, _, token| {
+        shift(host, token)
+    })?;
+    rewrite_rule_formulas(book, Some(sheet_name), log, shift)?;
+    names.rewrite(book, log, |token| shift("", token))
+}
+
+/// Point references at the renamed sheet.
+pub(super) fn rename_sheet(
+    book: &mut Spreadsheet,
+    old_name: &str,
+    new_name: &str,
+    log: &mut RewriteLog,
+) -> Result<()> {
+    let new_prefix = format_sheet_prefix_for_formula(new_name);
+    let rename = |token: &str| match split_sheet(token) {
+        (Some(part), area) if sheet_part_matches(part, old_name) => {
+            Some(format!("{new_prefix}{area}"))
+        }
+        _ => None,
+    };
+    let names = NameSnapshot::capture(book);
+    rewrite_cell_formulas(book, None, log, |_, _, token| rename(token))?;
+    rewrite_rule_formulas(book, None, log, |_, token| rename(token))?;
+    names.rewrite(book, log, rename)
+}
+
+/// Delete a sheet the way Excel does: names scoped to it go with it, and
+/// every remaining reference to it becomes `#REF!`.
+pub(super) fn delete_sheet(book: &mut Spreadsheet, name: &str, log: &mut RewriteLog) -> Result<()> {
+    let index = book
+        .get_sheet_collection_no_check()
+        .iter()
+        .position(|s| s.get_name() == name)
+        .ok_or_else(|| anyhow!("sheet '{}' not found", name))?;
+
+    let mut removed: Vec<(String, String)> = book.get_sheet_collection_no_check()[index]
+        .get_defined_names()
+        .iter()
+        .map(|d| (d.get_name().to_string(), d.get_address()))
+        .collect();
+    book.get_defined_names_mut().retain(|defined| {
+        let scoped =
+            defined.has_local_sheet_id() && *defined.get_local_sheet_id() as usize == index;
+        if scoped {
+            removed.push((defined.get_name().to_string(), defined.get_address()));
+        }
+        !scoped
+    });
+    for (defined_name, address) in &removed {
+        log.record(
+            name_kind(defined_name),
+            Some(name),
+            Some(defined_name),
+            address,
+            None,
+        );
+    }
+    for defined in book.get_defined_names_mut().iter_mut() {
+        if defined.has_local_sheet_id() && *defined.get_local_sheet_id() as usize > index {
+            let id = *defined.get_local_sheet_id();
+            defined.set_local_sheet_id(id - 1);
+        }
+    }
+
+    book.remove_sheet_by_name(name)
+        .map_err(|e| anyhow!("failed to delete sheet '{}': {}", name, e))?;
+
+    let to_ref_error = |token: &str| match split_sheet(token) {
+        (Some(part), area) if sheet_part_matches(part, name) => Some(format!("#REF!{area}")),
+        _ => None,
+    };
+    let names = NameSnapshot::capture(book);
+    rewrite_cell_formulas(book, None, log, |_, _, token| to_ref_error(token))?;
+    rewrite_rule_formulas(book, None, log, |_, token| to_ref_error(token))?;
+    names.rewrite(book, log, to_ref_error)
+}
+
+/// Keep workbook-level names scoped to the same sheet after a new sheet is
+/// inserted at `position`.
+pub(super) fn sheet_inserted_at(book: &mut Spreadsheet, position: usize) {
+    for defined in book.get_defined_names_mut().iter_mut() {
+        if defined.has_local_sheet_id() && *defined.get_local_sheet_id() as usize >= position {
+            let id = *defined.get_local_sheet_id();
+            defined.set_local_sheet_id(id + 1);
+        }
+    }
+}
+
+/// Re-point references after a cut/paste of `src_range` to `dest_anchor`.
+///
+/// References lying entirely inside the moved block follow it, from any sheet
+/// and from defined names. Formulas that moved with the block keep referring
+/// to the cells they referred to before, so an unqualified reference in a
+/// formula moved to another sheet gains its original sheet prefix.
+pub(super) fn repoint_moved_range(
+    book: &mut Spreadsheet,
+    src_sheet: &str,
+    dest_sheet: &str,
+    src_range: &str,
+    dest_anchor: &str,
+    log: &mut RewriteLog,
+) -> Result<()> {
+    let bounds = parse_range_bounds(src_range)?;
+    let (dest_col, dest_row) = parse_cell_ref(dest_anchor)?;
+    let delta_col = dest_col as i64 - bounds.min_col as i64;
+    let delta_row = dest_row as i64 - bounds.min_row as i64;
+    let in_dest_block = |(col, row): (u32, u32)| {
+        (dest_col..dest_col + bounds.cols).contains(&col)
+            && (dest_row..dest_row + bounds.rows).contains(&row)
+    };
+
+    let repoint = |host_now: &str, host_before: &str, token: &str| -> Option<String> {
+        let (sheet_part, area) = split_sheet(token);
+        if refers_to_sheet(sheet_part, host_before, src_sheet)
+            && let Some(moved) = move_area(area, &bounds, delta_col, delta_row)
+        {
+            return Some(match sheet_part {
+                None if host_now == dest_sheet => moved,
+                Some(part) if dest_sheet == src_sheet => format!("{part}!{moved}"),
+                _ => format!("{}{moved}", format_sheet_prefix_for_formula(dest_sheet)),
+            });
+        }
+        if sheet_part.is_none() && host_now != host_before && parse_area(area).is_some() {
+            return Some(format!(
+                "{}{area}",
+                format_sheet_prefix_for_formula(host_before)
+            ));
+        }
+        None
+    };
+
+    let names = NameSnapshot::capture(book);
+    rewrite_cell_formulas(book, None, log, |host, pos, token| {
+        let host_before = if host == dest_sheet && in_dest_block(pos) {
+            src_sheet
+        } else {
+            host
+        };
+        repoint(host, host_before, token)
+    })?;
+    // Rules stay on their sheet when cells move, so they resolve against it.
+    rewrite_rule_formulas(book, None, log, |host, token| repoint(host, host, token))?;
+    names.rewrite(book, log, |token| repoint("", "", token))
+}
+
+// ---------------------------------------------------------------------------
+// Range-bearing objects of one sheet
+// ---------------------------------------------------------------------------
+
+/// Everything on a sheet that holds a range, captured before the cell edit.
+struct SheetObjects {
+    formulas: Vec<((u32, u32), String)>,
+    tables: Vec<Table>,
+    conditional_formats: Vec<ConditionalFormatting>,
+    data_validations: Option<DataValidations>,
+    merges: Vec<Range>,
+    auto_filter: Option<String>,
+}
+
+impl SheetObjects {
+    fn capture(sheet: &Worksheet) -> Self {
+        Self {
+            formulas: sheet
+                .get_cell_collection()
+                .into_iter()
+                .filter(|cell| cell.is_formula())
+                .map(|cell| {
+                    let coordinate = cell.get_coordinate();
+                    (
+                        (*coordinate.get_col_num(), *coordinate.get_row_num()),
+                        cell.get_formula().to_string(),
+                    )
+                })
+                .collect(),
+            tables: sheet.get_tables().to_vec(),
+            conditional_formats: sheet.get_conditional_formatting_collection().to_vec(),
+            data_validations: sheet.get_data_validations().cloned(),
+            merges: sheet.get_merge_cells().to_vec(),
+            auto_filter: sheet
+                .get_auto_filter()
+                .map(|filter| filter.get_range().get_range()),
+        }
+    }
+
+    /// Write every captured object back with its shifted range, whatever the
+    /// cell edit did to it in the meantime.
+    fn restore(
+        self,
+        sheet: &mut Worksheet,
+        axis: StructureAxis,
+        edit: StructureEdit,
+        log: &mut RewriteLog,
+    ) -> Result<()> {
+        let host = sheet.get_name().to_string();
+        let host = host.as_str();
+
+        for ((col, row), original) in self.formulas {
+            let new_pos = match axis {
+                StructureAxis::Row => adjust_index(row, edit).map(|r| (col, r)),
+                StructureAxis::Col => adjust_index(col, edit).map(|c| (c, row)),
+            };
+            let Some((col, row)) = new_pos else {
+                continue;
+            };
+            let updated = map_range_tokens(&original, |token| {
+                shift_token(token, host, host, axis, edit)
+            })?
+            .map(|f| f.strip_prefix('=').unwrap_or(&f).to_string())
+            .unwrap_or_else(|| original.clone());
+            let cell = sheet.get_cell_mut((col, row));
+            if cell.get_formula() != updated {
+                cell.set_formula(updated.clone());
+            }
+            if updated != original {
+                let address = crate::utils::cell_address(col, row);
+                log.record(
+                    RewriteKind::Formula,
+                    Some(host),
+                    Some(&address),
+                    &original,
+                    Some(&updated),
+                );
+            }
+        }
+
+        let mut tables = Vec::with_capacity(self.tables.len());
+        for mut table in self.tables {
+            let name = table.get_display_name().to_string();
+            let (start, end) = table.get_area();
+            let (mut start, mut end) = (
+                (*start.get_col_num(), *start.get_row_num()),
+                (*end.get_col_num(), *end.get_row_num()),
+            );
+            let before = format!(
+                "{}:{}",
+                crate::utils::cell_address(start.0, start.1),
+                crate::utils::cell_address(end.0, end.1)
+            );
+            let span = match axis {
+                StructureAxis::Row => adjust_span(start.1, end.1, edit),
+                StructureAxis::Col => adjust_span(start.0, end.0, edit),
+            };
+            let Some((first, last)) = span else {
+                log.record(RewriteKind::Table, Some(host), Some(&name), &before, None);
+                continue;
+            };
+            match axis {
+                StructureAxis::Row => (start.1, end.1) = (first, last),
+                StructureAxis::Col => {
+                    sync_table_columns(&mut table, start.0, end.0, edit);
+                    (start.0, end.0) = (first, last);
+                }
+            }
+            let start_ref = crate::utils::cell_address(start.0, start.1);
+            let end_ref = crate::utils::cell_address(end.0, end.1);
+            let after = format!("{start_ref}:{end_ref}");
+            if after != before {
+                log.record(
+                    RewriteKind::Table,
+                    Some(host),
+                    Some(&name),
+                    &before,
+                    Some(&after),
+                );
+            }
+            table.set_area((start_ref.as_str(), end_ref.as_str()));
+            tables.push(table);
+        }
+        *sheet.get_tables_mut() = tables;
+
+        let mut formats = Vec::with_capacity(self.conditional_formats.len());
+        for mut cf in self.conditional_formats {
+            let before = cf.get_sequence_of_references().get_sqref();
+            let after = shift_sqref(&before, axis, edit);
+            if after.as_deref() != Some(before.as_str()) {
+                log.record(
+                    RewriteKind::ConditionalFormat,
+                    Some(host),
+                    None,
+                    &before,
+                    after.as_deref(),
+                );
+            }
+            if let Some(after) = after {
+                cf.get_sequence_of_references_mut().set_sqref(after);
+                formats.push(cf);
+            }
+        }
+        sheet.set_conditional_formatting_collection(formats);
+
+        if let Some(mut validations) = self.data_validations {
+            let list = validations.get_data_validation_list_mut();
+            for mut dv in std::mem::take(list) {
+                let before = dv.get_sequence_of_references().get_sqref();
+                let after = shift_sqref(&before, axis, edit);
+                if after.as_deref() != Some(before.as_str()) {
+                    log.record(
+                        RewriteKind::DataValidation,
+                        Some(host),
+                        None,
+                        &before,
+                        after.as_deref(),
+                    );
+                }
+                if let Some(after) = after {
+                    dv.get_sequence_of_references_mut().set_sqref(after);
+                    list.push(dv);
+                }
+            }
+            if list.is_empty() {
+                sheet.remove_data_validations();
+            } else {
+                sheet.set_data_validations(validations);
+            }
+        }
+
+        let mut merges = Vec::with_capacity(self.merges.len());
+        for mut range in self.merges {
+            let before = range.get_range();
+            // A merge shrunk to a single cell is no merge at all.
+            let after = shift_area(&before, axis, edit).filter(|area| {
+                area.split_once(':')
+                    .is_some_and(|(start, end)| start != end)
+            });
+            if after.as_deref() != Some(before.as_str()) {
+                log.record(
+                    RewriteKind::MergedCell,
+                    Some(host),
+                    None,
+                    &before,
+                    after.as_deref(),
+                );
+            }
+            if let Some(after) = after {
+                range.set_range(after);
+                merges.push(range);
+            }
+        }
+        *sheet.get_merge_cells_mut() = merges;
+
+        if let Some(before) = self.auto_filter {
+            let after = shift_area(&before, axis, edit);
+            if after.as_deref() != Some(before.as_str()) {
+                log.record(
+                    RewriteKind::AutoFilter,
+                    Some(host),
+                    None,
+                    &before,
+                    after.as_deref(),
+                );
+            }
+            match after {
+                Some(after) => sheet.set_auto_filter(after),
+                None => sheet.remove_auto_filter(),
+            }
+        }
+
+        rewrite_sheet_rules(sheet, log, |token| {
+            shift_token(token, host, host, axis, edit)
+        })
+    }
+}
+
+/// Keep a table's column list in step with a column insert/delete that falls
+/// inside it (`first`..=`last` are the table's columns before the edit).
+fn sync_table_columns(table: &mut Table, first: u32, last: u32, edit: StructureEdit) {
+    match edit {
+        StructureEdit::Insert { at, count } => {
+            if at <= first || at > last {
+                return;
+            }
+            let mut taken: HashSet<String> = table
+                .get_columns()
+                .iter()
+                .map(|c| c.get_name().to_ascii_lowercase())
+                .collect();
+            let index = (at - first) as usize;
+            let mut next = 1u32;
+            for offset in 0..count as usize {
+                let name = loop {
+                    let candidate = format!("Column{next}");
+                    next += 1;
+                    if taken.insert(candidate.to_ascii_lowercase()) {
+                        break candidate;
+                    }
+                };
+                table
+                    .get_columns_mut()
+                    .insert(index + offset, TableColumn::new(&name));
+            }
+        }
+        StructureEdit::Delete { start, count } => {
+            let end = start.saturating_add(count.saturating_sub(1));
+            let columns = table.get_columns_mut();
+            for col in (first.max(start)..=last.min(end)).rev() {
+                let index = (col - first) as usize;
+                if index < columns.len() {
+                    columns.remove(index);
+                }
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Structured references
+// ---------------------------------------------------------------------------
+
+/// A table edit that structured references (`Sales[Qty]`, `[@Qty]`) follow.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum TableRefEdit<'a> {
+    Rename {
+        old: &'a str,
+        new: &'a str,
+    },
+    /// References naming any of `columns` of `table` become `#REF!`.
+    DropColumns {
+        table: &'a str,
+        columns: &'a [String],
+    },
+}
+
+/// Split `Sales[[#Totals],[Qty]]` into its table name (empty for `[@Qty]`)
+/// and bracketed specifier.
+fn split_structured(token: &str) -> Option<(&str, &str)> {
+    let open = token.find('[')?;
+    if !token.ends_with(']') {
+        return None;
+    }
+    let (table, spec) = token.split_at(open);
+    table
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '\\'))
+        .then_some((table, spec))
+}
+
+/// Column names a specifier mentions. Special items (`#Headers`,
+/// `#This Row`, ...) are skipped and `'` escapes are undone.
+fn structured_columns(spec: &str) -> Vec<String> {
+    let inner = &spec[1..spec.len() - 1];
+    let inner = inner.strip_prefix('@').unwrap_or(inner);
+    if !inner.starts_with('[') {
+        return match inner {
+            "" => Vec::new(),
+            _ if inner.starts_with('#') => Vec::new(),
+            _ => vec![unescape_column(inner)],
+        };
+    }
+    let mut columns = Vec::new();
+    let mut item: Option<String> = None;
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match (c, item.as_mut()) {
+            ('\'', Some(text)) => text.extend(chars.next()),
+            (']', Some(_)) => {
+                let text = item.take().unwrap_or_default();
+                if !text.starts_with('#') {
+                    columns.push(text);
+                }
+            }
+            (c, Some(text)) => text.push(c),
+            ('[', None) => item = Some(String::new()),
+            _ => {}
+        }
+    }
+    columns
+}
+
+fn unescape_column(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\'' {
+            out.extend(chars.next());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Escape a column name for use inside a structured reference.
+pub(super) fn escape_column(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.chars() {
+        if matches!(c, '[' | ']' | '#' | '\'') {
+            out.push('\'');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Rewrite structured references in every formula and defined name. An
+/// unqualified reference belongs to the table its formula sits in.
+pub(super) fn rewrite_structured_refs(
+    book: &mut Spreadsheet,
+    edit: TableRefEdit<'_>,
+    log: &mut RewriteLog,
+) -> Result<()> {
+    let homes: Vec<(String, String, (u32, u32), (u32, u32))> = book
+        .get_sheet_collection_no_check()
+        .iter()
+        .flat_map(|sheet| {
+            sheet.get_tables().iter().map(|table| {
+                let (start, end) = table.get_area();
+                (
+                    sheet.get_name().to_string(),
+                    table.get_display_name().to_string(),
+                    (*start.get_col_num(), *start.get_row_num()),
+                    (*end.get_col_num(), *end.get_row_num()),
+                )
+            })
+        })
+        .collect();
+
+    let rewrite = |home: Option<&str>, token: &str| -> Option<String> {
+        let (table, spec) = split_structured(token)?;
+        let qualified = !table.is_empty();
+        let table = if qualified { table } else { home? };
+        match edit {
+            TableRefEdit::Rename { old, new } => {
+                (qualified && table.eq_ignore_ascii_case(old)).then(|| format!("{new}{spec}"))
+            }
+            TableRefEdit::DropColumns {
+                table: target,
+                columns,
+            } => (table.eq_ignore_ascii_case(target)
+                && structured_columns(spec)
+                    .iter()
+                    .any(|c| columns.iter().any(|d| d.eq_ignore_ascii_case(c))))
+            .then(|| "#REF!".to_string()),
+        }
+    };
+
+    let names = NameSnapshot::capture(book);
+    rewrite_cell_formulas(book, None, log, |host, (col, row), token| {
+        let home = homes
+            .iter()
+            .find(|(sheet, _, start, end)| {
+                sheet == host
+                    && (start.0..=end.0).contains(&col)
+                    && (start.1..=end.1).contains(&row)
+            })
+            .map(|(_, name, _, _)| name.as_str());
+        rewrite(home, token)
+    })?;
+    names.rewrite(book, log, |token| rewrite(None, token))
+}
+
+/// Turn every reference lying entirely inside `range` of `sheet_name` into
+/// `#REF!`, as Excel does for cells deleted with a shift.
+pub(super) fn invalidate_range(
+    book: &mut Spreadsheet,
+    sheet_name: &str,
+    range: &str,
+    log: &mut RewriteLog,
+) -> Result<()> {
+    let bounds = parse_range_bounds(range)?;
+    let invalidate = |host: &str, token: &str| -> Option<String> {
+        let (sheet_part, area) = split_sheet(token);
+        if !refers_to_sheet(sheet_part, host, sheet_name) {
+            return None;
+        }
+        move_area(area, &bounds, 0, 0)?;
+        Some(match sheet_part {
+            Some(part) => format!("{part}!#REF!"),
+            None => "#REF!".to_string(),
+        })
+    };
+
+    let names = NameSnapshot::capture(book);
+    rewrite_cell_formulas(book, None, log, |host, _, token| invalidate(host, token))?;
+    rewrite_rule_formulas(book, None, log, invalidate)?;
+    names.rewrite(book, log, |token| invalidate("", token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shift_area_follows_excel_rules() {
+        let rows = StructureAxis::Row;
+        let insert = StructureEdit::Insert { at: 5, count: 2 };
+        assert_eq!(
+            shift_area("A1:A10", rows, insert).as_deref(),
+            Some("A1:A12")
+        );
+        assert_eq!(shift_area("$B$5", rows, insert).as_deref(), Some("$B$7"));
+        assert_eq!(shift_area("A:A", rows, insert).as_deref(), Some("A:A"));
+        assert_eq!(shift_area("3:8", rows, insert).as_deref(), Some("3:10"));
+
+        let delete = StructureEdit::Delete { start: 1, count: 2 };
+        assert_eq!(shift_area("A2:A5", rows, delete).as_deref(), Some("A1:A3"));
+        assert_eq!(shift_area("A1:B2", rows, delete), None);
+        assert_eq!(
+            shift_area("MyName", rows, delete).as_deref(),
+            Some("MyName")
+        );
+
+        let cols = StructureAxis::Col;
+        let delete = StructureEdit::Delete { start: 2, count: 1 };
+        assert_eq!(shift_area("A1:C3", cols, delete).as_deref(), Some("A1:B3"));
+        assert_eq!(shift_area("$D:$E", cols, delete).as_deref(), Some("$C:$D"));
+    }
+
+    #[test]
+    fn map_range_tokens_rewrites_qualified_references() {
+        let rewritten = map_range_tokens(
+            "SUM('My Sheet'!A1:A3)+Other!B2",
+            |token| match split_sheet(token) {
+                (Some(part), area) if sheet_part_matches(part, "My Sheet") => {
+                    Some(format!("#REF!{area}"))
+                }
+                _ => None,
+            },
+        )
+        .unwrap();
+        assert_eq!(rewritten.as_deref(), Some("SUM(#REF!A1:A3)+Other!B2"));
+    }
+
+    #[test]
+    fn move_area_requires_whole_reference_inside_block() {
+        let bounds = parse_range_bounds("A1:B2").unwrap();
+        assert_eq!(move_area("A1:B2", &bounds, 2, 3).as_deref(), Some("C4:D5"));
+        assert_eq!(move_area("$A$1", &bounds, 2, 3).as_deref(), Some("$C$4"));
+        assert_eq!(move_area("A1:C3", &bounds, 2, 3), None);
+    }
+
+    #[test]
+    fn structured_columns_reads_every_specifier_form() {
+        let columns = |token: &str| structured_columns(split_structured(token).unwrap().1);
+        assert_eq!(columns("Sales[Qty]"), vec!["Qty"]);
+        assert_eq!(columns("[@Qty]"), vec!["Qty"]);
+        assert_eq!(columns("Sales[@[Unit Price]]"), vec!["Unit Price"]);
+        assert_eq!(
+            columns("Sales[[#This Row],[Qty]:[Price '#]]"),
+            vec!["Qty", "Price #"]
+        );
+        assert!(columns("Sales[#All]").is_empty());
+        assert!(columns("Sales[]").is_empty());
+        assert_eq!(split_structured("[1]Sheet1!A1"), None);
+        assert_eq!(escape_column("Price #"), "Price '#");
+    }
+}
@@ -0,0 +1,1220 @@
+//! Sheet view, outline and protection edits for `sheet_settings_batch`.
+//!
+//! These settings are edited at the package level, as charts are: the
+//! worksheet, workbook and styles parts are changed as XML trees, so
+//! whatever else those parts carry is written back untouched.
+
+use super::conditional_formats::normalize_argb;
+use super::package::Package;
+use crate::charts::XmlNode;
+use crate::data_validation::Area;
+use crate::sheet_settings::{PROTECTION_ACTIONS, is_selection_action, split_cell_ref};
+use crate::utils::column_number_to_name;
+use anyhow::{Result, anyhow, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+const WORKBOOK_PART: &str = "xl/workbook.xml";
+const STYLES_PART: &str = "xl/styles.xml";
+
+/// Cells or rows one op may create or restyle.
+const MAX_LOCK_CELLS: u64 = 50_000;
+const MAX_OUTLINE_LEVEL: u8 = 7;
+const DEFAULT_COL_WIDTH: &str = "9.140625";
+
+const WORKSHEET_ORDER: &[&str] = &[
+    "sheetPr",
+    "dimension",
+    "sheetViews",
+    "sheetFormatPr",
+    "cols",
+    "sheetData",
+    "sheetCalcPr",
+    "sheetProtection",
+    "protectedRanges",
+    "scenarios",
+    "autoFilter",
+    "sortState",
+    "dataConsolidate",
+    "customSheetViews",
+    "mergeCells",
+    "phoneticPr",
+    "conditionalFormatting",
+    "dataValidations",
+    "hyperlinks",
+    "printOptions",
+    "pageMargins",
+    "pageSetup",
+    "headerFooter",
+    "rowBreaks",
+    "colBreaks",
+    "customProperties",
+    "cellWatches",
+    "ignoredErrors",
+    "smartTags",
+    "drawing",
+    "legacyDrawing",
+    "legacyDrawingHF",
+    "drawingHF",
+    "picture",
+    "oleObjects",
+    "controls",
+    "webPublishItems",
+    "tableParts",
+    "extLst",
+];
+const SHEET_PR_ORDER: &[&str] = &["tabColor", "outlinePr", "pageSetUpPr"];
+const SHEET_VIEW_ORDER: &[&str] = &["pane", "selection", "pivotSelection", "extLst"];
+const WORKBOOK_ORDER: &[&str] = &[
+    "fileVersion",
+    "fileSharing",
+    "workbookPr",
+    "workbookProtection",
+    "bookViews",
+    "sheets",
+    "functionGroups",
+    "externalReferences",
+    "definedNames",
+    "calcPr",
+    "oleSize",
+    "customWorkbookViews",
+    "pivotCaches",
+    "smartTagPr",
+    "smartTagTypes",
+    "webPublishing",
+    "fileRecoveryPr",
+    "webPublishObjects",
+    "extLst",
+];
+const XF_ORDER: &[&str] = &["alignment", "protection", "extLst"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SheetState {
+    Visible,
+    Hidden,
+    /// Hidden and absent from Excel's Unhide dialog.
+    VeryHidden,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SheetSettingsOp {
+    /// Freeze the top `rows` rows and left `cols` columns; 0 and 0 unfreezes.
+    FreezePanes {
+        sheet_name: String,
+        #[serde(default)]
+        rows: u32,
+        #[serde(default)]
+        cols: u32,
+    },
+    /// Split the window at these distances from its top-left corner, in
+    /// points; 0 and 0 removes the split. Replaces frozen panes.
+    SplitPanes {
+        sheet_name: String,
+        #[serde(default)]
+        x_points: f64,
+        #[serde(default)]
+        y_points: f64,
+    },
+    /// At least one sheet must stay visible.
+    SetVisibility {
+        sheet_name: String,
+        state: SheetState,
+    },
+    /// "#RRGGBB" or "AARRGGBB"; omit `color` to remove the tab color.
+    SetTabColor {
+        sheet_name: String,
+        #[serde(default)]
+        color: Option<String>,
+    },
+    /// Only the fields given change.
+    SetView {
+        sheet_name: String,
+        /// Percentage, 10..=400.
+        #[serde(default)]
+        zoom: Option<u32>,
+        #[serde(default)]
+        show_gridlines: Option<bool>,
+    },
+    /// Add one outline level to whole rows ("5:9") or columns ("C:E").
+    Group {
+        sheet_name: String,
+        range: String,
+        /// Hide the grouped rows or columns straight away.
+        #[serde(default)]
+        collapsed: bool,
+    },
+    /// Remove one outline level; rows or columns leaving their last group
+    /// are shown again.
+    Ungroup {
+        sheet_name: String,
+        range: String,
+    },
+    /// Collapse (hide) or expand (show) grouped rows or columns.
+    SetCollapsed {
+        sheet_name: String,
+        range: String,
+        collapsed: bool,
+    },
+    /// Protect the sheet so locked cells cannot be edited. `allow` lists
+    /// what stays permitted: select_locked_cells, select_unlocked_cells,
+    /// format_cells, format_columns, format_rows, insert_columns,
+    /// insert_rows, insert_hyperlinks, delete_columns, delete_rows, sort,
+    /// auto_filter, pivot_tables. Defaults to the two selections.
+    ProtectSheet {
+        sheet_name: String,
+        #[serde(default)]
+        password: Option<String>,
+        #[serde(default)]
+        allow: Option<Vec<String>>,
+    },
+    UnprotectSheet {
+        sheet_name: String,
+    },
+    /// Lock the workbook structure (adding, deleting, renaming, moving and
+    /// unhiding sheets) and/or its windows.
+    ProtectWorkbook {
+        #[serde(default)]
+        password: Option<String>,
+        #[serde(default = "default_true")]
+        lock_structure: bool,
+        #[serde(default)]
+        lock_windows: bool,
+    },
+    UnprotectWorkbook,
+    /// Mark cells locked or unlocked for when the sheet is protected. Whole
+    /// columns ("C:E") or rows ("5:9") also change the column or row format,
+    /// so cells typed there later follow it.
+    SetCellLock {
+        sheet_name: String,
+        range: String,
+        locked: bool,
+        /// Hide formulas of the cells while the sheet is protected.
+        #[serde(default)]
+        hide_formulas: Option<bool>,
+    },
+}
+
+impl SheetSettingsOp {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SheetSettingsOp::FreezePanes { .. } => "freeze_panes",
+            SheetSettingsOp::SplitPanes { .. } => "split_panes",
+            SheetSettingsOp::SetVisibility { .. } => "set_visibility",
+            SheetSettingsOp::SetTabColor { .. } => "set_tab_color",
+            SheetSettingsOp::SetView { .. } => "set_view",
+            SheetSettingsOp::Group { .. } => "group",
+            SheetSettingsOp::Ungroup { .. } => "ungroup",
+            SheetSettingsOp::SetCollapsed { .. } => "set_collapsed",
+            SheetSettingsOp::ProtectSheet { .. } => "protect_sheet",
+            SheetSettingsOp::UnprotectSheet { .. } => "unprotect_sheet",
+            SheetSettingsOp::ProtectWorkbook { .. } => "protect_workbook",
+            SheetSettingsOp::UnprotectWorkbook => "unprotect_workbook",
+            SheetSettingsOp::SetCellLock { .. } => "set_cell_lock",
+        }
+    }
+
+    /// The sheet the op edits; `None` for workbook protection.
+    pub fn sheet_name(&self) -> Option<&str> {
+        match self {
+            SheetSettingsOp::FreezePanes { sheet_name, .. }
+            | SheetSettingsOp::SplitPanes { sheet_name, .. }
+            | SheetSettingsOp::SetVisibility { sheet_name, .. }
+            | SheetSettingsOp::SetTabColor { sheet_name, .. }
+            | SheetSettingsOp::SetView { sheet_name, .. }
+            | SheetSettingsOp::Group { sheet_name, .. }
+            | SheetSettingsOp::Ungroup { sheet_name, .. }
+            | SheetSettingsOp::SetCollapsed { sheet_name, .. }
+            | SheetSettingsOp::ProtectSheet { sheet_name, .. }
+            | SheetSettingsOp::UnprotectSheet { sheet_name }
+            | SheetSettingsOp::SetCellLock { sheet_name, .. } => Some(sheet_name),
+            SheetSettingsOp::ProtectWorkbook { .. } | SheetSettingsOp::UnprotectWorkbook => None,
+        }
+    }
+
+    /// The rows, columns or cells the op touches, for the change summary.
+    pub fn range(&self) -> Option<&str> {
+        match self {
+            SheetSettingsOp::Group { range, .. }
+            | SheetSettingsOp::Ungroup { range, .. }
+            | SheetSettingsOp::SetCollapsed { range, .. }
+            | SheetSettingsOp::SetCellLock { range, .. } => Some(range),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Axis {
+    Rows,
+    Columns,
+}
+
+impl Package {
+    /// Apply one op. Returns how many cells, rows or columns changed for
+    /// ops that work on a range.
+    pub(super) fn apply_sheet_settings(&mut self, op: &SheetSettingsOp) -> Result<u64> {
+        match op {
+            SheetSettingsOp::FreezePanes {
+                sheet_name,
+                rows,
+                cols,
+            } => self.edit_sheet(sheet_name, |worksheet| {
+                let view = first_sheet_view(worksheet);
+                let top_left = format!("{}{}", column_number_to_name(cols + 1), rows + 1);
+                let mut pane = Vec::new();
+                if *cols > 0 {
+                    pane.push(("xSplit", cols.to_string()));
+                }
+                if *rows > 0 {
+                    pane.push(("ySplit", rows.to_string()));
+                }
+                if !pane.is_empty() {
+                    pane.push(("topLeftCell", top_left.clone()));
+                    pane.push(("activePane", active_pane(*cols > 0, *rows > 0).to_string()));
+                    pane.push(("state", "frozen".to_string()));
+                }
+                set_pane(view, pane, Some(&top_left));
+                Ok(0)
+            }),
+            SheetSettingsOp::SplitPanes {
+                sheet_name,
+                x_points,
+                y_points,
+            } => {
+                if *x_points < 0.0 || *y_points < 0.0 {
+                    bail!("split positions cannot be negative");
+                }
+                self.edit_sheet(sheet_name, |worksheet| {
+                    let view = first_sheet_view(worksheet);
+                    // Split positions are stored in twentieths of a point.
+                    let mut pane = Vec::new();
+                    if *x_points > 0.0 {
+                        pane.push(("xSplit", format!("{}", (x_points * 20.0).round())));
+                    }
+                    if *y_points > 0.0 {
+                        pane.push(("ySplit", format!("{}", (y_points * 20.0).round())));
+                    }
+                    if !pane.is_empty() {
+                        let active = active_pane(*x_points > 0.0, *y_points > 0.0);
+                        pane.push(("activePane", active.to_string()));
+                    }
+                    set_pane(view, pane, None);
+                    Ok(0)
+                })
+            }
+            SheetSettingsOp::SetVisibility { sheet_name, state } => {
+                self.set_visibility(sheet_name, *state)?;
+                Ok(0)
+            }
+            SheetSettingsOp::SetTabColor { sheet_name, color } => {
+                let color = color.as_deref().map(argb).transpose()?;
+                self.edit_sheet(sheet_name, |worksheet| {
+                    let sheet_pr = ensure_child(worksheet, "sheetPr", WORKSHEET_ORDER);
+                    sheet_pr.children.retain(|c| c.name != "tabColor");
+                    if let Some(color) = color {
+                        ensure_child(sheet_pr, "tabColor", SHEET_PR_ORDER).set_attr("rgb", &color);
+                    }
+                    Ok(0)
+                })
+            }
+            SheetSettingsOp::SetView {
+                sheet_name,
+                zoom,
+                show_gridlines,
+            } => {
+                if let Some(zoom) = zoom
+                    && !(10..=400).contains(zoom)
+                {
+                    bail!("zoom must be between 10 and 400, got {}", zoom);
+                }
+                self.edit_sheet(sheet_name, |worksheet| {
+                    let view = first_sheet_view(worksheet);
+                    if let Some(zoom) = zoom {
+                        view.set_attr("zoomScale", &zoom.to_string());
+                    }
+                    match show_gridlines {
+                        Some(false) => view.set_attr("showGridLines", "0"),
+                        Some(true) => remove_attr(view, "showGridLines"),
+                        None => {}
+                    }
+                    Ok(0)
+                })
+            }
+            SheetSettingsOp::Group {
+                sheet_name,
+                range,
+                collapsed,
+            } => {
+                let (axis, lo, hi) = outline_span(range)?;
+                self.edit_sheet(sheet_name, |worksheet| {
+                    let count = shift_outline(worksheet, axis, lo, hi, true)?;
+                    if *collapsed {
+                        set_collapsed(worksheet, axis, lo, hi, true)?;
+                    }
+                    Ok(count)
+                })
+            }
+            SheetSettingsOp::Ungroup { sheet_name, range } => {
+                let (axis, lo, hi) = outline_span(range)?;
+                self.edit_sheet(sheet_name, |worksheet| {
+                    shift_outline(worksheet, axis, lo, hi, false)
+                })
+            }
+            SheetSettingsOp::SetCollapsed {
+                sheet_name,
+                range,
+                collapsed,
+            } => {
+                let (axis, lo, hi) = outline_span(range)?;
+                self.edit_sheet(sheet_name, |worksheet| {
+                    set_collapsed(worksheet, axis, lo, hi, *collapsed)
+                })
+            }
+            SheetSettingsOp::ProtectSheet {
+                sheet_name,
+                password,
+                allow,
+            } => {
+                let allow: Vec<&str> = match allow {
+                    Some(allow) => allow.iter().map(String::as_str).collect(),
+                    None => vec!["select_locked_cells", "select_unlocked_cells"],
+                };
+                for action in &allow {
+                    if !PROTECTION_ACTIONS.iter().any(|(name, _)| name == action) {
+                        let names: Vec<_> = PROTECTION_ACTIONS.iter().map(|(n, _)| *n).collect();
+                        bail!(
+                            "unknown protection action '{}'; expected one of {}",
+                            action,
+                            names.join(", ")
+                        );
+                    }
+                }
+                self.edit_sheet(sheet_name, |worksheet| {
+                    worksheet.children.retain(|c| c.name != "sheetProtection");
+                    let protection = ensure_child(worksheet, "sheetProtection", WORKSHEET_ORDER);
+                    if let Some(password) = password.as_deref().filter(|p| !p.is_empty()) {
+                        protection.set_attr("password", &legacy_password_hash(password));
+                    }
+                    for attr in ["sheet", "objects", "scenarios"] {
+                        protection.set_attr(attr, "1");
+                    }
+                    for (name, attr) in PROTECTION_ACTIONS {
+                        let allowed = allow.contains(name);
+                        if is_selection_action(attr) && !allowed {
+                            protection.set_attr(attr, "1");
+                        } else if !is_selection_action(attr) && allowed {
+                            protection.set_attr(attr, "0");
+                        }
+                    }
+                    Ok(0)
+                })
+            }
+            SheetSettingsOp::UnprotectSheet { sheet_name } => {
+                self.edit_sheet(sheet_name, |worksheet| {
+                    worksheet.children.retain(|c| c.name != "sheetProtection");
+                    Ok(0)
+                })
+            }
+            SheetSettingsOp::ProtectWorkbook {
+                password,
+                lock_structure,
+                lock_windows,
+            } => {
+                if !lock_structure && !lock_windows {
+                    bail!("protect_workbook needs lock_structure or lock_windows");
+                }
+                self.edit_part(WORKBOOK_PART, "workbook", |workbook| {
+                    workbook.children.retain(|c| c.name != "workbookProtection");
+                    let protection = ensure_child(workbook, "workbookProtection", WORKBOOK_ORDER);
+                    if let Some(password) = password.as_deref().filter(|p| !p.is_empty()) {
+                        protection.set_attr("workbookPassword", &legacy_password_hash(password));
+                    }
+                    if *lock_structure {
+                        protection.set_attr("lockStructure", "1");
+                    }
+                    if *lock_windows {
+                        protection.set_attr("lockWindows", "1");
+                    }
+                    Ok(0)
+                })
+            }
+            SheetSettingsOp::UnprotectWorkbook => {
+                self.edit_part(WORKBOOK_PART, "workbook", |workbook| {
+                    workbook.children.retain(|c| c.name != "workbookProtection");
+                    Ok(0)
+                })
+            }
+            SheetSettingsOp::SetCellLock {
+                sheet_name,
+                range,
+                locked,
+                hide_formulas,
+            } => {
+                let area =
+                    Area::parse(range).ok_or_else(|| anyhow!("invalid range '{}'", range))?;
+                let styles = self
+                    .read(STYLES_PART)?
+                    .ok_or_else(|| anyhow!("package has no {}", STYLES_PART))?;
+                let mut styles = XmlNode::parse(&styles)?;
+                let mut formats = ProtectionFormats::new(&mut styles, *locked, *hide_formulas)?;
+                let count = self.edit_sheet(sheet_name, |worksheet| {
+                    set_cell_lock(worksheet, &area, &mut formats)
+                })?;
+                if formats.added {
+                    self.write(STYLES_PART, styles.to_document());
+                }
+                Ok(count)
+            }
+        }
+    }
+
+    fn edit_part<T>(
+        &mut self,
+        part: &str,
+        root_name: &str,
+        edit: impl FnOnce(&mut XmlNode) -> Result<T>,
+    ) -> Result<T> {
+        let xml = self
+            .read(part)?
+            .ok_or_else(|| anyhow!("package has no {}", part))?;
+        let mut root = XmlNode::parse(&xml)?;
+        let node = root
+            .child_mut(root_name)
+            .ok_or_else(|| anyhow!("{} has no {} element", part, root_name))?;
+        let out = edit(node)?;
+        self.write(part, root.to_document());
+        Ok(out)
+    }
+
+    fn edit_sheet<T>(
+        &mut self,
+        sheet_name: &str,
+        edit: impl FnOnce(&mut XmlNode) -> Result<T>,
+    ) -> Result<T> {
+        let part = self.sheet_path(sheet_name)?;
+        self.edit_part(&part, "worksheet", edit)
+    }
+
+    fn set_visibility(&mut self, sheet_name: &str, state: SheetState) -> Result<()> {
+        self.sheet_path(sheet_name)?;
+        // Hidden sheets cannot stay the active or a selected tab.
+        let new_active = self.edit_part(WORKBOOK_PART, "workbook", |workbook| {
+            let sheets = workbook
+                .child_mut("sheets")
+                .ok_or_else(|| anyhow!("workbook has no sheets element"))?;
+            let sheet = sheets
+                .children
+                .iter_mut()
+                .filter(|c| c.name == "sheet")
+                .find(|c| c.attr("name") == Some(sheet_name))
+                .ok_or_else(|| anyhow!("sheet '{}' not found", sheet_name))?;
+            match state {
+                SheetState::Visible => remove_attr(sheet, "state"),
+                SheetState::Hidden => sheet.set_attr("state", "hidden"),
+                SheetState::VeryHidden => sheet.set_attr("state", "veryHidden"),
+            }
+            let names: Vec<(String, bool)> = sheets
+                .children_named("sheet")
+                .map(|s| {
+                    (
+                        s.attr("name").unwrap_or_default().to_string(),
+                        s.attr("state").is_none_or(|st| st == "visible"),
+                    )
+                })
+                .collect();
+            let first_visible =
+                names
+                    .iter()
+                    .position(|(_, visible)| *visible)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "cannot hide '{}': a workbook needs at least one visible sheet",
+                            sheet_name
+                        )
+                    })?;
+            let Some(view) = workbook
+                .child_mut("bookViews")
+                .and_then(|views| views.child_mut("workbookView"))
+            else {
+                return Ok(None);
+            };
+            let visible = |key: &str, default: Option<usize>| {
+                view.attr(key)
+                    .and_then(|v| v.parse::<usize>().ok())
+                    .or(default)
+                    .is_none_or(|i| names.get(i).is_some_and(|(_, visible)| *visible))
+            };
+            let first_sheet_visible = visible("firstSheet", None);
+            let active_visible = visible("activeTab", Some(0));
+            if !first_sheet_visible {
+                view.set_attr("firstSheet", &first_visible.to_string());
+            }
+            if active_visible {
+                return Ok(None);
+            }
+            view.set_attr("activeTab", &first_visible.to_string());
+            Ok(Some(names[first_visible].0.clone()))
+        })?;
+        if state != SheetState::Visible {
+            self.edit_sheet(sheet_name, |worksheet| {
+                if let Some(view) = worksheet
+                    .child_mut("sheetViews")
+                    .and_then(|views| views.child_mut("sheetView"))
+                {
+                    remove_attr(view, "tabSelected");
+                }
+                Ok(())
+            })?;
+        }
+        if let Some(active) = new_active {
+            self.edit_sheet(&active, |worksheet| {
+                first_sheet_view(worksheet).set_attr("tabSelected", "1");
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+}
+
+fn argb(value: &str) -> Result<String> {
+    let hex = normalize_argb(value);
+    if hex.len() != 8 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("color '{}' is not \"#RRGGBB\" or \"AARRGGBB\" hex", value);
+    }
+    Ok(hex)
+}
+
+fn remove_attr(node: &mut XmlNode, key: &str) {
+    node.attrs.retain(|(k, _)| k != key);
+}
+
+fn flag(node: &XmlNode, key: &str) -> bool {
+    node.attr(key)
+        .is_some_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
+fn number(node: &XmlNode, key: &str) -> Option<u32> {
+    node.attr(key).and_then(|v| v.parse().ok())
+}
+
+/// A new element named like its siblings-to-be, sharing the parent's prefix.
+fn element(parent: &XmlNode, name: &str) -> XmlNode {
+    let qname = match parent.qname.split_once(':') {
+        Some((prefix, _)) => format!("{prefix}:{name}"),
+        None => name.to_string(),
+    };
+    XmlNode {
+        name: name.to_string(),
+        qname,
+        ..Default::default()
+    }
+}
+
+/// The child named `name`, created at its schema position when missing.
+fn ensure_child<'a>(parent: &'a mut XmlNode, name: &str, order: &[&str]) -> &'a mut XmlNode {
+    let idx = match parent.children.iter().position(|c| c.name == name) {
+        Some(idx) => idx,
+        None => {
+            let rank = |n: &str| order.iter().position(|o| *o == n);
+            let at = parent
+                .children
+                .iter()
+                .position(|c| matches!((rank(&c.name), rank(name)), (Some(a), Some(b)) if a > b))
+                .unwrap_or(parent.children.len());
+            let node = element(parent, name);
+            parent.children.insert(at, node);
+            at
+        }
+    };
+    &mut parent.children[idx]
+}
+
+fn first_sheet_view(worksheet: &mut XmlNode) -> &mut XmlNode {
+    let views = ensure_child(worksheet, "sheetViews", WORKSHEET_ORDER);
+    if views.child("sheetView").is_none() {
+        let mut view = element(views, "sheetView");
+        view.set_attr("workbookViewId", "0");
+        views.children.push(view);
+    }
+    views
+        .child_mut("sheetView")
+        .expect("sheetView was just ensured")
+}
+
+fn active_pane(x_split: bool, y_split: bool) -> &'static str {
+    match (x_split, y_split) {
+        (true, true) => "bottomRight",
+        (false, true) => "bottomLeft",
+        _ => "topRight",
+    }
+}
+
+/// Replace the view's pane and selections; no attributes leaves no pane.
+fn set_pane(view: &mut XmlNode, attrs: Vec<(&str, String)>, active_cell: Option<&str>) {
+    view.children
+        .retain(|c| c.name != "pane" && c.name != "selection");
+    let Some(active) = attrs
+        .iter()
+        .find(|(k, _)| *k == "activePane")
+        .map(|(_, v)| v.clone())
+    else {
+        return;
+    };
+    let pane = ensure_child(view, "pane", SHEET_VIEW_ORDER);
+    for (key, value) in &attrs {
+        pane.set_attr(key, value);
+    }
+    let mut selection = element(view, "selection");
+    selection.set_attr("pane", &active);
+    if let Some(cell) = active_cell {
+        selection.set_attr("activeCell", cell);
+        selection.set_attr("sqref", cell);
+    }
+    let at = view
+        .children
+        .iter()
+        .position(|c| c.name == "pane")
+        .map_or(0, |i| i + 1);
+    view.children.insert(at, selection);
+}
+
+fn outline_span(range: &str) -> Result<(Axis, u32, u32)> {
+    let area = Area::parse(range).ok_or_else(|| anyhow!("invalid range '{}'", range))?;
+    if area.is_whole_rows() {
+        Ok((Axis::Rows, area.min_row, area.max_row))
+    } else if area.is_whole_columns() {
+        Ok((Axis::Columns, area.min_col, area.max_col))
+    } else {
+        bail!(
+            "'{}' is not whole rows like \"5:9\" or columns like \"C:E\"",
+            range
+        )
+    }
+}
+
+fn check_span(lo: u32, hi: u32, what: &str) -> Result<()> {
+    if u64::from(hi - lo) + 1 > MAX_LOCK_CELLS {
+        bail!(
+            "{} {}..{} exceed the {} per op limit",
+            what,
+            lo,
+            hi,
+            MAX_LOCK_CELLS
+        );
+    }
+    Ok(())
+}
+
+/// Make sure rows `lo..=hi` exist in `sheetData`, keeping rows in order.
+fn ensure_rows(sheet_data: &mut XmlNode, lo: u32, hi: u32) -> Result<()> {
+    check_span(lo, hi, "rows")?;
+    let existing: HashSet<u32> = sheet_data
+        .children
+        .iter()
+        .filter_map(|row| number(row, "r"))
+        .collect();
+    let missing: Vec<u32> = (lo..=hi).filter(|r| !existing.contains(r)).collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    // Unnumbered rows are placed by position, which inserting would shift.
+    if existing.len() < sheet_data.children.len() {
+        bail!("sheet has rows without row numbers; cannot add rows to it");
+    }
+    for r in missing {
+        let mut row = element(sheet_data, "row");
+        row.set_attr("r", &r.to_string());
+        sheet_data.children.push(row);
+    }
+    sheet_data
+        .children
+        .sort_by_key(|row| number(row, "r").unwrap_or(0));
+    Ok(())
+}
+
+fn rows_in(sheet_data: &mut XmlNode, lo: u32, hi: u32) -> impl Iterator<Item = &mut XmlNode> {
+    sheet_data
+        .children
+        .iter_mut()
+        .filter(move |row| number(row, "r").is_some_and(|r| (lo..=hi).contains(&r)))
+}
+
+fn cell_column(cell: &XmlNode) -> Option<u32> {
+    cell.attr("r").and_then(split_cell_ref).map(|(col, _)| col)
+}
+
+/// Split or add `<col>` entries so each one lies wholly inside or outside
+/// `lo..=hi` and the span is fully covered.
+fn split_cols(cols: &mut XmlNode, lo: u32, hi: u32, default_width: &str) {
+    let mut out: Vec<XmlNode> = Vec::new();
+    for col in cols.children.drain(..) {
+        let (Some(min), Some(max)) = (number(&col, "min"), number(&col, "max")) else {
+            out.push(col);
+            continue;
+        };
+        for (a, b) in [
+            (min, max.min(lo - 1)),
+            (min.max(lo), max.min(hi)),
+            (min.max(hi + 1), max),
+        ] {
+            if a <= b {
+                let mut piece = col.clone();
+                piece.set_attr("min", &a.to_string());
+                piece.set_attr("max", &b.to_string());
+                out.push(piece);
+            }
+        }
+    }
+    let mut covered: Vec<(u32, u32)> = out
+        .iter()
+        .filter_map(|c| Some((number(c, "min")?, number(c, "max")?)))
+        .filter(|(a, b)| *a >= lo && *b <= hi)
+        .collect();
+    covered.sort_unstable();
+    let mut next = lo;
+    let mut gaps = Vec::new();
+    for (a, b) in covered {
+        if a > next {
+            gaps.push((next, a - 1));
+        }
+        next = next.max(b + 1);
+    }
+    if next <= hi {
+        gaps.push((next, hi));
+    }
+    for (a, b) in gaps {
+        let mut col = element(cols, "col");
+        col.set_attr("min", &a.to_string());
+        col.set_attr("max", &b.to_string());
+        col.set_attr("width", default_width);
+        out.push(col);
+    }
+    out.sort_by_key(|c| number(c, "min").unwrap_or(0));
+    cols.children = out;
+}
+
+fn cols_in(cols: &mut XmlNode, lo: u32, hi: u32) -> impl Iterator<Item = &mut XmlNode> {
+    cols.children.iter_mut().filter(move |c| {
+        number(c, "min").is_some_and(|min| min >= lo)
+            && number(c, "max").is_some_and(|max| max <= hi)
+    })
+}
+
+fn default_col_width(worksheet: &XmlNode) -> String {
+    worksheet
+        .child("sheetFormatPr")
+        .and_then(|f| f.attr("defaultColWidth"))
+        .unwrap_or(DEFAULT_COL_WIDTH)
+        .to_string()
+}
+
+/// The rows or columns of `lo..=hi`, created as needed.
+fn outline_members(
+    worksheet: &mut XmlNode,
+    axis: Axis,
+    lo: u32,
+    hi: u32,
+) -> Result<Vec<&mut XmlNode>> {
+    Ok(match axis {
+        Axis::Rows => {
+            let sheet_data = ensure_child(worksheet, "sheetData", WORKSHEET_ORDER);
+            ensure_rows(sheet_data, lo, hi)?;
+            rows_in(sheet_data, lo, hi).collect()
+        }
+        Axis::Columns => {
+            let width = default_col_width(worksheet);
+            let cols = ensure_child(worksheet, "cols", WORKSHEET_ORDER);
+            split_cols(cols, lo, hi, &width);
+            cols_in(cols, lo, hi).collect()
+        }
+    })
+}
+
+/// Raise or lower the outline level of `lo..=hi` by one. Returns how many
+/// rows or columns changed.
+fn shift_outline(
+    worksheet: &mut XmlNode,
+    axis: Axis,
+    lo: u32,
+    hi: u32,
+    raise: bool,
+) -> Result<u64> {
+    let mut count = 0;
+    for member in outline_members(worksheet, axis, lo, hi)? {
+        let level = number(member, "outlineLevel").unwrap_or(0) as u8;
+        let next = if raise {
+            if level >= MAX_OUTLINE_LEVEL {
+                bail!("outline levels stop at {}", MAX_OUTLINE_LEVEL);
+            }
+            level + 1
+        } else {
+            level.saturating_sub(1)
+        };
+        if next == level {
+            continue;
+        }
+        if next == 0 {
+            remove_attr(member, "outlineLevel");
+            remove_attr(member, "hidden");
+        } else {
+            member.set_attr("outlineLevel", &next.to_string());
+        }
+        count += match axis {
+            Axis::Rows => 1,
+            Axis::Columns => u64::from(
+                number(member, "max").unwrap_or(0) - number(member, "min").unwrap_or(0) + 1,
+            ),
+        };
+    }
+    if !raise {
+        // A group that is gone leaves no collapsed marker behind.
+        set_summary_collapsed(worksheet, axis, lo, hi, false)?;
+    }
+    update_outline_levels(worksheet);
+    Ok(count)
+}
+
+/// Hide or show the grouped rows or columns and flag their summary row or
+/// column as collapsed.
+fn set_collapsed(
+    worksheet: &mut XmlNode,
+    axis: Axis,
+    lo: u32,
+    hi: u32,
+    collapsed: bool,
+) -> Result<u64> {
+    let mut count = 0;
+    for member in outline_members(worksheet, axis, lo, hi)? {
+        if number(member, "outlineLevel").unwrap_or(0) == 0 {
+            bail!("{}..{} is not fully grouped; group it first", lo, hi);
+        }
+        if collapsed {
+            member.set_attr("hidden", "1");
+        } else {
+            remove_attr(member, "hidden");
+        }
+        count += 1;
+    }
+    set_summary_collapsed(worksheet, axis, lo, hi, collapsed)?;
+    Ok(count)
+}
+
+/// Summary rows sit below their group and summary columns to its right
+/// unless `outlinePr` says otherwise.
+fn set_summary_collapsed(
+    worksheet: &mut XmlNode,
+    axis: Axis,
+    lo: u32,
+    hi: u32,
+    collapsed: bool,
+) -> Result<()> {
+    let outline_pr = worksheet
+        .child("sheetPr")
+        .and_then(|pr| pr.child("outlinePr"));
+    let after = match axis {
+        Axis::Rows => outline_pr.and_then(|p| p.attr("summaryBelow")),
+        Axis::Columns => outline_pr.and_then(|p| p.attr("summaryRight")),
+    }
+    .is_none_or(|v| v != "0");
+    let summary = if after { hi + 1 } else { lo - 1 };
+    let last = match axis {
+        Axis::Rows => 1_048_576,
+        Axis::Columns => 16_384,
+    };
+    if summary == 0 || summary > last {
+        return Ok(());
+    }
+    if !collapsed {
+        let existing: Vec<&mut XmlNode> = match axis {
+            Axis::Rows => worksheet
+                .child_mut("sheetData")
+                .map(|data| rows_in(data, summary, summary).collect())
+                .unwrap_or_default(),
+            Axis::Columns => worksheet
+                .child_mut("cols")
+                .map(|cols| {
+                    cols.children
+                        .iter_mut()
+                        .filter(|c| {
+                            number(c, "min").is_some_and(|min| min <= summary)
+                                && number(c, "max").is_some_and(|max| max >= summary)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        for node in existing {
+            remove_attr(node, "collapsed");
+        }
+        return Ok(());
+    }
+    for node in outline_members(worksheet, axis, summary, summary)? {
+        node.set_attr("collapsed", "1");
+    }
+    Ok(())
+}
+
+/// Keep `sheetFormatPr` outline levels in line with the deepest group.
+fn update_outline_levels(worksheet: &mut XmlNode) {
+    let deepest = |parent: Option<&XmlNode>| {
+        parent
+            .map(|p| {
+                p.children
+                    .iter()
+                    .filter_map(|c| number(c, "outlineLevel"))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .unwrap_or(0)
+    };
+    let rows = deepest(worksheet.child("sheetData"));
+    let cols = deepest(worksheet.child("cols"));
+    if rows == 0 && cols == 0 && worksheet.child("sheetFormatPr").is_none() {
+        return;
+    }
+    let format = ensure_child(worksheet, "sheetFormatPr", WORKSHEET_ORDER);
+    if format.attr("defaultRowHeight").is_none() {
+        format.set_attr("defaultRowHeight", "15");
+    }
+    for (key, level) in [("outlineLevelRow", rows), ("outlineLevelCol", cols)] {
+        if level == 0 {
+            remove_attr(format, key);
+        } else {
+            format.set_attr(key, &level.to_string());
+        }
+    }
+}
+
+/// `cellXfs` entries carrying the requested protection, cloned from the
+/// formats cells already use.
+struct ProtectionFormats<'a> {
+    xfs: &'a mut XmlNode,
+    locked: bool,
+    hide_formulas: Option<bool>,
+    mapped: HashMap<u32, u32>,
+    added: bool,
+}
+
+impl<'a> ProtectionFormats<'a> {
+    fn new(styles: &'a mut XmlNode, locked: bool, hide_formulas: Option<bool>) -> Result<Self> {
+        let xfs = styles
+            .child_mut("styleSheet")
+            .and_then(|sheet| sheet.child_mut("cellXfs"))
+            .ok_or_else(|| anyhow!("styles part has no cellXfs"))?;
+        Ok(Self {
+            xfs,
+            locked,
+            hide_formulas,
+            mapped: HashMap::new(),
+            added: false,
+        })
+    }
+
+    /// Index of the format like `base` but with the requested protection,
+    /// reusing an identical entry when there is one.
+    fn map(&mut self, base: u32) -> Result<u32> {
+        if let Some(idx) = self.mapped.get(&base) {
+            return Ok(*idx);
+        }
+        let mut xf = self
+            .xfs
+            .children_named("xf")
+            .nth(base as usize)
+            .cloned()
+            .ok_or_else(|| anyhow!("cell format {} is missing from styles", base))?;
+        let hidden = self
+            .hide_formulas
+            .unwrap_or_else(|| xf.child("protection").is_some_and(|p| flag(p, "hidden")));
+        xf.children.retain(|c| c.name != "protection");
+        if self.locked && !hidden {
+            remove_attr(&mut xf, "applyProtection");
+        } else {
+            let protection = ensure_child(&mut xf, "protection", XF_ORDER);
+            if !self.locked {
+                protection.set_attr("locked", "0");
+            }
+            if hidden {
+                protection.set_attr("hidden", "1");
+            }
+            xf.set_attr("applyProtection", "1");
+        }
+        let key = serialize(&xf);
+        let existing = self
+            .xfs
+            .children_named("xf")
+            .position(|other| serialize(other) == key);
+        let idx = match existing {
+            Some(idx) => idx as u32,
+            None => {
+                self.xfs.children.push(xf);
+                self.added = true;
+                let count = self.xfs.children_named("xf").count();
+                self.xfs.set_attr("count", &count.to_string());
+                count as u32 - 1
+            }
+        };
+        self.mapped.insert(base, idx);
+        Ok(idx)
+    }
+
+    fn restyle(&mut self, node: &mut XmlNode) -> Result<()> {
+        let idx = self.map(number(node, "s").unwrap_or(0))?;
+        if idx == 0 {
+            remove_attr(node, "s");
+        } else {
+            node.set_attr("s", &idx.to_string());
+        }
+        Ok(())
+    }
+}
+
+fn serialize(node: &XmlNode) -> String {
+    XmlNode {
+        children: vec![node.clone()],
+        ..Default::default()
+    }
+    .to_document()
+}
+
+/// Apply the lock to `area`. Whole rows or columns restyle the row or
+/// column and the cells already in it; other ranges restyle every cell,
+/// creating empty styled cells where none exist. Returns cells restyled.
+fn set_cell_lock(
+    worksheet: &mut XmlNode,
+    area: &Area,
+    formats: &mut ProtectionFormats,
+) -> Result<u64> {
+    let mut count = 0;
+    if area.is_whole_columns() {
+        let width = default_col_width(worksheet);
+        let cols = ensure_child(worksheet, "cols", WORKSHEET_ORDER);
+        split_cols(cols, area.min_col, area.max_col, &width);
+        for col in cols_in(cols, area.min_col, area.max_col) {
+            let style = formats.map(number(col, "style").unwrap_or(0))?;
+            col.set_attr("style", &style.to_string());
+        }
+    } else if area.is_whole_rows() {
+        let sheet_data = ensure_child(worksheet, "sheetData", WORKSHEET_ORDER);
+        ensure_rows(sheet_data, area.min_row, area.max_row)?;
+        for row in rows_in(sheet_data, area.min_row, area.max_row) {
+            formats.restyle(row)?;
+            if row.attr("s").is_some() {
+                row.set_attr("customFormat", "1");
+            } else {
+                remove_attr(row, "customFormat");
+            }
+        }
+    } else {
+        let cells =
+            u64::from(area.max_row - area.min_row + 1) * u64::from(area.max_col - area.min_col + 1);
+        if cells > MAX_LOCK_CELLS {
+            bail!(
+                "{} cells exceed the {} per op limit; lock whole rows or columns instead",
+                cells,
+                MAX_LOCK_CELLS
+            );
+        }
+        let sheet_data = ensure_child(worksheet, "sheetData", WORKSHEET_ORDER);
+        ensure_rows(sheet_data, area.min_row, area.max_row)?;
+        for row in rows_in(sheet_data, area.min_row, area.max_row) {
+            ensure_cells(row, area.min_col, area.max_col);
+        }
+    }
+    let Some(sheet_data) = worksheet.child_mut("sheetData") else {
+        return Ok(count);
+    };
+    for row in rows_in(sheet_data, area.min_row, area.max_row) {
+        for cell in row.children.iter_mut().filter(|c| {
+            c.name == "c"
+                && cell_column(c).is_some_and(|col| (area.min_col..=area.max_col).contains(&col))
+        }) {
+            formats.restyle(cell)?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Make sure cells for columns `lo..=hi` exist in `row`, in column order.
+fn ensure_cells(row: &mut XmlNode, lo: u32, hi: u32) {
+    let Some(r) = number(row, "r") else {
+        return;
+    };
+    let existing: HashSet<u32> = row.children.iter().filter_map(cell_column).collect();
+    if existing.len() < row.children.iter().filter(|c| c.name == "c").count() {
+        // Cells without references are placed by position.
+        return;
+    }
+    let mut added = false;
+    for col in (lo..=hi).filter(|c| !existing.contains(c)) {
+        let mut cell = element(row, "c");
+        cell.set_attr("r", &format!("{}{}", column_number_to_name(col), r));
+        row.children.push(cell);
+        added = true;
+    }
+    if added {
+        row.children
+            .sort_by_key(|cell| cell_column(cell).unwrap_or(u32::MAX));
+    }
+}
+
+/// The legacy 16-bit hash Excel stores for sheet and workbook passwords.
+fn legacy_password_hash(password: &str) -> String {
+    let bytes: Vec<u16> = password.chars().map(|c| (c as u32 & 0xff) as u16).collect();
+    let rotate = |hash: u16| ((hash >> 14) & 0x01) | ((hash << 1) & 0x7fff);
+    let mut hash = 0u16;
+    for byte in bytes.iter().rev() {
+        hash = rotate(hash) ^ byte;
+    }
+    hash = rotate(hash) ^ (bytes.len() as u16) ^ 0xCE4B;
+    format!("{hash:04X}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_passwords_like_excel() {
+        assert_eq!(legacy_password_hash("password"), "83AF");
+    }
+
+    #[test]
+    fn splits_columns_around_a_span() {
+        let mut root = XmlNode::parse(
+            r#"<worksheet><cols><col min="1" max="5" width="12" customWidth="1"/></cols></worksheet>"#,
+        )
+        .unwrap();
+        let cols = root
+            .child_mut("worksheet")
+            .and_then(|w| w.child_mut("cols"))
+            .unwrap();
+        split_cols(cols, 3, 7, DEFAULT_COL_WIDTH);
+        let spans: Vec<_> = cols
+            .children
+            .iter()
+            .map(|c| {
+                (
+                    number(c, "min").unwrap(),
+                    number(c, "max").unwrap(),
+                    c.attr("width").unwrap().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (1, 2, "12".to_string()),
+                (3, 5, "12".to_string()),
+                (6, 7, DEFAULT_COL_WIDTH.to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,771 @@
+//! Excel table (ListObject) edits for `structure_batch`.
+//!
+//! A table's first row is its header row and, while the totals row is shown,
+//! its last row is the totals row. Table names are workbook-wide, so renames
+//! and dropped columns are followed by every structured reference in the book.
+//! Adding or removing a column shifts only the cells of the table's rows, the
+//! way Excel's "Insert/Delete Table Columns" does.
+
+use super::parse_range_bounds;
+use super::references::{self, RewriteKind, RewriteLog, TableRefEdit, escape_column};
+use crate::utils::cell_address;
+use anyhow::{Result, anyhow, bail};
+use std::collections::{BTreeMap, HashSet};
+use umya_spreadsheet::helper::coordinate::column_index_from_string;
+use umya_spreadsheet::structs::{Table, TableColumn, TableStyleInfo, TotalsRowFunctionValues};
+use umya_spreadsheet::{Spreadsheet, Worksheet};
+
+const DEFAULT_TABLE_STYLE: &str = "TableStyleMedium2";
+const MAX_COLUMN: u32 = 16_384;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    min_col: u32,
+    min_row: u32,
+    max_col: u32,
+    max_row: u32,
+}
+
+impl Span {
+    fn of(table: &Table) -> Self {
+        let (start, end) = table.get_area();
+        Self {
+            min_col: *start.get_col_num(),
+            min_row: *start.get_row_num(),
+            max_col: *end.get_col_num(),
+            max_row: *end.get_row_num(),
+        }
+    }
+
+    fn parse(range: &str) -> Result<Self> {
+        let bounds = parse_range_bounds(range)?;
+        Ok(Self {
+            min_col: bounds.min_col,
+            min_row: bounds.min_row,
+            max_col: bounds.max_col,
+            max_row: bounds.max_row,
+        })
+    }
+
+    fn start(&self) -> String {
+        cell_address(self.min_col, self.min_row)
+    }
+
+    fn end(&self) -> String {
+        cell_address(self.max_col, self.max_row)
+    }
+
+    fn to_a1(&self) -> String {
+        format!("{}:{}", self.start(), self.end())
+    }
+
+    fn intersects(&self, other: &Span) -> bool {
+        self.min_col <= other.max_col
+            && other.min_col <= self.max_col
+            && self.min_row <= other.max_row
+            && other.min_row <= self.max_row
+    }
+
+    fn apply(&self, table: &mut Table) {
+        table.set_area((self.start().as_str(), self.end().as_str()));
+    }
+}
+
+/// Sheet index and table index of the table named `name`.
+fn locate(book: &Spreadsheet, name: &str) -> Result<(usize, usize)> {
+    for (sheet_index, sheet) in book.get_sheet_collection_no_check().iter().enumerate() {
+        if let Some(index) = sheet
+            .get_tables()
+            .iter()
+            .position(|t| t.get_display_name().eq_ignore_ascii_case(name))
+        {
+            return Ok((sheet_index, index));
+        }
+    }
+    bail!("table '{}' not found", name)
+}
+
+fn sheet_at(book: &mut Spreadsheet, index: usize) -> &mut Worksheet {
+    &mut book.get_sheet_collection_mut()[index]
+}
+
+fn is_blank(sheet: &Worksheet, col: u32, row: u32) -> bool {
+    sheet
+        .get_cell((col, row))
+        .is_none_or(|cell| !cell.is_formula() && cell.get_value().is_empty())
+}
+
+/// Excel's rules: letters, digits, `_`, `.` and `\`, starting with a letter,
+/// `_` or `\`, not a cell reference, unique among tables and defined names.
+fn check_table_name(book: &Spreadsheet, name: &str, current: Option<&str>) -> Result<()> {
+    let valid_start = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || matches!(c, '_' | '\\'));
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '\\'));
+    let upper = name.to_ascii_uppercase();
+    let rest = upper.strip_prefix('R').unwrap_or(&upper);
+    let rest = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+    let rest = rest.strip_prefix('C').unwrap_or(rest);
+    let r1c1 = rest
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .is_empty();
+    if !valid_start || !valid_chars || name.chars().count() > 255 || r1c1 || is_cell_ref(&upper) {
+        bail!("'{}' is not a valid table name", name);
+    }
+    if current.is_some_and(|c| c.eq_ignore_ascii_case(name)) {
+        return Ok(());
+    }
+    let sheets = book.get_sheet_collection_no_check();
+    let taken = sheets
+        .iter()
+        .flat_map(|s| s.get_tables())
+        .any(|t| t.get_display_name().eq_ignore_ascii_case(name))
+        || book
+            .get_defined_names()
+            .iter()
+            .chain(sheets.iter().flat_map(|s| s.get_defined_names()))
+            .any(|d| d.get_name().eq_ignore_ascii_case(name));
+    if taken {
+        bail!(
+            "the name '{}' is already used by a table or defined name",
+            name
+        );
+    }
+    Ok(())
+}
+
+fn is_cell_ref(upper: &str) -> bool {
+    let letters = upper
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .count();
+    let digits = &upper[letters..];
+    (1..=3).contains(&letters)
+        && !digits.is_empty()
+        && digits.chars().all(|c| c.is_ascii_digit())
+        && column_index_from_string(&upper[..letters]) <= MAX_COLUMN
+}
+
+fn next_table_name(book: &Spreadsheet) -> String {
+    (1..)
+        .map(|n| format!("Table{n}"))
+        .find(|name| check_table_name(book, name, None).is_ok())
+        .unwrap_or_default()
+}
+
+/// Name a header cell the way Excel does: blanks become `Column{n}` and
+/// duplicates get a numeric suffix. The chosen name is written back as text.
+fn claim_header(
+    sheet: &mut Worksheet,
+    col: u32,
+    row: u32,
+    position: usize,
+    taken: &mut HashSet<String>,
+    warnings: &mut Vec<String>,
+) -> String {
+    let current = sheet
+        .get_cell((col, row))
+        .map(|cell| cell.get_value().trim().to_string())
+        .unwrap_or_default();
+    let base = if current.is_empty() {
+        format!("Column{}", position + 1)
+    } else {
+        current.clone()
+    };
+    let mut name = base.clone();
+    let mut suffix = 2;
+    while !taken.insert(name.to_ascii_lowercase()) {
+        name = format!("{base}{suffix}");
+        suffix += 1;
+    }
+    if name != current {
+        let address = cell_address(col, row);
+        warnings.push(match current.is_empty() {
+            true => format!("header {address} was blank; named it '{name}'"),
+            false => format!("header {address} '{current}' is a duplicate; renamed to '{name}'"),
+        });
+    }
+    let cell = sheet.get_cell_mut((col, row));
+    if cell.is_formula() || cell.get_value() != name {
+        cell.get_cell_value_mut().remove_formula();
+        cell.set_value_string(name.clone());
+    }
+    name
+}
+
+fn check_free(sheet: &Worksheet, span: &Span, skip: Option<usize>) -> Result<()> {
+    for (index, table) in sheet.get_tables().iter().enumerate() {
+        if Some(index) != skip && Span::of(table).intersects(span) {
+            bail!(
+                "range {} overlaps table '{}'",
+                span.to_a1(),
+                table.get_display_name()
+            );
+        }
+    }
+    for merge in sheet.get_merge_cells() {
+        if let Ok(merged) = Span::parse(&merge.get_range())
+            && merged.intersects(span)
+        {
+            bail!(
+                "range {} contains merged cells ({})",
+                span.to_a1(),
+                merge.get_range()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn has_totals_row(table: &Table) -> bool {
+    *table.get_totals_row_count() > 0
+}
+
+/// Create a table over `range` and return its name.
+pub(super) fn create_table(
+    book: &mut Spreadsheet,
+    sheet_name: &str,
+    range: &str,
+    table_name: Option<&str>,
+    style: Option<&str>,
+    warnings: &mut Vec<String>,
+) -> Result<String> {
+    let span = Span::parse(range)?;
+    if span.max_row == span.min_row {
+        bail!("a table needs a header row and at least one data row");
+    }
+    let name = match table_name {
+        Some(name) => {
+            check_table_name(book, name, None)?;
+            name.to_string()
+        }
+        None => next_table_name(book),
+    };
+    let sheet = book
+        .get_sheet_by_name_mut(sheet_name)
+        .ok_or_else(|| anyhow!("sheet '{}' not found", sheet_name))?;
+    check_free(sheet, &span, None)?;
+
+    let mut table = Table::new(&name, (span.start().as_str(), span.end().as_str()));
+    let mut taken = HashSet::new();
+    for (position, col) in (span.min_col..=span.max_col).enumerate() {
+        let header = claim_header(sheet, col, span.min_row, position, &mut taken, warnings);
+        table.add_column(TableColumn::new(&header));
+    }
+    table.set_style_info(Some(TableStyleInfo::new(
+        style.unwrap_or(DEFAULT_TABLE_STYLE),
+        false,
+        false,
+        true,
+        false,
+    )));
+    sheet.add_table(table);
+    Ok(name)
+}
+
+/// Rename a table and every structured reference to it; returns its sheet.
+pub(super) fn rename_table(
+    book: &mut Spreadsheet,
+    table_name: &str,
+    new_name: &str,
+    log: &mut RewriteLog,
+) -> Result<String> {
+    let (sheet_index, index) = locate(book, table_name)?;
+    check_table_name(book, new_name, Some(table_name))?;
+    let old = book.get_sheet_collection_no_check()[sheet_index].get_tables()[index]
+        .get_display_name()
+        .to_string();
+    references::rewrite_structured_refs(
+        book,
+        TableRefEdit::Rename {
+            old: &old,
+            new: new_name,
+        },
+        log,
+    )?;
+    let sheet = sheet_at(book, sheet_index);
+    let table = &mut sheet.get_tables_mut()[index];
+    table.set_name(new_name);
+    table.set_display_name(new_name);
+    Ok(sheet.get_name().to_string())
+}
+
+/// Move a table's bounds. The header row stays put and the new range must
+/// overlap the old one; columns that fall out of the table are dropped and
+/// columns that come in are named from their header cells.
+pub(super) fn resize_table(
+    book: &mut Spreadsheet,
+    table_name: &str,
+    range: &str,
+    log: &mut RewriteLog,
+    warnings: &mut Vec<String>,
+) -> Result<String> {
+    let (sheet_index, index) = locate(book, table_name)?;
+    let new_span = Span::parse(range)?;
+    let (old_span, name, columns) = {
+        let table = &book.get_sheet_collection_no_check()[sheet_index].get_tables()[index];
+        if has_totals_row(table) {
+            bail!(
+                "hide the totals row of table '{}' before resizing it",
+                table.get_display_name()
+            );
+        }
+        (
+            Span::of(table),
+            table.get_display_name().to_string(),
+            table.get_columns().to_vec(),
+        )
+    };
+    if new_span.min_row != old_span.min_row {
+        bail!(
+            "the header row of table '{}' must stay on row {}",
+            name,
+            old_span.min_row
+        );
+    }
+    if !new_span.intersects(&old_span) {
+        bail!(
+            "the new range of table '{}' must overlap {}",
+            name,
+            old_span.to_a1()
+        );
+    }
+    if new_span.max_row == new_span.min_row {
+        bail!("a table needs a header row and at least one data row");
+    }
+    check_free(
+        &book.get_sheet_collection_no_check()[sheet_index],
+        &new_span,
+        Some(index),
+    )?;
+
+    let kept = |col: u32| (new_span.min_col..=new_span.max_col).contains(&col);
+    let dropped: Vec<String> = (old_span.min_col..=old_span.max_col)
+        .filter(|col| !kept(*col))
+        .filter_map(|col| columns.get((col - old_span.min_col) as usize))
+        .map(|c| c.get_name().to_string())
+        .collect();
+    if !dropped.is_empty() {
+        references::rewrite_structured_refs(
+            book,
+            TableRefEdit::DropColumns {
+                table: &name,
+                columns: &dropped,
+            },
+            log,
+        )?;
+    }
+
+    let sheet = sheet_at(book, sheet_index);
+    let mut taken: HashSet<String> = (new_span.min_col..=new_span.max_col)
+        .filter(|col| (old_span.min_col..=old_span.max_col).contains(col))
+        .filter_map(|col| columns.get((col - old_span.min_col) as usize))
+        .map(|c| c.get_name().to_ascii_lowercase())
+        .collect();
+    let mut resized = Vec::new();
+    for (position, col) in (new_span.min_col..=new_span.max_col).enumerate() {
+        let existing = (old_span.min_col..=old_span.max_col)
+            .contains(&col)
+            .then(|| columns.get((col - old_span.min_col) as usize))
+            .flatten();
+        match existing {
+            Some(column) => resized.push(column.clone()),
+            None => {
+                let header =
+                    claim_header(sheet, col, new_span.min_row, position, &mut taken, warnings);
+                resized.push(TableColumn::new(&header));
+            }
+        }
+    }
+    let table = &mut sheet.get_tables_mut()[index];
+    *table.get_columns_mut() = resized;
+    new_span.apply(table);
+    log.record(
+        RewriteKind::Table,
+        Some(sheet.get_name()),
+        Some(&name),
+        &old_span.to_a1(),
+        Some(&new_span.to_a1()),
+    );
+    Ok(sheet.get_name().to_string())
+}
+
+/// Move the cells of rows `rows` in columns `first..=last` one column left
+/// (`delta` -1) or right (`delta` 1).
+fn shift_cells(sheet: &mut Worksheet, rows: (u32, u32), first: u32, last: u32, delta: i32) {
+    let cols: Vec<u32> = if delta > 0 {
+        (first..=last).rev().collect()
+    } else {
+        (first..=last).collect()
+    };
+    for row in rows.0..=rows.1 {
+        for &col in &cols {
+            let dest = (col as i64 + delta as i64) as u32;
+            let Some(src) = sheet.get_cell((col, row)) else {
+                sheet.remove_cell((dest, row));
+                continue;
+            };
+            let formula = src.is_formula().then(|| src.get_formula().to_string());
+            let value = src.get_value().to_string();
+            let style = src.get_style().clone();
+            sheet.remove_cell((dest, row));
+            let cell = sheet.get_cell_mut((dest, row));
+            cell.set_style(style);
+            match formula {
+                Some(formula) => {
+                    cell.set_formula(formula);
+                    cell.set_formula_result_default("");
+                }
+                None => {
+                    cell.set_value(value);
+                }
+            }
+            sheet.remove_cell((col, row));
+        }
+    }
+}
+
+fn block(first: u32, last: u32, rows: (u32, u32)) -> String {
+    format!(
+        "{}:{}",
+        cell_address(first, rows.0),
+        cell_address(last, rows.1)
+    )
+}
+
+/// Insert a column at `position` (0-based, default last), shifting the table
+/// cells to its right. The column just right of the table must be empty.
+pub(super) fn add_table_column(
+    book: &mut Spreadsheet,
+    table_name: &str,
+    column_name: &str,
+    position: Option<u32>,
+    log: &mut RewriteLog,
+) -> Result<String> {
+    let (sheet_index, index) = locate(book, table_name)?;
+    let sheet = &book.get_sheet_collection_no_check()[sheet_index];
+    let table = &sheet.get_tables()[index];
+    let (span, name) = (Span::of(table), table.get_display_name().to_string());
+    let width = table.get_columns().len() as u32;
+    let position = position.unwrap_or(width);
+    if position > width {
+        bail!("table '{}' has only {} columns", name, width);
+    }
+    let column_name = column_name.trim();
+    if column_name.is_empty() {
+        bail!("column_name must not be empty");
+    }
+    if table
+        .get_columns()
+        .iter()
+        .any(|c| c.get_name().eq_ignore_ascii_case(column_name))
+    {
+        bail!("table '{}' already has a column '{}'", name, column_name);
+    }
+    let outside = span.max_col + 1;
+    if let Some(row) = (span.min_row..=span.max_row).find(|row| !is_blank(sheet, outside, *row)) {
+        bail!(
+            "cell {} next to table '{}' is not empty",
+            cell_address(outside, row),
+            name
+        );
+    }
+    let grown = Span {
+        max_col: outside,
+        ..span
+    };
+    check_free(sheet, &grown, Some(index))?;
+
+    let rows = (span.min_row, span.max_row);
+    let at = span.min_col + position;
+    let sheet_name = sheet.get_name().to_string();
+    if at <= span.max_col {
+        shift_cells(sheet_at(book, sheet_index), rows, at, span.max_col, 1);
+        references::repoint_moved_range(
+            book,
+            &sheet_name,
+            &sheet_name,
+            &block(at, span.max_col, rows),
+            &cell_address(at + 1, span.min_row),
+            log,
+        )?;
+    }
+
+    let sheet = sheet_at(book, sheet_index);
+    sheet
+        .get_cell_mut((at, span.min_row))
+        .set_value_string(column_name);
+    let table = &mut sheet.get_tables_mut()[index];
+    table
+        .get_columns_mut()
+        .insert(position as usize, TableColumn::new(column_name));
+    grown.apply(table);
+    log.record(
+        RewriteKind::Table,
+        Some(&sheet_name),
+        Some(&name),
+        &span.to_a1(),
+        Some(&grown.to_a1()),
+    );
+    Ok(sheet_name)
+}
+
+/// Delete a column and its cells, shifting the table cells to its right.
+/// Structured and A1 references to the deleted cells become `#REF!`.
+pub(super) fn remove_table_column(
+    book: &mut Spreadsheet,
+    table_name: &str,
+    column_name: &str,
+    log: &mut RewriteLog,
+) -> Result<String> {
+    let (sheet_index, index) = locate(book, table_name)?;
+    let sheet = &book.get_sheet_collection_no_check()[sheet_index];
+    let table = &sheet.get_tables()[index];
+    let (span, name) = (Span::of(table), table.get_display_name().to_string());
+    let position = table
+        .get_columns()
+        .iter()
+        .position(|c| c.get_name().eq_ignore_ascii_case(column_name.trim()))
+        .ok_or_else(|| anyhow!("table '{}' has no column '{}'", name, column_name))?;
+    if table.get_columns().len() == 1 {
+        bail!("cannot remove the only column of table '{}'", name);
+    }
+    let column = table.get_columns()[position].get_name().to_string();
+    let sheet_name = sheet.get_name().to_string();
+
+    references::rewrite_structured_refs(
+        book,
+        TableRefEdit::DropColumns {
+            table: &name,
+            columns: std::slice::from_ref(&column),
+        },
+        log,
+    )?;
+    let rows = (span.min_row, span.max_row);
+    let at = span.min_col + position as u32;
+    references::invalidate_range(book, &sheet_name, &block(at, at, rows), log)?;
+
+    let sheet = sheet_at(book, sheet_index);
+    for row in span.min_row..=span.max_row {
+        sheet.remove_cell((at, row));
+    }
+    if at < span.max_col {
+        shift_cells(sheet, rows, at + 1, span.max_col, -1);
+        references::repoint_moved_range(
+            book,
+            &sheet_name,
+            &sheet_name,
+            &block(at + 1, span.max_col, rows),
+            &cell_address(at, span.min_row),
+            log,
+        )?;
+    }
+
+    let shrunk = Span {
+        max_col: span.max_col - 1,
+        ..span
+    };
+    let table = &mut sheet_at(book, sheet_index).get_tables_mut()[index];
+    table.get_columns_mut().remove(position);
+    shrunk.apply(table);
+    log.record(
+        RewriteKind::Table,
+        Some(&sheet_name),
+        Some(&name),
+        &span.to_a1(),
+        Some(&shrunk.to_a1()),
+    );
+    Ok(sheet_name)
+}
+
+/// Totals-row function and its SUBTOTAL code (which skips filtered rows).
+fn totals_function(name: &str) -> Result<(TotalsRowFunctionValues, Option<u32>)> {
+    Ok(match name.to_ascii_lowercase().as_str() {
+        "sum" => (TotalsRowFunctionValues::Sum, Some(109)),
+        "average" => (TotalsRowFunctionValues::Average, Some(101)),
+        "count" => (TotalsRowFunctionValues::Count, Some(103)),
+        "count_nums" => (TotalsRowFunctionValues::CountNumbers, Some(102)),
+        "max" => (TotalsRowFunctionValues::Max, Some(104)),
+        "min" => (TotalsRowFunctionValues::Min, Some(105)),
+        "std_dev" => (TotalsRowFunctionValues::StandardDeviation, Some(107)),
+        "var" => (TotalsRowFunctionValues::Variance, Some(110)),
+        "none" => (TotalsRowFunctionValues::None, None),
+        other => bail!(
+            "unknown totals function '{}' (expected sum, average, count, count_nums, max, min, std_dev, var or none)",
+            other
+        ),
+    })
+}
+
+/// Show or hide the totals row and set per-column functions. Showing it
+/// takes the row below the table, which must be empty.
+pub(super) fn set_table_totals(
+    book: &mut Spreadsheet,
+    table_name: &str,
+    show: bool,
+    functions: &BTreeMap<String, String>,
+    label: Option<&str>,
+    log: &mut RewriteLog,
+) -> Result<String> {
+    let (sheet_index, index) = locate(book, table_name)?;
+    let sheet = sheet_at(book, sheet_index);
+    let sheet_name = sheet.get_name().to_string();
+    let table = &sheet.get_tables()[index];
+    let (span, name) = (Span::of(table), table.get_display_name().to_string());
+    let shown = has_totals_row(table);
+    if !show && (!functions.is_empty() || label.is_some()) {
+        bail!("functions and label need show=true");
+    }
+    let columns: Vec<String> = table
+        .get_columns()
+        .iter()
+        .map(|c| c.get_name().to_string())
+        .collect();
+    let mut chosen = Vec::with_capacity(functions.len());
+    for (column, function) in functions {
+        let position = columns
+            .iter()
+            .position(|c| c.eq_ignore_ascii_case(column))
+            .ok_or_else(|| anyhow!("table '{}' has no column '{}'", name, column))?;
+        chosen.push((position, totals_function(function)?));
+    }
+
+    let new_span = match (show, shown) {
+        (true, false) => {
+            let below = span.max_row + 1;
+            if let Some(col) =
+                (span.min_col..=span.max_col).find(|col| !is_blank(sheet, *col, below))
+            {
+                bail!(
+                    "cell {} below table '{}' is not empty",
+                    cell_address(col, below),
+                    name
+                );
+            }
+            Span {
+                max_row: below,
+                ..span
+            }
+        }
+        (false, true) => {
+            for col in span.min_col..=span.max_col {
+                sheet.remove_cell((col, span.max_row));
+            }
+            Span {
+                max_row: span.max_row - 1,
+                ..span
+            }
+        }
+        _ => span,
+    };
+    let totals_row = new_span.max_row;
+
+    let first_has_function = chosen.iter().any(|(position, _)| *position == 0);
+    if label.is_some() && first_has_function {
+        bail!("the first column takes either a totals label or a function, not both");
+    }
+
+    if show {
+        let label = label.or((!shown && !first_has_function).then_some("Total"));
+        let table = &mut sheet.get_tables_mut()[index];
+        if let Some(label) = label {
+            let first = &mut table.get_columns_mut()[0];
+            first.set_totals_row_label(label);
+            first.set_totals_row_function(TotalsRowFunctionValues::None);
+        }
+        for (position, (function, _)) in &chosen {
+            table.get_columns_mut()[*position].set_totals_row_function(function.clone());
+        }
+        if let Some(label) = label {
+            sheet
+                .get_cell_mut((new_span.min_col, totals_row))
+                .set_value_string(label);
+        }
+        for (position, (_, code)) in chosen {
+            let cell = sheet.get_cell_mut((new_span.min_col + position as u32, totals_row));
+            cell.get_cell_value_mut().remove_formula();
+            match code {
+                Some(code) => {
+                    cell.set_formula(format!(
+                        "SUBTOTAL({code},{name}[{}])",
+                        escape_column(&columns[position])
+                    ));
+                    cell.set_formula_result_default("");
+                }
+                None => {
+                    cell.set_value_string("");
+                }
+            }
+        }
+    }
+
+    let table = &mut sheet.get_tables_mut()[index];
+    table.set_totals_row_count(u32::from(show));
+    if show {
+        table.set_totals_row_shown(true);
+    }
+    if new_span != span {
+        new_span.apply(table);
+        log.record(
+            RewriteKind::Table,
+            Some(&sheet_name),
+            Some(&name),
+            &span.to_a1(),
+            Some(&new_span.to_a1()),
+        );
+    }
+    Ok(sheet_name)
+}
+
+/// Requested table style and banding options; `None` keeps the current one.
+pub(super) struct TableStyleChange<'a> {
+    pub style: Option<&'a str>,
+    pub show_row_stripes: Option<bool>,
+    pub show_column_stripes: Option<bool>,
+    pub show_first_column: Option<bool>,
+    pub show_last_column: Option<bool>,
+}
+
+/// Change the table style; style "none" removes it.
+pub(super) fn set_table_style(
+    book: &mut Spreadsheet,
+    table_name: &str,
+    change: TableStyleChange<'_>,
+) -> Result<String> {
+    let (sheet_index, index) = locate(book, table_name)?;
+    let sheet = sheet_at(book, sheet_index);
+    let table = &mut sheet.get_tables_mut()[index];
+    if change
+        .style
+        .is_some_and(|style| style.eq_ignore_ascii_case("none"))
+    {
+        table.set_style_info(None);
+        return Ok(sheet.get_name().to_string());
+    }
+    let current = table.get_style_info();
+    let style = change
+        .style
+        .map(str::to_string)
+        .or_else(|| current.map(|info| info.get_name().to_string()))
+        .unwrap_or_else(|| DEFAULT_TABLE_STYLE.to_string());
+    let info = TableStyleInfo::new(
+        &style,
+        change
+            .show_first_column
+            .unwrap_or_else(|| current.is_some_and(|i| i.is_show_first_col())),
+        change
+            .show_last_column
+            .unwrap_or_else(|| current.is_some_and(|i| i.is_show_last_col())),
+        change
+            .show_row_stripes
+            .unwrap_or_else(|| current.is_none_or(|i| i.is_show_row_stripes())),
+        change
+            .show_column_stripes
+            .unwrap_or_else(|| current.is_some_and(|i| i.is_show_col_stripes())),
+    );
+    table.set_style_info(Some(info));
+    Ok(sheet.get_name().to_string())
+}
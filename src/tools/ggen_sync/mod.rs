@@ -34,6 +34,7 @@ use self::report::SyncMode;
 
 use crate::audit::integration::audit_tool;
 use crate::codegen::validation::compute_string_hash;
+use crate::progress::ProgressReporter;
 use crate::state::AppState;
 use crate::template::{RenderConfig, SafeRenderer};
 use crate::validation::validate_path_safe;
@@ -58,6 +59,7 @@ const TEMPLATES_DIR: &str = "templates";
 const ONTOLOGY_DIR: &str = "ontology";
 const CACHE_DIR: &str = ".ggen/cache";
 const MAX_CACHE_AGE_SECS: u64 = 3600;
+const TOTAL_STAGES: usize = 15;
 
 // ============================================================================
 // Public API
@@ -67,14 +69,25 @@ const MAX_CACHE_AGE_SECS: u64 = 3600;
 ///
 /// Consolidates entire ontology-driven code generation pipeline into
 /// one atomic transaction with automatic rollback on failure.
-pub async fn sync_ggen(_state: Arc<AppState>, params: SyncGgenParams) -> Result<SyncGgenResponse> {
+pub async fn sync_ggen(state: Arc<AppState>, params: SyncGgenParams) -> Result<SyncGgenResponse> {
+    sync_ggen_with_progress(state, params, ProgressReporter::none()).await
+}
+
+/// [`sync_ggen`] reporting progress after each stage. Cancellation is honoured
+/// until the atomic write; nothing has been written at that point, so a
+/// cancelled sync leaves the workspace untouched.
+pub async fn sync_ggen_with_progress(
+    _state: Arc<AppState>,
+    params: SyncGgenParams,
+    progress: ProgressReporter,
+) -> Result<SyncGgenResponse> {
     let _span = audit_tool("sync_ggen", &params);
 
     // Validate workspace root
     validate_path_safe(&params.workspace_root)?;
 
     // Execute 13-stage pipeline
-    let executor = PipelineExecutor::new(params);
+    let executor = PipelineExecutor::new(params).with_progress(progress);
     executor.execute().await
 }
 
@@ -624,11 +637,34 @@ impl QueryCache {
 
 pub struct PipelineExecutor {
     params: SyncGgenParams,
+    progress: ProgressReporter,
 }
 
 impl PipelineExecutor {
     fn new(params: SyncGgenParams) -> Self {
-        Self { params }
+        Self {
+            params,
+            progress: ProgressReporter::none(),
+        }
+    }
+
+    fn with_progress(mut self, progress: ProgressReporter) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    fn report_stage(&self, stage: &StageResult) {
+        self.progress.report(
+            stage.stage_number as usize,
+            TOTAL_STAGES,
+            stage.stage_name.as_str(),
+        );
+    }
+
+    /// Report a completed stage and stop if the client cancelled.
+    fn advance(&self, stage: &StageResult) -> Result<()> {
+        self.report_stage(stage);
+        self.progress.check_cancelled()
     }
 
     /// Execute 13-stage pipeline
@@ -642,6 +678,7 @@ impl PipelineExecutor {
 
         // Stage 1: Load ggen.toml (skipped if not present)
         let stage1 = self.stage_load_config(workspace);
+        self.advance(&stage1)?;
         stages.push(stage1);
 
         // Stage 2: Discover ontology files
@@ -666,6 +703,7 @@ impl PipelineExecutor {
                 ));
             }
         };
+        self.advance(&stage2)?;
         stages.push(stage2);
 
         // Stage 3: Load RDF stores
@@ -687,6 +725,7 @@ impl PipelineExecutor {
                 ));
             }
         };
+        self.advance(&stage3)?;
         stages.push(stage3);
 
         // Stage 4: Discover SPARQL queries (already done in stage 2)
@@ -697,6 +736,7 @@ impl PipelineExecutor {
             duration_ms: 0,
             details: format!("Found {} SPARQL queries", resources.queries.len()),
         };
+        self.advance(&stage4)?;
         stages.push(stage4);
 
         // Stage 5: Execute queries (with caching)
@@ -721,6 +761,7 @@ impl PipelineExecutor {
                 ));
             }
         };
+        self.advance(&stage5)?;
         stages.push(stage5);
 
         // Stage 6: Discover templates (already done in stage 2)
@@ -731,6 +772,7 @@ impl PipelineExecutor {
             duration_ms: 0,
             details: format!("Found {} Tera templates", resources.templates.len()),
         };
+        self.advance(&stage6)?;
         stages.push(stage6);
 
         // Stage 7: Render templates
@@ -753,14 +795,19 @@ impl PipelineExecutor {
                 ));
             }
         };
+        self.advance(&stage7)?;
         stages.push(stage7);
 
         // Stage 8: Validate syntax
         let stage8 = self.stage_validate_syntax(&rendered_files);
+        self.advance(&stage8)?;
         stages.push(stage8);
 
         // Stage 9: Format code (best effort, don't fail)
         let (formatted_files, stage9) = self.stage_format_code(rendered_files);
+        // Last chance to cancel: from here on files are written and the
+        // pipeline completes so they always get a receipt.
+        self.advance(&stage9)?;
         stages.push(stage9);
 
         // Stage 10: Atomic write
@@ -792,6 +839,7 @@ impl PipelineExecutor {
                 details: "Skipped in preview mode".to_string(),
             }
         };
+        self.report_stage(&stage10);
         stages.push(stage10);
 
         // Stage 11: Generate audit receipts
@@ -802,10 +850,12 @@ impl PipelineExecutor {
             &formatted_files,
             total_duration_so_far,
         );
+        self.report_stage(&stage11);
         stages.push(stage11);
 
         // Stage 12: Verify determinism (hash check)
         let stage12 = self.stage_verify_determinism(&formatted_files);
+        self.report_stage(&stage12);
         stages.push(stage12);
 
         // Stage 13: Collect statistics
@@ -822,12 +872,14 @@ impl PipelineExecutor {
                 cache_misses
             ),
         };
+        self.report_stage(&stage13);
         stages.push(stage13);
 
         // Stage 14: Jira Integration (optional)
         let jira_result = self
             .stage_jira_integration(workspace, &formatted_files)
             .await;
+        self.progress.report(14, TOTAL_STAGES, "Jira Integration");
 
         // Build response
         let files_generated: Vec<GeneratedFileInfo> = formatted_files
@@ -877,6 +929,7 @@ impl PipelineExecutor {
             &audit_receipt,
         );
         if let Some(stage) = stage15 {
+            self.report_stage(&stage);
             stages.push(stage);
         }
 
@@ -0,0 +1,960 @@
+//! `merge_forks`: three-way merge of two forks created from the same base.
+//!
+//! Both forks are diffed against the shared base with `calculate_changeset`.
+//! Cells, defined names and tables changed only in fork B are copied into
+//! fork A; items both forks changed to different results are conflicts,
+//! resolved by the requested policy or staged on fork A so they can be
+//! reviewed and applied with `apply_staged_change`.
+
+use crate::diff::merge::{CellDiff, values_equal};
+use crate::diff::names::NameDiff;
+use crate::diff::sheets::SheetDiff;
+use crate::diff::tables::TableDiff;
+use crate::diff::{Change, calculate_changeset};
+use crate::fork::{ChangeSummary, EditOp, StagedChange, StagedOp};
+use crate::model::{StyleDescriptor, WorkbookId};
+use crate::state::AppState;
+use crate::styles::{descriptor_from_style, stable_style_id, style_from_descriptor};
+use crate::utils::make_short_random_id;
+use anyhow::{Result, anyhow, bail};
+use chrono::Utc;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use umya_spreadsheet::structs::{Table, TableColumn, TableStyleInfo};
+use umya_spreadsheet::{Spreadsheet, Style};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MergePolicy {
+    /// Keep fork A's side of every conflict.
+    PreferA,
+    /// Take fork B's side of every conflict.
+    PreferB,
+    /// Stage fork B's side of the conflicts on fork A for manual review.
+    #[default]
+    Stage,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MergeForksParams {
+    /// Fork that receives the merged changes.
+    pub fork_a: String,
+    /// Fork whose changes are merged into `fork_a`; it is left unchanged.
+    pub fork_b: String,
+    #[serde(default)]
+    pub policy: MergePolicy,
+    /// Only merge changes on this sheet.
+    #[serde(default)]
+    pub sheet_name: Option<String>,
+    /// Label for the staged change created by `policy = "stage"`.
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    CellContent,
+    CellStyle,
+    Name,
+    Table,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    KeptA,
+    TookB,
+    Staged,
+}
+
+/// One side of a conflict. `deleted` means the cell is empty or the name or
+/// table is absent.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct ConflictSide {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formula: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<StyleDescriptor>,
+    /// Table range, for table conflicts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<String>,
+    pub deleted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct MergeConflict {
+    pub kind: ConflictKind,
+    /// Cell or table sheet, or the scope sheet of a sheet-local name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sheet: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// Defined name or table display name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub base: ConflictSide,
+    pub fork_a: ConflictSide,
+    pub fork_b: ConflictSide,
+    pub resolution: ConflictResolution,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MergeForksResponse {
+    pub fork_a: String,
+    pub fork_b: String,
+    pub policy: MergePolicy,
+    /// Staged change on `fork_a` holding fork B's side of the conflicts.
+    pub change_id: Option<String>,
+    pub conflicts: Vec<MergeConflict>,
+    pub summary: ChangeSummary,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum CellContent {
+    Value { value: String },
+    Formula { formula: String },
+    Cleared,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CellWrite {
+    sheet: String,
+    address: String,
+    #[serde(default)]
+    content: Option<CellContent>,
+    #[serde(default)]
+    style: Option<StyleDescriptor>,
+}
+
+impl CellWrite {
+    fn edit_op(&self) -> Option<EditOp> {
+        let (value, is_formula) = match self.content.as_ref()? {
+            CellContent::Value { value } => (value.clone(), false),
+            CellContent::Formula { formula } => (formula.clone(), true),
+            CellContent::Cleared => (String::new(), false),
+        };
+        Some(EditOp {
+            timestamp: Utc::now(),
+            sheet: self.sheet.clone(),
+            address: self.address.clone(),
+            value,
+            is_formula,
+        })
+    }
+}
+
+/// A defined name to set (`formula`) or remove (`None`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct NameWrite {
+    name: String,
+    scope_sheet: Option<String>,
+    formula: Option<String>,
+}
+
+/// Writes applied to fork A; also the payload of a staged `merge_forks` op.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct MergeStagedPayload {
+    #[serde(default)]
+    sheets: Vec<String>,
+    #[serde(default)]
+    cells: Vec<CellWrite>,
+    #[serde(default)]
+    names: Vec<NameWrite>,
+    #[serde(default)]
+    tables: Vec<TableWrite>,
+}
+
+impl MergeStagedPayload {
+    fn is_empty(&self) -> bool {
+        self.sheets.is_empty()
+            && self.cells.is_empty()
+            && self.names.is_empty()
+            && self.tables.is_empty()
+    }
+
+    fn affected_sheets(&self) -> Vec<String> {
+        let sheets: BTreeSet<String> = self
+            .sheets
+            .iter()
+            .cloned()
+            .chain(self.cells.iter().map(|w| w.sheet.clone()))
+            .chain(self.tables.iter().map(|w| w.sheet.clone()))
+            .collect();
+        sheets.into_iter().collect()
+    }
+}
+
+/// A table's definition as fork B has it, enough to rebuild the table when
+/// a staged merge is applied after fork B has moved on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct TableDefinition {
+    range: String,
+    columns: Vec<String>,
+    #[serde(default)]
+    style: Option<String>,
+    #[serde(default)]
+    show_first_column: bool,
+    #[serde(default)]
+    show_last_column: bool,
+    #[serde(default)]
+    show_row_stripes: bool,
+    #[serde(default)]
+    show_column_stripes: bool,
+    #[serde(default)]
+    totals_row_count: u32,
+}
+
+impl TableDefinition {
+    fn of(table: &Table) -> Self {
+        let (start, end) = table.get_area();
+        let style = table.get_style_info();
+        Self {
+            range: format!(
+                "{}:{}",
+                crate::utils::cell_address(*start.get_col_num(), *start.get_row_num()),
+                crate::utils::cell_address(*end.get_col_num(), *end.get_row_num())
+            ),
+            columns: table
+                .get_columns()
+                .iter()
+                .map(|c| c.get_name().to_string())
+                .collect(),
+            style: style.map(|info| info.get_name().to_string()),
+            show_first_column: style.is_some_and(|info| info.is_show_first_col()),
+            show_last_column: style.is_some_and(|info| info.is_show_last_col()),
+            show_row_stripes: style.is_some_and(|info| info.is_show_row_stripes()),
+            show_column_stripes: style.is_some_and(|info| info.is_show_col_stripes()),
+            totals_row_count: *table.get_totals_row_count(),
+        }
+    }
+
+    fn build(&self, display_name: &str) -> Table {
+        let (start, end) = self
+            .range
+            .split_once(':')
+            .unwrap_or((&self.range, &self.range));
+        let mut table = Table::new(display_name, (start, end));
+        for column in &self.columns {
+            table.add_column(TableColumn::new(column));
+        }
+        if let Some(style) = &self.style {
+            table.set_style_info(Some(TableStyleInfo::new(
+                style,
+                self.show_first_column,
+                self.show_last_column,
+                self.show_row_stripes,
+                self.show_column_stripes,
+            )));
+        }
+        if self.totals_row_count > 0 {
+            table.set_totals_row_count(self.totals_row_count);
+            table.set_totals_row_shown(true);
+        }
+        table
+    }
+}
+
+/// A table (by display name) to set to fork B's `definition`, or remove (`None`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TableWrite {
+    sheet: String,
+    display_name: String,
+    #[serde(default)]
+    definition: Option<TableDefinition>,
+}
+
+enum ConflictWrite {
+    Cell(CellWrite),
+    Name(NameWrite),
+    Table(TableWrite),
+}
+
+impl ConflictWrite {
+    fn push_into(self, payload: &mut MergeStagedPayload) {
+        match self {
+            ConflictWrite::Cell(write) => payload.cells.push(write),
+            ConflictWrite::Name(write) => payload.names.push(write),
+            ConflictWrite::Table(write) => payload.tables.push(write),
+        }
+    }
+}
+
+struct PlannedConflict {
+    conflict: MergeConflict,
+    take_b: ConflictWrite,
+}
+
+#[derive(Default)]
+struct MergePlan {
+    auto: MergeStagedPayload,
+    conflicts: Vec<PlannedConflict>,
+    identical: u64,
+    warnings: Vec<String>,
+}
+
+pub async fn merge_forks(
+    state: Arc<AppState>,
+    params: MergeForksParams,
+) -> Result<MergeForksResponse> {
+    let registry = state
+        .fork_registry()
+        .ok_or_else(|| anyhow!("fork registry not available"))?;
+
+    if params.fork_a == params.fork_b {
+        bail!("fork_a and fork_b must be different forks");
+    }
+    let fork_a = registry.get_fork(&params.fork_a)?;
+    let fork_b = registry.get_fork(&params.fork_b)?;
+    if fork_a.base_path != fork_b.base_path || fork_a.base_hash() != fork_b.base_hash() {
+        bail!(
+            "forks {} and {} were not created from the same base workbook",
+            params.fork_a,
+            params.fork_b
+        );
+    }
+    fork_a.validate_base_unchanged()?;
+
+    let plan = tokio::task::spawn_blocking({
+        let base_path = fork_a.diff_base_path().to_path_buf();
+        let a_path = fork_a.work_path.clone();
+        let b_path = fork_b.work_path.clone();
+        let sheet_name = params.sheet_name.clone();
+        move || plan_merge(&base_path, &a_path, &b_path, sheet_name.as_deref())
+    })
+    .await??;
+
+    let MergePlan {
+        mut auto,
+        conflicts,
+        identical,
+        warnings,
+    } = plan;
+
+    let mut staged = MergeStagedPayload::default();
+    let mut reported = Vec::with_capacity(conflicts.len());
+    for PlannedConflict {
+        mut conflict,
+        take_b,
+    } in conflicts
+    {
+        conflict.resolution = match params.policy {
+            MergePolicy::PreferA => ConflictResolution::KeptA,
+            MergePolicy::PreferB => {
+                take_b.push_into(&mut auto);
+                ConflictResolution::TookB
+            }
+            MergePolicy::Stage => {
+                take_b.push_into(&mut staged);
+                ConflictResolution::Staged
+            }
+        };
+        reported.push(conflict);
+    }
+
+    let mut summary = ChangeSummary {
+        op_kinds: vec!["merge_forks".to_string()],
+        affected_sheets: auto.affected_sheets(),
+        warnings,
+        ..Default::default()
+    };
+    summary
+        .counts
+        .insert("cells_merged".to_string(), auto.cells.len() as u64);
+    summary
+        .counts
+        .insert("cells_identical".to_string(), identical);
+    summary
+        .counts
+        .insert("names_merged".to_string(), auto.names.len() as u64);
+    summary
+        .counts
+        .insert("tables_merged".to_string(), auto.tables.len() as u64);
+    summary
+        .counts
+        .insert("sheets_added".to_string(), auto.sheets.len() as u64);
+    summary
+        .counts
+        .insert("conflicts".to_string(), reported.len() as u64);
+
+    if !auto.is_empty() {
+        let edits: Vec<EditOp> = auto.cells.iter().filter_map(CellWrite::edit_op).collect();
+        tokio::task::spawn_blocking({
+            let work_path = fork_a.work_path.clone();
+            let source_path = fork_b.work_path.clone();
+            let auto = auto.clone();
+            move || apply_merge_to_file(&work_path, &auto, Some(source_path.as_path()))
+        })
+        .await??;
+
+        registry.with_fork_mut(&params.fork_a, |ctx| {
+            ctx.edits.extend(edits);
+            Ok(())
+        })?;
+        let _ = state.close_workbook(&WorkbookId(params.fork_a.clone()));
+    }
+
+    let change_id = if params.policy == MergePolicy::Stage && !staged.is_empty() {
+        let change_id = make_short_random_id("chg", 12);
+        let snapshot_path = registry.staged_snapshot_path(&params.fork_a, &change_id)?;
+        fs::copy(&fork_a.work_path, &snapshot_path)?;
+
+        tokio::task::spawn_blocking({
+            let snapshot_path = snapshot_path.clone();
+            let staged = staged.clone();
+            move || apply_merge_to_file(&snapshot_path, &staged, None)
+        })
+        .await??;
+
+        let mut staged_summary = ChangeSummary {
+            op_kinds: vec!["merge_forks".to_string()],
+            affected_sheets: staged.affected_sheets(),
+            ..Default::default()
+        };
+        staged_summary
+            .counts
+            .insert("cells".to_string(), staged.cells.len() as u64);
+        staged_summary
+            .counts
+            .insert("names".to_string(), staged.names.len() as u64);
+        staged_summary
+            .counts
+            .insert("tables".to_string(), staged.tables.len() as u64);
+
+        registry.add_staged_change(
+            &params.fork_a,
+            StagedChange {
+                change_id: change_id.clone(),
+                created_at: Utc::now(),
+                label: params
+                    .label
+                    .clone()
+                    .or_else(|| Some(format!("merge conflicts from {}", params.fork_b))),
+                ops: vec![StagedOp {
+                    kind: "merge_forks".to_string(),
+                    payload: serde_json::to_value(&staged)?,
+                }],
+                summary: staged_summary,
+                fork_path_snapshot: Some(snapshot_path),
+            },
+        )?;
+        Some(change_id)
+    } else {
+        None
+    };
+
+    Ok(MergeForksResponse {
+        fork_a: params.fork_a,
+        fork_b: params.fork_b,
+        policy: params.policy,
+        change_id,
+        conflicts: reported,
+        summary,
+    })
+}
+
+/// Apply a staged `merge_forks` op to a fork's work file.
+pub(crate) fn apply_staged_merge(path: &Path, payload: serde_json::Value) -> Result<()> {
+    let payload: MergeStagedPayload = serde_json::from_value(payload)
+        .map_err(|e| anyhow!("invalid merge_forks payload: {}", e))?;
+    apply_merge_to_file(path, &payload, None)
+}
+
+fn read_book(path: &Path) -> Result<Spreadsheet> {
+    umya_spreadsheet::reader::xlsx::read(path)
+        .map_err(|e| anyhow!("failed to read {}: {}", path.display(), e))
+}
+
+struct CellSnapshot {
+    value: Option<String>,
+    formula: Option<String>,
+    style: StyleDescriptor,
+    style_id: String,
+}
+
+impl CellSnapshot {
+    fn read(book: &Spreadsheet, sheet: &str, address: &str) -> Self {
+        let cell = book
+            .get_sheet_by_name(sheet)
+            .and_then(|s| s.get_cell(address));
+        let style = match cell {
+            Some(cell) => descriptor_from_style(cell.get_style()),
+            None => descriptor_from_style(&Style::default()),
+        };
+        Self {
+            value: cell
+                .map(|c| c.get_value().to_string())
+                .filter(|v| !v.is_empty()),
+            formula: cell
+                .filter(|c| c.is_formula())
+                .map(|c| c.get_formula().to_string())
+                .filter(|f| !f.is_empty()),
+            style_id: stable_style_id(&style),
+            style,
+        }
+    }
+
+    /// Formula cells are compared by formula, so recalculated results are not edits.
+    fn content(&self) -> CellContent {
+        match (&self.formula, &self.value) {
+            (Some(formula), _) => CellContent::Formula {
+                formula: formula.clone(),
+            },
+            (None, Some(value)) => CellContent::Value {
+                value: value.clone(),
+            },
+            (None, None) => CellContent::Cleared,
+        }
+    }
+
+    fn content_side(&self) -> ConflictSide {
+        ConflictSide {
+            value: self.value.clone(),
+            formula: self.formula.clone(),
+            deleted: self.value.is_none() && self.formula.is_none(),
+            ..Default::default()
+        }
+    }
+
+    fn style_side(&self) -> ConflictSide {
+        ConflictSide {
+            style: Some(self.style.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+fn same_content(a: &CellContent, b: &CellContent) -> bool {
+    match (a, b) {
+        (CellContent::Value { value: x }, CellContent::Value { value: y }) => {
+            values_equal(&Some(x.clone()), &Some(y.clone()))
+        }
+        _ => a == b,
+    }
+}
+
+fn find_table<'a>(book: &'a Spreadsheet, sheet: &str, display_name: &str) -> Option<&'a Table> {
+    book.get_sheet_by_name(sheet)?
+        .get_tables()
+        .iter()
+        .find(|t| t.get_display_name().eq_ignore_ascii_case(display_name))
+}
+
+fn table_side(definition: Option<&TableDefinition>) -> ConflictSide {
+    ConflictSide {
+        range: definition.map(|d| d.range.clone()),
+        deleted: definition.is_none(),
+        ..Default::default()
+    }
+}
+
+fn name_side(formula: &Option<String>) -> ConflictSide {
+    ConflictSide {
+        formula: formula.clone(),
+        deleted: formula.is_none(),
+        ..Default::default()
+    }
+}
+
+/// Defined-name changes keyed by (lowercased name, scope sheet):
+/// (display name, base formula if known, resulting formula or `None` when deleted).
+type NameChanges = HashMap<(String, Option<String>), (String, Option<String>, Option<String>)>;
+
+fn name_changes(changes: &[Change]) -> NameChanges {
+    changes
+        .iter()
+        .filter_map(|change| match change {
+            Change::Name(NameDiff::NameAdded {
+                name,
+                formula,
+                scope_sheet,
+            }) => Some((name, scope_sheet, None, Some(formula.clone()))),
+            Change::Name(NameDiff::NameDeleted { name, scope_sheet }) => {
+                Some((name, scope_sheet, None, None))
+            }
+            Change::Name(NameDiff::NameModified {
+                name,
+                scope_sheet,
+                old_formula,
+                new_formula,
+            }) => Some((
+                name,
+                scope_sheet,
+                Some(old_formula.clone()),
+                Some(new_formula.clone()),
+            )),
+            _ => None,
+        })
+        .map(|(name, scope, old, new)| {
+            (
+                (name.to_ascii_lowercase(), scope.clone()),
+                (name.clone(), old, new),
+            )
+        })
+        .collect()
+}
+
+fn table_key(diff: &TableDiff) -> (&str, &str) {
+    match diff {
+        TableDiff::TableAdded {
+            display_name,
+            sheet,
+            ..
+        }
+        | TableDiff::TableDeleted {
+            display_name,
+            sheet,
+        }
+        | TableDiff::TableModified {
+            display_name,
+            sheet,
+            ..
+        } => (sheet, display_name),
+    }
+}
+
+fn plan_merge(
+    base_path: &Path,
+    a_path: &Path,
+    b_path: &Path,
+    sheet_filter: Option<&str>,
+) -> Result<MergePlan> {
+    let changes_a = calculate_changeset(base_path, a_path, sheet_filter)?;
+    let changes_b = calculate_changeset(base_path, b_path, sheet_filter)?;
+    let base = read_book(base_path)?;
+    let book_a = read_book(a_path)?;
+    let book_b = read_book(b_path)?;
+
+    let mut plan = MergePlan::default();
+
+    // Sheets added in fork B that fork A does not have
+    for change in &changes_b {
+        if let Change::Sheet(SheetDiff::SheetAdded { sheet, .. }) = change
+            && book_a.get_sheet_by_name(sheet).is_none()
+        {
+            plan.auto.sheets.push(sheet.clone());
+        }
+    }
+
+    // Cells: every address fork B touched is compared three ways
+    let touched: BTreeSet<(String, String)> = changes_b
+        .iter()
+        .filter_map(|change| match change {
+            Change::Cell(cell) => {
+                let address = match &cell.diff {
+                    CellDiff::Added { address, .. }
+                    | CellDiff::Deleted { address, .. }
+                    | CellDiff::Modified { address, .. } => address,
+                };
+                Some((cell.sheet.clone(), address.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let mut missing_sheets: BTreeMap<String, usize> = BTreeMap::new();
+    for (sheet, address) in touched {
+        if book_a.get_sheet_by_name(&sheet).is_none() && !plan.auto.sheets.contains(&sheet) {
+            *missing_sheets.entry(sheet).or_default() += 1;
+            continue;
+        }
+        let base_cell = CellSnapshot::read(&base, &sheet, &address);
+        let a_cell = CellSnapshot::read(&book_a, &sheet, &address);
+        let b_cell = CellSnapshot::read(&book_b, &sheet, &address);
+
+        let mut write = CellWrite {
+            sheet: sheet.clone(),
+            address: address.clone(),
+            content: None,
+            style: None,
+        };
+
+        let (base_content, a_content, b_content) =
+            (base_cell.content(), a_cell.content(), b_cell.content());
+        if !same_content(&base_content, &b_content) {
+            if same_content(&a_content, &b_content) {
+                plan.identical += 1;
+            } else if same_content(&base_content, &a_content) {
+                write.content = Some(b_content);
+            } else {
+                plan.conflicts.push(PlannedConflict {
+                    conflict: MergeConflict {
+                        kind: ConflictKind::CellContent,
+                        sheet: Some(sheet.clone()),
+                        address: Some(address.clone()),
+                        name: None,
+                        base: base_cell.content_side(),
+                        fork_a: a_cell.content_side(),
+                        fork_b: b_cell.content_side(),
+                        resolution: ConflictResolution::KeptA,
+                    },
+                    take_b: ConflictWrite::Cell(CellWrite {
+                        sheet: sheet.clone(),
+                        address: address.clone(),
+                        content: Some(b_content),
+                        style: None,
+                    }),
+                });
+            }
+        }
+
+        if base_cell.style_id != b_cell.style_id {
+            if a_cell.style_id == b_cell.style_id {
+                plan.identical += 1;
+            } else if base_cell.style_id == a_cell.style_id {
+                write.style = Some(b_cell.style.clone());
+            } else {
+                plan.conflicts.push(PlannedConflict {
+                    conflict: MergeConflict {
+                        kind: ConflictKind::CellStyle,
+                        sheet: Some(sheet.clone()),
+                        address: Some(address.clone()),
+                        name: None,
+                        base: base_cell.style_side(),
+                        fork_a: a_cell.style_side(),
+                        fork_b: b_cell.style_side(),
+                        resolution: ConflictResolution::KeptA,
+                    },
+                    take_b: ConflictWrite::Cell(CellWrite {
+                        sheet: sheet.clone(),
+                        address: address.clone(),
+                        content: None,
+                        style: Some(b_cell.style.clone()),
+                    }),
+                });
+            }
+        }
+
+        if write.content.is_some() || write.style.is_some() {
+            plan.auto.cells.push(write);
+        }
+    }
+    for (sheet, count) in missing_sheets {
+        plan.warnings.push(format!(
+            "sheet '{}' no longer exists in fork_a; {} cell change(s) from fork_b were not merged",
+            sheet, count
+        ));
+    }
+
+    // Defined names
+    let names_a = name_changes(&changes_a);
+    let mut names_b: Vec<_> = name_changes(&changes_b).into_iter().collect();
+    names_b.sort_by(|x, y| x.0.cmp(&y.0));
+    for (key, (name, base_formula, b_formula)) in names_b {
+        let write = NameWrite {
+            name: name.clone(),
+            scope_sheet: key.1.clone(),
+            formula: b_formula.clone(),
+        };
+        match names_a.get(&key) {
+            None => plan.auto.names.push(write),
+            Some((_, _, a_formula)) if *a_formula == b_formula => plan.identical += 1,
+            Some((_, a_base, a_formula)) => plan.conflicts.push(PlannedConflict {
+                conflict: MergeConflict {
+                    kind: ConflictKind::Name,
+                    sheet: key.1.clone(),
+                    address: None,
+                    name: Some(name),
+                    base: name_side(&base_formula.or_else(|| a_base.clone())),
+                    fork_a: name_side(a_formula),
+                    fork_b: name_side(&b_formula),
+                    resolution: ConflictResolution::KeptA,
+                },
+                take_b: ConflictWrite::Name(write),
+            }),
+        }
+    }
+
+    // Tables are copied whole from fork B (or removed) when fork A left them
+    // alone; tables both forks changed differently are conflicts
+    let tables_a: HashSet<(String, String)> = changes_a
+        .iter()
+        .filter_map(|change| match change {
+            Change::Table(diff) => {
+                let (sheet, name) = table_key(diff);
+                Some((sheet.to_string(), name.to_ascii_lowercase()))
+            }
+            _ => None,
+        })
+        .collect();
+    for change in &changes_b {
+        let Change::Table(diff) = change else {
+            continue;
+        };
+        let (sheet, display_name) = table_key(diff);
+        let b_table = find_table(&book_b, sheet, display_name).map(TableDefinition::of);
+        let write = TableWrite {
+            sheet: sheet.to_string(),
+            display_name: display_name.to_string(),
+            definition: b_table.clone(),
+        };
+        if !tables_a.contains(&(sheet.to_string(), display_name.to_ascii_lowercase())) {
+            plan.auto.tables.push(write);
+            continue;
+        }
+        let a_table = find_table(&book_a, sheet, display_name).map(TableDefinition::of);
+        if a_table == b_table {
+            plan.identical += 1;
+            continue;
+        }
+        let base_table = find_table(&base, sheet, display_name).map(TableDefinition::of);
+        plan.conflicts.push(PlannedConflict {
+            conflict: MergeConflict {
+                kind: ConflictKind::Table,
+                sheet: Some(sheet.to_string()),
+                address: None,
+                name: Some(display_name.to_string()),
+                base: table_side(base_table.as_ref()),
+                fork_a: table_side(a_table.as_ref()),
+                fork_b: table_side(b_table.as_ref()),
+                resolution: ConflictResolution::KeptA,
+            },
+            take_b: ConflictWrite::Table(write),
+        });
+    }
+
+    // Everything else is reported rather than merged
+    let mut unmerged: BTreeMap<&str, usize> = BTreeMap::new();
+    for change in &changes_b {
+        let kind = match change {
+            Change::Comment(_) => "comment",
+            Change::Chart(_) => "chart",
+            Change::DataValidation(_) => "data_validation",
+            Change::ConditionalFormat(_) => "conditional_format",
+            Change::Merge(_) => "merge",
+            Change::Sheet(SheetDiff::SheetAdded { .. }) => continue,
+            Change::Sheet(_) => "sheet",
+            _ => continue,
+        };
+        *unmerged.entry(kind).or_default() += 1;
+    }
+    for (kind, count) in unmerged {
+        plan.warnings.push(format!(
+            "{} {} change(s) in fork_b are not merged; inspect them with get_changeset",
+            count, kind
+        ));
+    }
+
+    Ok(plan)
+}
+
+/// Apply `payload` to the workbook at `path`. With `source` (fork B's work
+/// file) tables are copied from it whole; without it they are rebuilt from
+/// the definitions in the payload.
+fn apply_merge_to_file(
+    path: &Path,
+    payload: &MergeStagedPayload,
+    source: Option<&Path>,
+) -> Result<()> {
+    let mut book = read_book(path)?;
+
+    for name in &payload.sheets {
+        if book.get_sheet_by_name(name).is_none() {
+            book.new_sheet(name.as_str())
+                .map_err(|e| anyhow!("failed to add sheet '{}': {}", name, e))?;
+        }
+    }
+
+    for write in &payload.cells {
+        let sheet = book
+            .get_sheet_by_name_mut(&write.sheet)
+            .ok_or_else(|| anyhow!("sheet '{}' not found", write.sheet))?;
+        let cell = sheet.get_cell_mut(write.address.as_str());
+        if let Some(style) = &write.style {
+            cell.set_style(style_from_descriptor(style));
+        }
+        match &write.content {
+            Some(CellContent::Value { value }) => {
+                cell.get_cell_value_mut().remove_formula();
+                cell.set_value(value.clone());
+            }
+            Some(CellContent::Formula { formula }) => {
+                cell.set_formula(formula.clone());
+                cell.set_formula_result_default("");
+            }
+            Some(CellContent::Cleared) => {
+                cell.get_cell_value_mut().remove_formula();
+                cell.set_value(String::new());
+            }
+            None => {}
+        }
+    }
+
+    for write in &payload.names {
+        apply_name_write(&mut book, write)?;
+    }
+
+    let source = match source {
+        Some(source) if !payload.tables.is_empty() => Some(read_book(source)?),
+        _ => None,
+    };
+    for write in &payload.tables {
+        apply_table_write(&mut book, source.as_ref(), write)?;
+    }
+
+    umya_spreadsheet::writer::xlsx::write(&book, path)?;
+    Ok(())
+}
+
+fn apply_table_write(
+    book: &mut Spreadsheet,
+    source: Option<&Spreadsheet>,
+    write: &TableWrite,
+) -> Result<()> {
+    let table = write.definition.as_ref().map(|definition| {
+        source
+            .and_then(|source| find_table(source, &write.sheet, &write.display_name))
+            .cloned()
+            .unwrap_or_else(|| definition.build(&write.display_name))
+    });
+    let sheet = book
+        .get_sheet_by_name_mut(&write.sheet)
+        .ok_or_else(|| anyhow!("sheet '{}' not found", write.sheet))?;
+    let tables = sheet.get_tables_mut();
+    let existing = tables.iter().position(|t| {
+        t.get_display_name()
+            .eq_ignore_ascii_case(&write.display_name)
+    });
+    match (existing, table) {
+        (Some(index), Some(table)) => tables[index] = table,
+        (Some(index), None) => {
+            tables.remove(index);
+        }
+        (None, Some(table)) => tables.push(table),
+        (None, None) => {}
+    }
+    Ok(())
+}
+
+fn apply_name_write(book: &mut Spreadsheet, write: &NameWrite) -> Result<()> {
+    let matches = |defined: &umya_spreadsheet::structs::DefinedName| {
+        defined.get_name().eq_ignore_ascii_case(&write.name)
+    };
+    let result = match &write.scope_sheet {
+        Some(scope) => {
+            let sheet = book
+                .get_sheet_by_name_mut(scope)
+                .ok_or_else(|| anyhow!("sheet '{}' not found", scope))?;
+            sheet.get_defined_names_mut().retain(|d| !matches(d));
+            match &write.formula {
+                Some(formula) => sheet.add_defined_name(write.name.clone(), formula.clone()),
+                None => Ok(()),
+            }
+        }
+        None => {
+            book.get_defined_names_mut().retain(|d| !matches(d));
+            match &write.formula {
+                Some(formula) => book.add_defined_name(write.name.clone(), formula.clone()),
+                None => Ok(()),
+            }
+        }
+    };
+    result.map_err(|e| anyhow!("failed to set defined name '{}': {}", write.name, e))
+}
@@ -1,3 +1,5 @@
+pub mod charts;
+pub mod diff;
 pub mod dod;
 pub mod filters;
 #[cfg(feature = "recalc")]
@@ -10,8 +12,12 @@ pub mod jira_export;
 pub mod jira_integration;
 pub mod jira_unified;
 pub mod manifest;
+#[cfg(feature = "recalc")]
+pub mod merge;
 pub mod ontology_generation;
 pub mod ontology_sparql;
+pub mod pivots;
+pub mod render;
 pub mod sparql_safety;
 pub mod template_safety;
 pub mod tera_authoring;
@@ -20,7 +26,10 @@ pub mod vba;
 pub mod verify_receipt;
 
 use crate::analysis::{formula::FormulaGraph, stats};
+use crate::comments::ThreadedComments;
+use crate::data_validation::{scan_violations, sheet_rules};
 use crate::model::*;
+use crate::sheet_settings;
 use crate::state::AppState;
 use crate::utils::column_number_to_name;
 use crate::validation::validate_sample_size;
@@ -65,7 +74,12 @@ pub async fn describe_workbook(
     params: DescribeWorkbookParams,
 ) -> Result<WorkbookDescription> {
     let workbook = state.open_workbook(&params.workbook_or_fork_id).await?;
-    let desc = workbook.describe();
+    let mut desc = workbook.describe();
+    let path = workbook.path.clone();
+    desc.protection =
+        tokio::task::spawn_blocking(move || sheet_settings::read_workbook_protection(&path))
+            .await?
+            .unwrap_or_default();
     Ok(desc)
 }
 
@@ -272,10 +286,38 @@ pub async fn sheet_overview(
     params: SheetOverviewParams,
 ) -> Result<SheetOverviewResponse> {
     let workbook = state.open_workbook(&params.workbook_or_fork_id).await?;
+    let workbook_path = workbook.path.clone();
     let sheet_name = params.sheet_name.clone();
     let mut overview =
         tokio::task::spawn_blocking(move || workbook.sheet_overview(&sheet_name)).await??;
 
+    // Pivot output is plain cells to the region detector; say where it came from.
+    let pivot_tables = pivots::read_sheet_pivots(&workbook_path, Some(&params.sheet_name))
+        .await
+        .unwrap_or_default();
+    for table in pivot_tables.iter().flat_map(|(_, tables)| tables) {
+        let source = &table.cache.source;
+        let source = match (&source.name, &source.sheet, &source.range) {
+            (Some(name), _, _) => name.clone(),
+            (None, Some(sheet), Some(range)) => format!("{sheet}!{range}"),
+            (None, None, Some(range)) => range.clone(),
+            _ => format!("{} source", source.kind),
+        };
+        overview.notes.push(format!(
+            "{} is pivot table '{}' summarizing {}; use list_pivot_tables or pivot_data.",
+            table.location, table.name, source
+        ));
+    }
+
+    let (settings_path, settings_sheet) = (workbook_path.clone(), params.sheet_name.clone());
+    overview.sheet_settings = tokio::task::spawn_blocking(move || {
+        sheet_settings::read_workbook_settings(&settings_path, Some(settings_sheet.as_str()))
+    })
+    .await?
+    .ok()
+    .and_then(|settings| settings.sheets.into_iter().next())
+    .map(|(_, sheet)| sheet);
+
     let max_regions = params
         .max_regions
         .unwrap_or(DEFAULT_OVERVIEW_MAX_REGIONS)
@@ -377,6 +419,8 @@ pub struct SheetPageParams {
     pub include_header: bool,
     #[serde(default)]
     pub format: Option<SheetPageFormat>,
+    #[serde(default)]
+    pub value_mode: Option<ValueMode>,
 }
 
 impl Default for SheetPageParams {
@@ -392,6 +436,7 @@ impl Default for SheetPageParams {
             include_styles: false,
             include_header: default_include_header(),
             format: None,
+            value_mode: None,
         }
     }
 }
@@ -475,6 +520,8 @@ pub struct ReadTableParams {
     pub limit: Option<u32>,
     #[serde(default)]
     pub offset: Option<u32>,
+    #[serde(default)]
+    pub value_mode: Option<ValueMode>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema, Clone)]
@@ -508,6 +555,8 @@ pub struct RangeValuesParams {
     pub ranges: Vec<String>,
     #[serde(default)]
     pub include_headers: Option<bool>,
+    #[serde(default)]
+    pub value_mode: Option<ValueMode>,
 }
 
 pub async fn sheet_page(
@@ -529,6 +578,7 @@ pub async fn sheet_page(
     let columns = params.columns.clone();
     let columns_by_header = params.columns_by_header.clone();
     let include_header = params.include_header;
+    let value_mode = params.value_mode.unwrap_or_default();
 
     let page = workbook.with_sheet(&params.sheet_name, |sheet| {
         build_page(
@@ -540,6 +590,7 @@ pub async fn sheet_page(
             include_formulas,
             include_styles,
             include_header,
+            value_mode,
         )
     })?;
 
@@ -555,6 +606,7 @@ pub async fn sheet_page(
             &page.header,
             &page.rows,
             include_header,
+            value_mode,
         ))
     } else {
         None
@@ -565,6 +617,7 @@ pub async fn sheet_page(
             &page.header,
             &page.rows,
             include_header,
+            value_mode,
         ))
     } else {
         None
@@ -775,6 +828,7 @@ fn build_page(
     include_formulas: bool,
     include_styles: bool,
     include_header: bool,
+    value_mode: ValueMode,
 ) -> PageBuildResult {
     let max_col = sheet.get_highest_column();
     let end_row = (start_row + page_size - 1).min(sheet.get_highest_row().max(start_row));
@@ -788,6 +842,7 @@ fn build_page(
             &column_indices,
             include_formulas,
             include_styles,
+            value_mode,
         ))
     } else {
         None
@@ -801,6 +856,7 @@ fn build_page(
             &column_indices,
             include_formulas,
             include_styles,
+            value_mode,
         ));
     }
 
@@ -817,11 +873,17 @@ fn build_row_snapshot(
     columns: &[u32],
     include_formulas: bool,
     include_styles: bool,
+    value_mode: ValueMode,
 ) -> RowSnapshot {
     let mut cells = Vec::new();
     for &col in columns {
         if let Some(cell) = sheet.get_cell((col, row_index)) {
-            cells.push(build_cell_snapshot(cell, include_formulas, include_styles));
+            cells.push(build_cell_snapshot(
+                cell,
+                include_formulas,
+                include_styles,
+                value_mode,
+            ));
         } else {
             let address = crate::utils::cell_address(col, row_index);
             cells.push(CellSnapshot {
@@ -832,6 +894,7 @@ fn build_row_snapshot(
                 number_format: None,
                 style_tags: Vec::new(),
                 notes: Vec::new(),
+                formatted: None,
             });
         }
     }
@@ -843,9 +906,10 @@ fn build_cell_snapshot(
     cell: &umya_spreadsheet::Cell,
     include_formulas: bool,
     include_styles: bool,
+    value_mode: ValueMode,
 ) -> CellSnapshot {
     let address = cell.get_coordinate().get_coordinate();
-    let value = crate::workbook::cell_to_value(cell);
+    let (value, formatted) = cell_value_for_mode(cell, value_mode);
     let formula = if include_formulas && cell.is_formula() {
         Some(cell.get_formula().to_string())
     } else {
@@ -879,6 +943,26 @@ fn build_cell_snapshot(
         number_format,
         style_tags,
         notes: Vec::new(),
+        formatted,
+    }
+}
+
+/// The value a read tool reports for `cell` under `mode`, plus its display
+/// text when both are requested.
+fn cell_value_for_mode(
+    cell: &umya_spreadsheet::Cell,
+    mode: ValueMode,
+) -> (Option<CellValue>, Option<String>) {
+    match mode {
+        ValueMode::Raw => (cell_to_value(cell), None),
+        ValueMode::Formatted => (
+            crate::number_format::cell_display_text(cell).map(CellValue::Text),
+            None,
+        ),
+        ValueMode::Both => (
+            cell_to_value(cell),
+            crate::number_format::cell_display_text(cell),
+        ),
     }
 }
 
@@ -963,6 +1047,7 @@ fn build_compact_payload(
     header: &Option<RowSnapshot>,
     rows: &[RowSnapshot],
     include_header: bool,
+    value_mode: ValueMode,
 ) -> SheetPageCompact {
     let headers = derive_headers(header, rows);
     let header_row = if include_header {
@@ -982,11 +1067,21 @@ fn build_compact_payload(
             vals
         })
         .collect();
+    let formatted_rows = (value_mode == ValueMode::Both).then(|| {
+        rows.iter()
+            .map(|row| {
+                let mut texts = vec![Some(row.row_index.to_string())];
+                texts.extend(row.cells.iter().map(|c| c.formatted.clone()));
+                texts
+            })
+            .collect()
+    });
 
     SheetPageCompact {
         headers,
         header_row,
         rows: data_rows,
+        formatted_rows,
     }
 }
 
@@ -994,16 +1089,25 @@ fn build_values_only_payload(
     header: &Option<RowSnapshot>,
     rows: &[RowSnapshot],
     include_header: bool,
+    value_mode: ValueMode,
 ) -> SheetPageValues {
-    let mut data = Vec::new();
-    if include_header && let Some(h) = header {
-        data.push(h.cells.iter().map(|c| c.value.clone()).collect());
-    }
-    for row in rows {
-        data.push(row.cells.iter().map(|c| c.value.clone()).collect());
-    }
+    let header = header.as_ref().filter(|_| include_header);
+    let snapshots: Vec<&RowSnapshot> = header.into_iter().chain(rows).collect();
+    let data = snapshots
+        .iter()
+        .map(|row| row.cells.iter().map(|c| c.value.clone()).collect())
+        .collect();
+    let formatted_rows = (value_mode == ValueMode::Both).then(|| {
+        snapshots
+            .iter()
+            .map(|row| row.cells.iter().map(|c| c.formatted.clone()).collect())
+            .collect()
+    });
 
-    SheetPageValues { rows: data }
+    SheetPageValues {
+        rows: data,
+        formatted_rows,
+    }
 }
 
 fn derive_headers(header: &Option<RowSnapshot>, rows: &[RowSnapshot]) -> Vec<String> {
@@ -1200,7 +1304,8 @@ fn extract_table_rows(
     limit: usize,
     offset: usize,
     sample_mode: &str,
-) -> Result<(Vec<String>, Vec<TableRow>, u32)> {
+    include_formatted: bool,
+) -> Result<(Vec<String>, Vec<TableRow>, Vec<FormattedTableRow>, u32)> {
     let ((start_col, start_row), (end_col, end_row)) = target.range;
     let mut header_start = header_row.or(target.header_hint).unwrap_or(start_row);
     if header_start < start_row {
@@ -1218,18 +1323,25 @@ fn extract_table_rows(
     };
 
     let headers = build_headers(sheet, &column_indices, header_start, header_rows_count);
-    let mut all_rows: Vec<TableRow> = Vec::new();
+    let mut all_rows: Vec<(TableRow, FormattedTableRow)> = Vec::new();
     let mut total_rows: u32 = 0;
 
     for row_idx in data_start_row..=end_row {
         let mut row = BTreeMap::new();
+        let mut formatted = BTreeMap::new();
         for (i, col_idx) in column_indices.iter().enumerate() {
             let header = headers
                 .get(i)
                 .cloned()
                 .unwrap_or_else(|| format!("Col{col_idx}"));
-            let value = sheet.get_cell((*col_idx, row_idx)).and_then(cell_to_value);
-            row.insert(header, value);
+            let cell = sheet.get_cell((*col_idx, row_idx));
+            if include_formatted {
+                formatted.insert(
+                    header.clone(),
+                    cell.and_then(crate::number_format::cell_display_text),
+                );
+            }
+            row.insert(header, cell.and_then(cell_to_value));
         }
         if !row_passes_filters(&row, filters.as_ref()) {
             continue;
@@ -1238,12 +1350,14 @@ fn extract_table_rows(
         if matches!(sample_mode, "first" | "all") && total_rows as usize > offset + limit {
             continue;
         }
-        all_rows.push(row);
+        all_rows.push((row, formatted));
     }
 
-    let rows = sample_rows(all_rows, limit, offset, sample_mode);
+    let (rows, formatted_rows) = sample_rows(all_rows, limit, offset, sample_mode)
+        .into_iter()
+        .unzip();
 
-    Ok((headers, rows, total_rows))
+    Ok((headers, rows, formatted_rows, total_rows))
 }
 
 fn build_headers(
@@ -1397,7 +1511,7 @@ fn value_lt(cell: &Option<CellValue>, cmp: &serde_json::Value) -> bool {
     }
 }
 
-fn sample_rows(rows: Vec<TableRow>, limit: usize, offset: usize, mode: &str) -> Vec<TableRow> {
+fn sample_rows<T: Clone>(rows: Vec<T>, limit: usize, offset: usize, mode: &str) -> Vec<T> {
     if rows.is_empty() {
         return rows;
     }
@@ -1809,6 +1923,210 @@ pub async fn find_formula(
     Ok(response)
 }
 
+fn default_sheet_comments_limit() -> u32 {
+    100
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SheetCommentsParams {
+    #[serde(alias = "workbook_id")]
+    pub workbook_or_fork_id: WorkbookId,
+    /// Limit to one sheet; all sheets when omitted.
+    pub sheet_name: Option<String>,
+    /// Skip resolved threads.
+    #[serde(default)]
+    pub unresolved_only: bool,
+    #[serde(default = "default_sheet_comments_limit")]
+    pub limit: u32,
+    #[serde(default)]
+    pub offset: u32,
+}
+
+pub async fn sheet_comments(
+    state: Arc<AppState>,
+    params: SheetCommentsParams,
+) -> Result<SheetCommentsResponse> {
+    let workbook = state.open_workbook(&params.workbook_or_fork_id).await?;
+    let sheet_names: Vec<String> = match &params.sheet_name {
+        Some(sheet) => vec![sheet.clone()],
+        None => workbook.sheet_names(),
+    };
+    let threads = {
+        let path = workbook.path.clone();
+        tokio::task::spawn_blocking(move || ThreadedComments::read(&path)).await??
+    };
+
+    let mut comments = Vec::new();
+    for sheet_name in &sheet_names {
+        let notes = workbook.with_sheet(sheet_name, |sheet| {
+            sheet
+                .get_comments()
+                .iter()
+                .map(|c| {
+                    (
+                        c.get_coordinate().to_string(),
+                        c.get_author().to_string(),
+                        c.get_text().get_text().to_string(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })?;
+
+        let mut sheet_comments: Vec<SheetComment> = threads
+            .threads(sheet_name)
+            .into_iter()
+            .map(|thread| SheetComment {
+                sheet_name: sheet_name.clone(),
+                address: thread.root.address.clone(),
+                kind: CommentKind::Thread,
+                author: threads.author(&thread.root.person_id).map(str::to_string),
+                text: thread.root.text.clone(),
+                resolved: Some(thread.root.done),
+                created_at: thread.root.created_at.clone(),
+                replies: thread
+                    .replies
+                    .iter()
+                    .map(|reply| CommentReply {
+                        author: threads.author(&reply.person_id).map(str::to_string),
+                        text: reply.text.clone(),
+                        created_at: reply.created_at.clone(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        // Threads carry a placeholder note at the same cell; report the thread only.
+        for (address, author, text) in notes {
+            if sheet_comments.iter().any(|c| c.address == address) {
+                continue;
+            }
+            sheet_comments.push(SheetComment {
+                sheet_name: sheet_name.clone(),
+                address,
+                kind: CommentKind::Note,
+                author: (!author.is_empty()).then_some(author),
+                text,
+                resolved: None,
+                created_at: None,
+                replies: Vec::new(),
+            });
+        }
+        sheet_comments.sort_by_key(|c| parse_address(&c.address).map(|(col, row)| (row, col)));
+        comments.extend(sheet_comments);
+    }
+    if params.unresolved_only {
+        comments.retain(|c| c.resolved != Some(true));
+    }
+
+    let total = comments.len() as u32;
+    let limit = params.limit.clamp(1, 500);
+    let offset = params.offset.min(total);
+    let comments: Vec<SheetComment> = comments
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect();
+    let end = offset + comments.len() as u32;
+    let truncated = end < total;
+
+    Ok(SheetCommentsResponse {
+        workbook_id: workbook.id.clone(),
+        workbook_short_id: workbook.short_id.clone(),
+        comments,
+        total,
+        truncated,
+        next_offset: truncated.then_some(end),
+    })
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DataValidationsParams {
+    #[serde(alias = "workbook_id")]
+    pub workbook_or_fork_id: WorkbookId,
+    /// Limit to one sheet; all sheets when omitted.
+    pub sheet_name: Option<String>,
+}
+
+pub async fn data_validations(
+    state: Arc<AppState>,
+    params: DataValidationsParams,
+) -> Result<DataValidationsResponse> {
+    let workbook = state.open_workbook(&params.workbook_or_fork_id).await?;
+    let sheet_names: Vec<String> = match &params.sheet_name {
+        Some(sheet) => vec![sheet.clone()],
+        None => workbook.sheet_names(),
+    };
+
+    let rules = workbook.with_spreadsheet(|book| {
+        let mut rules = Vec::new();
+        for sheet_name in &sheet_names {
+            let sheet = book
+                .get_sheet_by_name(sheet_name)
+                .ok_or_else(|| anyhow!("sheet {} not found", sheet_name))?;
+            rules.extend(sheet_rules(book, sheet));
+        }
+        Ok::<_, anyhow::Error>(rules)
+    })??;
+
+    Ok(DataValidationsResponse {
+        workbook_id: workbook.id.clone(),
+        workbook_short_id: workbook.short_id.clone(),
+        rules,
+    })
+}
+
+fn default_validation_violations_limit() -> u32 {
+    100
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FindValidationViolationsParams {
+    #[serde(alias = "workbook_id")]
+    pub workbook_or_fork_id: WorkbookId,
+    /// Limit to one sheet; all sheets when omitted.
+    pub sheet_name: Option<String>,
+    #[serde(default = "default_validation_violations_limit")]
+    pub limit: u32,
+}
+
+pub async fn find_validation_violations(
+    state: Arc<AppState>,
+    params: FindValidationViolationsParams,
+) -> Result<ValidationViolationsResponse> {
+    let workbook = state.open_workbook(&params.workbook_or_fork_id).await?;
+    let sheet_names: Vec<String> = match &params.sheet_name {
+        Some(sheet) => vec![sheet.clone()],
+        None => workbook.sheet_names(),
+    };
+    let limit = params.limit.clamp(1, 1000) as usize;
+
+    let mut response = ValidationViolationsResponse {
+        workbook_id: workbook.id.clone(),
+        workbook_short_id: workbook.short_id.clone(),
+        violations: Vec::new(),
+        total: 0,
+        truncated: false,
+        rules_checked: 0,
+        unchecked_rules: Vec::new(),
+    };
+    workbook.with_spreadsheet(|book| {
+        for sheet_name in &sheet_names {
+            let sheet = book
+                .get_sheet_by_name(sheet_name)
+                .ok_or_else(|| anyhow!("sheet {} not found", sheet_name))?;
+            let remaining = limit.saturating_sub(response.violations.len());
+            let scan = scan_violations(book, sheet, remaining, |_| true);
+            response.violations.extend(scan.violations);
+            response.total += scan.total;
+            response.rules_checked += scan.rules_checked;
+            response.unchecked_rules.extend(scan.unchecked_rules);
+        }
+        Ok::<_, anyhow::Error>(())
+    })??;
+    response.truncated = (response.violations.len() as u32) < response.total;
+
+    Ok(response)
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct ScanVolatilesParams {
     #[serde(alias = "workbook_id")]
@@ -2294,6 +2612,7 @@ pub async fn range_values(
 ) -> Result<RangeValuesResponse> {
     let workbook = state.open_workbook(&params.workbook_or_fork_id).await?;
     let include_headers = params.include_headers.unwrap_or(false);
+    let value_mode = params.value_mode.unwrap_or_default();
     let values = workbook.with_sheet(&params.sheet_name, |sheet| {
         params
             .ranges
@@ -2301,20 +2620,30 @@ pub async fn range_values(
             .filter_map(|range| {
                 parse_range(range).map(|((start_col, start_row), (end_col, end_row))| {
                     let mut rows = Vec::new();
+                    let mut formatted_rows = Vec::new();
                     for r in start_row..=end_row {
                         let mut row_vals = Vec::new();
+                        let mut row_texts = Vec::new();
                         for c in start_col..=end_col {
-                            if include_headers && r == start_row && start_row == 1 {
-                                row_vals.push(sheet.get_cell((c, 1u32)).and_then(cell_to_value));
+                            let row = if include_headers && r == start_row && start_row == 1 {
+                                1u32
                             } else {
-                                row_vals.push(sheet.get_cell((c, r)).and_then(cell_to_value));
-                            }
+                                r
+                            };
+                            let (value, formatted) = sheet
+                                .get_cell((c, row))
+                                .map(|cell| cell_value_for_mode(cell, value_mode))
+                                .unwrap_or_default();
+                            row_vals.push(value);
+                            row_texts.push(formatted);
                         }
                         rows.push(row_vals);
+                        formatted_rows.push(row_texts);
                     }
                     RangeValuesEntry {
                         range: range.clone(),
                         rows,
+                        formatted_rows: (value_mode == ValueMode::Both).then_some(formatted_rows),
                     }
                 })
             })
@@ -2409,21 +2738,42 @@ pub async fn read_table(
         .clone()
         .unwrap_or_else(|| "first".to_string());
 
-    let (headers, rows, total_rows) = workbook.with_sheet(&resolved.sheet_name, |sheet| {
-        extract_table_rows(
-            sheet,
-            &resolved,
-            params.header_row,
-            params.header_rows,
-            params.columns.clone(),
-            params.filters.clone(),
-            limit,
-            offset,
-            &sample_mode,
-        )
-    })??;
+    let value_mode = params.value_mode.unwrap_or_default();
+
+    let (headers, rows, formatted_rows, total_rows) =
+        workbook.with_sheet(&resolved.sheet_name, |sheet| {
+            extract_table_rows(
+                sheet,
+                &resolved,
+                params.header_row,
+                params.header_rows,
+                params.columns.clone(),
+                params.filters.clone(),
+                limit,
+                offset,
+                &sample_mode,
+                value_mode != ValueMode::Raw,
+            )
+        })??;
 
     let has_more = offset + rows.len() < total_rows as usize;
+    // Filters and sampling run on stored values; formatted text replaces
+    // them only once the rows are chosen.
+    let (rows, formatted_rows) = match value_mode {
+        ValueMode::Raw => (rows, None),
+        ValueMode::Formatted => (
+            formatted_rows
+                .into_iter()
+                .map(|row| {
+                    row.into_iter()
+                        .map(|(header, text)| (header, text.map(CellValue::Text)))
+                        .collect()
+                })
+                .collect(),
+            None,
+        ),
+        ValueMode::Both => (rows, Some(formatted_rows)),
+    };
 
     Ok(ReadTableResponse {
         workbook_id: workbook.id.clone(),
@@ -2434,6 +2784,7 @@ pub async fn read_table(
         rows,
         total_rows,
         has_more,
+        formatted_rows,
     })
 }
 
@@ -2457,6 +2808,7 @@ pub async fn table_profile(
             sample_mode: params.sample_mode.clone(),
             limit: params.sample_size,
             offset: Some(0),
+            value_mode: None,
         },
     )?;
 
@@ -2466,7 +2818,7 @@ pub async fn table_profile(
         .clone()
         .unwrap_or_else(|| "distributed".to_string());
 
-    let (headers, rows, total_rows) = workbook.with_sheet(&resolved.sheet_name, |sheet| {
+    let (headers, rows, _, total_rows) = workbook.with_sheet(&resolved.sheet_name, |sheet| {
         extract_table_rows(
             sheet,
             &resolved,
@@ -2477,6 +2829,7 @@ pub async fn table_profile(
             requested_sample_size,
             0,
             &sample_mode,
+            false,
         )
     })??;
 
@@ -2600,8 +2953,8 @@ fn collect_formula_matches(
 
         let context = if include_context {
             let columns = vec![column];
-            let context_row = build_row_snapshot(sheet, row, &columns, true, false);
-            let header_row = build_row_snapshot(sheet, 1, &columns, false, false);
+            let context_row = build_row_snapshot(sheet, row, &columns, true, false, ValueMode::Raw);
+            let header_row = build_row_snapshot(sheet, 1, &columns, false, false, ValueMode::Raw);
             vec![header_row, context_row]
         } else {
             Vec::new()
@@ -0,0 +1,291 @@
+use crate::model::WorkbookId;
+use crate::pivots::{self, PivotTableDef};
+use crate::state::AppState;
+use anyhow::{Result, anyhow};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+const DEFAULT_MAX_ROWS: u32 = 500;
+const MAX_ROWS_LIMIT: u32 = 10_000;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListPivotTablesParams {
+    #[serde(alias = "workbook_id")]
+    pub workbook_or_fork_id: WorkbookId,
+    /// Limit to one sheet; all sheets when omitted.
+    #[serde(default)]
+    pub sheet_name: Option<String>,
+}
+
+fn default_max_rows() -> u32 {
+    DEFAULT_MAX_ROWS
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PivotDataParams {
+    #[serde(alias = "workbook_id")]
+    pub workbook_or_fork_id: WorkbookId,
+    /// Sheet holding the pivot table.
+    pub sheet_name: String,
+    /// Pivot table name, as reported by list_pivot_tables.
+    pub pivot_name: String,
+    /// Result rows returned (1..=10000).
+    #[serde(default = "default_max_rows")]
+    pub max_rows: u32,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PivotSourceInfo {
+    /// worksheet | external | consolidation | scenario
+    pub kind: String,
+    pub sheet: Option<String>,
+    pub range: Option<String>,
+    /// Table or defined name the cache reads from.
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PivotPageFieldInfo {
+    pub field: String,
+    /// The item the page is filtered to; `null` shows all items.
+    pub selected: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PivotValueFieldInfo {
+    /// Caption, e.g. `Sum of Units`.
+    pub name: String,
+    pub field: String,
+    /// sum | count | average | max | min | product | countNums | stdDev | stdDevp | var | varp
+    pub aggregation: String,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PivotFilterInfo {
+    pub field: String,
+    /// Label, value or top-N filter type, e.g. `captionEqual`, `valueGreaterThan`, `count`.
+    pub kind: String,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PivotTableSummary {
+    pub sheet_name: String,
+    pub name: String,
+    /// Cells the pivot renders into, e.g. `A3:D12`.
+    pub location: String,
+    pub source: PivotSourceInfo,
+    pub row_fields: Vec<String>,
+    pub column_fields: Vec<String>,
+    pub page_fields: Vec<PivotPageFieldInfo>,
+    pub value_fields: Vec<PivotValueFieldInfo>,
+    pub filters: Vec<PivotFilterInfo>,
+    /// Items unchecked in a field's item list, by field.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub hidden_items: BTreeMap<String, Vec<String>>,
+    pub record_count: Option<u64>,
+    /// Whether the cache saved its records, which pivot_data needs.
+    pub records_cached: bool,
+    pub refreshed_by: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ListPivotTablesResponse {
+    pub workbook_id: WorkbookId,
+    pub workbook_short_id: String,
+    pub pivot_tables: Vec<PivotTableSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PivotDataRow {
+    /// Items of the row fields, in field order.
+    pub row_items: Vec<String>,
+    /// Items of the column fields, in field order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub column_items: Vec<String>,
+    /// One figure per value field.
+    pub values: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PivotDataResponse {
+    pub workbook_id: WorkbookId,
+    pub workbook_short_id: String,
+    pub sheet_name: String,
+    pub pivot_name: String,
+    pub row_fields: Vec<String>,
+    pub column_fields: Vec<String>,
+    pub value_fields: Vec<String>,
+    /// One row per combination of row and column items.
+    pub rows: Vec<PivotDataRow>,
+    pub grand_totals: Vec<Option<f64>>,
+    /// Cache records that passed the page fields and hidden items.
+    pub records_used: usize,
+    /// Always `cache_records`: figures come from the cache as of its last
+    /// refresh, not from the current source cells.
+    pub source: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+pub async fn list_pivot_tables(
+    state: Arc<AppState>,
+    params: ListPivotTablesParams,
+) -> Result<ListPivotTablesResponse> {
+    let workbook = state.open_workbook(&params.workbook_or_fork_id).await?;
+    let defs = read_sheet_pivots(&workbook.path, params.sheet_name.as_deref()).await?;
+    let pivot_tables = defs
+        .iter()
+        .flat_map(|(sheet_name, tables)| tables.iter().map(move |t| summarize(sheet_name, t)))
+        .collect();
+    Ok(ListPivotTablesResponse {
+        workbook_id: workbook.id.clone(),
+        workbook_short_id: workbook.short_id.clone(),
+        pivot_tables,
+    })
+}
+
+pub async fn pivot_data(
+    state: Arc<AppState>,
+    params: PivotDataParams,
+) -> Result<PivotDataResponse> {
+    let workbook = state.open_workbook(&params.workbook_or_fork_id).await?;
+    let max_rows = params.max_rows.clamp(1, MAX_ROWS_LIMIT) as usize;
+    let table = read_sheet_pivots(&workbook.path, Some(&params.sheet_name))
+        .await?
+        .into_iter()
+        .flat_map(|(_, tables)| tables)
+        .find(|t| t.name == params.pivot_name)
+        .ok_or_else(|| {
+            anyhow!(
+                "sheet '{}' has no pivot table named '{}'; use list_pivot_tables",
+                params.sheet_name,
+                params.pivot_name
+            )
+        })?;
+
+    let path = workbook.path.clone();
+    let (table, result) = tokio::task::spawn_blocking(move || {
+        let records = pivots::read_cache_records(&path, &table)?;
+        let result = pivots::aggregate(&table, &records);
+        Ok::<_, anyhow::Error>((table, result))
+    })
+    .await??;
+
+    let truncated = result.lines.len() > max_rows;
+    let names =
+        |fields: &[usize]| -> Vec<String> { fields.iter().map(|f| table.field_name(*f)).collect() };
+    Ok(PivotDataResponse {
+        workbook_id: workbook.id.clone(),
+        workbook_short_id: workbook.short_id.clone(),
+        sheet_name: params.sheet_name,
+        pivot_name: table.name.clone(),
+        row_fields: names(&table.row_fields),
+        column_fields: names(&table.col_fields),
+        value_fields: table
+            .data_fields
+            .iter()
+            .map(|d| table.data_caption(d))
+            .collect(),
+        rows: result
+            .lines
+            .into_iter()
+            .take(max_rows)
+            .map(|line| PivotDataRow {
+                row_items: line.row_items,
+                column_items: line.column_items,
+                values: line.values,
+            })
+            .collect(),
+        grand_totals: result.grand_totals,
+        records_used: result.records_used,
+        source: "cache_records".to_string(),
+        truncated,
+        warnings: result.warnings,
+    })
+}
+
+pub(crate) async fn read_sheet_pivots(
+    path: &std::path::Path,
+    sheet_name: Option<&str>,
+) -> Result<Vec<(String, Vec<PivotTableDef>)>> {
+    let path = path.to_path_buf();
+    let mut defs = tokio::task::spawn_blocking(move || pivots::read_pivot_tables(&path)).await??;
+    if let Some(sheet_name) = sheet_name {
+        defs.retain(|(name, _)| name == sheet_name);
+    }
+    Ok(defs)
+}
+
+fn summarize(sheet_name: &str, table: &PivotTableDef) -> PivotTableSummary {
+    let source = &table.cache.source;
+    let hidden_items = table
+        .fields
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| !f.hidden.is_empty())
+        .map(|(idx, f)| {
+            (
+                table.field_name(idx),
+                f.hidden.iter().map(|i| table.item_label(idx, *i)).collect(),
+            )
+        })
+        .collect();
+    PivotTableSummary {
+        sheet_name: sheet_name.to_string(),
+        name: table.name.clone(),
+        location: table.location.clone(),
+        source: PivotSourceInfo {
+            kind: source.kind.clone(),
+            sheet: source.sheet.clone(),
+            range: source.range.clone(),
+            name: source.name.clone(),
+        },
+        row_fields: table
+            .row_fields
+            .iter()
+            .map(|f| table.field_name(*f))
+            .collect(),
+        column_fields: table
+            .col_fields
+            .iter()
+            .map(|f| table.field_name(*f))
+            .collect(),
+        page_fields: table
+            .page_fields
+            .iter()
+            .map(|page| PivotPageFieldInfo {
+                field: table.field_name(page.field),
+                selected: table
+                    .page_selection(page)
+                    .map(|item| table.item_label(page.field, item)),
+            })
+            .collect(),
+        value_fields: table
+            .data_fields
+            .iter()
+            .map(|data| PivotValueFieldInfo {
+                name: table.data_caption(data),
+                field: table.field_name(data.field),
+                aggregation: data.subtotal.clone(),
+            })
+            .collect(),
+        filters: table
+            .filters
+            .iter()
+            .map(|filter| PivotFilterInfo {
+                field: table.field_name(filter.field),
+                kind: filter.kind.clone(),
+                value: filter.value.clone(),
+            })
+            .collect(),
+        hidden_items,
+        record_count: table.cache.record_count,
+        records_cached: table.cache.records_part.is_some(),
+        refreshed_by: table.cache.refreshed_by.clone(),
+    }
+}
@@ -0,0 +1,165 @@
+use crate::data_validation::Area;
+use crate::model::WorkbookId;
+use crate::render::{self, RenderOptions};
+use crate::state::AppState;
+use crate::validation::{
+    DEFAULT_MAX_PNG_AREA_PX, DEFAULT_MAX_PNG_DIM_PX, validate_png_dimensions,
+    validate_screenshot_range,
+};
+use anyhow::{Result, anyhow, bail};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+
+const DEFAULT_RENDER_RANGE: &str = "A1:M40";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderFormat {
+    #[default]
+    Png,
+    Svg,
+    Html,
+}
+
+impl RenderFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            RenderFormat::Png => "png",
+            RenderFormat::Svg => "svg",
+            RenderFormat::Html => "html",
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            RenderFormat::Png => "image/png",
+            RenderFormat::Svg => "image/svg+xml",
+            RenderFormat::Html => "text/html",
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RenderSheetParams {
+    #[serde(alias = "workbook_id")]
+    pub workbook_or_fork_id: WorkbookId,
+    pub sheet_name: String,
+    #[serde(default)]
+    pub range: Option<String>,
+    #[serde(default)]
+    pub format: RenderFormat,
+    /// Draw column letters and row numbers.
+    #[serde(default = "default_true")]
+    pub headers: bool,
+    #[serde(default = "default_true")]
+    pub gridlines: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RenderSheetResponse {
+    pub workbook_id: String,
+    pub sheet_name: String,
+    pub range: String,
+    pub format: RenderFormat,
+    pub output_path: String,
+    pub size_bytes: u64,
+    pub width_px: u32,
+    pub height_px: u32,
+    /// Things the renderer could not draw faithfully, such as conditional
+    /// formats that need a formula engine.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+pub async fn render_sheet(
+    state: Arc<AppState>,
+    params: RenderSheetParams,
+) -> Result<RenderSheetResponse> {
+    let range = params
+        .range
+        .as_deref()
+        .unwrap_or(DEFAULT_RENDER_RANGE)
+        .trim()
+        .to_string();
+    let area = parse_render_range(&range)?;
+    validate_screenshot_range(
+        area.max_row - area.min_row + 1,
+        area.max_col - area.min_col + 1,
+    )
+    .map_err(|e| anyhow!("{e} Split {range} into smaller ranges."))?;
+
+    let workbook = state.open_workbook(&params.workbook_or_fork_id).await?;
+    let options = RenderOptions {
+        headers: params.headers,
+        gridlines: params.gridlines,
+    };
+    let layout = workbook.with_sheet(&params.sheet_name, |sheet| {
+        render::layout_sheet(sheet, area, &range, options)
+    })?;
+    let (width_px, height_px) = (layout.width_px(), layout.height_px());
+
+    let bytes = match params.format {
+        RenderFormat::Png => {
+            let max_dim = std::env::var("SPREADSHEET_MCP_MAX_PNG_DIM_PX")
+                .ok()
+                .and_then(|v| v.parse::<u32>().ok())
+                .unwrap_or(DEFAULT_MAX_PNG_DIM_PX);
+            let max_area = std::env::var("SPREADSHEET_MCP_MAX_PNG_AREA_PX")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(DEFAULT_MAX_PNG_AREA_PX);
+            validate_png_dimensions(width_px, height_px, Some(max_dim), Some(max_area))
+                .map_err(|e| anyhow!("{e}. Try a smaller range than {range}."))?;
+            let layout = layout.clone();
+            tokio::task::spawn_blocking(move || render::render_png(&layout)).await??
+        }
+        RenderFormat::Svg => render::render_svg(&layout).into_bytes(),
+        RenderFormat::Html => render::render_html(&layout).into_bytes(),
+    };
+
+    let filename = format!(
+        "{}_{}_{}.{}",
+        workbook.slug,
+        params.sheet_name.replace(' ', "_"),
+        range.replace(':', "-"),
+        params.format.extension()
+    );
+    let output_dir = state.config().workspace_root.join("screenshots");
+    tokio::fs::create_dir_all(&output_dir).await?;
+    let output_path = output_dir.join(&filename);
+    tokio::fs::write(&output_path, &bytes).await?;
+
+    Ok(RenderSheetResponse {
+        workbook_id: params.workbook_or_fork_id.0,
+        sheet_name: params.sheet_name,
+        range,
+        format: params.format,
+        output_path: format!("file://{}", output_path.display()),
+        size_bytes: bytes.len() as u64,
+        width_px,
+        height_px,
+        warnings: layout.warnings,
+    })
+}
+
+/// `A1` or `A1:Z99`; whole rows, columns and names are not renderable.
+fn parse_render_range(range: &str) -> Result<Area> {
+    static CELL_RANGE: OnceLock<Regex> = OnceLock::new();
+    let pattern = CELL_RANGE.get_or_init(|| {
+        Regex::new(r"^\$?[A-Za-z]{1,3}\$?[0-9]+(:\$?[A-Za-z]{1,3}\$?[0-9]+)?$")
+            .expect("valid range pattern")
+    });
+    if !pattern.is_match(range) {
+        bail!(
+            "Invalid range format '{}'. Expected 'A1' or 'A1:Z99'",
+            range
+        );
+    }
+    Area::parse(range).ok_or_else(|| anyhow!("Invalid range '{}'", range))
+}
@@ -43,6 +43,14 @@ pub fn create_configured_validator() -> SchemaValidator {
         .register::<tools::ManifestStubParams>("get_manifest_stub")
         .register::<tools::CloseWorkbookParams>("close_workbook")
         .register::<tools::WorkbookSummaryParams>("workbook_summary")
+        .register::<tools::SheetCommentsParams>("sheet_comments")
+        .register::<tools::DataValidationsParams>("data_validations")
+        .register::<tools::FindValidationViolationsParams>("find_validation_violations")
+        .register::<tools::render::RenderSheetParams>("render_sheet")
+        .register::<tools::charts::ListChartsParams>("list_charts")
+        .register::<tools::charts::ChartDataParams>("chart_data")
+        .register::<tools::pivots::ListPivotTablesParams>("list_pivot_tables")
+        .register::<tools::pivots::PivotDataParams>("pivot_data")
         .build();
 
     validator
@@ -83,6 +91,14 @@ pub fn create_configured_validator_with_vba() -> SchemaValidator {
         .register::<tools::ManifestStubParams>("get_manifest_stub")
         .register::<tools::CloseWorkbookParams>("close_workbook")
         .register::<tools::WorkbookSummaryParams>("workbook_summary")
+        .register::<tools::SheetCommentsParams>("sheet_comments")
+        .register::<tools::DataValidationsParams>("data_validations")
+        .register::<tools::FindValidationViolationsParams>("find_validation_violations")
+        .register::<tools::render::RenderSheetParams>("render_sheet")
+        .register::<tools::charts::ListChartsParams>("list_charts")
+        .register::<tools::charts::ChartDataParams>("chart_data")
+        .register::<tools::pivots::ListPivotTablesParams>("list_pivot_tables")
+        .register::<tools::pivots::PivotDataParams>("pivot_data")
         // VBA tools
         .register::<tools::vba::VbaProjectSummaryParams>("vba_project_summary")
         .register::<tools::vba::VbaModuleSourceParams>("vba_module_source")
@@ -126,6 +142,14 @@ pub fn create_configured_validator_with_recalc() -> SchemaValidator {
         .register::<tools::ManifestStubParams>("get_manifest_stub")
         .register::<tools::CloseWorkbookParams>("close_workbook")
         .register::<tools::WorkbookSummaryParams>("workbook_summary")
+        .register::<tools::SheetCommentsParams>("sheet_comments")
+        .register::<tools::DataValidationsParams>("data_validations")
+        .register::<tools::FindValidationViolationsParams>("find_validation_violations")
+        .register::<tools::render::RenderSheetParams>("render_sheet")
+        .register::<tools::charts::ListChartsParams>("list_charts")
+        .register::<tools::charts::ChartDataParams>("chart_data")
+        .register::<tools::pivots::ListPivotTablesParams>("list_pivot_tables")
+        .register::<tools::pivots::PivotDataParams>("pivot_data")
         // Fork/recalc tools
         .register::<tools::fork::CreateForkParams>("create_fork")
         .register::<tools::fork::EditBatchParams>("edit_batch")
@@ -133,6 +157,11 @@ pub fn create_configured_validator_with_recalc() -> SchemaValidator {
         .register::<tools::fork::StyleBatchParams>("style_batch")
         .register::<tools::fork::ApplyFormulaPatternParams>("apply_formula_pattern")
         .register::<tools::fork::StructureBatchParams>("structure_batch")
+        .register::<tools::fork::CommentBatchParams>("comment_batch")
+        .register::<tools::fork::ValidationBatchParams>("validation_batch")
+        .register::<tools::fork::ConditionalFormatBatchParams>("conditional_format_batch")
+        .register::<tools::fork::ChartBatchParams>("chart_batch")
+        .register::<tools::fork::SheetSettingsBatchParams>("sheet_settings_batch")
         .register::<tools::fork::GetEditsParams>("get_edits")
         .register::<tools::fork::GetChangesetParams>("get_changeset")
         .register::<tools::fork::RecalculateParams>("recalculate")
@@ -186,7 +215,15 @@ pub fn create_full_validator() -> SchemaValidator {
         .register::<tools::WorkbookStyleSummaryParams>("workbook_style_summary")
         .register::<tools::ManifestStubParams>("get_manifest_stub")
         .register::<tools::CloseWorkbookParams>("close_workbook")
-        .register::<tools::WorkbookSummaryParams>("workbook_summary");
+        .register::<tools::WorkbookSummaryParams>("workbook_summary")
+        .register::<tools::SheetCommentsParams>("sheet_comments")
+        .register::<tools::DataValidationsParams>("data_validations")
+        .register::<tools::FindValidationViolationsParams>("find_validation_violations")
+        .register::<tools::render::RenderSheetParams>("render_sheet")
+        .register::<tools::charts::ListChartsParams>("list_charts")
+        .register::<tools::charts::ChartDataParams>("chart_data")
+        .register::<tools::pivots::ListPivotTablesParams>("list_pivot_tables")
+        .register::<tools::pivots::PivotDataParams>("pivot_data");
 
     // Conditionally add VBA tools
     builder = builder
@@ -203,6 +240,11 @@ pub fn create_full_validator() -> SchemaValidator {
             .register::<tools::fork::StyleBatchParams>("style_batch")
             .register::<tools::fork::ApplyFormulaPatternParams>("apply_formula_pattern")
             .register::<tools::fork::StructureBatchParams>("structure_batch")
+            .register::<tools::fork::CommentBatchParams>("comment_batch")
+            .register::<tools::fork::ValidationBatchParams>("validation_batch")
+            .register::<tools::fork::ConditionalFormatBatchParams>("conditional_format_batch")
+            .register::<tools::fork::ChartBatchParams>("chart_batch")
+            .register::<tools::fork::SheetSettingsBatchParams>("sheet_settings_batch")
             .register::<tools::fork::GetEditsParams>("get_edits")
             .register::<tools::fork::GetChangesetParams>("get_changeset")
             .register::<tools::fork::RecalculateParams>("recalculate")
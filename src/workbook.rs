@@ -3,11 +3,12 @@ use crate::analysis::{
     formula::{FormulaAtlas, FormulaGraph},
     style,
 };
+use crate::backends;
 use crate::caps::BackendCaps;
 use crate::config::ServerConfig;
 use crate::model::{
-    NamedItemKind, NamedRangeDescriptor, SheetClassification, SheetOverviewResponse, SheetSummary,
-    WorkbookDescription, WorkbookId, WorkbookListResponse,
+    DelimitedSourceInfo, NamedItemKind, NamedRangeDescriptor, SheetClassification,
+    SheetOverviewResponse, SheetSummary, WorkbookDescription, WorkbookId, WorkbookListResponse,
 };
 use crate::tools::filters::WorkbookFilter;
 use crate::utils::{
@@ -22,7 +23,6 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
-use umya_spreadsheet::reader::xlsx;
 use umya_spreadsheet::{DefinedName, Spreadsheet, Worksheet};
 
 const KV_MAX_WIDTH_FOR_DENSITY_CHECK: u32 = 6;
@@ -68,6 +68,7 @@ pub struct WorkbookContext {
     pub slug: String,
     pub path: PathBuf,
     pub caps: BackendCaps,
+    pub delimited: Option<DelimitedSourceInfo>,
     pub bytes: u64,
     pub last_modified: Option<DateTime<Utc>>,
     spreadsheet: Arc<RwLock<Spreadsheet>>,
@@ -149,8 +150,7 @@ impl WorkbookContext {
         let bytes = metadata.len();
         let last_modified = metadata.modified().ok().and_then(system_time_to_rfc3339);
         let id = WorkbookId(hash_path_metadata(path, &metadata));
-        let spreadsheet =
-            xlsx::read(path).with_context(|| format!("failed to parse workbook {:?}", path))?;
+        let loaded = backends::load(path)?;
         let short_id = make_short_workbook_id(&slug, id.as_str());
 
         Ok(Self {
@@ -158,10 +158,11 @@ impl WorkbookContext {
             short_id,
             slug,
             path: path.to_path_buf(),
-            caps: BackendCaps::xlsx(),
+            caps: backends::caps_for_path(path),
+            delimited: loaded.delimited,
             bytes,
             last_modified,
-            spreadsheet: Arc::new(RwLock::new(spreadsheet)),
+            spreadsheet: Arc::new(RwLock::new(loaded.spreadsheet)),
             sheet_cache: RwLock::new(HashMap::new()),
             formula_atlas: Arc::new(FormulaAtlas::default()),
         })
@@ -199,6 +200,8 @@ impl WorkbookContext {
                 .last_modified
                 .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)),
             caps: self.caps.clone(),
+            delimited: self.delimited.clone(),
+            protection: None,
         }
     }
 
@@ -367,6 +370,7 @@ impl WorkbookContext {
             },
             notable_features: entry.style_tags.clone(),
             notes: entry.region_notes(),
+            sheet_settings: None,
         })
     }
 
@@ -1613,7 +1617,7 @@ pub fn build_workbook_list(
             .unwrap_or_else(|| "workbook".to_string());
         let folder = derive_folder(config, single);
         let short_id = make_short_workbook_id(&slug, id.as_str());
-        let caps = BackendCaps::xlsx();
+        let caps = backends::caps_for_path(single);
 
         if filter.matches(&slug, folder.as_deref(), single) {
             let relative = single
@@ -1660,7 +1664,7 @@ pub fn build_workbook_list(
             .unwrap_or_else(|| "workbook".to_string());
         let folder = derive_folder(config, path);
         let short_id = make_short_workbook_id(&slug, id.as_str());
-        let caps = BackendCaps::xlsx();
+        let caps = backends::caps_for_path(path);
 
         if !filter.matches(&slug, folder.as_deref(), path) {
             continue;
@@ -0,0 +1,210 @@
+//! chart_batch creates, updates and deletes charts in a fork.
+
+#![cfg(feature = "recalc")]
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use spreadsheet_mcp::diff::Change;
+use spreadsheet_mcp::diff::charts::ChartDiff;
+use spreadsheet_mcp::model::WorkbookId;
+use spreadsheet_mcp::state::AppState;
+use spreadsheet_mcp::tools::charts::{ChartSummary, ListChartsParams, list_charts};
+use spreadsheet_mcp::tools::fork::{
+    ApplyStagedChangeParams, ChartBatchParams, ChartKind, ChartOp, GetChangesetParams, SeriesSpec,
+    apply_staged_change, chart_batch, get_changeset,
+};
+
+#[path = "./support/mod.rs"]
+mod support;
+
+async fn setup() -> Result<(support::TestWorkspace, Arc<AppState>, String)> {
+    support::recalc_fork("sales.xlsx", |book| {
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.get_cell_mut("A1").set_value("Month");
+        sheet.get_cell_mut("B1").set_value("North");
+        sheet.get_cell_mut("C1").set_value("South");
+        for (row, month, north, south) in [
+            (2, "Jan", 12.0, 7.0),
+            (3, "Feb", 18.5, 9.5),
+            (4, "Mar", 9.0, 14.0),
+        ] {
+            sheet.get_cell_mut((1, row)).set_value(month);
+            sheet.get_cell_mut((2, row)).set_value_number(north);
+            sheet.get_cell_mut((3, row)).set_value_number(south);
+        }
+    })
+    .await
+}
+
+async fn charts(state: &Arc<AppState>, fork_id: &str) -> Result<Vec<ChartSummary>> {
+    Ok(list_charts(
+        state.clone(),
+        ListChartsParams {
+            workbook_or_fork_id: WorkbookId(fork_id.to_string()),
+            sheet_name: None,
+        },
+    )
+    .await?
+    .charts)
+}
+
+fn create_from_source() -> ChartOp {
+    ChartOp::Create {
+        sheet_name: "Sheet1".to_string(),
+        chart_type: ChartKind::Column,
+        source: Some("A1:C4".to_string()),
+        series_in_rows: false,
+        series: None,
+        anchor: "E2".to_string(),
+        anchor_to: None,
+        title: Some("Units by region".to_string()),
+        x_axis_title: Some("Month".to_string()),
+        y_axis_title: None,
+    }
+}
+
+async fn run(
+    state: &Arc<AppState>,
+    fork_id: &str,
+    ops: Vec<ChartOp>,
+    mode: Option<&str>,
+) -> Result<spreadsheet_mcp::tools::fork::ChartBatchResponse> {
+    chart_batch(
+        state.clone(),
+        ChartBatchParams {
+            fork_id: fork_id.to_string(),
+            ops,
+            mode: mode.map(str::to_string),
+            label: None,
+        },
+    )
+    .await
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn chart_batch_creates_updates_and_deletes_charts() -> Result<()> {
+    let (_workspace, state, fork_id) = setup().await?;
+
+    let created = run(&state, &fork_id, vec![create_from_source()], None).await?;
+    assert_eq!(created.summary.counts.get("charts_created"), Some(&1));
+
+    let listed = charts(&state, &fork_id).await?;
+    assert_eq!(listed.len(), 1);
+    let chart = &listed[0];
+    assert_eq!(chart.chart_type, "column");
+    assert_eq!(chart.title.as_deref(), Some("Units by region"));
+    assert_eq!(chart.anchor.from.as_deref(), Some("E2"));
+    assert_eq!(chart.anchor.to.as_deref(), Some("M17"));
+    let names: Vec<_> = chart.series.iter().map(|s| s.name.clone()).collect();
+    assert_eq!(
+        names,
+        vec![Some("North".to_string()), Some("South".to_string())]
+    );
+    let values = chart.series[1].values.as_ref().expect("value reference");
+    assert_eq!(values.ranges, vec!["Sheet1!C2:C4".to_string()]);
+
+    let changeset = get_changeset(
+        state.clone(),
+        GetChangesetParams {
+            fork_id: fork_id.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert!(changeset.changes.iter().any(|c| matches!(
+        c,
+        Change::Chart(ChartDiff::ChartAdded { sheet, info, .. })
+            if sheet == "Sheet1" && info.series.len() == 2
+    )));
+
+    let updated = run(
+        &state,
+        &fork_id,
+        vec![ChartOp::Update {
+            sheet_name: "Sheet1".to_string(),
+            chart_index: 0,
+            title: Some("North only".to_string()),
+            x_axis_title: None,
+            y_axis_title: Some("Units".to_string()),
+            series: Some(vec![SeriesSpec {
+                values: "B2:B4".to_string(),
+                categories: Some("A2:A4".to_string()),
+                name_ref: None,
+                name: Some("North".to_string()),
+            }]),
+        }],
+        None,
+    )
+    .await?;
+    assert_eq!(updated.summary.counts.get("charts_updated"), Some(&1));
+    let chart = &charts(&state, &fork_id).await?[0];
+    assert_eq!(chart.title.as_deref(), Some("North only"));
+    assert_eq!(chart.series.len(), 1);
+    let axis_titles: Vec<_> = chart.axes.iter().filter_map(|a| a.title.clone()).collect();
+    assert_eq!(axis_titles, vec!["Month".to_string(), "Units".to_string()]);
+
+    // Create needs exactly one of source or series.
+    let err = run(
+        &state,
+        &fork_id,
+        vec![ChartOp::Create {
+            sheet_name: "Sheet1".to_string(),
+            chart_type: ChartKind::Line,
+            source: None,
+            series_in_rows: false,
+            series: None,
+            anchor: "E20".to_string(),
+            anchor_to: None,
+            title: None,
+            x_axis_title: None,
+            y_axis_title: None,
+        }],
+        None,
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("source"), "{err}");
+
+    let deleted = run(
+        &state,
+        &fork_id,
+        vec![ChartOp::Delete {
+            sheet_name: "Sheet1".to_string(),
+            chart_index: 0,
+        }],
+        None,
+    )
+    .await?;
+    assert_eq!(deleted.summary.counts.get("charts_deleted"), Some(&1));
+    assert!(charts(&state, &fork_id).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn chart_batch_preview_stages_create() -> Result<()> {
+    let (_workspace, state, fork_id) = setup().await?;
+
+    let preview = run(
+        &state,
+        &fork_id,
+        vec![create_from_source()],
+        Some("preview"),
+    )
+    .await?;
+    assert_eq!(preview.mode, "preview");
+    assert!(charts(&state, &fork_id).await?.is_empty());
+
+    apply_staged_change(
+        state.clone(),
+        ApplyStagedChangeParams {
+            fork_id: fork_id.clone(),
+            change_id: preview.change_id.expect("staged change"),
+        },
+    )
+    .await?;
+    assert_eq!(charts(&state, &fork_id).await?.len(), 1);
+
+    Ok(())
+}
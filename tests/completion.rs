@@ -0,0 +1,203 @@
+//! Completion candidates come from the workspace, are narrowed by arguments
+//! already filled in and are ranked by fuzzy prefix matching.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use spreadsheet_mcp::completion::{self, CompletionSource};
+use spreadsheet_mcp::tools::{ListWorkbooksParams, list_workbooks};
+
+#[path = "./support/mod.rs"]
+mod support;
+
+fn args(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn fuzzy_filter_ranks_prefixes_before_looser_matches() {
+    let candidates: Vec<String> = ["Summary", "Q1 Summary", "NetRevenue", "Sheet1", "Data_2024"]
+        .map(String::from)
+        .into();
+    let filter = |input: &str| completion::fuzzy_filter(input, candidates.clone());
+
+    assert_eq!(filter(""), candidates);
+    assert_eq!(filter("SUM"), vec!["Summary", "Q1 Summary"]);
+    assert_eq!(filter("q1s"), vec!["Q1 Summary"]);
+    assert_eq!(filter("rev"), vec!["NetRevenue"]);
+    assert_eq!(filter("dat2"), vec!["Data_2024"]);
+    assert_eq!(filter("qsmry"), vec!["Q1 Summary"]);
+    assert!(filter("zzz").is_empty());
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn completes_workbooks_sheets_regions_rules_and_artifacts() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("budget.xlsx", |book| {
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.set_name("Q1 Summary");
+        sheet.get_cell_mut("A1").set_value("Item");
+        sheet.get_cell_mut("B1").set_value("Amount");
+        for row in 2..=5 {
+            sheet
+                .get_cell_mut((1, row))
+                .set_value(format!("Item {row}"));
+            sheet
+                .get_cell_mut((2, row))
+                .set_value_number(row as f64 * 10.0);
+        }
+        book.new_sheet("Net Revenue").unwrap();
+    });
+    let receipts = workspace.root().join(".ggen/receipts");
+    std::fs::create_dir_all(&receipts)?;
+    std::fs::write(receipts.join("sync-001.json"), "{}")?;
+    let state = workspace.app_state();
+    let workbook_id = list_workbooks(
+        state.clone(),
+        ListWorkbooksParams {
+            slug_prefix: None,
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?
+    .workbooks[0]
+        .workbook_id
+        .0
+        .clone();
+
+    let workbooks =
+        completion::complete(&state, CompletionSource::WorkbookId, "", &args(&[])).await?;
+    assert_eq!(workbooks, vec![workbook_id.clone()]);
+
+    let sheet_context = args(&[("workbook_or_fork_id", &workbook_id)]);
+    let sheets =
+        completion::complete(&state, CompletionSource::SheetName, "rev", &sheet_context).await?;
+    assert_eq!(sheets, vec!["Net Revenue"]);
+    let no_workbook =
+        completion::complete(&state, CompletionSource::SheetName, "", &args(&[])).await?;
+    assert!(no_workbook.is_empty());
+
+    let regions = completion::complete(
+        &state,
+        CompletionSource::RegionId,
+        "",
+        &args(&[("workbook_id", &workbook_id), ("sheet_name", "Q1 Summary")]),
+    )
+    .await?;
+    assert!(!regions.is_empty());
+
+    // Rule names come from the crate's own ggen.toml.
+    let rules =
+        completion::complete(&state, CompletionSource::RuleName, "mcp-prom", &args(&[])).await?;
+    assert_eq!(rules, vec!["mcp-prompts"]);
+
+    let resource_sheets = completion::complete_resource(
+        &state,
+        "workbook://{workbook_or_fork_id}/sheet/{sheet_name}",
+        "sheet_name",
+        "q1",
+        &sheet_context,
+    )
+    .await?;
+    assert_eq!(resource_sheets, vec!["Q1 Summary"]);
+
+    let files =
+        completion::complete_resource(&state, "ggen://receipts/{file}", "file", "sync", &args(&[]))
+            .await?;
+    assert_eq!(files, vec!["sync-001.json"]);
+
+    for (template, argument, expected) in [
+        ("workbook://{id}/cells", "id", "unknown resource template"),
+        (
+            "fork://{fork_id}/changeset",
+            "sheet_name",
+            "has no variable",
+        ),
+    ] {
+        let err = completion::complete_resource(&state, template, argument, "", &args(&[]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains(expected), "{template}: {err}");
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "recalc")]
+#[tokio::test(flavor = "current_thread")]
+async fn completes_forks_and_their_checkpoints() -> Result<()> {
+    use spreadsheet_mcp::tools::fork::{
+        CheckpointForkParams, CreateForkParams, checkpoint_fork, create_fork,
+    };
+
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("model.xlsx", |book| {
+        book.get_sheet_mut(&0)
+            .unwrap()
+            .get_cell_mut("A1")
+            .set_value_number(1);
+    });
+    let state = support::app_state_with_config(workspace.config_with(|cfg| {
+        cfg.recalc_enabled = true;
+    }));
+    let workbook_id = list_workbooks(
+        state.clone(),
+        ListWorkbooksParams {
+            slug_prefix: None,
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?
+    .workbooks[0]
+        .workbook_id
+        .clone();
+    let fork_id = create_fork(
+        state.clone(),
+        CreateForkParams {
+            workbook_or_fork_id: workbook_id.clone(),
+        },
+    )
+    .await?
+    .fork_id;
+    let checkpoint_id = checkpoint_fork(
+        state.clone(),
+        CheckpointForkParams {
+            fork_id: fork_id.clone(),
+            label: Some("before edits".to_string()),
+        },
+    )
+    .await?
+    .checkpoint
+    .checkpoint_id;
+
+    let forks = completion::complete(&state, CompletionSource::ForkId, "", &args(&[])).await?;
+    assert_eq!(forks, vec![fork_id.clone()]);
+
+    let ids = completion::complete(&state, CompletionSource::WorkbookId, "", &args(&[])).await?;
+    assert_eq!(ids, vec![workbook_id.0.clone(), fork_id.clone()]);
+
+    let checkpoints = completion::complete(
+        &state,
+        CompletionSource::CheckpointId,
+        &checkpoint_id[..3],
+        &args(&[("fork_id", &fork_id)]),
+    )
+    .await?;
+    assert_eq!(checkpoints, vec![checkpoint_id]);
+
+    let unknown_fork = completion::complete(
+        &state,
+        CompletionSource::CheckpointId,
+        "",
+        &args(&[("fork_id", "missing")]),
+    )
+    .await?;
+    assert!(unknown_fork.is_empty());
+
+    Ok(())
+}
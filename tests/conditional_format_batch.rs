@@ -0,0 +1,228 @@
+//! conditional_format_batch authors conditional formats in a fork.
+
+#![cfg(feature = "recalc")]
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use spreadsheet_mcp::diff::Change;
+use spreadsheet_mcp::diff::sheet_parts::ConditionalFormatDiff;
+use spreadsheet_mcp::model::{ConditionalFormatSummary, StylePatch, WorkbookId};
+use spreadsheet_mcp::state::AppState;
+use spreadsheet_mcp::tools::fork::{
+    ApplyStagedChangeParams, ConditionalFormatBatchParams, ConditionalFormatOp,
+    ConditionalFormatRuleSpec, CreateForkParams, GetChangesetParams, StyleTarget,
+    apply_staged_change, conditional_format_batch, create_fork, get_changeset,
+};
+use spreadsheet_mcp::tools::{WorkbookStyleSummaryParams, workbook_style_summary};
+
+#[path = "./support/mod.rs"]
+mod support;
+
+async fn setup() -> Result<(support::TestWorkspace, Arc<AppState>, WorkbookId)> {
+    support::recalc_workbook("scores.xlsx", |book| {
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.get_cell_mut("A1").set_value("Name");
+        sheet.get_cell_mut("B1").set_value("Score");
+        for (row, name, score) in [(2, "Ada", 91), (3, "Bo", 42), (4, "Cy", 67), (5, "Bo", 55)] {
+            sheet.get_cell_mut((1, row)).set_value(name);
+            sheet.get_cell_mut((2, row)).set_value_number(score);
+        }
+    })
+    .await
+}
+
+async fn formats(state: &Arc<AppState>, id: &str) -> Result<Vec<ConditionalFormatSummary>> {
+    let resp = workbook_style_summary(
+        state.clone(),
+        WorkbookStyleSummaryParams {
+            workbook_or_fork_id: WorkbookId(id.to_string()),
+            max_styles: None,
+            max_conditional_formats: None,
+            max_cells_scan: None,
+        },
+    )
+    .await?;
+    Ok(resp.conditional_formats)
+}
+
+fn op(
+    range: &str,
+    rule: ConditionalFormatRuleSpec,
+    style: Option<StylePatch>,
+) -> ConditionalFormatOp {
+    ConditionalFormatOp {
+        sheet_name: "Sheet1".to_string(),
+        target: StyleTarget::Range {
+            range: range.to_string(),
+        },
+        rule,
+        style,
+        stop_if_true: false,
+    }
+}
+
+fn red_fill() -> StylePatch {
+    serde_json::from_value(serde_json::json!({
+        "fill": {"kind": "pattern", "pattern_type": "solid", "foreground_color": "FFFFC7CE"}
+    }))
+    .unwrap()
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn conditional_format_batch_adds_each_rule_kind() -> Result<()> {
+    let (_workspace, state, workbook_id) = setup().await?;
+    let fork = create_fork(
+        state.clone(),
+        CreateForkParams {
+            workbook_or_fork_id: workbook_id,
+        },
+    )
+    .await?;
+
+    let resp = conditional_format_batch(
+        state.clone(),
+        ConditionalFormatBatchParams {
+            fork_id: fork.fork_id.clone(),
+            ops: vec![
+                op(
+                    "B2:B5",
+                    ConditionalFormatRuleSpec::CellIs {
+                        operator: "less_than".to_string(),
+                        formula: "50".to_string(),
+                        formula2: None,
+                    },
+                    Some(red_fill()),
+                ),
+                op(
+                    "B2:B5",
+                    ConditionalFormatRuleSpec::ColorScale {
+                        colors: vec!["F8696B".to_string(), "63BE7B".to_string()],
+                        points: None,
+                    },
+                    None,
+                ),
+                op(
+                    "A2:A5",
+                    ConditionalFormatRuleSpec::Duplicate { unique: false },
+                    Some(red_fill()),
+                ),
+            ],
+            mode: None,
+            label: None,
+        },
+    )
+    .await?;
+    assert_eq!(resp.ops_applied, 3);
+    assert_eq!(resp.summary.counts.get("cell_is_rules_added"), Some(&1));
+    assert_eq!(resp.summary.counts.get("color_scales_added"), Some(&1));
+    assert_eq!(resp.summary.counts.get("duplicate_rules_added"), Some(&1));
+
+    let summary = formats(&state, &fork.fork_id).await?;
+    let mut kinds: Vec<(String, Vec<String>)> = summary
+        .into_iter()
+        .map(|cf| (cf.range, cf.rule_types))
+        .collect();
+    kinds.sort();
+    assert_eq!(
+        kinds,
+        vec![
+            ("A2:A5".to_string(), vec!["duplicateValues".to_string()]),
+            ("B2:B5".to_string(), vec!["cellIs".to_string()]),
+            ("B2:B5".to_string(), vec!["colorScale".to_string()]),
+        ]
+    );
+
+    let changeset = get_changeset(
+        state.clone(),
+        GetChangesetParams {
+            fork_id: fork.fork_id.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert!(changeset.changes.iter().any(|c| matches!(
+        c,
+        Change::ConditionalFormat(ConditionalFormatDiff::ConditionalFormatAdded { range, .. })
+            if range == "A2:A5"
+    )));
+
+    // Rules that format cells need a style; scales do not take one.
+    let err = conditional_format_batch(
+        state.clone(),
+        ConditionalFormatBatchParams {
+            fork_id: fork.fork_id.clone(),
+            ops: vec![op(
+                "B2:B5",
+                ConditionalFormatRuleSpec::TopN {
+                    rank: 1,
+                    bottom: false,
+                    percent: false,
+                },
+                None,
+            )],
+            mode: None,
+            label: None,
+        },
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("needs a style"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn conditional_format_batch_preview_stages_clear() -> Result<()> {
+    let (_workspace, state, workbook_id) = setup().await?;
+    let fork = create_fork(
+        state.clone(),
+        CreateForkParams {
+            workbook_or_fork_id: workbook_id,
+        },
+    )
+    .await?;
+    conditional_format_batch(
+        state.clone(),
+        ConditionalFormatBatchParams {
+            fork_id: fork.fork_id.clone(),
+            ops: vec![op(
+                "B2:B5",
+                ConditionalFormatRuleSpec::Expression {
+                    formula: "=$B2>60".to_string(),
+                },
+                Some(red_fill()),
+            )],
+            mode: None,
+            label: None,
+        },
+    )
+    .await?;
+
+    let preview = conditional_format_batch(
+        state.clone(),
+        ConditionalFormatBatchParams {
+            fork_id: fork.fork_id.clone(),
+            ops: vec![op("B2:B3", ConditionalFormatRuleSpec::Clear, None)],
+            mode: Some("preview".to_string()),
+            label: None,
+        },
+    )
+    .await?;
+    assert_eq!(preview.summary.counts.get("formats_cleared"), Some(&1));
+    assert_eq!(formats(&state, &fork.fork_id).await?[0].range, "B2:B5");
+
+    apply_staged_change(
+        state.clone(),
+        ApplyStagedChangeParams {
+            fork_id: fork.fork_id.clone(),
+            change_id: preview.change_id.expect("staged change"),
+        },
+    )
+    .await?;
+    let after = formats(&state, &fork.fork_id).await?;
+    assert_eq!(after.len(), 1);
+    assert_eq!(after[0].range, "B4:B5");
+
+    Ok(())
+}
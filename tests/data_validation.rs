@@ -0,0 +1,235 @@
+//! data_validations / find_validation_violations read rules; validation_batch edits them in a fork.
+
+#![cfg(feature = "recalc")]
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use spreadsheet_mcp::model::{ListSource, WorkbookId};
+use spreadsheet_mcp::state::AppState;
+use spreadsheet_mcp::tools::fork::{
+    ApplyStagedChangeParams, CreateForkParams, TransformTarget, ValidationAction,
+    ValidationBatchParams, ValidationOp, ValidationRuleSpec, apply_staged_change, create_fork,
+    validation_batch,
+};
+use spreadsheet_mcp::tools::{
+    DataValidationsParams, FindValidationViolationsParams, data_validations,
+    find_validation_violations,
+};
+use umya_spreadsheet::structs::{DataValidation, DataValidationValues, DataValidations};
+
+#[path = "./support/mod.rs"]
+mod support;
+
+async fn setup() -> Result<(support::TestWorkspace, Arc<AppState>, WorkbookId)> {
+    support::recalc_workbook("intake.xlsx", |book| {
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.get_cell_mut("A1").set_value("Status");
+        sheet.get_cell_mut("B1").set_value("Qty");
+        for (row, status, qty) in [(2, "Open", 3), (3, "closed", 12), (4, "Pending", 7)] {
+            sheet.get_cell_mut((1, row)).set_value(status);
+            sheet.get_cell_mut((2, row)).set_value_number(qty);
+        }
+        sheet.get_cell_mut("E1").set_value("Open");
+        sheet.get_cell_mut("E2").set_value("Closed");
+        sheet.get_cell_mut("E3").set_value("Pending");
+
+        let mut status = DataValidation::default();
+        status.set_type(DataValidationValues::List);
+        status.set_formula1("\"Open,Closed\"");
+        status.set_error_message("Pick a status");
+        status.get_sequence_of_references_mut().set_sqref("A2:A10");
+        let mut qty = DataValidation::default();
+        qty.set_type(DataValidationValues::Whole);
+        qty.set_formula1("1");
+        qty.set_formula2("10");
+        qty.get_sequence_of_references_mut().set_sqref("B2:B10");
+        let mut validations = DataValidations::default();
+        validations.get_data_validation_list_mut().push(status);
+        validations.get_data_validation_list_mut().push(qty);
+        sheet.set_data_validations(validations);
+    })
+    .await
+}
+
+async fn violations(state: &Arc<AppState>, id: &str) -> Result<Vec<(String, String)>> {
+    let resp = find_validation_violations(
+        state.clone(),
+        FindValidationViolationsParams {
+            workbook_or_fork_id: WorkbookId(id.to_string()),
+            sheet_name: None,
+            limit: 100,
+        },
+    )
+    .await?;
+    Ok(resp
+        .violations
+        .into_iter()
+        .map(|v| (v.address, v.reason))
+        .collect())
+}
+
+fn op(range: &str, action: ValidationAction) -> ValidationOp {
+    ValidationOp {
+        sheet_name: "Sheet1".to_string(),
+        target: TransformTarget::Range {
+            range: range.to_string(),
+        },
+        action,
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn reads_rules_and_flags_existing_violations() -> Result<()> {
+    let (_workspace, state, workbook_id) = setup().await?;
+
+    let resp = data_validations(
+        state.clone(),
+        DataValidationsParams {
+            workbook_or_fork_id: workbook_id.clone(),
+            sheet_name: None,
+        },
+    )
+    .await?;
+    assert_eq!(resp.rules.len(), 2);
+    let status = &resp.rules[0];
+    assert_eq!(status.range, "A2:A10");
+    assert_eq!(status.validation_type, "list");
+    assert_eq!(status.error_message.as_deref(), Some("Pick a status"));
+    assert!(matches!(
+        &status.list_source,
+        Some(ListSource::Inline { values }) if values == &["Open", "Closed"]
+    ));
+    let qty = &resp.rules[1];
+    assert_eq!(qty.validation_type, "whole");
+    assert_eq!(qty.operator.as_deref(), Some("between"));
+    assert_eq!(qty.formula2.as_deref(), Some("10"));
+
+    // Lists match case-insensitively, so "closed" passes.
+    assert_eq!(
+        violations(&state, &workbook_id.0).await?,
+        vec![
+            ("A4".to_string(), "value is not in the list".to_string()),
+            (
+                "B3".to_string(),
+                "value must be between 1 and 10".to_string()
+            ),
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn validation_batch_adds_modifies_and_removes_rules() -> Result<()> {
+    let (_workspace, state, workbook_id) = setup().await?;
+    let fork = create_fork(
+        state.clone(),
+        CreateForkParams {
+            workbook_or_fork_id: workbook_id,
+        },
+    )
+    .await?;
+
+    // Re-point the status column at a range-backed list; the old rule is replaced there.
+    let resp = validation_batch(
+        state.clone(),
+        ValidationBatchParams {
+            fork_id: fork.fork_id.clone(),
+            ops: vec![
+                op(
+                    "A2:A10",
+                    ValidationAction::Add {
+                        rule: ValidationRuleSpec {
+                            validation_type: Some("list".to_string()),
+                            formula1: Some("=$E$1:$E$3".to_string()),
+                            ..Default::default()
+                        },
+                    },
+                ),
+                op(
+                    "B5",
+                    ValidationAction::Modify {
+                        rule: ValidationRuleSpec {
+                            formula2: Some("5".to_string()),
+                            error_style: Some("warning".to_string()),
+                            ..Default::default()
+                        },
+                    },
+                ),
+            ],
+            mode: None,
+            label: None,
+        },
+    )
+    .await?;
+    assert_eq!(resp.summary.counts.get("rules_added"), Some(&1));
+    assert_eq!(resp.summary.counts.get("rules_replaced"), Some(&1));
+    assert_eq!(resp.summary.counts.get("rules_modified"), Some(&1));
+    assert_eq!(resp.summary.counts.get("existing_violations"), Some(&2));
+
+    let rules = data_validations(
+        state.clone(),
+        DataValidationsParams {
+            workbook_or_fork_id: WorkbookId(fork.fork_id.clone()),
+            sheet_name: Some("Sheet1".to_string()),
+        },
+    )
+    .await?
+    .rules;
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0].formula2.as_deref(), Some("5"));
+    assert_eq!(rules[0].error_style, "warning");
+    assert!(matches!(
+        &rules[1].list_source,
+        Some(ListSource::Range { reference, resolved: true, values, .. })
+            if reference == "$E$1:$E$3" && values == &["Open", "Closed", "Pending"]
+    ));
+    assert_eq!(
+        violations(&state, &fork.fork_id).await?,
+        vec![
+            (
+                "B3".to_string(),
+                "value must be between 1 and 5".to_string()
+            ),
+            (
+                "B4".to_string(),
+                "value must be between 1 and 5".to_string()
+            ),
+        ]
+    );
+
+    // Removing part of a rule shrinks it; staged until applied.
+    let preview = validation_batch(
+        state.clone(),
+        ValidationBatchParams {
+            fork_id: fork.fork_id.clone(),
+            ops: vec![op("B2:B4", ValidationAction::Remove)],
+            mode: Some("preview".to_string()),
+            label: None,
+        },
+    )
+    .await?;
+    assert_eq!(violations(&state, &fork.fork_id).await?.len(), 2);
+    apply_staged_change(
+        state.clone(),
+        ApplyStagedChangeParams {
+            fork_id: fork.fork_id.clone(),
+            change_id: preview.change_id.expect("staged change"),
+        },
+    )
+    .await?;
+
+    let rules = data_validations(
+        state.clone(),
+        DataValidationsParams {
+            workbook_or_fork_id: WorkbookId(fork.fork_id.clone()),
+            sheet_name: None,
+        },
+    )
+    .await?
+    .rules;
+    assert_eq!(rules[0].range, "B5:B10");
+    assert!(violations(&state, &fork.fork_id).await?.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,201 @@
+//! CSV/TSV files as workbooks: discovery, forking through a converted xlsx
+//! base, and exporting a fork back to delimited text.
+
+use std::fs;
+use std::sync::Arc;
+
+use anyhow::Result;
+use spreadsheet_mcp::caps::BackendKind;
+use spreadsheet_mcp::model::WorkbookId;
+use spreadsheet_mcp::state::AppState;
+use spreadsheet_mcp::tools::{
+    DescribeWorkbookParams, ListWorkbooksParams, describe_workbook, list_workbooks,
+};
+
+#[path = "./support/mod.rs"]
+mod support;
+
+async fn only_workbook(state: Arc<AppState>) -> Result<(WorkbookId, String)> {
+    let list = list_workbooks(
+        state,
+        ListWorkbooksParams {
+            slug_prefix: None,
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?;
+    assert_eq!(list.workbooks.len(), 1, "expected exactly 1 workbook");
+    let descriptor = &list.workbooks[0];
+    assert!(matches!(descriptor.caps.backend, BackendKind::Delimited));
+    Ok((descriptor.workbook_id.clone(), descriptor.path.clone()))
+}
+
+#[tokio::test]
+async fn list_workbooks_reports_csv_files() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    fs::write(
+        workspace.path("data.csv"),
+        "name,amount\nalpha,10\nbeta,20\n",
+    )?;
+    let state = workspace.app_state();
+
+    let (workbook_id, path) = only_workbook(state.clone()).await?;
+    assert!(path.ends_with("data.csv"), "{path}");
+
+    let description = describe_workbook(
+        state,
+        DescribeWorkbookParams {
+            workbook_or_fork_id: workbook_id,
+        },
+    )
+    .await?;
+    let delimited = description.delimited.expect("delimited source info");
+    assert_eq!(delimited.delimiter, ",");
+    assert_eq!(delimited.header_row, Some(1));
+
+    Ok(())
+}
+
+#[cfg(feature = "recalc")]
+mod forks {
+    use super::*;
+    use spreadsheet_mcp::tools::fork::{
+        CellEdit, CreateForkParams, EditBatchParams, SaveForkParams, create_fork, edit_batch,
+        save_fork,
+    };
+
+    fn recalc_state(workspace: &support::TestWorkspace) -> Arc<AppState> {
+        support::app_state_with_config(workspace.config_with(|cfg| {
+            cfg.recalc_enabled = true;
+        }))
+    }
+
+    /// Forks `file` (a one-sheet delimited workbook), sets B2 to 15 and
+    /// saves the fork to `target`, returning the exported text.
+    async fn fork_edit_and_save(
+        workspace: &support::TestWorkspace,
+        file: &str,
+        sheet_name: &str,
+        target: &str,
+    ) -> Result<String> {
+        let state = recalc_state(workspace);
+        let (workbook_id, _) = only_workbook(state.clone()).await?;
+
+        let fork = create_fork(
+            state.clone(),
+            CreateForkParams {
+                workbook_or_fork_id: workbook_id,
+            },
+        )
+        .await?;
+
+        let registry = state.fork_registry().expect("fork registry");
+        let converted = registry
+            .get_fork(&fork.fork_id)?
+            .converted_base
+            .clone()
+            .expect("non-xlsx base is converted");
+        assert_eq!(
+            converted.file_name().and_then(|name| name.to_str()),
+            Some(format!("{}.base.xlsx", fork.fork_id).as_str()),
+            "{file}"
+        );
+        assert!(converted.exists());
+
+        edit_batch(
+            state.clone(),
+            EditBatchParams {
+                fork_id: fork.fork_id.clone(),
+                sheet_name: sheet_name.to_string(),
+                edits: vec![CellEdit {
+                    address: "B2".to_string(),
+                    value: "15".to_string(),
+                    is_formula: false,
+                }],
+            },
+        )
+        .await?;
+
+        let saved = save_fork(
+            state,
+            SaveForkParams {
+                fork_id: fork.fork_id,
+                target_path: Some(target.to_string()),
+                sheet_name: None,
+                drop_fork: true,
+            },
+        )
+        .await?;
+        assert!(saved.saved_to.ends_with(target), "{}", saved.saved_to);
+
+        Ok(fs::read_to_string(workspace.path(target))?)
+    }
+
+    #[tokio::test]
+    async fn csv_fork_round_trips_to_csv() -> Result<()> {
+        let workspace = support::TestWorkspace::new();
+        fs::write(
+            workspace.path("data.csv"),
+            "name,amount\nalpha,10\nbeta,20\n",
+        )?;
+
+        let text = fork_edit_and_save(&workspace, "data.csv", "data", "out.csv").await?;
+        assert_eq!(text, "name,amount\nalpha,15\nbeta,20\n");
+
+        // The original is untouched.
+        assert_eq!(
+            fs::read_to_string(workspace.path("data.csv"))?,
+            "name,amount\nalpha,10\nbeta,20\n"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tsv_fork_keeps_tab_delimiter() -> Result<()> {
+        let workspace = support::TestWorkspace::new();
+        fs::write(
+            workspace.path("data.tsv"),
+            "name\tamount\nalpha, inc\t10\nbeta\t20\n",
+        )?;
+
+        let text = fork_edit_and_save(&workspace, "data.tsv", "data", "out.tsv").await?;
+        assert_eq!(text, "name\tamount\nalpha, inc\t15\nbeta\t20\n");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sheet_name_is_rejected_for_workbook_targets() -> Result<()> {
+        let workspace = support::TestWorkspace::new();
+        fs::write(workspace.path("data.csv"), "name,amount\nalpha,10\n")?;
+        let state = recalc_state(&workspace);
+        let (workbook_id, _) = only_workbook(state.clone()).await?;
+        let fork = create_fork(
+            state.clone(),
+            CreateForkParams {
+                workbook_or_fork_id: workbook_id,
+            },
+        )
+        .await?;
+
+        for target in ["out.xlsx", "out.ods"] {
+            let err = save_fork(
+                state.clone(),
+                SaveForkParams {
+                    fork_id: fork.fork_id.clone(),
+                    target_path: Some(target.to_string()),
+                    sheet_name: Some("data".to_string()),
+                    drop_fork: false,
+                },
+            )
+            .await
+            .unwrap_err();
+            assert!(err.to_string().contains("sheet_name"), "{target}: {err}");
+            assert!(!workspace.path(target).exists(), "{target}");
+        }
+
+        Ok(())
+    }
+}
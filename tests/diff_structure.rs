@@ -1,10 +1,16 @@
 #![cfg(feature = "recalc")]
 
-use spreadsheet_mcp::diff::{Change, calculate_changeset, names::NameDiff, tables::TableDiff};
+use spreadsheet_mcp::diff::{
+    Change, calculate_changeset, comments::CommentDiff, names::NameDiff, sheet_parts::MergeDiff,
+    sheets::SheetDiff, styles::StyleDiff, tables::TableDiff,
+};
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::PathBuf;
-use umya_spreadsheet::{Spreadsheet, structs::Table};
+use umya_spreadsheet::{
+    Spreadsheet,
+    structs::{Comment, Table},
+};
 use zip::write::FileOptions;
 use zip::{ZipArchive, ZipWriter};
 
@@ -204,3 +210,78 @@ fn test_table_addition() {
     let added = diffs.iter().find(|d| matches!(d, Change::Table(TableDiff::TableAdded { display_name, .. }) if display_name == "NewTable"));
     assert!(added.is_some());
 }
+
+#[test]
+fn test_sheet_level_changes() {
+    let scenario = DiffScenario::new();
+
+    scenario.setup(
+        |book| {
+            let sheet = book.get_sheet_mut(&0).unwrap();
+            sheet.set_name("Sheet1");
+            sheet.get_cell_mut("A1").set_value("Header");
+            sheet.get_cell_mut("A2").set_value_number(1);
+            book.new_sheet("Notes").unwrap();
+        },
+        |book| {
+            let sheet = book.get_sheet_mut(&0).unwrap();
+            sheet.set_name("Sheet1");
+            sheet.get_cell_mut("A1").set_value("Header");
+            sheet.get_cell_mut("A2").set_value_number(1);
+            sheet.get_style_mut("A1").get_font_mut().set_bold(true);
+            sheet.add_merge_cells("A1:C1");
+            let mut comment = Comment::default();
+            comment.new_comment("A2");
+            comment.set_author("Reviewer");
+            comment.set_text_string("Check this");
+            sheet.add_comments(comment);
+            let notes = book.new_sheet("Notes").unwrap();
+            notes.set_sheet_state("hidden".to_string());
+        },
+    );
+
+    let diffs = scenario.run_diff(None);
+
+    assert!(diffs.iter().any(|d| matches!(
+        d,
+        Change::Merge(MergeDiff::MergeAdded { sheet, range }) if sheet == "Sheet1" && range == "A1:C1"
+    )));
+    assert!(diffs.iter().any(|d| matches!(
+        d,
+        Change::Comment(CommentDiff::CommentAdded { address, text, .. })
+            if address == "A2" && text == "Check this"
+    )));
+    assert!(diffs.iter().any(|d| matches!(
+        d,
+        Change::Sheet(SheetDiff::SheetVisibilityChanged { sheet, new_state, .. })
+            if sheet == "Notes" && new_state == "hidden"
+    )));
+    let style = diffs
+        .iter()
+        .find_map(|d| match d {
+            Change::Style(StyleDiff::StyleChanged {
+                ranges, new_style, ..
+            }) => Some((ranges, new_style)),
+            _ => None,
+        })
+        .expect("style change");
+    assert_eq!(style.0, &vec!["A1".to_string()]);
+    assert_eq!(
+        style.1.font.as_ref().and_then(|f| f.bold),
+        Some(true),
+        "bold font reported"
+    );
+
+    // Unchanged plain values are not reported as style changes.
+    assert!(!diffs.iter().any(|d| matches!(
+        d,
+        Change::Style(StyleDiff::StyleChanged { ranges, .. }) if ranges.iter().any(|r| r.contains("A2"))
+    )));
+
+    let notes_only = scenario.run_diff(Some("Notes"));
+    assert!(
+        notes_only
+            .iter()
+            .all(|d| matches!(d, Change::Sheet(SheetDiff::SheetVisibilityChanged { .. })))
+    );
+}
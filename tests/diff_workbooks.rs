@@ -0,0 +1,252 @@
+//! Integration tests for diff_workbooks: address and key-aligned comparison of two workbooks.
+//! Plain workbooks are compared without recalc; only fork and checkpoint sides need it.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use spreadsheet_mcp::diff::Change;
+use spreadsheet_mcp::diff::keyed::RowDiff;
+use spreadsheet_mcp::model::WorkbookId;
+use spreadsheet_mcp::state::AppState;
+use spreadsheet_mcp::tools::diff::{DiffWorkbooksParams, diff_workbooks};
+use spreadsheet_mcp::tools::{ListWorkbooksParams, list_workbooks};
+
+#[path = "./support/mod.rs"]
+mod support;
+
+fn write_orders(book: &mut umya_spreadsheet::Spreadsheet, rows: &[(&str, &str, f64)]) {
+    let sheet = book.get_sheet_mut(&0).unwrap();
+    sheet.set_name("Orders");
+    sheet.get_cell_mut("A1").set_value("Order ID");
+    sheet.get_cell_mut("B1").set_value("Customer");
+    sheet.get_cell_mut("C1").set_value("Amount");
+    for (idx, (id, customer, amount)) in rows.iter().enumerate() {
+        let row = idx as u32 + 2;
+        sheet.get_cell_mut((1, row)).set_value(*id);
+        sheet.get_cell_mut((2, row)).set_value(*customer);
+        sheet.get_cell_mut((3, row)).set_value_number(*amount);
+    }
+}
+
+async fn workbook_id(state: Arc<AppState>, slug: &str) -> Result<WorkbookId> {
+    let list = list_workbooks(
+        state,
+        ListWorkbooksParams {
+            slug_prefix: Some(slug.to_string()),
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?;
+    Ok(list.workbooks[0].workbook_id.clone())
+}
+
+fn params(base_id: WorkbookId, target_id: WorkbookId) -> DiffWorkbooksParams {
+    DiffWorkbooksParams {
+        base_id,
+        target_id,
+        base_checkpoint_id: None,
+        target_checkpoint_id: None,
+        sheet_name: Some("Orders".to_string()),
+        key_column: None,
+        header_row: None,
+        limit: 200,
+        offset: 0,
+        summary_only: false,
+        include_types: None,
+        exclude_types: None,
+        include_subtypes: None,
+        exclude_subtypes: None,
+    }
+}
+
+#[tokio::test]
+async fn key_column_aligns_resorted_rows() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("before.xlsx", |book| {
+        write_orders(
+            book,
+            &[
+                ("O-1", "Acme", 100.0),
+                ("O-2", "Beta", 250.0),
+                ("O-3", "Core", 75.0),
+            ],
+        );
+    });
+    // Sorted by amount descending, O-1 amended, O-3 removed, O-4 added.
+    workspace.create_workbook("after.xlsx", |book| {
+        write_orders(
+            book,
+            &[
+                ("O-2", "Beta", 250.0),
+                ("O-1", "Acme", 120.0),
+                ("O-4", "Delta", 50.0),
+            ],
+        );
+    });
+
+    let state = workspace.app_state();
+    let before = workbook_id(state.clone(), "before").await?;
+    let after = workbook_id(state.clone(), "after").await?;
+
+    let by_address = diff_workbooks(state.clone(), params(before.clone(), after.clone())).await?;
+    assert!(by_address.summary.total_changes > 3);
+    assert!(by_address.summary.counts_by_kind.contains_key("cell"));
+
+    let by_key = diff_workbooks(
+        state.clone(),
+        DiffWorkbooksParams {
+            key_column: Some("order id".to_string()),
+            ..params(before.clone(), after.clone())
+        },
+    )
+    .await?;
+    assert_eq!(by_key.summary.total_changes, 3);
+    assert!(!by_key.summary.counts_by_kind.contains_key("cell"));
+    assert_eq!(by_key.summary.counts_by_type.get("row_modified"), Some(&1));
+    assert_eq!(by_key.summary.counts_by_type.get("row_added"), Some(&1));
+    assert_eq!(by_key.summary.counts_by_type.get("row_deleted"), Some(&1));
+
+    let modified = by_key
+        .changes
+        .iter()
+        .find_map(|change| match change {
+            Change::Row(row) => match &row.diff {
+                RowDiff::RowModified {
+                    key,
+                    old_row,
+                    new_row,
+                    cells,
+                    ..
+                } => Some((key, *old_row, *new_row, cells)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .expect("row_modified");
+    assert_eq!(modified.0, "O-1");
+    assert_eq!((modified.1, modified.2), (2, 3));
+    assert_eq!(modified.3.len(), 1);
+    assert_eq!(modified.3[0].header, "Amount");
+    assert_eq!(modified.3[0].new_value.as_deref(), Some("120"));
+
+    let filtered = diff_workbooks(
+        state,
+        DiffWorkbooksParams {
+            key_column: Some("A".to_string()),
+            include_types: Some(vec!["row_added".to_string()]),
+            ..params(before, after)
+        },
+    )
+    .await?;
+    assert_eq!(filtered.summary.total_changes, 1);
+    assert_eq!(filtered.changes.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn missing_key_column_is_an_error_for_a_named_sheet() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("left.xlsx", |book| {
+        write_orders(book, &[("O-1", "Acme", 100.0)]);
+    });
+    workspace.create_workbook("right.xlsx", |book| {
+        write_orders(book, &[("O-1", "Acme", 90.0)]);
+    });
+
+    let state = workspace.app_state();
+    let left = workbook_id(state.clone(), "left").await?;
+    let right = workbook_id(state.clone(), "right").await?;
+
+    let err = diff_workbooks(
+        state,
+        DiffWorkbooksParams {
+            key_column: Some("SKU".to_string()),
+            ..params(left, right)
+        },
+    )
+    .await
+    .expect_err("unknown key column");
+    assert!(err.to_string().contains("SKU"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn structural_change_types_are_filterable() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("plain.xlsx", |book| {
+        write_orders(book, &[("O-1", "Acme", 100.0)]);
+    });
+    workspace.create_workbook("styled.xlsx", |book| {
+        write_orders(book, &[("O-1", "Acme", 100.0)]);
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.add_merge_cells("A1:B1");
+        sheet.get_style_mut("C2").get_font_mut().set_bold(true);
+    });
+
+    let state = workspace.app_state();
+    let plain = workbook_id(state.clone(), "plain").await?;
+    let styled = workbook_id(state.clone(), "styled").await?;
+
+    let all = diff_workbooks(state.clone(), params(plain.clone(), styled.clone())).await?;
+    assert_eq!(all.summary.counts_by_kind.get("merge"), Some(&1));
+    assert_eq!(all.summary.counts_by_kind.get("style"), Some(&1));
+
+    let merges_only = diff_workbooks(
+        state.clone(),
+        DiffWorkbooksParams {
+            include_types: Some(vec!["merge_added".to_string()]),
+            ..params(plain.clone(), styled.clone())
+        },
+    )
+    .await?;
+    assert_eq!(merges_only.summary.total_changes, 1);
+
+    let without_styles = diff_workbooks(
+        state,
+        DiffWorkbooksParams {
+            exclude_types: Some(vec!["style_changed".to_string()]),
+            ..params(plain, styled)
+        },
+    )
+    .await?;
+    assert!(!without_styles.summary.counts_by_kind.contains_key("style"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fork_sides_without_recalc_are_reported() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("left.xlsx", |book| {
+        write_orders(book, &[("O-1", "Acme", 100.0)]);
+    });
+    let state = workspace.app_state();
+    let left = workbook_id(state.clone(), "left").await?;
+
+    let err = diff_workbooks(
+        state.clone(),
+        params(left.clone(), WorkbookId("fork-0123456789ab".to_string())),
+    )
+    .await
+    .expect_err("forks are unavailable without recalc");
+    assert!(
+        format!("{err:#}").contains("looks like a fork id"),
+        "{err:#}"
+    );
+
+    let err = diff_workbooks(
+        state,
+        DiffWorkbooksParams {
+            base_checkpoint_id: Some("cp-1".to_string()),
+            ..params(left.clone(), left)
+        },
+    )
+    .await
+    .expect_err("checkpoints are unavailable without recalc");
+    assert!(format!("{err:#}").contains("recalc"), "{err:#}");
+
+    Ok(())
+}
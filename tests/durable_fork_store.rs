@@ -0,0 +1,151 @@
+//! Forks kept in a durable store survive a registry restart.
+#![cfg(feature = "recalc")]
+
+use anyhow::Result;
+use chrono::Utc;
+use spreadsheet_mcp::fork::{
+    ChangeSummary, EditOp, ForkConfig, ForkRegistry, StagedChange, StagedOp,
+};
+use std::path::Path;
+use std::time::Duration;
+
+#[path = "./support/mod.rs"]
+mod support;
+
+fn durable_config(store: &Path, ttl: Duration) -> ForkConfig {
+    ForkConfig {
+        ttl,
+        max_forks: 10,
+        fork_dir: store.to_path_buf(),
+        durable: true,
+    }
+}
+
+#[tokio::test]
+async fn registry_rebuilds_forks_from_store() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    let base_path = workspace.create_workbook("source.xlsx", |_| {});
+    let store = tempfile::tempdir()?;
+
+    let registry = ForkRegistry::new(durable_config(store.path(), Duration::ZERO))?;
+    let fork_id = registry.create_fork(&base_path, workspace.root())?;
+    registry.with_fork_mut(&fork_id, |ctx| {
+        ctx.edits.push(EditOp {
+            timestamp: Utc::now(),
+            sheet: "Sheet1".to_string(),
+            address: "A1".to_string(),
+            value: "10".to_string(),
+            is_formula: false,
+        });
+        Ok(())
+    })?;
+    let checkpoint = registry.create_checkpoint(&fork_id, Some("first".to_string()))?;
+    registry.add_staged_change(
+        &fork_id,
+        StagedChange {
+            change_id: "chg1".to_string(),
+            created_at: Utc::now(),
+            label: Some("pending".to_string()),
+            ops: vec![StagedOp {
+                kind: "edit_batch".to_string(),
+                payload: serde_json::json!({"sheet_name":"Sheet1","edits":[]}),
+            }],
+            summary: ChangeSummary::default(),
+            fork_path_snapshot: Some(store.path().join("gone.xlsx")),
+        },
+    )?;
+    let before = registry.get_fork(&fork_id)?;
+    drop(registry);
+
+    assert!(before.work_path.exists(), "work file survives shutdown");
+
+    let registry = ForkRegistry::new(durable_config(store.path(), Duration::ZERO))?;
+    let after = registry.get_fork(&fork_id)?;
+    assert_eq!(after.base_path, base_path);
+    assert_eq!(after.base_hash(), before.base_hash());
+    assert_eq!(after.version(), before.version());
+    assert_eq!(after.edits.len(), 1);
+    assert_eq!(after.edits[0].value, "10");
+    assert_eq!(after.checkpoints.len(), 1);
+    assert_eq!(after.checkpoints[0].checkpoint_id, checkpoint.checkpoint_id);
+    assert_eq!(after.staged_changes.len(), 1);
+    assert_eq!(after.staged_changes[0].ops[0].kind, "edit_batch");
+    assert!(after.staged_changes[0].fork_path_snapshot.is_none());
+
+    registry.restore_checkpoint(&fork_id, &checkpoint.checkpoint_id)?;
+    registry.discard_fork(&fork_id)?;
+    assert!(!store.path().join(format!("{fork_id}.json")).exists());
+
+    let registry = ForkRegistry::new(durable_config(store.path(), Duration::ZERO))?;
+    assert!(registry.list_forks().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn expired_forks_are_evicted_at_startup() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    let base_path = workspace.create_workbook("source.xlsx", |_| {});
+    let store = tempfile::tempdir()?;
+    let ttl = Duration::from_secs(3600);
+
+    let registry = ForkRegistry::new(durable_config(store.path(), ttl))?;
+    let fork_id = registry.create_fork(&base_path, workspace.root())?;
+    let work_path = registry.get_fork(&fork_id)?.work_path.clone();
+    drop(registry);
+
+    let manifest_path = store.path().join(format!("{fork_id}.json"));
+    let mut manifest: serde_json::Value = serde_json::from_slice(&std::fs::read(&manifest_path)?)?;
+    manifest["last_accessed"] = serde_json::json!("2000-01-01T00:00:00Z");
+    std::fs::write(&manifest_path, serde_json::to_vec(&manifest)?)?;
+
+    let registry = ForkRegistry::new(durable_config(store.path(), ttl))?;
+    assert!(registry.get_fork(&fork_id).is_err());
+    assert!(!work_path.exists());
+    assert!(!manifest_path.exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn reads_refresh_the_persisted_access_time() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    let base_path = workspace.create_workbook("source.xlsx", |_| {});
+    let store = tempfile::tempdir()?;
+    let ttl = Duration::from_secs(3600);
+
+    let registry = ForkRegistry::new(durable_config(store.path(), Duration::ZERO))?;
+    let fork_id = registry.create_fork(&base_path, workspace.root())?;
+    drop(registry);
+
+    // Last written long ago, then only read: the read must reach the manifest.
+    let manifest_path = store.path().join(format!("{fork_id}.json"));
+    let mut manifest: serde_json::Value = serde_json::from_slice(&std::fs::read(&manifest_path)?)?;
+    manifest["last_accessed"] = serde_json::json!("2000-01-01T00:00:00Z");
+    std::fs::write(&manifest_path, serde_json::to_vec(&manifest)?)?;
+
+    let registry = ForkRegistry::new(durable_config(store.path(), Duration::ZERO))?;
+    registry.get_fork(&fork_id)?;
+    drop(registry);
+
+    let manifest: serde_json::Value = serde_json::from_slice(&std::fs::read(&manifest_path)?)?;
+    let last_accessed: chrono::DateTime<Utc> =
+        serde_json::from_value(manifest["last_accessed"].clone())?;
+    assert!(Utc::now() - last_accessed < chrono::Duration::minutes(5));
+
+    let registry = ForkRegistry::new(durable_config(store.path(), ttl))?;
+    assert!(registry.get_fork(&fork_id).is_ok());
+    Ok(())
+}
+
+#[tokio::test]
+async fn staged_snapshots_live_in_the_store() -> Result<()> {
+    let store = tempfile::tempdir()?;
+    let registry = ForkRegistry::new(durable_config(store.path(), Duration::ZERO))?;
+
+    let path = registry.staged_snapshot_path("fork-abc", "chg-1")?;
+    assert_eq!(
+        path,
+        store.path().join("staged").join("fork-abc_chg-1.xlsx")
+    );
+    assert!(path.parent().is_some_and(Path::is_dir));
+    Ok(())
+}
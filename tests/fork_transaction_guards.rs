@@ -109,6 +109,7 @@ fn test_fork_creation_rollback_on_invalid_base() -> Result<()> {
         ttl: std::time::Duration::from_secs(3600),
         max_forks: 10,
         fork_dir: fork_dir.clone(),
+        durable: false,
     };
 
     let registry = ForkRegistry::new(config)?;
@@ -146,6 +147,7 @@ fn test_fork_creation_rollback_on_invalid_extension() -> Result<()> {
         ttl: std::time::Duration::from_secs(3600),
         max_forks: 10,
         fork_dir: fork_dir.clone(),
+        durable: false,
     };
 
     let registry = ForkRegistry::new(config)?;
@@ -464,6 +466,7 @@ fn test_fork_context_drop_cleanup() -> Result<()> {
         ttl: std::time::Duration::from_secs(3600),
         max_forks: 10,
         fork_dir: fork_dir.clone(),
+        durable: false,
     };
 
     let registry = ForkRegistry::new(config)?;
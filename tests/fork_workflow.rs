@@ -521,6 +521,7 @@ async fn test_save_fork_overwrites_original() -> Result<()> {
         SaveForkParams {
             fork_id: fork.fork_id.clone(),
             target_path: None, // Overwrite original
+            sheet_name: None,
             drop_fork: true,
         },
     )
@@ -580,6 +581,7 @@ async fn test_save_fork_to_new_path() -> Result<()> {
         SaveForkParams {
             fork_id: fork.fork_id.clone(),
             target_path: Some("copy.xlsx".to_string()),
+            sheet_name: None,
             drop_fork: true,
         },
     )
@@ -686,6 +688,7 @@ async fn test_full_workflow_without_recalc() -> Result<()> {
         SaveForkParams {
             fork_id: fork.fork_id.clone(),
             target_path: Some("workflow_updated.xlsx".to_string()),
+            sheet_name: None,
             drop_fork: true,
         },
     )
@@ -0,0 +1,379 @@
+//! HTTP transport authentication: static, HMAC and client certificate
+//! credentials resolve to a principal whose tools, workspace and access level
+//! come from its identity.
+
+use anyhow::Result;
+use axum::body::Body;
+use axum::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use axum::http::{HeaderMap, HeaderValue, Request, StatusCode};
+use axum::routing::get;
+use axum::{Extension, Router};
+use http_body_util::BodyExt;
+use spreadsheet_mcp::SpreadsheetServer;
+use spreadsheet_mcp::auth::{
+    self, Access, AuthError, AuthMethod, HttpAuth, HttpAuthConfig, Principal,
+};
+use std::net::IpAddr;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+#[path = "./support/mod.rs"]
+mod support;
+
+const ANALYST_TOKEN: &str = "analyst-token-0123456789";
+const HMAC_SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+fn auth_config() -> HttpAuthConfig {
+    serde_yaml::from_str(&format!(
+        r#"
+identities:
+  - name: analyst
+    workspace_subpath: reports
+  - name: editor
+    access: read_write
+    tools: [list_workbooks, create_fork, edit_batch, save_fork]
+bearer_tokens:
+  - identity: analyst
+    token: {ANALYST_TOKEN}
+hmac:
+  secret: {HMAC_SECRET}
+  max_ttl_secs: 3600
+mtls:
+  subjects:
+    "CN=ci-bot,O=Example": editor
+  trusted_proxies: ["10.0.0.2"]
+"#
+    ))
+    .expect("valid http_auth yaml")
+}
+
+fn bearer(token: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
+    );
+    headers
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+#[test]
+fn static_and_hmac_tokens_resolve_principals() -> Result<()> {
+    let auth = HttpAuth::from_config(&auth_config())?;
+
+    let analyst = auth.authenticate(&bearer(ANALYST_TOKEN), None).unwrap();
+    assert_eq!(analyst.name, "analyst");
+    assert_eq!(analyst.method, AuthMethod::BearerToken);
+    assert_eq!(analyst.access, Access::ReadOnly);
+    assert!(analyst.may_call("read_table"));
+    assert!(!analyst.may_call("edit_batch"));
+    assert!(!analyst.may_call("create_fork"));
+
+    assert_eq!(
+        auth.authenticate(&HeaderMap::new(), None).unwrap_err(),
+        AuthError::MissingCredentials
+    );
+    assert_eq!(
+        auth.authenticate(&bearer("not-the-analyst-token"), None)
+            .unwrap_err(),
+        AuthError::InvalidToken
+    );
+
+    let secret = HMAC_SECRET.as_bytes();
+    let token = auth::sign_hmac_token(secret, "editor", now() + 600);
+    let editor = auth.authenticate(&bearer(&token), None).unwrap();
+    assert_eq!(editor.name, "editor");
+    assert_eq!(editor.method, AuthMethod::HmacToken);
+    assert!(editor.may_call("EDIT_BATCH"));
+    assert!(!editor.may_call("read_table"));
+
+    for (token, expected) in [
+        (
+            auth::sign_hmac_token(secret, "editor", now() - 1),
+            AuthError::ExpiredToken,
+        ),
+        (
+            auth::sign_hmac_token(secret, "editor", now() + 7200),
+            AuthError::TokenLifetimeTooLong,
+        ),
+        (
+            auth::sign_hmac_token(b"another-secret-another-secret-00", "editor", now() + 600),
+            AuthError::InvalidToken,
+        ),
+        (
+            auth::sign_hmac_token(secret, "nobody", now() + 600),
+            AuthError::InvalidToken,
+        ),
+        (
+            token.replacen("editor", "analyst", 1),
+            AuthError::InvalidToken,
+        ),
+    ] {
+        assert_eq!(
+            auth.authenticate(&bearer(&token), None).unwrap_err(),
+            expected,
+            "{token}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn client_certificate_header_is_trusted_only_from_proxies() -> Result<()> {
+    let auth = HttpAuth::from_config(&auth_config())?;
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "x-client-cert-subject",
+        HeaderValue::from_static("CN=ci-bot,O=Example"),
+    );
+    let proxy: IpAddr = "10.0.0.2".parse()?;
+    let client: IpAddr = "10.0.0.9".parse()?;
+
+    let principal = auth.authenticate(&headers, Some(proxy)).unwrap();
+    assert_eq!(principal.name, "editor");
+    assert_eq!(principal.method, AuthMethod::ClientCertificate);
+
+    // A client talking to the server directly cannot claim a certificate,
+    // even alongside a valid token.
+    headers.insert(
+        AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {ANALYST_TOKEN}"))?,
+    );
+    assert_eq!(
+        auth.authenticate(&headers, Some(client)).unwrap_err(),
+        AuthError::UntrustedProxy
+    );
+
+    headers.insert(
+        "x-client-cert-subject",
+        HeaderValue::from_static("CN=someone-else"),
+    );
+    assert_eq!(
+        auth.authenticate(&headers, Some(proxy)).unwrap_err(),
+        AuthError::UnknownSubject
+    );
+
+    Ok(())
+}
+
+#[test]
+fn invalid_configurations_are_rejected() {
+    let cases = [
+        (
+            "identities: [{name: a}]\nbearer_tokens: [{identity: a, token: short}]",
+            "at least 16",
+        ),
+        (
+            "identities: [{name: a}]\nbearer_tokens: [{identity: b, token: 0123456789abcdef}]",
+            "unknown identity 'b'",
+        ),
+        (
+            "identities: [{name: a, workspace_subpath: ../outside}]\nhmac: {secret: 0123456789abcdef0123456789abcdef}",
+            "without '..'",
+        ),
+        (
+            "identities: [{name: a}]\nhmac: {secret: too-short}",
+            "at least 32",
+        ),
+        (
+            "identities: [{name: a}]\nmtls: {subjects: {CN=a: a}, trusted_proxies: []}",
+            "trusted_proxies",
+        ),
+        (
+            "identities: [{name: a}]",
+            "needs bearer_tokens, hmac or mtls",
+        ),
+    ];
+    for (yaml, expected) in cases {
+        let config: HttpAuthConfig = serde_yaml::from_str(yaml).expect(yaml);
+        let err = HttpAuth::from_config(&config).unwrap_err();
+        assert!(format!("{err:#}").contains(expected), "{yaml}: {err:#}");
+    }
+}
+
+#[test]
+fn scoped_config_confines_identity() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    std::fs::create_dir_all(workspace.path("reports"))?;
+    let base = workspace.config_with(|cfg| {
+        cfg.recalc_enabled = true;
+        cfg.fork_store_dir = Some(workspace.path("forks"));
+    });
+    let auth = HttpAuth::from_config(&auth_config())?;
+    let tools: Vec<String> = ["list_workbooks", "read_table", "edit_batch", "save_fork"]
+        .map(String::from)
+        .into();
+
+    let analyst = auth.scoped_config(&base, "analyst", &tools)?;
+    assert_eq!(
+        analyst.workspace_root,
+        workspace.path("reports").canonicalize()?
+    );
+    assert!(!analyst.recalc_enabled);
+    assert!(analyst.http_auth.is_none());
+    assert!(analyst.is_tool_enabled("read_table"));
+    assert!(!analyst.is_tool_enabled("edit_batch"));
+    assert_eq!(
+        analyst.fork_store_dir,
+        Some(workspace.path("forks").join("analyst"))
+    );
+
+    let editor = auth.scoped_config(&base, "editor", &tools)?;
+    assert_eq!(editor.workspace_root, workspace.root());
+    assert!(editor.recalc_enabled);
+    assert!(editor.is_tool_enabled("edit_batch"));
+    assert!(!editor.is_tool_enabled("read_table"));
+
+    // Server-wide enabled_tools still apply on top of the identity's list.
+    let narrowed = workspace.config_with(|cfg| {
+        cfg.enabled_tools = Some(["list_workbooks".to_string()].into());
+    });
+    let editor = auth.scoped_config(&narrowed, "editor", &tools)?;
+    assert!(editor.is_tool_enabled("list_workbooks"));
+    assert!(!editor.is_tool_enabled("edit_batch"));
+
+    std::fs::remove_dir(workspace.path("reports"))?;
+    let err = auth.scoped_config(&base, "analyst", &tools).unwrap_err();
+    assert!(err.to_string().contains("does not exist"), "{err}");
+
+    Ok(())
+}
+
+#[test]
+fn read_only_tokens_are_refused_side_effecting_tools() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    std::fs::create_dir_all(workspace.path("reports"))?;
+    let auth = HttpAuth::from_config(&auth_config())?;
+    let analyst = auth.authenticate(&bearer(ANALYST_TOKEN), None).unwrap();
+
+    // Rendering writes screenshots, DoD runs cargo builds and code validation
+    // can update golden files.
+    for tool in [
+        "render_sheet",
+        "validate_definition_of_done",
+        "validate_generated_code",
+    ] {
+        assert!(!analyst.may_call(tool), "{tool}");
+    }
+    // Tools missing from the read list are denied by default.
+    assert!(!analyst.may_call("some_future_tool"));
+    assert!(analyst.may_call("diff_workbooks"));
+
+    let state = support::app_state_with_config(workspace.config_with(|cfg| {
+        cfg.vba_enabled = true;
+    }));
+    let tools = SpreadsheetServer::from_state(state).tool_names();
+    for tool in auth::READ_TOOLS {
+        assert!(
+            tools.iter().any(|name| name == tool),
+            "unknown read tool {tool}"
+        );
+    }
+    let scoped = auth.scoped_config(&workspace.config(), "analyst", &tools)?;
+    assert!(!scoped.is_tool_enabled("render_sheet"));
+    assert!(!scoped.is_tool_enabled("validate_definition_of_done"));
+    assert!(scoped.is_tool_enabled("read_table"));
+    // close_workbook changes the shared cache.
+    assert!(!scoped.is_tool_enabled("close_workbook"));
+    // Project tools read relative to the working directory, outside the
+    // analyst's subpath.
+    for tool in auth::PROJECT_TOOLS {
+        assert!(!scoped.is_tool_enabled(tool), "{tool}");
+    }
+
+    // Without a subpath, read-only identities keep the project read tools.
+    let reader: HttpAuthConfig = serde_yaml::from_str(
+        r#"
+identities:
+  - name: reader
+bearer_tokens:
+  - identity: reader
+    token: reader-token-0123456789
+"#,
+    )?;
+    let scoped =
+        HttpAuth::from_config(&reader)?.scoped_config(&workspace.config(), "reader", &tools)?;
+    assert!(scoped.is_tool_enabled("read_ggen_config"));
+    assert!(!scoped.is_tool_enabled("sync_ggen"));
+
+    Ok(())
+}
+
+#[cfg(feature = "recalc")]
+#[tokio::test(flavor = "current_thread")]
+async fn scoped_states_share_recalc_backend_and_locks() -> Result<()> {
+    use spreadsheet_mcp::RecalcBackendKind;
+
+    let workspace = support::TestWorkspace::new();
+    let base = || {
+        workspace.config_with(|cfg| {
+            cfg.recalc_enabled = true;
+            cfg.recalc_backend = RecalcBackendKind::Native;
+            cfg.fork_store_dir = Some(workspace.path("forks"));
+        })
+    };
+    let auth = HttpAuth::from_config(&auth_config())?;
+    let state = support::app_state_with_config(base());
+    let tools = vec!["create_fork".to_string()];
+    let editor = state.scoped(Arc::new(auth.scoped_config(&base(), "editor", &tools)?));
+
+    assert!(Arc::ptr_eq(
+        state.recalc_backend().expect("backend"),
+        editor.recalc_backend().expect("backend")
+    ));
+    assert!(Arc::ptr_eq(
+        &state.recalc_semaphore().expect("recalc lock").0,
+        &editor.recalc_semaphore().expect("recalc lock").0
+    ));
+    // Forks stay per identity.
+    assert!(!Arc::ptr_eq(
+        state.fork_registry().expect("registry"),
+        editor.fork_registry().expect("registry")
+    ));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn middleware_rejects_missing_credentials_and_attaches_principal() -> Result<()> {
+    let auth = Arc::new(HttpAuth::from_config(&auth_config())?);
+    let router =
+        Router::new()
+            .route(
+                "/whoami",
+                get(
+                    |Extension(principal): Extension<Arc<Principal>>| async move {
+                        principal.name.clone()
+                    },
+                ),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                auth,
+                auth::require_auth,
+            ));
+
+    let response = router
+        .clone()
+        .oneshot(Request::builder().uri("/whoami").body(Body::empty())?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()[WWW_AUTHENTICATE], "Bearer");
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .uri("/whoami")
+                .header(AUTHORIZATION, format!("Bearer {ANALYST_TOKEN}"))
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await?.to_bytes();
+    assert_eq!(&body[..], b"analyst");
+
+    Ok(())
+}
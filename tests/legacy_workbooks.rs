@@ -0,0 +1,164 @@
+//! Read tools over legacy `.xls` and binary `.xlsb` workbooks, in both the
+//! 1900 and the 1904 (old Mac) date systems. Every fixture holds the same
+//! `Sales` sheet, so dates must read back identically.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use spreadsheet_mcp::model::CellValue;
+use spreadsheet_mcp::tools::{
+    FindValueParams, ListWorkbooksParams, ReadTableParams, SheetFormulaMapParams, SheetPageParams,
+    find_value, list_workbooks, read_table, sheet_formula_map, sheet_page,
+};
+
+#[path = "./support/mod.rs"]
+mod support;
+
+fn text(value: &Option<CellValue>) -> Option<&str> {
+    match value {
+        Some(CellValue::Text(s)) => Some(s),
+        _ => None,
+    }
+}
+
+fn date(value: &Option<CellValue>) -> Option<&str> {
+    match value {
+        Some(CellValue::Date(s)) => Some(s),
+        _ => None,
+    }
+}
+
+/// Runs the read tools over `tests/test_files/<fixture>`.
+async fn read_sales_fixture(fixture: &str) -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    let source = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/test_files")
+        .join(fixture);
+    workspace.copy_workbook(&source, fixture);
+    let state = workspace.app_state();
+    let list = list_workbooks(
+        state.clone(),
+        ListWorkbooksParams {
+            slug_prefix: None,
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?;
+    assert_eq!(list.workbooks.len(), 1);
+    let workbook_id = list.workbooks[0].workbook_id.clone();
+
+    let page = sheet_page(
+        state.clone(),
+        SheetPageParams {
+            workbook_or_fork_id: workbook_id.clone(),
+            sheet_name: "Sales".into(),
+            start_row: 2,
+            page_size: 10,
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert_eq!(page.rows.len(), 3, "{fixture}");
+    assert!(!page.has_more);
+    let header = page.header_row.expect("header row");
+    let header: Vec<_> = header.cells.iter().map(|cell| text(&cell.value)).collect();
+    assert_eq!(
+        header,
+        ["Region", "Product", "Units", "Price", "Total", "Shipped"].map(Some)
+    );
+    let first = &page.rows[0].cells;
+    assert_eq!(text(&first[0].value), Some("East"));
+    assert!(matches!(first[2].value, Some(CellValue::Number(n)) if n == 12.0));
+    assert_eq!(first[4].address, "E2");
+    assert_eq!(first[4].formula.as_deref(), Some("C2*D2"));
+    assert!(matches!(first[4].cached_value, Some(CellValue::Number(n)) if n == 30.0));
+    assert_eq!(date(&first[5].value), Some("2024-03-15"), "{fixture}");
+
+    let table = read_table(
+        state.clone(),
+        ReadTableParams {
+            workbook_or_fork_id: workbook_id.clone(),
+            sheet_name: Some("Sales".into()),
+            range: Some("A1:F4".into()),
+            header_row: Some(1),
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert_eq!(
+        table.headers,
+        ["Region", "Product", "Units", "Price", "Total", "Shipped"]
+    );
+    assert_eq!(table.total_rows, 3);
+    let shipped: Vec<_> = table.rows.iter().map(|row| date(&row["Shipped"])).collect();
+    assert_eq!(
+        shipped,
+        ["2024-03-15", "2024-03-18", "2024-04-02"].map(Some),
+        "{fixture}"
+    );
+    assert!(matches!(table.rows[1]["Total"], Some(CellValue::Number(n)) if n == 50.0));
+
+    for (query, address) in [("Gadget", "B3"), ("2024-04-02", "F4")] {
+        let found = find_value(
+            state.clone(),
+            FindValueParams {
+                workbook_or_fork_id: workbook_id.clone(),
+                query: query.into(),
+                sheet_name: Some("Sales".into()),
+                ..Default::default()
+            },
+        )
+        .await?;
+        let addresses: Vec<_> = found.matches.iter().map(|m| m.address.as_str()).collect();
+        assert_eq!(addresses, [address], "{fixture}: {query}");
+    }
+
+    let formulas = sheet_formula_map(
+        state,
+        SheetFormulaMapParams {
+            workbook_or_fork_id: workbook_id,
+            sheet_name: "Sales".into(),
+            range: None,
+            expand: true,
+            limit: None,
+            sort_by: None,
+        },
+    )
+    .await?;
+    let mut addresses: Vec<_> = formulas
+        .groups
+        .iter()
+        .flat_map(|group| group.addresses.iter().cloned())
+        .collect();
+    addresses.sort();
+    assert_eq!(addresses, ["E2", "E3", "E4"]);
+    assert!(
+        formulas
+            .groups
+            .iter()
+            .all(|group| group.formula.contains('*'))
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn read_tools_load_xls() -> Result<()> {
+    read_sales_fixture("sales.xls").await
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn read_tools_load_xls_in_the_1904_date_system() -> Result<()> {
+    read_sales_fixture("sales_1904.xls").await
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn read_tools_load_xlsb() -> Result<()> {
+    read_sales_fixture("sales.xlsb").await
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn read_tools_load_xlsb_in_the_1904_date_system() -> Result<()> {
+    read_sales_fixture("sales_1904.xlsb").await
+}
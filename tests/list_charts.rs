@@ -0,0 +1,99 @@
+//! list_charts and chart_data read chart parts and resolve series back to cells.
+
+use anyhow::Result;
+use spreadsheet_mcp::tools::charts::{ChartDataParams, ListChartsParams, chart_data, list_charts};
+use spreadsheet_mcp::tools::{ListWorkbooksParams, list_workbooks};
+use umya_spreadsheet::structs::drawing::spreadsheet::MarkerType;
+use umya_spreadsheet::structs::{Chart, ChartType};
+
+mod support;
+
+#[tokio::test(flavor = "current_thread")]
+async fn lists_charts_and_returns_plotted_values() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("sales.xlsx", |book| {
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.get_cell_mut("A1").set_value("Month");
+        sheet.get_cell_mut("B1").set_value("Units");
+        for (row, month, units) in [(2, "Jan", 12.0), (3, "Feb", 18.5), (4, "Mar", 9.0)] {
+            sheet.get_cell_mut((1, row)).set_value(month);
+            sheet.get_cell_mut((2, row)).set_value_number(units);
+        }
+
+        let mut from_marker = MarkerType::default();
+        from_marker.set_coordinate("D2");
+        let mut to_marker = MarkerType::default();
+        to_marker.set_coordinate("K16");
+        let mut chart = Chart::default();
+        chart.new_chart(
+            ChartType::LineChart,
+            from_marker,
+            to_marker,
+            vec!["Sheet1!$B$2:$B$4"],
+        );
+        sheet.add_chart(chart);
+    });
+    let state = workspace.app_state();
+    let workbook_id = list_workbooks(
+        state.clone(),
+        ListWorkbooksParams {
+            slug_prefix: None,
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?
+    .workbooks[0]
+        .workbook_id
+        .clone();
+
+    let listed = list_charts(
+        state.clone(),
+        ListChartsParams {
+            workbook_or_fork_id: workbook_id.clone(),
+            sheet_name: None,
+        },
+    )
+    .await?;
+    assert_eq!(listed.charts.len(), 1);
+    let chart = &listed.charts[0];
+    assert_eq!(chart.sheet_name, "Sheet1");
+    assert_eq!(chart.chart_index, 0);
+    assert_eq!(chart.chart_type, "line");
+    assert_eq!(chart.anchor.from.as_deref(), Some("D2"));
+    assert_eq!(chart.anchor.to.as_deref(), Some("K16"));
+    assert_eq!(chart.series.len(), 1);
+    let values = chart.series[0].values.as_ref().expect("value reference");
+    assert_eq!(values.ranges, vec!["Sheet1!B2:B4".to_string()]);
+    assert_eq!(chart.series[0].point_count, 3);
+
+    let data = chart_data(
+        state.clone(),
+        ChartDataParams {
+            workbook_or_fork_id: workbook_id.clone(),
+            sheet_name: "Sheet1".into(),
+            chart_index: 0,
+            max_points: 2,
+        },
+    )
+    .await?;
+    let series = &data.series[0];
+    assert_eq!(series.source, "cells");
+    assert_eq!(series.values, vec![Some(12.0), Some(18.5)]);
+    assert!(series.truncated);
+
+    let missing = chart_data(
+        state.clone(),
+        ChartDataParams {
+            workbook_or_fork_id: workbook_id,
+            sheet_name: "Sheet1".into(),
+            chart_index: 3,
+            max_points: 10,
+        },
+    )
+    .await
+    .unwrap_err();
+    assert!(missing.to_string().contains("list_charts"), "{missing}");
+
+    Ok(())
+}
@@ -0,0 +1,361 @@
+//! Integration tests for merge_forks: three-way merge of two forks of one workbook.
+
+#![cfg(feature = "recalc")]
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use spreadsheet_mcp::model::WorkbookId;
+use spreadsheet_mcp::state::AppState;
+use spreadsheet_mcp::tools::fork::{
+    ApplyStagedChangeParams, CellEdit, CreateForkParams, EditBatchParams, StructureBatchParams,
+    StructureOp, apply_staged_change, create_fork, edit_batch, structure_batch,
+};
+use spreadsheet_mcp::tools::merge::{
+    ConflictKind, ConflictResolution, MergeForksParams, MergePolicy, merge_forks,
+};
+use spreadsheet_mcp::tools::{ListWorkbooksParams, list_workbooks};
+
+#[path = "./support/mod.rs"]
+mod support;
+
+async fn setup() -> Result<(support::TestWorkspace, Arc<AppState>, String, String)> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("budget.xlsx", |book| {
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.get_cell_mut("A1").set_value("Item");
+        sheet.get_cell_mut("B1").set_value("Cost");
+        sheet.get_cell_mut("A2").set_value("Rent");
+        sheet.get_cell_mut("B2").set_value_number(1000);
+        sheet.get_cell_mut("A3").set_value("Power");
+        sheet.get_cell_mut("B3").set_value_number(200);
+    });
+    let state = support::app_state_with_config(workspace.config_with(|cfg| {
+        cfg.recalc_enabled = true;
+    }));
+    let list = list_workbooks(
+        state.clone(),
+        ListWorkbooksParams {
+            slug_prefix: None,
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?;
+    let workbook_id = list.workbooks[0].workbook_id.clone();
+
+    let mut forks = Vec::new();
+    for _ in 0..2 {
+        let fork = create_fork(
+            state.clone(),
+            CreateForkParams {
+                workbook_or_fork_id: workbook_id.clone(),
+            },
+        )
+        .await?;
+        forks.push(fork.fork_id);
+    }
+    let fork_b = forks.pop().unwrap();
+    let fork_a = forks.pop().unwrap();
+
+    // Both forks change B2; each also changes a cell the other leaves alone.
+    edit(&state, &fork_a, &[("B2", "1100"), ("A4", "Water")]).await?;
+    edit(&state, &fork_b, &[("B2", "1250"), ("B3", "180")]).await?;
+
+    Ok((workspace, state, fork_a, fork_b))
+}
+
+async fn edit(state: &Arc<AppState>, fork_id: &str, edits: &[(&str, &str)]) -> Result<()> {
+    edit_batch(
+        state.clone(),
+        EditBatchParams {
+            fork_id: fork_id.to_string(),
+            sheet_name: "Sheet1".to_string(),
+            edits: edits
+                .iter()
+                .map(|(address, value)| CellEdit {
+                    address: address.to_string(),
+                    value: value.to_string(),
+                    is_formula: false,
+                })
+                .collect(),
+        },
+    )
+    .await?;
+    Ok(())
+}
+
+async fn cell_value(state: &Arc<AppState>, fork_id: &str, address: &str) -> Result<String> {
+    let workbook = state
+        .open_workbook(&WorkbookId(fork_id.to_string()))
+        .await?;
+    workbook.with_sheet("Sheet1", |sheet| {
+        sheet
+            .get_cell(address)
+            .map(|c| c.get_value().to_string())
+            .unwrap_or_default()
+    })
+}
+
+fn params(fork_a: &str, fork_b: &str, policy: MergePolicy) -> MergeForksParams {
+    MergeForksParams {
+        fork_a: fork_a.to_string(),
+        fork_b: fork_b.to_string(),
+        policy,
+        sheet_name: None,
+        label: None,
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn prefer_a_applies_non_overlapping_changes_and_reports_conflicts() -> Result<()> {
+    let (_workspace, state, fork_a, fork_b) = setup().await?;
+
+    let resp = merge_forks(
+        state.clone(),
+        params(&fork_a, &fork_b, MergePolicy::PreferA),
+    )
+    .await?;
+
+    assert_eq!(resp.conflicts.len(), 1);
+    let conflict = &resp.conflicts[0];
+    assert!(matches!(conflict.kind, ConflictKind::CellContent));
+    assert!(matches!(conflict.resolution, ConflictResolution::KeptA));
+    assert_eq!(conflict.address.as_deref(), Some("B2"));
+    assert_eq!(conflict.base.value.as_deref(), Some("1000"));
+    assert_eq!(conflict.fork_a.value.as_deref(), Some("1100"));
+    assert_eq!(conflict.fork_b.value.as_deref(), Some("1250"));
+    assert!(resp.change_id.is_none());
+    assert_eq!(resp.summary.counts.get("cells_merged"), Some(&1));
+
+    assert_eq!(cell_value(&state, &fork_a, "B2").await?, "1100");
+    assert_eq!(cell_value(&state, &fork_a, "B3").await?, "180");
+    assert_eq!(cell_value(&state, &fork_a, "A4").await?, "Water");
+    // fork_b is never modified
+    assert_eq!(cell_value(&state, &fork_b, "A4").await?, "");
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn stage_policy_stages_fork_b_side_of_conflicts() -> Result<()> {
+    let (_workspace, state, fork_a, fork_b) = setup().await?;
+
+    let resp = merge_forks(state.clone(), params(&fork_a, &fork_b, MergePolicy::Stage)).await?;
+    assert!(matches!(
+        resp.conflicts[0].resolution,
+        ConflictResolution::Staged
+    ));
+    let change_id = resp.change_id.expect("staged change");
+
+    assert_eq!(cell_value(&state, &fork_a, "B2").await?, "1100");
+    assert_eq!(cell_value(&state, &fork_a, "B3").await?, "180");
+
+    apply_staged_change(
+        state.clone(),
+        ApplyStagedChangeParams {
+            fork_id: fork_a.clone(),
+            change_id,
+        },
+    )
+    .await?;
+    assert_eq!(cell_value(&state, &fork_a, "B2").await?, "1250");
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn prefer_b_takes_fork_b_values() -> Result<()> {
+    let (_workspace, state, fork_a, fork_b) = setup().await?;
+
+    let resp = merge_forks(
+        state.clone(),
+        params(&fork_a, &fork_b, MergePolicy::PreferB),
+    )
+    .await?;
+    assert!(matches!(
+        resp.conflicts[0].resolution,
+        ConflictResolution::TookB
+    ));
+    assert_eq!(cell_value(&state, &fork_a, "B2").await?, "1250");
+    assert_eq!(cell_value(&state, &fork_a, "A4").await?, "Water");
+
+    Ok(())
+}
+
+/// Forks of a workbook with tables `Costs` (A1:B3) and `Stock` (D1:E3).
+/// Fork A grows `Costs`; fork B shrinks it and renames `Stock`.
+async fn setup_tables() -> Result<(support::TestWorkspace, Arc<AppState>, String, String)> {
+    use umya_spreadsheet::structs::{Table, TableColumn};
+
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("tables.xlsx", |book| {
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        for (address, value) in [
+            ("A1", "Item"),
+            ("B1", "Cost"),
+            ("A2", "Rent"),
+            ("A3", "Power"),
+            ("A4", "Water"),
+            ("D1", "Code"),
+            ("E1", "Qty"),
+            ("D2", "X1"),
+            ("D3", "X2"),
+        ] {
+            sheet.get_cell_mut(address).set_value(value);
+        }
+        for (name, area, columns) in [
+            ("Costs", ("A1", "B3"), ["Item", "Cost"]),
+            ("Stock", ("D1", "E3"), ["Code", "Qty"]),
+        ] {
+            let mut table = Table::new(name, area);
+            for column in columns {
+                table.add_column(TableColumn::new(column));
+            }
+            sheet.add_table(table);
+        }
+    });
+    let state = support::app_state_with_config(workspace.config_with(|cfg| {
+        cfg.recalc_enabled = true;
+    }));
+    let list = list_workbooks(
+        state.clone(),
+        ListWorkbooksParams {
+            slug_prefix: None,
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?;
+    let workbook_id = list.workbooks[0].workbook_id.clone();
+
+    let mut forks = Vec::new();
+    for ops in [
+        vec![StructureOp::ResizeTable {
+            table_name: "Costs".to_string(),
+            range: "A1:B4".to_string(),
+        }],
+        vec![
+            StructureOp::ResizeTable {
+                table_name: "Costs".to_string(),
+                range: "A1:B2".to_string(),
+            },
+            StructureOp::RenameTable {
+                table_name: "Stock".to_string(),
+                new_name: "Inventory".to_string(),
+            },
+        ],
+    ] {
+        let fork_id = create_fork(
+            state.clone(),
+            CreateForkParams {
+                workbook_or_fork_id: workbook_id.clone(),
+            },
+        )
+        .await?
+        .fork_id;
+        structure_batch(
+            state.clone(),
+            StructureBatchParams {
+                fork_id: fork_id.clone(),
+                ops,
+                mode: Some("apply".to_string()),
+                label: None,
+            },
+        )
+        .await?;
+        forks.push(fork_id);
+    }
+    let fork_b = forks.pop().unwrap();
+    let fork_a = forks.pop().unwrap();
+    Ok((workspace, state, fork_a, fork_b))
+}
+
+async fn tables(state: &Arc<AppState>, fork_id: &str) -> Result<Vec<(String, String)>> {
+    let workbook = state
+        .open_workbook(&WorkbookId(fork_id.to_string()))
+        .await?;
+    workbook.with_sheet("Sheet1", |sheet| {
+        let mut tables: Vec<(String, String)> = sheet
+            .get_tables()
+            .iter()
+            .map(|table| {
+                let (start, end) = table.get_area();
+                (
+                    table.get_display_name().to_string(),
+                    format!("{}:{}", start.get_coordinate(), end.get_coordinate()),
+                )
+            })
+            .collect();
+        tables.sort();
+        tables
+    })
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn table_conflicts_follow_the_policy_and_deletions_merge() -> Result<()> {
+    let (_workspace, state, fork_a, fork_b) = setup_tables().await?;
+
+    let resp = merge_forks(
+        state.clone(),
+        params(&fork_a, &fork_b, MergePolicy::PreferB),
+    )
+    .await?;
+
+    assert_eq!(resp.conflicts.len(), 1);
+    let conflict = &resp.conflicts[0];
+    assert!(matches!(conflict.kind, ConflictKind::Table));
+    assert!(matches!(conflict.resolution, ConflictResolution::TookB));
+    assert_eq!(conflict.name.as_deref(), Some("Costs"));
+    assert_eq!(conflict.base.range.as_deref(), Some("A1:B3"));
+    assert_eq!(conflict.fork_a.range.as_deref(), Some("A1:B4"));
+    assert_eq!(conflict.fork_b.range.as_deref(), Some("A1:B2"));
+    // The rename reaches fork A as a deletion plus an addition.
+    assert_eq!(
+        tables(&state, &fork_a).await?,
+        vec![
+            ("Costs".to_string(), "A1:B2".to_string()),
+            ("Inventory".to_string(), "D1:E3".to_string()),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn stage_policy_stages_table_conflicts() -> Result<()> {
+    let (_workspace, state, fork_a, fork_b) = setup_tables().await?;
+
+    let resp = merge_forks(state.clone(), params(&fork_a, &fork_b, MergePolicy::Stage)).await?;
+    assert!(matches!(resp.conflicts[0].kind, ConflictKind::Table));
+    assert!(matches!(
+        resp.conflicts[0].resolution,
+        ConflictResolution::Staged
+    ));
+    let change_id = resp.change_id.expect("staged change");
+    assert_eq!(
+        tables(&state, &fork_a).await?,
+        vec![
+            ("Costs".to_string(), "A1:B4".to_string()),
+            ("Inventory".to_string(), "D1:E3".to_string()),
+        ]
+    );
+
+    apply_staged_change(
+        state.clone(),
+        ApplyStagedChangeParams {
+            fork_id: fork_a.clone(),
+            change_id,
+        },
+    )
+    .await?;
+    assert_eq!(
+        tables(&state, &fork_a).await?,
+        vec![
+            ("Costs".to_string(), "A1:B2".to_string()),
+            ("Inventory".to_string(), "D1:E3".to_string()),
+        ]
+    );
+
+    Ok(())
+}
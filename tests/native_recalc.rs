@@ -0,0 +1,109 @@
+//! `recalculate` with the in-process backend: lookups, conditional
+//! aggregates and date functions are evaluated and cached with their types.
+
+#![cfg(feature = "recalc")]
+
+use anyhow::Result;
+use spreadsheet_mcp::RecalcBackendKind;
+use spreadsheet_mcp::model::WorkbookId;
+use spreadsheet_mcp::tools::filters::WorkbookFilter;
+use spreadsheet_mcp::tools::fork::{CreateForkParams, RecalculateParams, create_fork, recalculate};
+
+#[path = "./support/mod.rs"]
+mod support;
+
+#[tokio::test(flavor = "current_thread")]
+async fn native_recalculate_caches_typed_results() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("native.xlsx", |book| {
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        for (row, region, product, amount) in [
+            (2, "East", "Widget", 120.0),
+            (3, "West", "Gadget", 80.0),
+            (4, "East", "Gadget", 45.5),
+            (5, "East", "Widget", 30.0),
+        ] {
+            sheet.get_cell_mut((1, row)).set_value_string(region);
+            sheet.get_cell_mut((2, row)).set_value_string(product);
+            sheet.get_cell_mut((3, row)).set_value_number(amount);
+        }
+        // Text that looks like numbers must stay text.
+        sheet.get_cell_mut("E1").set_value_string("00123");
+        sheet.get_cell_mut("E2").set_value_string("1e3");
+
+        for (addr, formula) in [
+            ("G1", "SUMIFS(C2:C5,A2:A5,\"East\",B2:B5,\"Widget\")"),
+            ("G2", "VLOOKUP(\"Gadget\",B2:C5,2,FALSE)"),
+            ("G3", "INDEX(C2:C5,MATCH(\"West\",A2:A5,0))"),
+            ("G4", "DATE(2024,2,29)"),
+            ("G5", "YEAR(EDATE(G4,12))*100+DAY(EOMONTH(G4,0))"),
+            ("G6", "E1&\"-\"&E2"),
+            ("G7", "ISNUMBER(E1)"),
+        ] {
+            sheet.get_cell_mut(addr).set_formula(formula.to_string());
+        }
+    });
+    let state = support::app_state_with_config(workspace.config_with(|cfg| {
+        cfg.recalc_enabled = true;
+        cfg.recalc_backend = RecalcBackendKind::Native;
+    }));
+    let workbook_id = state
+        .list_workbooks(WorkbookFilter::default())?
+        .workbooks
+        .remove(0)
+        .workbook_id;
+    let fork_id = create_fork(
+        state.clone(),
+        CreateForkParams {
+            workbook_or_fork_id: workbook_id,
+        },
+    )
+    .await?
+    .fork_id;
+
+    let resp = recalculate(
+        state.clone(),
+        RecalculateParams {
+            fork_id: fork_id.clone(),
+            timeout_ms: 30_000,
+        },
+    )
+    .await?;
+    assert_eq!(resp.backend, "native");
+    assert!(resp.unsupported.is_empty(), "{:?}", resp.unsupported);
+
+    let fork = state.open_workbook(&WorkbookId(fork_id)).await?;
+    let cached = fork.with_sheet("Sheet1", |sheet| {
+        (1..=7)
+            .map(|row| {
+                let cell = sheet.get_cell((7, row)).expect("formula cell");
+                assert!(cell.is_formula(), "G{row} lost its formula");
+                (
+                    cell.get_value().to_string(),
+                    cell.get_data_type().to_string(),
+                )
+            })
+            .collect::<Vec<_>>()
+    })?;
+    let expected = [
+        ("150", "n"),
+        ("80", "n"),
+        ("80", "n"),
+        ("45351", "n"),
+        ("202529", "n"),
+        ("00123-1e3", "str"),
+        ("FALSE", "b"),
+    ];
+    for (row, ((value, data_type), (want_value, want_type))) in
+        cached.iter().zip(expected).enumerate()
+    {
+        assert!(
+            value.eq_ignore_ascii_case(want_value),
+            "G{}: {value}",
+            row + 1
+        );
+        assert_eq!(data_type, want_type, "G{}", row + 1);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! Read tools return display text under each cell's number format on request.
+
+use anyhow::Result;
+use serde_json::json;
+use spreadsheet_mcp::model::{CellValue, SheetPageFormat, ValueMode};
+use spreadsheet_mcp::tools::{
+    ListWorkbooksParams, RangeValuesParams, ReadTableParams, SheetPageParams, TableFilter,
+    list_workbooks, range_values, read_table, sheet_page,
+};
+
+mod support;
+
+fn text(value: &Option<CellValue>) -> Option<&str> {
+    match value {
+        Some(CellValue::Text(s)) => Some(s),
+        _ => None,
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn read_tools_return_formatted_raw_or_both() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("rates.xlsx", |book| {
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.get_cell_mut("A1").set_value("Name");
+        sheet.get_cell_mut("B1").set_value("Rate");
+        sheet.get_cell_mut("C1").set_value("Balance");
+        for (row, name, rate, balance) in [(2, "Alpha", 0.0725, 1234.5), (3, "Beta", 0.05, -42.0)] {
+            sheet.get_cell_mut((1, row)).set_value(name);
+            sheet.get_cell_mut((2, row)).set_value_number(rate);
+            sheet.get_cell_mut((3, row)).set_value_number(balance);
+            sheet
+                .get_style_mut((2, row))
+                .get_number_format_mut()
+                .set_format_code("0.00%");
+            sheet
+                .get_style_mut((3, row))
+                .get_number_format_mut()
+                .set_format_code("$#,##0.00;[Red]($#,##0.00)");
+        }
+    });
+    let state = workspace.app_state();
+    let workbook_id = list_workbooks(
+        state.clone(),
+        ListWorkbooksParams {
+            slug_prefix: None,
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?
+    .workbooks[0]
+        .workbook_id
+        .clone();
+
+    let page = sheet_page(
+        state.clone(),
+        SheetPageParams {
+            workbook_or_fork_id: workbook_id.clone(),
+            sheet_name: "Sheet1".into(),
+            start_row: 2,
+            page_size: 2,
+            include_header: false,
+            format: Some(SheetPageFormat::ValuesOnly),
+            value_mode: Some(ValueMode::Formatted),
+            ..Default::default()
+        },
+    )
+    .await?;
+    let values = page.values_only.expect("values_only payload");
+    let first: Vec<_> = values.rows[0].iter().map(text).collect();
+    assert_eq!(first, [Some("Alpha"), Some("7.25%"), Some("$1,234.50")]);
+    assert_eq!(text(&values.rows[1][2]), Some("($42.00)"));
+    assert!(values.formatted_rows.is_none());
+
+    let ranges = range_values(
+        state.clone(),
+        RangeValuesParams {
+            workbook_or_fork_id: workbook_id.clone(),
+            sheet_name: "Sheet1".into(),
+            ranges: vec!["B2:C2".into()],
+            include_headers: None,
+            value_mode: Some(ValueMode::Both),
+        },
+    )
+    .await?;
+    let entry = &ranges.values[0];
+    assert!(matches!(entry.rows[0][0], Some(CellValue::Number(n)) if (n - 0.0725).abs() < 1e-12));
+    assert_eq!(
+        entry.formatted_rows.as_ref().unwrap()[0],
+        vec![Some("7.25%".to_string()), Some("$1,234.50".to_string())]
+    );
+
+    // Filters compare stored numbers even when formatted text is returned.
+    let table = read_table(
+        state.clone(),
+        ReadTableParams {
+            workbook_or_fork_id: workbook_id.clone(),
+            sheet_name: Some("Sheet1".into()),
+            range: Some("A1:C3".into()),
+            filters: Some(vec![TableFilter {
+                column: "Rate".into(),
+                op: "gt".into(),
+                value: json!(0.06),
+            }]),
+            value_mode: Some(ValueMode::Formatted),
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert_eq!(table.rows.len(), 1);
+    assert_eq!(text(&table.rows[0]["Rate"]), Some("7.25%"));
+    assert!(table.formatted_rows.is_none());
+
+    let raw = read_table(
+        state.clone(),
+        ReadTableParams {
+            workbook_or_fork_id: workbook_id.clone(),
+            sheet_name: Some("Sheet1".into()),
+            range: Some("A1:C3".into()),
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert!(matches!(raw.rows[1]["Balance"], Some(CellValue::Number(n)) if n == -42.0));
+    assert!(raw.formatted_rows.is_none());
+
+    Ok(())
+}
@@ -0,0 +1,65 @@
+//! Saving forks of `.ods` workbooks: the writer drops cell styles, so the
+//! original is only overwritten when named explicitly and saves carry a
+//! warning.
+
+#![cfg(feature = "recalc")]
+
+use anyhow::Result;
+use spreadsheet_mcp::backends::ods;
+use spreadsheet_mcp::tools::filters::WorkbookFilter;
+use spreadsheet_mcp::tools::fork::{CreateForkParams, SaveForkParams, create_fork, save_fork};
+
+#[path = "./support/mod.rs"]
+mod support;
+
+#[tokio::test]
+async fn saving_over_an_ods_original_needs_an_explicit_target() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    let mut book = umya_spreadsheet::new_file();
+    book.get_sheet_mut(&0)
+        .unwrap()
+        .get_cell_mut("A1")
+        .set_value("Total");
+    ods::write(&book, &workspace.path("budget.ods"))?;
+    let state = support::app_state_with_config(workspace.config_with(|cfg| {
+        cfg.recalc_enabled = true;
+        cfg.allow_overwrite = true;
+    }));
+    let workbook_id = state
+        .list_workbooks(WorkbookFilter::default())?
+        .workbooks
+        .remove(0)
+        .workbook_id;
+    let fork_id = create_fork(
+        state.clone(),
+        CreateForkParams {
+            workbook_or_fork_id: workbook_id,
+        },
+    )
+    .await?
+    .fork_id;
+    let save = |target_path: Option<&str>| {
+        save_fork(
+            state.clone(),
+            SaveForkParams {
+                fork_id: fork_id.clone(),
+                target_path: target_path.map(String::from),
+                sheet_name: None,
+                drop_fork: false,
+            },
+        )
+    };
+
+    let err = save(None).await.unwrap_err();
+    assert!(err.to_string().contains("target_path"), "{err}");
+
+    let saved = save(Some("budget.ods")).await?;
+    assert!(saved.saved_to.ends_with("budget.ods"), "{}", saved.saved_to);
+    assert_eq!(saved.warnings.len(), 1);
+    assert!(saved.warnings[0].contains("styles"), "{:?}", saved.warnings);
+
+    let saved = save(Some("budget.xlsx")).await?;
+    assert!(saved.warnings.is_empty(), "{:?}", saved.warnings);
+
+    Ok(())
+}
@@ -0,0 +1,228 @@
+//! list_pivot_tables and pivot_data read pivot definitions and their caches.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use anyhow::Result;
+use spreadsheet_mcp::tools::pivots::{
+    ListPivotTablesParams, PivotDataParams, list_pivot_tables, pivot_data,
+};
+use spreadsheet_mcp::tools::{
+    ListWorkbooksParams, SheetOverviewParams, list_workbooks, sheet_overview,
+};
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+mod support;
+
+const PIVOT_TABLE: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<pivotTableDefinition xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" name="UnitsByRegion" cacheId="1" dataCaption="Values">
+  <location ref="F3:G6" firstHeaderRow="1" firstDataRow="1" firstDataCol="1" rowPageCount="1" colPageCount="1"/>
+  <pivotFields count="3">
+    <pivotField axis="axisRow" showAll="0"><items count="3"><item x="0"/><item x="1"/><item t="default"/></items></pivotField>
+    <pivotField axis="axisPage" showAll="0"><items count="3"><item x="0"/><item x="1"/><item t="default"/></items></pivotField>
+    <pivotField dataField="1" showAll="0"/>
+  </pivotFields>
+  <rowFields count="1"><field x="0"/></rowFields>
+  <pageFields count="1"><pageField fld="1" item="1" hier="-1"/></pageFields>
+  <dataFields count="1"><dataField name="Sum of Units" fld="2" baseField="0" baseItem="0"/></dataFields>
+</pivotTableDefinition>"#;
+
+const CACHE_DEFINITION: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<pivotCacheDefinition xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" r:id="rId1" refreshedBy="ana" recordCount="4">
+  <cacheSource type="worksheet"><worksheetSource ref="A1:C5" sheet="Sheet1"/></cacheSource>
+  <cacheFields count="3">
+    <cacheField name="Region" numFmtId="0"><sharedItems count="2"><s v="North"/><s v="South"/></sharedItems></cacheField>
+    <cacheField name="Year" numFmtId="0"><sharedItems containsNumber="1" containsInteger="1" count="2"><n v="2023"/><n v="2024"/></sharedItems></cacheField>
+    <cacheField name="Units" numFmtId="0"><sharedItems containsNumber="1"/></cacheField>
+  </cacheFields>
+</pivotCacheDefinition>"#;
+
+// The cached figures deliberately differ from the cells, which were edited
+// after the last refresh.
+const CACHE_RECORDS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<pivotCacheRecords xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" count="4">
+  <r><x v="0"/><x v="0"/><n v="5"/></r>
+  <r><x v="0"/><x v="1"/><n v="7"/></r>
+  <r><x v="1"/><x v="1"/><n v="3"/></r>
+  <r><x v="1"/><x v="1"/><n v="4"/></r>
+</pivotCacheRecords>"#;
+
+fn rels(entries: &[(&str, &str, &str)]) -> String {
+    let body: String = entries
+        .iter()
+        .map(|(id, kind, target)| {
+            format!(
+                r#"<Relationship Id="{id}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/{kind}" Target="{target}"/>"#
+            )
+        })
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{body}</Relationships>"#
+    )
+}
+
+/// Add a pivot table on Sheet1, with its cache, to a saved workbook.
+fn inject_pivot(path: &Path) {
+    let mut archive = ZipArchive::new(File::open(path).unwrap()).unwrap();
+    let mut files = HashMap::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).unwrap();
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer).unwrap();
+        files.insert(file.name().to_string(), buffer);
+    }
+
+    let sheet_rel = r#"<Relationship Id="rIdPivot1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/pivotTable" Target="../pivotTables/pivotTable1.xml"/>"#;
+    let sheet_rels = match files.get("xl/worksheets/_rels/sheet1.xml.rels") {
+        Some(existing) => String::from_utf8(existing.clone())
+            .unwrap()
+            .replace("</Relationships>", &format!("{sheet_rel}</Relationships>")),
+        None => rels(&[("rIdPivot1", "pivotTable", "../pivotTables/pivotTable1.xml")]),
+    };
+    files.insert(
+        "xl/worksheets/_rels/sheet1.xml.rels".to_string(),
+        sheet_rels.into_bytes(),
+    );
+    for (name, content) in [
+        ("xl/pivotTables/pivotTable1.xml", PIVOT_TABLE.to_string()),
+        (
+            "xl/pivotTables/_rels/pivotTable1.xml.rels",
+            rels(&[(
+                "rId1",
+                "pivotCacheDefinition",
+                "../pivotCache/pivotCacheDefinition1.xml",
+            )]),
+        ),
+        (
+            "xl/pivotCache/pivotCacheDefinition1.xml",
+            CACHE_DEFINITION.to_string(),
+        ),
+        (
+            "xl/pivotCache/_rels/pivotCacheDefinition1.xml.rels",
+            rels(&[("rId1", "pivotCacheRecords", "pivotCacheRecords1.xml")]),
+        ),
+        (
+            "xl/pivotCache/pivotCacheRecords1.xml",
+            CACHE_RECORDS.to_string(),
+        ),
+    ] {
+        files.insert(name.to_string(), content.into_bytes());
+    }
+
+    let mut zip = ZipWriter::new(File::create(path).unwrap());
+    for (name, content) in files {
+        zip.start_file(name, FileOptions::default()).unwrap();
+        zip.write_all(&content).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn lists_pivot_tables_and_aggregates_cache_records() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    let path = workspace.create_workbook("pivots.xlsx", |book| {
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.get_cell_mut("A1").set_value("Region");
+        sheet.get_cell_mut("B1").set_value("Year");
+        sheet.get_cell_mut("C1").set_value("Units");
+        for (row, region, year, units) in [
+            (2, "North", 2023, 50),
+            (3, "North", 2024, 70),
+            (4, "South", 2024, 30),
+            (5, "South", 2024, 40),
+        ] {
+            sheet.get_cell_mut((1, row)).set_value(region);
+            sheet.get_cell_mut((2, row)).set_value_number(year);
+            sheet.get_cell_mut((3, row)).set_value_number(units);
+        }
+    });
+    inject_pivot(&path);
+
+    let state = workspace.app_state();
+    let workbook_id = list_workbooks(
+        state.clone(),
+        ListWorkbooksParams {
+            slug_prefix: None,
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?
+    .workbooks[0]
+        .workbook_id
+        .clone();
+
+    let listed = list_pivot_tables(
+        state.clone(),
+        ListPivotTablesParams {
+            workbook_or_fork_id: workbook_id.clone(),
+            sheet_name: None,
+        },
+    )
+    .await?;
+    assert_eq!(listed.pivot_tables.len(), 1);
+    let pivot = &listed.pivot_tables[0];
+    assert_eq!(pivot.sheet_name, "Sheet1");
+    assert_eq!(pivot.name, "UnitsByRegion");
+    assert_eq!(pivot.location, "F3:G6");
+    assert_eq!(pivot.source.sheet.as_deref(), Some("Sheet1"));
+    assert_eq!(pivot.source.range.as_deref(), Some("A1:C5"));
+    assert_eq!(pivot.row_fields, vec!["Region".to_string()]);
+    assert_eq!(pivot.page_fields[0].field, "Year");
+    assert_eq!(pivot.page_fields[0].selected.as_deref(), Some("2024"));
+    assert_eq!(pivot.value_fields[0].name, "Sum of Units");
+    assert_eq!(pivot.value_fields[0].aggregation, "sum");
+    assert_eq!(pivot.record_count, Some(4));
+    assert!(pivot.records_cached);
+
+    let data = pivot_data(
+        state.clone(),
+        PivotDataParams {
+            workbook_or_fork_id: workbook_id.clone(),
+            sheet_name: "Sheet1".into(),
+            pivot_name: "UnitsByRegion".into(),
+            max_rows: 500,
+        },
+    )
+    .await?;
+    assert_eq!(data.source, "cache_records");
+    assert_eq!(data.records_used, 3);
+    let rows: Vec<_> = data
+        .rows
+        .iter()
+        .map(|r| (r.row_items.clone(), r.values.clone()))
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            (vec!["North".to_string()], vec![Some(7.0)]),
+            (vec!["South".to_string()], vec![Some(7.0)]),
+        ]
+    );
+    assert_eq!(data.grand_totals, vec![Some(14.0)]);
+
+    let overview = sheet_overview(
+        state.clone(),
+        SheetOverviewParams {
+            workbook_or_fork_id: workbook_id,
+            sheet_name: "Sheet1".into(),
+            max_regions: None,
+            max_headers: None,
+            include_headers: None,
+        },
+    )
+    .await?;
+    assert!(
+        overview
+            .notes
+            .iter()
+            .any(|n| n.contains("pivot table 'UnitsByRegion'") && n.contains("Sheet1!A1:C5")),
+        "{:?}",
+        overview.notes
+    );
+
+    Ok(())
+}
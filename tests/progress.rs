@@ -0,0 +1,172 @@
+//! Long tools report progress through a `ProgressReporter` and stop without
+//! leaving partial changes once the request is cancelled.
+
+use anyhow::Result;
+use spreadsheet_mcp::progress::{CancelledError, ProgressReporter};
+use spreadsheet_mcp::spawn_cache_warming;
+use spreadsheet_mcp::tools::dod::{
+    ValidateDefinitionOfDoneParams, validate_definition_of_done_with_progress,
+};
+use spreadsheet_mcp::tools::ggen_sync::{SyncGgenParams, sync_ggen_with_progress};
+use tokio_util::sync::CancellationToken;
+
+#[path = "./support/mod.rs"]
+mod support;
+
+fn cancelled_reporter() -> ProgressReporter {
+    let token = CancellationToken::new();
+    token.cancel();
+    ProgressReporter::channel(token).0
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn cancelled_sync_and_dod_stop_before_doing_work() -> Result<()> {
+    // Both tools run against the crate root; a cancelled request must stop
+    // before the first write.
+    let workspace = support::TestWorkspace::new();
+    let state = workspace.app_state();
+
+    let params: SyncGgenParams = serde_json::from_value(serde_json::json!({ "mode": "apply" }))?;
+    let err = sync_ggen_with_progress(state.clone(), params, cancelled_reporter())
+        .await
+        .unwrap_err();
+    assert!(err.is::<CancelledError>(), "{err}");
+
+    let err = validate_definition_of_done_with_progress(
+        state,
+        ValidateDefinitionOfDoneParams {
+            profile: "minimal".to_string(),
+            workspace_path: None,
+            include_remediation: false,
+            include_evidence: false,
+            fail_fast: false,
+        },
+        cancelled_reporter(),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.is::<CancelledError>(), "{err}");
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn startup_cache_warming_stops_on_shutdown() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    for name in ["a.xlsx", "b.xlsx"] {
+        workspace.create_workbook(name, |_| {});
+    }
+
+    // Shutdown already under way: nothing is loaded.
+    let state = workspace.app_state();
+    let shutdown = CancellationToken::new();
+    shutdown.cancel();
+    let err = spawn_cache_warming(state.clone(), shutdown)
+        .await?
+        .unwrap_err();
+    assert!(err.is::<CancelledError>(), "{err}");
+    assert_eq!(state.cache_stats().size, 0);
+
+    let state = workspace.app_state();
+    let warmed = spawn_cache_warming(state.clone(), CancellationToken::new()).await??;
+    assert_eq!(warmed.loaded, 2);
+    assert_eq!(state.cache_stats().size, 2);
+
+    Ok(())
+}
+
+#[cfg(feature = "recalc")]
+#[tokio::test(flavor = "current_thread")]
+async fn transform_batch_reports_ops_and_rolls_back_on_cancel() -> Result<()> {
+    use spreadsheet_mcp::tools::fork::{
+        CreateForkParams, ListStagedChangesParams, TransformBatchParams, TransformOp,
+        TransformTarget, create_fork, list_staged_changes, transform_batch_with_progress,
+    };
+    use spreadsheet_mcp::tools::{ListWorkbooksParams, list_workbooks};
+
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("grid.xlsx", |book| {
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.get_cell_mut("A1").set_value("old");
+        sheet.get_cell_mut("B1").set_value("old");
+    });
+    let state = support::app_state_with_config(workspace.config_with(|cfg| {
+        cfg.recalc_enabled = true;
+    }));
+    let workbook_id = list_workbooks(
+        state.clone(),
+        ListWorkbooksParams {
+            slug_prefix: None,
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?
+    .workbooks[0]
+        .workbook_id
+        .clone();
+    let fork_id = create_fork(
+        state.clone(),
+        CreateForkParams {
+            workbook_or_fork_id: workbook_id,
+        },
+    )
+    .await?
+    .fork_id;
+    let ops: Vec<TransformOp> = ["A1", "B1"]
+        .map(|cell| TransformOp::FillRange {
+            sheet_name: "Sheet1".to_string(),
+            target: TransformTarget::Range {
+                range: cell.to_string(),
+            },
+            value: "new".to_string(),
+            is_formula: false,
+            overwrite_formulas: false,
+        })
+        .into();
+    let params = |mode: &str| TransformBatchParams {
+        fork_id: fork_id.clone(),
+        ops: ops.clone(),
+        mode: Some(mode.to_string()),
+        label: None,
+    };
+    let work_path = state
+        .fork_registry()
+        .expect("fork registry")
+        .get_fork(&fork_id)?
+        .work_path
+        .clone();
+    let a1 = || -> Result<String> {
+        let book = umya_spreadsheet::reader::xlsx::read(&work_path)?;
+        Ok(book.get_sheet(&0).unwrap().get_value("A1"))
+    };
+
+    for mode in ["apply", "preview"] {
+        let err = transform_batch_with_progress(state.clone(), params(mode), cancelled_reporter())
+            .await
+            .unwrap_err();
+        assert!(err.is::<CancelledError>(), "{mode}: {err}");
+    }
+    assert_eq!(a1()?, "old");
+    let staged = list_staged_changes(
+        state.clone(),
+        ListStagedChangesParams {
+            fork_id: fork_id.clone(),
+        },
+    )
+    .await?;
+    assert!(staged.staged_changes.is_empty());
+
+    let (progress, mut updates) = ProgressReporter::channel(CancellationToken::new());
+    let result = transform_batch_with_progress(state.clone(), params("apply"), progress).await?;
+    assert_eq!(result.ops_applied, 2);
+    let mut seen = Vec::new();
+    while let Ok(update) = updates.try_recv() {
+        assert_eq!(update.total, Some(2));
+        seen.push(update.progress);
+    }
+    assert_eq!(seen, vec![0, 1, 2]);
+    assert_eq!(a1()?, "new");
+
+    Ok(())
+}
@@ -0,0 +1,165 @@
+//! MCP prompts are filtered by configuration, validate their arguments and
+//! render numbered steps; argument completion suggests workspace values.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use anyhow::Result;
+use spreadsheet_mcp::prompts;
+use spreadsheet_mcp::tools::{ListWorkbooksParams, list_workbooks};
+
+#[path = "./support/mod.rs"]
+mod support;
+
+fn args(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn prompts_render_steps_and_complete_arguments() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("budget.xlsx", |book| {
+        book.get_sheet_mut(&0)
+            .unwrap()
+            .get_cell_mut("A1")
+            .set_value("Item");
+        book.new_sheet("Summary").unwrap();
+        book.new_sheet("Scenarios").unwrap();
+    });
+    let state = workspace.app_state();
+    let workbook_id = list_workbooks(
+        state.clone(),
+        ListWorkbooksParams {
+            slug_prefix: None,
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?
+    .workbooks[0]
+        .workbook_id
+        .0
+        .clone();
+
+    // safe_fork_edit needs recalc, which is off here.
+    let listed: Vec<String> = prompts::list_prompts(&state.config())
+        .into_iter()
+        .map(|prompt| prompt.name)
+        .collect();
+    assert!(listed.contains(&"profile_workbook".to_string()));
+    assert!(listed.contains(&"run_definition_of_done".to_string()));
+    assert!(!listed.contains(&"safe_fork_edit".to_string()));
+
+    let rendered = prompts::render_prompt(
+        &state,
+        "profile_workbook",
+        &args(&[("workbook_id", &workbook_id), ("sheet_name", "Summary")]),
+    )
+    .await?;
+    assert!(
+        rendered
+            .text
+            .contains("has sheets: Sheet1, Summary, Scenarios")
+    );
+    assert!(
+        rendered
+            .text
+            .contains(&format!("workbook_or_fork_id=`{workbook_id}`"))
+    );
+    assert!(rendered.text.contains("on sheet `Summary`"));
+    assert!(!rendered.text.contains("on each sheet"));
+    assert!(!rendered.text.contains('{'), "{}", rendered.text);
+
+    let dod = prompts::render_prompt(&state, "run_definition_of_done", &HashMap::new()).await?;
+    assert!(dod.text.contains("profile=`comprehensive`"));
+
+    let entity = prompts::render_prompt(
+        &state,
+        "add_entity_and_regenerate",
+        &args(&[("entity_name", "Invoice")]),
+    )
+    .await?;
+    assert!(
+        entity
+            .text
+            .contains("Ask the user which properties `Invoice` needs")
+    );
+    assert!(entity.text.contains("STOP: Show the preview"));
+
+    for (name, arguments, expected) in [
+        (
+            "profile_workbook",
+            args(&[]),
+            "requires argument 'workbook_id'",
+        ),
+        (
+            "profile_workbook",
+            args(&[("workbook_id", &workbook_id), ("sheet_name", "Nope")]),
+            "sheet 'Nope' not found",
+        ),
+        (
+            "run_definition_of_done",
+            args(&[("profile", "quick")]),
+            "must be one of",
+        ),
+        (
+            "run_definition_of_done",
+            args(&[("verbose", "true")]),
+            "has no argument 'verbose'",
+        ),
+        ("safe_fork_edit", args(&[]), "not available"),
+    ] {
+        let err = prompts::render_prompt(&state, name, &arguments)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains(expected), "{name}: {err}");
+    }
+
+    let workbooks =
+        prompts::complete_argument(&state, "profile_workbook", "workbook_id", "", &args(&[]))
+            .await?;
+    assert_eq!(workbooks, vec![workbook_id.clone()]);
+
+    let sheets: HashSet<String> = prompts::complete_argument(
+        &state,
+        "profile_workbook",
+        "sheet_name",
+        "s",
+        &args(&[("workbook_id", &workbook_id)]),
+    )
+    .await?
+    .into_iter()
+    .collect();
+    assert_eq!(
+        sheets,
+        HashSet::from(["Sheet1".into(), "Summary".into(), "Scenarios".into()])
+    );
+
+    let profiles = prompts::complete_argument(
+        &state,
+        "run_definition_of_done",
+        "profile",
+        "St",
+        &args(&[]),
+    )
+    .await?;
+    assert_eq!(profiles, vec!["standard"]);
+
+    Ok(())
+}
+
+#[test]
+fn prompts_hidden_when_a_step_tool_is_disabled() {
+    let workspace = support::TestWorkspace::new();
+    let config = workspace.config_with(|cfg| {
+        cfg.enabled_tools = Some(
+            ["list_workbooks", "describe_workbook"]
+                .map(String::from)
+                .into(),
+        );
+    });
+    assert!(prompts::available_prompts(&config).is_empty());
+}
@@ -0,0 +1,145 @@
+//! render_sheet draws a range natively, without LibreOffice or recalc.
+
+use anyhow::Result;
+use spreadsheet_mcp::tools::render::{RenderFormat, RenderSheetParams, render_sheet};
+use spreadsheet_mcp::tools::{ListWorkbooksParams, list_workbooks};
+use umya_spreadsheet::structs::{
+    Color, ColorScale, ConditionalFormatValueObject, ConditionalFormatValueObjectValues,
+    ConditionalFormatValues, ConditionalFormatting, ConditionalFormattingRule,
+};
+
+mod support;
+
+fn scale_point(kind: ConditionalFormatValueObjectValues) -> ConditionalFormatValueObject {
+    let mut cfvo = ConditionalFormatValueObject::default();
+    cfvo.set_type(kind);
+    cfvo
+}
+
+fn params(
+    workbook_id: &spreadsheet_mcp::model::WorkbookId,
+    format: RenderFormat,
+) -> RenderSheetParams {
+    RenderSheetParams {
+        workbook_or_fork_id: workbook_id.clone(),
+        sheet_name: "Sheet1".to_string(),
+        range: Some("A1:C4".to_string()),
+        format,
+        headers: true,
+        gridlines: true,
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn renders_formatted_values_styles_and_conditional_colors() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("report.xlsx", |book| {
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.get_cell_mut("A1").set_value("Region & Totals");
+        sheet.add_merge_cells("A1:C1");
+        sheet.get_style_mut("A1").get_font_mut().set_bold(true);
+        sheet.get_style_mut("A1").set_background_color("FF4472C4");
+        sheet.get_column_dimension_mut("A").set_width(18.0);
+        for (row, name, amount) in [(2, "North", 1250.5), (3, "South", 980.0), (4, "West", 0.0)] {
+            sheet.get_cell_mut((1, row)).set_value(name);
+            sheet.get_cell_mut((2, row)).set_value_number(amount);
+            sheet
+                .get_style_mut((2, row))
+                .get_number_format_mut()
+                .set_format_code("#,##0.00");
+        }
+
+        let mut scale = ColorScale::default();
+        scale.add_cfvo_collection(scale_point(ConditionalFormatValueObjectValues::Min));
+        scale.add_cfvo_collection(scale_point(ConditionalFormatValueObjectValues::Max));
+        for argb in ["FFF8696B", "FF63BE7B"] {
+            let mut color = Color::default();
+            color.set_argb(argb);
+            scale.add_color_collection(color);
+        }
+        let mut rule = ConditionalFormattingRule::default();
+        rule.set_type(ConditionalFormatValues::ColorScale);
+        rule.set_color_scale(scale);
+        rule.set_priority(1);
+        let mut format = ConditionalFormatting::default();
+        format.get_sequence_of_references_mut().set_sqref("B2:B4");
+        format.add_conditional_collection(rule);
+        sheet.set_conditional_formatting_collection(vec![format]);
+    });
+    let state = workspace.app_state();
+    let workbook_id = list_workbooks(
+        state.clone(),
+        ListWorkbooksParams {
+            slug_prefix: None,
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?
+    .workbooks[0]
+        .workbook_id
+        .clone();
+
+    let html = render_sheet(state.clone(), params(&workbook_id, RenderFormat::Html)).await?;
+    assert!(html.warnings.is_empty(), "{:?}", html.warnings);
+    assert!(html.output_path.ends_with(".html"));
+    let markup = std::fs::read_to_string(html.output_path.trim_start_matches("file://"))?;
+    assert!(markup.contains(r#"colspan="3""#));
+    assert!(markup.contains("Region &amp; Totals"));
+    assert!(markup.contains("1,250.50"));
+    assert!(markup.contains("background:#4472C4"));
+    // Lowest value takes the first scale color, highest the last.
+    assert!(markup.contains("background:#F8696B"));
+    assert!(markup.contains("background:#63BE7B"));
+
+    let svg = render_sheet(state.clone(), params(&workbook_id, RenderFormat::Svg)).await?;
+    let markup = std::fs::read_to_string(svg.output_path.trim_start_matches("file://"))?;
+    assert!(markup.starts_with("<svg"));
+    assert!(markup.contains("980.00"));
+    assert!(markup.contains(r#"font-weight="bold""#));
+    // Headers plus columns of 126, 64 and 64 pixels.
+    assert_eq!(svg.width_px, 40 + 126 + 64 + 64);
+    assert!(svg.height_px > 20);
+
+    let png = render_sheet(state.clone(), params(&workbook_id, RenderFormat::Png)).await?;
+    let bytes = std::fs::read(png.output_path.trim_start_matches("file://"))?;
+    assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(png.size_bytes, bytes.len() as u64);
+    assert_eq!((png.width_px, png.height_px), (svg.width_px, svg.height_px));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn rejects_ranges_past_the_render_limits() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("wide.xlsx", |book| {
+        book.get_sheet_mut(&0)
+            .unwrap()
+            .get_cell_mut("A1")
+            .set_value("x");
+    });
+    let state = workspace.app_state();
+    let workbook_id = list_workbooks(
+        state.clone(),
+        ListWorkbooksParams {
+            slug_prefix: None,
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?
+    .workbooks[0]
+        .workbook_id
+        .clone();
+
+    let mut too_wide = params(&workbook_id, RenderFormat::Svg);
+    too_wide.range = Some("A1:AF10".to_string());
+    let err = render_sheet(state.clone(), too_wide).await.unwrap_err();
+    assert!(err.to_string().contains("max 100 x 30"), "{err}");
+
+    let mut named = params(&workbook_id, RenderFormat::Svg);
+    named.range = Some("Sales".to_string());
+    assert!(render_sheet(state.clone(), named).await.is_err());
+    Ok(())
+}
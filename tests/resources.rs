@@ -0,0 +1,142 @@
+//! MCP resources list and read workbooks, sheets, fork changesets and ggen
+//! artifacts, and fork edits are published for resource subscriptions.
+
+#![cfg(feature = "recalc")]
+
+use anyhow::Result;
+use spreadsheet_mcp::resources::{self, ArtifactKind, ResourceUri};
+use spreadsheet_mcp::tools::fork::{
+    CellEdit, CreateForkParams, EditBatchParams, SheetSettingsBatchParams, create_fork, edit_batch,
+    sheet_settings_batch,
+};
+use spreadsheet_mcp::tools::{ListWorkbooksParams, list_workbooks};
+
+#[path = "./support/mod.rs"]
+mod support;
+
+#[tokio::test(flavor = "current_thread")]
+async fn resources_list_read_and_publish_fork_changes() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("plan.xlsx", |book| {
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.get_cell_mut("A1").set_value("Month");
+        sheet.get_cell_mut("B1").set_value("Units");
+        sheet.get_cell_mut("A2").set_value("Jan");
+        sheet.get_cell_mut("B2").set_value_number(12);
+        book.new_sheet("Q1 Plan & Notes").unwrap();
+    });
+    let receipts = workspace.root().join(".ggen/receipts");
+    std::fs::create_dir_all(&receipts)?;
+    std::fs::write(receipts.join("sync-1.json"), r#"{"sync_id": "sync-1"}"#)?;
+
+    let state = support::app_state_with_config(workspace.config_with(|cfg| {
+        cfg.recalc_enabled = true;
+    }));
+    let workbook_id = list_workbooks(
+        state.clone(),
+        ListWorkbooksParams {
+            slug_prefix: None,
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?
+    .workbooks[0]
+        .workbook_id
+        .clone();
+    let fork_id = create_fork(
+        state.clone(),
+        CreateForkParams {
+            workbook_or_fork_id: workbook_id.clone(),
+        },
+    )
+    .await?
+    .fork_id;
+
+    let listed: Vec<String> = resources::list_resources(&state)?
+        .into_iter()
+        .map(|resource| resource.raw.uri)
+        .collect();
+    for expected in [
+        format!("workbook://{}", workbook_id.as_str()),
+        format!("workbook://{fork_id}"),
+        format!("fork://{fork_id}/changeset"),
+        "ggen://receipts/sync-1.json".to_string(),
+    ] {
+        assert!(listed.contains(&expected), "{expected} not in {listed:?}");
+    }
+
+    let workbook = resources::read_workbook(state.clone(), workbook_id.clone()).await?;
+    let plan = workbook
+        .sheets
+        .iter()
+        .find(|sheet| sheet.name == "Q1 Plan & Notes")
+        .expect("sheet link");
+    assert_eq!(
+        plan.uri,
+        format!(
+            "workbook://{}/sheet/Q1%20Plan%20%26%20Notes",
+            workbook_id.as_str()
+        )
+    );
+    let ResourceUri::Sheet {
+        workbook_id: linked,
+        sheet_name,
+    } = ResourceUri::parse(&plan.uri)?
+    else {
+        panic!("sheet uri parsed as another resource");
+    };
+    assert_eq!(linked, workbook_id);
+    let overview = resources::read_sheet(state.clone(), linked, sheet_name).await?;
+    assert_eq!(overview.sheet_name, "Q1 Plan & Notes");
+
+    let receipt =
+        resources::read_artifact(state.clone(), ArtifactKind::Receipt, "sync-1.json".into())
+            .await?;
+    assert!(receipt.contains("sync-1"));
+    assert!(
+        resources::read_artifact(state.clone(), ArtifactKind::Report, "missing.md".into())
+            .await
+            .is_err()
+    );
+
+    // Edits through the registry and in-place batch edits both bump the fork.
+    let registry = state.fork_registry().expect("fork registry");
+    let mut changes = registry.subscribe_changes();
+    edit_batch(
+        state.clone(),
+        EditBatchParams {
+            fork_id: fork_id.clone(),
+            sheet_name: "Sheet1".to_string(),
+            edits: vec![CellEdit {
+                address: "B2".to_string(),
+                value: "15".to_string(),
+                is_formula: false,
+            }],
+        },
+    )
+    .await?;
+    assert_eq!(changes.try_recv()?, fork_id);
+
+    sheet_settings_batch(
+        state.clone(),
+        SheetSettingsBatchParams {
+            fork_id: fork_id.clone(),
+            ops: serde_json::from_str(
+                r#"[{"kind": "freeze_panes", "sheet_name": "Sheet1", "rows": 1}]"#,
+            )?,
+            mode: None,
+            label: None,
+        },
+    )
+    .await?;
+    assert_eq!(changes.try_recv()?, fork_id);
+
+    let changeset = resources::read_changeset(state.clone(), fork_id.clone()).await?;
+    assert!(changeset.summary.total_changes > 0);
+
+    registry.discard_fork(&fork_id)?;
+    assert_eq!(changes.try_recv()?, fork_id);
+
+    Ok(())
+}
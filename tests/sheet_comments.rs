@@ -0,0 +1,192 @@
+//! sheet_comments reads notes and threaded comments; comment_batch writes them in a fork.
+
+#![cfg(feature = "recalc")]
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use spreadsheet_mcp::diff::Change;
+use spreadsheet_mcp::diff::comments::CommentDiff;
+use spreadsheet_mcp::model::{CommentKind, SheetComment, WorkbookId};
+use spreadsheet_mcp::state::AppState;
+use spreadsheet_mcp::tools::fork::{
+    ApplyStagedChangeParams, CommentAction, CommentBatchParams, CommentOp, CreateForkParams,
+    GetChangesetParams, apply_staged_change, comment_batch, create_fork, get_changeset,
+};
+use spreadsheet_mcp::tools::{SheetCommentsParams, sheet_comments};
+use umya_spreadsheet::structs::Comment;
+
+#[path = "./support/mod.rs"]
+mod support;
+
+async fn setup() -> Result<(support::TestWorkspace, Arc<AppState>, WorkbookId)> {
+    support::recalc_workbook("review.xlsx", |book| {
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.get_cell_mut("A2").set_value_number(1);
+        sheet.get_cell_mut("B2").set_value_number(2);
+        let mut comment = Comment::default();
+        comment.new_comment("A2");
+        comment.set_author("Reviewer");
+        comment.set_text_string("Check this");
+        sheet.add_comments(comment);
+    })
+    .await
+}
+
+async fn comments(state: &Arc<AppState>, id: &str) -> Result<Vec<SheetComment>> {
+    let resp = sheet_comments(
+        state.clone(),
+        SheetCommentsParams {
+            workbook_or_fork_id: WorkbookId(id.to_string()),
+            sheet_name: Some("Sheet1".to_string()),
+            unresolved_only: false,
+            limit: 100,
+            offset: 0,
+        },
+    )
+    .await?;
+    Ok(resp.comments)
+}
+
+fn op(address: &str, action: CommentAction) -> CommentOp {
+    CommentOp {
+        sheet_name: "Sheet1".to_string(),
+        address: address.to_string(),
+        action,
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn comment_batch_threads_replies_and_resolves() -> Result<()> {
+    let (_workspace, state, workbook_id) = setup().await?;
+
+    let base = comments(&state, &workbook_id.0).await?;
+    assert_eq!(base.len(), 1);
+    assert_eq!(base[0].address, "A2");
+    assert_eq!(base[0].kind, CommentKind::Note);
+    assert_eq!(base[0].author.as_deref(), Some("Reviewer"));
+    assert_eq!(base[0].text, "Check this");
+
+    let fork = create_fork(
+        state.clone(),
+        CreateForkParams {
+            workbook_or_fork_id: workbook_id,
+        },
+    )
+    .await?;
+    let resp = comment_batch(
+        state.clone(),
+        CommentBatchParams {
+            fork_id: fork.fork_id.clone(),
+            ops: vec![
+                op(
+                    "b2",
+                    CommentAction::Add {
+                        text: "Where does this come from?".to_string(),
+                        author: Some("Reviewer".to_string()),
+                        threaded: true,
+                    },
+                ),
+                op(
+                    "B2",
+                    CommentAction::Reply {
+                        text: "From the Q3 ledger.".to_string(),
+                        author: Some("Agent".to_string()),
+                    },
+                ),
+                op("A2", CommentAction::Resolve { resolved: true }),
+            ],
+            mode: None,
+            label: None,
+        },
+    )
+    .await?;
+    assert_eq!(resp.summary.counts.get("comments_added"), Some(&1));
+    assert_eq!(resp.summary.counts.get("replies_added"), Some(&1));
+    assert_eq!(resp.summary.counts.get("comments_resolved"), Some(&1));
+
+    let forked = comments(&state, &fork.fork_id).await?;
+    assert_eq!(forked.len(), 2);
+    let a2 = &forked[0];
+    assert_eq!(a2.address, "A2");
+    assert_eq!(a2.kind, CommentKind::Thread);
+    assert_eq!(a2.text, "Check this");
+    assert_eq!(a2.resolved, Some(true));
+    let b2 = &forked[1];
+    assert_eq!(b2.kind, CommentKind::Thread);
+    assert_eq!(b2.resolved, Some(false));
+    assert_eq!(b2.replies.len(), 1);
+    assert_eq!(b2.replies[0].author.as_deref(), Some("Agent"));
+    assert_eq!(b2.replies[0].text, "From the Q3 ledger.");
+
+    let changeset = get_changeset(
+        state.clone(),
+        GetChangesetParams {
+            fork_id: fork.fork_id.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert!(changeset.changes.iter().any(|c| matches!(
+        c,
+        Change::Comment(CommentDiff::CommentResolved { address, resolved: true, .. })
+            if address == "A2"
+    )));
+    assert!(changeset.changes.iter().any(|c| matches!(
+        c,
+        Change::Comment(CommentDiff::CommentAdded { address, text, .. })
+            if address == "B2" && text.contains("Agent: From the Q3 ledger.")
+    )));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn comment_batch_preview_stages_delete() -> Result<()> {
+    let (_workspace, state, workbook_id) = setup().await?;
+    let fork = create_fork(
+        state.clone(),
+        CreateForkParams {
+            workbook_or_fork_id: workbook_id,
+        },
+    )
+    .await?;
+
+    let resp = comment_batch(
+        state.clone(),
+        CommentBatchParams {
+            fork_id: fork.fork_id.clone(),
+            ops: vec![op("A2", CommentAction::Delete)],
+            mode: Some("preview".to_string()),
+            label: None,
+        },
+    )
+    .await?;
+    let change_id = resp.change_id.expect("staged change");
+    assert_eq!(comments(&state, &fork.fork_id).await?.len(), 1);
+
+    apply_staged_change(
+        state.clone(),
+        ApplyStagedChangeParams {
+            fork_id: fork.fork_id.clone(),
+            change_id,
+        },
+    )
+    .await?;
+    assert!(comments(&state, &fork.fork_id).await?.is_empty());
+
+    let err = comment_batch(
+        state.clone(),
+        CommentBatchParams {
+            fork_id: fork.fork_id.clone(),
+            ops: vec![op("A2", CommentAction::Delete)],
+            mode: None,
+            label: None,
+        },
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("no comment at Sheet1!A2"));
+
+    Ok(())
+}
@@ -0,0 +1,246 @@
+//! sheet_settings_batch sets panes, visibility, outlines and protection in a
+//! fork, and sheet_overview/describe_workbook report them.
+
+#![cfg(feature = "recalc")]
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use spreadsheet_mcp::diff::Change;
+use spreadsheet_mcp::diff::sheets::SheetDiff;
+use spreadsheet_mcp::model::{SheetOverviewResponse, WorkbookId};
+use spreadsheet_mcp::state::AppState;
+use spreadsheet_mcp::tools::fork::{
+    ApplyStagedChangeParams, GetChangesetParams, SheetSettingsBatchParams,
+    SheetSettingsBatchResponse, SheetSettingsOp, SheetState, apply_staged_change, get_changeset,
+    sheet_settings_batch,
+};
+use spreadsheet_mcp::tools::{
+    DescribeWorkbookParams, SheetOverviewParams, describe_workbook, sheet_overview,
+};
+
+#[path = "./support/mod.rs"]
+mod support;
+
+async fn setup() -> Result<(support::TestWorkspace, Arc<AppState>, String)> {
+    support::recalc_fork("model.xlsx", |book| {
+        let sheet = book.get_sheet_mut(&0).unwrap();
+        sheet.get_cell_mut("A1").set_value("Input");
+        sheet.get_cell_mut("B1").set_value("Value");
+        for row in 2..=6 {
+            sheet
+                .get_cell_mut((1, row))
+                .set_value(format!("item {row}"));
+            sheet.get_cell_mut((2, row)).set_value_number(row as f64);
+        }
+        let lookups = book.new_sheet("Lookups").unwrap();
+        lookups.get_cell_mut("A1").set_value("rate");
+    })
+    .await
+}
+
+async fn run(
+    state: &Arc<AppState>,
+    fork_id: &str,
+    ops: Vec<SheetSettingsOp>,
+    mode: Option<&str>,
+) -> Result<SheetSettingsBatchResponse> {
+    sheet_settings_batch(
+        state.clone(),
+        SheetSettingsBatchParams {
+            fork_id: fork_id.to_string(),
+            ops,
+            mode: mode.map(str::to_string),
+            label: None,
+        },
+    )
+    .await
+}
+
+async fn overview(
+    state: &Arc<AppState>,
+    fork_id: &str,
+    sheet: &str,
+) -> Result<SheetOverviewResponse> {
+    sheet_overview(
+        state.clone(),
+        SheetOverviewParams {
+            workbook_or_fork_id: WorkbookId(fork_id.to_string()),
+            sheet_name: sheet.to_string(),
+            max_regions: None,
+            max_headers: None,
+            include_headers: None,
+        },
+    )
+    .await
+}
+
+fn ops(json: &str) -> Vec<SheetSettingsOp> {
+    serde_json::from_str(json).expect("valid ops")
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn sheet_settings_batch_sets_views_outlines_and_protection() -> Result<()> {
+    let (_workspace, state, fork_id) = setup().await?;
+
+    let result = run(
+        &state,
+        &fork_id,
+        ops(r##"[
+            {"kind": "freeze_panes", "sheet_name": "Sheet1", "rows": 1, "cols": 1},
+            {"kind": "set_tab_color", "sheet_name": "Sheet1", "color": "#00B050"},
+            {"kind": "set_view", "sheet_name": "Sheet1", "zoom": 85, "show_gridlines": false},
+            {"kind": "group", "sheet_name": "Sheet1", "range": "3:5", "collapsed": true},
+            {"kind": "group", "sheet_name": "Sheet1", "range": "C:D"},
+            {"kind": "set_cell_lock", "sheet_name": "Sheet1", "range": "B2:B6", "locked": false},
+            {"kind": "protect_sheet", "sheet_name": "Sheet1", "password": "secret",
+             "allow": ["select_unlocked_cells", "sort"]},
+            {"kind": "set_visibility", "sheet_name": "Lookups", "state": "very_hidden"},
+            {"kind": "protect_workbook"}
+        ]"##),
+        None,
+    )
+    .await?;
+    assert_eq!(result.ops_applied, 9);
+    assert_eq!(result.summary.counts.get("group"), Some(&2));
+    assert!(
+        result.summary.warnings.is_empty(),
+        "{:?}",
+        result.summary.warnings
+    );
+
+    let settings = overview(&state, &fork_id, "Sheet1")
+        .await?
+        .sheet_settings
+        .expect("sheet settings");
+    let frozen = settings.frozen.expect("frozen panes");
+    assert_eq!((frozen.rows, frozen.cols), (1, 1));
+    assert_eq!(settings.tab_color.as_deref(), Some("FF00B050"));
+    assert_eq!(settings.zoom, 85);
+    assert!(!settings.show_gridlines);
+    assert_eq!(settings.row_groups.len(), 1);
+    assert_eq!(settings.row_groups[0].range, "3:5");
+    assert!(settings.row_groups[0].collapsed);
+    assert_eq!(settings.column_groups[0].range, "C:D");
+    let protection = settings.protection.expect("sheet protection");
+    assert!(protection.password_set);
+    assert_eq!(protection.allowed, vec!["select_unlocked_cells", "sort"]);
+    assert_eq!(settings.unlocked_ranges, vec!["B2:B6"]);
+
+    let lookups = overview(&state, &fork_id, "Lookups").await?;
+    assert_eq!(
+        lookups.sheet_settings.expect("settings").state,
+        "very_hidden"
+    );
+
+    let description = describe_workbook(
+        state.clone(),
+        DescribeWorkbookParams {
+            workbook_or_fork_id: WorkbookId(fork_id.clone()),
+        },
+    )
+    .await?;
+    let protection = description.protection.expect("workbook protection");
+    assert!(protection.lock_structure);
+    assert!(!protection.password_set);
+
+    let changeset = get_changeset(
+        state.clone(),
+        GetChangesetParams {
+            fork_id: fork_id.clone(),
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert!(changeset.changes.iter().any(|c| matches!(
+        c,
+        Change::Sheet(SheetDiff::SheetVisibilityChanged { sheet, new_state, .. })
+            if sheet == "Lookups" && new_state == "veryHidden"
+    )));
+
+    // The only visible sheet cannot be hidden.
+    let err = run(
+        &state,
+        &fork_id,
+        vec![SheetSettingsOp::SetVisibility {
+            sheet_name: "Sheet1".to_string(),
+            state: SheetState::Hidden,
+        }],
+        None,
+    )
+    .await
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("at least one visible sheet"),
+        "{err}"
+    );
+
+    run(
+        &state,
+        &fork_id,
+        ops(r#"[
+            {"kind": "ungroup", "sheet_name": "Sheet1", "range": "3:5"},
+            {"kind": "freeze_panes", "sheet_name": "Sheet1"},
+            {"kind": "unprotect_sheet", "sheet_name": "Sheet1"},
+            {"kind": "unprotect_workbook"}
+        ]"#),
+        None,
+    )
+    .await?;
+    let settings = overview(&state, &fork_id, "Sheet1")
+        .await?
+        .sheet_settings
+        .expect("sheet settings");
+    assert!(settings.row_groups.is_empty());
+    assert!(settings.frozen.is_none());
+    assert!(settings.protection.is_none());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn sheet_settings_batch_preview_stages_changes() -> Result<()> {
+    let (_workspace, state, fork_id) = setup().await?;
+
+    let preview = run(
+        &state,
+        &fork_id,
+        ops(r#"[{"kind": "set_cell_lock", "sheet_name": "Sheet1", "range": "B:B", "locked": false}]"#),
+        Some("preview"),
+    )
+    .await?;
+    assert_eq!(preview.mode, "preview");
+    assert!(
+        preview
+            .summary
+            .warnings
+            .iter()
+            .any(|w| w.contains("not protected")),
+        "{:?}",
+        preview.summary.warnings
+    );
+    let before = overview(&state, &fork_id, "Sheet1").await?;
+    assert!(
+        before
+            .sheet_settings
+            .expect("settings")
+            .unlocked_ranges
+            .is_empty()
+    );
+
+    apply_staged_change(
+        state.clone(),
+        ApplyStagedChangeParams {
+            fork_id: fork_id.clone(),
+            change_id: preview.change_id.expect("staged change"),
+        },
+    )
+    .await?;
+    let after = overview(&state, &fork_id, "Sheet1").await?;
+    assert_eq!(
+        after.sheet_settings.expect("settings").unlocked_ranges,
+        vec!["B:B"]
+    );
+
+    Ok(())
+}
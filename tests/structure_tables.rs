@@ -0,0 +1,236 @@
+//! structure_batch table ops: create, rename, resize, columns, totals and style.
+
+#![cfg(feature = "recalc")]
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use spreadsheet_mcp::model::WorkbookId;
+use spreadsheet_mcp::tools::fork::{
+    CellEdit, EditBatchParams, StructureBatchParams, StructureOp, edit_batch, structure_batch,
+};
+
+mod support;
+
+async fn forked_orders() -> Result<(
+    support::TestWorkspace,
+    std::sync::Arc<spreadsheet_mcp::state::AppState>,
+    String,
+)> {
+    support::recalc_fork("orders.xlsx", |book| {
+        let sheet = book.get_sheet_by_name_mut("Sheet1").unwrap();
+        for (col, header) in ["Item", "Qty", "", "Price"].iter().enumerate() {
+            sheet
+                .get_cell_mut((col as u32 + 1, 1))
+                .set_value(header.to_string());
+        }
+        for (row, item, qty, price) in [(2, "Nuts", 3, 1.5), (3, "Bolts", 5, 0.25)] {
+            sheet.get_cell_mut((1, row)).set_value(item);
+            sheet.get_cell_mut((2, row)).set_value_number(qty);
+            sheet.get_cell_mut((4, row)).set_value_number(price);
+        }
+        sheet
+            .get_cell_mut("G1")
+            .set_formula("SUM(D2:D3)".to_string());
+    })
+    .await
+}
+
+async fn apply(
+    state: &std::sync::Arc<spreadsheet_mcp::state::AppState>,
+    fork_id: &str,
+    ops: Vec<StructureOp>,
+) -> Result<spreadsheet_mcp::tools::fork::StructureBatchResponse> {
+    structure_batch(
+        state.clone(),
+        StructureBatchParams {
+            fork_id: fork_id.to_string(),
+            ops,
+            mode: Some("apply".to_string()),
+            label: None,
+        },
+    )
+    .await
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn table_lifecycle_keeps_structured_references_valid() -> Result<()> {
+    let (_workspace, state, fork_id) = forked_orders().await?;
+
+    let resp = apply(
+        &state,
+        &fork_id,
+        vec![StructureOp::CreateTable {
+            sheet_name: "Sheet1".to_string(),
+            range: "A1:D3".to_string(),
+            table_name: Some("Orders".to_string()),
+            style: None,
+        }],
+    )
+    .await?;
+    assert_eq!(resp.summary.counts.get("tables_created"), Some(&1));
+    assert!(
+        resp.summary
+            .warnings
+            .iter()
+            .any(|w| w.contains("C1 was blank"))
+    );
+
+    // Formulas written against the table before it is renamed and reshaped.
+    edit_batch(
+        state.clone(),
+        EditBatchParams {
+            fork_id: fork_id.clone(),
+            sheet_name: "Sheet1".to_string(),
+            edits: vec![
+                CellEdit {
+                    address: "H1".to_string(),
+                    value: "SUM(Orders[Qty])".to_string(),
+                    is_formula: true,
+                },
+                CellEdit {
+                    address: "H2".to_string(),
+                    value: "SUM(Orders[Column3])".to_string(),
+                    is_formula: true,
+                },
+            ],
+        },
+    )
+    .await?;
+
+    let resp = apply(
+        &state,
+        &fork_id,
+        vec![
+            StructureOp::RenameTable {
+                table_name: "Orders".to_string(),
+                new_name: "Sales".to_string(),
+            },
+            StructureOp::RemoveTableColumn {
+                table_name: "Sales".to_string(),
+                column_name: "Column3".to_string(),
+            },
+            StructureOp::AddTableColumn {
+                table_name: "Sales".to_string(),
+                column_name: "Region".to_string(),
+                position: Some(1),
+            },
+            StructureOp::SetTableTotals {
+                table_name: "Sales".to_string(),
+                show: true,
+                functions: BTreeMap::from([("Qty".to_string(), "sum".to_string())]),
+                label: None,
+            },
+            StructureOp::SetTableStyle {
+                table_name: "Sales".to_string(),
+                style: Some("TableStyleLight9".to_string()),
+                show_row_stripes: None,
+                show_column_stripes: Some(true),
+                show_first_column: None,
+                show_last_column: None,
+            },
+        ],
+    )
+    .await?;
+    assert_eq!(resp.summary.counts.get("tables_renamed"), Some(&1));
+    assert_eq!(resp.summary.counts.get("table_columns_removed"), Some(&1));
+    assert_eq!(resp.summary.counts.get("table_columns_added"), Some(&1));
+
+    let fork_wb = state.open_workbook(&WorkbookId(fork_id.clone())).await?;
+    fork_wb.with_sheet("Sheet1", |sheet| {
+        let table = &sheet.get_tables()[0];
+        assert_eq!(table.get_display_name(), "Sales");
+        let (start, end) = table.get_area();
+        assert_eq!(
+            (start.get_coordinate(), end.get_coordinate()),
+            ("A1".to_string(), "D4".to_string())
+        );
+        let columns: Vec<&str> = table.get_columns().iter().map(|c| c.get_name()).collect();
+        assert_eq!(columns, vec!["Item", "Region", "Qty", "Price"]);
+        assert_eq!(
+            table.get_style_info().map(|s| s.get_name()),
+            Some("TableStyleLight9")
+        );
+
+        let formula = |addr: &str| sheet.get_cell(addr).unwrap().get_formula().to_string();
+        assert_eq!(formula("H1"), "SUM(Sales[Qty])");
+        assert_eq!(formula("H2"), "SUM(#REF!)");
+        // Price moved from D to C and back to D; the A1 reference follows it.
+        assert_eq!(formula("G1"), "SUM(D2:D3)");
+        assert_eq!(formula("C4"), "SUBTOTAL(109,Sales[Qty])");
+        assert_eq!(sheet.get_cell("A4").unwrap().get_value(), "Total");
+        assert_eq!(sheet.get_cell("C2").unwrap().get_value(), "3");
+        assert_eq!(sheet.get_cell("D3").unwrap().get_value(), "0.25");
+    })?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn table_ops_reject_invalid_shapes() -> Result<()> {
+    let (_workspace, state, fork_id) = forked_orders().await?;
+    apply(
+        &state,
+        &fork_id,
+        vec![StructureOp::CreateTable {
+            sheet_name: "Sheet1".to_string(),
+            range: "A1:D3".to_string(),
+            table_name: None,
+            style: None,
+        }],
+    )
+    .await?;
+
+    let err = apply(
+        &state,
+        &fork_id,
+        vec![StructureOp::CreateTable {
+            sheet_name: "Sheet1".to_string(),
+            range: "C2:E5".to_string(),
+            table_name: Some("Other".to_string()),
+            style: None,
+        }],
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("overlaps table 'Table1'"));
+
+    let err = apply(
+        &state,
+        &fork_id,
+        vec![StructureOp::RenameTable {
+            table_name: "Table1".to_string(),
+            new_name: "A1".to_string(),
+        }],
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("not a valid table name"));
+
+    let err = apply(
+        &state,
+        &fork_id,
+        vec![StructureOp::ResizeTable {
+            table_name: "Table1".to_string(),
+            range: "A2:D5".to_string(),
+        }],
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("must stay on row 1"));
+
+    let resp = apply(
+        &state,
+        &fork_id,
+        vec![StructureOp::ResizeTable {
+            table_name: "Table1".to_string(),
+            range: "A1:E6".to_string(),
+        }],
+    )
+    .await?;
+    let rewrite = &resp.rewrites[0];
+    assert_eq!(rewrite.before, "A1:D3");
+    assert_eq!(rewrite.after.as_deref(), Some("A1:E6"));
+
+    Ok(())
+}
@@ -7,12 +7,14 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::Result;
+use spreadsheet_mcp::model::WorkbookId;
 use spreadsheet_mcp::state::AppState;
+use spreadsheet_mcp::tools::{ListWorkbooksParams, list_workbooks};
 use spreadsheet_mcp::{ServerConfig, SpreadsheetServer, TransportKind};
 use tempfile::{TempDir, tempdir};
 use umya_spreadsheet::{self, Spreadsheet};
 
-const DEFAULT_EXTENSIONS: &[&str] = &["xlsx", "xlsm", "xls", "xlsb"];
+const DEFAULT_EXTENSIONS: &[&str] = &["xlsx", "xlsm", "xls", "xlsb", "ods", "csv", "tsv"];
 
 #[allow(dead_code)]
 pub fn build_workbook<F>(f: F) -> PathBuf
@@ -123,6 +125,52 @@ pub fn app_state_with_config(config: ServerConfig) -> Arc<AppState> {
     Arc::new(AppState::new(config))
 }
 
+/// A workspace holding one workbook built by `f`, a recalc-enabled state over
+/// it, and the workbook's id.
+pub async fn recalc_workbook<F>(
+    name: &str,
+    f: F,
+) -> Result<(TestWorkspace, Arc<AppState>, WorkbookId)>
+where
+    F: FnOnce(&mut Spreadsheet),
+{
+    let workspace = TestWorkspace::new();
+    workspace.create_workbook(name, f);
+    let state = app_state_with_config(workspace.config_with(|cfg| {
+        cfg.recalc_enabled = true;
+    }));
+    let list = list_workbooks(
+        state.clone(),
+        ListWorkbooksParams {
+            slug_prefix: None,
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?;
+    let workbook_id = list.workbooks[0].workbook_id.clone();
+    Ok((workspace, state, workbook_id))
+}
+
+/// Like `recalc_workbook`, returning the id of a fork of the workbook.
+#[cfg(feature = "recalc")]
+pub async fn recalc_fork<F>(name: &str, f: F) -> Result<(TestWorkspace, Arc<AppState>, String)>
+where
+    F: FnOnce(&mut Spreadsheet),
+{
+    use spreadsheet_mcp::tools::fork::{CreateForkParams, create_fork};
+
+    let (workspace, state, workbook_id) = recalc_workbook(name, f).await?;
+    let fork = create_fork(
+        state.clone(),
+        CreateForkParams {
+            workbook_or_fork_id: workbook_id,
+        },
+    )
+    .await?;
+    Ok((workspace, state, fork.fork_id))
+}
+
 pub fn touch_file(path: &Path) {
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent).expect("create dir");
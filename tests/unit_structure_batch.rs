@@ -4,7 +4,7 @@ use anyhow::Result;
 use spreadsheet_mcp::model::WorkbookId;
 use spreadsheet_mcp::styles::descriptor_from_style;
 use spreadsheet_mcp::tools::fork::{
-    ApplyStagedChangeParams, CreateForkParams, StructureBatchParams, StructureOp,
+    ApplyStagedChangeParams, CreateForkParams, RewriteKind, StructureBatchParams, StructureOp,
     apply_staged_change, create_fork, structure_batch,
 };
 use spreadsheet_mcp::tools::{ListWorkbooksParams, list_workbooks};
@@ -551,3 +551,362 @@ async fn structure_batch_delete_sheet_guard_prevents_last_sheet() -> Result<()>
 
     Ok(())
 }
+
+async fn fork_first_workbook(
+    state: &std::sync::Arc<spreadsheet_mcp::state::AppState>,
+) -> Result<String> {
+    let list = list_workbooks(
+        state.clone(),
+        ListWorkbooksParams {
+            slug_prefix: None,
+            folder: None,
+            path_glob: None,
+        },
+    )
+    .await?;
+    let fork = create_fork(
+        state.clone(),
+        CreateForkParams {
+            workbook_or_fork_id: list.workbooks[0].workbook_id.clone(),
+        },
+    )
+    .await?;
+    Ok(fork.fork_id)
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn structure_batch_delete_sheet_turns_references_into_ref_errors() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("structure_delete_refs.xlsx", |book| {
+        let inputs = book.get_sheet_mut(&0).unwrap();
+        inputs.set_name("Inputs");
+        inputs.get_cell_mut("A1").set_value_number(1);
+        book.new_sheet("Calc").unwrap();
+        let calc = book.get_sheet_by_name_mut("Calc").unwrap();
+        calc.get_cell_mut("A1")
+            .set_formula("SUM(Inputs!A1:A2)+1".to_string());
+    });
+
+    let state = recalc_state(&workspace);
+    let fork_id = fork_first_workbook(&state).await?;
+
+    let resp = structure_batch(
+        state.clone(),
+        StructureBatchParams {
+            fork_id: fork_id.clone(),
+            ops: vec![StructureOp::DeleteSheet {
+                name: "Inputs".to_string(),
+            }],
+            mode: Some("apply".to_string()),
+            label: None,
+        },
+    )
+    .await?;
+
+    assert!(resp.summary.warnings.is_empty());
+    assert_eq!(resp.summary.counts.get("formulas_rewritten"), Some(&1));
+    let rewrite = &resp.rewrites[0];
+    assert_eq!(rewrite.kind, RewriteKind::Formula);
+    assert_eq!(rewrite.sheet.as_deref(), Some("Calc"));
+    assert_eq!(rewrite.target.as_deref(), Some("A1"));
+
+    let fork_wb = state.open_workbook(&WorkbookId(fork_id)).await?;
+    let formula = fork_wb.with_sheet("Calc", |sheet| {
+        sheet.get_cell("A1").unwrap().get_formula().to_string()
+    })?;
+    assert_eq!(formula, "SUM(#REF!A1:A2)+1");
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn structure_batch_move_range_repoints_dependent_formulas() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("structure_move_refs.xlsx", |book| {
+        let sheet = book.get_sheet_by_name_mut("Sheet1").unwrap();
+        sheet.get_cell_mut("A1").set_value_number(5);
+        sheet.get_cell_mut("A2").set_formula("A1*3".to_string());
+        sheet.get_cell_mut("E1").set_value_number(7);
+        sheet.get_cell_mut("B1").set_formula("A2+E1".to_string());
+        book.new_sheet("Other").unwrap();
+        let other = book.get_sheet_by_name_mut("Other").unwrap();
+        other
+            .get_cell_mut("A1")
+            .set_formula("Sheet1!A1*2".to_string());
+    });
+
+    let state = recalc_state(&workspace);
+    let fork_id = fork_first_workbook(&state).await?;
+
+    structure_batch(
+        state.clone(),
+        StructureBatchParams {
+            fork_id: fork_id.clone(),
+            ops: vec![StructureOp::MoveRange {
+                sheet_name: "Sheet1".to_string(),
+                dest_sheet_name: None,
+                src_range: "A1:A2".to_string(),
+                dest_anchor: "C3".to_string(),
+                include_styles: false,
+                include_formulas: true,
+            }],
+            mode: Some("apply".to_string()),
+            label: None,
+        },
+    )
+    .await?;
+
+    let fork_wb = state.open_workbook(&WorkbookId(fork_id)).await?;
+    let (moved, dependent) = fork_wb.with_sheet("Sheet1", |sheet| {
+        (
+            sheet.get_cell("C4").unwrap().get_formula().to_string(),
+            sheet.get_cell("B1").unwrap().get_formula().to_string(),
+        )
+    })?;
+    // Moved formulas keep pointing at the cells they referred to.
+    assert_eq!(moved, "C3*3");
+    assert_eq!(dependent, "C4+E1");
+    let external = fork_wb.with_sheet("Other", |sheet| {
+        sheet.get_cell("A1").unwrap().get_formula().to_string()
+    })?;
+    assert_eq!(external, "Sheet1!C3*2");
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn structure_batch_insert_rows_shifts_range_bearing_objects() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("structure_rows_objects.xlsx", |book| {
+        let sheet = book.get_sheet_by_name_mut("Sheet1").unwrap();
+        sheet.get_cell_mut("A5").set_value("merged");
+        sheet.add_merge_cells("A5:B5");
+        sheet
+            .add_defined_name("Inputs", "Sheet1!$A$2:$A$6")
+            .expect("defined name");
+        sheet
+            .add_defined_name("_xlnm.Print_Area", "Sheet1!$A$1:$B$10")
+            .expect("print area");
+    });
+
+    let state = recalc_state(&workspace);
+    let fork_id = fork_first_workbook(&state).await?;
+
+    let resp = structure_batch(
+        state.clone(),
+        StructureBatchParams {
+            fork_id: fork_id.clone(),
+            ops: vec![StructureOp::InsertRows {
+                sheet_name: "Sheet1".to_string(),
+                at_row: 3,
+                count: 2,
+            }],
+            mode: Some("apply".to_string()),
+            label: None,
+        },
+    )
+    .await?;
+
+    let after = |kind: RewriteKind| {
+        resp.rewrites
+            .iter()
+            .find(|r| r.kind == kind)
+            .and_then(|r| r.after.clone())
+    };
+    assert_eq!(after(RewriteKind::MergedCell).as_deref(), Some("A7:B7"));
+    assert_eq!(
+        after(RewriteKind::DefinedName).as_deref(),
+        Some("Sheet1!$A$2:$A$8")
+    );
+    assert_eq!(
+        after(RewriteKind::PrintArea).as_deref(),
+        Some("Sheet1!$A$1:$B$12")
+    );
+    assert_eq!(resp.summary.counts.get("print_areas_rewritten"), Some(&1));
+
+    let fork_wb = state.open_workbook(&WorkbookId(fork_id)).await?;
+    let merges = fork_wb.with_sheet("Sheet1", |sheet| {
+        sheet
+            .get_merge_cells()
+            .iter()
+            .map(|range| range.get_range())
+            .collect::<Vec<_>>()
+    })?;
+    assert_eq!(merges, vec!["A7:B7".to_string()]);
+
+    Ok(())
+}
+
+fn rule_formulas(sheet: &umya_spreadsheet::Worksheet) -> (Vec<(String, String)>, Vec<String>) {
+    let validations = sheet
+        .get_data_validations()
+        .map(|v| {
+            v.get_data_validation_list()
+                .iter()
+                .map(|dv| {
+                    (
+                        dv.get_sequence_of_references().get_sqref(),
+                        dv.get_formula1().to_string(),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let formats = sheet
+        .get_conditional_formatting_collection()
+        .iter()
+        .flat_map(|format| {
+            let sqref = format.get_sequence_of_references().get_sqref();
+            format.get_conditional_collection().iter().map(move |rule| {
+                let formula = rule
+                    .get_formula()
+                    .map(|f| f.get_string_value().to_string())
+                    .unwrap_or_default();
+                format!("{sqref} {formula}")
+            })
+        })
+        .collect();
+    (validations, formats)
+}
+
+fn add_rules(book: &mut umya_spreadsheet::Spreadsheet) {
+    use umya_spreadsheet::structs::{
+        ConditionalFormatValues, ConditionalFormatting, ConditionalFormattingRule, DataValidation,
+        DataValidationValues, DataValidations, Formula,
+    };
+
+    let sheet = book.get_sheet_mut(&0).unwrap();
+    sheet.set_name("Data");
+    let mut validations = DataValidations::default();
+    for (sqref, source) in [("B2:B10", "$A$2:$A$10"), ("C2", "Lists!$A:$A")] {
+        let mut dv = DataValidation::default();
+        dv.set_type(DataValidationValues::List);
+        dv.set_formula1(source);
+        dv.get_sequence_of_references_mut().set_sqref(sqref);
+        validations.get_data_validation_list_mut().push(dv);
+    }
+    sheet.set_data_validations(validations);
+
+    let mut formula = Formula::default();
+    formula.set_string_value("D2>$A$2");
+    let mut rule = ConditionalFormattingRule::default();
+    rule.set_type(ConditionalFormatValues::Expression);
+    rule.set_formula(formula);
+    rule.set_priority(1);
+    let mut format = ConditionalFormatting::default();
+    format.get_sequence_of_references_mut().set_sqref("D2:D10");
+    format.add_conditional_collection(rule);
+    sheet.set_conditional_formatting_collection(vec![format]);
+
+    book.new_sheet("Lists").unwrap();
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn structure_batch_rewrites_rule_formulas_on_inserts_and_renames() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("structure_rule_formulas.xlsx", add_rules);
+
+    let state = recalc_state(&workspace);
+    let fork_id = fork_first_workbook(&state).await?;
+
+    let resp = structure_batch(
+        state.clone(),
+        StructureBatchParams {
+            fork_id: fork_id.clone(),
+            ops: vec![
+                StructureOp::InsertRows {
+                    sheet_name: "Data".to_string(),
+                    at_row: 1,
+                    count: 1,
+                },
+                StructureOp::RenameSheet {
+                    old_name: "Lists".to_string(),
+                    new_name: "Choice List".to_string(),
+                },
+            ],
+            mode: Some("apply".to_string()),
+            label: None,
+        },
+    )
+    .await?;
+
+    assert!(resp.rewrites.iter().any(|r| {
+        r.kind == RewriteKind::DataValidation
+            && r.target.as_deref() == Some("B3:B11")
+            && r.before == "$A$2:$A$10"
+            && r.after.as_deref() == Some("$A$3:$A$11")
+    }));
+    assert!(resp.rewrites.iter().any(|r| {
+        r.kind == RewriteKind::ConditionalFormat
+            && r.target.as_deref() == Some("D3:D11")
+            && r.after.as_deref() == Some("D3>$A$3")
+    }));
+
+    let fork_wb = state.open_workbook(&WorkbookId(fork_id)).await?;
+    let (validations, formats) = fork_wb.with_sheet("Data", rule_formulas)?;
+    assert_eq!(
+        validations,
+        vec![
+            ("B3:B11".to_string(), "$A$3:$A$11".to_string()),
+            ("C3".to_string(), "'Choice List'!$A:$A".to_string()),
+        ]
+    );
+    assert_eq!(formats, vec!["D3:D11 D3>$A$3".to_string()]);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn structure_batch_rewrites_rule_formulas_on_moves_and_sheet_deletes() -> Result<()> {
+    let workspace = support::TestWorkspace::new();
+    workspace.create_workbook("structure_rule_moves.xlsx", add_rules);
+
+    let state = recalc_state(&workspace);
+    let fork_id = fork_first_workbook(&state).await?;
+
+    let resp = structure_batch(
+        state.clone(),
+        StructureBatchParams {
+            fork_id: fork_id.clone(),
+            ops: vec![
+                StructureOp::MoveRange {
+                    sheet_name: "Data".to_string(),
+                    dest_sheet_name: None,
+                    src_range: "A2:A10".to_string(),
+                    dest_anchor: "F2".to_string(),
+                    include_styles: false,
+                    include_formulas: true,
+                },
+                StructureOp::DeleteSheet {
+                    name: "Lists".to_string(),
+                },
+            ],
+            mode: Some("apply".to_string()),
+            label: None,
+        },
+    )
+    .await?;
+
+    assert_eq!(
+        resp.summary.counts.get("data_validations_rewritten"),
+        Some(&2)
+    );
+    assert_eq!(
+        resp.summary.counts.get("conditional_formats_rewritten"),
+        Some(&1)
+    );
+
+    let fork_wb = state.open_workbook(&WorkbookId(fork_id)).await?;
+    let (validations, formats) = fork_wb.with_sheet("Data", rule_formulas)?;
+    assert_eq!(
+        validations,
+        vec![
+            ("B2:B10".to_string(), "$F$2:$F$10".to_string()),
+            ("C2".to_string(), "#REF!$A:$A".to_string()),
+        ]
+    );
+    // `D2` lies outside the moved block; `$A$2` moved with it.
+    assert_eq!(formats, vec!["D2:D10 D2>$F$2".to_string()]);
+
+    Ok(())
+}
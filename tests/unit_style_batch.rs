@@ -570,7 +570,8 @@ async fn style_batch_idempotent_noop_counts_and_no_diff() -> Result<()> {
             CellDiff::Modified { subtype, .. } => !matches!(subtype, ModificationType::StyleEdit),
             CellDiff::Added { .. } | CellDiff::Deleted { .. } => true,
         },
-        Change::Table(_) | Change::Name(_) => true,
+        Change::Style(_) => false,
+        _ => true,
     });
     assert!(!non_style_change);
 